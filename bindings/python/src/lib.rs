@@ -0,0 +1,242 @@
+//! python bindings: `shredder.Shredder`, the named standards and progress
+//! callbacks
+//!
+//! ```python
+//! import shredder
+//!
+//! def show(progress):
+//!     print(f"pass {progress.current_pass}/{progress.passes}: {progress.fraction:.0%}")
+//!
+//! report = shredder.Shredder("dod", progress=show).wipe("secret.pdf")
+//! print(report["bytes_written"])
+//! ```
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use shredder_rs::progress::Progress as WipeProgress;
+use shredder_rs::report::WipeReport;
+use shredder_rs::standards::{NamedStandard, VerificationLevel, WipeStandard};
+use shredder_rs::storage::{StorageCapabilities, StorageProbe, StorageType, SystemProbe};
+use shredder_rs::WipeError;
+use std::path::PathBuf;
+
+create_exception!(
+    shredder,
+    ShredderError,
+    PyException,
+    "A wipe that failed or couldn't be verified."
+);
+
+/// io errors become OSError, bad settings ValueError, the rest ShredderError
+fn to_py_err(error: WipeError) -> PyErr {
+    match error {
+        WipeError::Io(e) => e.into(),
+        WipeError::Config(message) => PyValueError::new_err(message),
+        other => ShredderError::new_err(other.to_string()),
+    }
+}
+
+/// progress of an overwrite pass, handed to the `progress` callback
+#[pyclass(frozen, get_all, module = "shredder")]
+#[derive(Clone)]
+struct Progress {
+    /// target being overwritten
+    path: PathBuf,
+    /// current pass, starting at 1
+    current_pass: usize,
+    /// number of passes the standard performs
+    passes: usize,
+    /// bytes written so far in the current pass
+    bytes_written: u64,
+    /// bytes each pass writes
+    bytes_per_pass: u64,
+    /// fraction of the whole wipe that is done, from 0.0 to 1.0
+    fraction: f64,
+}
+
+#[pymethods]
+impl Progress {
+    fn __repr__(&self) -> String {
+        format!(
+            "Progress(path={:?}, pass {}/{}, {:.1}%)",
+            self.path,
+            self.current_pass,
+            self.passes,
+            self.fraction * 100.0
+        )
+    }
+}
+
+/// wipes files and devices with one standard
+///
+/// `standard` and `verify` take the names `shred --standard` and `--verify`
+/// take. `storage` is "hdd", "ssd" or "flash"; left out, it is detected for
+/// every target. `progress` is called with a `Progress` after every buffer
+/// written, on the wiping thread. multi-pass standards such as "gutmann" are
+/// refused on SSDs and flash unless `force_multipass` is true
+#[pyclass(module = "shredder")]
+struct Shredder {
+    standard: WipeStandard,
+    storage: Option<StorageType>,
+    verify: Option<VerificationLevel>,
+    progress: Option<PyObject>,
+    force_multipass: bool,
+}
+
+#[pymethods]
+impl Shredder {
+    #[new]
+    #[pyo3(signature = (standard = "nist", *, storage = None, verify = None, progress = None, force_multipass = false))]
+    fn new(
+        standard: &str,
+        storage: Option<&str>,
+        verify: Option<&str>,
+        progress: Option<PyObject>,
+        force_multipass: bool,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            standard: standard.parse().map_err(to_py_err)?,
+            storage: storage.map(parse_storage).transpose()?,
+            verify: verify.map(str::parse).transpose().map_err(to_py_err)?,
+            progress,
+            force_multipass,
+        })
+    }
+
+    /// name of the standard, e.g. "nist"
+    #[getter]
+    fn standard(&self) -> String {
+        self.standard.to_string()
+    }
+
+    /// wipes a file or device and returns its report as a dict
+    ///
+    /// the GIL is released while the target is overwritten
+    fn wipe<'py>(&self, py: Python<'py>, path: PathBuf) -> PyResult<Bound<'py, PyDict>> {
+        let storage = match &self.storage {
+            Some(storage) => storage.clone(),
+            None => SystemProbe.probe(&path).map_err(to_py_err)?.device_type,
+        };
+        let mut builder = shredder_rs::Shredder::builder()
+            .standard(self.standard.clone())
+            .storage(storage);
+        if let Some(level) = self.verify {
+            builder = builder.verify(level);
+        }
+        if self.force_multipass {
+            builder = builder.force_multipass();
+        }
+        let mut shredder = builder
+            .build()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if let Some(callback) = &self.progress {
+            let callback = callback.clone_ref(py);
+            shredder = shredder.with_progress(move |progress| report_progress(&callback, progress));
+        }
+
+        let report = py
+            .allow_threads(|| shredder.wipe_with_report(&path))
+            .map_err(to_py_err)?;
+        report_dict(py, &report)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Shredder({:?})", self.standard.to_string())
+    }
+}
+
+/// the named standards, as dicts with their name, title, summary, the media
+/// they suit and the documents they follow
+#[pyfunction]
+fn standards(py: Python<'_>) -> PyResult<Vec<Bound<'_, PyDict>>> {
+    NamedStandard::ALL
+        .iter()
+        .map(|standard| {
+            let dict = PyDict::new_bound(py);
+            dict.set_item("name", standard.name)?;
+            dict.set_item("title", standard.title)?;
+            dict.set_item("summary", standard.summary)?;
+            dict.set_item("media", standard.media)?;
+            dict.set_item("references", standard.references.to_vec())?;
+            Ok(dict)
+        })
+        .collect()
+}
+
+/// storage given by name instead of detected, with the capabilities such
+/// devices usually have
+fn parse_storage(name: &str) -> PyResult<StorageType> {
+    let caps = |trim, secure_erase, wear_leveling| StorageCapabilities {
+        supports_trim: trim,
+        supports_secure_erase: secure_erase,
+        supports_nvme_sanitize: false,
+        has_wear_leveling: wear_leveling,
+    };
+    match name.to_ascii_lowercase().as_str() {
+        "hdd" => Ok(StorageType::Hdd(caps(false, false, false))),
+        "ssd" => Ok(StorageType::Ssd(caps(true, false, true))),
+        "flash" => Ok(StorageType::Flash(caps(false, false, true))),
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown storage '{}', expected hdd, ssd or flash",
+            name
+        ))),
+    }
+}
+
+/// calls the python progress callback; exceptions it raises can't stop the
+/// wipe, so they are reported as unraisable
+fn report_progress(callback: &PyObject, progress: &WipeProgress) {
+    Python::with_gil(|py| {
+        let progress = Progress {
+            path: progress.path.to_path_buf(),
+            current_pass: progress.pass,
+            passes: progress.passes,
+            bytes_written: progress.bytes_written,
+            bytes_per_pass: progress.bytes_per_pass,
+            fraction: progress.fraction(),
+        };
+        if let Err(e) = callback.call1(py, (progress,)) {
+            e.write_unraisable_bound(py, None);
+        }
+    });
+}
+
+fn report_dict<'py>(py: Python<'py>, report: &WipeReport) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("path", &report.path)?;
+    dict.set_item("filesystem", &report.filesystem)?;
+    dict.set_item("passes", report.passes)?;
+    dict.set_item("bytes_written", report.bytes_written)?;
+    dict.set_item("sha256", &report.sha256)?;
+    dict.set_item("pass_sha256", &report.pass_sha256)?;
+    dict.set_item("remnants", report.remnants)?;
+    dict.set_item("limitations", &report.limitations)?;
+    dict.set_item("nist_action", report.nist_action.map(|a| a.to_string()))?;
+    dict.set_item("destroy_required", report.destroy_required)?;
+    let device = match &report.device {
+        Some(identity) => {
+            let device = PyDict::new_bound(py);
+            device.set_item("device", &identity.device)?;
+            device.set_item("model", &identity.model)?;
+            device.set_item("serial", &identity.serial)?;
+            device.set_item("wwn", &identity.wwn)?;
+            device.set_item("firmware", &identity.firmware)?;
+            device.set_item("capacity", identity.capacity)?;
+            Some(device)
+        }
+        None => None,
+    };
+    dict.set_item("device", device)?;
+    Ok(dict)
+}
+
+#[pymodule]
+fn shredder(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Shredder>()?;
+    m.add_class::<Progress>()?;
+    m.add_function(wrap_pyfunction!(standards, m)?)?;
+    m.add("ShredderError", m.py().get_type_bound::<ShredderError>())?;
+    Ok(())
+}
@@ -0,0 +1,865 @@
+use crate::jobs::{self, JobId};
+use crate::report::{device_json, json_option, json_string, partition_json};
+use crate::schedule::CalendarTime;
+use crate::{Result, WipeError, WipeReport};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// largest request body accepted, job submissions are a few hundred bytes
+const MAX_BODY: usize = 64 * 1024;
+
+/// largest request line and headers accepted, together
+const MAX_HEAD: u64 = 16 * 1024;
+
+/// most headers accepted in one request
+const MAX_HEADERS: usize = 64;
+
+/// connections served at the same time; more are turned away with a 503
+const MAX_CONNECTIONS: usize = 32;
+
+/// finished jobs remembered at most, like the library's job registry
+const KEPT: usize = 1024;
+
+/// a wipe submitted through the API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRequest {
+    /// file or device to wipe
+    pub path: PathBuf,
+
+    /// wiping standard, or None for the daemon's default
+    pub standard: Option<String>,
+
+    /// verification level, or None for the daemon's default
+    pub verify: Option<String>,
+}
+
+/// where a submitted job stands
+#[derive(Debug, Clone)]
+enum JobState {
+    /// waiting for earlier jobs to finish
+    Queued,
+    /// being wiped
+    Running,
+    /// wiped; the report serves as the job's certificate
+    Succeeded(Box<WipeReport>),
+    /// the wipe failed with this error
+    Failed(String),
+}
+
+impl JobState {
+    fn name(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Succeeded(_) => "succeeded",
+            JobState::Failed(_) => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Job {
+    id: u64,
+    request: JobRequest,
+    state: JobState,
+    submitted: CalendarTime,
+    finished: Option<CalendarTime>,
+    /// library job the wipe runs as, once it has started
+    wipe: Option<JobId>,
+}
+
+/// layout of the queue file: the jobs not finished yet
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct QueueFile {
+    /// id of the next submitted job, so ids aren't reused after a restart
+    next_id: u64,
+    jobs: Vec<QueuedJob>,
+}
+
+/// a queued or running job as saved in the queue file
+#[derive(Debug, Serialize, Deserialize)]
+struct QueuedJob {
+    id: u64,
+    path: PathBuf,
+    standard: Option<String>,
+    verify: Option<String>,
+    /// "queued" or "running"
+    state: String,
+    submitted: CalendarTime,
+}
+
+/// HTTP API for submitting wipes and tracking them, used by `shred daemon --listen`
+///
+/// every request must carry `Authorization: Bearer <token>`. jobs are run one
+/// at a time in submission order. endpoints:
+///   POST /jobs                   submit a job (form fields path, standard, verify)
+///   GET  /jobs                   list all jobs
+///   GET  /jobs/<id>              status of one job
+///   GET  /jobs/<id>/progress     pass and bytes written so far
+///   GET  /jobs/<id>/certificate  wipe report of a job that succeeded
+pub struct ApiServer {
+    listener: TcpListener,
+    token: String,
+    jobs: Arc<Mutex<Vec<Job>>>,
+    /// where unfinished jobs are kept across restarts, if anywhere
+    queue_file: Option<PathBuf>,
+    /// id of the first job submitted, following those of earlier runs
+    first_id: u64,
+}
+
+impl ApiServer {
+    /// binds the API to an address
+    ///
+    /// # Arguments
+    /// * `addr` - Address to listen on, e.g. 127.0.0.1:7878
+    /// * `token` - Bearer token clients must present
+    ///
+    /// # Returns
+    /// * `Result<ApiServer>` - Bound server, ready to `run`
+    pub fn bind<A: ToSocketAddrs>(addr: A, token: String) -> Result<Self> {
+        if token.is_empty() {
+            return Err(WipeError::Config(
+                "The API needs a non-empty api_token".into(),
+            ));
+        }
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            token,
+            jobs: Arc::new(Mutex::new(Vec::new())),
+            queue_file: None,
+            first_id: 1,
+        })
+    }
+
+    /// keeps queued and running jobs in a file, so a restart or reboot of the
+    /// daemon doesn't lose them
+    ///
+    /// the jobs already in the file are queued again ahead of new submissions,
+    /// under their old ids. one that was running when the daemon stopped is
+    /// wiped again from the start
+    ///
+    /// # Arguments
+    /// * `path` - Queue file, created along with its directory when a job is submitted
+    ///
+    /// # Returns
+    /// * `Result<ApiServer>` - Server with the saved jobs queued, or a config error
+    ///   if the file can't be parsed
+    pub fn with_queue_file(mut self, path: PathBuf) -> Result<Self> {
+        let saved = match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str::<QueueFile>(&text)
+                .map_err(|e| WipeError::Config(format!("{}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => QueueFile::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut jobs = self.jobs.lock().unwrap();
+        for queued in saved.jobs {
+            if queued.state == "running" {
+                log::warn!(
+                    "API job {} was interrupted while wiping {}; wiping it again",
+                    queued.id,
+                    queued.path.display()
+                );
+            }
+            jobs.push(Job {
+                id: queued.id,
+                request: JobRequest {
+                    path: queued.path,
+                    standard: queued.standard,
+                    verify: queued.verify,
+                },
+                state: JobState::Queued,
+                submitted: queued.submitted,
+                finished: None,
+                wipe: None,
+            });
+        }
+        if !jobs.is_empty() {
+            log::info!("Resuming {} API job(s) from {}", jobs.len(), path.display());
+        }
+        self.first_id = jobs.last().map_or(saved.next_id, |job| job.id + 1).max(1);
+        drop(jobs);
+
+        self.queue_file = Some(path);
+        Ok(self)
+    }
+
+    /// address the server is listening on
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// serves requests until the listener fails
+    ///
+    /// # Arguments
+    /// * `runner` - Performs a submitted wipe as the given library job (see
+    ///   [`Shredder::with_job`](crate::Shredder::with_job)) and returns its report
+    pub fn run<F>(self, runner: F) -> Result<()>
+    where
+        F: Fn(&JobRequest, JobId) -> Result<WipeReport> + Send + 'static,
+    {
+        let (queue, queued) = mpsc::channel::<u64>();
+        // jobs restored from the queue file go first
+        for job in self.jobs.lock().unwrap().iter() {
+            let _ = queue.send(job.id);
+        }
+
+        let jobs = Arc::clone(&self.jobs);
+        let (queue_file, first_id) = (self.queue_file.clone(), self.first_id);
+        std::thread::spawn(move || {
+            for id in queued {
+                let (request, wipe) = {
+                    let mut jobs = jobs.lock().unwrap();
+                    let Some(job) = jobs.iter_mut().find(|job| job.id == id) else {
+                        continue;
+                    };
+                    job.state = JobState::Running;
+                    let wipe = jobs::register(&job.request.path);
+                    job.wipe = Some(wipe);
+                    let request = job.request.clone();
+                    save_queue(queue_file.as_deref(), &jobs, first_id);
+                    (request, wipe)
+                };
+                log::info!("API job {}: wiping {}", id, request.path.display());
+
+                let state = match runner(&request, wipe) {
+                    Ok(report) => JobState::Succeeded(Box::new(report)),
+                    Err(e) => {
+                        log::error!("API job {} failed: {}", id, e);
+                        JobState::Failed(e.to_string())
+                    }
+                };
+                let mut jobs = jobs.lock().unwrap();
+                if let Some(job) = jobs.iter_mut().find(|job| job.id == id) {
+                    job.state = state;
+                    job.finished = Some(CalendarTime::now());
+                }
+                forget_finished(&mut jobs);
+                save_queue(queue_file.as_deref(), &jobs, first_id);
+            }
+        });
+
+        // each connection is served on its own thread, so a stalled client
+        // only holds up itself
+        let server = Arc::new(self);
+        let connections = Arc::new(AtomicUsize::new(0));
+        for stream in server.listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("API connection failed: {}", e);
+                    continue;
+                }
+            };
+            if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                connections.fetch_sub(1, Ordering::SeqCst);
+                let _ = write_response(&mut stream, 503, &json_error("Too many connections"));
+                continue;
+            }
+
+            let (server, connections, queue) =
+                (Arc::clone(&server), Arc::clone(&connections), queue.clone());
+            std::thread::spawn(move || {
+                server.serve(stream, &queue);
+                connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+        Ok(())
+    }
+
+    /// answers the one request of a connection
+    fn serve(&self, mut stream: TcpStream, queue: &mpsc::Sender<u64>) {
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+        let (status, body) = match read_request(&mut stream) {
+            Ok(request) => self.handle(&request, queue),
+            Err((status, message)) => (status, json_error(&message)),
+        };
+        if let Err(e) = write_response(&mut stream, status, &body) {
+            log::debug!("API response failed: {}", e);
+        }
+    }
+
+    fn handle(&self, request: &Request, queue: &mpsc::Sender<u64>) -> (u16, String) {
+        let authorized = request
+            .header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.trim(), &self.token));
+        if !authorized {
+            return (401, json_error("Missing or wrong bearer token"));
+        }
+
+        let segments: Vec<&str> = request
+            .path
+            .trim_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let mut jobs = self.jobs.lock().unwrap();
+
+        match (request.method.as_str(), segments.as_slice()) {
+            ("POST", ["jobs"]) => {
+                let fields = parse_form(&request.body);
+                let field = |name: &str| {
+                    fields
+                        .iter()
+                        .find(|(key, _)| key == name)
+                        .map(|(_, value)| value.clone())
+                        .filter(|value| !value.is_empty())
+                };
+                let Some(path) = field("path") else {
+                    return (400, json_error("The path field is required"));
+                };
+                let id = next_id(&jobs, self.first_id);
+                let job = Job {
+                    id,
+                    request: JobRequest {
+                        path: PathBuf::from(path),
+                        standard: field("standard"),
+                        verify: field("verify"),
+                    },
+                    state: JobState::Queued,
+                    submitted: CalendarTime::now(),
+                    finished: None,
+                    wipe: None,
+                };
+                let body = job_json(&job);
+                jobs.push(job);
+                save_queue(self.queue_file.as_deref(), &jobs, self.first_id);
+                let _ = queue.send(id);
+                (202, body)
+            }
+            ("GET", ["jobs"]) => {
+                let list: Vec<String> = jobs.iter().map(job_json).collect();
+                (200, format!("[{}]", list.join(",")))
+            }
+            ("GET", ["jobs", id, rest @ ..]) => {
+                let Some(job) = id
+                    .parse::<u64>()
+                    .ok()
+                    .and_then(|id| jobs.iter().find(|job| job.id == id))
+                else {
+                    return (404, json_error("No such job"));
+                };
+                match rest {
+                    [] => (200, job_json(job)),
+                    ["progress"] => (200, progress_json(job)),
+                    ["certificate"] => match &job.state {
+                        JobState::Succeeded(report) => (200, certificate_json(job, report)),
+                        state => (
+                            409,
+                            json_error(&format!("Job is {}, no certificate", state.name())),
+                        ),
+                    },
+                    _ => (404, json_error("Not found")),
+                }
+            }
+            _ => (404, json_error("Not found")),
+        }
+    }
+}
+
+/// location of the queue file when the configuration doesn't name one:
+/// jobs.toml in [`Config::state_dir`](crate::config::Config::state_dir)
+pub fn default_queue_path() -> PathBuf {
+    crate::config::Config::state_dir().join("jobs.toml")
+}
+
+/// forgets the oldest finished jobs beyond `KEPT`
+fn forget_finished(jobs: &mut Vec<Job>) {
+    let finished = |job: &Job| matches!(job.state, JobState::Succeeded(_) | JobState::Failed(_));
+    let mut excess = jobs
+        .iter()
+        .filter(|job| finished(job))
+        .count()
+        .saturating_sub(KEPT);
+    jobs.retain(|job| {
+        let forget = excess > 0 && finished(job);
+        if forget {
+            excess -= 1;
+        }
+        !forget
+    });
+}
+
+fn next_id(jobs: &[Job], first_id: u64) -> u64 {
+    jobs.last().map_or(first_id, |job| job.id + 1)
+}
+
+/// saves the queued and running jobs, replacing the file in one step so a
+/// crash while saving leaves the previous queue; failures are only logged,
+/// since the jobs themselves can still run
+fn save_queue(path: Option<&Path>, jobs: &[Job], first_id: u64) {
+    let Some(path) = path else {
+        return;
+    };
+    let file = QueueFile {
+        next_id: next_id(jobs, first_id),
+        jobs: jobs
+            .iter()
+            .filter(|job| matches!(job.state, JobState::Queued | JobState::Running))
+            .map(|job| QueuedJob {
+                id: job.id,
+                path: job.request.path.clone(),
+                standard: job.request.standard.clone(),
+                verify: job.request.verify.clone(),
+                state: job.state.name().to_string(),
+                submitted: job.submitted,
+            })
+            .collect(),
+    };
+
+    let saved = toml::to_string(&file)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        .and_then(|text| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let temp_path = path.with_extension("toml.tmp");
+            std::fs::write(&temp_path, text)?;
+            std::fs::rename(&temp_path, path)
+        });
+    if let Err(e) = saved {
+        log::warn!("Unable to save the job queue to {}: {}", path.display(), e);
+    }
+}
+
+/// the parts of an HTTP request the API looks at
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// reads a request, or says with which status to turn it away
+///
+/// the request line and headers are read through a `MAX_HEAD` limit, so a
+/// client can't make the server buffer more than that before it is answered
+fn read_request(stream: &mut TcpStream) -> std::result::Result<Request, (u16, String)> {
+    let invalid = |message: &str| (400, message.to_string());
+    let mut reader = BufReader::new(Read::take(stream, MAX_HEAD));
+
+    let mut line = String::new();
+    read_head_line(&mut reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts
+        .next()
+        .ok_or_else(|| invalid("Empty request"))?
+        .to_string();
+    let target = parts
+        .next()
+        .ok_or_else(|| invalid("Missing request target"))?;
+    // query strings aren't used
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        if read_head_line(&mut reader, &mut line)? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(head_too_large());
+        }
+        if let Some((key, value)) = header.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.parse::<usize>())
+        .transpose()
+        .map_err(|_| invalid("Invalid Content-Length"))?
+        .unwrap_or(0);
+    if length > MAX_BODY {
+        return Err(invalid("Request body too large"));
+    }
+    // part of the body may be buffered already; the limit only covers the rest
+    reader.get_mut().set_limit(length as u64);
+    let mut body = vec![0u8; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| invalid(&e.to_string()))?;
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+/// reads one line of the request line and headers into `line`
+fn read_head_line<R: Read>(
+    reader: &mut BufReader<std::io::Take<R>>,
+    line: &mut String,
+) -> std::result::Result<usize, (u16, String)> {
+    line.clear();
+    let read = reader.read_line(line).map_err(|e| (400, e.to_string()))?;
+    // a line cut off by the limit rather than ended by the client
+    if read > 0 && !line.ends_with('\n') && reader.get_ref().limit() == 0 {
+        return Err(head_too_large());
+    }
+    Ok(read)
+}
+
+fn head_too_large() -> (u16, String) {
+    (431, "Request header fields too large".to_string())
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, body: &str) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// parses an application/x-www-form-urlencoded body
+fn parse_form(body: &str) -> Vec<(String, String)> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// compares tokens without leaking the matching prefix length through timing
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn json_error(message: &str) -> String {
+    format!("{{\"error\":{}}}", json_string(message))
+}
+
+fn job_json(job: &Job) -> String {
+    let error = match &job.state {
+        JobState::Failed(error) => Some(error.as_str()),
+        _ => None,
+    };
+    format!(
+        "{{\"id\":{},\"path\":{},\"standard\":{},\"verify\":{},\"status\":\"{}\",\"submitted\":{},\"finished\":{},\"error\":{}}}",
+        job.id,
+        json_string(&job.request.path.to_string_lossy()),
+        json_option(job.request.standard.as_deref()),
+        json_option(job.request.verify.as_deref()),
+        job.state.name(),
+        json_string(&job.submitted.to_string()),
+        json_option(job.finished.map(|time| time.to_string()).as_deref()),
+        json_option(error),
+    )
+}
+
+/// how far a job's passes have got; zeros until its wipe starts
+fn progress_json(job: &Job) -> String {
+    let progress = job.wipe.and_then(jobs::progress).unwrap_or_default();
+    format!(
+        "{{\"id\":{},\"status\":\"{}\",\"pass\":{},\"passes\":{},\"bytes_written\":{},\"total_bytes\":{},\"fraction\":{:.4}}}",
+        job.id,
+        job.state.name(),
+        progress.pass,
+        progress.passes,
+        progress.total_written(),
+        progress.total_bytes(),
+        match job.state {
+            JobState::Succeeded(_) => 1.0,
+            _ => progress.fraction(),
+        },
+    )
+}
+
+fn certificate_json(job: &Job, report: &WipeReport) -> String {
+    let limitations: Vec<String> = report
+        .limitations
+        .iter()
+        .map(|limitation| json_string(limitation))
+        .collect();
+    let pass_sha256: Vec<String> = report
+        .pass_sha256
+        .iter()
+        .map(|digest| json_string(digest))
+        .collect();
+    format!(
+        "{{\"id\":{},\"path\":{},\"filesystem\":{},\"passes\":{},\"bytes_written\":{},\"sha256\":{},\"pass_sha256\":[{}],\"remnants\":{},\"limitations\":[{}],\"nist_action\":{},\"destroy_required\":{},\"quick\":{},\"partition\":{},\"device\":{},\"submitted\":{},\"finished\":{}}}",
+        job.id,
+        json_string(&report.path.to_string_lossy()),
+        json_option(report.filesystem.as_deref()),
+        report.passes,
+        report.bytes_written,
+        json_option(report.sha256.as_deref()),
+        pass_sha256.join(","),
+        report
+            .remnants
+            .map_or_else(|| "null".to_string(), |n| n.to_string()),
+        limitations.join(","),
+        json_option(report.nist_action.map(|a| a.to_string()).as_deref()),
+        report.destroy_required,
+        report.quick,
+        partition_json(report.partition.as_ref()),
+        device_json(report.device.as_ref()),
+        json_string(&job.submitted.to_string()),
+        json_option(job.finished.map(|time| time.to_string()).as_deref()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(stream_addr: std::net::SocketAddr, raw: &str) -> String {
+        let mut stream = TcpStream::connect(stream_addr).unwrap();
+        stream.write_all(raw.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_parse_form() {
+        assert_eq!(
+            parse_form("path=%2Ftmp%2Fmy+file&standard=dod"),
+            vec![
+                ("path".to_string(), "/tmp/my file".to_string()),
+                ("standard".to_string(), "dod".to_string()),
+            ]
+        );
+        assert_eq!(json_string("a\"b\\\n"), "\"a\\\"b\\\\\\n\"");
+    }
+
+    #[test]
+    fn test_request_limits() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let read = |raw: String| {
+            let mut client = TcpStream::connect(addr).unwrap();
+            client.write_all(raw.as_bytes()).unwrap();
+            let (mut stream, _) = listener.accept().unwrap();
+            read_request(&mut stream).map(|request| request.body)
+        };
+
+        let body = "path=%2Fsrv%2Fa";
+        let fine = format!(
+            "POST /jobs HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        assert_eq!(read(fine), Ok(body.to_string()));
+
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_HEAD as usize));
+        assert_eq!(read(long_line).unwrap_err().0, 431);
+
+        let many_headers = format!(
+            "GET /jobs HTTP/1.1\r\n{}\r\n",
+            "X-A: b\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert_eq!(read(many_headers).unwrap_err().0, 431);
+    }
+
+    #[test]
+    fn test_forget_finished() {
+        let job = |id, state| Job {
+            id,
+            request: JobRequest {
+                path: PathBuf::from("/srv/a"),
+                standard: None,
+                verify: None,
+            },
+            state,
+            submitted: CalendarTime::now(),
+            finished: None,
+            wipe: None,
+        };
+        let mut jobs = vec![job(1, JobState::Queued)];
+        jobs.extend((2..KEPT as u64 + 12).map(|id| job(id, JobState::Failed("gone".into()))));
+        forget_finished(&mut jobs);
+
+        // the queued job stays however old, the oldest finished ones go
+        assert_eq!(jobs.len(), KEPT + 1);
+        assert_eq!(jobs[0].id, 1);
+        assert_eq!(jobs[1].id, 12);
+        assert_eq!(next_id(&jobs, 1), KEPT as u64 + 12);
+    }
+
+    #[test]
+    fn test_queue_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("jobs.toml");
+        let submit = |server: &ApiServer, queue: &mpsc::Sender<u64>, target: &str| {
+            let body = format!("path={}", target);
+            let request = Request {
+                method: "POST".into(),
+                path: "/jobs".into(),
+                headers: vec![("Authorization".into(), "Bearer secret".into())],
+                body,
+            };
+            server.handle(&request, queue)
+        };
+
+        // nothing takes jobs off this queue, so they stay queued
+        let (queue, _queued) = mpsc::channel();
+        let server = ApiServer::bind("127.0.0.1:0", "secret".into())
+            .unwrap()
+            .with_queue_file(path.clone())
+            .unwrap();
+        assert_eq!(submit(&server, &queue, "/srv/a").0, 202);
+        assert_eq!(submit(&server, &queue, "/srv/b").0, 202);
+        server.jobs.lock().unwrap()[0].state = JobState::Running;
+        let jobs = server.jobs.lock().unwrap();
+        save_queue(Some(&path), &jobs, server.first_id);
+        drop(jobs);
+        drop(server);
+
+        // after a restart both are queued again under their ids, and new
+        // submissions carry on from there
+        let restarted = ApiServer::bind("127.0.0.1:0", "secret".into())
+            .unwrap()
+            .with_queue_file(path.clone())
+            .unwrap();
+        {
+            let jobs = restarted.jobs.lock().unwrap();
+            let restored: Vec<_> = jobs
+                .iter()
+                .map(|job| (job.id, job.request.path.clone(), job.state.name()))
+                .collect();
+            assert_eq!(
+                restored,
+                [
+                    (1, PathBuf::from("/srv/a"), "queued"),
+                    (2, PathBuf::from("/srv/b"), "queued")
+                ]
+            );
+        }
+        let (status, body) = submit(&restarted, &queue, "/srv/c");
+        assert_eq!(status, 202);
+        assert!(body.contains("\"id\":3"));
+
+        // finished jobs leave the file, but their ids aren't handed out again
+        restarted.jobs.lock().unwrap().clear();
+        save_queue(Some(&path), &[], 4);
+        let empty = ApiServer::bind("127.0.0.1:0", "secret".into())
+            .unwrap()
+            .with_queue_file(path)
+            .unwrap();
+        assert!(empty.jobs.lock().unwrap().is_empty());
+        assert_eq!(empty.first_id, 4);
+    }
+
+    #[test]
+    fn test_submit_and_fetch_certificate() {
+        let server = ApiServer::bind("127.0.0.1:0", "secret".into()).unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || {
+            server.run(|request, _| {
+                let mut report = WipeReport::new(request.path.clone());
+                report.record_pass(4096);
+                Ok(report)
+            })
+        });
+
+        let unauthorized = request(addr, "GET /jobs HTTP/1.1\r\n\r\n");
+        assert!(unauthorized.starts_with("HTTP/1.1 401"));
+
+        let body = "path=%2Fsrv%2Fold.db&standard=dod";
+        let submitted = request(
+            addr,
+            &format!(
+                "POST /jobs HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+        );
+        assert!(submitted.starts_with("HTTP/1.1 202"));
+        assert!(submitted.contains("\"id\":1"));
+
+        // the worker thread finishes the job almost immediately
+        let mut certificate = String::new();
+        for _ in 0..50 {
+            certificate = request(
+                addr,
+                "GET /jobs/1/certificate HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n",
+            );
+            if certificate.starts_with("HTTP/1.1 200") {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(certificate.starts_with("HTTP/1.1 200"));
+        assert!(certificate.contains("\"path\":\"/srv/old.db\""));
+        assert!(certificate.contains("\"bytes_written\":4096"));
+
+        let progress = request(
+            addr,
+            "GET /jobs/1/progress HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n",
+        );
+        assert!(progress.starts_with("HTTP/1.1 200"));
+        assert!(progress.contains("\"status\":\"succeeded\""));
+        assert!(progress.contains("\"fraction\":1.0000"));
+    }
+}
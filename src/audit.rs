@@ -0,0 +1,211 @@
+use crate::report::WipeReport;
+use crate::Result;
+
+/// event log source wipes are logged under
+#[cfg(windows)]
+const SOURCE: &str = "shredder";
+
+/// kind of record; its value is the event ID in the windows event log
+#[derive(Debug, Clone, Copy)]
+enum Record {
+    Started = 1,
+    Finished = 2,
+    Failed = 3,
+}
+
+/// records the start, finish and failure of wipes in the system log: syslog on
+/// unix, the application event log on windows
+///
+/// destruction records usually have to end up somewhere tamper-resistant and
+/// collected centrally, which the system log already is
+pub struct AuditLog {
+    #[cfg(windows)]
+    handle: winapi::um::winnt::HANDLE,
+}
+
+impl AuditLog {
+    /// connects to the system log
+    ///
+    /// # Returns
+    /// * `Result<AuditLog>` - Connection, or an error if the event source could
+    ///   not be registered (windows)
+    pub fn open() -> Result<Self> {
+        #[cfg(unix)]
+        {
+            // openlog keeps the pointer, so the ident has to live forever
+            static IDENT: &std::ffi::CStr = c"shredder";
+            unsafe { libc::openlog(IDENT.as_ptr(), libc::LOG_PID, libc::LOG_AUTH) };
+            Ok(Self {})
+        }
+
+        #[cfg(windows)]
+        {
+            let source: Vec<u16> = SOURCE.encode_utf16().chain(Some(0)).collect();
+            let handle = unsafe {
+                winapi::um::winbase::RegisterEventSourceW(std::ptr::null(), source.as_ptr())
+            };
+            if handle.is_null() {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(Self { handle })
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(crate::WipeError::UnsupportedOperation(
+            "No system log on this platform".to_string(),
+        ))
+    }
+
+    /// records that a wipe started
+    ///
+    /// # Arguments
+    /// * `subject` - What is wiped, e.g. a path or "job 'print-spool'"
+    pub fn started(&self, subject: &str) {
+        self.write(Record::Started, &started_message(subject));
+    }
+
+    /// records that a wipe finished, with one record per wiped target
+    ///
+    /// # Arguments
+    /// * `subject` - What was wiped
+    /// * `reports` - Reports of the wiped targets
+    pub fn finished(&self, subject: &str, reports: &[WipeReport]) {
+        for message in finished_messages(subject, reports) {
+            self.write(Record::Finished, &message);
+        }
+    }
+
+    /// records that a wipe failed, with one record per error
+    ///
+    /// # Arguments
+    /// * `subject` - What was being wiped
+    /// * `errors` - Errors that occurred
+    pub fn failed(&self, subject: &str, errors: &[String]) {
+        for error in errors {
+            self.write(Record::Failed, &failed_message(subject, error));
+        }
+    }
+
+    #[cfg(unix)]
+    fn write(&self, record: Record, message: &str) {
+        let priority = match record {
+            Record::Failed => libc::LOG_ERR,
+            Record::Started | Record::Finished => libc::LOG_NOTICE,
+        };
+        // interior NULs can't be passed to syslog
+        let message =
+            std::ffi::CString::new(message.replace('\0', " ")).expect("NULs were replaced");
+        unsafe { libc::syslog(priority, c"%s".as_ptr(), message.as_ptr()) };
+    }
+
+    #[cfg(windows)]
+    fn write(&self, record: Record, message: &str) {
+        use winapi::um::winnt::{EVENTLOG_ERROR_TYPE, EVENTLOG_INFORMATION_TYPE};
+
+        let kind = match record {
+            Record::Failed => EVENTLOG_ERROR_TYPE,
+            Record::Started | Record::Finished => EVENTLOG_INFORMATION_TYPE,
+        };
+        let wide: Vec<u16> = message.encode_utf16().chain(Some(0)).collect();
+        let mut strings = [wide.as_ptr()];
+        let success = unsafe {
+            winapi::um::winbase::ReportEventW(
+                self.handle,
+                kind,
+                0,
+                record as u32,
+                std::ptr::null_mut(),
+                1,
+                0,
+                strings.as_mut_ptr(),
+                std::ptr::null_mut(),
+            )
+        };
+        if success == 0 {
+            log::warn!(
+                "Unable to write to the event log: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn write(&self, _record: Record, _message: &str) {}
+}
+
+#[cfg(windows)]
+impl Drop for AuditLog {
+    fn drop(&mut self) {
+        unsafe { winapi::um::winbase::DeregisterEventSource(self.handle) };
+    }
+}
+
+/// who started the wipe, looking through sudo
+fn user() -> String {
+    #[cfg(unix)]
+    {
+        format!("uid={}", crate::locations::invoking_uid())
+    }
+    #[cfg(not(unix))]
+    {
+        format!(
+            "user={}",
+            std::env::var("USERNAME").unwrap_or_else(|_| "unknown".to_string())
+        )
+    }
+}
+
+fn started_message(subject: &str) -> String {
+    format!("wipe started: {} ({})", subject, user())
+}
+
+fn finished_messages(subject: &str, reports: &[WipeReport]) -> Vec<String> {
+    let mut messages: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            let mut message = format!(
+                "wiped {}: {} pass(es), {} bytes written",
+                report.path.display(),
+                report.passes,
+                report.bytes_written
+            );
+            if let Some(sha256) = &report.sha256 {
+                message.push_str(&format!(", sha256 {}", sha256));
+            }
+            message
+        })
+        .collect();
+    messages.push(format!(
+        "wipe finished: {}, {} target(s) ({})",
+        subject,
+        reports.len(),
+        user()
+    ));
+    messages
+}
+
+fn failed_message(subject: &str, error: &str) -> String {
+    format!("wipe failed: {}: {} ({})", subject, error, user())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_messages() {
+        let mut report = WipeReport::new(PathBuf::from("/srv/old.db"));
+        report.record_pass(4096);
+        let messages = finished_messages("job 'cleanup'", &[report]);
+        assert_eq!(
+            messages[0],
+            "wiped /srv/old.db: 1 pass(es), 4096 bytes written"
+        );
+        assert!(messages[1].starts_with("wipe finished: job 'cleanup', 1 target(s) ("));
+
+        let message = failed_message("/srv/locked.db", "Target is open");
+        assert!(message.starts_with("wipe failed: /srv/locked.db: Target is open ("));
+        assert!(started_message("/dev/sdb").starts_with("wipe started: /dev/sdb ("));
+    }
+}
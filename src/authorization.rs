@@ -0,0 +1,147 @@
+//! starting a tool as root through macOS Authorization Services, which shows
+//! the system's authentication dialog, so apps and GUI wrappers built on the
+//! library can get administrator rights for the hardware operations without
+//! running as root themselves
+//!
+//! AuthorizationExecuteWithPrivileges is deprecated, but unlike a blessed
+//! launchd helper (SMJobBless) it needs no signed app bundle to install, and
+//! it hands back a pipe to the tool's stdin and stdout
+
+use crate::{Result, WipeError};
+use std::ffi::{c_char, c_void, CString};
+use std::fs::File;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::FromRawFd;
+use std::path::Path;
+use std::ptr;
+
+type OsStatus = i32;
+type AuthorizationRef = *mut c_void;
+
+#[repr(C)]
+struct AuthorizationItem {
+    name: *const c_char,
+    value_length: usize,
+    value: *mut c_void,
+    flags: u32,
+}
+
+#[repr(C)]
+struct AuthorizationItemSet {
+    count: u32,
+    items: *mut AuthorizationItem,
+}
+
+const ERR_AUTHORIZATION_SUCCESS: OsStatus = 0;
+const ERR_AUTHORIZATION_CANCELED: OsStatus = -60006;
+const ERR_AUTHORIZATION_DENIED: OsStatus = -60005;
+
+const FLAG_INTERACTION_ALLOWED: u32 = 1 << 0;
+const FLAG_EXTEND_RIGHTS: u32 = 1 << 1;
+const FLAG_PRE_AUTHORIZE: u32 = 1 << 4;
+
+/// the right to run tools as root
+const RIGHT_EXECUTE: &[u8] = b"system.privilege.admin\0";
+
+#[link(name = "Security", kind = "framework")]
+extern "C" {
+    fn AuthorizationCreate(
+        rights: *const AuthorizationItemSet,
+        environment: *const AuthorizationItemSet,
+        flags: u32,
+        authorization: *mut AuthorizationRef,
+    ) -> OsStatus;
+
+    fn AuthorizationExecuteWithPrivileges(
+        authorization: AuthorizationRef,
+        path_to_tool: *const c_char,
+        options: u32,
+        arguments: *const *const c_char,
+        communications_pipe: *mut *mut libc::FILE,
+    ) -> OsStatus;
+
+    fn AuthorizationFree(authorization: AuthorizationRef, flags: u32) -> OsStatus;
+}
+
+/// asks for administrator rights and starts `program` with `args` as root
+///
+/// # Arguments
+/// * `program` - Absolute path of the tool
+/// * `args` - Its arguments
+///
+/// # Returns
+/// * `Result<File>` - A pipe connected to the tool's stdin and stdout, or a
+///   permission error if the dialog was cancelled or the user isn't an administrator
+pub(crate) fn execute_with_privileges(program: &Path, args: &[&str]) -> Result<File> {
+    let path = CString::new(program.as_os_str().as_bytes())
+        .map_err(|_| WipeError::Helper(format!("invalid path {}", program.display())))?;
+    let args = args
+        .iter()
+        .map(|arg| CString::new(*arg))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| WipeError::Helper(e.to_string()))?;
+    let mut argv: Vec<*const c_char> = args.iter().map(|arg| arg.as_ptr()).collect();
+    argv.push(ptr::null());
+
+    let mut item = AuthorizationItem {
+        name: RIGHT_EXECUTE.as_ptr().cast(),
+        value_length: 0,
+        value: ptr::null_mut(),
+        flags: 0,
+    };
+    let rights = AuthorizationItemSet {
+        count: 1,
+        items: &mut item,
+    };
+
+    let mut authorization: AuthorizationRef = ptr::null_mut();
+    let status = unsafe {
+        AuthorizationCreate(
+            &rights,
+            ptr::null(),
+            FLAG_INTERACTION_ALLOWED | FLAG_EXTEND_RIGHTS | FLAG_PRE_AUTHORIZE,
+            &mut authorization,
+        )
+    };
+    if status != ERR_AUTHORIZATION_SUCCESS {
+        return Err(error("authorization", status));
+    }
+
+    let mut pipe: *mut libc::FILE = ptr::null_mut();
+    let status = unsafe {
+        let status = AuthorizationExecuteWithPrivileges(
+            authorization,
+            path.as_ptr(),
+            0,
+            argv.as_ptr(),
+            &mut pipe,
+        );
+        AuthorizationFree(authorization, 0);
+        status
+    };
+    if status != ERR_AUTHORIZATION_SUCCESS {
+        return Err(error("starting the tool", status));
+    }
+    if pipe.is_null() {
+        return Err(WipeError::Helper("no pipe to the elevated tool".into()));
+    }
+
+    // a plain descriptor, so reads aren't buffered behind our back by stdio
+    let fd = unsafe { libc::dup(libc::fileno(pipe)) };
+    unsafe { libc::fclose(pipe) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+fn error(what: &str, status: OsStatus) -> WipeError {
+    match status {
+        ERR_AUTHORIZATION_CANCELED | ERR_AUTHORIZATION_DENIED => WipeError::Io(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "administrator rights were not granted",
+        )),
+        status => WipeError::Helper(format!("{} failed (OSStatus {})", what, status)),
+    }
+}
@@ -0,0 +1,194 @@
+use crate::patterns::WipePattern;
+use crate::{device, Result, WipeError};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// buffer sizes tried, from 64 KiB to the 16 MiB maximum of `with_buffer_size`
+pub const CANDIDATES: [usize; 9] = [
+    64 << 10,
+    128 << 10,
+    256 << 10,
+    512 << 10,
+    1 << 20,
+    2 << 20,
+    4 << 20,
+    8 << 20,
+    16 << 20,
+];
+
+/// throughput measured with one buffer size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchResult {
+    /// buffer size in bytes
+    pub buffer_size: usize,
+
+    /// write throughput, synced to the device, in bytes per second
+    pub write_speed: f64,
+
+    /// read throughput in bytes per second; may come from the page cache on
+    /// platforms where it can't be dropped
+    pub read_speed: f64,
+}
+
+/// measures write and read throughput with each of the candidate buffer sizes
+///
+/// in a directory, a temporary file is written and removed afterwards. a device
+/// is written directly, which DESTROYS the first `bytes` of it
+///
+/// # Arguments
+/// * `target` - Directory on the storage to measure, or a block device
+/// * `bytes` - Data written per buffer size
+///
+/// # Returns
+/// * `Result<Vec<BenchResult>>` - One result per candidate, in the order of `CANDIDATES`
+pub fn benchmark(target: &Path, bytes: u64) -> Result<Vec<BenchResult>> {
+    let (mut file, _temp) = if device::is_block_device(target) {
+        let mut file = OpenOptions::new().read(true).write(true).open(target)?;
+        let size = device::device_size(&mut file)?;
+        if size < bytes {
+            return Err(WipeError::UnsupportedOperation(format!(
+                "{} is smaller than the {} bytes to write",
+                target.display(),
+                bytes
+            )));
+        }
+        (file, None)
+    } else if target.is_dir() {
+        let temp = BenchFile(target.join(format!(".shredder-bench-{}", std::process::id())));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&temp.0)?;
+        (file, Some(temp))
+    } else {
+        return Err(WipeError::UnsupportedOperation(format!(
+            "{} is neither a directory nor a block device",
+            target.display()
+        )));
+    };
+
+    let mut data = vec![0u8; *CANDIDATES.iter().max().unwrap_or(&(1 << 20))];
+    WipePattern::Random.fill_buffer(&mut data);
+
+    CANDIDATES
+        .iter()
+        .map(|&buffer_size| {
+            let write = time_writes(&mut file, &data[..buffer_size], bytes)?;
+            drop_cache(&file, bytes);
+            let read = time_reads(&mut file, buffer_size, bytes)?;
+            Ok(BenchResult {
+                buffer_size,
+                write_speed: bytes as f64 / write.as_secs_f64().max(1e-9),
+                read_speed: bytes as f64 / read.as_secs_f64().max(1e-9),
+            })
+        })
+        .collect()
+}
+
+/// the file written in a directory, removed when dropped
+struct BenchFile(PathBuf);
+
+impl Drop for BenchFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// the buffer size with the best write throughput
+///
+/// sizes within 5% of the fastest count as equally fast, and the smallest of
+/// those wins since it uses less memory
+///
+/// # Arguments
+/// * `results` - Measurements from `benchmark`
+///
+/// # Returns
+/// * `Option<usize>` - Buffer size in bytes, or None without results
+pub fn best_buffer_size(results: &[BenchResult]) -> Option<usize> {
+    let fastest = results
+        .iter()
+        .map(|result| result.write_speed)
+        .fold(0.0, f64::max);
+    results
+        .iter()
+        .filter(|result| result.write_speed >= fastest * 0.95)
+        .map(|result| result.buffer_size)
+        .min()
+}
+
+fn time_writes(file: &mut File, buffer: &[u8], bytes: u64) -> Result<Duration> {
+    let started = Instant::now();
+    file.seek(SeekFrom::Start(0))?;
+    let mut written = 0;
+    while written < bytes {
+        let len = (bytes - written).min(buffer.len() as u64) as usize;
+        file.write_all(&buffer[..len])?;
+        written += len as u64;
+    }
+    file.sync_data()?;
+    Ok(started.elapsed())
+}
+
+fn time_reads(file: &mut File, buffer_size: usize, bytes: u64) -> Result<Duration> {
+    let mut buffer = vec![0u8; buffer_size];
+    let started = Instant::now();
+    file.seek(SeekFrom::Start(0))?;
+    let mut read = 0;
+    while read < bytes {
+        let len = (bytes - read).min(buffer_size as u64) as usize;
+        file.read_exact(&mut buffer[..len])?;
+        read += len as u64;
+    }
+    Ok(started.elapsed())
+}
+
+/// drops the just-written data from the page cache so reads hit the device
+#[cfg(target_os = "linux")]
+fn drop_cache(file: &File, bytes: u64) {
+    use std::os::unix::io::AsRawFd;
+    unsafe {
+        libc::posix_fadvise(
+            file.as_raw_fd(),
+            0,
+            bytes as libc::off_t,
+            libc::POSIX_FADV_DONTNEED,
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn drop_cache(_file: &File, _bytes: u64) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_best_buffer_size() {
+        let result = |buffer_size, write_speed| BenchResult {
+            buffer_size,
+            write_speed,
+            read_speed: 0.0,
+        };
+        let results = [
+            result(64 << 10, 100.0),
+            result(1 << 20, 197.0),
+            result(4 << 20, 200.0),
+            result(16 << 20, 150.0),
+        ];
+        assert_eq!(best_buffer_size(&results), Some(1 << 20));
+        assert_eq!(best_buffer_size(&[]), None);
+    }
+
+    #[test]
+    fn test_benchmark_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let results = benchmark(dir.path(), 256 << 10).unwrap();
+        assert_eq!(results.len(), CANDIDATES.len());
+        // the temporary file is gone
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,370 @@
+use crate::filesystem::MountInfo;
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+use crate::report::WipeReport;
+use crate::storage::{StorageCapabilities, StorageType};
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+use crate::{storage::StorageInfo, Result};
+use std::path::{Path, PathBuf};
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+use std::process::Command;
+
+/// disk drivers whose devices are named <driver><unit><partition>: physical
+/// disks (sd, wd, ld), wedges (dk) and the software volumes built from them
+/// (cgd, raid, ccd and vnd on NetBSD; softraid volumes are sd disks)
+const DRIVERS: [&str; 8] = ["sd", "wd", "ld", "dk", "cgd", "raid", "ccd", "vnd"];
+
+/// what a software volume does with the disks beneath it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SoftwareVolume {
+    /// softraid CRYPTO or RAID1C, cgd
+    Encrypted,
+    /// softraid RAID 0/1/5, RAIDframe
+    Raid,
+    /// softraid CONCAT, ccd
+    Concat,
+}
+
+/// the disk a device node or partition belongs to, e.g. "sd0" for /dev/rsd0c
+/// or sd0a and "cgd1" for /dev/cgd1e; None for anything that isn't a disk
+pub(crate) fn disk_name(device: &str) -> Option<String> {
+    let name = device.strip_prefix("/dev/").unwrap_or(device);
+    let letters = name.find(|c: char| !c.is_ascii_alphabetic())?;
+    let digits = name[letters..]
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(name.len() - letters);
+    if digits == 0 {
+        return None;
+    }
+    // raw devices carry an r in front: rsd0c, rwd0d
+    let driver = &name[..letters];
+    let driver = if DRIVERS.contains(&driver) {
+        driver
+    } else {
+        driver.strip_prefix('r').filter(|d| DRIVERS.contains(d))?
+    };
+    Some(format!("{}{}", driver, &name[letters..letters + digits]))
+}
+
+/// what `disklabel <disk>` reports about a disk
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct DiskLabel {
+    /// "disk:", the model on NetBSD and the bus ("SCSI disk") on OpenBSD
+    disk: String,
+    /// "label:", the model on OpenBSD and usually "fictitious" on NetBSD
+    label: String,
+    bytes_per_sector: Option<usize>,
+    total_sectors: Option<u64>,
+}
+
+impl DiskLabel {
+    pub(crate) fn parse(output: &str) -> Self {
+        let mut label = DiskLabel::default();
+        for line in output.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "disk" => label.disk = value.to_string(),
+                "label" => label.label = value.to_string(),
+                "bytes/sector" => label.bytes_per_sector = value.parse().ok(),
+                "total sectors" => label.total_sectors = value.parse().ok(),
+                _ => {}
+            }
+        }
+        label
+    }
+
+    /// vendor and model of the disk, whichever field holds them
+    fn model(&self) -> &str {
+        match self.label.as_str() {
+            "" | "fictitious" | "default label" => &self.disk,
+            label => label,
+        }
+    }
+
+    /// the storage type the model suggests; neither system reports whether a
+    /// disk rotates, so anything not recognizably solid state is a hard disk
+    fn storage_type(&self) -> StorageType {
+        let model = self.model().to_ascii_lowercase();
+        let caps = |has_wear_leveling| StorageCapabilities {
+            // neither TRIM nor secure erase is done on these systems
+            supports_trim: false,
+            supports_secure_erase: false,
+            supports_nvme_sanitize: false,
+            has_wear_leveling,
+        };
+        if ["sd/mmc", "usb", "flash", "card"]
+            .iter()
+            .any(|hint| model.contains(hint))
+        {
+            StorageType::Flash(caps(true))
+        } else if ["ssd", "nvme", "solid state"]
+            .iter()
+            .any(|hint| model.contains(hint))
+        {
+            StorageType::Ssd(caps(true))
+        } else {
+            StorageType::Hdd(caps(false))
+        }
+    }
+}
+
+/// finds a softraid volume in `bioctl` output: its RAID level is the last
+/// column of its line and its chunks follow, indented, as <sd0a>
+#[cfg(any(target_os = "openbsd", test))]
+pub(crate) fn parse_bioctl(output: &str, disk: &str) -> Option<(SoftwareVolume, Vec<String>)> {
+    let mut lines = output.lines();
+    let level = lines.find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        (fields.get(4) == Some(&disk)).then(|| fields.last().copied())?
+    })?;
+    let kind = match level {
+        "CRYPTO" | "RAID1C" => SoftwareVolume::Encrypted,
+        "CONCAT" => SoftwareVolume::Concat,
+        _ => SoftwareVolume::Raid,
+    };
+    let chunks = lines
+        .take_while(|line| line.starts_with(char::is_whitespace))
+        .flat_map(device_tokens)
+        .collect();
+    Some((kind, chunks))
+}
+
+/// the devices named in a line of cgdconfig, raidctl, ccdconfig or bioctl
+/// output: /dev/wd0e, /dev/wd1a: or <sd0a>
+fn device_tokens(line: &str) -> Vec<String> {
+    line.split_whitespace()
+        .filter(|token| token.starts_with("/dev/") || token.starts_with('<'))
+        .map(|token| token.trim_matches(|c| c == '<' || c == '>' || c == ':'))
+        .filter_map(disk_name)
+        .collect()
+}
+
+/// parses `mount` output: "/dev/sd0a on / type ffs (local, read-only)"
+pub(crate) fn parse_mount(output: &str) -> Vec<MountInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (source, rest) = line.split_once(" on ")?;
+            let (mount_point, rest) = rest.rsplit_once(" type ")?;
+            let (fs_type, options) = rest.split_once(' ').unwrap_or((rest, ""));
+            Some(MountInfo {
+                mount_point: PathBuf::from(mount_point),
+                source: source.to_string(),
+                fs_type: fs_type.to_string(),
+                read_only: options.contains("read-only"),
+            })
+        })
+        .collect()
+}
+
+/// the filesystem a path is on: the mount with the longest matching mount point
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+pub(crate) fn mount_info(path: &Path) -> Result<MountInfo> {
+    let path = std::fs::canonicalize(path)?;
+    let output = Command::new("mount").output()?;
+    parse_mount(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.as_os_str().len())
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "No mount holds the path").into()
+        })
+}
+
+/// the disk a path is on: the disk of a device node, or of the device its
+/// filesystem was mounted from
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+fn disk_of(path: &Path) -> Result<String> {
+    let canonical_path = std::fs::canonicalize(path)?;
+    let source = if canonical_path.starts_with("/dev") {
+        canonical_path.to_string_lossy().into_owned()
+    } else {
+        mount_info(&canonical_path)?.source
+    };
+    disk_name(&source)
+        .ok_or_else(|| std::io::Error::other(format!("{} is not a disk", source)).into())
+}
+
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+fn disklabel(disk: &str) -> Result<DiskLabel> {
+    let output = Command::new("disklabel").arg(disk).output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        )
+        .into());
+    }
+    Ok(DiskLabel::parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// whether a disk is a software volume, and the disks it is built from
+#[cfg(target_os = "openbsd")]
+fn software_volume(disk: &str) -> Option<(SoftwareVolume, Vec<String>)> {
+    // softraid volumes are sd disks; bioctl only knows the ones it manages
+    let output = Command::new("bioctl").arg(disk).output().ok()?;
+    parse_bioctl(&String::from_utf8_lossy(&output.stdout), disk)
+}
+
+#[cfg(target_os = "netbsd")]
+fn software_volume(disk: &str) -> Option<(SoftwareVolume, Vec<String>)> {
+    let (kind, command, args) = if disk.starts_with("cgd") {
+        (SoftwareVolume::Encrypted, "cgdconfig", vec!["-l", disk])
+    } else if disk.starts_with("raid") {
+        (SoftwareVolume::Raid, "raidctl", vec!["-s", disk])
+    } else if disk.starts_with("ccd") {
+        (SoftwareVolume::Concat, "ccdconfig", vec!["-g", disk])
+    } else {
+        return None;
+    };
+    let components = Command::new(command)
+        .args(args)
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .flat_map(device_tokens)
+                .filter(|component| component != disk)
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((kind, components))
+}
+
+/// detects the storage a path is on from its disklabel; for a software
+/// volume the media are those of the first disk it is built from
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+pub(crate) fn detect_storage(path: &Path) -> Result<StorageInfo> {
+    let disk = disk_of(path)?;
+    let label = disklabel(&disk)?;
+
+    // volumes can stack (cgd on raid); two levels cover what is common
+    let mut media = disk.clone();
+    for _ in 0..2 {
+        match software_volume(&media).and_then(|(_, disks)| disks.into_iter().next()) {
+            Some(beneath) => media = beneath,
+            None => break,
+        }
+    }
+    let device_type = if media == disk {
+        label.storage_type()
+    } else {
+        disklabel(&media)?.storage_type()
+    };
+
+    let block_size = label.bytes_per_sector.unwrap_or(512);
+    Ok(StorageInfo {
+        device_type,
+        block_size,
+        physical_block_size: block_size,
+        total_size: label.total_sectors.unwrap_or(0) * block_size as u64,
+    })
+}
+
+/// records in the report what an overwrite of a target on a software volume
+/// does and doesn't reach
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+pub(crate) fn check_software_volume(path: &Path, report: &mut WipeReport) {
+    let Ok(disk) = disk_of(path) else {
+        return;
+    };
+    match software_volume(&disk) {
+        Some((SoftwareVolume::Encrypted, _)) => report.add_limitation(format!(
+            "Target is on the encrypted volume {}: the passes reach the disks beneath as \
+             ciphertext and the volume's key is left in place. Destroying the volume or its \
+             key makes everything it holds unreadable at once.",
+            disk
+        )),
+        Some((SoftwareVolume::Raid, _)) => report.add_limitation(format!(
+            "Target is on the software RAID volume {}: the passes reach its current disks, \
+             not disks that failed or were replaced before the wipe.",
+            disk
+        )),
+        Some((SoftwareVolume::Concat, _)) | None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_names() {
+        assert_eq!(disk_name("/dev/rsd0c").as_deref(), Some("sd0"));
+        assert_eq!(disk_name("sd2a").as_deref(), Some("sd2"));
+        assert_eq!(disk_name("/dev/wd1e").as_deref(), Some("wd1"));
+        assert_eq!(disk_name("/dev/rcgd0d").as_deref(), Some("cgd0"));
+        assert_eq!(disk_name("/dev/raid1a").as_deref(), Some("raid1"));
+        assert_eq!(disk_name("/dev/cd0a"), None);
+        assert_eq!(disk_name("tmpfs"), None);
+    }
+
+    #[test]
+    fn test_disklabel() {
+        let openbsd = "# /dev/rsd0c:
+type: SCSI
+disk: SCSI disk
+label: Samsung SSD 860
+duid: 2f7e3b1a9c4d5e6f
+bytes/sector: 512
+total sectors: 976773168
+";
+        let label = DiskLabel::parse(openbsd);
+        assert_eq!(label.model(), "Samsung SSD 860");
+        assert_eq!(label.bytes_per_sector, Some(512));
+        assert_eq!(label.total_sectors, Some(976773168));
+        assert!(matches!(label.storage_type(), StorageType::Ssd(_)));
+
+        let netbsd = "# /dev/rwd0d:
+type: ESDI
+disk: WDC WD10EZEX-08W
+label: fictitious
+bytes/sector: 512
+total sectors: 1953525168
+rpm: 3600
+";
+        let label = DiskLabel::parse(netbsd);
+        assert_eq!(label.model(), "WDC WD10EZEX-08W");
+        assert!(matches!(label.storage_type(), StorageType::Hdd(_)));
+    }
+
+    #[test]
+    fn test_software_volumes() {
+        let bioctl = "Volume      Status               Size Device
+softraid0 0 Online       256060514304 sd2     CRYPTO
+          0 Online       256060514304 0:0.0   noencl <sd0a>
+softraid0 1 Online       500107862016 sd3     RAID1
+          0 Online       500107862016 1:0.0   noencl <wd0a>
+          1 Online       500107862016 1:1.0   noencl <wd1a>
+";
+        assert_eq!(
+            parse_bioctl(bioctl, "sd2"),
+            Some((SoftwareVolume::Encrypted, vec!["sd0".to_string()]))
+        );
+        assert_eq!(
+            parse_bioctl(bioctl, "sd3"),
+            Some((
+                SoftwareVolume::Raid,
+                vec!["wd0".to_string(), "wd1".to_string()]
+            ))
+        );
+        assert_eq!(parse_bioctl(bioctl, "sd0"), None);
+        assert_eq!(device_tokens("           /dev/wd0a: optimal"), ["wd0"]);
+    }
+
+    #[test]
+    fn test_mount_output() {
+        let mounts = parse_mount(
+            "/dev/sd2a on / type ffs (local, wxallowed)
+/dev/sd2e on /home type ffs (local, nodev, nosuid, read-only)
+",
+        );
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(mounts[1].mount_point, Path::new("/home"));
+        assert_eq!(mounts[1].source, "/dev/sd2e");
+        assert_eq!(mounts[1].fs_type, "ffs");
+        assert!(mounts[1].read_only);
+        assert!(!mounts[0].read_only);
+    }
+}
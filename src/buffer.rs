@@ -0,0 +1,486 @@
+use crate::patterns::{Keystream, SeedSource, WipePattern};
+use log::debug;
+use std::alloc::{self, Layout};
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::time::Duration;
+
+/// alignment of the buffers, a page and a multiple of any sector size
+const ALIGN: usize = 4096;
+
+/// smallest write size the tuner goes down to
+const MIN_SIZE: usize = 64 * 1024;
+
+/// largest write size, the same cap as `Shredder::with_buffer_size`
+const MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// time spent writing before the throughput of a size is judged
+const WINDOW: Duration = Duration::from_millis(250);
+
+/// a single write taking longer than this makes the size shrink, so progress
+/// and cancellation stay responsive on slow or stalling devices
+const SLOW_WRITE: Duration = Duration::from_secs(1);
+
+/// a change of less than this is noise
+const SIGNIFICANT: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Grow,
+    Shrink,
+    Settled,
+}
+
+/// adjusts the write size during an overwrite pass
+///
+/// starts from the configured size and doubles it while that makes writing
+/// measurably faster; once a step makes it slower, it steps back and settles.
+/// independently of that, the size is halved when a single write stalls or
+/// when little memory is left
+#[derive(Debug)]
+pub(crate) struct BufferTuner {
+    size: usize,
+    direction: Direction,
+    /// throughput of the previous window, in bytes per second
+    previous: Option<f64>,
+    window_bytes: u64,
+    window_time: Duration,
+    slowest: Duration,
+}
+
+impl BufferTuner {
+    pub(crate) fn new(initial: usize) -> Self {
+        Self {
+            size: initial.min(MAX_SIZE),
+            direction: Direction::Grow,
+            previous: None,
+            window_bytes: 0,
+            window_time: Duration::ZERO,
+            slowest: Duration::ZERO,
+        }
+    }
+
+    /// size for the next write
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    /// records a write of `bytes` that took `elapsed`
+    pub(crate) fn record(&mut self, bytes: usize, elapsed: Duration) {
+        self.window_bytes += bytes as u64;
+        self.window_time += elapsed;
+        self.slowest = self.slowest.max(elapsed);
+
+        if self.window_time >= WINDOW {
+            let speed = self.window_bytes as f64 / self.window_time.as_secs_f64();
+            self.adjust(speed, self.slowest, available_memory());
+            self.window_bytes = 0;
+            self.window_time = Duration::ZERO;
+            self.slowest = Duration::ZERO;
+        }
+    }
+
+    /// picks the size for the next window from the last one's measurements
+    fn adjust(&mut self, speed: f64, slowest: Duration, available: Option<u64>) {
+        let before = self.size;
+
+        // a few buffers' worth of memory must stay free for everything else
+        let low_memory = available.is_some_and(|free| free < self.size as u64 * 16);
+        if slowest > SLOW_WRITE || low_memory {
+            self.size = (self.size / 2).max(MIN_SIZE.min(self.size));
+            self.direction = Direction::Settled;
+        } else {
+            match (self.direction, self.previous) {
+                (Direction::Settled, _) => {}
+                (_, None) => self.step(),
+                (_, Some(previous)) if speed > previous * (1.0 + SIGNIFICANT) => self.step(),
+                (direction, Some(previous)) if speed < previous * (1.0 - SIGNIFICANT) => {
+                    // the last step hurt: undo it and stay there
+                    self.direction = match direction {
+                        Direction::Grow => Direction::Shrink,
+                        _ => Direction::Grow,
+                    };
+                    self.step();
+                    self.direction = Direction::Settled;
+                }
+                _ => self.direction = Direction::Settled,
+            }
+        }
+        self.previous = Some(speed);
+
+        if self.size != before {
+            debug!(
+                "Write buffer {} KiB -> {} KiB ({:.0} MB/s, slowest write {:?})",
+                before / 1024,
+                self.size / 1024,
+                speed / 1e6,
+                slowest
+            );
+        }
+    }
+
+    fn step(&mut self) {
+        let next = match self.direction {
+            Direction::Grow if self.size * 2 <= MAX_SIZE => Some(self.size * 2),
+            Direction::Shrink if self.size / 2 >= MIN_SIZE => Some(self.size / 2),
+            _ => None,
+        };
+        match next {
+            Some(next) => self.size = next,
+            None => self.direction = Direction::Settled,
+        }
+    }
+}
+
+/// length of the next write at a block-aligned offset with `remaining` bytes
+/// to go: a whole `chunk`, or what is left of the target. when the target
+/// doesn't end on a block boundary, the last partial block goes in a write of
+/// its own, so every other write stays in whole blocks
+///
+/// # Arguments
+/// * `remaining` - Bytes left to write
+/// * `chunk` - Write size, a multiple of `block`
+/// * `block` - Block size of the storage
+pub(crate) fn next_write(remaining: u64, chunk: usize, block: usize) -> usize {
+    if remaining >= chunk as u64 {
+        return chunk;
+    }
+    let remaining = remaining as usize;
+    match remaining % block {
+        0 => remaining,
+        tail if tail == remaining => remaining,
+        tail => remaining - tail,
+    }
+}
+
+/// zeroed heap memory aligned to a page
+pub(crate) struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// the buffer owns its memory, like a Vec<u8>
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+impl AlignedBuffer {
+    pub(crate) fn new(len: usize) -> Self {
+        let ptr = unsafe { alloc::alloc_zeroed(Self::layout(len)) };
+        match NonNull::new(ptr) {
+            Some(ptr) => Self { ptr, len },
+            None => alloc::handle_alloc_error(Self::layout(len)),
+        }
+    }
+
+    fn layout(len: usize) -> Layout {
+        Layout::from_size_align(len.max(1).next_multiple_of(ALIGN), ALIGN)
+            .expect("buffer size overflows")
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        // patterns, and data read back from the target, don't stay behind in
+        // freed memory
+        zeroize::Zeroize::zeroize(&mut **self);
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), Self::layout(self.len)) };
+    }
+}
+
+/// what a pass writes
+pub(crate) enum PassSource<'a> {
+    /// a pattern repeated over the target from offset 0
+    Repeat(&'a [u8]),
+    /// random data, different at every offset
+    Keystream(Keystream),
+}
+
+impl<'a> PassSource<'a> {
+    /// the source for a pass of `pattern`; repeating patterns are filled into
+    /// `unit`, random data comes from the next keystream of `seeds`
+    pub(crate) fn new(pattern: &WipePattern, unit: &'a mut [u8], seeds: &SeedSource) -> Self {
+        match pattern {
+            WipePattern::Random => PassSource::Keystream(seeds.keystream()),
+            _ => {
+                pattern.fill_buffer(unit);
+                PassSource::Repeat(unit)
+            }
+        }
+    }
+
+    /// seed of a random pass, which regenerates everything it writes
+    pub(crate) fn seed(&self) -> Option<[u8; 32]> {
+        match self {
+            PassSource::Keystream(keystream) => Some(keystream.seed()),
+            PassSource::Repeat(_) => None,
+        }
+    }
+
+    /// the pattern as `shred verify --pattern` takes it: random data only as
+    /// "random", which is checked for entropy, and a repeated pattern as the
+    /// hex bytes of its shortest period
+    pub(crate) fn pattern_arg(&self) -> String {
+        match self {
+            PassSource::Keystream(_) => "random".to_string(),
+            PassSource::Repeat(unit) => {
+                let period = (1..unit.len())
+                    .find(|&p| (p..unit.len()).all(|i| unit[i] == unit[i - p]))
+                    .unwrap_or(unit.len());
+                unit[..period]
+                    .iter()
+                    .map(|byte| format!("{:#04x}", byte))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }
+        }
+    }
+
+    /// fills `buffer` with what the pass writes at `offset`
+    pub(crate) fn fill(&self, offset: u64, buffer: &mut [u8]) {
+        match self {
+            PassSource::Repeat(unit) => {
+                let mut pos = 0;
+                while pos < buffer.len() {
+                    let phase = ((offset + pos as u64) % unit.len() as u64) as usize;
+                    let n = (unit.len() - phase).min(buffer.len() - pos);
+                    buffer[pos..pos + n].copy_from_slice(&unit[phase..phase + n]);
+                    pos += n;
+                }
+            }
+            PassSource::Keystream(keystream) => keystream.fill(offset, buffer),
+        }
+    }
+}
+
+/// buffers of one writing thread
+pub(crate) struct ThreadBuffers {
+    /// keystream data about to be written
+    pub(crate) scratch: AlignedBuffer,
+    /// data read back or read ahead
+    pub(crate) read: AlignedBuffer,
+}
+
+/// a pass's data, ready to be cut into chunks
+pub(crate) struct PassData<'a> {
+    source: &'a PassSource<'a>,
+    rendered: &'a [u8],
+}
+
+impl PassData<'_> {
+    /// the `len` bytes the pass writes at `offset`; a repeated pattern is
+    /// sliced from the rendered one, keystream data is generated into `scratch`
+    pub(crate) fn chunk<'b>(&'b self, scratch: &'b mut [u8], offset: u64, len: usize) -> &'b [u8] {
+        match self.source {
+            PassSource::Repeat(unit) => {
+                let from = (offset % unit.len() as u64) as usize;
+                &self.rendered[from..from + len]
+            }
+            PassSource::Keystream(keystream) => {
+                keystream.fill(offset, &mut scratch[..len]);
+                &scratch[..len]
+            }
+        }
+    }
+}
+
+/// write and read-back buffers shared by all passes of a wipe
+///
+/// a repeated pattern is rendered once per pass into a buffer, repeated from
+/// offset 0, and a write at offset `o` of the target takes its data from
+/// `o % pattern.len()` on. the target so holds the pattern without seams
+/// whatever the write sizes, and nothing is refilled while writing
+pub(crate) struct PassBuffers {
+    rendered: AlignedBuffer,
+    /// bytes of `rendered` holding the current pattern
+    rendered_len: usize,
+    threads: Vec<ThreadBuffers>,
+}
+
+impl PassBuffers {
+    pub(crate) fn new() -> Self {
+        Self {
+            rendered: AlignedBuffer::new(0),
+            rendered_len: 0,
+            threads: Vec::new(),
+        }
+    }
+
+    /// starts a pass with a new pattern; nothing is allocated
+    pub(crate) fn start_pass(&mut self) {
+        self.rendered_len = 0;
+    }
+
+    /// the pass's data, for chunks of up to `len` bytes, and buffers of that
+    /// size for `threads` threads
+    ///
+    /// rendering and allocation only happen when `len` grows past what an
+    /// earlier call asked for
+    pub(crate) fn get<'a>(
+        &'a mut self,
+        source: &'a PassSource<'a>,
+        len: usize,
+        threads: usize,
+    ) -> (PassData<'a>, &'a mut [ThreadBuffers]) {
+        let keystream = matches!(source, PassSource::Keystream(_));
+        if let PassSource::Repeat(unit) = source {
+            self.render(unit, len + unit.len());
+        }
+
+        let too_small = |buffers: &ThreadBuffers| {
+            buffers.read.len() < len || (keystream && buffers.scratch.len() < len)
+        };
+        if self.threads.len() < threads || self.threads.iter().any(too_small) {
+            self.threads = (0..threads.max(self.threads.len()))
+                .map(|_| ThreadBuffers {
+                    scratch: AlignedBuffer::new(if keystream { len } else { 0 }),
+                    read: AlignedBuffer::new(len),
+                })
+                .collect();
+        }
+
+        let data = PassData {
+            source,
+            rendered: &self.rendered[..self.rendered_len],
+        };
+        (data, &mut self.threads[..threads])
+    }
+
+    /// repeats the pattern over the first `needed` bytes of the rendered buffer
+    fn render(&mut self, unit: &[u8], needed: usize) {
+        if self.rendered.len() < needed {
+            // round up so a growing write size doesn't reallocate every step
+            self.rendered = AlignedBuffer::new(needed.next_power_of_two());
+            self.rendered_len = 0;
+        }
+        while self.rendered_len < needed {
+            let phase = self.rendered_len % unit.len();
+            let n = (unit.len() - phase).min(needed - self.rendered_len);
+            self.rendered[self.rendered_len..self.rendered_len + n]
+                .copy_from_slice(&unit[phase..phase + n]);
+            self.rendered_len += n;
+        }
+    }
+}
+
+/// memory the system can hand out without swapping, where that is known
+#[cfg(target_os = "linux")]
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn available_memory() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: usize = 1024 * 1024;
+    const MS: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn test_grows_while_faster_then_settles() {
+        let mut tuner = BufferTuner::new(MIB);
+        tuner.adjust(100e6, MS, None);
+        assert_eq!(tuner.size(), 2 * MIB);
+        tuner.adjust(150e6, MS, None);
+        assert_eq!(tuner.size(), 4 * MIB);
+        // 4 MiB was slower than 2 MiB: back to 2 MiB for good
+        tuner.adjust(120e6, MS, None);
+        assert_eq!(tuner.size(), 2 * MIB);
+        tuner.adjust(300e6, MS, None);
+        assert_eq!(tuner.size(), 2 * MIB);
+    }
+
+    #[test]
+    fn test_next_write() {
+        assert_eq!(next_write(10 * 4096, 4 * 4096, 4096), 4 * 4096);
+        assert_eq!(next_write(3 * 4096, 4 * 4096, 4096), 3 * 4096);
+        // the whole blocks, then the partial one on its own
+        assert_eq!(next_write(3 * 4096 + 100, 4 * 4096, 4096), 3 * 4096);
+        assert_eq!(next_write(100, 4 * 4096, 4096), 100);
+        assert_eq!(next_write(4 * 4096 + 100, 4 * 4096, 4096), 4 * 4096);
+    }
+
+    #[test]
+    fn test_pattern_arg() {
+        let mut unit = [0u8; 1000];
+        let source = PassSource::new(
+            &WipePattern::Custom(vec![0x92, 0x49, 0x24]),
+            &mut unit,
+            &SeedSource::System,
+        );
+        assert_eq!(source.pattern_arg(), "0x92,0x49,0x24");
+        let source = PassSource::new(&WipePattern::Zeros, &mut unit, &SeedSource::System);
+        assert_eq!(source.pattern_arg(), "0x00");
+        let source = PassSource::new(&WipePattern::Random, &mut unit, &SeedSource::System);
+        assert_eq!(source.pattern_arg(), "random");
+    }
+
+    #[test]
+    fn test_pass_buffers_render_without_seams() {
+        let mut buffers = PassBuffers::new();
+        let mut unit = [0u8; 3];
+        let source = PassSource::new(
+            &WipePattern::Custom(vec![1, 2, 3]),
+            &mut unit,
+            &SeedSource::System,
+        );
+        let (data, threads) = buffers.get(&source, 8, 2);
+        assert_eq!(threads.len(), 2);
+        assert_eq!(data.rendered.as_ptr() as usize % ALIGN, 0);
+        // a repeated pattern needs no scratch buffer
+        assert_eq!(data.chunk(&mut [], 5, 4), &[3, 1, 2, 3]);
+
+        // a longer write continues the pattern
+        let (data, _) = buffers.get(&source, 5000, 1);
+        let chunk = data.chunk(&mut [], 0, 5000);
+        assert!(chunk
+            .iter()
+            .enumerate()
+            .all(|(i, &b)| b == [1, 2, 3][i % 3]));
+
+        // keystream data is generated at the offset
+        buffers.start_pass();
+        let mut unit = [0u8; 4096];
+        let random = PassSource::new(&WipePattern::Random, &mut unit, &SeedSource::System);
+        let mut expected = [0u8; 100];
+        random.fill(12345, &mut expected);
+        let (data, threads) = buffers.get(&random, 4096, 1);
+        assert_eq!(data.chunk(&mut threads[0].scratch, 12345, 100), &expected);
+    }
+
+    #[test]
+    fn test_shrinks_on_stalls_and_low_memory() {
+        let mut tuner = BufferTuner::new(8 * MIB);
+        tuner.adjust(100e6, Duration::from_secs(3), None);
+        assert_eq!(tuner.size(), 4 * MIB);
+        tuner.adjust(100e6, MS, Some(32 * MIB as u64));
+        assert_eq!(tuner.size(), 2 * MIB);
+        // never below the minimum
+        let mut tuner = BufferTuner::new(MIN_SIZE);
+        tuner.adjust(1e6, Duration::from_secs(3), None);
+        assert_eq!(tuner.size(), MIN_SIZE);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::policy::Policy;
+use crate::standards::{NamedStandard, SanitizationMethod, VerificationLevel, WipeStandard};
+use crate::storage::{StorageProbe, StorageType};
+use crate::Shredder;
+use std::path::Path;
+use thiserror::Error;
+
+/// a combination of settings a shredder can't honor, found before any I/O
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// no storage type was given
+    #[error("No storage type given")]
+    MissingStorage,
+
+    /// the storage a target is on couldn't be detected
+    #[error("Couldn't detect the storage of {path}: {reason}")]
+    Detection { path: String, reason: String },
+
+    /// a custom standard without any passes
+    #[error("A custom standard needs at least one pass")]
+    NoPasses,
+
+    /// a sampled verification reading none or more than all of the blocks
+    #[error("Sampled verification needs a share above 0 and up to 1, not {0}")]
+    InvalidSample(f64),
+
+    /// a multi-pass legacy standard such as Gutmann on storage that remaps
+    /// writes, without [`force_multipass`](ShredderBuilder::force_multipass)
+    #[error(
+        "{standard} on {media}: its {passes} passes only add wear and can't reach remapped blocks"
+    )]
+    MultipassOnWearLeveledStorage {
+        standard: String,
+        passes: usize,
+        media: &'static str,
+    },
+
+    /// a standard or verification weaker than the policy requires
+    #[error("{0}")]
+    Policy(String),
+
+    /// NIST Purge on storage where an overwrite isn't a purge
+    #[error(
+        "NIST Purge of {0} needs a hardware secure erase or sanitize command, which the device doesn't support"
+    )]
+    PurgeWithoutHardwareErase(&'static str),
+}
+
+/// builds a [`Shredder`] after checking that its settings go together
+///
+/// ```
+/// use shredder::standards::VerificationLevel;
+/// use shredder::storage::{StorageCapabilities, StorageType};
+/// use shredder::Shredder;
+///
+/// let ssd = StorageType::Ssd(StorageCapabilities {
+///     supports_trim: true,
+///     supports_secure_erase: true,
+///     supports_nvme_sanitize: false,
+///     has_wear_leveling: true,
+/// });
+/// let shredder = Shredder::builder()
+///     .standard("nist".parse().unwrap())
+///     .storage(ssd)
+///     .verify(VerificationLevel::Full)
+///     .build();
+/// assert!(shredder.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShredderBuilder {
+    standard: WipeStandard,
+    storage: Option<std::result::Result<StorageType, BuildError>>,
+    block_size: Option<usize>,
+    verify: Option<VerificationLevel>,
+    rng_seed: Option<u64>,
+    force_multipass: bool,
+    policy: Policy,
+}
+
+impl Default for ShredderBuilder {
+    fn default() -> Self {
+        Self {
+            standard: NamedStandard::ALL[0].standard(),
+            storage: None,
+            block_size: None,
+            verify: None,
+            rng_seed: None,
+            force_multipass: false,
+            policy: Policy::default(),
+        }
+    }
+}
+
+impl ShredderBuilder {
+    /// sets the standard; NIST Purge if not set
+    pub fn standard(mut self, standard: WipeStandard) -> Self {
+        self.standard = standard;
+        self
+    }
+
+    /// sets the type of storage the targets are on; required
+    pub fn storage(mut self, storage: StorageType) -> Self {
+        self.storage = Some(Ok(storage));
+        self
+    }
+
+    /// sets the storage type and block size to whatever `probe` detects for
+    /// `path`, instead of giving them with [`storage`](Self::storage)
+    ///
+    /// # Arguments
+    /// * `path` - File or device the shredder will wipe
+    /// * `probe` - How to detect it, e.g. [`SystemProbe`](crate::storage::SystemProbe)
+    ///   or a fake device in tests
+    pub fn detect_storage(mut self, path: &Path, probe: &dyn StorageProbe) -> Self {
+        let detected = probe.probe(path).map_err(|e| BuildError::Detection {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        });
+        self.block_size = detected.as_ref().ok().map(|info| info.physical_block_size);
+        self.storage = Some(detected.map(|info| info.device_type));
+        self
+    }
+
+    /// sets the verification level, overriding the standard's own
+    ///
+    /// legacy and custom standards only verify or don't, so any level but None
+    /// turns their verification on
+    pub fn verify(mut self, level: VerificationLevel) -> Self {
+        self.verify = Some(level);
+        self
+    }
+
+    /// makes random passes reproducible, for tests and simulations; see
+    /// [`Shredder::with_rng_seed`]. left out, they are seeded by the operating
+    /// system
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    /// allows multi-pass legacy standards on SSDs and flash; see
+    /// [`Shredder::with_force_multipass`]
+    pub fn force_multipass(mut self) -> Self {
+        self.force_multipass = true;
+        self
+    }
+
+    /// adds requirements to the system policy, which is always enforced; see
+    /// [`Shredder::with_policy`]
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// checks the settings and creates the shredder
+    ///
+    /// # Returns
+    /// * `Result<Shredder, BuildError>` - The shredder, or the first setting
+    ///   that doesn't fit the others
+    pub fn build(self) -> Result<Shredder, BuildError> {
+        let storage = self.storage.ok_or(BuildError::MissingStorage)??;
+        let mut standard = self.standard;
+        if let Some(level) = self.verify {
+            match &mut standard {
+                WipeStandard::Modern(config) => config.verify_level = level,
+                WipeStandard::Legacy(config) => {
+                    config.extra_verification = level != VerificationLevel::None
+                }
+                WipeStandard::Custom(config) => {
+                    config.verify_each_pass = level != VerificationLevel::None
+                }
+                WipeStandard::Auto(config) => config.verify_level = level,
+            }
+        }
+        let standard = standard.resolve(&storage);
+        let policy = Policy::system().merge(self.policy.clone());
+        if let Err(e) = policy.check_standard(&standard) {
+            return Err(BuildError::Policy(e.to_string()));
+        }
+
+        match &standard {
+            WipeStandard::Custom(config) if config.passes.is_empty() => {
+                return Err(BuildError::NoPasses);
+            }
+            WipeStandard::Modern(config) => {
+                if let VerificationLevel::Sampled(share) = config.verify_level {
+                    if !(share > 0.0 && share <= 1.0) {
+                        return Err(BuildError::InvalidSample(share));
+                    }
+                }
+                if matches!(config.method, SanitizationMethod::Purge)
+                    && storage.requires_wear_leveling_handling()
+                    && !storage.supports_hardware_erase()
+                {
+                    return Err(BuildError::PurgeWithoutHardwareErase(media(&storage)));
+                }
+            }
+            WipeStandard::Legacy(_)
+                if standard.is_multipass_legacy()
+                    && storage.requires_wear_leveling_handling()
+                    && !self.force_multipass =>
+            {
+                return Err(BuildError::MultipassOnWearLeveledStorage {
+                    standard: standard.to_string(),
+                    passes: standard.passes().len(),
+                    media: media(&storage),
+                });
+            }
+            _ => {}
+        }
+
+        let shredder = Shredder::new(standard, storage)
+            .with_force_multipass(self.force_multipass)
+            .with_policy(self.policy);
+        let shredder = match self.block_size {
+            Some(size) => shredder.with_block_size(size),
+            None => shredder,
+        };
+        Ok(match self.rng_seed {
+            Some(seed) => shredder.with_rng_seed(seed),
+            None => shredder,
+        })
+    }
+}
+
+/// the kind of storage, for error messages
+pub(crate) fn media(storage: &StorageType) -> &'static str {
+    match storage {
+        StorageType::Hdd(_) => "hard disks",
+        StorageType::Ssd(_) => "SSDs",
+        StorageType::Flash(_) => "flash media",
+    }
+}
@@ -0,0 +1,363 @@
+//! checkpoints of device wipes, so a wipe cut short by a power loss or crash
+//! picks up close to where it stopped instead of starting over
+//!
+//! while a device is overwritten, the pass and the first logical block it has
+//! not written yet are saved every `INTERVAL`, after syncing the writes before
+//! it, so the saved block is one the media holds. the file is named after the
+//! drive's serial number, since device names change across reboots, and is
+//! removed once the wipe succeeds. a later wipe of the same drive with the same
+//! standard counts the passes before the saved one as done and starts that one
+//! at the saved block with the same random keystream, so it verifies as a whole
+//!
+//! a checkpointed pass is written front to back on one thread, so that one
+//! block marks how far it got. the wipe is registered here by the path it
+//! opens for as long as it runs, which the lock module keeps to one wipe per
+//! target at a time
+
+use crate::report::DeviceIdentity;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// how often a checkpoint is saved while a pass runs
+const INTERVAL: Duration = Duration::from_secs(30);
+
+/// device wipes being checkpointed by this process
+static ACTIVE: Mutex<Vec<Active>> = Mutex::new(Vec::new());
+
+/// how far a device wipe got, as saved in its checkpoint file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    /// device node the wipe ran on
+    pub(crate) device: PathBuf,
+    /// serial number of the drive, if known
+    pub(crate) serial: Option<String>,
+    /// capacity of the drive in bytes, if known
+    pub(crate) capacity: Option<u64>,
+    /// standard the wipe follows, as displayed
+    pub(crate) standard: String,
+    /// passes the standard performs
+    pub(crate) passes: usize,
+    /// pass in progress, starting at 1; the ones before it are complete
+    pub(crate) pass: usize,
+    /// first logical block of the pass not yet written
+    pub(crate) lba: u64,
+    /// logical block size in bytes
+    pub(crate) sector_size: u64,
+    /// keystream seed of a random pass as hex, so it goes on with the same data
+    pub(crate) seed: Option<String>,
+}
+
+impl Checkpoint {
+    /// whether `saved` was written by a wipe of the same drive with the same standard
+    fn continues(&self, saved: &Checkpoint) -> bool {
+        let same_drive = match (&self.serial, &saved.serial) {
+            (Some(serial), Some(saved_serial)) => serial == saved_serial,
+            (None, None) => self.device == saved.device,
+            _ => false,
+        };
+        same_drive
+            && self.capacity == saved.capacity
+            && self.standard == saved.standard
+            && self.passes == saved.passes
+            && self.sector_size == saved.sector_size
+            && (1..=self.passes).contains(&saved.pass)
+    }
+}
+
+struct Active {
+    /// path the wipe opens
+    device: PathBuf,
+    /// checkpoint file
+    file: PathBuf,
+    /// the latest checkpoint, starting from the one resumed if any
+    current: Checkpoint,
+    /// where the wipe picked up, if it resumed an interrupted one
+    resumed: Option<Checkpoint>,
+    saved: Instant,
+    /// saving failed before, which was reported then
+    failed: bool,
+}
+
+fn active() -> std::sync::MutexGuard<'static, Vec<Active>> {
+    ACTIVE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn with_active<T>(device: &Path, f: impl FnOnce(&mut Active) -> T) -> Option<T> {
+    active()
+        .iter_mut()
+        .find(|active| active.device == device)
+        .map(f)
+}
+
+/// checkpoints a device wipe while it runs; dropped without `complete`, e.g.
+/// when the wipe fails, the last checkpoint stays for the next attempt
+pub(crate) struct CheckpointGuard {
+    device: PathBuf,
+}
+
+impl CheckpointGuard {
+    /// registers the wipe of `device`, resuming from a checkpoint in `dir`
+    /// left by an interrupted wipe of the same drive with the same standard
+    ///
+    /// # Arguments
+    /// * `dir` - Directory of checkpoint files
+    /// * `device` - Device as the wipe opens it
+    /// * `identity` - Drive the device belongs to, if known
+    /// * `standard` - Standard the wipe follows, as displayed
+    /// * `passes` - Passes the standard performs
+    /// * `sector_size` - Logical block size of the device
+    pub(crate) fn begin(
+        dir: &Path,
+        device: &Path,
+        identity: Option<&DeviceIdentity>,
+        standard: &str,
+        passes: usize,
+        sector_size: u64,
+    ) -> Self {
+        let serial = identity.and_then(|identity| identity.serial.clone());
+        let file = dir.join(file_name(device, serial.as_deref()));
+        let current = Checkpoint {
+            device: device.to_path_buf(),
+            serial,
+            capacity: identity.and_then(|identity| identity.capacity),
+            standard: standard.to_string(),
+            passes,
+            pass: 1,
+            lba: 0,
+            sector_size,
+            seed: None,
+        };
+
+        let resumed = match load(&file) {
+            Ok(Some(saved)) if current.continues(&saved) => Some(saved),
+            Ok(Some(_)) => {
+                log::info!("Ignoring the checkpoint {} of another wipe", file.display());
+                None
+            }
+            Ok(None) => None,
+            Err(e) => {
+                log::warn!("Unable to read the checkpoint {}: {}", file.display(), e);
+                None
+            }
+        };
+
+        let mut registered = active();
+        registered.retain(|active| active.device != device);
+        registered.push(Active {
+            device: device.to_path_buf(),
+            file,
+            current: resumed.clone().unwrap_or(current),
+            resumed,
+            saved: Instant::now(),
+            failed: false,
+        });
+        Self {
+            device: device.to_path_buf(),
+        }
+    }
+
+    /// the checkpoint the wipe resumes from, if it picked up an interrupted one
+    pub(crate) fn resumed(&self) -> Option<Checkpoint> {
+        with_active(&self.device, |active| active.resumed.clone()).flatten()
+    }
+
+    /// the wipe succeeded: its checkpoint file is removed
+    pub(crate) fn complete(self) {
+        let file = with_active(&self.device, |active| active.file.clone());
+        if let Some(file) = file {
+            match std::fs::remove_file(&file) {
+                Ok(()) => log::debug!("Removed the checkpoint {}", file.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("Unable to remove the checkpoint {}: {}", file.display(), e),
+            }
+        }
+    }
+}
+
+impl Drop for CheckpointGuard {
+    fn drop(&mut self) {
+        active().retain(|active| active.device != self.device);
+    }
+}
+
+/// whether the wipe of `device` is checkpointed
+pub(crate) fn is_active(device: &Path) -> bool {
+    with_active(device, |_| ()).is_some()
+}
+
+/// whether `pass` was completed by the interrupted wipe this one resumes
+pub(crate) fn skipped(device: &Path, pass: usize) -> bool {
+    with_active(device, |active| {
+        active
+            .resumed
+            .as_ref()
+            .is_some_and(|saved| pass < saved.pass)
+    })
+    .unwrap_or(false)
+}
+
+/// byte offset `pass` resumes at, 0 if it starts from the beginning
+pub(crate) fn resume_offset(device: &Path, pass: usize) -> u64 {
+    with_active(device, |active| match &active.resumed {
+        Some(saved) if saved.pass == pass => saved.lba * saved.sector_size,
+        _ => 0,
+    })
+    .unwrap_or(0)
+}
+
+/// keystream seed the interrupted `pass` was written with, if it was random
+pub(crate) fn resume_seed(device: &Path, pass: usize) -> Option<[u8; 32]> {
+    with_active(device, |active| match &active.resumed {
+        Some(saved) if saved.pass == pass => saved.seed.as_deref().and_then(decode_seed),
+        _ => None,
+    })
+    .flatten()
+}
+
+/// whether it is time to save another checkpoint of the wipe of `device`
+pub(crate) fn due(device: &Path) -> bool {
+    with_active(device, |active| active.saved.elapsed() >= INTERVAL).unwrap_or(false)
+}
+
+/// saves that `pass` of the wipe of `device` has written the bytes before
+/// `offset`, which must be on the media already
+///
+/// failing to save is reported once and doesn't stop the wipe
+pub(crate) fn save(device: &Path, pass: usize, offset: u64, seed: Option<[u8; 32]>) {
+    with_active(device, |active| {
+        active.current.pass = pass;
+        active.current.lba = offset / active.current.sector_size;
+        active.current.seed = seed.map(|seed| encode_seed(&seed));
+        active.saved = Instant::now();
+        match store(&active.file, &active.current) {
+            Ok(()) => log::debug!(
+                "Checkpoint: pass {} written up to block {}",
+                pass,
+                active.current.lba
+            ),
+            Err(e) if !active.failed => {
+                active.failed = true;
+                log::warn!(
+                    "Unable to save the checkpoint {}: {}; an interrupted wipe will start over",
+                    active.file.display(),
+                    e
+                );
+            }
+            Err(_) => {}
+        }
+    });
+}
+
+/// checkpoint file of a drive: its serial number, or the device's path
+fn file_name(device: &Path, serial: Option<&str>) -> String {
+    let name = serial.map_or_else(|| device.to_string_lossy().into_owned(), str::to_string);
+    let name: String = name
+        .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}.toml", name)
+}
+
+fn load(file: &Path) -> std::io::Result<Option<Checkpoint>> {
+    match std::fs::read_to_string(file) {
+        Ok(text) => toml::from_str(&text)
+            .map(Some)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// writes the checkpoint next to the file and renames it into place, synced,
+/// so a power loss leaves either the previous checkpoint or this one
+fn store(file: &Path, checkpoint: &Checkpoint) -> std::io::Result<()> {
+    let text = toml::to_string(checkpoint)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp_path = file.with_extension("toml.tmp");
+    let mut temp = std::fs::File::create(&temp_path)?;
+    std::io::Write::write_all(&mut temp, text.as_bytes())?;
+    temp.sync_all()?;
+    std::fs::rename(&temp_path, file)
+}
+
+fn encode_seed(seed: &[u8; 32]) -> String {
+    seed.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_seed(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut seed = [0u8; 32];
+    for (i, byte) in seed.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let device = Path::new("/dev/checkpoint-test");
+        let drive = DeviceIdentity {
+            serial: Some("WD-1234 5678".into()),
+            capacity: Some(1 << 40),
+            ..Default::default()
+        };
+        let begin = || CheckpointGuard::begin(dir.path(), device, Some(&drive), "dod", 3, 512);
+
+        // the first run gets interrupted in its second pass
+        let first = begin();
+        assert!(first.resumed().is_none() && is_active(device));
+        save(device, 2, 1 << 20, Some([7; 32]));
+        drop(first);
+        assert!(!is_active(device));
+        assert!(dir.path().join("WD_1234_5678.toml").exists());
+
+        let second = begin();
+        let resumed = second.resumed().unwrap();
+        assert_eq!((resumed.pass, resumed.lba), (2, 2048));
+        assert!(skipped(device, 1) && !skipped(device, 2));
+        assert_eq!(resume_offset(device, 2), 1 << 20);
+        assert_eq!(resume_offset(device, 3), 0);
+        assert_eq!(resume_seed(device, 2), Some([7; 32]));
+        second.complete();
+        assert!(!dir.path().join("WD_1234_5678.toml").exists());
+
+        // another standard, or another drive, starts over
+        let first = begin();
+        save(device, 3, 4096, None);
+        drop(first);
+        let other = CheckpointGuard::begin(dir.path(), device, Some(&drive), "gutmann", 35, 512);
+        assert!(other.resumed().is_none());
+        assert_eq!(resume_offset(device, 3), 0);
+        drop(other);
+        let swapped = DeviceIdentity {
+            capacity: Some(1 << 30),
+            ..drive.clone()
+        };
+        assert!(
+            CheckpointGuard::begin(dir.path(), device, Some(&swapped), "dod", 3, 512)
+                .resumed()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_seed_encoding() {
+        let seed: [u8; 32] = std::array::from_fn(|i| i as u8 * 8);
+        assert_eq!(decode_seed(&encode_seed(&seed)), Some(seed));
+        assert_eq!(decode_seed("00"), None);
+        assert_eq!(file_name(Path::new("/dev/sdb"), None), "dev_sdb.toml");
+    }
+}
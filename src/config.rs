@@ -0,0 +1,273 @@
+use crate::{Result, WipeError};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// settings read from the configuration file
+///
+/// every field is optional so a config file only needs the keys it changes
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// paths that must never be wiped, in addition to the built-in denylist.
+    /// anything inside one of these directories is refused as well
+    pub protected_paths: Vec<PathBuf>,
+
+    /// recurring wipes run by `shred daemon`
+    pub jobs: Vec<JobConfig>,
+
+    /// bearer token required by the HTTP API (`shred daemon --listen`)
+    pub api_token: Option<String>,
+
+    /// file where the daemon keeps the API jobs not finished yet, so they
+    /// survive a restart; jobs.toml in the state directory when not set
+    pub job_queue: Option<PathBuf>,
+
+    /// directory of the checkpoints interrupted device wipes resume from;
+    /// checkpoints/ in the state directory when not set
+    pub checkpoint_dir: Option<PathBuf>,
+
+    /// where to send the outcome of wipes and jobs
+    pub notify: NotifyConfig,
+
+    /// write buffer size in bytes, as measured by `shred bench`; without it
+    /// the built-in default is used
+    pub buffer_size: Option<usize>,
+
+    /// record the start, finish and failure of every wipe in syslog or the
+    /// windows event log, as if --audit-log was passed
+    pub audit_log: bool,
+
+    /// drive models whose hardware erase or TRIM must be skipped, as
+    /// `[[quirks]]` tables, in addition to the built-in ones
+    pub quirks: Vec<crate::quirks::Quirk>,
+
+    /// seconds `shred trash-put` leaves items in the trash before shredding
+    /// them, as if --grace was passed; 0 shreds them right away
+    pub trash_grace_secs: u64,
+}
+
+/// a recurring wipe, configured as a `[[jobs]]` table
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobConfig {
+    /// name used in logs and reports
+    pub name: String,
+
+    /// cron expression, e.g. "0 3 * * *" for every night at 3:00
+    pub schedule: String,
+
+    /// file to wipe, or directory whose contents are wiped
+    pub path: PathBuf,
+
+    /// wiping standard for this job, overriding the command line
+    pub standard: Option<String>,
+
+    /// verification level for this job, overriding the command line
+    pub verify: Option<String>,
+
+    /// file that gets one line appended per run
+    pub report: Option<PathBuf>,
+}
+
+/// notification targets, configured as a `[notify]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// URL that receives a JSON POST when a wipe or job finishes or fails
+    pub webhook: Option<String>,
+
+    /// mail sent when a wipe or job finishes or fails
+    pub email: Option<EmailConfig>,
+
+    /// show a desktop notification when an interactive wipe finishes or fails
+    pub desktop: bool,
+
+    /// only wipes that took at least this many seconds get a desktop notification
+    pub desktop_after_secs: u64,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            webhook: None,
+            email: None,
+            desktop: true,
+            desktop_after_secs: 60,
+        }
+    }
+}
+
+/// an SMTP relay and the addresses to mail, configured as `[notify.email]`
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    /// relay as host:port, e.g. "localhost:25" or "smtp.example.com:587"
+    pub smtp: String,
+
+    /// sender address
+    pub from: String,
+
+    /// recipient addresses
+    pub to: Vec<String>,
+
+    /// whether the connection is upgraded with STARTTLS
+    #[serde(default)]
+    pub starttls: StartTls,
+
+    /// user to log in to the relay as; needs STARTTLS
+    pub username: Option<String>,
+
+    /// password for `username`
+    pub password: Option<String>,
+}
+
+/// when to upgrade the connection to the relay with STARTTLS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartTls {
+    /// whenever the relay offers it, otherwise the mail goes out in plain text
+    #[default]
+    Opportunistic,
+
+    /// always; the mail isn't sent if the relay doesn't offer it
+    Required,
+
+    /// never, e.g. for a local relay without a certificate
+    Never,
+}
+
+impl Config {
+    /// reads and parses a TOML configuration file
+    ///
+    /// # Arguments
+    /// * `path` - Path to the configuration file
+    ///
+    /// # Returns
+    /// * `Result<Config>` - Parsed configuration
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents)
+            .map_err(|e| WipeError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// sets `buffer_size` in a configuration file, keeping the rest of it as it is
+    ///
+    /// # Arguments
+    /// * `path` - Configuration file, created along with its directory if missing
+    /// * `size` - Buffer size in bytes
+    pub fn save_buffer_size(path: &Path, size: usize) -> Result<()> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let updated = with_buffer_size(&contents, size);
+        // refuse to write back something that no longer parses
+        toml::from_str::<Config>(&updated)
+            .map_err(|e| WipeError::Config(format!("{}: {}", path.display(), e)))?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, updated)?;
+        Ok(())
+    }
+
+    /// loads the configuration from the default location, if there is one
+    ///
+    /// # Returns
+    /// * `Result<Config>` - Parsed configuration, or the defaults when no file exists
+    pub fn load_default() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// location of the per-user configuration file
+    ///
+    /// $XDG_CONFIG_HOME/shredder/config.toml (falling back to ~/.config) on
+    /// unix and %APPDATA%\shredder\config.toml on windows
+    pub fn default_path() -> Option<PathBuf> {
+        #[cfg(windows)]
+        let base = std::env::var_os("APPDATA").map(PathBuf::from);
+        #[cfg(not(windows))]
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+        base.map(|base| base.join("shredder").join("config.toml"))
+    }
+
+    /// directory for what has to outlive a run, such as the daemon's job
+    /// queue and device wipe checkpoints
+    ///
+    /// /var/lib/shredder on unix and %ProgramData%\shredder on windows
+    pub fn state_dir() -> PathBuf {
+        #[cfg(windows)]
+        let base = std::env::var_os("ProgramData")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"));
+        #[cfg(not(windows))]
+        let base = PathBuf::from("/var/lib");
+
+        base.join("shredder")
+    }
+}
+
+/// replaces the top-level buffer_size key, or adds it before the first table
+fn with_buffer_size(contents: &str, size: usize) -> String {
+    let line = format!("buffer_size = {}", size);
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let first_table = lines
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let existing = lines[..first_table].iter().position(|l| {
+        l.trim_start()
+            .strip_prefix("buffer_size")
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    });
+
+    match existing {
+        Some(index) => lines[index] = line,
+        None => {
+            // keep the blank line that separates it from the table
+            let mut index = first_table;
+            while index > 0 && lines[index - 1].trim().is_empty() {
+                index -= 1;
+            }
+            lines.insert(index, line);
+        }
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_buffer_size() {
+        let config =
+            "# settings\nprotected_paths = [\"/srv\"]\n\n[notify]\nwebhook = \"http://hook\"\n";
+        let updated = with_buffer_size(config, 4194304);
+        assert_eq!(
+            updated,
+            "# settings\nprotected_paths = [\"/srv\"]\nbuffer_size = 4194304\n\n[notify]\nwebhook = \"http://hook\"\n"
+        );
+        let parsed: Config = toml::from_str(&with_buffer_size(&updated, 65536)).unwrap();
+        assert_eq!(parsed.buffer_size, Some(65536));
+        assert_eq!(with_buffer_size("", 1024), "buffer_size = 1024\n");
+    }
+
+    #[test]
+    fn test_quirks() {
+        let parsed: Config = toml::from_str(
+            "[[quirks]]\nmodel = \"ACME SSD*\"\nfrozen_security = true\nnote = \"always frozen\"\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.quirks.len(), 1);
+        assert!(parsed.quirks[0].frozen_security && !parsed.quirks[0].broken_trim);
+    }
+}
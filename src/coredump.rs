@@ -0,0 +1,147 @@
+//! no core dumps while a wipe runs, since a crash would write the buffers
+//! holding patterns and the target's contents to disk
+//!
+//! the first wipe to start saves the process's settings and the last to end
+//! restores them, so concurrent wipes (the API, `wipe_many` callers on several
+//! threads) don't restore them under each other
+
+use std::sync::Mutex;
+
+/// how many wipes are running, and the settings from before the first
+static ACTIVE: Mutex<(usize, Option<Saved>)> = Mutex::new((0, None));
+
+/// keeps core dumps off until dropped
+pub(crate) struct CoreDumpGuard(());
+
+impl CoreDumpGuard {
+    pub(crate) fn new() -> Self {
+        let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+        if active.0 == 0 {
+            active.1 = disable();
+        }
+        active.0 += 1;
+        Self(())
+    }
+}
+
+impl Drop for CoreDumpGuard {
+    fn drop(&mut self) {
+        let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+        active.0 -= 1;
+        if active.0 == 0 {
+            if let Some(saved) = active.1.take() {
+                restore(saved);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+struct Saved {
+    limit: libc::rlimit,
+    #[cfg(target_os = "linux")]
+    dumpable: libc::c_int,
+}
+
+#[cfg(windows)]
+struct Saved {
+    mode: u32,
+}
+
+/// sets the core size limit to zero; on linux the process is also marked
+/// undumpable, since a core_pattern piping to a handler (systemd-coredump,
+/// apport) ignores the limit
+#[cfg(unix)]
+fn disable() -> Option<Saved> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) } != 0 {
+        log::warn!(
+            "Unable to read the core dump limit: {}",
+            std::io::Error::last_os_error()
+        );
+        return None;
+    }
+    let off = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: limit.rlim_max,
+    };
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &off) } != 0 {
+        log::warn!(
+            "Unable to turn core dumps off: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    let dumpable = unsafe {
+        let dumpable = libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0);
+        libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0);
+        dumpable
+    };
+    log::debug!("Core dumps off for the wipe");
+    Some(Saved {
+        limit,
+        #[cfg(target_os = "linux")]
+        dumpable,
+    })
+}
+
+#[cfg(unix)]
+fn restore(saved: Saved) {
+    unsafe {
+        libc::setrlimit(libc::RLIMIT_CORE, &saved.limit);
+        #[cfg(target_os = "linux")]
+        if saved.dumpable >= 0 {
+            libc::prctl(libc::PR_SET_DUMPABLE, saved.dumpable, 0, 0, 0);
+        }
+    }
+}
+
+/// keeps windows error reporting from offering to debug or dump a crash
+#[cfg(windows)]
+fn disable() -> Option<Saved> {
+    use winapi::um::errhandlingapi::{GetErrorMode, SetErrorMode};
+    use winapi::um::winbase::SEM_NOGPFAULTERRORBOX;
+
+    let mode = unsafe { GetErrorMode() };
+    unsafe { SetErrorMode(mode | SEM_NOGPFAULTERRORBOX) };
+    Some(Saved { mode })
+}
+
+#[cfg(windows)]
+fn restore(saved: Saved) {
+    unsafe { winapi::um::errhandlingapi::SetErrorMode(saved.mode) };
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    fn core_limit() -> libc::rlim_t {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) };
+        limit.rlim_cur
+    }
+
+    // other tests may be wiping at the same time, so only what holds while
+    // a guard is alive is checked
+    #[test]
+    fn test_guard() {
+        let running = || ACTIVE.lock().unwrap().0;
+        let outer = CoreDumpGuard::new();
+        let inner = CoreDumpGuard::new();
+        assert_eq!(core_limit(), 0);
+        assert_eq!(unsafe { libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) }, 0);
+        drop(outer);
+        // still off while another wipe runs
+        assert!(running() >= 1);
+        assert_eq!(core_limit(), 0);
+        drop(inner);
+    }
+}
@@ -0,0 +1,804 @@
+//! org.shredder.Manager on the session bus, for file managers and desktop
+//! applets that want to start wipes without running the command line tool
+//!
+//! the service speaks just enough of the D-Bus wire protocol for that: EXTERNAL
+//! authentication over a unix socket, method calls in and replies and signals
+//! out. wipes are queued and run one at a time, as the user the service runs
+//! as; the bus only lets that user's own processes call it. interface, at
+//! /org/shredder/Manager:
+//!   WipeFile(s path) -> t job          wipe a file or device
+//!   WipeDirectory(s path) -> t job     wipe a directory and everything in it
+//!   signal Progress(t job, s path, u pass, u passes, t written, t total)
+//!   signal Finished(t job, b success, u wiped, u failed, s error)
+//!
+//! `Progress` covers the file being wiped, its bytes summed over the passes,
+//! and is sent at most every `PROGRESS_INTERVAL` per job
+
+use crate::progress::{Progress, ProgressCallback};
+use crate::report::MultiWipeReport;
+use crate::{Result, WipeError};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// well-known name the service owns on the bus
+pub const BUS_NAME: &str = "org.shredder.Manager";
+
+/// object the interface is served on
+pub const OBJECT_PATH: &str = "/org/shredder/Manager";
+
+/// the service's interface
+pub const INTERFACE: &str = "org.shredder.Manager";
+
+/// how often a job's progress is signalled at most
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// largest message accepted from the bus; calls to the service are tiny
+const MAX_MESSAGE: usize = 1 << 20;
+
+const METHOD_CALL: u8 = 1;
+const METHOD_RETURN: u8 = 2;
+const ERROR: u8 = 3;
+const SIGNAL: u8 = 4;
+
+/// flag of calls whose caller doesn't want a reply
+const NO_REPLY_EXPECTED: u8 = 0x1;
+
+const INTROSPECTION: &str =
+    "<!DOCTYPE node PUBLIC \"-//freedesktop//DTD D-BUS Object Introspection 1.0//EN\"\n \
+     \"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd\">\n\
+     <node>\n  \
+     <interface name=\"org.shredder.Manager\">\n    \
+     <method name=\"WipeFile\">\n      \
+     <arg name=\"path\" type=\"s\" direction=\"in\"/>\n      \
+     <arg name=\"job\" type=\"t\" direction=\"out\"/>\n    \
+     </method>\n    \
+     <method name=\"WipeDirectory\">\n      \
+     <arg name=\"path\" type=\"s\" direction=\"in\"/>\n      \
+     <arg name=\"job\" type=\"t\" direction=\"out\"/>\n    \
+     </method>\n    \
+     <signal name=\"Progress\">\n      \
+     <arg name=\"job\" type=\"t\"/>\n      \
+     <arg name=\"path\" type=\"s\"/>\n      \
+     <arg name=\"pass\" type=\"u\"/>\n      \
+     <arg name=\"passes\" type=\"u\"/>\n      \
+     <arg name=\"written\" type=\"t\"/>\n      \
+     <arg name=\"total\" type=\"t\"/>\n    \
+     </signal>\n    \
+     <signal name=\"Finished\">\n      \
+     <arg name=\"job\" type=\"t\"/>\n      \
+     <arg name=\"success\" type=\"b\"/>\n      \
+     <arg name=\"wiped\" type=\"u\"/>\n      \
+     <arg name=\"failed\" type=\"u\"/>\n      \
+     <arg name=\"error\" type=\"s\"/>\n    \
+     </signal>\n  \
+     </interface>\n  \
+     <interface name=\"org.freedesktop.DBus.Introspectable\">\n    \
+     <method name=\"Introspect\">\n      \
+     <arg name=\"xml\" type=\"s\" direction=\"out\"/>\n    \
+     </method>\n  \
+     </interface>\n  \
+     <interface name=\"org.freedesktop.DBus.Peer\">\n    \
+     <method name=\"Ping\"/>\n  \
+     </interface>\n\
+     </node>\n";
+
+/// the service, connected to a bus and owning `BUS_NAME` on it
+pub struct DbusService {
+    bus: Arc<Bus>,
+    reader: UnixStream,
+}
+
+/// the write side of the connection, shared with the thread running the wipes
+struct Bus {
+    writer: Mutex<UnixStream>,
+    serial: AtomicU32,
+}
+
+impl Bus {
+    fn send(&self, message: &Message) -> Result<u32> {
+        let serial = self.serial.fetch_add(1, Ordering::Relaxed);
+        let bytes = message.encode(serial);
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.write_all(&bytes)?;
+        Ok(serial)
+    }
+
+    fn signal(&self, member: &str, body: Body) {
+        let mut message = Message::new(SIGNAL, body);
+        message.path = Some(OBJECT_PATH.to_string());
+        message.interface = Some(INTERFACE.to_string());
+        message.member = Some(member.to_string());
+        if let Err(e) = self.send(&message) {
+            log::warn!("Unable to send the D-Bus {} signal: {}", member, e);
+        }
+    }
+}
+
+impl DbusService {
+    /// connects to the session bus of the user and takes `BUS_NAME`
+    ///
+    /// # Returns
+    /// * `Result<DbusService>` - Service ready to `run`, or an error if there
+    ///   is no session bus or another service has the name
+    pub fn connect_session() -> Result<Self> {
+        let address = std::env::var("DBUS_SESSION_BUS_ADDRESS").map_err(|_| {
+            WipeError::Dbus("DBUS_SESSION_BUS_ADDRESS is not set; no session bus".to_string())
+        })?;
+        Self::connect(&address)
+    }
+
+    /// connects to the bus at `address` and takes `BUS_NAME`
+    ///
+    /// # Arguments
+    /// * `address` - D-Bus address, e.g. "unix:path=/run/user/1000/bus"
+    ///
+    /// # Returns
+    /// * `Result<DbusService>` - Service ready to `run`
+    pub fn connect(address: &str) -> Result<Self> {
+        let mut stream = open(address)?;
+        authenticate(&mut stream)?;
+
+        let service = DbusService {
+            bus: Arc::new(Bus {
+                writer: Mutex::new(stream.try_clone()?),
+                serial: AtomicU32::new(1),
+            }),
+            reader: stream,
+        };
+        service.call_bus("Hello", Body::new())?;
+        let reply = service.call_bus(
+            "RequestName",
+            Body::new().string(BUS_NAME).u32(0x4), // DO_NOT_QUEUE
+        )?;
+        match reply.reader().u32()? {
+            1 | 4 => Ok(service),
+            _ => Err(WipeError::Dbus(format!(
+                "{} is already taken on the bus; is another shred dbus running?",
+                BUS_NAME
+            ))),
+        }
+    }
+
+    /// calls a method of the bus itself and waits for its reply
+    fn call_bus(&self, member: &str, body: Body) -> Result<Message> {
+        let mut call = Message::new(METHOD_CALL, body);
+        call.path = Some("/org/freedesktop/DBus".to_string());
+        call.interface = Some("org.freedesktop.DBus".to_string());
+        call.member = Some(member.to_string());
+        call.destination = Some("org.freedesktop.DBus".to_string());
+        let serial = self.bus.send(&call)?;
+
+        let mut reader = &self.reader;
+        loop {
+            let message = Message::read(&mut reader)?;
+            if message.reply_serial != Some(serial) {
+                continue;
+            }
+            if message.kind == ERROR {
+                return Err(WipeError::Dbus(format!(
+                    "{} failed: {}",
+                    member,
+                    message.error_name.unwrap_or_default()
+                )));
+            }
+            return Ok(message);
+        }
+    }
+
+    /// serves calls until the connection to the bus is lost
+    ///
+    /// # Arguments
+    /// * `runner` - Wipes a file or directory, reporting progress to the
+    ///   callback, e.g. with `Shredder::with_progress` and `wipe_many`
+    pub fn run<F>(self, runner: F) -> Result<()>
+    where
+        F: Fn(&Path, ProgressCallback) -> MultiWipeReport + Send + 'static,
+    {
+        let (queue, queued) = mpsc::channel::<(u64, PathBuf)>();
+        let bus = Arc::clone(&self.bus);
+        std::thread::spawn(move || {
+            for (job, path) in queued {
+                log::info!("D-Bus job {}: wiping {}", job, path.display());
+                let outcome = runner(&path, progress_signals(&bus, job));
+                let errors = outcome.error_messages();
+                for error in &errors {
+                    log::error!("D-Bus job {} failed to wipe {}", job, error);
+                }
+                bus.signal(
+                    "Finished",
+                    Body::new()
+                        .u64(job)
+                        .boolean(errors.is_empty())
+                        .u32(outcome.wiped.len() as u32)
+                        .u32(outcome.failed.len() as u32)
+                        .string(errors.first().map_or("", String::as_str)),
+                );
+            }
+        });
+
+        let next_job = AtomicU64::new(1);
+        let mut reader = &self.reader;
+        loop {
+            let call = Message::read(&mut reader)?;
+            if call.kind != METHOD_CALL {
+                continue;
+            }
+            let reply = match self.handle(&call, &next_job) {
+                Ok((body, Some(queued))) => {
+                    let _ = queue.send(queued);
+                    call.reply(body)
+                }
+                Ok((body, None)) => call.reply(body),
+                Err((name, text)) => call.error(name, text),
+            };
+            if call.flags & NO_REPLY_EXPECTED == 0 {
+                self.bus.send(&reply)?;
+            }
+        }
+    }
+
+    /// answers one method call, and hands back the job it queues, if any
+    #[allow(clippy::type_complexity)]
+    fn handle(
+        &self,
+        call: &Message,
+        next_job: &AtomicU64,
+    ) -> std::result::Result<(Body, Option<(u64, PathBuf)>), (&'static str, String)> {
+        let path = call.path.as_deref().unwrap_or("");
+        let member = call.member.as_deref().unwrap_or("");
+        match (call.interface.as_deref(), member) {
+            (Some("org.freedesktop.DBus.Introspectable") | None, "Introspect") => {
+                Ok((Body::new().string(&introspect(path)), None))
+            }
+            (Some("org.freedesktop.DBus.Peer") | None, "Ping") => Ok((Body::new(), None)),
+            (Some(INTERFACE) | None, "WipeFile" | "WipeDirectory") if path == OBJECT_PATH => {
+                let target = match (call.signature.as_str(), call.reader().string()) {
+                    ("s", Ok(target)) => PathBuf::from(target),
+                    _ => {
+                        return Err((
+                            "org.freedesktop.DBus.Error.InvalidArgs",
+                            format!("{} takes one string, the path", member),
+                        ))
+                    }
+                };
+                check_target(&target, member == "WipeDirectory")
+                    .map_err(|e| ("org.shredder.Manager.Error.InvalidPath", e))?;
+                let job = next_job.fetch_add(1, Ordering::Relaxed);
+                Ok((Body::new().u64(job), Some((job, target))))
+            }
+            _ => Err((
+                "org.freedesktop.DBus.Error.UnknownMethod",
+                format!("No method {} on {}", member, path),
+            )),
+        }
+    }
+}
+
+/// D-Bus activation file that lets the bus start the service when it is
+/// first called, installed in $XDG_DATA_HOME/dbus-1/services
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+///
+/// # Returns
+/// * `String` - Contents of org.shredder.Manager.service
+pub fn activation_file(program: &Path) -> String {
+    format!(
+        "[D-BUS Service]\nName={}\nExec={} dbus\n",
+        BUS_NAME,
+        crate::remote::shell_quote(&program.to_string_lossy())
+    )
+}
+
+/// why a target can't be wiped by the method called, if it can't
+fn check_target(target: &Path, directory: bool) -> std::result::Result<(), String> {
+    if !target.is_absolute() {
+        return Err(format!("{} is not an absolute path", target.display()));
+    }
+    let metadata =
+        std::fs::symlink_metadata(target).map_err(|e| format!("{}: {}", target.display(), e))?;
+    match (directory, metadata.is_dir()) {
+        (true, false) => Err(format!("{} is not a directory", target.display())),
+        (false, true) => Err(format!(
+            "{} is a directory; use WipeDirectory",
+            target.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// introspection data of `path`: the interface on the service's object, and
+/// the way down to it on the objects above
+fn introspect(path: &str) -> String {
+    if path == OBJECT_PATH {
+        return INTROSPECTION.to_string();
+    }
+    let prefix = if path == "/" {
+        "/".to_string()
+    } else {
+        format!("{}/", path)
+    };
+    let child = OBJECT_PATH
+        .strip_prefix(&prefix)
+        .and_then(|rest| rest.split('/').next())
+        .map(|name| format!("  <node name=\"{}\"/>\n", name))
+        .unwrap_or_default();
+    format!("<node>\n{}</node>\n", child)
+}
+
+/// progress callback that signals a job's progress, at most every `PROGRESS_INTERVAL`
+fn progress_signals(bus: &Arc<Bus>, job: u64) -> ProgressCallback {
+    let bus = Arc::clone(bus);
+    let last: Mutex<Option<(Instant, PathBuf, usize)>> = Mutex::new(None);
+    Box::new(move |progress: &Progress| {
+        let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+        // a new file or pass is always signalled, and so is the end of one
+        let due = match &*last {
+            Some((sent, path, pass)) => {
+                path != progress.path
+                    || *pass != progress.pass
+                    || progress.bytes_written == progress.bytes_per_pass
+                    || sent.elapsed() >= PROGRESS_INTERVAL
+            }
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        *last = Some((Instant::now(), progress.path.to_path_buf(), progress.pass));
+        bus.signal(
+            "Progress",
+            Body::new()
+                .u64(job)
+                .string(&progress.path.to_string_lossy())
+                .u32(progress.pass as u32)
+                .u32(progress.passes as u32)
+                .u64(progress.total_written())
+                .u64(progress.total_bytes()),
+        );
+    })
+}
+
+/// connects to the first address of a D-Bus address list that works
+fn open(addresses: &str) -> Result<UnixStream> {
+    let mut failure = None;
+    for address in addresses.split(';').filter(|a| !a.is_empty()) {
+        let Some(("unix", params)) = address.split_once(':') else {
+            failure = Some(format!("{}: only unix sockets are supported", address));
+            continue;
+        };
+        for (key, value) in params.split(',').filter_map(|p| p.split_once('=')) {
+            let value = unescape(value);
+            let stream = match key {
+                "path" => UnixStream::connect(&value),
+                #[cfg(target_os = "linux")]
+                "abstract" => {
+                    use std::os::linux::net::SocketAddrExt;
+                    std::os::unix::net::SocketAddr::from_abstract_name(value.as_bytes())
+                        .and_then(|addr| UnixStream::connect_addr(&addr))
+                }
+                _ => continue,
+            };
+            match stream {
+                Ok(stream) => return Ok(stream),
+                Err(e) => failure = Some(format!("{}: {}", address, e)),
+            }
+        }
+    }
+    Err(WipeError::Dbus(failure.unwrap_or_else(|| {
+        format!("No usable address in '{}'", addresses)
+    })))
+}
+
+/// decodes the %XX escapes of an address value
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = value
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// EXTERNAL authentication: the bus checks the uid of the socket's peer
+fn authenticate(stream: &mut UnixStream) -> Result<()> {
+    let uid = unsafe { libc::getuid() }.to_string();
+    let hex: String = uid.bytes().map(|b| format!("{:02x}", b)).collect();
+    stream.write_all(format!("\0AUTH EXTERNAL {}\r\n", hex).as_bytes())?;
+
+    // byte by byte, so nothing after the line is consumed
+    let mut line = Vec::new();
+    let mut byte = [0u8];
+    while !line.ends_with(b"\r\n") {
+        if stream.read(&mut byte)? == 0 || line.len() > 512 {
+            return Err(WipeError::Dbus("Bus closed the connection".to_string()));
+        }
+        line.push(byte[0]);
+    }
+    if !line.starts_with(b"OK ") {
+        return Err(WipeError::Dbus(format!(
+            "Bus refused authentication: {}",
+            String::from_utf8_lossy(&line).trim()
+        )));
+    }
+    stream.write_all(b"BEGIN\r\n")?;
+    Ok(())
+}
+
+/// the arguments of a message, marshalled little-endian, with their signature
+#[derive(Debug, Default)]
+struct Body {
+    data: Vec<u8>,
+    signature: String,
+}
+
+impl Body {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn align(&mut self, n: usize) {
+        while !self.data.len().is_multiple_of(n) {
+            self.data.push(0);
+        }
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+        self.signature.push('u');
+        self.put_u32(value);
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Self {
+        self.signature.push('t');
+        self.align(8);
+        self.data.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn boolean(mut self, value: bool) -> Self {
+        self.signature.push('b');
+        self.put_u32(value as u32);
+        self
+    }
+
+    fn string(mut self, value: &str) -> Self {
+        self.signature.push('s');
+        self.put_string(value);
+        self
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.align(4);
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_string(&mut self, value: &str) {
+        self.put_u32(value.len() as u32);
+        self.data.extend_from_slice(value.as_bytes());
+        self.data.push(0);
+    }
+
+    fn put_signature(&mut self, value: &str) {
+        self.data.push(value.len() as u8);
+        self.data.extend_from_slice(value.as_bytes());
+        self.data.push(0);
+    }
+}
+
+/// reads marshalled values, in the byte order the message was sent in
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| WipeError::Dbus("Truncated message".to_string()))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn align(&mut self, n: usize) -> Result<()> {
+        let padding = (n - self.pos % n) % n;
+        self.take(padding).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.align(4)?;
+        let bytes: [u8; 4] = self.take(4)?.try_into().unwrap();
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len + 1)?;
+        String::from_utf8(bytes[..len].to_vec())
+            .map_err(|_| WipeError::Dbus("String is not UTF-8".to_string()))
+    }
+
+    fn signature(&mut self) -> Result<String> {
+        let len = self.u8()? as usize;
+        let bytes = self.take(len + 1)?;
+        Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+    }
+}
+
+/// a message and the header fields the service uses
+#[derive(Debug, Default)]
+struct Message {
+    kind: u8,
+    flags: u8,
+    serial: u32,
+    path: Option<String>,
+    interface: Option<String>,
+    member: Option<String>,
+    error_name: Option<String>,
+    reply_serial: Option<u32>,
+    destination: Option<String>,
+    sender: Option<String>,
+    signature: String,
+    body: Vec<u8>,
+    big_endian: bool,
+}
+
+impl Message {
+    fn new(kind: u8, body: Body) -> Self {
+        Self {
+            kind,
+            signature: body.signature,
+            body: body.data,
+            ..Default::default()
+        }
+    }
+
+    /// successful reply to this call
+    fn reply(&self, body: Body) -> Message {
+        let mut reply = Message::new(METHOD_RETURN, body);
+        reply.reply_serial = Some(self.serial);
+        reply.destination = self.sender.clone();
+        reply
+    }
+
+    /// error reply to this call
+    fn error(&self, name: &str, text: String) -> Message {
+        let mut reply = Message::new(ERROR, Body::new().string(&text));
+        reply.error_name = Some(name.to_string());
+        reply.reply_serial = Some(self.serial);
+        reply.destination = self.sender.clone();
+        reply
+    }
+
+    /// reader over the arguments
+    fn reader(&self) -> Reader<'_> {
+        Reader {
+            data: &self.body,
+            pos: 0,
+            big_endian: self.big_endian,
+        }
+    }
+
+    /// the message on the wire, little-endian
+    fn encode(&self, serial: u32) -> Vec<u8> {
+        let mut header = Body::new();
+        header
+            .data
+            .extend_from_slice(&[b'l', self.kind, self.flags, 1]);
+        header.put_u32(self.body.len() as u32);
+        header.put_u32(serial);
+
+        let mut fields = Body::new();
+        let strings = [
+            (1, 'o', &self.path),
+            (2, 's', &self.interface),
+            (3, 's', &self.member),
+            (4, 's', &self.error_name),
+            (6, 's', &self.destination),
+            (7, 's', &self.sender),
+        ];
+        for (code, kind, value) in strings {
+            if let Some(value) = value {
+                fields.align(8);
+                fields.data.push(code);
+                fields.put_signature(&kind.to_string());
+                fields.put_string(value);
+            }
+        }
+        if let Some(reply_serial) = self.reply_serial {
+            fields.align(8);
+            fields.data.push(5);
+            fields.put_signature("u");
+            fields.put_u32(reply_serial);
+        }
+        if !self.signature.is_empty() {
+            fields.align(8);
+            fields.data.push(8);
+            fields.put_signature("g");
+            fields.put_signature(&self.signature);
+        }
+
+        // the fields start 8-aligned at offset 16, so their padding carries over
+        header.put_u32(fields.data.len() as u32);
+        header.data.extend_from_slice(&fields.data);
+        header.align(8);
+        header.data.extend_from_slice(&self.body);
+        header.data
+    }
+
+    /// reads the next message from the bus
+    fn read<R: Read>(stream: &mut R) -> Result<Message> {
+        let mut fixed = [0u8; 16];
+        stream.read_exact(&mut fixed)?;
+        let big_endian = match fixed[0] {
+            b'l' => false,
+            b'B' => true,
+            _ => return Err(WipeError::Dbus("Unknown byte order".to_string())),
+        };
+        let number = |bytes: &[u8]| {
+            let bytes: [u8; 4] = bytes.try_into().unwrap();
+            (if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            }) as usize
+        };
+        let body_len = number(&fixed[4..8]);
+        let fields_len = number(&fixed[12..16]);
+        let header_len = (16 + fields_len).div_ceil(8) * 8;
+        if header_len + body_len > MAX_MESSAGE {
+            return Err(WipeError::Dbus("Message too large".to_string()));
+        }
+        let mut data = fixed.to_vec();
+        data.resize(header_len + body_len, 0);
+        stream.read_exact(&mut data[16..])?;
+        Self::decode(&data)
+    }
+
+    fn decode(data: &[u8]) -> Result<Message> {
+        let mut reader = Reader {
+            data,
+            pos: 0,
+            big_endian: data.first() == Some(&b'B'),
+        };
+        let mut message = Message {
+            big_endian: reader.big_endian,
+            ..Default::default()
+        };
+        reader.u8()?;
+        message.kind = reader.u8()?;
+        message.flags = reader.u8()?;
+        reader.u8()?;
+        let body_len = reader.u32()? as usize;
+        message.serial = reader.u32()?;
+
+        let fields_len = reader.u32()? as usize;
+        let end = reader.pos + fields_len;
+        while reader.pos < end {
+            reader.align(8)?;
+            let code = reader.u8()?;
+            let kind = reader.signature()?;
+            match (code, kind.as_str()) {
+                (5, "u") => message.reply_serial = Some(reader.u32()?),
+                (9, "u") => {
+                    reader.u32()?;
+                }
+                (8, "g") => message.signature = reader.signature()?,
+                (_, "s" | "o") => {
+                    let value = Some(reader.string()?);
+                    match code {
+                        1 => message.path = value,
+                        2 => message.interface = value,
+                        3 => message.member = value,
+                        4 => message.error_name = value,
+                        6 => message.destination = value,
+                        7 => message.sender = value,
+                        _ => {}
+                    }
+                }
+                _ => {
+                    return Err(WipeError::Dbus(format!(
+                        "Unexpected header field {} of type {}",
+                        code, kind
+                    )))
+                }
+            }
+        }
+        reader.align(8)?;
+        message.body = reader.take(body_len)?.to_vec();
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_round_trip() {
+        let mut call = Message::new(METHOD_CALL, Body::new().string("/home/ann/tax.pdf"));
+        call.path = Some(OBJECT_PATH.to_string());
+        call.interface = Some(INTERFACE.to_string());
+        call.member = Some("WipeFile".to_string());
+        call.sender = Some(":1.42".to_string());
+        let bytes = call.encode(7);
+        let decoded = Message::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded.serial, 7);
+        assert_eq!(decoded.member.as_deref(), Some("WipeFile"));
+        assert_eq!(decoded.path.as_deref(), Some(OBJECT_PATH));
+        assert_eq!(decoded.signature, "s");
+        assert_eq!(decoded.reader().string().unwrap(), "/home/ann/tax.pdf");
+
+        let reply = decoded.reply(Body::new().u64(3));
+        let reply = Message::decode(&reply.encode(8)).unwrap();
+        assert_eq!(
+            (reply.kind, reply.reply_serial, reply.destination.as_deref()),
+            (METHOD_RETURN, Some(7), Some(":1.42"))
+        );
+        assert_eq!(reply.signature, "t");
+        assert_eq!(reply.body, 3u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_big_endian_header() {
+        // a Ping from a big-endian peer: serial 2, PATH "/" and MEMBER "Ping"
+        let mut data = vec![b'B', METHOD_CALL, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 29];
+        data.extend_from_slice(&[1, 1, b'o', 0, 0, 0, 0, 1, b'/', 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[3, 1, b's', 0, 0, 0, 0, 4, b'P', b'i', b'n', b'g', 0]);
+        data.extend_from_slice(&[0, 0, 0]);
+        let message = Message::decode(&data).unwrap();
+        assert_eq!(message.serial, 2);
+        assert_eq!(message.path.as_deref(), Some("/"));
+        assert_eq!(message.member.as_deref(), Some("Ping"));
+    }
+
+    #[test]
+    fn test_introspect_and_addresses() {
+        assert!(introspect(OBJECT_PATH).contains("<method name=\"WipeDirectory\">"));
+        assert_eq!(introspect("/"), "<node>\n  <node name=\"org\"/>\n</node>\n");
+        assert_eq!(
+            introspect("/org/shredder"),
+            "<node>\n  <node name=\"Manager\"/>\n</node>\n"
+        );
+        assert_eq!(unescape("/run/user/1000/my%20bus"), "/run/user/1000/my bus");
+        assert!(matches!(
+            open("tcp:host=localhost,port=1"),
+            Err(WipeError::Dbus(_))
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check_target(dir.path(), true).is_ok());
+        assert!(check_target(dir.path(), false)
+            .unwrap_err()
+            .contains("use WipeDirectory"));
+        assert!(check_target(Path::new("relative"), false).is_err());
+    }
+}
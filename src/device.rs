@@ -0,0 +1,1174 @@
+use crate::Result;
+#[cfg(windows)]
+use crate::WipeError;
+use std::path::{Path, PathBuf};
+
+/// checks whether the path refers to a whole block device or partition
+/// rather than a regular file
+pub fn is_block_device(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        std::fs::metadata(path)
+            .map(|m| m.file_type().is_block_device() || m.file_type().is_char_device())
+            .unwrap_or(false)
+    }
+    #[cfg(windows)]
+    {
+        // raw devices are opened through the \\.\ namespace
+        path.to_str()
+            .map(|s| s.starts_with(r"\\.\"))
+            .unwrap_or(false)
+    }
+}
+
+/// size of an open block device in bytes
+///
+/// # Arguments
+/// * `device` - The device, opened for reading
+///
+/// # Returns
+/// * `Result<u64>` - Capacity of the device
+pub fn device_size(device: &mut std::fs::File) -> Result<u64> {
+    #[cfg(unix)]
+    {
+        use std::io::{Seek, SeekFrom};
+
+        // seeking to the end works for block devices on linux, macOS and BSD
+        let size = device.seek(SeekFrom::End(0))?;
+        device.seek(SeekFrom::Start(0))?;
+        Ok(size)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::io::AsRawHandle;
+        use winapi::um::ioapiset::DeviceIoControl;
+        use winapi::um::winioctl::{GET_LENGTH_INFORMATION, IOCTL_DISK_GET_LENGTH_INFO};
+
+        let mut length: GET_LENGTH_INFORMATION = unsafe { std::mem::zeroed() };
+        let mut bytes_returned = 0u32;
+        let success = unsafe {
+            DeviceIoControl(
+                device.as_raw_handle() as *mut _,
+                IOCTL_DISK_GET_LENGTH_INFO,
+                std::ptr::null_mut(),
+                0,
+                &mut length as *mut _ as *mut _,
+                std::mem::size_of::<GET_LENGTH_INFORMATION>() as u32,
+                &mut bytes_returned,
+                std::ptr::null_mut(),
+            )
+        };
+        if success == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(unsafe { *length.Length.QuadPart() } as u64)
+    }
+}
+
+/// sector sizes of a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectorSizes {
+    /// smallest unit the device can be addressed in
+    pub logical: usize,
+    /// unit the device writes internally; 4096 on 512e drives, whose logical
+    /// sectors are 512 bytes, so writing part of one makes it read, modify
+    /// and write back the whole
+    pub physical: usize,
+}
+
+/// asks an open device for its logical and physical sector sizes: BLKSSZGET
+/// and BLKPBSZGET on linux, DKIOCGETBLOCKSIZE and DKIOCGETPHYSICALBLOCKSIZE on
+/// macOS, the sector and stripe size on FreeBSD and the storage access
+/// alignment on windows
+///
+/// # Arguments
+/// * `device` - The device, opened for reading (on windows any access will do)
+///
+/// # Returns
+/// * `Result<SectorSizes>` - The sizes; the physical one is the logical one
+///   where the device doesn't report it
+pub fn sector_sizes(device: &std::fs::File) -> Result<SectorSizes> {
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        #[cfg(target_os = "linux")]
+        let (logical_request, physical_request) = (libc::BLKSSZGET, libc::BLKPBSZGET);
+        // _IOR('d', 24, uint32_t) and _IOR('d', 77, uint32_t)
+        #[cfg(target_os = "macos")]
+        let (logical_request, physical_request): (libc::c_ulong, libc::c_ulong) =
+            (0x4004_6418, 0x4004_644d);
+        // DIOCGSECTORSIZE, _IOR('d', 128, u_int), and DIOCGSTRIPESIZE,
+        // _IOR('d', 139, off_t), which is how GEOM reports 4K physical sectors
+        #[cfg(target_os = "freebsd")]
+        let (logical_request, physical_request): (libc::c_ulong, libc::c_ulong) =
+            (0x4004_6480, 0x4008_648b);
+
+        let fd = device.as_raw_fd();
+        let mut logical: libc::c_uint = 0;
+        if unsafe { libc::ioctl(fd, logical_request as _, &mut logical) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        #[cfg(target_os = "freebsd")]
+        let mut physical: libc::off_t = 0;
+        #[cfg(not(target_os = "freebsd"))]
+        let mut physical: libc::c_uint = 0;
+        if unsafe { libc::ioctl(fd, physical_request as _, &mut physical) } != 0 {
+            physical = 0;
+        }
+        Ok(sector_sizes_from(logical as usize, physical as usize))
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::io::AsRawHandle;
+        use winapi::um::ioapiset::DeviceIoControl;
+        use winapi::um::winioctl::{
+            PropertyStandardQuery, StorageAccessAlignmentProperty, IOCTL_STORAGE_QUERY_PROPERTY,
+            STORAGE_PROPERTY_QUERY,
+        };
+
+        /// STORAGE_ACCESS_ALIGNMENT_DESCRIPTOR
+        #[repr(C)]
+        #[derive(Default)]
+        struct AccessAlignment {
+            version: u32,
+            size: u32,
+            bytes_per_cache_line: u32,
+            bytes_offset_for_cache_alignment: u32,
+            bytes_per_logical_sector: u32,
+            bytes_per_physical_sector: u32,
+            bytes_offset_for_sector_alignment: u32,
+        }
+
+        let mut query: STORAGE_PROPERTY_QUERY = unsafe { std::mem::zeroed() };
+        query.PropertyId = StorageAccessAlignmentProperty;
+        query.QueryType = PropertyStandardQuery;
+        let mut alignment = AccessAlignment::default();
+        let mut bytes_returned = 0u32;
+        let success = unsafe {
+            DeviceIoControl(
+                device.as_raw_handle() as *mut _,
+                IOCTL_STORAGE_QUERY_PROPERTY,
+                &mut query as *mut _ as *mut _,
+                std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as u32,
+                &mut alignment as *mut _ as *mut _,
+                std::mem::size_of::<AccessAlignment>() as u32,
+                &mut bytes_returned,
+                std::ptr::null_mut(),
+            )
+        };
+        if success == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(sector_sizes_from(
+            alignment.bytes_per_logical_sector as usize,
+            alignment.bytes_per_physical_sector as usize,
+        ))
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "freebsd",
+        windows
+    )))]
+    {
+        let _ = device;
+        Err(std::io::Error::from(std::io::ErrorKind::Unsupported).into())
+    }
+}
+
+/// sector sizes from what a device reported; a physical size that isn't a
+/// whole number of logical sectors, or none, is taken to be the logical one
+pub(crate) fn sector_sizes_from(logical: usize, physical: usize) -> SectorSizes {
+    let logical = if logical.is_power_of_two() {
+        logical
+    } else {
+        512
+    };
+    let physical = if physical.is_power_of_two() && physical > logical {
+        physical
+    } else {
+        logical
+    };
+    SectorSizes { logical, physical }
+}
+
+/// lists the mount points of filesystems on the device or any of its partitions
+///
+/// # Arguments
+/// * `device` - Path to the block device (e.g., /dev/sdb)
+///
+/// # Returns
+/// * `Result<Vec<PathBuf>>` - Mount points in use; empty if nothing is mounted
+pub fn mounted_filesystems(device: &Path) -> Result<Vec<PathBuf>> {
+    #[cfg(target_os = "linux")]
+    {
+        mounted_filesystems_linux(device)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        mounted_filesystems_macos(device)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = device;
+        Err(crate::WipeError::UnsupportedOperation(
+            "Mount detection for devices not supported on this platform".into(),
+        ))
+    }
+}
+
+/// unmounts the given filesystems, innermost first
+///
+/// # Arguments
+/// * `device` - Device the filesystems belong to
+/// * `mount_points` - Mount points returned by [`mounted_filesystems`]
+pub fn unmount(device: &Path, mount_points: &[PathBuf]) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = device;
+        unmount_linux(mount_points)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let _ = mount_points;
+        unmount_macos(device)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = (device, mount_points);
+        Err(crate::WipeError::UnsupportedOperation(
+            "Unmounting not supported on this platform".into(),
+        ))
+    }
+}
+
+/// identifying details of a device, shown before destructive operations
+#[derive(Debug, Clone, Default)]
+pub struct DeviceInfo {
+    /// vendor and model as reported by the device
+    pub model: Option<String>,
+
+    /// serial number, used to confirm the operator picked the right disk
+    pub serial: Option<String>,
+
+    /// world wide name or other unique identifier (e.g., naa.5000c500a1b2c3d4)
+    pub wwn: Option<String>,
+
+    /// firmware revision
+    pub firmware: Option<String>,
+
+    /// capacity in bytes
+    pub size: Option<u64>,
+
+    /// partitions or volumes on the device (e.g., sdb1, disk2s1, E:)
+    pub partitions: Vec<String>,
+}
+
+/// reads model, serial number, size and partitions of a device
+///
+/// # Arguments
+/// * `device` - Path to the block device (e.g., /dev/sdb)
+///
+/// # Returns
+/// * `Result<DeviceInfo>` - Whatever the platform reports; missing fields are `None`
+pub fn device_info(device: &Path) -> Result<DeviceInfo> {
+    #[cfg(target_os = "linux")]
+    {
+        device_info_linux(device)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        device_info_macos(device)
+    }
+    #[cfg(windows)]
+    {
+        device_info_windows(device)
+    }
+    #[cfg(target_os = "freebsd")]
+    {
+        device_info_freebsd(device)
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "freebsd",
+        windows
+    )))]
+    {
+        let _ = device;
+        Ok(DeviceInfo::default())
+    }
+}
+
+/// the device a file or device target is stored on: the target itself for a
+/// device, otherwise the device its filesystem was mounted from
+///
+/// # Arguments
+/// * `path` - File or device
+///
+/// # Returns
+/// * `Option<PathBuf>` - Device node (on windows the volume, e.g. `\\.\C:`),
+///   or `None` where the filesystem isn't on a local device
+pub fn underlying_device(path: &Path) -> Option<PathBuf> {
+    if is_block_device(path) {
+        return Some(path.to_path_buf());
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        // /sys/dev/block/<major>:<minor> links to the partition or disk;
+        // the disk is the physical unit
+        let dev = std::fs::metadata(path).ok()?.dev();
+        // newer libc releases made major and minor safe
+        #[allow(unused_unsafe)]
+        let (major, minor) = unsafe { (libc::major(dev), libc::minor(dev)) };
+        let link = format!("/sys/dev/block/{}:{}", major, minor);
+        let sys_path = std::fs::canonicalize(link).ok()?;
+        let disk_path = if sys_path.join("partition").exists() {
+            sys_path.parent()?
+        } else {
+            &sys_path
+        };
+        Some(Path::new("/dev").join(disk_path.file_name()?))
+    }
+    #[cfg(windows)]
+    {
+        let mount = crate::filesystem::mount_info(path).ok()?;
+        let letter = mount.mount_point.to_string_lossy().chars().next()?;
+        Some(PathBuf::from(format!(r"\\.\{}:", letter)))
+    }
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        let source = crate::filesystem::mount_info(path).ok()?.source;
+        source.starts_with("/dev/").then(|| PathBuf::from(source))
+    }
+}
+
+/// identity of the device a target is stored on, for the wipe report
+///
+/// # Arguments
+/// * `path` - File or device that is wiped
+///
+/// # Returns
+/// * `Option<DeviceIdentity>` - `None` if the device isn't known
+pub fn identity(path: &Path) -> Option<crate::report::DeviceIdentity> {
+    let device = underlying_device(path)?;
+    let info = device_info(&device).unwrap_or_default();
+    Some(crate::report::DeviceIdentity {
+        device: Some(device),
+        model: info.model,
+        serial: info.serial,
+        wwn: info.wwn,
+        firmware: info.firmware,
+        capacity: info.size,
+    })
+}
+
+/// where a partition device sits on its disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionDevice {
+    /// the whole disk, e.g. /dev/sda for /dev/sda3
+    pub disk: PathBuf,
+    /// number of the partition in the disk's table
+    pub number: u32,
+    /// first byte on the disk, where the OS reports it
+    pub start: Option<u64>,
+}
+
+/// finds the disk a partition device is on
+///
+/// # Arguments
+/// * `device` - Path to the block device (e.g., /dev/sda3)
+///
+/// # Returns
+/// * `Option<PartitionDevice>` - `None` for whole disks, and for partitions
+///   on windows, which are not opened by partition
+pub fn partition_device(device: &Path) -> Option<PartitionDevice> {
+    #[cfg(target_os = "linux")]
+    {
+        partition_device_linux(device)
+    }
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    {
+        partition_device_by_name(device)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+    {
+        let _ = device;
+        None
+    }
+}
+
+/// checks whether the device holds an operating system
+///
+/// that is the disk the running system was booted from, or on linux any disk
+/// with an EFI system partition (an installed OS seen from a live system)
+///
+/// # Arguments
+/// * `device` - Path to the block device (e.g., /dev/sda)
+///
+/// # Returns
+/// * `Result<bool>` - true if wiping the device would destroy an OS installation
+pub fn is_system_disk(device: &Path) -> Result<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        is_system_disk_linux(device)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        is_system_disk_macos(device)
+    }
+    #[cfg(windows)]
+    {
+        is_system_disk_windows(device)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    {
+        let _ = device;
+        Ok(false)
+    }
+}
+
+/// linux: matches /proc/mounts sources against the device, its partitions and
+/// anything stacked on top of them (LVM, dm-crypt, md)
+#[cfg(target_os = "linux")]
+fn mounted_filesystems_linux(device: &Path) -> Result<Vec<PathBuf>> {
+    let related = related_block_devices(&kernel_name(device)?);
+    let mounts = std::fs::read_to_string("/proc/mounts")?;
+    Ok(mounts_on_devices(&mounts, &related))
+}
+
+/// resolves a device path (including /dev/disk/by-* links) to its kernel name
+#[cfg(target_os = "linux")]
+fn kernel_name(device: &Path) -> Result<String> {
+    let canonical = std::fs::canonicalize(device)?;
+    canonical
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid device path").into()
+        })
+}
+
+#[cfg(target_os = "linux")]
+fn device_info_linux(device: &Path) -> Result<DeviceInfo> {
+    let name = kernel_name(device)?;
+    let sys_path = std::fs::canonicalize(Path::new("/sys/class/block").join(&name))?;
+    // partitions share the identity of the disk they are on
+    let disk_path = if sys_path.join("partition").exists() {
+        sys_path.parent().unwrap_or(&sys_path).to_path_buf()
+    } else {
+        sys_path.clone()
+    };
+
+    let read = |path: PathBuf| {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let model = match (
+        read(disk_path.join("device/vendor")),
+        read(disk_path.join("device/model")),
+    ) {
+        (Some(vendor), Some(model)) => Some(format!("{} {}", vendor, model)),
+        (vendor, model) => model.or(vendor),
+    };
+    // NVMe and virtio expose the serial directly, SCSI/SATA through VPD page 0x80
+    let serial = read(disk_path.join("device/serial"))
+        .or_else(|| read(disk_path.join("serial")))
+        .or_else(|| {
+            std::fs::read(disk_path.join("device/vpd_pg80"))
+                .ok()
+                .and_then(|page| parse_vpd_serial(&page))
+        });
+    // NVMe namespaces have their own wwid, SCSI/SATA disks one on the device
+    let wwn = read(disk_path.join("wwid")).or_else(|| read(disk_path.join("device/wwid")));
+    let firmware =
+        read(disk_path.join("device/firmware_rev")).or_else(|| read(disk_path.join("device/rev")));
+    let size = read(sys_path.join("size"))
+        .and_then(|sectors| sectors.parse::<u64>().ok())
+        .map(|sectors| sectors * 512); // always in 512-byte units
+
+    Ok(DeviceInfo {
+        model,
+        serial,
+        wwn,
+        firmware,
+        size,
+        partitions: related_block_devices(&name).into_iter().skip(1).collect(),
+    })
+}
+
+/// extracts the unit serial number from a SCSI VPD page 0x80
+#[cfg(target_os = "linux")]
+fn parse_vpd_serial(page: &[u8]) -> Option<String> {
+    if page.len() < 4 || page[1] != 0x80 {
+        return None;
+    }
+    let len = page[3] as usize;
+    let serial = page.get(4..4 + len)?;
+    let serial = String::from_utf8_lossy(serial).trim().to_string();
+    (!serial.is_empty()).then_some(serial)
+}
+
+/// mount points that only exist on the disk the system runs from
+#[cfg(target_os = "linux")]
+const SYSTEM_MOUNT_POINTS: [&str; 4] = ["/", "/boot", "/boot/efi", "/usr"];
+
+#[cfg(target_os = "linux")]
+fn is_system_disk_linux(device: &Path) -> Result<bool> {
+    let related = related_block_devices(&kernel_name(device)?);
+
+    let mounts = std::fs::read_to_string("/proc/mounts")?;
+    let running_system = mounts_on_devices(&mounts, &related)
+        .iter()
+        .any(|mount_point| {
+            SYSTEM_MOUNT_POINTS
+                .iter()
+                .any(|p| mount_point == Path::new(p))
+        });
+    if running_system {
+        return Ok(true);
+    }
+
+    // an installed OS that isn't running, e.g. the internal disk seen from a live USB
+    Ok(related.iter().any(|name| {
+        let dev = std::fs::read_to_string(Path::new("/sys/class/block").join(name).join("dev"))
+            .unwrap_or_default();
+        std::fs::read_to_string(format!("/run/udev/data/b{}", dev.trim()))
+            .map(|data| is_efi_system_partition(&data))
+            .unwrap_or(false)
+    }))
+}
+
+/// checks a udev database entry for the EFI system partition type
+#[cfg(target_os = "linux")]
+fn is_efi_system_partition(udev_data: &str) -> bool {
+    const ESP_GUID: &str = "c12a7328-f81f-11d2-ba4b-00a0c93ec93b";
+
+    udev_data.lines().any(|line| {
+        line.strip_prefix("E:ID_PART_ENTRY_TYPE=")
+            // GPT type GUID, or MBR partition type 0xef
+            .is_some_and(|kind| kind.eq_ignore_ascii_case(ESP_GUID) || kind == "0xef")
+    })
+}
+
+/// linux: sysfs lists partitions under their disk, with their number and
+/// start in 512-byte sectors
+#[cfg(target_os = "linux")]
+fn partition_device_linux(device: &Path) -> Option<PartitionDevice> {
+    let name = kernel_name(device).ok()?;
+    let sys_path = std::fs::canonicalize(Path::new("/sys/class/block").join(name)).ok()?;
+    let read = |file: &str| -> Option<u64> {
+        std::fs::read_to_string(sys_path.join(file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    let number = read("partition")?;
+    Some(PartitionDevice {
+        disk: Path::new("/dev").join(sys_path.parent()?.file_name()?),
+        number: number as u32,
+        start: read("start").map(|sectors| sectors * 512),
+    })
+}
+
+/// macOS names partitions disk2s3 (rdisk2s3), FreeBSD ada0p3 on GPT and
+/// ada0s1 on MBR
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn partition_device_by_name(device: &Path) -> Option<PartitionDevice> {
+    let name = device.file_name()?.to_str()?;
+    let separators: &[char] = if cfg!(target_os = "macos") {
+        &['s']
+    } else {
+        &['p', 's']
+    };
+    let at = name.rfind(separators)?;
+    let (disk, number) = (&name[..at], &name[at + 1..]);
+    if !disk.ends_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(PartitionDevice {
+        disk: device.with_file_name(disk),
+        number: number.parse().ok()?,
+        start: None,
+    })
+}
+
+/// collects the kernel names of a device, its partitions and their holders
+#[cfg(target_os = "linux")]
+fn related_block_devices(name: &str) -> Vec<String> {
+    let mut names = vec![name.to_string()];
+    let mut i = 0;
+
+    while i < names.len() {
+        let sys_path = Path::new("/sys/class/block").join(&names[i]);
+        let mut children = Vec::new();
+
+        // partitions appear as subdirectories containing a "partition" file
+        if let Ok(entries) = std::fs::read_dir(&sys_path) {
+            for entry in entries.flatten() {
+                if entry.path().join("partition").exists() {
+                    children.push(entry.file_name().to_string_lossy().into_owned());
+                }
+            }
+        }
+        // device-mapper and md devices built on top of this one
+        if let Ok(entries) = std::fs::read_dir(sys_path.join("holders")) {
+            for entry in entries.flatten() {
+                children.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+
+        for child in children {
+            if !names.contains(&child) {
+                names.push(child);
+            }
+        }
+        i += 1;
+    }
+
+    names
+}
+
+/// returns mount points whose source device is one of `devices`
+#[cfg(target_os = "linux")]
+fn mounts_on_devices(mounts: &str, devices: &[String]) -> Vec<PathBuf> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?;
+            let mount_point = fields.next()?;
+            if !source.starts_with("/dev/") {
+                return None;
+            }
+            // resolve /dev/mapper/* and /dev/disk/by-* links to kernel names
+            let source = std::fs::canonicalize(source).unwrap_or_else(|_| PathBuf::from(source));
+            let source_name = source.file_name()?.to_str()?;
+            devices
+                .iter()
+                .any(|device| device == source_name)
+                .then(|| PathBuf::from(mount_point.replace("\\040", " ")))
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn unmount_linux(mount_points: &[PathBuf]) -> Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    // deepest mounts first so nested mounts don't keep parents busy
+    let mut sorted = mount_points.to_vec();
+    sorted.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+
+    for mount_point in sorted {
+        log::info!("Unmounting {}", mount_point.display());
+        let c_path = CString::new(mount_point.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        if unsafe { libc::umount2(c_path.as_ptr(), 0) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+
+    Ok(())
+}
+
+/// macOS: parses `mount` output for the disk and its slices (disk2s1, ...)
+#[cfg(target_os = "macos")]
+fn mounted_filesystems_macos(device: &Path) -> Result<Vec<PathBuf>> {
+    use std::process::Command;
+
+    let disk = macos_disk_name(device)?;
+    let output = Command::new("mount").output()?;
+    let listing = String::from_utf8_lossy(&output.stdout);
+
+    Ok(listing
+        .lines()
+        .filter_map(|line| {
+            let (source, rest) = line.split_once(" on ")?;
+            let source = source.trim_start_matches("/dev/");
+            let is_ours = macos_disk_matches(source, &disk);
+            // mount point ends where the "(type, flags)" suffix starts
+            let mount_point = rest.rsplit_once(" (").map(|(mp, _)| mp)?;
+            is_ours.then(|| PathBuf::from(mount_point))
+        })
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+fn unmount_macos(device: &Path) -> Result<()> {
+    use std::process::Command;
+
+    let disk = macos_disk_name(device)?;
+    log::info!("Unmounting all volumes on {}", disk);
+    let output = Command::new("diskutil")
+        .args(["unmountDisk", &format!("/dev/{}", disk)])
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(crate::WipeError::UnsupportedOperation(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn device_info_macos(device: &Path) -> Result<DeviceInfo> {
+    use std::process::Command;
+
+    let disk = macos_disk_name(device)?;
+    let output = Command::new("diskutil").args(["info", &disk]).output()?;
+    let info = String::from_utf8_lossy(&output.stdout);
+
+    let field = |key: &str| {
+        info.lines()
+            .find_map(|line| line.trim().strip_prefix(key))
+            .map(|value| value.trim_start_matches(':').trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    // "Disk Size: 500.1 GB (500107862016 Bytes) (exactly ...)"
+    let size = field("Disk Size").and_then(|value| {
+        let (_, bytes) = value.split_once('(')?;
+        bytes.split_whitespace().next()?.parse::<u64>().ok()
+    });
+
+    let output = Command::new("diskutil").args(["list", &disk]).output()?;
+    let partitions = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .filter(|identifier| *identifier != disk && macos_disk_matches(identifier, &disk))
+        .map(str::to_string)
+        .collect();
+
+    Ok(DeviceInfo {
+        model: field("Device / Media Name"),
+        // diskutil doesn't report serial numbers, WWNs or firmware
+        serial: None,
+        wwn: None,
+        firmware: None,
+        size,
+        partitions,
+    })
+}
+
+/// macOS: the boot volume's whole disk, or the physical store of its APFS container
+#[cfg(target_os = "macos")]
+fn is_system_disk_macos(device: &Path) -> Result<bool> {
+    use std::process::Command;
+
+    let disk = macos_disk_name(device)?;
+    let output = Command::new("diskutil").args(["info", "/"]).output()?;
+    let info = String::from_utf8_lossy(&output.stdout);
+
+    Ok(info.lines().any(|line| {
+        let line = line.trim();
+        ["Part of Whole:", "APFS Physical Store:"]
+            .iter()
+            .filter_map(|key| line.strip_prefix(key))
+            .any(|identifier| macos_disk_matches(identifier.trim(), &disk))
+    }))
+}
+
+/// whether `identifier` is `disk` or one of its slices (disk2s1 but not disk21)
+#[cfg(target_os = "macos")]
+fn macos_disk_matches(identifier: &str, disk: &str) -> bool {
+    identifier == disk
+        || identifier
+            .strip_prefix(disk)
+            .is_some_and(|suffix| suffix.starts_with('s'))
+}
+
+/// maps /dev/rdisk2 and /dev/disk2 to "disk2"
+#[cfg(target_os = "macos")]
+fn macos_disk_name(device: &Path) -> Result<String> {
+    let name = device
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid device path")
+        })?;
+    Ok(name.strip_prefix('r').unwrap_or(name).to_string())
+}
+
+/// volumes held locked and dismounted for the duration of a device wipe
+///
+/// windows releases the locks when the handles are closed, so this must be
+/// kept alive until the wipe has finished
+#[cfg(windows)]
+pub struct VolumeLock {
+    handles: Vec<winapi::um::winnt::HANDLE>,
+}
+
+#[cfg(windows)]
+impl Drop for VolumeLock {
+    fn drop(&mut self) {
+        use winapi::um::handleapi::CloseHandle;
+        use winapi::um::winioctl::FSCTL_UNLOCK_VOLUME;
+
+        for &handle in &self.handles {
+            let _ = volume_control(handle, FSCTL_UNLOCK_VOLUME);
+            unsafe { CloseHandle(handle) };
+        }
+    }
+}
+
+/// locks and dismounts every volume on the target device
+///
+/// # Arguments
+/// * `device` - A raw disk (\\.\PhysicalDrive1) or volume (\\.\E:)
+///
+/// # Returns
+/// * `Result<VolumeLock>` - Guard that keeps the volumes locked until dropped
+#[cfg(windows)]
+pub fn lock_volumes(device: &Path) -> Result<VolumeLock> {
+    use winapi::um::winioctl::{FSCTL_DISMOUNT_VOLUME, FSCTL_LOCK_VOLUME};
+
+    let volumes = match physical_drive_number(device) {
+        Some(disk_number) => volumes_on_disk(disk_number),
+        None => vec![device.to_string_lossy().trim_end_matches('\\').to_string()],
+    };
+
+    let mut lock = VolumeLock {
+        handles: Vec::new(),
+    };
+    for volume in volumes {
+        let handle = open_volume(&volume, true).map_err(|source| WipeError::VolumeLock {
+            volume: volume.clone(),
+            source,
+        })?;
+        // register first so the handle is closed even if locking fails
+        lock.handles.push(handle);
+
+        // the lock fails while other processes have files open on the volume;
+        // those usually close within a few seconds (indexers, antivirus)
+        let mut locked = volume_control(handle, FSCTL_LOCK_VOLUME);
+        for _ in 0..10 {
+            if locked.is_ok() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            locked = volume_control(handle, FSCTL_LOCK_VOLUME);
+        }
+        locked.map_err(|source| WipeError::VolumeLock {
+            volume: volume.clone(),
+            source,
+        })?;
+
+        volume_control(handle, FSCTL_DISMOUNT_VOLUME).map_err(|source| WipeError::VolumeLock {
+            volume: volume.clone(),
+            source,
+        })?;
+        log::info!("Locked and dismounted volume {}", volume);
+    }
+
+    Ok(lock)
+}
+
+/// finds the drive-letter volumes that have an extent on the given disk
+#[cfg(windows)]
+fn volumes_on_disk(disk_number: u32) -> Vec<String> {
+    (b'A'..=b'Z')
+        .map(|letter| format!(r"\\.\{}:", letter as char))
+        .filter(|volume| volume_disks(volume).contains(&disk_number))
+        .collect()
+}
+
+/// lists the numbers of the physical disks a volume has extents on
+#[cfg(windows)]
+fn volume_disks(volume: &str) -> Vec<u32> {
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winioctl::{
+        DISK_EXTENT, IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS, VOLUME_DISK_EXTENTS,
+    };
+
+    // room for a handful of extents (spanned/striped volumes)
+    #[repr(C)]
+    struct Extents {
+        header: VOLUME_DISK_EXTENTS,
+        more: [DISK_EXTENT; 15],
+    }
+
+    let handle = match open_volume(volume, false) {
+        Ok(handle) => handle,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut extents: Extents = unsafe { std::mem::zeroed() };
+    let mut bytes_returned = 0u32;
+    let success = unsafe {
+        DeviceIoControl(
+            handle,
+            IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS,
+            std::ptr::null_mut(),
+            0,
+            &mut extents as *mut _ as *mut _,
+            std::mem::size_of::<Extents>() as u32,
+            &mut bytes_returned,
+            std::ptr::null_mut(),
+        )
+    };
+    unsafe { CloseHandle(handle) };
+
+    if success == 0 {
+        return Vec::new();
+    }
+    let count = (extents.header.NumberOfDiskExtents as usize).min(16);
+    let first = extents.header.Extents.as_ptr();
+    (0..count)
+        .map(|i| unsafe { (*first.add(i)).DiskNumber })
+        .collect()
+}
+
+/// the disk number of a \\.\PhysicalDriveN path
+#[cfg(windows)]
+fn physical_drive_number(device: &Path) -> Option<u32> {
+    device
+        .to_str()?
+        .strip_prefix(r"\\.\PhysicalDrive")?
+        .parse()
+        .ok()
+}
+
+/// windows: the disk or volume holding %SystemDrive%
+#[cfg(windows)]
+fn is_system_disk_windows(device: &Path) -> Result<bool> {
+    let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".into());
+    let system_volume = format!(r"\\.\{}", system_drive);
+
+    Ok(match physical_drive_number(device) {
+        Some(disk_number) => volume_disks(&system_volume).contains(&disk_number),
+        None => device
+            .to_string_lossy()
+            .trim_end_matches('\\')
+            .eq_ignore_ascii_case(&system_volume),
+    })
+}
+
+/// FreeBSD: what GEOM reports about the disk a device node is on
+#[cfg(target_os = "freebsd")]
+fn device_info_freebsd(device: &Path) -> Result<DeviceInfo> {
+    use crate::storage::{geom_disk_name, GeomDisk};
+
+    let canonical = std::fs::canonicalize(device)?;
+    let provider = canonical.to_string_lossy();
+    let disk = geom_disk_name(&provider).ok_or_else(|| {
+        std::io::Error::other(format!("{} is not on a disk GEOM knows", provider))
+    })?;
+    let output = std::process::Command::new("geom")
+        .args(["disk", "list", disk])
+        .output()?;
+    let geom = GeomDisk::parse(&String::from_utf8_lossy(&output.stdout));
+
+    Ok(DeviceInfo {
+        model: geom.descr,
+        serial: geom.ident,
+        wwn: geom.lunid,
+        firmware: None,
+        size: geom.media_size,
+        partitions: Vec::new(),
+    })
+}
+
+#[cfg(windows)]
+fn device_info_windows(device: &Path) -> Result<DeviceInfo> {
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winioctl::{
+        PropertyStandardQuery, StorageDeviceProperty, GET_LENGTH_INFORMATION,
+        IOCTL_DISK_GET_LENGTH_INFO, IOCTL_STORAGE_QUERY_PROPERTY, STORAGE_PROPERTY_QUERY,
+    };
+
+    let handle = open_volume(&device.to_string_lossy(), false)?;
+
+    let mut query: STORAGE_PROPERTY_QUERY = unsafe { std::mem::zeroed() };
+    query.PropertyId = StorageDeviceProperty;
+    query.QueryType = PropertyStandardQuery;
+    // STORAGE_DEVICE_DESCRIPTOR followed by the strings it points into
+    let mut descriptor = vec![0u8; 1024];
+    let mut bytes_returned = 0u32;
+    let described = unsafe {
+        DeviceIoControl(
+            handle,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            &mut query as *mut _ as *mut _,
+            std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as u32,
+            descriptor.as_mut_ptr() as *mut _,
+            descriptor.len() as u32,
+            &mut bytes_returned,
+            std::ptr::null_mut(),
+        )
+    } != 0;
+
+    let mut length: GET_LENGTH_INFORMATION = unsafe { std::mem::zeroed() };
+    let sized = unsafe {
+        DeviceIoControl(
+            handle,
+            IOCTL_DISK_GET_LENGTH_INFO,
+            std::ptr::null_mut(),
+            0,
+            &mut length as *mut _ as *mut _,
+            std::mem::size_of::<GET_LENGTH_INFORMATION>() as u32,
+            &mut bytes_returned,
+            std::ptr::null_mut(),
+        )
+    } != 0;
+    unsafe { CloseHandle(handle) };
+
+    // offsets of the vendor, product and serial strings within the descriptor
+    let string_at = |field_offset: usize| {
+        if !described {
+            return None;
+        }
+        let bytes: [u8; 4] = descriptor
+            .get(field_offset..field_offset + 4)?
+            .try_into()
+            .ok()?;
+        let offset = u32::from_le_bytes(bytes) as usize;
+        let tail = descriptor.get(offset..).filter(|_| offset != 0)?;
+        let end = tail.iter().position(|&b| b == 0).unwrap_or(tail.len());
+        let value = String::from_utf8_lossy(&tail[..end]).trim().to_string();
+        (!value.is_empty()).then_some(value)
+    };
+    let model = match (string_at(12), string_at(16)) {
+        (Some(vendor), Some(product)) => Some(format!("{} {}", vendor, product)),
+        (vendor, product) => product.or(vendor),
+    };
+
+    Ok(DeviceInfo {
+        model,
+        serial: string_at(24),
+        wwn: None,
+        firmware: string_at(20),
+        size: sized.then(|| unsafe { *length.Length.QuadPart() } as u64),
+        partitions: physical_drive_number(device)
+            .map(volumes_on_disk)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|volume| volume.trim_start_matches(r"\\.\").to_string())
+            .collect(),
+    })
+}
+
+#[cfg(windows)]
+fn open_volume(volume: &str, write: bool) -> std::io::Result<winapi::um::winnt::HANDLE> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
+    use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+    use winapi::um::winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE};
+
+    let wide: Vec<u16> = std::ffi::OsStr::new(volume)
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let access = if write {
+        GENERIC_READ | GENERIC_WRITE
+    } else {
+        0
+    };
+
+    let handle = unsafe {
+        CreateFileW(
+            wide.as_ptr(),
+            access,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            std::ptr::null_mut(),
+            OPEN_EXISTING,
+            0,
+            std::ptr::null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(handle)
+    }
+}
+
+/// issues a control code that takes no input or output buffers
+#[cfg(windows)]
+fn volume_control(handle: winapi::um::winnt::HANDLE, code: u32) -> std::io::Result<()> {
+    use winapi::um::ioapiset::DeviceIoControl;
+
+    let mut bytes_returned = 0u32;
+    let success = unsafe {
+        DeviceIoControl(
+            handle,
+            code,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            0,
+            &mut bytes_returned,
+            std::ptr::null_mut(),
+        )
+    };
+    if success == 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mounts_on_devices() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+                      proc /proc proc rw 0 0\n\
+                      /dev/sdb1 /mnt/usb vfat rw 0 0\n\
+                      /dev/sdb2 /mnt/my\\040data ext4 rw 0 0\n";
+
+        let devices = vec!["sdb".to_string(), "sdb1".to_string(), "sdb2".to_string()];
+        assert_eq!(
+            mounts_on_devices(mounts, &devices),
+            vec![PathBuf::from("/mnt/usb"), PathBuf::from("/mnt/my data")]
+        );
+    }
+
+    #[test]
+    fn test_sector_sizes_from() {
+        // 512e, 4Kn and a device that doesn't report a physical size
+        let sizes = |logical, physical| SectorSizes { logical, physical };
+        assert_eq!(sector_sizes_from(512, 4096), sizes(512, 4096));
+        assert_eq!(sector_sizes_from(4096, 4096), sizes(4096, 4096));
+        assert_eq!(sector_sizes_from(512, 0), sizes(512, 512));
+        assert_eq!(sector_sizes_from(4096, 512), sizes(4096, 4096));
+        assert_eq!(sector_sizes_from(0, 3000), sizes(512, 512));
+    }
+
+    #[test]
+    fn test_parse_vpd_serial() {
+        let page = [
+            0x00, 0x80, 0x00, 0x0a, b' ', b' ', b'Z', b'A', b'4', b'1', b'2', b'3', b'4', b' ',
+        ];
+        assert_eq!(parse_vpd_serial(&page), Some("ZA41234".to_string()));
+        // wrong page code
+        assert_eq!(parse_vpd_serial(&[0x00, 0x83, 0x00, 0x01, b'x']), None);
+    }
+
+    #[test]
+    fn test_is_efi_system_partition() {
+        let esp = "S:disk/by-partuuid/1234\n\
+                   E:ID_PART_ENTRY_SCHEME=gpt\n\
+                   E:ID_PART_ENTRY_TYPE=c12a7328-f81f-11d2-ba4b-00a0c93ec93b\n";
+        let data = "E:ID_PART_ENTRY_SCHEME=gpt\n\
+                    E:ID_PART_ENTRY_TYPE=0fc63daf-8483-4772-8e79-3d69d8477de4\n";
+        assert!(is_efi_system_partition(esp));
+        assert!(!is_efi_system_partition(data));
+    }
+}
@@ -0,0 +1,175 @@
+//! running shred again with administrator rights when a command needs them,
+//! instead of asking the user to start over from an elevated prompt
+//!
+//! on windows the command is relaunched through UAC (ShellExecuteEx with the
+//! "runas" verb) with the same arguments and working directory, and the
+//! elevated run's exit code is passed on. it runs in a console window of its
+//! own, which closes when it ends; pass --report to keep its outcome
+
+use std::ffi::OsString;
+
+/// a command line for CreateProcess, quoted so that CommandLineToArgvW and the
+/// C runtime split it back into `args`
+///
+/// # Arguments
+/// * `args` - Arguments, without the program
+///
+/// # Returns
+/// * `String` - The arguments quoted and joined by spaces
+pub fn command_line(args: &[OsString]) -> String {
+    args.iter()
+        .map(|arg| quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\x0b', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // backslashes before a quote are escaped, and so is the quote
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            c => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // backslashes before the closing quote are escaped too
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// runs `program` with `args` as administrator after the UAC prompt, in the
+/// current directory, and waits for it
+///
+/// # Arguments
+/// * `program` - Executable to run, e.g. `std::env::current_exe()`
+/// * `args` - Its arguments
+///
+/// # Returns
+/// * `Result<i32>` - Its exit code, or a permission error if the prompt was declined
+#[cfg(windows)]
+pub fn run_elevated(program: &std::path::Path, args: &[OsString]) -> crate::Result<i32> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use std::{io, mem};
+    use winapi::shared::winerror::ERROR_CANCELLED;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::GetExitCodeProcess;
+    use winapi::um::shellapi::{
+        ShellExecuteExW, SEE_MASK_NOASYNC, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW,
+    };
+    use winapi::um::synchapi::WaitForSingleObject;
+    use winapi::um::winbase::INFINITE;
+    use winapi::um::winuser::SW_SHOWNORMAL;
+
+    let wide = |s: &OsStr| s.encode_wide().chain(Some(0)).collect::<Vec<u16>>();
+    let verb = wide(OsStr::new("runas"));
+    let file = wide(program.as_os_str());
+    let parameters = wide(OsStr::new(&command_line(args)));
+    let directory = std::env::current_dir()?;
+    let directory = wide(directory.as_os_str());
+
+    let mut info: SHELLEXECUTEINFOW = unsafe { mem::zeroed() };
+    info.cbSize = mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+    info.fMask = SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC;
+    info.lpVerb = verb.as_ptr();
+    info.lpFile = file.as_ptr();
+    info.lpParameters = parameters.as_ptr();
+    info.lpDirectory = directory.as_ptr();
+    info.nShow = SW_SHOWNORMAL;
+
+    if unsafe { ShellExecuteExW(&mut info) } == 0 {
+        let error = io::Error::last_os_error();
+        if error.raw_os_error() == Some(ERROR_CANCELLED as i32) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the administrator prompt was declined",
+            )
+            .into());
+        }
+        return Err(error.into());
+    }
+    if info.hProcess.is_null() {
+        return Err(io::Error::other("the elevated process could not be tracked").into());
+    }
+
+    let mut code = 0u32;
+    let got_code = unsafe {
+        WaitForSingleObject(info.hProcess, INFINITE);
+        let got = GetExitCodeProcess(info.hProcess, &mut code);
+        CloseHandle(info.hProcess);
+        got
+    };
+    if got_code == 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(code as i32)
+}
+
+/// whether shred runs with administrator rights, i.e. its token is elevated
+///
+/// # Returns
+/// * `bool` - True if the process token is elevated
+#[cfg(windows)]
+pub fn is_elevated() -> bool {
+    use std::{mem, ptr};
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::{GetCurrentProcess, OpenProcessToken};
+    use winapi::um::securitybaseapi::GetTokenInformation;
+    use winapi::um::winnt::{TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+
+    let mut token = ptr::null_mut();
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) } == 0 {
+        return false;
+    }
+    let mut elevation: TOKEN_ELEVATION = unsafe { mem::zeroed() };
+    let mut size = 0u32;
+    let got = unsafe {
+        let got = GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut _ as *mut _,
+            mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut size,
+        );
+        CloseHandle(token);
+        got
+    };
+    got != 0 && elevation.TokenIsElevated != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let args: Vec<OsString> = [
+            "--report",
+            r"C:\Users\me\My Reports\",
+            r#"say "hi""#,
+            "",
+            r"\\.\PhysicalDrive1",
+        ]
+        .iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(
+            command_line(&args),
+            r#"--report "C:\Users\me\My Reports\\" "say \"hi\"" "" \\.\PhysicalDrive1"#
+        );
+    }
+}
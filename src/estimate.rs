@@ -0,0 +1,170 @@
+use crate::device;
+use crate::standards::{AutoConfig, Nist80088Config, VerificationLevel, WipeStandard};
+use crate::storage::StorageType;
+use crate::Result;
+use std::path::Path;
+use std::time::Duration;
+
+/// what a wipe would take, worked out without touching the target
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    /// files that would be wiped (1 for a file or device)
+    pub files: u64,
+
+    /// size of the data, in bytes
+    pub bytes: u64,
+
+    /// overwrite passes of the standard
+    pub passes: usize,
+
+    /// bytes the overwrite passes write, i.e. size × passes
+    pub bytes_written: u64,
+
+    /// bytes read back to verify the passes
+    pub bytes_verified: u64,
+
+    /// whether the device is expected to erase itself instead of being overwritten;
+    /// the passes are still the fallback if that fails
+    pub hardware_erase: bool,
+
+    /// assumed sustained throughput, in bytes per second
+    pub throughput: u64,
+}
+
+impl Estimate {
+    /// time the overwrite passes and verification take at the assumed throughput
+    pub fn duration(&self) -> Duration {
+        let bytes = self.bytes_written + self.bytes_verified;
+        Duration::from_secs_f64(bytes as f64 / self.throughput.max(1) as f64)
+    }
+}
+
+/// estimates a wipe of a file, directory tree or device
+///
+/// # Arguments
+/// * `path` - Target, as it would be given to the shredder
+/// * `standard` - Standard the wipe would use
+/// * `storage_type` - Storage the target is on
+/// * `throughput` - Measured throughput in bytes per second, or None for a
+///   typical figure for the storage type
+///
+/// # Returns
+/// * `Result<Estimate>` - The estimate, or an IO error if the target can't be read
+pub fn estimate(
+    path: &Path,
+    standard: &WipeStandard,
+    storage_type: &StorageType,
+    throughput: Option<u64>,
+) -> Result<Estimate> {
+    let (files, bytes) = measure(path)?;
+    let standard = &standard.resolve(storage_type);
+    let passes = standard.passes().len();
+
+    Ok(Estimate {
+        files,
+        bytes,
+        passes,
+        bytes_written: bytes * passes as u64,
+        bytes_verified: verified_bytes(standard, bytes),
+        hardware_erase: standard.tries_hardware_erase() && storage_type.supports_secure_erase(),
+        throughput: throughput.unwrap_or_else(|| typical_throughput(storage_type)),
+    })
+}
+
+/// a conservative sustained write speed for the storage type, in bytes per second
+///
+/// # Arguments
+/// * `storage_type` - Detected storage type
+///
+/// # Returns
+/// * `u64` - 150 MB/s for hard disks, 400 MB/s for SSDs (1.5 GB/s for NVMe) and
+///   20 MB/s for USB sticks and memory cards
+pub fn typical_throughput(storage_type: &StorageType) -> u64 {
+    const MB: u64 = 1_000_000;
+    match storage_type {
+        StorageType::Hdd(_) => 150 * MB,
+        StorageType::Ssd(caps) if caps.supports_nvme_sanitize => 1500 * MB,
+        StorageType::Ssd(_) => 400 * MB,
+        StorageType::Flash(_) => 20 * MB,
+    }
+}
+
+/// counts the files and bytes a wipe of the path would overwrite
+fn measure(path: &Path) -> Result<(u64, u64)> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        let mut totals = (0, 0);
+        for entry in std::fs::read_dir(path)? {
+            let (files, bytes) = measure(&entry?.path())?;
+            totals = (totals.0 + files, totals.1 + bytes);
+        }
+        Ok(totals)
+    } else if device::is_block_device(path) {
+        let mut file = std::fs::File::open(path)?;
+        Ok((1, device::device_size(&mut file)?))
+    } else if metadata.is_file() {
+        Ok((1, metadata.len()))
+    } else {
+        // symlinks, sockets and the like aren't overwritten
+        Ok((0, 0))
+    }
+}
+
+/// bytes read back by the verification the standard performs
+fn verified_bytes(standard: &WipeStandard, bytes: u64) -> u64 {
+    match standard {
+        WipeStandard::Modern(Nist80088Config { verify_level, .. })
+        | WipeStandard::Auto(AutoConfig { verify_level }) => match verify_level {
+            VerificationLevel::None => 0,
+            // random samples of about 1%
+            VerificationLevel::Basic => bytes / 100,
+            VerificationLevel::Sampled(share) => (bytes as f64 * *share) as u64,
+            VerificationLevel::Full => bytes,
+            // two full reads
+            VerificationLevel::Enhanced => bytes * 2,
+            // every pass is read back
+            VerificationLevel::Hash => bytes * standard.passes().len() as u64,
+        },
+        // a sample after every pass and a full read at the end
+        WipeStandard::Legacy(config) if config.extra_verification => {
+            bytes / 100 * config.standard.get_patterns().len() as u64 + bytes
+        }
+        WipeStandard::Legacy(_) => 0,
+        WipeStandard::Custom(config) if config.verify_each_pass => {
+            bytes * config.passes.len() as u64
+        }
+        WipeStandard::Custom(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::standards::NamedStandard;
+    use crate::storage::StorageCapabilities;
+
+    #[test]
+    fn test_estimate_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), vec![0u8; 1000]).unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/b"), vec![0u8; 500]).unwrap();
+
+        let hdd = StorageType::Hdd(StorageCapabilities {
+            supports_trim: false,
+            supports_secure_erase: false,
+            supports_nvme_sanitize: false,
+            has_wear_leveling: false,
+        });
+        let dod = NamedStandard::find("dod").unwrap().standard();
+        let estimate = estimate(dir.path(), &dod, &hdd, Some(1500)).unwrap();
+
+        assert_eq!((estimate.files, estimate.bytes), (2, 1500));
+        assert_eq!(estimate.bytes_written, 4500);
+        assert!(!estimate.hardware_erase);
+        assert_eq!(
+            estimate.duration(),
+            Duration::from_secs_f64(6045.0 / 1500.0)
+        );
+    }
+}
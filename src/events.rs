@@ -0,0 +1,42 @@
+use crate::standards::VerificationLevel;
+use crate::{WipeError, WipeReport};
+
+/// what happens during a wipe started with
+/// [`Shredder::wipe_with_events`](crate::Shredder::wipe_with_events), in order.
+/// the last event is either `Completed` or `Error`
+#[derive(Debug)]
+pub enum WipeEvent {
+    /// an overwrite pass starts
+    PassStarted {
+        /// pass number, starting at 1
+        pass: usize,
+        /// number of passes the standard performs
+        passes: usize,
+    },
+
+    /// a chunk of the current pass was written
+    Progress {
+        /// current pass, starting at 1
+        pass: usize,
+        /// number of passes the standard performs
+        passes: usize,
+        /// bytes written so far in the current pass
+        bytes_written: u64,
+        /// size of the target, i.e. the bytes written by each pass
+        bytes_per_pass: u64,
+    },
+
+    /// the target is read back after a pass
+    VerifyStarted {
+        /// pass whose data is checked
+        pass: usize,
+        /// how much of the target is read
+        level: VerificationLevel,
+    },
+
+    /// the target was wiped
+    Completed(Box<WipeReport>),
+
+    /// the wipe failed
+    Error(WipeError),
+}
@@ -0,0 +1,189 @@
+use log::debug;
+use rand::Rng;
+use std::fs::File;
+use std::io;
+
+/// truncations done by `obfuscate_size`
+const SHRINK_STEPS: usize = 4;
+
+/// truncates a wiped file to a few random, ever smaller sizes, syncing each
+///
+/// the filesystem journal and the inode then record a series of sizes that
+/// have nothing to do with the file, and the last one recorded before the
+/// unlink isn't the original. it works like the size obfuscation of GNU shred
+///
+/// # Arguments
+/// * `file` - The wiped file, open for writing
+///
+/// # Returns
+/// * `io::Result<()>` - Error if a truncation or sync failed
+pub(crate) fn obfuscate_size(file: &File) -> io::Result<()> {
+    let mut rng = rand::thread_rng();
+    let mut len = file.metadata()?.len();
+    for _ in 0..SHRINK_STEPS {
+        if len == 0 {
+            break;
+        }
+        len = rng.gen_range(0..len);
+        file.set_len(len)?;
+        file.sync_all()?;
+        debug!("Truncated to {} bytes", len);
+    }
+    Ok(())
+}
+
+/// hands a wiped file's blocks back to the filesystem before it is unlinked
+///
+/// the blocks are deallocated (a hole is punched over the whole file where the
+/// filesystem supports it) and the file is truncated to zero, so neither its
+/// allocated size nor its length tells how large it was. punching holes also
+/// lets filesystems mounted with discard TRIM the blocks
+///
+/// # Arguments
+/// * `file` - The wiped file, open for writing
+///
+/// # Returns
+/// * `io::Result<()>` - Error if the file could not be truncated; a filesystem
+///   that can't punch holes is not an error
+pub(crate) fn release(file: &File) -> io::Result<()> {
+    let len = file.metadata()?.len();
+    if len > 0 {
+        match punch_hole(file, len) {
+            Ok(()) => debug!("Deallocated {} bytes", len),
+            Err(e) => debug!("Could not punch a hole over the file: {}", e),
+        }
+    }
+    file.set_len(0)?;
+    file.sync_all()
+}
+
+#[cfg(target_os = "linux")]
+fn punch_hole(file: &File, len: u64) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let result = unsafe {
+        libc::fallocate(
+            file.as_raw_fd(),
+            libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+            0,
+            len as libc::off_t,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn punch_hole(file: &File, len: u64) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let hole = libc::fpunchhole_t {
+        fp_flags: 0,
+        reserved: 0,
+        fp_offset: 0,
+        fp_length: len as libc::off_t,
+    };
+    let result = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PUNCHHOLE, &hole) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn punch_hole(file: &File, len: u64) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use std::ptr;
+    use winapi::shared::minwindef::DWORD;
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winioctl::{FSCTL_SET_SPARSE, FSCTL_SET_ZERO_DATA};
+
+    #[repr(C)]
+    struct FileZeroDataInformation {
+        file_offset: i64,
+        beyond_final_zero: i64,
+    }
+
+    let handle = file.as_raw_handle() as *mut _;
+    let mut bytes_returned: DWORD = 0;
+
+    // zeroing a range only deallocates it in a sparse file; elsewhere NTFS
+    // writes the zeros out
+    let success = unsafe {
+        DeviceIoControl(
+            handle,
+            FSCTL_SET_SPARSE,
+            ptr::null_mut(),
+            0,
+            ptr::null_mut(),
+            0,
+            &mut bytes_returned,
+            ptr::null_mut(),
+        )
+    };
+    if success == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut range = FileZeroDataInformation {
+        file_offset: 0,
+        beyond_final_zero: len as i64,
+    };
+    let success = unsafe {
+        DeviceIoControl(
+            handle,
+            FSCTL_SET_ZERO_DATA,
+            &mut range as *mut _ as *mut _,
+            std::mem::size_of::<FileZeroDataInformation>() as DWORD,
+            ptr::null_mut(),
+            0,
+            &mut bytes_returned,
+            ptr::null_mut(),
+        )
+    };
+    if success == 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn punch_hole(_file: &File, _len: u64) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "hole punching not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_obfuscate_size() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&vec![0x55; 1 << 20]).unwrap();
+
+        obfuscate_size(&file).unwrap();
+        assert!(file.metadata().unwrap().len() < 1 << 20);
+    }
+
+    #[test]
+    fn test_release() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&vec![0x55; 1 << 20]).unwrap();
+        file.sync_all().unwrap();
+
+        release(&file).unwrap();
+        let metadata = file.metadata().unwrap();
+        assert_eq!(metadata.len(), 0);
+        #[cfg(unix)]
+        assert_eq!(std::os::unix::fs::MetadataExt::blocks(&metadata), 0);
+    }
+}
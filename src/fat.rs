@@ -0,0 +1,167 @@
+//! overwriting the directory entries a deleted file leaves behind on FAT and
+//! exFAT (USB sticks, SD cards)
+//!
+//! deleting a file there only marks its directory entries free: FAT replaces
+//! the first byte of the short name with 0xE5, exFAT clears an in-use bit, and
+//! the long filename entries keep spelling out the name. the entries are
+//! overwritten the way the filesystem itself would overwrite them, without
+//! writing to the mounted volume underneath it: new entries go in the first
+//! free run of the directory, so placeholder files are created until the
+//! directory has to grow, which means every free entry was taken, and then
+//! removed again, leaving only their names behind
+//!
+//! a short numeric name takes one entry on FAT, so every free entry is reused.
+//! on exFAT every file takes at least three, so runs of one or two free
+//! entries are left as they are
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// FAT directories hold at most this many entries
+const MAX_ENTRIES: usize = 65536;
+
+/// whether `fs_type`, as reported by the mount table, is FAT or exFAT
+///
+/// # Arguments
+/// * `fs_type` - Filesystem type, e.g. "vfat", "msdos", "msdosfs", "FAT32", "exFAT"
+pub(crate) fn is_fat(fs_type: &str) -> bool {
+    let fs_type = fs_type.to_ascii_lowercase();
+    matches!(
+        fs_type.as_str(),
+        "vfat" | "msdos" | "msdosfs" | "fat" | "fat12" | "fat16" | "fat32" | "exfat"
+    )
+}
+
+/// whether `fs_type` is exFAT, where runs of fewer than three free entries can't be reused
+pub(crate) fn is_exfat(fs_type: &str) -> bool {
+    fs_type.eq_ignore_ascii_case("exfat")
+}
+
+/// fills the free entries of `dir` with placeholder files and removes them
+///
+/// # Arguments
+/// * `dir` - Directory a file was deleted from
+///
+/// # Returns
+/// * `io::Result<usize>` - How many placeholders were created
+pub(crate) fn scrub_deleted_entries(dir: &Path) -> io::Result<usize> {
+    let initial = directory_size(dir);
+    if initial.is_none() {
+        log::debug!(
+            "Size of {} unknown; filling up to {} entries",
+            dir.display(),
+            MAX_ENTRIES
+        );
+    }
+
+    let mut placeholders: Vec<PathBuf> = Vec::new();
+    let result = fill(dir, initial, &mut placeholders);
+    // removed even when filling stopped early, so none are left behind
+    for placeholder in placeholders.iter().rev() {
+        if let Err(e) = std::fs::remove_file(placeholder) {
+            log::warn!(
+                "Unable to remove placeholder {}: {}",
+                placeholder.display(),
+                e
+            );
+        }
+    }
+    result.map(|()| placeholders.len())
+}
+
+fn fill(dir: &Path, initial: Option<u64>, placeholders: &mut Vec<PathBuf>) -> io::Result<()> {
+    for i in 0..MAX_ENTRIES {
+        // eight digits are a valid 8.3 name, so no long filename entries are needed
+        let placeholder = dir.join(format!("{:08}", i));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&placeholder)
+        {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            // a FAT12/16 root directory is fixed in size, and full now
+            Err(_) if !placeholders.is_empty() => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        placeholders.push(placeholder);
+
+        if let Some(initial) = initial {
+            if directory_size(dir).is_some_and(|size| size > initial) {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// bytes allocated to the directory, which FAT grows a cluster at a time
+#[cfg(unix)]
+fn directory_size(dir: &Path) -> Option<u64> {
+    std::fs::metadata(dir)
+        .ok()
+        .map(|metadata| metadata.len())
+        .filter(|&size| size > 0)
+}
+
+/// directories report no length on windows, but do report their allocation
+#[cfg(windows)]
+fn directory_size(dir: &Path) -> Option<u64> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::fileapi::FILE_STANDARD_INFO;
+    use winapi::um::minwinbase::FileStandardInfo;
+    use winapi::um::winbase::{GetFileInformationByHandleEx, FILE_FLAG_BACKUP_SEMANTICS};
+
+    let directory = OpenOptions::new()
+        .read(true)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(dir)
+        .ok()?;
+    let mut info: FILE_STANDARD_INFO = unsafe { std::mem::zeroed() };
+    let ok = unsafe {
+        GetFileInformationByHandleEx(
+            directory.as_raw_handle() as _,
+            FileStandardInfo,
+            &mut info as *mut FILE_STANDARD_INFO as *mut _,
+            std::mem::size_of::<FILE_STANDARD_INFO>() as u32,
+        )
+    };
+    let size = unsafe { *info.AllocationSize.QuadPart() } as u64;
+    (ok != 0 && size > 0).then_some(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_fat() {
+        for fs_type in ["vfat", "msdos", "msdosfs", "FAT32", "exFAT", "exfat"] {
+            assert!(is_fat(fs_type), "{}", fs_type);
+        }
+        for fs_type in ["ext4", "ntfs", "apfs", "fuseblk"] {
+            assert!(!is_fat(fs_type), "{}", fs_type);
+        }
+        assert!(is_exfat("exFAT") && !is_exfat("vfat"));
+    }
+
+    #[test]
+    fn test_scrub_deleted_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("00000000"), b"kept").unwrap();
+        std::fs::write(dir.path().join("other"), b"kept").unwrap();
+
+        let created = scrub_deleted_entries(dir.path()).unwrap();
+        assert!(created >= 1);
+        // placeholders are gone, and existing files with their names untouched
+        let mut names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["00000000", "other"]);
+        assert_eq!(std::fs::read(dir.path().join("00000000")).unwrap(), b"kept");
+    }
+}
@@ -0,0 +1,628 @@
+use crate::Result;
+use std::path::{Path, PathBuf};
+
+/// information about the filesystem a target lives on
+#[derive(Debug, Clone)]
+pub struct MountInfo {
+    /// directory the filesystem is mounted at (e.g., / or C:\)
+    pub mount_point: PathBuf,
+
+    /// what is mounted there (device node, ZFS dataset name, or volume path)
+    pub source: String,
+
+    /// filesystem type as reported by the OS (e.g., ext4, apfs, NTFS)
+    pub fs_type: String,
+
+    /// whether the filesystem is mounted read-only
+    pub read_only: bool,
+}
+
+impl MountInfo {
+    /// whether the filesystem is copy-on-write
+    ///
+    /// on these filesystems an in-place overwrite is written to fresh extents,
+    /// leaving the original data blocks intact until they are reused
+    pub fn is_copy_on_write(&self) -> bool {
+        matches!(
+            self.fs_type.to_ascii_lowercase().as_str(),
+            "btrfs" | "zfs" | "apfs"
+        )
+    }
+}
+
+/// looks up the filesystem that contains the given path
+///
+/// # Arguments
+/// * `path` - Path to a file or device on the filesystem of interest
+///
+/// # Returns
+/// * `Result<MountInfo>` - Mount point, type and flags of the filesystem
+pub fn mount_info(path: &Path) -> Result<MountInfo> {
+    // platform-specific implementations
+    #[cfg(target_os = "linux")]
+    {
+        mount_info_linux(path)
+    }
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    {
+        mount_info_statfs(path)
+    }
+    #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+    {
+        crate::bsd::mount_info(path)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        mount_info_windows(path)
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "windows"
+    )))]
+    {
+        let _ = path;
+        Err(crate::WipeError::UnsupportedOperation(
+            "Mount detection not supported on this platform".into(),
+        ))
+    }
+}
+
+/// linux implementation: mount point and type from /proc/mounts, flags from statvfs
+#[cfg(target_os = "linux")]
+fn mount_info_linux(path: &Path) -> Result<MountInfo> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let canonical_path = std::fs::canonicalize(path)?;
+
+    let mounts = std::fs::read_to_string("/proc/mounts")?;
+    let (source, mount_point, fs_type) = find_mount(&mounts, &canonical_path).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Could not determine mount point",
+        )
+    })?;
+
+    // statvfs reflects the effective flags, including remounts
+    let c_path = CString::new(canonical_path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(MountInfo {
+        mount_point,
+        source,
+        fs_type,
+        read_only: stat.f_flag & libc::ST_RDONLY != 0,
+    })
+}
+
+/// finds the longest mount point in /proc/mounts that contains `path`
+#[cfg(target_os = "linux")]
+fn find_mount(mounts: &str, path: &Path) -> Option<(String, PathBuf, String)> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = unescape_mount_field(fields.next()?);
+            let mount_point = PathBuf::from(unescape_mount_field(fields.next()?));
+            let fs_type = fields.next()?.to_string();
+            Some((source, mount_point, fs_type))
+        })
+        .filter(|(_, mount_point, _)| path.starts_with(mount_point))
+        // later entries shadow earlier ones mounted at the same place
+        .max_by_key(|(_, mount_point, _)| mount_point.components().count())
+}
+
+/// lists the mount points of real (device-backed) filesystems
+#[cfg(target_os = "linux")]
+pub(crate) fn mount_points() -> Result<Vec<PathBuf>> {
+    let mounts = std::fs::read_to_string("/proc/mounts")?;
+    Ok(mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?;
+            let mount_point = fields.next()?;
+            source
+                .starts_with("/dev/")
+                .then(|| PathBuf::from(unescape_mount_field(mount_point)))
+        })
+        .collect())
+}
+
+/// decodes the octal escapes (\040 for space, etc.) used in /proc/mounts
+/// and /proc/swaps
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() {
+            let digits = std::str::from_utf8(&bytes[i + 1..i + 4]).unwrap_or("");
+            if let Ok(value) = u8::from_str_radix(digits, 8) {
+                out.push(value);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// macOS and FreeBSD implementation using statfs, which reports everything in
+/// one call
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn mount_info_statfs(path: &Path) -> Result<MountInfo> {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let mount_point = unsafe { CStr::from_ptr(stat.f_mntonname.as_ptr()) };
+    let source = unsafe { CStr::from_ptr(stat.f_mntfromname.as_ptr()) };
+    let fs_type = unsafe { CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+
+    Ok(MountInfo {
+        mount_point: PathBuf::from(mount_point.to_string_lossy().into_owned()),
+        source: source.to_string_lossy().into_owned(),
+        fs_type: fs_type.to_string_lossy().into_owned(),
+        // f_flags is 32 bits wide on macOS and 64 on FreeBSD
+        #[allow(clippy::unnecessary_cast)]
+        read_only: stat.f_flags as u64 & libc::MNT_RDONLY as u64 != 0,
+    })
+}
+
+/// windows implementation using the volume path and volume information APIs
+#[cfg(target_os = "windows")]
+fn mount_info_windows(path: &Path) -> Result<MountInfo> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::ptr;
+    use winapi::um::fileapi::{GetVolumeInformationW, GetVolumePathNameW};
+    use winapi::um::winnt::FILE_READ_ONLY_VOLUME;
+
+    let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+
+    // resolve the volume mount point (e.g., C:\ or C:\mnt\data\)
+    let mut volume_path = vec![0u16; 32 * 1024];
+    let success = unsafe {
+        GetVolumePathNameW(
+            wide_path.as_ptr(),
+            volume_path.as_mut_ptr(),
+            volume_path.len() as u32,
+        )
+    };
+    if success == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let mut fs_name = vec![0u16; 261];
+    let mut flags = 0u32;
+    let success = unsafe {
+        GetVolumeInformationW(
+            volume_path.as_ptr(),
+            ptr::null_mut(),
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut flags,
+            fs_name.as_mut_ptr(),
+            fs_name.len() as u32,
+        )
+    };
+    if success == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let to_string = |wide: &[u16]| {
+        let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+        OsString::from_wide(&wide[..len])
+    };
+
+    Ok(MountInfo {
+        mount_point: PathBuf::from(to_string(&volume_path)),
+        source: to_string(&volume_path).to_string_lossy().into_owned(),
+        fs_type: to_string(&fs_name).to_string_lossy().into_owned(),
+        read_only: flags & FILE_READ_ONLY_VOLUME != 0,
+    })
+}
+
+/// attributes that stop a file from being modified, even by root
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtectionFlags {
+    /// the file can't be written, renamed or deleted (chattr +i, chflags uchg/schg)
+    pub immutable: bool,
+
+    /// the file can only be appended to (chattr +a, chflags uappnd/sappnd)
+    pub append_only: bool,
+}
+
+impl ProtectionFlags {
+    /// whether any flag that blocks overwriting is set
+    pub fn any(&self) -> bool {
+        self.immutable || self.append_only
+    }
+}
+
+impl std::fmt::Display for ProtectionFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.immutable, self.append_only) {
+            (true, true) => write!(f, "immutable, append-only"),
+            (true, false) => write!(f, "immutable"),
+            (false, true) => write!(f, "append-only"),
+            (false, false) => write!(f, "unprotected"),
+        }
+    }
+}
+
+/// linux inode flags from <linux/fs.h>
+#[cfg(target_os = "linux")]
+const FS_IMMUTABLE_FL: libc::c_int = 0x0000_0010;
+#[cfg(target_os = "linux")]
+const FS_APPEND_FL: libc::c_int = 0x0000_0020;
+
+/// the user and system variants of the BSD flags, as st_flags holds them;
+/// libc declares them as c_uint on macOS but c_ulong on FreeBSD
+#[cfg(target_os = "macos")]
+const BSD_IMMUTABLE: u32 = libc::UF_IMMUTABLE | libc::SF_IMMUTABLE;
+#[cfg(target_os = "macos")]
+const BSD_APPEND: u32 = libc::UF_APPEND | libc::SF_APPEND;
+#[cfg(target_os = "freebsd")]
+const BSD_IMMUTABLE: u32 = (libc::UF_IMMUTABLE | libc::SF_IMMUTABLE) as u32;
+#[cfg(target_os = "freebsd")]
+const BSD_APPEND: u32 = (libc::UF_APPEND | libc::SF_APPEND) as u32;
+
+/// reads the immutable and append-only flags of a file
+///
+/// # Arguments
+/// * `path` - Path to the file
+///
+/// # Returns
+/// * `Result<ProtectionFlags>` - Which protection flags are set
+pub fn protection_flags(path: &Path) -> Result<ProtectionFlags> {
+    #[cfg(target_os = "linux")]
+    {
+        let flags = inode_flags(path, None)?;
+        Ok(ProtectionFlags {
+            immutable: flags & FS_IMMUTABLE_FL != 0,
+            append_only: flags & FS_APPEND_FL != 0,
+        })
+    }
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    {
+        let flags = bsd_file_flags(path)?;
+        Ok(ProtectionFlags {
+            immutable: flags & BSD_IMMUTABLE != 0,
+            append_only: flags & BSD_APPEND != 0,
+        })
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+    {
+        let _ = path;
+        Ok(ProtectionFlags::default())
+    }
+}
+
+/// removes the immutable and append-only flags from a file
+///
+/// needs CAP_LINUX_IMMUTABLE on linux; the system (schg/sappnd) variants on
+/// BSD can only be cleared by root outside of secure level 1+
+pub(crate) fn clear_protection_flags(path: &Path) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        let flags = inode_flags(path, None)?;
+        inode_flags(path, Some(flags & !(FS_IMMUTABLE_FL | FS_APPEND_FL)))?;
+        Ok(())
+    }
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let flags = bsd_file_flags(path)? & !(BSD_IMMUTABLE | BSD_APPEND);
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        if unsafe { libc::chflags(c_path.as_ptr(), flags as _) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+    {
+        let _ = path;
+        Err(crate::WipeError::UnsupportedOperation(
+            "File flags not supported on this platform".into(),
+        ))
+    }
+}
+
+/// reads the inode flags, or replaces them when `new_flags` is given
+#[cfg(target_os = "linux")]
+fn inode_flags(path: &Path, new_flags: Option<libc::c_int>) -> Result<libc::c_int> {
+    use std::os::unix::io::AsRawFd;
+
+    // read-only is enough for both ioctls and works on immutable files
+    let file = std::fs::File::open(path)?;
+    let mut flags: libc::c_int = 0;
+    let result = unsafe {
+        match new_flags {
+            Some(new_flags) => {
+                flags = new_flags;
+                libc::ioctl(file.as_raw_fd(), libc::FS_IOC_SETFLAGS, &flags)
+            }
+            None => libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags),
+        }
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(flags)
+}
+
+#[cfg(target_os = "macos")]
+fn bsd_file_flags(path: &Path) -> Result<u32> {
+    use std::os::macos::fs::MetadataExt;
+    Ok(std::fs::metadata(path)?.st_flags())
+}
+
+#[cfg(target_os = "freebsd")]
+fn bsd_file_flags(path: &Path) -> Result<u32> {
+    use std::os::freebsd::fs::MetadataExt;
+    Ok(std::fs::metadata(path)?.st_flags())
+}
+
+/// clears the read-only, hidden and system attributes on windows
+///
+/// read-only files can't be opened for writing, and hidden/system files
+/// can't be replaced by the renames done while wiping
+///
+/// # Returns
+/// * `Result<bool>` - true if any attribute was cleared
+pub(crate) fn clear_file_attributes(path: &Path) -> Result<bool> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use std::os::windows::fs::MetadataExt;
+        use winapi::um::fileapi::SetFileAttributesW;
+        use winapi::um::winnt::{
+            FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_NORMAL, FILE_ATTRIBUTE_READONLY,
+            FILE_ATTRIBUTE_SYSTEM,
+        };
+
+        let mask = FILE_ATTRIBUTE_READONLY | FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM;
+        let attributes = std::fs::metadata(path)?.file_attributes();
+        if attributes & mask == 0 {
+            return Ok(false);
+        }
+
+        // an empty attribute set has to be spelled FILE_ATTRIBUTE_NORMAL
+        let remaining = match attributes & !mask {
+            0 => FILE_ATTRIBUTE_NORMAL,
+            remaining => remaining,
+        };
+        let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        if unsafe { SetFileAttributesW(wide_path.as_ptr(), remaining) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(true)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = path;
+        Ok(false)
+    }
+}
+
+/// converts a path to the windows extended-length form (\\?\C:\... or \\?\UNC\...)
+///
+/// extended-length paths bypass MAX_PATH and the Win32 name normalization that
+/// strips trailing dots and spaces, so such files can be opened at all. the
+/// path is made absolute and `.`/`..` are resolved lexically, since neither is
+/// interpreted once the prefix is present. on other platforms the path is
+/// returned unchanged
+///
+/// # Arguments
+/// * `path` - Path as given by the user
+///
+/// # Returns
+/// * `PathBuf` - Path usable for all further file operations
+pub fn extended_length_path(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let current_dir = std::env::current_dir().unwrap_or_default();
+        match (path.to_str(), current_dir.to_str()) {
+            (Some(path), Some(current_dir)) => PathBuf::from(to_extended_length(path, current_dir)),
+            _ => path.to_path_buf(),
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        path.to_path_buf()
+    }
+}
+
+#[cfg(any(windows, test))]
+fn to_extended_length(path: &str, current_dir: &str) -> String {
+    // already verbatim, or a device path
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return path.to_string();
+    }
+    let current_dir = match current_dir.strip_prefix(r"\\?\UNC\") {
+        Some(unc) => format!(r"\\{}", unc),
+        None => current_dir.trim_start_matches(r"\\?\").to_string(),
+    };
+
+    let path = path.replace('/', "\\");
+    let absolute = if path.starts_with(r"\\") || path.get(1..3) == Some(":\\") {
+        path
+    } else if path.starts_with('\\') {
+        // rooted on the current drive
+        format!("{}{}", current_dir.get(..2).unwrap_or("C:"), path)
+    } else {
+        format!("{}\\{}", current_dir.trim_end_matches('\\'), path)
+    };
+
+    // the drive letter, or server and share, can't be climbed out of
+    let (prefix, rest, root_len) = match absolute.strip_prefix(r"\\") {
+        Some(unc) => (r"\\?\UNC\", unc, 2),
+        None => (r"\\?\", absolute.as_str(), 1),
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split('\\') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.len() > root_len {
+                    parts.pop();
+                }
+            }
+            _ => parts.push(part),
+        }
+    }
+
+    let mut extended = format!("{}{}", prefix, parts.join("\\"));
+    if parts.len() == 1 {
+        // bare drive: keep the root directory
+        extended.push('\\');
+    }
+    extended
+}
+
+/// replaces the file with a copy that has copy-on-write disabled
+///
+/// btrfs only honours the NOCOW flag on empty files, so the data is copied into
+/// a freshly flagged file which then atomically replaces the original. later
+/// passes overwrite the copy in place; the original extents are released, not
+/// overwritten.
+#[cfg(target_os = "linux")]
+pub(crate) fn relocate_to_nocow(path: &Path) -> Result<()> {
+    use std::fs::OpenOptions;
+
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid path"))?;
+    let temp_path = parent.join(format!(".{}.nocow", file_name.to_string_lossy()));
+
+    let mut copy = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)?;
+
+    // set the flag while the file is still empty
+    if let Err(err) = set_nocow(&copy) {
+        drop(copy);
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(err.into());
+    }
+
+    let copied = std::fs::File::open(path).and_then(|mut original| {
+        std::io::copy(&mut original, &mut copy)?;
+        copy.sync_all()
+    });
+    if let Err(e) = copied {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// sets the btrfs NOCOW flag on an open file, which must still be empty
+#[cfg(target_os = "linux")]
+pub(crate) fn set_nocow(file: &std::fs::File) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    const FS_NOCOW_FL: libc::c_int = 0x0080_0000;
+
+    let mut flags: libc::c_int = 0;
+    let result = unsafe {
+        if libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) == 0 {
+            flags |= FS_NOCOW_FL;
+            libc::ioctl(file.as_raw_fd(), libc::FS_IOC_SETFLAGS, &flags)
+        } else {
+            -1
+        }
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn relocate_to_nocow(_path: &Path) -> Result<()> {
+    Err(crate::WipeError::UnsupportedOperation(
+        "Disabling copy-on-write is only supported on Linux btrfs".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_length_path() {
+        let cwd = r"C:\Users\me";
+        assert_eq!(
+            to_extended_length(r"secret.txt.", cwd),
+            r"\\?\C:\Users\me\secret.txt."
+        );
+        assert_eq!(
+            to_extended_length(r"..\other/./file ", cwd),
+            r"\\?\C:\Users\other\file "
+        );
+        assert_eq!(
+            to_extended_length(r"\\server\share\..\dir\f", cwd),
+            r"\\?\UNC\server\share\dir\f"
+        );
+        assert_eq!(to_extended_length(r"\temp\f", cwd), r"\\?\C:\temp\f");
+        assert_eq!(
+            to_extended_length(r"\\.\PhysicalDrive1", cwd),
+            r"\\.\PhysicalDrive1"
+        );
+    }
+
+    /// the deepest matching mount point wins
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_find_mount_longest_prefix() {
+        let mounts = "/dev/sda1 / ext4 rw,relatime 0 0\n\
+                      /dev/sdb1 /mnt/data xfs ro,relatime 0 0\n\
+                      tmpfs /mnt/data/tmp tmpfs rw 0 0\n";
+
+        let (source, mount_point, fs_type) =
+            find_mount(mounts, Path::new("/mnt/data/file.bin")).unwrap();
+        assert_eq!(source, "/dev/sdb1");
+        assert_eq!(mount_point, PathBuf::from("/mnt/data"));
+        assert_eq!(fs_type, "xfs");
+    }
+
+    /// spaces in mount points are octal-escaped by the kernel
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_find_mount_escaped_path() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n/dev/sdc1 /media/USB\\040DISK vfat ro 0 0\n";
+
+        let (_, mount_point, _) = find_mount(mounts, Path::new("/media/USB DISK/a.txt")).unwrap();
+        assert_eq!(mount_point, PathBuf::from("/media/USB DISK"));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::Result;
+use std::path::Path;
+
+/// how to react when other processes have the target open
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpenFilePolicy {
+    /// do not look for open handles
+    Ignore,
+    /// wipe anyway and record the processes in the report
+    #[default]
+    Warn,
+    /// refuse to wipe while other processes have the target open
+    Refuse,
+}
+
+/// a process that holds the target open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenHandle {
+    /// process id
+    pub pid: u32,
+
+    /// process or application name
+    pub name: String,
+}
+
+impl std::fmt::Display for OpenHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (pid {})", self.name, self.pid)
+    }
+}
+
+/// lists other processes that have the file or device open
+///
+/// # Arguments
+/// * `path` - Path to the file or device about to be wiped
+///
+/// # Returns
+/// * `Result<Vec<OpenHandle>>` - Processes holding the target open, excluding this one
+pub fn find_open_handles(path: &Path) -> Result<Vec<OpenHandle>> {
+    #[cfg(target_os = "linux")]
+    {
+        find_open_handles_linux(path)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        find_open_handles_macos(path)
+    }
+    #[cfg(windows)]
+    {
+        find_open_handles_windows(path)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    {
+        let _ = path;
+        Err(crate::WipeError::UnsupportedOperation(
+            "Open file detection not supported on this platform".into(),
+        ))
+    }
+}
+
+/// linux: walks /proc/<pid>/fd and /proc/<pid>/maps (memory-mapped files)
+#[cfg(target_os = "linux")]
+fn find_open_handles_linux(path: &Path) -> Result<Vec<OpenHandle>> {
+    let target = std::fs::canonicalize(path)?;
+    let own_pid = std::process::id();
+    let mut handles = Vec::new();
+
+    for entry in std::fs::read_dir("/proc")?.flatten() {
+        let pid = match entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        {
+            Some(pid) if pid != own_pid => pid,
+            _ => continue,
+        };
+        let proc_dir = entry.path();
+
+        // processes we aren't allowed to inspect are skipped
+        let has_fd = std::fs::read_dir(proc_dir.join("fd"))
+            .map(|fds| {
+                fds.flatten()
+                    .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|link| link == target))
+            })
+            .unwrap_or(false);
+        let has_mapping = !has_fd
+            && std::fs::read_to_string(proc_dir.join("maps")).is_ok_and(|maps| {
+                maps.lines()
+                    .any(|line| line.ends_with(target.to_string_lossy().as_ref()))
+            });
+
+        if has_fd || has_mapping {
+            let name = std::fs::read_to_string(proc_dir.join("comm"))
+                .map(|comm| comm.trim().to_string())
+                .unwrap_or_default();
+            handles.push(OpenHandle { pid, name });
+        }
+    }
+
+    Ok(handles)
+}
+
+/// macOS: asks lsof, which has the privileges to see other users' files when run as root
+#[cfg(target_os = "macos")]
+fn find_open_handles_macos(path: &Path) -> Result<Vec<OpenHandle>> {
+    use std::process::Command;
+
+    // -F pc prints one field per line: p<pid> and c<command>
+    let output = Command::new("lsof")
+        .args(["-F", "pc", "--"])
+        .arg(path)
+        .output()?;
+    // lsof exits with 1 when nothing has the file open
+    let listing = String::from_utf8_lossy(&output.stdout);
+    let own_pid = std::process::id();
+
+    Ok(parse_lsof_fields(&listing)
+        .into_iter()
+        .filter(|handle| handle.pid != own_pid)
+        .collect())
+}
+
+/// parses `lsof -F pc` output
+#[cfg(any(target_os = "macos", test))]
+fn parse_lsof_fields(listing: &str) -> Vec<OpenHandle> {
+    let mut handles: Vec<OpenHandle> = Vec::new();
+
+    for line in listing.lines() {
+        if let Some(pid) = line.strip_prefix('p').and_then(|pid| pid.parse().ok()) {
+            handles.push(OpenHandle {
+                pid,
+                name: String::new(),
+            });
+        } else if let (Some(name), Some(handle)) = (line.strip_prefix('c'), handles.last_mut()) {
+            handle.name = name.to_string();
+        }
+    }
+
+    handles
+}
+
+/// windows: asks the Restart Manager which applications and services use the file
+#[cfg(windows)]
+fn find_open_handles_windows(path: &Path) -> Result<Vec<OpenHandle>> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::shared::winerror::{ERROR_MORE_DATA, ERROR_SUCCESS};
+    use winapi::um::restartmanager::{
+        RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+        RM_PROCESS_INFO,
+    };
+
+    let mut session = 0u32;
+    let mut session_key = [0u16; CCH_RM_SESSION_KEY + 1];
+    let status = unsafe { RmStartSession(&mut session, 0, session_key.as_mut_ptr()) };
+    if status != ERROR_SUCCESS {
+        return Err(std::io::Error::from_raw_os_error(status as i32).into());
+    }
+    let _session = scopeguard::guard(session, |session| unsafe {
+        RmEndSession(session);
+    });
+
+    let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut files = [wide_path.as_ptr()];
+    let status = unsafe {
+        RmRegisterResources(
+            session,
+            1,
+            files.as_mut_ptr(),
+            0,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return Err(std::io::Error::from_raw_os_error(status as i32).into());
+    }
+
+    // the list can grow between calls, so retry until it fits
+    let mut processes: Vec<RM_PROCESS_INFO> = Vec::new();
+    loop {
+        let mut needed = 0u32;
+        let mut count = processes.len() as u32;
+        let mut reboot_reasons = 0u32;
+        let status = unsafe {
+            RmGetList(
+                session,
+                &mut needed,
+                &mut count,
+                processes.as_mut_ptr(),
+                &mut reboot_reasons,
+            )
+        };
+        match status {
+            ERROR_SUCCESS => {
+                processes.truncate(count as usize);
+                break;
+            }
+            ERROR_MORE_DATA => {
+                processes = vec![unsafe { std::mem::zeroed() }; needed as usize];
+            }
+            _ => return Err(std::io::Error::from_raw_os_error(status as i32).into()),
+        }
+    }
+
+    let own_pid = std::process::id();
+    Ok(processes
+        .iter()
+        .filter(|info| info.Process.dwProcessId != own_pid)
+        .map(|info| {
+            let len = info
+                .strAppName
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(info.strAppName.len());
+            OpenHandle {
+                pid: info.Process.dwProcessId,
+                name: String::from_utf16_lossy(&info.strAppName[..len]),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lsof_fields() {
+        let listing = "p412\ncvim\np977\ncbackupd\n";
+        assert_eq!(
+            parse_lsof_fields(listing),
+            vec![
+                OpenHandle {
+                    pid: 412,
+                    name: "vim".into()
+                },
+                OpenHandle {
+                    pid: 977,
+                    name: "backupd".into()
+                },
+            ]
+        );
+    }
+
+    /// a file we hold open ourselves is not reported
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_own_handles_are_ignored() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert!(find_open_handles(file.path()).unwrap().is_empty());
+    }
+}
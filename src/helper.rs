@@ -0,0 +1,494 @@
+//! hardware operations (ATA secure erase, NVMe sanitize, TRIM) run in a small
+//! elevated helper process, so the rest of a wipe can run as the invoking user
+//!
+//! the helper is `shred helper`, started the first time a hardware operation
+//! is needed: with pkexec on linux when shredder's polkit action is installed,
+//! and through Authorization Services on macOS, so desktop sessions get an
+//! authentication dialog, and through sudo otherwise. the two processes talk over the helper's
+//! stdin and stdout, one request and one reply per line:
+//!
+//! ```text
+//! secure-erase <0|1 allow system disk> <hex path>    trim <hex path>
+//! ok                                                  error <kind> <message>
+//! ```
+//!
+//! the helper opens every target as the invoking user before touching it, so
+//! it only acts on devices and files that user could overwrite anyway. a
+//! secure erase is then run by path, so that path must lead to the very device
+//! that was opened, with no symlinks or directories the caller could change
+
+use crate::{Result, WipeError};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+
+/// an elevated helper process, started on first use and stopped when dropped
+pub struct Helper {
+    /// shred executable run as `<program> helper`
+    program: PathBuf,
+
+    /// the running helper, once started
+    session: Mutex<Option<Session>>,
+}
+
+impl std::fmt::Debug for Helper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Helper")
+            .field("program", &self.program)
+            .finish_non_exhaustive()
+    }
+}
+
+/// a running helper and the two ends of its pipe
+struct Session {
+    /// the helper process, when it is a child of this one
+    child: Option<Child>,
+    stdin: Option<Box<dyn Write + Send>>,
+    stdout: BufReader<Box<dyn Read + Send>>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // the helper exits when its stdin is closed
+        drop(self.stdin.take());
+        if let Some(child) = &mut self.child {
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Helper {
+    /// a helper that runs `<program> helper` when it is first needed
+    ///
+    /// # Arguments
+    /// * `program` - shred executable to run, e.g. `std::env::current_exe()`
+    pub fn new<P: AsRef<Path>>(program: P) -> Self {
+        Self {
+            program: program.as_ref().to_path_buf(),
+            session: Mutex::new(None),
+        }
+    }
+
+    /// erases a device with its built-in secure erase or sanitize command
+    ///
+    /// # Arguments
+    /// * `path` - Device to erase
+    /// * `allow_system_disk` - Erase it even if it holds an operating system
+    pub fn secure_erase(&self, path: &Path, allow_system_disk: bool) -> Result<()> {
+        let flag = if allow_system_disk { "1" } else { "0" };
+        self.request(&format!("secure-erase {} {}", flag, encode_path(path)?))
+    }
+
+    /// discards the unused blocks of the filesystem holding a file, or of a device
+    ///
+    /// # Arguments
+    /// * `path` - File or device to TRIM
+    pub fn trim(&self, path: &Path) -> Result<()> {
+        self.request(&format!("trim {}", encode_path(path)?))
+    }
+
+    /// starts the helper now rather than on the first request, e.g. before the
+    /// thread loses the right to run sudo
+    pub(crate) fn connect(&self) -> Result<()> {
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        if session.is_none() {
+            *session = Some(self.start()?);
+        }
+        Ok(())
+    }
+
+    /// sends one request and waits for its reply, starting the helper first if needed
+    fn request(&self, line: &str) -> Result<()> {
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        if session.is_none() {
+            *session = Some(self.start()?);
+        }
+        let result = exchange(session.as_mut().expect("started above"), line);
+        if let Err(WipeError::Helper(_)) = &result {
+            // the helper died or garbled its reply; start a new one next time
+            *session = None;
+        }
+        result
+    }
+
+    fn start(&self) -> Result<Session> {
+        log::info!(
+            "Starting the elevated helper {} helper",
+            self.program.display()
+        );
+        #[cfg(target_os = "macos")]
+        match crate::authorization::execute_with_privileges(&self.program, &["helper"]) {
+            Ok(pipe) => {
+                let stdin = pipe.try_clone()?;
+                return Ok(Session {
+                    child: None,
+                    stdin: Some(Box::new(stdin)),
+                    stdout: BufReader::new(Box::new(pipe)),
+                });
+            }
+            Err(e @ WipeError::Io(_)) if is_denied(&e) => return Err(e),
+            // e.g. no window server to show the dialog on, over ssh
+            Err(e) => log::debug!("Authorization Services unavailable ({}), using sudo", e),
+        }
+        let mut command = elevated(&self.program);
+        let mut child = command
+            .arg("helper")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                WipeError::Helper(format!("unable to start {}: {}", self.program.display(), e))
+            })?;
+        let stdin = child
+            .stdin
+            .take()
+            .map(|stdin| Box::new(stdin) as Box<dyn Write + Send>);
+        match child.stdout.take() {
+            Some(stdout) => Ok(Session {
+                child: Some(child),
+                stdin,
+                stdout: BufReader::new(Box::new(stdout)),
+            }),
+            None => Err(WipeError::Helper("the helper has no stdout".into())),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn is_denied(e: &WipeError) -> bool {
+    matches!(e, WipeError::Io(e) if e.kind() == io::ErrorKind::PermissionDenied)
+}
+
+/// `program` run with root privileges: through pkexec if shredder's polkit
+/// action is installed, otherwise through sudo, which asks for a password on
+/// the terminal if it needs one
+#[cfg(unix)]
+fn elevated(program: &Path) -> Command {
+    #[cfg(target_os = "linux")]
+    if crate::polkit::installed() {
+        // pkexec only matches the action to an absolute path
+        let mut command = Command::new("pkexec");
+        command.arg(program);
+        return command;
+    }
+    let mut command = Command::new("sudo");
+    command.arg("--").arg(program);
+    command
+}
+
+/// `program` run as it is; it is only elevated if this process already is
+#[cfg(windows)]
+fn elevated(program: &Path) -> Command {
+    Command::new(program)
+}
+
+fn exchange(session: &mut Session, line: &str) -> Result<()> {
+    let lost = |e: io::Error| WipeError::Helper(format!("lost the connection: {}", e));
+    let stdin = session
+        .stdin
+        .as_mut()
+        .ok_or_else(|| WipeError::Helper("the helper's stdin is closed".into()))?;
+    writeln!(stdin, "{}", line).map_err(lost)?;
+    stdin.flush().map_err(lost)?;
+
+    let mut reply = String::new();
+    if session.stdout.read_line(&mut reply).map_err(lost)? == 0 {
+        return Err(WipeError::Helper(match &mut session.child {
+            Some(child) => format!("the helper exited ({})", child.wait().map_err(lost)?),
+            None => "the helper exited".to_string(),
+        }));
+    }
+    parse_reply(reply.trim_end())
+}
+
+fn parse_reply(reply: &str) -> Result<()> {
+    if reply == "ok" {
+        return Ok(());
+    }
+    let Some(error) = reply.strip_prefix("error ") else {
+        return Err(WipeError::Helper(format!("unexpected reply '{}'", reply)));
+    };
+    let (kind, message) = error.split_once(' ').unwrap_or((error, ""));
+    let message = message.to_string();
+    Err(match kind {
+        "unsupported" => WipeError::UnsupportedOperation(message),
+        "denied" => WipeError::Io(io::Error::new(io::ErrorKind::PermissionDenied, message)),
+        "system-disk" => WipeError::SystemDisk(PathBuf::from(message)),
+        _ => WipeError::Helper(message),
+    })
+}
+
+/// answers requests read from `input` until it is closed, for `shred helper`
+///
+/// # Arguments
+/// * `input` - Where requests come from, the helper's stdin
+/// * `output` - Where replies go, the helper's stdout
+///
+/// # Returns
+/// * `Result<()>` - Ok once `input` is closed, or the I/O error that ended it
+pub fn serve<R: BufRead, W: Write>(input: R, mut output: W) -> Result<()> {
+    let caller = caller();
+    log::debug!("Helper serving {:?}", caller);
+    for line in input.lines() {
+        let line = line?;
+        let reply = match handle(&line, caller) {
+            Ok(()) => "ok".to_string(),
+            Err(e) => {
+                log::warn!("Helper request '{}' failed: {}", line, e);
+                let (kind, message) = match e {
+                    WipeError::UnsupportedOperation(message) => ("unsupported", message),
+                    WipeError::SystemDisk(path) => ("system-disk", path.display().to_string()),
+                    WipeError::Io(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                        ("denied", e.to_string())
+                    }
+                    other => ("failed", other.to_string()),
+                };
+                format!("error {} {}", kind, message.replace('\n', " "))
+            }
+        };
+        writeln!(output, "{}", reply)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn handle(line: &str, caller: Option<Caller>) -> Result<()> {
+    let mut words = line.split(' ');
+    match (words.next(), words.next(), words.next(), words.next()) {
+        (Some("secure-erase"), Some(flag @ ("0" | "1")), Some(path), None) => {
+            let path = std::fs::canonicalize(decode_path(path)?)?;
+            // only devices the caller could overwrite themselves
+            let device = open_as(&path, caller)?;
+            pinned_device(&device, &path)?;
+            crate::secure_erase::perform_secure_erase(&path, flag == "1")
+        }
+        (Some("trim"), Some(path), None, None) => {
+            let path = decode_path(path)?;
+            let mut file = open_as(&path, caller)?;
+            crate::trim::perform_trim(&mut file)
+        }
+        _ => Err(WipeError::Helper(format!("unknown request '{}'", line))),
+    }
+}
+
+/// the user who started the helper through pkexec, sudo or Authorization
+/// Services, when it runs as root for them
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy)]
+struct Caller {
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+}
+
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy)]
+struct Caller;
+
+#[cfg(unix)]
+fn caller() -> Option<Caller> {
+    if unsafe { libc::geteuid() } != 0 {
+        return None;
+    }
+    // set by pkexec and sudo themselves, so the caller can't pick them.
+    // pkexec clears the environment, so SUDO_* can't be left over from a sudo
+    // that started it
+    let var = |name: &str| std::env::var(name).ok()?.parse().ok();
+    if let Some(uid) = var("PKEXEC_UID").filter(|&uid| uid != 0) {
+        let user = unsafe { libc::getpwuid(uid) };
+        if user.is_null() {
+            // nobody to act for; opening as root would let anyone in
+            return Some(Caller { uid, gid: uid });
+        }
+        let gid = unsafe { (*user).pw_gid };
+        return Some(Caller { uid, gid });
+    }
+    match (var("SUDO_UID"), var("SUDO_GID")) {
+        (Some(uid), Some(gid)) if uid != 0 => Some(Caller { uid, gid }),
+        // Authorization Services and setuid binaries only raise the effective user
+        _ => match unsafe { (libc::getuid(), libc::getgid()) } {
+            (0, _) => None,
+            (uid, gid) => Some(Caller { uid, gid }),
+        },
+    }
+}
+
+#[cfg(windows)]
+fn caller() -> Option<Caller> {
+    None
+}
+
+/// opens `path` for writing with the caller's permissions, or with the
+/// helper's own if it wasn't started for another user
+#[cfg(unix)]
+fn open_as(path: &Path, caller: Option<Caller>) -> Result<File> {
+    let open = || OpenOptions::new().read(true).write(true).open(path);
+    let Some(caller) = caller else {
+        return Ok(open()?);
+    };
+
+    let mut groups = vec![0 as libc::gid_t; 256];
+    let count = unsafe { libc::getgroups(groups.len() as _, groups.as_mut_ptr()) };
+    if count < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    groups.truncate(count as usize);
+
+    let user = unsafe { libc::getpwuid(caller.uid) };
+    if user.is_null() {
+        return Err(WipeError::Helper(format!("unknown user {}", caller.uid)));
+    }
+    let switched = unsafe {
+        libc::initgroups((*user).pw_name, caller.gid as _) == 0
+            && libc::setegid(caller.gid) == 0
+            && libc::seteuid(caller.uid) == 0
+    };
+    let file = if switched {
+        open()
+    } else {
+        Err(io::Error::last_os_error())
+    };
+    let restored = unsafe {
+        libc::seteuid(0) == 0
+            && libc::setegid(0) == 0
+            && libc::setgroups(groups.len() as _, groups.as_ptr()) == 0
+    };
+    if !restored {
+        // carrying on with the caller's identity half in place is worse than stopping
+        log::error!("Helper unable to restore its privileges, exiting");
+        std::process::exit(1);
+    }
+    Ok(file?)
+}
+
+#[cfg(windows)]
+fn open_as(path: &Path, _caller: Option<Caller>) -> Result<File> {
+    Ok(OpenOptions::new().read(true).write(true).open(path)?)
+}
+
+/// makes sure the canonical `path` still names `device`, the device opened
+/// as the caller, and that nothing on the way to it belongs to anyone but
+/// root, so the caller can't swap it for another device before the erase
+/// opens it again by path
+#[cfg(unix)]
+fn pinned_device(device: &File, path: &Path) -> Result<()> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let opened = device.metadata()?;
+    if !(opened.file_type().is_block_device() || opened.file_type().is_char_device()) {
+        return Err(WipeError::UnsupportedOperation(format!(
+            "{} is not a device",
+            path.display()
+        )));
+    }
+    for dir in path.ancestors().skip(1) {
+        let metadata = std::fs::metadata(dir)?;
+        if metadata.uid() != 0 || metadata.mode() & 0o022 != 0 {
+            return Err(WipeError::Io(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} can be changed by users other than root", dir.display()),
+            )));
+        }
+    }
+    if std::fs::metadata(path)?.rdev() != opened.rdev() {
+        return Err(WipeError::Helper(format!(
+            "{} changed while it was checked",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// the helper isn't started for another user on windows
+#[cfg(windows)]
+fn pinned_device(_device: &File, _path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// paths go over the pipe as hex, so spaces, newlines and non-UTF-8 bytes survive
+fn encode_path(path: &Path) -> Result<String> {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+    #[cfg(windows)]
+    let bytes = path
+        .to_str()
+        .ok_or_else(|| {
+            WipeError::UnsupportedOperation(format!("{} is not valid Unicode", path.display()))
+        })?
+        .as_bytes();
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn decode_path(hex: &str) -> Result<PathBuf> {
+    let invalid = || WipeError::Helper(format!("invalid path '{}'", hex));
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+    #[cfg(unix)]
+    let path =
+        PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(bytes));
+    #[cfg(windows)]
+    let path = PathBuf::from(String::from_utf8(bytes).map_err(|_| invalid())?);
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol() {
+        let path = Path::new("/tmp/a b\nc");
+        assert_eq!(decode_path(&encode_path(path).unwrap()).unwrap(), path);
+        assert!(decode_path("2f7").is_err() && decode_path("zz").is_err());
+
+        assert!(parse_reply("ok").is_ok());
+        assert!(matches!(
+            parse_reply("error unsupported no hdparm"),
+            Err(WipeError::UnsupportedOperation(message)) if message == "no hdparm"
+        ));
+        assert!(matches!(parse_reply("garbage"), Err(WipeError::Helper(_))));
+
+        // a missing target and an unknown request are answered, not fatal
+        let requests = format!(
+            "trim {}\nformat {}\n",
+            encode_path(Path::new("/nonexistent/shredder")).unwrap(),
+            encode_path(path).unwrap()
+        );
+        let mut replies = Vec::new();
+        serve(requests.as_bytes(), &mut replies).unwrap();
+        let replies = String::from_utf8(replies).unwrap();
+        let replies: Vec<&str> = replies.lines().collect();
+        assert_eq!(replies.len(), 2);
+        assert!(replies.iter().all(|reply| reply.starts_with("error ")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_secure_erase_needs_a_pinned_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("disk");
+        std::fs::write(&file, b"data").unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        // a regular file behind a symlink is refused before root touches it
+        let request = format!("secure-erase 0 {}", encode_path(&link).unwrap());
+        assert!(matches!(
+            handle(&request, None),
+            Err(WipeError::UnsupportedOperation(message)) if message.contains("not a device")
+        ));
+
+        // a device only counts through directories nobody but root can change
+        let device = File::open("/dev/null").unwrap();
+        assert!(pinned_device(&device, Path::new("/dev/null")).is_ok());
+        let moved = dir.path().join("null");
+        assert!(pinned_device(&device, &moved).is_err());
+    }
+}
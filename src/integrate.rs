@@ -0,0 +1,633 @@
+//! "Shred with shredder" entries in file managers: the context menu and Send
+//! To on windows, a Nautilus script and a Dolphin service menu on linux, and a
+//! Finder quick action on macOS
+//!
+//! every entry runs `shred shell-action -- <selected files>`, which asks in a
+//! dialog before wiping anything, since there is no terminal to type the
+//! confirmation into. the entries are installed for the current user only
+
+use crate::{Result, WipeError};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// label of the entries in the menus
+pub const LABEL: &str = "Shred with shredder";
+
+/// subcommand the entries run, followed by `--` and the selected files
+pub const ACTION: &str = "shell-action";
+
+/// selected files named in the confirmation dialog; the rest are counted
+const LISTED: usize = 5;
+
+/// a file an entry consists of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrationFile {
+    /// where the file is installed
+    pub path: PathBuf,
+
+    /// contents of the file
+    pub contents: String,
+
+    /// whether the file has to be executable, as scripts and KDE 6 service menus do
+    pub executable: bool,
+}
+
+/// script Nautilus offers under Scripts in the context menu, receiving the
+/// selected files as arguments
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+/// * `data_dir` - $XDG_DATA_HOME, usually ~/.local/share
+///
+/// # Returns
+/// * `IntegrationFile` - The executable script
+pub fn nautilus_script(program: &Path, data_dir: &Path) -> IntegrationFile {
+    IntegrationFile {
+        path: data_dir.join("nautilus/scripts").join(LABEL),
+        contents: format!(
+            "#!/bin/sh\n\
+             # added by `shred integrate`\n\
+             exec {} {} -- \"$@\"\n",
+            crate::remote::shell_quote(&program.to_string_lossy()),
+            ACTION
+        ),
+        executable: true,
+    }
+}
+
+/// service menu adding the entry to Dolphin's context menu for files and
+/// directories, in the places KDE 6 and KDE 5 look for it
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+/// * `data_dir` - $XDG_DATA_HOME, usually ~/.local/share
+///
+/// # Returns
+/// * `Vec<IntegrationFile>` - The same .desktop file for each version
+pub fn dolphin_service_menus(program: &Path, data_dir: &Path) -> Vec<IntegrationFile> {
+    let contents = format!(
+        "[Desktop Entry]\n\
+         Type=Service\n\
+         X-KDE-ServiceTypes=KonqPopupMenu/Plugin\n\
+         MimeType=all/allfiles;inode/directory;\n\
+         Actions=shred\n\
+         \n\
+         [Desktop Action shred]\n\
+         Name={}\n\
+         Icon=edit-delete-shred\n\
+         Exec={} {} -- %F\n",
+        LABEL,
+        desktop_exec_quote(&program.to_string_lossy()),
+        ACTION
+    );
+    ["kio/servicemenus", "kservices5/ServiceMenus"]
+        .into_iter()
+        .map(|dir| IntegrationFile {
+            path: data_dir.join(dir).join("shredder.desktop"),
+            contents: contents.clone(),
+            executable: true,
+        })
+        .collect()
+}
+
+/// the Automator workflow bundle Finder lists under Quick Actions
+pub fn finder_workflow_dir(home: &Path) -> PathBuf {
+    home.join("Library/Services")
+        .join(format!("{}.workflow", LABEL))
+}
+
+/// quick action Finder offers for selected files and folders: a workflow
+/// that runs a shell script with them as arguments
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+/// * `home` - Home directory of the user
+///
+/// # Returns
+/// * `Vec<IntegrationFile>` - Info.plist and document.wflow of the bundle
+pub fn finder_quick_action(program: &Path, home: &Path) -> Vec<IntegrationFile> {
+    let contents = finder_workflow_dir(home).join("Contents");
+    let script = format!(
+        "{} {} -- \"$@\"",
+        crate::remote::shell_quote(&program.to_string_lossy()),
+        ACTION
+    );
+    let info = format!(
+        "{}<dict>\n\
+         \t<key>NSServices</key>\n\
+         \t<array>\n\
+         \t\t<dict>\n\
+         \t\t\t<key>NSMenuItem</key>\n\
+         \t\t\t<dict>\n\
+         \t\t\t\t<key>default</key>\n\
+         \t\t\t\t<string>{}</string>\n\
+         \t\t\t</dict>\n\
+         \t\t\t<key>NSMessage</key>\n\
+         \t\t\t<string>runWorkflowAsService</string>\n\
+         \t\t\t<key>NSRequiredContext</key>\n\
+         \t\t\t<dict>\n\
+         \t\t\t\t<key>NSApplicationIdentifier</key>\n\
+         \t\t\t\t<string>com.apple.finder</string>\n\
+         \t\t\t</dict>\n\
+         \t\t\t<key>NSSendFileTypes</key>\n\
+         \t\t\t<array>\n\
+         \t\t\t\t<string>public.item</string>\n\
+         \t\t\t</array>\n\
+         \t\t</dict>\n\
+         \t</array>\n\
+         </dict>\n\
+         </plist>\n",
+        PLIST_HEADER, LABEL
+    );
+    // a single Run Shell Script action, passing the input as arguments
+    let document = format!(
+        "{}<dict>\n\
+         \t<key>AMApplicationBuild</key>\n\
+         \t<string>523</string>\n\
+         \t<key>AMApplicationVersion</key>\n\
+         \t<string>2.10</string>\n\
+         \t<key>AMDocumentVersion</key>\n\
+         \t<string>2</string>\n\
+         \t<key>actions</key>\n\
+         \t<array>\n\
+         \t\t<dict>\n\
+         \t\t\t<key>action</key>\n\
+         \t\t\t<dict>\n\
+         \t\t\t\t<key>AMAccepts</key>\n\
+         \t\t\t\t<dict>\n\
+         \t\t\t\t\t<key>Container</key>\n\
+         \t\t\t\t\t<string>List</string>\n\
+         \t\t\t\t\t<key>Optional</key>\n\
+         \t\t\t\t\t<true/>\n\
+         \t\t\t\t\t<key>Types</key>\n\
+         \t\t\t\t\t<array>\n\
+         \t\t\t\t\t\t<string>com.apple.cocoa.path</string>\n\
+         \t\t\t\t\t</array>\n\
+         \t\t\t\t</dict>\n\
+         \t\t\t\t<key>AMActionVersion</key>\n\
+         \t\t\t\t<string>2.0.3</string>\n\
+         \t\t\t\t<key>AMProvides</key>\n\
+         \t\t\t\t<dict>\n\
+         \t\t\t\t\t<key>Container</key>\n\
+         \t\t\t\t\t<string>List</string>\n\
+         \t\t\t\t\t<key>Types</key>\n\
+         \t\t\t\t\t<array>\n\
+         \t\t\t\t\t\t<string>com.apple.cocoa.string</string>\n\
+         \t\t\t\t\t</array>\n\
+         \t\t\t\t</dict>\n\
+         \t\t\t\t<key>ActionBundlePath</key>\n\
+         \t\t\t\t<string>/System/Library/Automator/Run Shell Script.action</string>\n\
+         \t\t\t\t<key>ActionName</key>\n\
+         \t\t\t\t<string>Run Shell Script</string>\n\
+         \t\t\t\t<key>ActionParameters</key>\n\
+         \t\t\t\t<dict>\n\
+         \t\t\t\t\t<key>COMMAND_STRING</key>\n\
+         \t\t\t\t\t<string>{}</string>\n\
+         \t\t\t\t\t<key>CheckedForUserDefaultShell</key>\n\
+         \t\t\t\t\t<true/>\n\
+         \t\t\t\t\t<key>inputMethod</key>\n\
+         \t\t\t\t\t<integer>1</integer>\n\
+         \t\t\t\t\t<key>shell</key>\n\
+         \t\t\t\t\t<string>/bin/sh</string>\n\
+         \t\t\t\t\t<key>source</key>\n\
+         \t\t\t\t\t<string></string>\n\
+         \t\t\t\t</dict>\n\
+         \t\t\t\t<key>BundleIdentifier</key>\n\
+         \t\t\t\t<string>com.apple.RunShellScript</string>\n\
+         \t\t\t\t<key>CFBundleVersion</key>\n\
+         \t\t\t\t<string>2.0.3</string>\n\
+         \t\t\t\t<key>Class Name</key>\n\
+         \t\t\t\t<string>RunShellScriptAction</string>\n\
+         \t\t\t</dict>\n\
+         \t\t</dict>\n\
+         \t</array>\n\
+         \t<key>connectors</key>\n\
+         \t<dict/>\n\
+         \t<key>workflowMetaData</key>\n\
+         \t<dict>\n\
+         \t\t<key>serviceInputTypeIdentifier</key>\n\
+         \t\t<string>com.apple.Automator.fileSystemObject</string>\n\
+         \t\t<key>serviceOutputTypeIdentifier</key>\n\
+         \t\t<string>com.apple.Automator.nothing</string>\n\
+         \t\t<key>workflowTypeIdentifier</key>\n\
+         \t\t<string>com.apple.Automator.servicesMenu</string>\n\
+         \t</dict>\n\
+         </dict>\n\
+         </plist>\n",
+        PLIST_HEADER,
+        crate::polkit::xml_escape(&script)
+    );
+    vec![
+        IntegrationFile {
+            path: contents.join("Info.plist"),
+            contents: info,
+            executable: false,
+        },
+        IntegrationFile {
+            path: contents.join("document.wflow"),
+            contents: document,
+            executable: false,
+        },
+    ]
+}
+
+const PLIST_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+     <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+     <plist version=\"1.0\">\n";
+
+/// batch file in the Send To menu, which Explorer runs with all the selected
+/// files; its console stays open if the wipe fails, so the error can be read
+///
+/// # Arguments
+/// * `program` - Absolute path of shred.exe
+/// * `app_data` - %APPDATA% of the user
+///
+/// # Returns
+/// * `IntegrationFile` - The .cmd file
+pub fn send_to_script(program: &Path, app_data: &Path) -> IntegrationFile {
+    IntegrationFile {
+        path: app_data
+            .join(r"Microsoft\Windows\SendTo")
+            .join(format!("{}.cmd", LABEL)),
+        contents: format!(
+            "@echo off\r\n\
+             rem added by `shred integrate`\r\n\
+             \"{}\" {} -- %*\r\n\
+             if errorlevel 1 pause\r\n",
+            program.to_string_lossy().replace('%', "%%"),
+            ACTION
+        ),
+        executable: false,
+    }
+}
+
+/// registry keys of the Explorer context menu entry, for files and for directories
+pub const CONTEXT_MENU_KEYS: [&str; 2] = [
+    r"HKCU\Software\Classes\*\shell\shredder",
+    r"HKCU\Software\Classes\Directory\shell\shredder",
+];
+
+/// registry values of the Explorer context menu entry, which Explorer runs
+/// once for each selected file
+///
+/// # Arguments
+/// * `program` - Absolute path of shred.exe
+///
+/// # Returns
+/// * `Vec<(String, Option<&str>, String)>` - Key, value name (None for the
+///   default value) and data
+pub fn context_menu_values(program: &Path) -> Vec<(String, Option<&'static str>, String)> {
+    let program = program.to_string_lossy();
+    CONTEXT_MENU_KEYS
+        .iter()
+        .flat_map(|key| {
+            [
+                (key.to_string(), None, LABEL.to_string()),
+                (key.to_string(), Some("Icon"), format!("\"{}\",0", program)),
+                (
+                    format!(r"{}\command", key),
+                    None,
+                    format!("\"{}\" {} -- \"%1\"", program, ACTION),
+                ),
+            ]
+        })
+        .collect()
+}
+
+/// files of the entries for the current user on this platform
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+///
+/// # Returns
+/// * `Result<Vec<IntegrationFile>>` - The files, or an error if the user's
+///   directories can't be found
+pub fn files(program: &Path) -> Result<Vec<IntegrationFile>> {
+    #[cfg(windows)]
+    {
+        let app_data = std::env::var_os("APPDATA")
+            .map(PathBuf::from)
+            .ok_or_else(|| WipeError::Config("%APPDATA% is not set".to_string()))?;
+        Ok(vec![send_to_script(program, &app_data)])
+    }
+    #[cfg(target_os = "macos")]
+    {
+        Ok(finder_quick_action(program, &home()?))
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => home()?.join(".local/share"),
+        };
+        let mut files = vec![nautilus_script(program, &data_dir)];
+        files.extend(dolphin_service_menus(program, &data_dir));
+        // lets the session bus start `shred dbus` when it is first called
+        #[cfg(feature = "dbus")]
+        files.push(IntegrationFile {
+            path: data_dir.join("dbus-1/services/org.shredder.Manager.service"),
+            contents: crate::dbus::activation_file(program),
+            executable: false,
+        });
+        Ok(files)
+    }
+}
+
+#[cfg(unix)]
+fn home() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| WipeError::Config("$HOME is not set".to_string()))
+}
+
+/// installs the entries for the current user
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+///
+/// # Returns
+/// * `Result<Vec<String>>` - What was added: files, and registry keys on windows
+pub fn install(program: &Path) -> Result<Vec<String>> {
+    #[cfg(unix)]
+    if unsafe { libc::geteuid() } == 0 && std::env::var_os("SUDO_UID").is_some() {
+        return Err(WipeError::UnsupportedOperation(
+            "the entries belong to a user; run shred integrate as that user, without sudo"
+                .to_string(),
+        ));
+    }
+
+    let mut added = Vec::new();
+    for file in files(program)? {
+        if let Some(dir) = file.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&file.path, &file.contents)?;
+        #[cfg(unix)]
+        if file.executable {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&file.path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        added.push(file.path.display().to_string());
+    }
+
+    #[cfg(windows)]
+    for (key, name, data) in context_menu_values(program) {
+        let mut command = Command::new("reg");
+        command.args(["add", &key]);
+        match name {
+            Some(name) => command.args(["/v", name]),
+            None => command.arg("/ve"),
+        };
+        command.args(["/d", &data, "/f"]);
+        run(&mut command)?;
+        if !added.contains(&key) {
+            added.push(key);
+        }
+    }
+    Ok(added)
+}
+
+/// removes the entries `install` added, skipping those already gone
+///
+/// # Returns
+/// * `Result<Vec<String>>` - What was removed
+pub fn uninstall() -> Result<Vec<String>> {
+    // the paths don't depend on the binary
+    let mut removed = Vec::new();
+    for file in files(Path::new(""))? {
+        match std::fs::remove_file(&file.path) {
+            Ok(()) => removed.push(file.path.display().to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let workflow = finder_workflow_dir(&home()?);
+        if workflow.exists() {
+            std::fs::remove_dir_all(&workflow)?;
+        }
+    }
+    #[cfg(windows)]
+    for key in CONTEXT_MENU_KEYS {
+        let exists = Command::new("reg")
+            .args(["query", key])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success());
+        if exists {
+            run(Command::new("reg").args(["delete", key, "/f"]))?;
+            removed.push(key.to_string());
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(windows)]
+fn run(command: &mut Command) -> Result<()> {
+    let output = command.stdin(Stdio::null()).output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(WipeError::UnsupportedOperation(format!(
+            "reg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// asks the user at the desktop whether to wipe the selected files
+///
+/// # Arguments
+/// * `paths` - The selected files and directories
+///
+/// # Returns
+/// * `Result<bool>` - Whether the user agreed, or an error if no dialog
+///   could be shown
+pub fn confirm(paths: &[PathBuf]) -> Result<bool> {
+    let question = question(paths);
+    for mut command in dialog_commands(&question) {
+        match command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+        {
+            Ok(status) => return Ok(status.success()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(WipeError::UnsupportedOperation(
+        "no program to show the confirmation dialog with was found".to_string(),
+    ))
+}
+
+/// text of the confirmation dialog
+fn question(paths: &[PathBuf]) -> String {
+    let mut text = match paths.len() {
+        1 => "Securely erase this item? It can't be recovered afterwards.\n".to_string(),
+        n => format!(
+            "Securely erase these {} items? They can't be recovered afterwards.\n",
+            n
+        ),
+    };
+    for path in paths.iter().take(LISTED) {
+        text.push_str(&format!("\n{}", path.display()));
+    }
+    if paths.len() > LISTED {
+        text.push_str(&format!("\nand {} more", paths.len() - LISTED));
+    }
+    text
+}
+
+/// dialog programs to try in turn; each exits successfully only if the user agreed
+#[cfg(all(unix, not(target_os = "macos")))]
+fn dialog_commands(question: &str) -> Vec<Command> {
+    let mut zenity = Command::new("zenity");
+    zenity.args([
+        "--question",
+        "--title=shredder",
+        "--icon-name=dialog-warning",
+        "--ok-label=Shred",
+        "--cancel-label=Cancel",
+        "--no-markup",
+        "--text",
+        question,
+    ]);
+    let mut kdialog = Command::new("kdialog");
+    kdialog.args([
+        "--title",
+        "shredder",
+        "--warningcontinuecancel",
+        question,
+        "--continue-label",
+        "Shred",
+    ]);
+    vec![zenity, kdialog]
+}
+
+#[cfg(target_os = "macos")]
+fn dialog_commands(question: &str) -> Vec<Command> {
+    let script = format!(
+        "display dialog {} with title \"shredder\" buttons {{\"Cancel\", \"Shred\"}} \
+         default button \"Cancel\" cancel button \"Cancel\" with icon caution",
+        crate::notify::applescript_string(question)
+    );
+    let mut osascript = Command::new("osascript");
+    osascript.args(["-e", &script]);
+    vec![osascript]
+}
+
+#[cfg(windows)]
+fn dialog_commands(question: &str) -> Vec<Command> {
+    // the text goes through the environment, so it needs no quoting
+    let mut powershell = Command::new("powershell");
+    powershell.env("SHREDDER_QUESTION", question).args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        "Add-Type -AssemblyName PresentationFramework; \
+         if ([System.Windows.MessageBox]::Show($env:SHREDDER_QUESTION, 'shredder', 'OKCancel', 'Warning') -eq 'OK') { exit 0 } else { exit 1 }",
+    ]);
+    vec![powershell]
+}
+
+/// quotes the program for the Exec key of a .desktop file: the quoting rule
+/// of the Exec key applied first, then the escapes of string values
+fn desktop_exec_quote(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted.replace('%', "%%").replace('\\', "\\\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linux_entries() {
+        let program = Path::new("/opt/my tools/shred");
+        let data_dir = Path::new("/home/ann/.local/share");
+
+        let script = nautilus_script(program, data_dir);
+        assert_eq!(
+            script.path,
+            Path::new("/home/ann/.local/share/nautilus/scripts/Shred with shredder")
+        );
+        assert!(script.executable);
+        assert!(script
+            .contents
+            .ends_with("exec '/opt/my tools/shred' shell-action -- \"$@\"\n"));
+
+        let menus = dolphin_service_menus(program, data_dir);
+        assert_eq!(
+            menus[0].path,
+            Path::new("/home/ann/.local/share/kio/servicemenus/shredder.desktop")
+        );
+        assert!(menus[0]
+            .contents
+            .contains("Exec=\"/opt/my tools/shred\" shell-action -- %F\n"));
+        assert_eq!(
+            desktop_exec_quote("/opt/$x\\100%"),
+            "\"/opt/\\\\$x\\\\\\\\100%%\""
+        );
+    }
+
+    #[test]
+    fn test_windows_and_macos_entries() {
+        let program = Path::new(r"C:\Program Files\shredder\shred.exe");
+        let send_to = send_to_script(program, Path::new(r"C:\Users\ann\AppData\Roaming"));
+        assert!(send_to
+            .contents
+            .contains("\"C:\\Program Files\\shredder\\shred.exe\" shell-action -- %*\r\n"));
+        let values = context_menu_values(program);
+        assert_eq!(values.len(), 6);
+        assert_eq!(
+            values[2],
+            (
+                r"HKCU\Software\Classes\*\shell\shredder\command".to_string(),
+                None,
+                r#""C:\Program Files\shredder\shred.exe" shell-action -- "%1""#.to_string()
+            )
+        );
+
+        let action = finder_quick_action(
+            Path::new("/Applications/a&b/shred"),
+            Path::new("/Users/ann"),
+        );
+        assert_eq!(
+            action[1].path,
+            Path::new(
+                "/Users/ann/Library/Services/Shred with shredder.workflow/Contents/document.wflow"
+            )
+        );
+        assert!(action[1].contents.contains(
+            "<string>'/Applications/a&amp;b/shred' shell-action -- &quot;$@&quot;</string>"
+        ));
+        assert!(action[0]
+            .contents
+            .contains("<string>Shred with shredder</string>"));
+    }
+
+    #[test]
+    fn test_question() {
+        let one = question(&[PathBuf::from("/home/ann/tax.pdf")]);
+        assert!(one.starts_with("Securely erase this item?"));
+        assert!(one.ends_with("\n/home/ann/tax.pdf"));
+
+        let many: Vec<PathBuf> = (0..7).map(|i| PathBuf::from(format!("/f{}", i))).collect();
+        let text = question(&many);
+        assert!(text.starts_with("Securely erase these 7 items?"));
+        assert!(text.contains("/f4\nand 2 more"));
+        assert!(!text.contains("/f5"));
+    }
+}
@@ -0,0 +1,252 @@
+//! job ids for wipes, and their status, progress and outcome while the
+//! process runs
+//!
+//! every wipe is registered as a job, either by `register` ahead of time
+//! (for callers that want the id before the wipe starts, e.g. to poll it from
+//! another thread) or when it starts. progress is recorded against the target
+//! being wiped, which only one wipe in the process can hold at a time (see
+//! the lock module). finished jobs are kept so their outcome can be fetched
+//! after the fact, up to `KEPT` of them, after which the oldest are forgotten
+
+use crate::{Result, WipeReport};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// finished jobs remembered at most
+const KEPT: usize = 1024;
+
+/// jobs of this process, oldest first
+static JOBS: Mutex<Registry> = Mutex::new(Registry {
+    next_id: 1,
+    jobs: Vec::new(),
+});
+
+/// identifies one wipe within the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JobId(pub u64);
+
+impl std::fmt::Display for JobId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// where a job stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// registered, the wipe hasn't started
+    Queued,
+    /// being wiped
+    Running,
+    /// wiped; `result` has the report
+    Succeeded,
+    /// the wipe failed; `result` has the error
+    Failed,
+}
+
+impl JobStatus {
+    /// lowercase name, as used in the API
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// how far the overwrite passes of a job have got
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobProgress {
+    /// current pass, starting at 1, or 0 before the first pass
+    pub pass: usize,
+
+    /// number of passes the standard performs
+    pub passes: usize,
+
+    /// bytes written so far in the current pass
+    pub bytes_written: u64,
+
+    /// size of the target, i.e. the bytes written by each pass
+    pub bytes_per_pass: u64,
+}
+
+impl JobProgress {
+    /// bytes written across all passes so far
+    pub fn total_written(&self) -> u64 {
+        (self.pass.saturating_sub(1) as u64) * self.bytes_per_pass + self.bytes_written
+    }
+
+    /// bytes all passes together write
+    pub fn total_bytes(&self) -> u64 {
+        self.passes as u64 * self.bytes_per_pass
+    }
+
+    /// fraction of the passes that is done, from 0.0 to 1.0
+    pub fn fraction(&self) -> f64 {
+        match self.total_bytes() {
+            0 => 0.0,
+            total => self.total_written() as f64 / total as f64,
+        }
+    }
+}
+
+struct Registry {
+    next_id: u64,
+    jobs: Vec<Job>,
+}
+
+struct Job {
+    id: JobId,
+    /// target the progress of the running wipe is recorded against
+    target: PathBuf,
+    status: JobStatus,
+    progress: JobProgress,
+    outcome: Option<std::result::Result<WipeReport, String>>,
+}
+
+impl Registry {
+    fn find(&mut self, id: JobId) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+}
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    JOBS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// registers a wipe of `path` that hasn't started yet
+///
+/// hand the id to [`Shredder::with_job`](crate::Shredder::with_job) so the
+/// wipe runs under it
+///
+/// # Arguments
+/// * `path` - File or device that will be wiped
+///
+/// # Returns
+/// * `JobId` - Id to query the job by
+pub fn register(path: &Path) -> JobId {
+    let mut registry = registry();
+    let id = JobId(registry.next_id);
+    registry.next_id += 1;
+    registry.jobs.push(Job {
+        id,
+        target: path.to_path_buf(),
+        status: JobStatus::Queued,
+        progress: JobProgress::default(),
+        outcome: None,
+    });
+    id
+}
+
+/// status of a job, or None if there is no such job (any more)
+pub fn status(id: JobId) -> Option<JobStatus> {
+    registry().find(id).map(|job| job.status)
+}
+
+/// progress of a job's overwrite passes, or None if there is no such job
+///
+/// a finished job keeps the progress it last made
+pub fn progress(id: JobId) -> Option<JobProgress> {
+    registry().find(id).map(|job| job.progress)
+}
+
+/// outcome of a finished job: its report, or why it failed
+///
+/// # Returns
+/// * `Option<Result<WipeReport, String>>` - None while the job is queued or
+///   running, or if there is no such job
+pub fn result(id: JobId) -> Option<std::result::Result<WipeReport, String>> {
+    registry().find(id).and_then(|job| job.outcome.clone())
+}
+
+/// marks a job as running on `target`, the path the wipe opens
+pub(crate) fn start(id: JobId, target: &Path) {
+    if let Some(job) = registry().find(id) {
+        job.status = JobStatus::Running;
+        job.target = target.to_path_buf();
+    }
+}
+
+/// records the progress of the running job wiping `target`
+pub(crate) fn record_progress(target: &Path, progress: JobProgress) {
+    let mut registry = registry();
+    if let Some(job) = registry
+        .jobs
+        .iter_mut()
+        .rev()
+        .find(|job| job.status == JobStatus::Running && job.target == target)
+    {
+        job.progress = progress;
+    }
+}
+
+/// records how a job ended, forgetting the oldest finished jobs beyond `KEPT`
+pub(crate) fn finish(id: JobId, result: &Result<WipeReport>) {
+    let mut registry = registry();
+    if let Some(job) = registry.find(id) {
+        let (status, outcome) = match result {
+            Ok(report) => (JobStatus::Succeeded, Ok(report.clone())),
+            Err(e) => (JobStatus::Failed, Err(e.to_string())),
+        };
+        job.status = status;
+        job.outcome = Some(outcome);
+    }
+
+    let finished = |job: &Job| matches!(job.status, JobStatus::Succeeded | JobStatus::Failed);
+    let mut excess = registry
+        .jobs
+        .iter()
+        .filter(|job| finished(job))
+        .count()
+        .saturating_sub(KEPT);
+    registry.jobs.retain(|job| {
+        let forget = excess > 0 && finished(job);
+        if forget {
+            excess -= 1;
+        }
+        !forget
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let target = Path::new("/jobs-test/target");
+        let id = register(target);
+        assert_eq!(status(id), Some(JobStatus::Queued));
+        assert!(result(id).is_none());
+
+        start(id, target);
+        let halfway = JobProgress {
+            pass: 2,
+            passes: 3,
+            bytes_written: 50,
+            bytes_per_pass: 100,
+        };
+        record_progress(target, halfway);
+        assert_eq!(status(id), Some(JobStatus::Running));
+        assert_eq!(progress(id), Some(halfway));
+        assert_eq!(halfway.fraction(), 0.5);
+
+        let mut report = WipeReport::new(target.to_path_buf());
+        report.record_pass(100);
+        finish(id, &Ok(report.clone()));
+        assert_eq!(status(id), Some(JobStatus::Succeeded));
+        assert_eq!(result(id), Some(Ok(report)));
+
+        let failed = register(target);
+        start(failed, target);
+        finish(failed, &Err(crate::WipeError::TargetBusy(target.into())));
+        assert_eq!(status(failed), Some(JobStatus::Failed));
+        assert!(matches!(result(failed), Some(Err(e)) if e.contains("jobs-test")));
+        assert_ne!(id, failed);
+        assert!(status(JobId(u64::MAX)).is_none());
+    }
+}
@@ -1,17 +1,86 @@
+#[cfg(feature = "api")]
+pub mod api; // HTTP API for submitting and tracking wipes
+pub mod audit; // start/finish/failure records in syslog or the windows event log
+#[cfg(target_os = "macos")]
+mod authorization; // starting the elevated helper through Authorization Services
+pub mod bench; // measuring the best buffer size for a disk
+#[cfg(any(target_os = "openbsd", target_os = "netbsd", test))]
+mod bsd; // disklabels, mounts and software volumes (softraid, cgd, RAIDframe) on OpenBSD and NetBSD
+mod buffer; // reusable write buffers and adapting the write size during a wipe
+pub mod builder; // shredders built from settings checked to go together
+mod checkpoint; // resuming device wipes near where a power loss or crash stopped them
+pub mod config; // configuration file
+mod coredump; // no core dumps while wipes run
+#[cfg(all(unix, feature = "dbus"))]
+pub mod dbus; // org.shredder.Manager service on the session bus
+pub mod device; // block device helpers (mount state, unmounting, volume locks)
+pub mod elevate; // relaunching shred with administrator rights (windows UAC)
+pub mod estimate; // bytes written and time taken by a wipe, before running it
+pub mod events; // typed events of a wipe, for consuming over a channel
+mod extents; // shrinking and releasing a wiped file before it is unlinked
+mod fat; // overwriting the directory entries deleted files leave on FAT/exFAT
+pub mod filesystem; // mount point and filesystem type detection
+pub mod handles; // other processes holding the target open
+mod hash; // SHA-256 of targets before they are wiped
+pub mod helper; // elevated helper process for secure erase and TRIM
+pub mod integrate; // "Shred with shredder" entries in file manager context menus
+pub mod jobs; // job ids of wipes, with their status, progress and outcome
+pub mod locations; // recycle bin, trash and other places deleted data lingers
+mod lock; // one wipe per target at a time, across threads and processes
+#[cfg(unix)]
+mod mmap; // memory-mapped overwrites of small files
+pub mod notify; // webhook and mail notifications when wipes finish
+#[cfg(windows)]
+mod ntfs;
+pub mod partitions; // MBR and GPT partition tables read from the device
 pub mod patterns; // contains wiping patterns (Zeros, Ones, Random)
+pub mod policy; // system-wide minimum standards, banned paths and required reports
+pub mod polkit; // polkit action for starting the elevated helper (linux)
+pub mod power; // keeping the machine awake during wipes
+pub mod presets; // bundles of application traces (browser caches, shell history, ...)
+pub mod progress; // progress reporting during overwrite passes
+pub mod protection; // denylist of paths that must never be wiped
+pub mod quirks; // drive models whose hardware erase or TRIM is known to be broken
+mod remnants; // looking for copies of wiped data on the volume afterwards
+pub mod remote; // wiping on other machines over ssh
+pub mod report; // per-wipe outcome and limitations
+mod sandbox; // capabilities and a seccomp filter for the wiping thread (linux)
+pub mod schedule; // cron-style schedules for daemon jobs
 mod secure_erase;
+pub mod signatures; // filesystem, RAID and LVM signatures, found and erased like wipefs
+pub mod snapshots; // btrfs/ZFS/APFS snapshot and shadow copy detection
+mod spans; // file, pass and chunk spans in the log
 pub mod standards; // contains wiping standards (DoD, NIST, etc.)
 pub mod storage; // storage device type detection and handling
+pub mod swap; // swap partition and swapfile wiping
+pub mod systemd; // unit files for the daemon and scheduled jobs
+pub mod target; // what a wipe overwrites: files, devices, free space, open handles
+pub mod trash_put; // trash-put that shreds what it trashes after a grace period
 mod trim;
-
-use log::{debug, info, warn};
-use patterns::WipePattern;
+pub mod verify; // checking that media holds an expected pattern, without wiping
+
+use buffer::{next_write, BufferTuner, PassBuffers, PassSource, ThreadBuffers};
+use events::WipeEvent;
+use handles::OpenFilePolicy;
+use jobs::{JobId, JobProgress, JobStatus};
+use log::{debug, info, trace, warn};
+use patterns::{Keystream, SeedSource, WipePattern};
+use policy::Policy;
+use progress::{Progress, ProgressCallback};
+use protection::ProtectedPaths;
+use quirks::{Quirk, Quirks};
+use report::{MultiWipeReport, NistAction, SampledVerification, WipeReport};
+use snapshots::SnapshotPolicy;
+use spans::Span;
 use standards::{SanitizationMethod, VerificationLevel, WipeStandard};
-use std::fs::{File, OpenOptions};
+use std::fs::File;
 use std::io::{self, Read, Seek, SeekFrom, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use storage::StorageType;
+use target::{BlockDevice, RegularFile, WipeTarget};
 use thiserror::Error;
+use verify::{SamplePlacement, VerificationReport};
+use zeroize::Zeroizing;
 
 /// represents various errors that can occur during secure deletion
 #[derive(Error, Debug)]
@@ -31,11 +100,162 @@ pub enum WipeError {
     /// parsing error for numeric values
     #[error("Parse error: {0}")]
     Parse(#[from] std::num::ParseIntError),
+
+    /// the target is on the protected-path denylist
+    #[error(
+        "Refusing to wipe {}: protected by denylist entry {}",
+        path.display(),
+        entry.display()
+    )]
+    ProtectedPath { path: PathBuf, entry: PathBuf },
+
+    /// the target device holds an operating system
+    #[error(
+        "Refusing to wipe {}: it holds an operating system (see --allow-system-disk)",
+        .0.display()
+    )]
+    SystemDisk(PathBuf),
+
+    /// a partition device reaches beyond its partition in the disk's table
+    #[error("Refusing to wipe {}: {reason}", device.display())]
+    PartitionBounds { device: PathBuf, reason: String },
+
+    /// the system policy forbids the wipe as configured
+    #[error("Refused by policy: {0}")]
+    PolicyViolation(String),
+
+    /// a multi-pass legacy standard on storage that remaps writes
+    #[error(
+        "Refusing {standard} on {media}: its {passes} passes only add wear and can't reach remapped blocks (see --force-multipass)"
+    )]
+    MultipassOnWearLeveledStorage {
+        standard: String,
+        passes: usize,
+        media: &'static str,
+    },
+
+    /// the configuration file could not be parsed
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    /// the target has the immutable or append-only attribute set
+    #[error("Cannot overwrite {}: file is marked {flags}", path.display())]
+    ProtectedFlags {
+        path: PathBuf,
+        flags: filesystem::ProtectionFlags,
+    },
+
+    /// the target lives on a filesystem mounted read-only
+    #[error("Read-only filesystem: {} is mounted read-only", .0.display())]
+    ReadOnlyFilesystem(PathBuf),
+
+    /// the device (or one of its partitions) has mounted filesystems
+    #[error(
+        "Device {} is in use: mounted at {}",
+        device.display(),
+        mount_points.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    DeviceMounted {
+        device: PathBuf,
+        mount_points: Vec<PathBuf>,
+    },
+
+    /// a volume on the target device could not be locked for exclusive access
+    #[error("Unable to lock volume {volume}: {source}")]
+    VolumeLock {
+        volume: String,
+        source: std::io::Error,
+    },
+
+    /// other processes have the target open
+    #[error("Target is open in other processes: {}", .0.join(", "))]
+    FileInUse(Vec<String>),
+
+    /// snapshots still reference the target, so overwriting it would not remove the data
+    #[error("Target data survives in snapshots: {}", .0.join(", "))]
+    SnapshotsPresent(Vec<String>),
+
+    /// a wipe report could not be written or parsed
+    #[error("Invalid report: {0}")]
+    Report(String),
+
+    /// a webhook or mail notification could not be delivered
+    #[error("Notification failed: {0}")]
+    Notification(String),
+
+    /// another wipe, in this process or another shredder, has the target
+    #[error("{} is being wiped by another shredder", .0.display())]
+    TargetBusy(PathBuf),
+
+    /// the elevated helper couldn't be started or stopped answering
+    #[error("Elevated helper failed: {0}")]
+    Helper(String),
+
+    /// the D-Bus connection failed or the bus answered unexpectedly
+    #[error("D-Bus error: {0}")]
+    Dbus(String),
+
+    /// the settings a shredder was built with don't go together
+    #[error("Invalid settings: {0}")]
+    Build(#[from] builder::BuildError),
+
+    /// an overwrite pass failed; says which pass, what it was doing and how far
+    /// into the target it got. syncs and removal cover the whole target and
+    /// report offset 0
+    #[error("Pass {pass} failed while {phase} at offset {offset}: {source}")]
+    PassFailed {
+        pass: usize,
+        phase: WipePhase,
+        offset: u64,
+        source: Box<WipeError>,
+    },
+}
+
+impl WipeError {
+    /// the error underneath any pass context
+    pub fn root_cause(&self) -> &WipeError {
+        match self {
+            WipeError::PassFailed { source, .. } => source.root_cause(),
+            e => e,
+        }
+    }
+}
+
+/// what a pass was doing when it failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WipePhase {
+    /// writing the pattern (or reading a chunk to see if it already holds it)
+    Write,
+    /// reading back what was written
+    Verify,
+    /// flushing the writes to the media
+    Sync,
+    /// unlinking the wiped file
+    Remove,
+}
+
+impl std::fmt::Display for WipePhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            WipePhase::Write => "writing",
+            WipePhase::Verify => "verifying",
+            WipePhase::Sync => "syncing",
+            WipePhase::Remove => "removing the target",
+        })
+    }
 }
 
 /// type alias for Result with our custom WipeError
 pub type Result<T> = std::result::Result<T, WipeError>;
 
+/// files up to this size are overwritten through a memory mapping; above it
+/// the write loop is as fast
+#[cfg(unix)]
+const MMAP_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// smallest region a worker thread is given; smaller targets use fewer threads
+const MIN_REGION: u64 = 16 * 1024 * 1024;
+
 /// main struct for secure file deletion operations
 pub struct Shredder {
     /// the wiping standard to use (e.g., NIST 800-88, DoD 5220.22-M)
@@ -46,6 +266,103 @@ pub struct Shredder {
 
     /// size of the buffer used for writing operations (default: 1MB)
     buffer_size: usize,
+
+    /// let the write size drift from buffer_size as throughput is measured
+    adaptive_buffer: bool,
+
+    /// fill files up to MMAP_THRESHOLD through a memory mapping (unix)
+    mmap: bool,
+
+    /// worker threads overwriting separate regions of a large target
+    threads: usize,
+
+    /// open the target with O_DSYNC (write-through on windows)
+    sync_writes: bool,
+
+    /// truncate wiped files to random smaller sizes before releasing them
+    obfuscate_size: bool,
+
+    /// read each chunk first and leave it alone if it already holds the pattern
+    skip_matching: bool,
+
+    /// record the SHA-256 of the target's contents in the report before wiping
+    hash_contents: bool,
+
+    /// after wiping, read the volume back looking for sectors of the target
+    scan_remnants: bool,
+
+    /// on btrfs, move the data into a NOCOW copy before overwriting
+    nocow_copy: bool,
+
+    /// how to react to snapshots that may still hold the target's data
+    snapshot_policy: SnapshotPolicy,
+
+    /// unmount filesystems on a target device instead of refusing to wipe it
+    unmount: bool,
+
+    /// paths that are refused before anything else happens
+    protected_paths: ProtectedPaths,
+
+    /// allow wiping a device that holds an operating system
+    allow_system_disk: bool,
+
+    /// run multi-pass legacy standards on SSDs and flash instead of refusing
+    force_multipass: bool,
+
+    /// drive models whose hardware erase or TRIM is skipped
+    quirks: Quirks,
+
+    /// requirements of the system policy, and any stricter ones added
+    policy: Policy,
+
+    /// how to react when other processes have the target open
+    open_file_policy: OpenFilePolicy,
+
+    /// share of the blocks read by Basic verification
+    sample_rate: f64,
+
+    /// where the blocks read by Basic verification are taken from
+    sample_placement: SamplePlacement,
+
+    /// shred executable that repeats Enhanced verification in its own process
+    verifier_process: Option<PathBuf>,
+
+    /// elevated process that runs secure erase and TRIM for an unprivileged one
+    helper: Option<helper::Helper>,
+
+    /// wipe on a thread of its own, confined once the target is open
+    sandbox: bool,
+
+    /// erase filesystem, RAID and LVM signatures of a device before its passes
+    erase_signatures: bool,
+
+    /// bytes overwritten at the start and end of a device and of each of its
+    /// partitions, instead of all of it
+    quick: Option<u64>,
+
+    /// block size of the storage; writes are sized and placed in whole blocks
+    block_size: usize,
+
+    /// clear immutable/append-only flags instead of refusing the target
+    clear_protection_flags: bool,
+
+    /// clear read-only/hidden/system attributes before wiping (windows)
+    clear_attributes: bool,
+
+    /// where random passes get their keystream seeds
+    seeds: SeedSource,
+
+    /// called as overwrite passes progress
+    progress: Option<ProgressCallback>,
+
+    /// where events go during `wipe_with_events`
+    events: Option<std::sync::mpsc::Sender<WipeEvent>>,
+
+    /// job registered ahead of the wipe, see `with_job`
+    job: Option<JobId>,
+
+    /// where device wipes save checkpoints to resume from, if anywhere
+    checkpoint_dir: Option<PathBuf>,
 }
 
 impl Shredder {
@@ -55,13 +372,56 @@ impl Shredder {
     /// * `standard` - The wiping standard to use
     /// * `storage_type` - The type of storage device being written to
     pub fn new(standard: WipeStandard, storage_type: StorageType) -> Self {
+        let threads = default_threads(&storage_type);
+        let resolved = standard.resolve(&storage_type);
+        if matches!(standard, WipeStandard::Auto(_)) {
+            info!("Automatic standard picked {} for the storage", resolved);
+        }
         Self {
-            standard,
+            standard: resolved,
             storage_type,
             buffer_size: 1024 * 1024, // 1MB default for optimal I/O performance
+            adaptive_buffer: true,
+            mmap: true,
+            threads,
+            sync_writes: false,
+            obfuscate_size: false,
+            skip_matching: false,
+            hash_contents: false,
+            scan_remnants: false,
+            nocow_copy: false,
+            snapshot_policy: SnapshotPolicy::default(),
+            unmount: false,
+            protected_paths: ProtectedPaths::default(),
+            allow_system_disk: false,
+            force_multipass: false,
+            quirks: Quirks::builtin(),
+            policy: Policy::system(),
+            open_file_policy: OpenFilePolicy::default(),
+            sample_rate: 0.01,
+            sample_placement: SamplePlacement::default(),
+            verifier_process: None,
+            helper: None,
+            sandbox: false,
+            erase_signatures: false,
+            quick: None,
+            block_size: 512,
+            clear_protection_flags: false,
+            clear_attributes: true,
+            seeds: SeedSource::System,
+            progress: None,
+            events: None,
+            job: None,
+            checkpoint_dir: None,
         }
     }
 
+    /// starts building a shredder whose settings are checked before it is
+    /// created, e.g. `Shredder::builder().standard(..).storage(..).build()?`
+    pub fn builder() -> builder::ShredderBuilder {
+        builder::ShredderBuilder::default()
+    }
+
     /// securely wipes a file using the configured standard
     ///
     /// # Arguments
@@ -70,368 +430,2655 @@ impl Shredder {
     /// # Returns
     /// * `Result<()>` - Success or error status
     pub fn wipe<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        match &self.standard {
-            WipeStandard::Modern(config) => self.perform_modern_wipe(path.as_ref(), config),
-            WipeStandard::Legacy(config) => self.perform_legacy_wipe(path.as_ref(), config),
-            WipeStandard::Custom(config) => self.perform_custom_wipe(path.as_ref(), config),
-        }
+        self.wipe_with_report(path).map(|_| ())
     }
 
-    /// implements NIST 800-88 compliant wiping
-    fn perform_modern_wipe<P: AsRef<Path>>(
-        &self,
-        path: P,
-        config: &standards::Nist80088Config,
-    ) -> Result<()> {
+    /// securely wipes a file and reports what was done
+    ///
+    /// # Arguments
+    /// * `path` - Path to the file to be wiped
+    ///
+    /// # Returns
+    /// * `Result<WipeReport>` - Passes performed and any limitations of the wipe
+    pub fn wipe_with_report<P: AsRef<Path>>(&self, path: P) -> Result<WipeReport> {
         let path = path.as_ref();
-        info!("Starting modern wipe for: {}", path.display());
-
-        // open file with write permissions
-        let mut file = OpenOptions::new().write(true).read(true).open(path)?;
-
-        // Get file size for verification
-        let file_size = file.metadata()?.len();
-        debug!("File size: {} bytes", file_size);
+        let job = self
+            .job
+            .filter(|&job| jobs::status(job) == Some(JobStatus::Queued))
+            .unwrap_or_else(|| jobs::register(path));
+        jobs::start(job, &filesystem::extended_length_path(path));
+        let started = std::time::Instant::now();
+        let result = self.wipe_job(path, job).map(|mut report| {
+            report.standard = Some(self.standard.to_string());
+            report.duration_secs = Some(started.elapsed().as_secs_f64());
+            report
+        });
+        jobs::finish(job, &result);
+        result
+    }
 
-        // create buffer sized according to storage characteristics
-        let buffer_size = self.calculate_optimal_buffer_size(file_size);
-        let mut buffer = vec![0u8; buffer_size];
+    /// the wipe of `wipe_with_report`, run as `job`
+    fn wipe_job(&self, path: &Path, job: JobId) -> Result<WipeReport> {
+        let _no_core_dumps = coredump::CoreDumpGuard::new();
+        let mut report = WipeReport::new(path.to_path_buf());
+        report.job = Some(job);
+        // \\?\ form on windows, so long paths and trailing dots/spaces can be opened
+        let path = &filesystem::extended_length_path(path);
+        let _span = Span::wipe(&report.path);
+
+        // before the checks, so they hold for the passes
+        let _lock = lock::TargetLock::acquire(path)?;
+        if self.quick.is_some() && !device::is_block_device(path) {
+            return Err(WipeError::UnsupportedOperation(
+                "quick wipes are for devices; files are always overwritten in full".to_string(),
+            ));
+        }
+        self.preflight_checks(path, &mut report)?;
 
-        // if SSD/Flash, handle wear leveling
-        if self.storage_type.requires_wear_leveling_handling() {
-            debug!("Storage device requires wear leveling handling");
-            self.handle_wear_leveling(&mut file)?;
+        if self.hash_contents {
+            let sha256 = hash::sha256_file(path)?;
+            info!("SHA-256 of {} before wiping: {}", path.display(), sha256);
+            report.sha256 = Some(sha256);
         }
 
-        match config.method {
-            SanitizationMethod::Clear => {
-                // single pass of random data for Clear method
-                debug!("Performing Clear operation with random data");
-                WipePattern::Random.fill_buffer(&mut buffer);
-                self.overwrite_file_contents(&mut file, &buffer, file_size)?;
+        // sampled before the passes, looked for once they're done
+        let remnant_scan = if self.scan_remnants {
+            self.prepare_remnant_scan(path)
+        } else {
+            None
+        };
+
+        // windows keeps writing to mounted volumes, so lock them for the whole wipe
+        #[cfg(windows)]
+        let _volume_lock = if device::is_block_device(path) {
+            Some(device::lock_volumes(path)?)
+        } else {
+            None
+        };
+
+        if let Some(region) = self.quick {
+            self.perform_quick_wipe(&BlockDevice::new(path), region, &mut report)?;
+        } else if device::is_block_device(path) {
+            if self.erase_signatures {
+                self.erase_device_signatures(path)?;
             }
-            SanitizationMethod::Purge => {
-                // for Purge, try hardware-based secure erase first
-                if self.storage_type.supports_secure_erase() {
-                    debug!("Attempting hardware-based secure erase");
-                    if let Err(e) = self.perform_hardware_secure_erase(path) {
-                        warn!(
-                            "Hardware secure erase failed: {}, falling back to software method",
-                            e
-                        );
-                        self.perform_purge_overwrite(&mut file, &mut buffer, file_size)?;
-                    }
-                } else {
-                    debug!("No hardware secure erase support, using software method");
-                    self.perform_purge_overwrite(&mut file, &mut buffer, file_size)?;
-                }
+            self.perform_wipe(&BlockDevice::new(path), &mut report)?;
+        } else {
+            self.perform_wipe(&RegularFile::new(path), &mut report)?;
+            if report.filesystem.as_deref().is_some_and(fat::is_fat) {
+                self.scrub_directory_entries(path, &mut report);
             }
         }
 
-        // verify wiping if required
-        if config.verify_level != VerificationLevel::None {
-            debug!(
-                "Performing verification at level: {:?}",
-                config.verify_level
-            );
-            self.verify_wiping(&mut file, &buffer, config.verify_level)?;
+        if let Some((signature, volume)) = remnant_scan {
+            self.scan_for_remnants(&signature, &volume, &mut report);
         }
 
-        // ensure all writes are synced to disk
-        file.sync_all()?;
-        debug!("File contents synced to disk");
+        Ok(report)
+    }
 
-        // drop file handle before removal
-        drop(file);
+    /// overwrites any kind of target with the configured standard, e.g. the
+    /// free space of a volume or a handle opened elsewhere
+    ///
+    /// the denylist and the policy's banned paths apply as they do to
+    /// `wipe_with_report`; its other checks (open handles, mounts, snapshots)
+    /// are not run, and the target is overwritten as it is
+    ///
+    /// # Arguments
+    /// * `target` - What to overwrite
+    ///
+    /// # Returns
+    /// * `Result<WipeReport>` - Passes performed and any limitations of the wipe
+    pub fn wipe_target(&self, target: &dyn WipeTarget) -> Result<WipeReport> {
+        let _no_core_dumps = coredump::CoreDumpGuard::new();
+        let mut report = WipeReport::new(target.path().to_path_buf());
+        let _span = Span::wipe(&report.path);
+        let _lock = lock::TargetLock::acquire(target.path())?;
+        self.protected_paths.check(target.path())?;
+        self.policy.check_path(target.path())?;
+        self.perform_wipe(target, &mut report)?;
+        Ok(report)
+    }
 
-        // remove file after successful wiping
-        std::fs::remove_file(path)?;
-        info!("File successfully wiped and removed");
+    /// erases the filesystem, RAID and LVM signatures of a device, wipefs-style,
+    /// after the same checks as a wipe (denylist, policy, system disk, mounts)
+    ///
+    /// only the magic bytes of each superblock and its backups are zeroed, so
+    /// nothing on the device is recognized or mounted any more while its data
+    /// stays in place; see `signatures`
+    ///
+    /// # Arguments
+    /// * `path` - Device or disk image
+    ///
+    /// # Returns
+    /// * `Result<Vec<Signature>>` - Signatures that were erased
+    pub fn erase_signatures<P: AsRef<Path>>(&self, path: P) -> Result<Vec<signatures::Signature>> {
+        let path = &filesystem::extended_length_path(path.as_ref());
+        let _lock = lock::TargetLock::acquire(path)?;
+        self.preflight_checks(path, &mut WipeReport::new(path.to_path_buf()))?;
+        #[cfg(windows)]
+        let _volume_lock = if device::is_block_device(path) {
+            Some(device::lock_volumes(path)?)
+        } else {
+            None
+        };
+        self.erase_device_signatures(path)
+    }
 
-        Ok(())
+    /// destroys the partition table of a disk, MBR and both GPT copies, after
+    /// the same checks as a wipe; see `partitions::destroy`
+    ///
+    /// # Arguments
+    /// * `path` - Whole disk or disk image
+    ///
+    /// # Returns
+    /// * `Result<Vec<(u64, u64)>>` - Byte ranges zeroed, as (start, end) pairs
+    pub fn destroy_partition_table<P: AsRef<Path>>(&self, path: P) -> Result<Vec<(u64, u64)>> {
+        let path = &filesystem::extended_length_path(path.as_ref());
+        let _lock = lock::TargetLock::acquire(path)?;
+        self.preflight_checks(path, &mut WipeReport::new(path.to_path_buf()))?;
+        #[cfg(windows)]
+        let _volume_lock = if device::is_block_device(path) {
+            Some(device::lock_volumes(path)?)
+        } else {
+            None
+        };
+        let zeroed = partitions::destroy(path)?;
+        info!("Destroyed the partition table of {}", path.display());
+        Ok(zeroed)
     }
 
-    /// implements legacy standard wiping (DoD, Gutmann, etc.)
-    fn perform_legacy_wipe<P: AsRef<Path>>(
-        &self,
-        path: P,
-        config: &standards::LegacyConfig,
-    ) -> Result<()> {
-        let path = path.as_ref();
-        info!("Starting legacy wipe using standard: {:?}", config.standard);
+    fn erase_device_signatures(&self, path: &Path) -> Result<Vec<signatures::Signature>> {
+        let erased = signatures::erase(path)?;
+        if erased.is_empty() {
+            info!("No signatures found on {}", path.display());
+        } else {
+            info!("Erased {} signature(s) on {}", erased.len(), path.display());
+        }
+        Ok(erased)
+    }
 
-        // get wiping patterns for the selected standard
-        let patterns = config.standard.get_patterns();
-        debug!("Using {} pass wiping pattern", patterns.len());
+    /// securely wipes a file on a thread of its own, sending what happens to
+    /// the returned channel
+    ///
+    /// an alternative to `with_progress` for callers that would rather receive
+    /// than be called back. the channel ends with `WipeEvent::Completed` or
+    /// `WipeEvent::Error` and is closed once the wipe is over. the shredder is
+    /// moved to the wiping thread; a progress callback set on it is still called
+    ///
+    /// # Arguments
+    /// * `path` - Path to the file to be wiped
+    ///
+    /// # Returns
+    /// * `Receiver<WipeEvent>` - Passes, progress and verification, then the outcome
+    pub fn wipe_with_events<P: AsRef<Path>>(
+        mut self,
+        path: P,
+    ) -> std::sync::mpsc::Receiver<WipeEvent> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.events = Some(sender.clone());
+        let path = path.as_ref().to_path_buf();
+        std::thread::spawn(move || {
+            let event = match self.wipe_with_report(&path) {
+                Ok(report) => WipeEvent::Completed(Box::new(report)),
+                Err(e) => WipeEvent::Error(e),
+            };
+            // nobody listening is not an error
+            let _ = sender.send(event);
+        });
+        receiver
+    }
 
-        // open file with write permissions
-        let mut file = OpenOptions::new().write(true).read(true).open(path)?;
+    /// securely wipes a file on a thread of its own, returning its job id at once
+    ///
+    /// poll [`jobs::status`], [`jobs::progress`] and [`jobs::result`] with the
+    /// id to follow the wipe and fetch its report once it's done
+    ///
+    /// # Arguments
+    /// * `path` - Path to the file to be wiped
+    ///
+    /// # Returns
+    /// * `JobId` - Id of the wipe, queued until the thread starts it
+    pub fn spawn<P: AsRef<Path>>(mut self, path: P) -> JobId {
+        let path = path.as_ref().to_path_buf();
+        let job = jobs::register(&path);
+        self.job = Some(job);
+        std::thread::spawn(move || self.wipe_with_report(&path));
+        job
+    }
 
-        let file_size = file.metadata()?.len();
-        let buffer_size = self.calculate_optimal_buffer_size(file_size);
-        let mut buffer = vec![0u8; buffer_size];
+    /// securely wipes a file, or every file below a directory
+    ///
+    /// directories are removed once their contents are wiped. symbolic links
+    /// are removed without touching what they point to. stops at the first
+    /// error, leaving the remaining entries in place
+    ///
+    /// # Arguments
+    /// * `path` - Path to the file or directory to be wiped
+    ///
+    /// # Returns
+    /// * `Result<Vec<WipeReport>>` - One report per wiped file
+    pub fn wipe_tree<P: AsRef<Path>>(&self, path: P) -> Result<Vec<WipeReport>> {
+        let path = path.as_ref();
+        let mut reports = Vec::new();
+
+        let file_type = std::fs::symlink_metadata(path)?.file_type();
+        if file_type.is_symlink() {
+            std::fs::remove_file(path)?;
+        } else if file_type.is_dir() {
+            self.protected_paths.check(path)?;
+            for entry in std::fs::read_dir(path)? {
+                reports.extend(self.wipe_tree(entry?.path())?);
+            }
+            std::fs::remove_dir(path)?;
+        } else {
+            reports.push(self.wipe_with_report(path)?);
+        }
 
-        // perform each pass
-        for (i, pattern) in patterns.iter().enumerate() {
-            debug!("Starting pass {}/{}", i + 1, patterns.len());
-            pattern.fill_buffer(&mut buffer);
-            self.overwrite_file_contents(&mut file, &buffer, file_size)?;
+        Ok(reports)
+    }
 
-            // verify after each pass if requested
-            if config.extra_verification {
-                debug!("Performing verification after pass {}", i + 1);
-                self.verify_wiping(&mut file, &buffer, VerificationLevel::Basic)?;
-            }
+    /// securely wipes several files and directories, carrying on past the
+    /// ones that fail
+    ///
+    /// like `wipe_tree` for each path, except that a file that can't be wiped
+    /// (locked, missing, refused by a safety check, ...) is recorded and the
+    /// rest are still wiped. directories holding a failed entry are left in place
+    ///
+    /// # Arguments
+    /// * `paths` - Files and directories to wipe
+    ///
+    /// # Returns
+    /// * `MultiWipeReport` - Reports of the wiped files and the errors of the failed ones
+    pub fn wipe_many<I, P>(&self, paths: I) -> MultiWipeReport
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut outcome = MultiWipeReport::default();
+        for path in paths {
+            self.wipe_tree_into(path.as_ref(), &mut outcome);
         }
+        outcome
+    }
 
-        // final verification if requested
-        if config.extra_verification {
-            debug!("Performing final full verification");
-            self.verify_wiping(&mut file, &buffer, VerificationLevel::Full)?;
+    /// `wipe_tree` that records failures in `outcome` instead of stopping at them
+    fn wipe_tree_into(&self, path: &Path, outcome: &mut MultiWipeReport) {
+        let failures = outcome.failed.len();
+        let result = std::fs::symlink_metadata(path)
+            .map_err(WipeError::from)
+            .and_then(|metadata| {
+                let file_type = metadata.file_type();
+                if file_type.is_symlink() {
+                    std::fs::remove_file(path)?;
+                } else if file_type.is_dir() {
+                    self.protected_paths.check(path)?;
+                    for entry in std::fs::read_dir(path)? {
+                        self.wipe_tree_into(&entry?.path(), outcome);
+                    }
+                    if outcome.failed.len() == failures {
+                        std::fs::remove_dir(path)?;
+                    }
+                } else {
+                    outcome.wiped.push(self.wipe_with_report(path)?);
+                }
+                Ok(())
+            });
+        if let Err(e) = result {
+            outcome.failed.push((path.to_path_buf(), e));
         }
+    }
 
-        // sync and remove file
-        file.sync_all()?;
-        drop(file);
-        std::fs::remove_file(path)?;
-        info!("Legacy wipe completed successfully");
+    /// samples the target and finds the volume to search afterwards; None, with
+    /// a warning, if there's nothing distinctive to sample or no volume to read
+    fn prepare_remnant_scan(&self, path: &Path) -> Option<(remnants::Signature, PathBuf)> {
+        let volume = if device::is_block_device(path) {
+            Some(path.to_path_buf())
+        } else {
+            filesystem::mount_info(path)
+                .ok()
+                .and_then(|mount| remnant_volume(&mount))
+        };
+        let Some(volume) = volume else {
+            warn!(
+                "Not scanning for remnants: no readable volume holds {}",
+                path.display()
+            );
+            return None;
+        };
+        match remnants::Signature::take(path) {
+            Ok(signature) if !signature.is_empty() => Some((signature, volume)),
+            Ok(_) => {
+                warn!(
+                    "Not scanning for remnants: {} has no distinctive sectors to look for",
+                    path.display()
+                );
+                None
+            }
+            Err(e) => {
+                warn!("Not scanning for remnants: {}", e);
+                None
+            }
+        }
+    }
 
-        Ok(())
+    /// reads the volume back and records the sectors that still begin like the
+    /// wiped data; a scan that can't be completed is only a warning
+    fn scan_for_remnants(
+        &self,
+        signature: &remnants::Signature,
+        volume: &Path,
+        report: &mut WipeReport,
+    ) {
+        info!(
+            "Scanning {} for remnants of the wiped data",
+            volume.display()
+        );
+        match signature.scan(volume) {
+            Ok(found) => {
+                report.remnants = Some(found.len() as u64);
+                if let Some(first) = found.first() {
+                    report.add_limitation(format!(
+                        "{} sector(s) of {} still begin like the wiped data, the first at offset {}; copies survive outside the overwritten blocks",
+                        found.len(),
+                        volume.display(),
+                        first
+                    ));
+                }
+            }
+            Err(e) => warn!("Unable to scan {} for remnants: {}", volume.display(), e),
+        }
     }
 
-    /// implements custom wiping patterns
-    fn perform_custom_wipe<P: AsRef<Path>>(
+    /// checks that a file or device holds a pattern, without wiping it
+    ///
+    /// for auditing media wiped earlier, by this or another tool. nothing is
+    /// written and the protection checks don't apply. random data can't be
+    /// compared byte for byte, so it only has to look random
+    ///
+    /// # Arguments
+    /// * `path` - File or device to check
+    /// * `pattern` - Pattern the target should hold
+    /// * `level` - How much to read: Basic samples the share of the 4 KiB blocks
+    ///   set with `with_sampling` (always the first and the last), Sampled reads
+    ///   its share spread evenly, Full reads everything, and Enhanced
+    ///   reads everything twice, reporting the read with more mismatches.
+    ///   None reads nothing
+    ///
+    /// # Returns
+    /// * `Result<VerificationReport>` - Blocks read and the ones that didn't match;
+    ///   a mismatch is reported, not returned as an error
+    pub fn verify<P: AsRef<Path>>(
         &self,
         path: P,
-        config: &standards::WipeConfig,
-    ) -> Result<()> {
-        let path = path.as_ref();
-        info!("Starting custom wipe with {} passes", config.passes.len());
+        pattern: &WipePattern,
+        level: VerificationLevel,
+    ) -> Result<VerificationReport> {
+        let target = filesystem::extended_length_path(path.as_ref());
+        let mut report = match level {
+            VerificationLevel::None => {
+                verify::verify_pattern(&target, pattern, 0.0, SamplePlacement::Random)?
+            }
+            VerificationLevel::Basic => {
+                verify::verify_pattern(&target, pattern, self.sample_rate, self.sample_placement)?
+            }
+            // nothing was written here to hash, so the blocks are compared
+            VerificationLevel::Full | VerificationLevel::Hash => {
+                verify::verify_pattern(&target, pattern, 1.0, SamplePlacement::Random)?
+            }
+            VerificationLevel::Sampled(share) => {
+                verify::verify_pattern(&target, pattern, share, SamplePlacement::Stratified)?
+            }
+            VerificationLevel::Enhanced => {
+                let first = verify::verify_pattern(&target, pattern, 1.0, SamplePlacement::Random)?;
+                let second =
+                    verify::verify_pattern(&target, pattern, 1.0, SamplePlacement::Random)?;
+                if second.mismatched_blocks > first.mismatched_blocks {
+                    second
+                } else {
+                    first
+                }
+            }
+        };
+        report.path = path.as_ref().to_path_buf();
+        Ok(report)
+    }
 
-        let mut file = OpenOptions::new().write(true).read(true).open(path)?;
+    /// checks the target's environment before any data is touched
+    fn preflight_checks(&self, path: &Path, report: &mut WipeReport) -> Result<()> {
+        self.protected_paths.check(path)?;
+        self.policy.check_path(path)?;
+        if self.open_file_policy != OpenFilePolicy::Ignore {
+            self.check_open_handles(path, report)?;
+        }
 
-        let file_size = file.metadata()?.len();
-        let buffer_size = self.calculate_optimal_buffer_size(file_size);
-        let mut buffer = vec![0u8; buffer_size];
+        #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+        bsd::check_software_volume(path, report);
 
-        // apply each custom pattern
-        for (i, pattern) in config.passes.iter().enumerate() {
-            debug!("Starting custom pass {}/{}", i + 1, config.passes.len());
-            pattern.fill_buffer(&mut buffer);
-            self.overwrite_file_contents(&mut file, &buffer, file_size)?;
+        // filesystem-level checks don't apply to raw devices
+        if device::is_block_device(path) {
+            self.check_system_disk(path)?;
+            self.check_device_unmounted(path)?;
+            return self.check_partition_bounds(path, report);
+        }
 
-            if config.verify_each_pass {
-                debug!("Verifying pass {}", i + 1);
-                self.verify_wiping(&mut file, &buffer, VerificationLevel::Full)?;
+        self.check_protection_flags(path)?;
+        if self.clear_attributes {
+            match filesystem::clear_file_attributes(path) {
+                Ok(true) => info!(
+                    "Cleared read-only/hidden/system attributes on {}",
+                    path.display()
+                ),
+                Ok(false) => {}
+                Err(e) => warn!(
+                    "Unable to clear file attributes of {}: {}",
+                    path.display(),
+                    e
+                ),
             }
         }
 
-        file.sync_all()?;
-        drop(file);
-        std::fs::remove_file(path)?;
-        info!("Custom wipe completed successfully");
+        match filesystem::mount_info(path) {
+            Ok(mount) => {
+                debug!(
+                    "Target is on {} filesystem mounted at {}",
+                    mount.fs_type,
+                    mount.mount_point.display()
+                );
+                // fail early rather than with EROFS halfway through a pass
+                if mount.read_only {
+                    return Err(WipeError::ReadOnlyFilesystem(mount.mount_point));
+                }
+                if mount.is_copy_on_write() {
+                    self.handle_copy_on_write(path, &mount, report);
+                }
+                if self.snapshot_policy != SnapshotPolicy::Ignore {
+                    self.check_snapshots(path, &mount, report)?;
+                }
+                #[cfg(windows)]
+                if mount.fs_type.eq_ignore_ascii_case("ntfs") {
+                    ntfs::check_encryption(path, &mount, report)?;
+                    // compressed/deduplicated data doesn't map 1:1 onto clusters
+                    if let Err(e) = ntfs::prepare_target(path, report) {
+                        warn!("Unable to prepare NTFS target: {}", e);
+                        report.add_limitation(format!(
+                            "NTFS compression or deduplication could not be undone ({}); \
+                             overwrite passes may not reach the original clusters.",
+                            e
+                        ));
+                    }
+                }
+                report.filesystem = Some(mount.fs_type);
+            }
+            Err(e) => debug!("Unable to inspect mount for {}: {}", path.display(), e),
+        }
 
         Ok(())
     }
 
-    /// overwrites file contents with provided buffer
-    fn overwrite_file_contents(
-        &self,
-        file: &mut File,
-        pattern: &[u8],
-        file_size: u64,
-    ) -> Result<()> {
-        // Create a buffer sized according to our buffer_size setting
-        let mut write_buffer = vec![0u8; self.buffer_size];
-
-        file.seek(SeekFrom::Start(0))?;
-        let mut written = 0u64;
-
-        while written < file_size {
-            // Fill write buffer with pattern
-            for chunk in write_buffer.chunks_mut(pattern.len()) {
-                let len = std::cmp::min(chunk.len(), pattern.len());
-                chunk[..len].copy_from_slice(&pattern[..len]);
+    /// overwrites the directory entries a wiped file left on FAT/exFAT, whose
+    /// long filename entries would still give its name away
+    fn scrub_directory_entries(&self, path: &Path, report: &mut WipeReport) {
+        let Some(dir) = path.parent() else {
+            return;
+        };
+        match fat::scrub_deleted_entries(dir) {
+            Ok(placeholders) => {
+                debug!(
+                    "Overwrote free directory entries in {} with {} placeholder(s)",
+                    dir.display(),
+                    placeholders
+                );
+                if report.filesystem.as_deref().is_some_and(fat::is_exfat) {
+                    report.add_limitation(
+                        "exFAT directory entries in runs of fewer than three can't be reused, \
+                         so part of the file's name may remain in its deleted entries."
+                            .to_string(),
+                    );
+                }
             }
-
-            let remaining = file_size - written;
-            let write_size = std::cmp::min(remaining as usize, write_buffer.len());
-
-            // Write and verify immediately
-            file.write_all(&write_buffer[..write_size])?;
-            file.flush()?;
-
-            // Verify this chunk
-            file.seek(SeekFrom::Start(written))?;
-            let mut verify_buffer = vec![0u8; write_size];
-            file.read_exact(&mut verify_buffer)?;
-
-            if verify_buffer != write_buffer[..write_size] {
-                return Err(WipeError::VerificationFailed(format!(
-                    "Immediate verification failed at offset {}",
-                    written
-                )));
+            Err(e) => {
+                warn!(
+                    "Unable to overwrite deleted directory entries in {}: {}",
+                    dir.display(),
+                    e
+                );
+                report.add_limitation(format!(
+                    "The deleted directory entries in {} could not be overwritten ({}); \
+                     the file's name may still be recoverable.",
+                    dir.display(),
+                    e
+                ));
             }
+        }
+    }
 
-            written += write_size as u64;
+    /// looks for other processes using the target
+    ///
+    /// data a process writes while (or after) the passes run ends up back on
+    /// disk, and a process reading it may crash on the pattern data
+    fn check_open_handles(&self, path: &Path, report: &mut WipeReport) -> Result<()> {
+        let handles = match handles::find_open_handles(path) {
+            Ok(handles) => handles,
+            Err(e) => {
+                debug!("Unable to check for open handles: {}", e);
+                return Ok(());
+            }
+        };
+        if handles.is_empty() {
+            return Ok(());
         }
 
-        // Final flush and sync to ensure all writes are on disk
-        file.flush()?;
-        file.sync_all()?;
+        let processes: Vec<String> = handles.iter().map(|h| h.to_string()).collect();
+        if self.open_file_policy == OpenFilePolicy::Refuse {
+            return Err(WipeError::FileInUse(processes));
+        }
 
+        warn!(
+            "{} is open in {} other process(es)",
+            path.display(),
+            processes.len()
+        );
+        report.add_limitation(format!(
+            "The target was open in other processes while it was wiped: {}. Anything they wrote \
+             during or after the wipe may be back on disk.",
+            processes.join(", ")
+        ));
         Ok(())
     }
 
-    /// performs the Purge-level overwrite sequence
-    fn perform_purge_overwrite(
-        &self,
-        file: &mut File,
-        buffer: &mut [u8],
-        file_size: u64,
-    ) -> Result<()> {
-        // multiple passes for Purge method
-        let patterns = [
-            WipePattern::Random, // random data pass
-            WipePattern::Zeros,  // zero pass
-            WipePattern::Ones,   // ones pass
-            WipePattern::Random, // final random pass
-        ];
+    /// deals with immutable/append-only files, which would otherwise fail
+    /// with a bare EPERM on the first write
+    fn check_protection_flags(&self, path: &Path) -> Result<()> {
+        let flags = match filesystem::protection_flags(path) {
+            Ok(flags) => flags,
+            Err(e) => {
+                // not every filesystem supports flags (e.g. FS_IOC_GETFLAGS on tmpfs)
+                debug!("Unable to read file flags of {}: {}", path.display(), e);
+                return Ok(());
+            }
+        };
+        if !flags.any() {
+            return Ok(());
+        }
 
-        for (i, pattern) in patterns.iter().enumerate() {
-            debug!("Starting purge pass {}/{}", i + 1, patterns.len());
-            pattern.fill_buffer(buffer);
-            self.overwrite_file_contents(file, buffer, file_size)?;
+        let refuse = || WipeError::ProtectedFlags {
+            path: path.to_path_buf(),
+            flags,
+        };
+        if !self.clear_protection_flags {
+            return Err(refuse());
+        }
+        match filesystem::clear_protection_flags(path) {
+            Ok(()) => {
+                info!("Cleared {} flag(s) on {}", flags, path.display());
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Unable to clear {} flag(s): {}", flags, e);
+                Err(refuse())
+            }
+        }
+    }
+
+    /// refuses devices that hold an operating system unless explicitly allowed
+    fn check_system_disk(&self, path: &Path) -> Result<()> {
+        match device::is_system_disk(path) {
+            Ok(true) if self.allow_system_disk => {
+                warn!(
+                    "{} holds an operating system, wiping as requested",
+                    path.display()
+                );
+                Ok(())
+            }
+            Ok(true) => Err(WipeError::SystemDisk(path.to_path_buf())),
+            Ok(false) => Ok(()),
+            Err(e) => {
+                warn!(
+                    "Unable to check whether {} is a system disk: {}",
+                    path.display(),
+                    e
+                );
+                Ok(())
+            }
         }
+    }
 
+    /// makes sure a partition device covers no more than its partition in
+    /// the disk's table, which it would if the table changed since the OS
+    /// read it, so the rest of the disk is left intact
+    fn check_partition_bounds(&self, path: &Path, report: &mut WipeReport) -> Result<()> {
+        let Some(location) = device::partition_device(path) else {
+            return Ok(());
+        };
+        let refuse = |reason: String| WipeError::PartitionBounds {
+            device: path.to_path_buf(),
+            reason,
+        };
+        let table = match partitions::read_table(&location.disk) {
+            Ok(Some(table)) => table,
+            // the OS still knows the partition from a table that is gone
+            Ok(None) if location.start.is_some() => {
+                return Err(refuse(format!(
+                    "{} has no partition table",
+                    location.disk.display()
+                )))
+            }
+            // e.g. an APFS volume, whose container has no table
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                warn!(
+                    "Unable to read the partition table of {}: {}",
+                    location.disk.display(),
+                    e
+                );
+                return Ok(());
+            }
+        };
+
+        let length = target_size(&mut File::open(path)?)?;
+        let partition = partitions::check_bounds(&table, location.number, location.start, length)
+            .map_err(refuse)?;
+        info!(
+            "{} is partition {} of {}, bytes {}..{}",
+            path.display(),
+            partition.number,
+            location.disk.display(),
+            partition.start,
+            partition.end()
+        );
+        report.partition = Some(partition);
         Ok(())
     }
 
-    /// calculates optimal buffer size based on file size and system memory
-    fn calculate_optimal_buffer_size(&self, file_size: u64) -> usize {
-        let max_buffer = 8 * 1024 * 1024; // 8MB max
-        let min_buffer = 4 * 1024; // 4KB min (typical page size)
+    /// makes sure no filesystem on the target device is mounted
+    ///
+    /// wiping underneath a mounted filesystem corrupts it while the kernel
+    /// keeps writing cached metadata back over the wiped blocks
+    fn check_device_unmounted(&self, path: &Path) -> Result<()> {
+        let mount_points = match device::mounted_filesystems(path) {
+            Ok(mount_points) => mount_points,
+            Err(e) => {
+                warn!(
+                    "Unable to check whether {} is mounted: {}",
+                    path.display(),
+                    e
+                );
+                return Ok(());
+            }
+        };
+        if mount_points.is_empty() {
+            return Ok(());
+        }
 
-        // use smaller buffer for small files
-        if file_size < min_buffer as u64 {
-            return file_size as usize;
+        if !self.unmount {
+            return Err(WipeError::DeviceMounted {
+                device: path.to_path_buf(),
+                mount_points,
+            });
         }
 
-        // scale buffer with file size, but cap at max_buffer
-        std::cmp::min(
-            max_buffer,
-            std::cmp::max(
-                min_buffer,
-                (file_size / 100) as usize, // Use ~1% of file size
-            ),
-        )
+        info!(
+            "Unmounting {} filesystem(s) on {}",
+            mount_points.len(),
+            path.display()
+        );
+        device::unmount(path, &mount_points)?;
+
+        // make sure nothing was remounted or left behind
+        let remaining = device::mounted_filesystems(path)?;
+        if !remaining.is_empty() {
+            return Err(WipeError::DeviceMounted {
+                device: path.to_path_buf(),
+                mount_points: remaining,
+            });
+        }
+        Ok(())
     }
 
-    /// verifies the wiping operation
-    fn verify_wiping(
+    /// adjusts strategy for copy-on-write filesystems
+    ///
+    /// overwriting in place allocates new extents, so the original blocks
+    /// survive until the filesystem reuses them
+    fn handle_copy_on_write(
         &self,
-        file: &mut File,
-        expected_pattern: &[u8],
-        level: VerificationLevel,
-    ) -> Result<()> {
-        match level {
-            VerificationLevel::None => Ok(()),
-            VerificationLevel::Basic => {
-                // sample ~1% of file at random locations
-                let file_size = file.metadata()?.len();
-                if file_size == 0 {
-                    return Ok(()); // Empty file is considered verified
-                }
+        path: &Path,
+        mount: &filesystem::MountInfo,
+        report: &mut WipeReport,
+    ) {
+        warn!(
+            "{} is on a copy-on-write filesystem ({}); overwriting does not destroy the original extents",
+            path.display(),
+            mount.fs_type
+        );
+
+        if self.nocow_copy && mount.fs_type.eq_ignore_ascii_case("btrfs") {
+            match filesystem::relocate_to_nocow(path) {
+                Ok(()) => debug!("Relocated data into a NOCOW copy before overwriting"),
+                Err(e) => warn!("Unable to disable copy-on-write for target: {}", e),
+            }
+        }
+
+        report.add_limitation(format!(
+            "{} is copy-on-write: overwrite passes were written to new extents and the original \
+             data may remain on disk. Use full-disk encryption with key destruction (crypto-erase) \
+             or wipe the free space of {} to remove it.",
+            mount.fs_type,
+            mount.mount_point.display()
+        ));
+    }
 
-                let mut verify_buf = vec![0u8; expected_pattern.len()];
-                let samples = std::cmp::max((file_size / 100) as usize, 1); // At least 1 sample
+    /// looks for snapshots that would keep the target's data alive
+    fn check_snapshots(
+        &self,
+        path: &Path,
+        mount: &filesystem::MountInfo,
+        report: &mut WipeReport,
+    ) -> Result<()> {
+        let snapshots = match snapshots::find_snapshots(path, mount) {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                debug!("Unable to enumerate snapshots: {}", e);
+                return Ok(());
+            }
+        };
+
+        let snapshots: Vec<_> = snapshots
+            .into_iter()
+            .filter(|snapshot| snapshot.may_contain_target())
+            .collect();
+        if snapshots.is_empty() {
+            return Ok(());
+        }
 
-                for _ in 0..samples {
-                    // ensure we don't exceed file size - pattern length
-                    let max_offset = file_size.saturating_sub(expected_pattern.len() as u64);
-                    if max_offset == 0 {
-                        break; // File is too small for pattern verification
+        let mut remaining = Vec::new();
+        for snapshot in snapshots {
+            match self.snapshot_policy {
+                SnapshotPolicy::Delete => match snapshots::delete_snapshot(&snapshot, mount) {
+                    Ok(()) => info!("Deleted snapshot {}", snapshot.name),
+                    Err(e) => {
+                        warn!("Unable to delete snapshot {}: {}", snapshot.name, e);
+                        remaining.push(snapshot.name);
                     }
+                },
+                _ => remaining.push(snapshot.name),
+            }
+        }
+        if remaining.is_empty() {
+            return Ok(());
+        }
+
+        if self.snapshot_policy == SnapshotPolicy::Refuse {
+            return Err(WipeError::SnapshotsPresent(remaining));
+        }
+
+        warn!(
+            "{} snapshot(s) may still reference {}",
+            remaining.len(),
+            path.display()
+        );
+        report.add_limitation(format!(
+            "The target's data may survive in snapshots: {}. Delete them to remove it.",
+            remaining.join(", ")
+        ));
+        Ok(())
+    }
+
+    /// implements NIST 800-88 compliant wiping
+    fn perform_modern_wipe(
+        &self,
+        target: &dyn WipeTarget,
+        config: &standards::Nist80088Config,
+        report: &mut WipeReport,
+    ) -> Result<()> {
+        let path = target.path();
+        info!("Starting modern wipe for: {}", path.display());
+
+        // open file with write permissions
+        let mut file = self.open_target(target)?;
+
+        // Get file size for verification
+        let file_size = target_size(&mut file)?;
+        debug!("File size: {} bytes", file_size);
 
-                    let offset = rand::random::<u64>() % max_offset;
-                    file.seek(SeekFrom::Start(offset))?;
-                    file.read_exact(&mut verify_buf)?;
+        // create buffer sized according to storage characteristics
+        let buffer_size = self.calculate_optimal_buffer_size(file_size);
+        let mut buffer = Zeroizing::new(vec![0u8; buffer_size]);
+
+        // if SSD/Flash, handle wear leveling
+        if self.storage_type.requires_wear_leveling_handling() {
+            debug!("Storage device requires wear leveling handling");
+            self.handle_wear_leveling(path, &mut file)?;
+        }
 
-                    if verify_buf != expected_pattern {
-                        return Err(WipeError::VerificationFailed(format!(
-                            "Pattern mismatch at offset {}",
-                            offset
-                        )));
+        // what the last pass wrote, for verification
+        let source = match config.method {
+            SanitizationMethod::Clear => {
+                // single pass of random data for Clear method
+                debug!("Performing Clear operation with random data");
+                let source = self.pass_source(path, 1, &WipePattern::Random, &mut buffer);
+                let mut buffers = PassBuffers::new();
+                let pass = (path, 1, 1);
+                let written = self.overwrite_file_contents(
+                    &mut file,
+                    &source,
+                    &mut buffers,
+                    file_size,
+                    pass,
+                )?;
+                report.record_pass(written);
+                report.nist_action = Some(NistAction::Clear);
+                source
+            }
+            SanitizationMethod::Purge => {
+                // for Purge, try hardware-based secure erase first, unless the
+                // drive is known to botch it
+                let erase_quirk = self
+                    .device_quirk(path)
+                    .filter(|quirk| self.hardware_erase_broken(quirk));
+                if let Some(quirk) = erase_quirk {
+                    warn!(
+                        "Skipping the hardware erase of {}: {}",
+                        quirk.model,
+                        quirk.reason()
+                    );
+                    self.record_purge_by_overwrite(
+                        report,
+                        &format!(
+                            "the hardware erase of {} was skipped ({})",
+                            quirk.model,
+                            quirk.reason()
+                        ),
+                    );
+                    self.perform_purge_overwrite(
+                        target,
+                        &mut file,
+                        &mut buffer,
+                        file_size,
+                        config.verify_level,
+                        report,
+                    )?
+                } else if self.storage_type.supports_secure_erase() {
+                    debug!("Attempting hardware-based secure erase");
+                    if let Err(e) = self.perform_hardware_secure_erase(path) {
+                        warn!(
+                            "Hardware secure erase failed: {}, falling back to software method",
+                            e
+                        );
+                        self.record_purge_by_overwrite(
+                            report,
+                            &format!("the hardware secure erase failed ({})", e),
+                        );
+                        self.perform_purge_overwrite(
+                            target,
+                            &mut file,
+                            &mut buffer,
+                            file_size,
+                            config.verify_level,
+                            report,
+                        )?
+                    } else {
+                        report.nist_action = Some(NistAction::Purge);
+                        // the device reads back as zeros
+                        PassSource::Repeat(&buffer)
                     }
+                } else {
+                    debug!("No hardware secure erase support, using software method");
+                    self.record_purge_by_overwrite(
+                        report,
+                        "the device has no hardware secure erase or sanitize command",
+                    );
+                    self.perform_purge_overwrite(
+                        target,
+                        &mut file,
+                        &mut buffer,
+                        file_size,
+                        config.verify_level,
+                        report,
+                    )?
                 }
-                Ok(())
             }
-            VerificationLevel::Full | VerificationLevel::Enhanced => {
-                // verify entire file
-                file.seek(SeekFrom::Start(0))?;
-                let mut verify_buf = vec![0u8; expected_pattern.len()];
+        };
 
-                if file.metadata()?.len() == 0 {
-                    return Ok(()); // empty file is considered verified
-                }
+        // verify wiping if required
+        if config.verify_level != VerificationLevel::None {
+            debug!(
+                "Performing verification at level: {:?}",
+                config.verify_level
+            );
+            self.verify_wiping(target, &file, &source, config.verify_level, report)?;
+        }
+
+        // ensure all writes are synced to disk
+        file.sync_all()
+            .map_err(pass_failed(report.passes, WipePhase::Sync, 0))?;
+        debug!("File contents synced to disk");
+
+        // hand the blocks back and drop file handle before removal
+        self.release_extents(&file);
+        drop(file);
+
+        // remove file after successful wiping
+        target
+            .remove()
+            .map_err(pass_failed(report.passes, WipePhase::Remove, 0))?;
+        info!("File successfully wiped and removed");
+
+        Ok(())
+    }
+
+    /// overwrites a target with the passes of the configured standard
+    fn perform_wipe(&self, target: &dyn WipeTarget, report: &mut WipeReport) -> Result<()> {
+        self.policy.check_standard(&self.standard)?;
+        if self.sandbox && self.verifier_process.is_some() {
+            return Err(WipeError::UnsupportedOperation(
+                "a sandboxed wipe can't start the separate verifier".into(),
+            ));
+        }
+        // looked up before the wipe, while a file target still exists
+        report.device = device::identity(target.path());
+        let checkpoints = self.start_checkpoints(target.path(), report);
+        self.run_sandboxed(|| self.perform_standard_wipe(target, report))?;
+        if let Some(checkpoints) = checkpoints {
+            checkpoints.complete();
+        }
+        Ok(())
+    }
+
+    /// checkpoints the wipe of a device, if checkpoints are on, noting in the
+    /// report when it picks up where an interrupted wipe stopped
+    fn start_checkpoints(
+        &self,
+        path: &Path,
+        report: &mut WipeReport,
+    ) -> Option<checkpoint::CheckpointGuard> {
+        let dir = self.checkpoint_dir.as_ref()?;
+        if !device::is_block_device(path) {
+            return None;
+        }
+        let sector_size = File::open(path)
+            .ok()
+            .and_then(|file| device::sector_sizes(&file).ok())
+            .map_or(512, |sectors| sectors.logical as u64);
+        let checkpoints = checkpoint::CheckpointGuard::begin(
+            dir,
+            path,
+            report.device.as_ref(),
+            &self.standard.to_string(),
+            self.standard.passes().len(),
+            sector_size,
+        );
+        if let Some(resumed) = checkpoints.resumed() {
+            info!(
+                "Resuming the interrupted wipe of {} at pass {} of {}, block {}",
+                path.display(),
+                resumed.pass,
+                resumed.passes,
+                resumed.lba
+            );
+            report.add_limitation(format!(
+                "Resumed an interrupted wipe at pass {} of {}, logical block {}; the passes and \
+                 blocks before it were written by the interrupted run.",
+                resumed.pass, resumed.passes, resumed.lba
+            ));
+        }
+        Some(checkpoints)
+    }
+
+    /// what a pass writes; a random pass resumed from a checkpoint goes on
+    /// with the keystream it was started with
+    fn pass_source<'a>(
+        &self,
+        path: &Path,
+        pass: usize,
+        pattern: &WipePattern,
+        unit: &'a mut [u8],
+    ) -> PassSource<'a> {
+        match checkpoint::resume_seed(path, pass) {
+            Some(seed) if matches!(pattern, WipePattern::Random) => {
+                PassSource::Keystream(Keystream::from_seed(seed))
+            }
+            _ => PassSource::new(pattern, unit, &self.seeds),
+        }
+    }
+
+    /// runs `work`, which opens the target, on a thread of its own when
+    /// sandboxed: the sandbox holds for the thread for good, so not the caller's
+    fn run_sandboxed<T: Send>(&self, work: impl FnOnce() -> T + Send) -> T {
+        if !self.sandbox {
+            return work();
+        }
+        std::thread::scope(|scope| {
+            scope
+                .spawn(work)
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
 
-                loop {
-                    match file.read_exact(&mut verify_buf) {
-                        Ok(_) => {
-                            if verify_buf != expected_pattern {
-                                return Err(WipeError::VerificationFailed(
-                                    "Pattern mismatch during full verification".into(),
-                                ));
+    /// overwrites the first and last `region` bytes of a device and of each
+    /// partition on it with zeros, after erasing its signatures
+    ///
+    /// that destroys the partition table, boot code, filesystem superblocks
+    /// and the metadata kept at the end of partitions (md, NTFS backup boot
+    /// sectors), which is enough to redeploy a device that held nothing
+    /// sensitive; everything in between is still there, so the wipe achieves
+    /// no NIST action and the report is marked quick
+    fn perform_quick_wipe(
+        &self,
+        target: &dyn WipeTarget,
+        region: u64,
+        report: &mut WipeReport,
+    ) -> Result<()> {
+        self.policy.check_quick()?;
+        let path = target.path();
+        report.device = device::identity(path);
+        // read before the start of the device, which holds it, is overwritten
+        let table = match partitions::read_table(path) {
+            Ok(table) => table,
+            Err(e) => {
+                debug!(
+                    "Unable to read the partition table of {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        };
+        self.erase_device_signatures(path)?;
+
+        let written =
+            self.run_sandboxed(|| -> Result<u64> {
+                let mut file = self.open_target(target)?;
+                let size = target_size(&mut file)?;
+                let regions = partitions::edges(size, region, table.as_ref());
+                let zeros = Zeroizing::new(vec![0u8; self.buffer_size.max(4096)]);
+                let mut written = 0;
+                for &(start, end) in &regions {
+                    let mut offset = start;
+                    while offset < end {
+                        let len = (end - offset).min(zeros.len() as u64) as usize;
+                        write_all_at(&file, &zeros[..len], offset).map_err(pass_failed(
+                            1,
+                            WipePhase::Write,
+                            offset,
+                        ))?;
+                        offset += len as u64;
+                    }
+                    written += end - start;
+                }
+                file.sync_all()
+                    .map_err(pass_failed(1, WipePhase::Sync, 0))?;
+
+                if self.standard.verifies() {
+                    let verify = target.open_for_verify()?;
+                    let mut read = Zeroizing::new(vec![0u8; zeros.len()]);
+                    for &(start, end) in &regions {
+                        let mut offset = start;
+                        while offset < end {
+                            let len = (end - offset).min(read.len() as u64) as usize;
+                            read_exact_at(&verify, &mut read[..len], offset)
+                                .map_err(pass_failed(1, WipePhase::Verify, offset))?;
+                            if read[..len].iter().any(|&b| b != 0) {
+                                return Err(WipeError::VerificationFailed(format!(
+                                    "{} does not read back as zeros at offset {}",
+                                    path.display(),
+                                    offset
+                                )));
                             }
+                            offset += len as u64;
                         }
-                        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
-                        Err(e) => return Err(e.into()),
                     }
                 }
-                Ok(())
-            }
+                info!(
+                    "Quick wipe of {} overwrote {} region(s), {} bytes",
+                    path.display(),
+                    regions.len(),
+                    written
+                );
+                Ok(written)
+            })?;
+
+        report.record_pass(written);
+        report.quick = true;
+        report.nist_action = None;
+        report.add_limitation(format!(
+            "Quick wipe: only the first and last {} MiB of the device and of each partition were \
+             overwritten, and filesystem signatures erased. The rest of the data remains, so this \
+             is NOT NIST 800-88 Clear or Purge; run a full wipe before the media leaves your control.",
+            region / (1024 * 1024)
+        ));
+        Ok(())
+    }
+
+    /// runs the passes of the configured standard
+    fn perform_standard_wipe(
+        &self,
+        target: &dyn WipeTarget,
+        report: &mut WipeReport,
+    ) -> Result<()> {
+        match &self.standard {
+            WipeStandard::Modern(config) => self.perform_modern_wipe(target, config, report),
+            WipeStandard::Legacy(config) => self.perform_legacy_wipe(target, config, report),
+            WipeStandard::Custom(config) => self.perform_custom_wipe(target, config, report),
+            WipeStandard::Auto(_) => unreachable!("resolved for the storage in Shredder::new"),
         }
     }
 
-    /// attempts to perform hardware-based secure erase
-    fn perform_hardware_secure_erase<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        secure_erase::perform_secure_erase(path.as_ref())
+    /// records what a Purge carried out by overwriting achieves: Purge on hard
+    /// disks, but only Clear where remapped blocks are out of reach
+    ///
+    /// # Arguments
+    /// * `report` - Report of the wipe
+    /// * `reason` - Why no hardware erase purged the media
+    fn record_purge_by_overwrite(&self, report: &mut WipeReport, reason: &str) {
+        if self.storage_type.requires_wear_leveling_handling() {
+            report.nist_action = Some(NistAction::Clear);
+            report.require_destroy(format!(
+                "{} and overwrites can't reach remapped blocks",
+                reason
+            ));
+        } else {
+            report.nist_action = Some(NistAction::Purge);
+        }
     }
 
-    /// performs TRIM operation for SSDs
-    fn perform_trim_operation(&self, file: &mut File) -> Result<()> {
-        trim::perform_trim(file)
+    /// refuses multi-pass legacy standards on wear-leveled storage, where the
+    /// controller remaps every pass to fresh blocks, unless they are forced
+    fn check_multipass_wear(&self, report: &mut WipeReport) -> Result<()> {
+        if !self.standard.is_multipass_legacy()
+            || !self.storage_type.requires_wear_leveling_handling()
+        {
+            return Ok(());
+        }
+        let passes = self.standard.passes().len();
+        let media = builder::media(&self.storage_type);
+        if !self.force_multipass {
+            return Err(WipeError::MultipassOnWearLeveledStorage {
+                standard: self.standard.to_string(),
+                passes,
+                media,
+            });
+        }
+        warn!(
+            "Running all {} passes of {} on {} as forced: they wear the media without reaching remapped blocks",
+            passes, self.standard, media
+        );
+        report.add_limitation(format!(
+            "{} was forced on {}; its {} passes add wear but can't reach blocks the controller \
+             remapped. Use a hardware secure erase or NIST Purge for assurance.",
+            self.standard, media, passes
+        ));
+        Ok(())
     }
 
-    /// handles wear leveling for SSDs and Flash storage
-    fn handle_wear_leveling(&self, file: &mut File) -> Result<()> {
-        // for SSDs/Flash, first try TRIM if available
-        if let StorageType::Ssd(caps) | StorageType::Flash(caps) = &self.storage_type {
-            if caps.supports_trim {
-                debug!("Attempting TRIM operation");
-                self.perform_trim_operation(file)?;
+    /// implements legacy standard wiping (DoD, Gutmann, etc.)
+    fn perform_legacy_wipe(
+        &self,
+        target: &dyn WipeTarget,
+        config: &standards::LegacyConfig,
+        report: &mut WipeReport,
+    ) -> Result<()> {
+        let path = target.path();
+        self.check_multipass_wear(report)?;
+        info!("Starting legacy wipe using standard: {:?}", config.standard);
+
+        // get wiping patterns for the selected standard
+        let patterns = config.standard.get_patterns();
+        debug!("Using {} pass wiping pattern", patterns.len());
+
+        // open file with write permissions
+        let mut file = self.open_target(target)?;
+
+        let file_size = target_size(&mut file)?;
+        let buffer_size = self.calculate_optimal_buffer_size(file_size);
+        let mut buffer = Zeroizing::new(vec![0u8; buffer_size]);
+        let mut buffers = PassBuffers::new();
+
+        // perform each pass
+        for (i, pattern) in patterns.iter().enumerate() {
+            if checkpoint::skipped(path, i + 1) {
+                report.record_pass(0);
+                continue;
+            }
+            debug!("Starting pass {}/{}", i + 1, patterns.len());
+            let source = self.pass_source(path, i + 1, pattern, &mut buffer);
+            let pass = (path, i + 1, patterns.len());
+            let written =
+                self.overwrite_file_contents(&mut file, &source, &mut buffers, file_size, pass)?;
+            report.record_pass(written);
+
+            // verify after each pass if requested
+            if config.extra_verification {
+                debug!("Performing verification after pass {}", i + 1);
+                self.verify_wiping(target, &file, &source, VerificationLevel::Basic, report)?;
+            }
+
+            // final verification if requested
+            if config.extra_verification && i + 1 == patterns.len() {
+                debug!("Performing final full verification");
+                self.verify_wiping(target, &file, &source, VerificationLevel::Full, report)?;
             }
         }
+
+        // overwrites beyond the first add no NIST assurance
+        report.nist_action = Some(NistAction::Clear);
+
+        // sync and remove file
+        let passes = patterns.len();
+        file.sync_all()
+            .map_err(pass_failed(passes, WipePhase::Sync, 0))?;
+        self.release_extents(&file);
+        drop(file);
+        target
+            .remove()
+            .map_err(pass_failed(passes, WipePhase::Remove, 0))?;
+        info!("Legacy wipe completed successfully");
+
         Ok(())
     }
 
-    /// sets the buffer size for I/O operations
-    ///
-    /// # Arguments
-    /// * `size` - The new buffer size in bytes (minimum 4KB, maximum 16MB)
-    ///
-    /// # Returns
-    /// the shredder instance for method chaining
-    pub fn with_buffer_size(mut self, size: usize) -> Self {
-        const MIN_BUFFER: usize = 4 * 1024; // 4KB
-        const MAX_BUFFER: usize = 16 * 1024 * 1024; // 16MB
+    /// implements custom wiping patterns
+    fn perform_custom_wipe(
+        &self,
+        target: &dyn WipeTarget,
+        config: &standards::WipeConfig,
+        report: &mut WipeReport,
+    ) -> Result<()> {
+        let path = target.path();
+        info!("Starting custom wipe with {} passes", config.passes.len());
 
-        self.buffer_size = size.clamp(MIN_BUFFER, MAX_BUFFER);
-        self
+        let mut file = self.open_target(target)?;
+
+        let file_size = target_size(&mut file)?;
+        let buffer_size = self.calculate_optimal_buffer_size(file_size);
+        let mut buffer = Zeroizing::new(vec![0u8; buffer_size]);
+        let mut buffers = PassBuffers::new();
+
+        // apply each custom pattern
+        for (i, pattern) in config.passes.iter().enumerate() {
+            if checkpoint::skipped(path, i + 1) {
+                report.record_pass(0);
+                continue;
+            }
+            debug!("Starting custom pass {}/{}", i + 1, config.passes.len());
+            let source = self.pass_source(path, i + 1, pattern, &mut buffer);
+            let pass = (path, i + 1, config.passes.len());
+            let written =
+                self.overwrite_file_contents(&mut file, &source, &mut buffers, file_size, pass)?;
+            report.record_pass(written);
+
+            if config.verify_each_pass {
+                debug!("Verifying pass {}", i + 1);
+                self.verify_wiping(target, &file, &source, VerificationLevel::Full, report)?;
+            }
+        }
+
+        report.nist_action = Some(NistAction::Clear);
+
+        let passes = config.passes.len();
+        file.sync_all()
+            .map_err(pass_failed(passes, WipePhase::Sync, 0))?;
+        self.release_extents(&file);
+        drop(file);
+        target
+            .remove()
+            .map_err(pass_failed(passes, WipePhase::Remove, 0))?;
+        info!("Custom wipe completed successfully");
+
+        Ok(())
     }
 
-    /// gets the current buffer size
-    pub fn get_buffer_size(&self) -> usize {
-        self.buffer_size
+    /// deallocates and truncates a wiped file, so its size doesn't outlive it,
+    /// shrinking it in random steps first if requested; devices are left alone
+    fn release_extents(&self, file: &File) {
+        if !file.metadata().is_ok_and(|metadata| metadata.is_file()) {
+            return;
+        }
+        // the data is already overwritten, so this isn't worth failing the wipe for
+        if self.obfuscate_size {
+            if let Err(e) = extents::obfuscate_size(file) {
+                warn!("Could not obfuscate the wiped file's size: {}", e);
+            }
+        }
+        if let Err(e) = extents::release(file) {
+            warn!("Could not release the wiped file's blocks: {}", e);
+        }
+    }
+
+    /// overwrites a file through a shared memory mapping: fill, msync, then
+    /// compare the mapping with the pattern if the standard verifies
+    #[cfg(unix)]
+    fn overwrite_mapped(
+        &self,
+        file: &mut File,
+        source: &PassSource,
+        buffers: &mut PassBuffers,
+        file_size: u64,
+        pass: (&Path, usize, usize),
+    ) -> Result<u64> {
+        // progress is reported every chunk
+        const CHUNK: usize = 1024 * 1024;
+
+        let (data, threads) = buffers.get(source, CHUNK, 1);
+        let scratch = &mut threads[0].scratch;
+
+        let mut map = mmap::MappedFile::map(file, file_size as usize).map_err(pass_failed(
+            pass.1,
+            WipePhase::Write,
+            0,
+        ))?;
+        let mut written = 0u64;
+        let mut skipped = 0u64;
+        for (index, chunk) in map.as_mut_slice().chunks_mut(CHUNK).enumerate() {
+            let offset = (index * CHUNK) as u64;
+            let _span = Span::chunk(pass.1, offset, chunk.len());
+            let expected = data.chunk(scratch, offset, chunk.len());
+            // comparing doesn't dirty the pages, so matching ones aren't written back
+            if self.skip_matching && chunk == expected {
+                skipped += chunk.len() as u64;
+            } else {
+                chunk.copy_from_slice(expected);
+            }
+            written += chunk.len() as u64;
+            self.report_progress(pass, written, file_size);
+        }
+        map.sync()
+            .map_err(pass_failed(pass.1, WipePhase::Sync, 0))?;
+
+        if self.standard.verifies() {
+            for (index, chunk) in map.as_mut_slice().chunks(CHUNK).enumerate() {
+                let offset = (index * CHUNK) as u64;
+                if chunk != data.chunk(scratch, offset, chunk.len()) {
+                    return Err(readback_mismatch(pass.1, offset));
+                }
+            }
+        }
+        drop(map);
+
+        file.sync_all()
+            .map_err(pass_failed(pass.1, WipePhase::Sync, 0))?;
+        Ok(file_size - skipped)
+    }
+
+    /// overwrites a large target with several threads, each writing (and, if the
+    /// standard verifies, reading back) its own region through its own file
+    /// descriptor
+    ///
+    /// the result is the same as from a single thread. if several regions fail,
+    /// the error of the first one is returned
+    fn overwrite_parallel(
+        &self,
+        file: &mut File,
+        source: &PassSource,
+        buffers: &mut PassBuffers,
+        file_size: u64,
+        regions: u64,
+        pass: (&Path, usize, usize),
+    ) -> Result<u64> {
+        let block = self.write_block(file);
+        let chunk = whole_blocks(self.buffer_size, block);
+        let region = file_size.div_ceil(regions).div_ceil(chunk as u64) * chunk as u64;
+        debug!(
+            "Overwriting {} in {} regions of {} bytes",
+            pass.0.display(),
+            regions,
+            region
+        );
+        let (data, threads) = buffers.get(source, chunk, regions as usize);
+
+        // the lock keeps the reported progress increasing
+        let written = std::sync::Mutex::new(0u64);
+        let skipped = std::sync::atomic::AtomicU64::new(0);
+        let failed = std::sync::atomic::AtomicBool::new(false);
+        let record = |bytes: u64| {
+            let mut written = written.lock().unwrap_or_else(|e| e.into_inner());
+            *written += bytes;
+            self.report_progress(pass, *written, file_size);
+        };
+
+        let verify_chunks = self.standard.verifies();
+        let skip_matching = self.skip_matching;
+        let worker =
+            |file: File, buffers: &mut ThreadBuffers, start: u64, end: u64| -> Result<()> {
+                let ThreadBuffers { scratch, read } = buffers;
+                let mut offset = start;
+                while offset < end && !failed.load(std::sync::atomic::Ordering::Relaxed) {
+                    let len = next_write(end - offset, chunk, block);
+                    let _span = Span::chunk(pass.1, offset, len);
+                    let expected = data.chunk(scratch, offset, len);
+                    if skip_matching {
+                        read_exact_at(&file, &mut read[..len], offset).map_err(pass_failed(
+                            pass.1,
+                            WipePhase::Write,
+                            offset,
+                        ))?;
+                        if read[..len] == *expected {
+                            skipped.fetch_add(len as u64, std::sync::atomic::Ordering::Relaxed);
+                            offset += len as u64;
+                            record(len as u64);
+                            continue;
+                        }
+                    }
+                    write_all_at(&file, expected, offset).map_err(pass_failed(
+                        pass.1,
+                        WipePhase::Write,
+                        offset,
+                    ))?;
+                    if verify_chunks {
+                        read_exact_at(&file, &mut read[..len], offset).map_err(pass_failed(
+                            pass.1,
+                            WipePhase::Verify,
+                            offset,
+                        ))?;
+                        if read[..len] != *expected {
+                            return Err(readback_mismatch(pass.1, offset));
+                        }
+                    }
+                    offset += len as u64;
+                    record(len as u64);
+                }
+                Ok(())
+            };
+
+        let results = std::thread::scope(|scope| {
+            let mut handles = Vec::new();
+            let mut start = 0;
+            for buffers in threads.iter_mut() {
+                if start >= file_size {
+                    break;
+                }
+                let end = (start + region).min(file_size);
+                let file =
+                    file.try_clone()
+                        .map_err(pass_failed(pass.1, WipePhase::Write, start))?;
+                let worker = &worker;
+                let failed = &failed;
+                handles.push(scope.spawn(move || {
+                    let result = worker(file, buffers, start, end);
+                    if result.is_err() {
+                        // no point in the other regions carrying on
+                        failed.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    result
+                }));
+                start = end;
+            }
+            Ok::<_, WipeError>(
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("wipe worker panicked"))
+                    .collect::<Vec<_>>(),
+            )
+        })?;
+        // in region order, so the lowest failing offset is reported
+        results.into_iter().collect::<Result<Vec<_>>>()?;
+
+        file.sync_all()
+            .map_err(pass_failed(pass.1, WipePhase::Sync, 0))?;
+        Ok(file_size - skipped.into_inner())
+    }
+
+    /// overwrites file contents with what the pass writes
+    ///
+    /// when the standard verifies, each chunk is read back right after it is
+    /// written, which catches a failing write at its offset but doubles the I/O.
+    /// with verification off, chunks are only written
+    ///
+    /// `buffers` are kept across the passes of a wipe and hold this pass's
+    /// pattern once it's rendered. `pass` is the target, the pass number and the
+    /// number of passes, for progress reporting
+    ///
+    /// returns the bytes written, which is less than the size when chunks that
+    /// already held the pattern were skipped
+    fn overwrite_file_contents(
+        &self,
+        file: &mut File,
+        source: &PassSource,
+        buffers: &mut PassBuffers,
+        file_size: u64,
+        pass: (&Path, usize, usize),
+    ) -> Result<u64> {
+        buffers.start_pass();
+        let _span = Span::pass(pass.0, pass.1, pass.2, file_size);
+        self.emit(|| WipeEvent::PassStarted {
+            pass: pass.1,
+            passes: pass.2,
+        });
+
+        // small files are faster to fill through a mapping than with a write per chunk
+        #[cfg(unix)]
+        if self.mmap
+            && !self.sync_writes
+            && file_size > 0
+            && file_size <= MMAP_THRESHOLD
+            && file.metadata()?.is_file()
+        {
+            return self.overwrite_mapped(file, source, buffers, file_size, pass);
+        }
+
+        // a checkpoint is one offset, so checkpointed passes go front to back
+        let checkpointed = checkpoint::is_active(pass.0);
+        let regions = (self.threads as u64).min(file_size / MIN_REGION);
+        if regions > 1 && !checkpointed {
+            return self.overwrite_parallel(file, source, buffers, file_size, regions, pass);
+        }
+
+        let verify_chunks = self.standard.verifies();
+
+        // starts at our buffer_size setting and follows the measured throughput
+        let mut tuner = BufferTuner::new(self.buffer_size);
+        let block = self.write_block(file);
+
+        let start = match checkpoint::resume_offset(pass.0, pass.1) {
+            offset if offset < file_size && offset % block as u64 == 0 => offset,
+            _ => 0,
+        };
+        if start > 0 {
+            info!(
+                "Resuming pass {}/{} of {} at byte {}",
+                pass.1,
+                pass.2,
+                pass.0.display(),
+                start
+            );
+        }
+        file.seek(SeekFrom::Start(start))
+            .map_err(pass_failed(pass.1, WipePhase::Write, start))?;
+        let mut written = start;
+        let mut skipped = 0u64;
+
+        while written < file_size {
+            let started = std::time::Instant::now();
+
+            // whole blocks from block-aligned offsets, so the device never
+            // reads a block back to change part of it
+            let chunk = whole_blocks(tuner.size(), block);
+            let write_size = next_write(file_size - written, chunk, block);
+            if write_size < block {
+                trace!("Writing the final partial block of {} bytes", write_size);
+            }
+
+            // the pattern continues where the previous chunk left off
+            let (data, threads) = buffers.get(source, tuner.size(), 1);
+            let ThreadBuffers { scratch, read } = &mut threads[0];
+            let _span = Span::chunk(pass.1, written, write_size);
+            let expected = data.chunk(scratch, written, write_size);
+
+            // leave chunks alone that already hold the pattern
+            if self.skip_matching {
+                let current = &mut read[..write_size];
+                read_exact_at(file, current, written).map_err(pass_failed(
+                    pass.1,
+                    WipePhase::Write,
+                    written,
+                ))?;
+                if *current == *expected {
+                    written += write_size as u64;
+                    skipped += write_size as u64;
+                    file.seek(SeekFrom::Start(written)).map_err(pass_failed(
+                        pass.1,
+                        WipePhase::Write,
+                        written,
+                    ))?;
+                    self.report_progress(pass, written, file_size);
+                    continue;
+                }
+                // reading moved the position past the chunk on windows
+                file.seek(SeekFrom::Start(written)).map_err(pass_failed(
+                    pass.1,
+                    WipePhase::Write,
+                    written,
+                ))?;
+            }
+
+            // Write and verify immediately
+            file.write_all(expected)
+                .and_then(|()| file.flush())
+                .map_err(pass_failed(pass.1, WipePhase::Write, written))?;
+
+            // Verify this chunk
+            if verify_chunks {
+                let verify_buffer = &mut read[..write_size];
+                file.seek(SeekFrom::Start(written))
+                    .and_then(|_| file.read_exact(verify_buffer))
+                    .map_err(pass_failed(pass.1, WipePhase::Verify, written))?;
+
+                if *verify_buffer != *expected {
+                    return Err(readback_mismatch(pass.1, written));
+                }
+            }
+
+            written += write_size as u64;
+            if self.adaptive_buffer {
+                tuner.record(write_size, started.elapsed());
+            }
+
+            self.report_progress(pass, written, file_size);
+
+            // only what reached the media counts as written
+            if checkpointed && written < file_size && checkpoint::due(pass.0) {
+                file.sync_data()
+                    .map_err(pass_failed(pass.1, WipePhase::Sync, written))?;
+                checkpoint::save(pass.0, pass.1, written, source.seed());
+            }
+        }
+
+        // Final flush and sync to ensure all writes are on disk
+        file.flush()
+            .and_then(|()| file.sync_all())
+            .map_err(pass_failed(pass.1, WipePhase::Sync, 0))?;
+        if checkpointed && pass.1 < pass.2 {
+            checkpoint::save(pass.0, pass.1 + 1, 0, None);
+        }
+
+        if skipped > 0 {
+            debug!("Skipped {} bytes that already held the pattern", skipped);
+        }
+        Ok(file_size - start - skipped)
+    }
+
+    /// hands the progress of a pass to the callback and the event channel, if
+    /// there are any
+    fn report_progress(&self, pass: (&Path, usize, usize), written: u64, file_size: u64) {
+        self.emit(|| WipeEvent::Progress {
+            pass: pass.1,
+            passes: pass.2,
+            bytes_written: written,
+            bytes_per_pass: file_size,
+        });
+        jobs::record_progress(
+            pass.0,
+            JobProgress {
+                pass: pass.1,
+                passes: pass.2,
+                bytes_written: written,
+                bytes_per_pass: file_size,
+            },
+        );
+        if let Some(progress) = &self.progress {
+            progress(&Progress {
+                path: pass.0,
+                pass: pass.1,
+                passes: pass.2,
+                bytes_written: written,
+                bytes_per_pass: file_size,
+            });
+        }
+    }
+
+    /// sends an event if the wipe was started with `wipe_with_events`
+    fn emit(&self, event: impl FnOnce() -> WipeEvent) {
+        if let Some(events) = &self.events {
+            // the receiver may have been dropped; the wipe goes on regardless
+            let _ = events.send(event());
+        }
+    }
+
+    /// performs the Purge-level overwrite sequence
+    ///
+    /// returns what the last pass wrote, for verification
+    fn perform_purge_overwrite<'a>(
+        &self,
+        target: &dyn WipeTarget,
+        file: &mut File,
+        buffer: &'a mut [u8],
+        file_size: u64,
+        verify_level: VerificationLevel,
+        report: &mut WipeReport,
+    ) -> Result<PassSource<'a>> {
+        // multiple passes for Purge method
+        let patterns = SanitizationMethod::Purge.patterns();
+        let mut buffers = PassBuffers::new();
+        let mut run = |i: usize, source: &PassSource, file: &mut File, report: &mut WipeReport| {
+            debug!("Starting purge pass {}/{}", i + 1, patterns.len());
+            let pass = (target.path(), i + 1, patterns.len());
+            let written =
+                self.overwrite_file_contents(file, source, &mut buffers, file_size, pass)?;
+            report.record_pass(written);
+            Ok::<_, WipeError>(())
+        };
+
+        let (last, first) = patterns.split_last().expect("purge performs passes");
+        for (i, pattern) in first.iter().enumerate() {
+            if checkpoint::skipped(target.path(), i + 1) {
+                report.record_pass(0);
+                continue;
+            }
+            let source = self.pass_source(target.path(), i + 1, pattern, &mut *buffer);
+            run(i, &source, file, report)?;
+            // hashes are per pass; the other levels check the last one only
+            if verify_level == VerificationLevel::Hash {
+                self.verify_wiping(target, file, &source, verify_level, report)?;
+            }
+        }
+        let source = self.pass_source(target.path(), patterns.len(), last, buffer);
+        run(first.len(), &source, file, report)?;
+        Ok(source)
+    }
+
+    /// calculates optimal buffer size based on file size and system memory
+    fn calculate_optimal_buffer_size(&self, file_size: u64) -> usize {
+        let max_buffer = 8 * 1024 * 1024; // 8MB max
+        let min_buffer = 4 * 1024; // 4KB min (typical page size)
+
+        // use smaller buffer for small files
+        if file_size < min_buffer as u64 {
+            return file_size as usize;
+        }
+
+        // scale buffer with file size, but cap at max_buffer
+        whole_blocks(
+            std::cmp::min(
+                max_buffer,
+                std::cmp::max(
+                    min_buffer,
+                    (file_size / 100) as usize, // Use ~1% of file size
+                ),
+            ),
+            self.block_size,
+        )
+    }
+
+    /// block size writes to `file` are aligned to: the configured one, or a
+    /// device's physical sector size where that is larger
+    fn write_block(&self, file: &File) -> usize {
+        if file.metadata().is_ok_and(|metadata| metadata.is_file()) {
+            return self.block_size;
+        }
+        match device::sector_sizes(file) {
+            Ok(sectors) => {
+                if sectors.physical != sectors.logical {
+                    debug!(
+                        "{}-byte logical sectors on {}-byte physical ones",
+                        sectors.logical, sectors.physical
+                    );
+                }
+                sectors.physical.clamp(self.block_size, 64 * 1024)
+            }
+            Err(e) => {
+                debug!("Unable to read the device's sector sizes: {}", e);
+                self.block_size
+            }
+        }
+    }
+
+    /// verifies the wiping operation
+    ///
+    /// the writes are synced first and the target is read through a fresh
+    /// handle with its cached pages dropped, so what is checked is what reached
+    /// the media rather than what is still in memory. `source` is what the
+    /// last pass recorded in `report` wrote
+    ///
+    /// a sampled verification records what it read and the confidence that
+    /// gives, a hash verification the digest of the pass
+    fn verify_wiping(
+        &self,
+        target: &dyn WipeTarget,
+        written: &File,
+        source: &PassSource,
+        level: VerificationLevel,
+        report: &mut WipeReport,
+    ) -> Result<()> {
+        // unit of the random samples
+        const SAMPLE: usize = 4096;
+
+        if level == VerificationLevel::None {
+            return Ok(());
+        }
+        let pass = report.passes;
+        let _span = Span::verify(target.path(), pass, &level);
+        self.emit(|| WipeEvent::VerifyStarted { pass, level });
+        written
+            .sync_all()
+            .map_err(pass_failed(pass, WipePhase::Sync, 0))?;
+        let file =
+            &mut target
+                .open_for_verify()
+                .map_err(pass_failed(pass, WipePhase::Verify, 0))?;
+
+        let file_size = target_size(file).map_err(pass_failed(pass, WipePhase::Verify, 0))?;
+        if file_size == 0 {
+            return Ok(()); // empty file is considered verified
+        }
+
+        match level {
+            VerificationLevel::None => Ok(()),
+            VerificationLevel::Basic => {
+                // sample the configured share of the blocks
+                let mut verify_buf = Zeroizing::new(vec![0u8; SAMPLE]);
+                let mut expected = Zeroizing::new(vec![0u8; SAMPLE]);
+                let blocks = file_size.div_ceil(SAMPLE as u64);
+                let wanted = (blocks as f64 * self.sample_rate).ceil() as u64;
+
+                for number in verify::sample_blocks(blocks, wanted, self.sample_placement) {
+                    let offset = number * SAMPLE as u64;
+                    let len = (file_size - offset).min(SAMPLE as u64) as usize;
+                    file.seek(SeekFrom::Start(offset))
+                        .and_then(|_| file.read_exact(&mut verify_buf[..len]))
+                        .map_err(pass_failed(pass, WipePhase::Verify, offset))?;
+                    source.fill(offset, &mut expected[..len]);
+
+                    if verify_buf[..len] != expected[..len] {
+                        return Err(pass_failed(pass, WipePhase::Verify, offset)(
+                            WipeError::VerificationFailed("Pattern mismatch".to_string()),
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            VerificationLevel::Sampled(share) => {
+                // one block from each stratum, so no stretch of the target
+                // goes unchecked
+                let mut verify_buf = Zeroizing::new(vec![0u8; SAMPLE]);
+                let mut expected = Zeroizing::new(vec![0u8; SAMPLE]);
+                let blocks = file_size.div_ceil(SAMPLE as u64);
+                let wanted = (blocks as f64 * share.clamp(0.0, 1.0)).ceil() as u64;
+                let numbers = verify::stratified_blocks(blocks, wanted);
+
+                for &number in &numbers {
+                    let offset = number * SAMPLE as u64;
+                    let len = (file_size - offset).min(SAMPLE as u64) as usize;
+                    file.seek(SeekFrom::Start(offset))
+                        .and_then(|_| file.read_exact(&mut verify_buf[..len]))
+                        .map_err(pass_failed(pass, WipePhase::Verify, offset))?;
+                    source.fill(offset, &mut expected[..len]);
+
+                    if verify_buf[..len] != expected[..len] {
+                        return Err(pass_failed(pass, WipePhase::Verify, offset)(
+                            WipeError::VerificationFailed("Pattern mismatch".to_string()),
+                        ));
+                    }
+                }
+                let checked = numbers.len() as u64;
+                report.sampled_verification = Some(SampledVerification {
+                    blocks,
+                    blocks_checked: checked,
+                    max_unwiped: verify::max_unwiped_share(checked),
+                });
+                Ok(())
+            }
+            VerificationLevel::Hash => {
+                let digest = compare_hashes(file, file_size, source, pass)?;
+                info!("Pass {} verified, SHA-256 {}", pass, digest);
+                report.pass_sha256.push(digest);
+                Ok(())
+            }
+            VerificationLevel::Full => {
+                compare_full(file, file_size, source, pass, false)?;
+                Ok(())
+            }
+            VerificationLevel::Enhanced => {
+                // independent full reads, each through a new handle with the
+                // cached pages dropped again. every other read goes from the
+                // end backwards, so it isn't served by read-ahead or a drive
+                // cache the read before it filled
+                const READS: usize = 2;
+                compare_full(file, file_size, source, pass, false)?;
+                for read in 1..READS {
+                    debug!("Enhanced verification: read {}/{}", read + 1, READS);
+                    let mut file = target.open_for_verify().map_err(pass_failed(
+                        pass,
+                        WipePhase::Verify,
+                        0,
+                    ))?;
+                    compare_full(&mut file, file_size, source, pass, read % 2 == 1)?;
+                }
+
+                if let Some(program) = &self.verifier_process {
+                    debug!("Enhanced verification: read in {}", program.display());
+                    verify::verify_in_process(program, target.path(), &source.pattern_arg())
+                        .map_err(pass_failed(pass, WipePhase::Verify, 0))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// opens the target for writing and, if asked to, sandboxes the thread
+    fn open_target(&self, target: &dyn WipeTarget) -> Result<File> {
+        let file = target.open(self.sync_writes)?;
+        if self.sandbox {
+            // no programs once sandboxed, so the helper is started now
+            if let Some(helper) = &self.helper {
+                if self.storage_type.requires_wear_leveling_handling()
+                    || self.storage_type.supports_secure_erase()
+                {
+                    helper.connect()?;
+                }
+            }
+            sandbox::confine_thread(sandbox::Needs {
+                remove: file.metadata().is_ok_and(|metadata| metadata.is_file()),
+                trim: self.helper.is_none() && self.storage_type.requires_wear_leveling_handling(),
+            })?;
+        }
+        Ok(file)
+    }
+
+    /// attempts to perform hardware-based secure erase
+    fn perform_hardware_secure_erase<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        match &self.helper {
+            Some(helper) => helper.secure_erase(path.as_ref(), self.allow_system_disk),
+            None => secure_erase::perform_secure_erase(path.as_ref(), self.allow_system_disk),
+        }
+    }
+
+    /// performs TRIM operation for SSDs
+    fn perform_trim_operation(&self, path: &Path, file: &mut File) -> Result<()> {
+        match &self.helper {
+            Some(helper) => helper.trim(path),
+            None => trim::perform_trim(file),
+        }
+    }
+
+    /// looks up the model of a device target in the quirks table; files
+    /// aren't looked up, since their device isn't known for certain
+    fn device_quirk(&self, path: &Path) -> Option<Quirk> {
+        if !device::is_block_device(path) {
+            return None;
+        }
+        let model = device::device_info(path).ok()?.model?;
+        self.quirks.lookup(&model)
+    }
+
+    /// whether the hardware erase this storage would get is known to be broken:
+    /// sanitize on NVMe, ATA secure erase elsewhere
+    fn hardware_erase_broken(&self, quirk: &Quirk) -> bool {
+        match &self.storage_type {
+            StorageType::Ssd(caps) if caps.supports_nvme_sanitize => quirk.broken_sanitize,
+            _ => quirk.frozen_security,
+        }
+    }
+
+    /// handles wear leveling for SSDs and Flash storage
+    fn handle_wear_leveling(&self, path: &Path, file: &mut File) -> Result<()> {
+        // for SSDs/Flash, first try TRIM if available
+        if let StorageType::Ssd(caps) | StorageType::Flash(caps) = &self.storage_type {
+            if let Some(quirk) = self.device_quirk(path).filter(|quirk| quirk.broken_trim) {
+                warn!("Skipping TRIM on {}: {}", quirk.model, quirk.reason());
+            } else if caps.supports_trim {
+                debug!("Attempting TRIM operation");
+                self.perform_trim_operation(path, file)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// sets the buffer size for I/O operations
+    ///
+    /// # Arguments
+    /// * `size` - The new buffer size in bytes (minimum 4KB, maximum 16MB)
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_buffer_size(mut self, size: usize) -> Self {
+        const MIN_BUFFER: usize = 4 * 1024; // 4KB
+        const MAX_BUFFER: usize = 16 * 1024 * 1024; // 16MB
+
+        self.buffer_size = size.clamp(MIN_BUFFER, MAX_BUFFER);
+        self
+    }
+
+    /// adapts the write size to the measured throughput during each pass
+    ///
+    /// enabled by default: starting from the buffer size, writes grow while that
+    /// makes them faster and shrink when a write stalls or memory runs low
+    ///
+    /// # Arguments
+    /// * `enabled` - False to always write in chunks of the buffer size
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_adaptive_buffer(mut self, enabled: bool) -> Self {
+        self.adaptive_buffer = enabled;
+        self
+    }
+
+    /// fills regular files of up to 16 MiB through a memory mapping instead of
+    /// a seek/write loop (unix)
+    ///
+    /// enabled by default; mapping, filling and msync-ing a small file saves the
+    /// write and read-back system calls per chunk, which makes it 10-25% faster
+    ///
+    /// # Arguments
+    /// * `enabled` - False to always use the write loop
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_mmap(mut self, enabled: bool) -> Self {
+        self.mmap = enabled;
+        self
+    }
+
+    /// sizes and places writes in whole blocks of the storage, as detected in
+    /// `StorageInfo::physical_block_size`, so a drive with 4 KiB sectors is
+    /// never sent part of a sector it would have to read, modify and write
+    /// back. a target that doesn't end on a block boundary gets its last
+    /// partial block in a write of its own
+    ///
+    /// defaults to 512 bytes. device targets are asked for their physical
+    /// sector size as well, and the larger of the two is used
+    ///
+    /// # Arguments
+    /// * `size` - Block size in bytes; anything but a power of two from 512
+    ///   to 64 KiB is ignored
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_block_size(mut self, size: usize) -> Self {
+        if size.is_power_of_two() && (512..=64 * 1024).contains(&size) {
+            self.block_size = size;
+        } else {
+            warn!("Ignoring block size of {} bytes", size);
+        }
+        self
+    }
+
+    /// sets the number of threads overwriting a large target, each in its own
+    /// region
+    ///
+    /// defaults to up to 4 on NVMe drives, where a single thread can't keep up
+    /// with the device, and 1 elsewhere. regions are at least 16 MiB, so small
+    /// targets use fewer threads
+    ///
+    /// # Arguments
+    /// * `threads` - Number of worker threads (1 to 64)
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.clamp(1, 64);
+        self
+    }
+
+    /// sets how much Basic verification reads and where from
+    ///
+    /// defaults to 1% of the 4 KiB blocks at random. stratified placement takes
+    /// one block from each stretch of the target instead, so a sample can't
+    /// miss a whole region by chance. the first and the last block are always
+    /// read
+    ///
+    /// # Arguments
+    /// * `rate` - Share of the blocks to read, from 0.0 to 1.0
+    /// * `placement` - Where the blocks are taken from
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_sampling(mut self, rate: f64, placement: SamplePlacement) -> Self {
+        self.sample_rate = rate.clamp(0.0, 1.0);
+        self.sample_placement = placement;
+        self
+    }
+
+    /// seeds the generator behind random passes, so they write the same bytes
+    /// on every run
+    ///
+    /// only for tests and simulations: anyone who knows the seed can tell the
+    /// overwritten data from what was there before. by default each random
+    /// pass is seeded from the operating system's generator
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the generator the passes' keystreams are drawn from
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.seeds = SeedSource::seeded(seed);
+        self
+    }
+
+    /// has Enhanced verification end with one more full read, done by running
+    /// `<program> verify` in a separate process
+    ///
+    /// that process shares no handle, mapping or cached page with this one, so
+    /// data that only reads back correctly here is caught. random data can only
+    /// be checked for entropy there, repeated patterns are checked byte for byte
+    ///
+    /// # Arguments
+    /// * `program` - shred executable to run, e.g. `std::env::current_exe()`
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_verifier_process<P: AsRef<Path>>(mut self, program: P) -> Self {
+        self.verifier_process = Some(program.as_ref().to_path_buf());
+        self
+    }
+
+    /// runs secure erase and TRIM in an elevated helper process, `<program>
+    /// helper`, instead of in this one
+    ///
+    /// for wipes run as an ordinary user: their own files are overwritten by
+    /// this process, and only the hardware operations need root. the helper is
+    /// started through sudo the first time one is needed and only acts on
+    /// targets the user could open for writing
+    ///
+    /// # Arguments
+    /// * `program` - shred executable to run, e.g. `std::env::current_exe()`
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_helper<P: AsRef<Path>>(mut self, program: P) -> Self {
+        self.helper = Some(helper::Helper::new(program));
+        self
+    }
+
+    /// overwrites each target on a thread of its own that is sandboxed once the
+    /// target is open (linux)
+    ///
+    /// on linux the thread keeps only the capabilities the rest of the wipe
+    /// uses, can't gain new privileges and gets a seccomp filter allowing only
+    /// the system calls a wipe makes, so it can't start programs, open sockets
+    /// or mount anything, and a wipe driven by untrusted input can do little
+    /// else. the progress callback runs on that thread and is confined too.
+    /// hardware erase goes through the helper (`with_helper`), which is started
+    /// before; without one it fails and the target is overwritten instead, and
+    /// the separate verifier can't be used at all. on macOS the first
+    /// sandboxed wipe confines the whole process for good: no networking and
+    /// no new programs. other platforms wipe as usual with a warning
+    ///
+    /// # Arguments
+    /// * `enabled` - True to sandbox wipes
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_sandbox(mut self, enabled: bool) -> Self {
+        self.sandbox = enabled;
+        self
+    }
+
+    /// overwrites only the first and last `region` bytes of devices and of
+    /// each of their partitions, after erasing their signatures, instead of
+    /// running the standard's passes
+    ///
+    /// meant for redeploying devices that held nothing sensitive in seconds
+    /// instead of hours: the partition table, boot code and superblocks are
+    /// gone, but the data in between is not, so reports are marked quick and
+    /// record no NIST action, and a policy asking for one refuses it. files are
+    /// refused; they are always overwritten in full
+    ///
+    /// # Arguments
+    /// * `region` - Bytes to overwrite at each end, e.g. 16 MiB
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_quick_wipe(mut self, region: u64) -> Self {
+        self.quick = Some(region);
+        self
+    }
+
+    /// erases the filesystem, RAID and LVM signatures of a device before its
+    /// first pass, so a wipe that is cut short still leaves nothing that
+    /// mounts or assembles. files are not affected
+    ///
+    /// # Arguments
+    /// * `enabled` - True to erase signatures first
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_signature_erase(mut self, enabled: bool) -> Self {
+        self.erase_signatures = enabled;
+        self
+    }
+
+    /// makes every write reach the media before it returns, by opening the
+    /// target with O_DSYNC (FILE_FLAG_WRITE_THROUGH on windows)
+    ///
+    /// each pass is synced when it ends anyway; with this, no part of a pass
+    /// waits in a cache, so an interrupted wipe leaves exactly what was written.
+    /// drives that support it get FUA writes, others a cache flush per write,
+    /// which makes wiping several times slower, most of all on hard disks. small
+    /// files are then written with the write loop rather than a memory mapping
+    ///
+    /// # Arguments
+    /// * `enabled` - True to write synchronously
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_sync_writes(mut self, enabled: bool) -> Self {
+        self.sync_writes = enabled;
+        self
+    }
+
+    /// truncates wiped files to a few random, ever smaller sizes after the last
+    /// pass, syncing each, so the journal and the last size recorded before the
+    /// unlink don't give the original size away
+    ///
+    /// # Arguments
+    /// * `enabled` - True to shrink wiped files in random steps
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_size_obfuscation(mut self, enabled: bool) -> Self {
+        self.obfuscate_size = enabled;
+        self
+    }
+
+    /// reads each chunk before writing it and skips the write if the chunk
+    /// already holds the pattern
+    ///
+    /// on mostly blank targets, such as freshly zeroed thin-provisioned images,
+    /// the zero passes then write next to nothing and the image stays sparse.
+    /// everywhere else it costs a read per chunk, and random passes never match.
+    /// skipped bytes are not counted in the report's bytes written
+    ///
+    /// # Arguments
+    /// * `enabled` - True to skip chunks that already match
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_skip_matching(mut self, enabled: bool) -> Self {
+        self.skip_matching = enabled;
+        self
+    }
+
+    /// hashes the target's contents before the first pass and records the
+    /// SHA-256 in the report, to prove which artifact was destroyed
+    ///
+    /// the target is read in full once more, after the safety checks pass
+    ///
+    /// # Arguments
+    /// * `enabled` - True to hash targets before wiping them
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_content_hash(mut self, enabled: bool) -> Self {
+        self.hash_contents = enabled;
+        self
+    }
+
+    /// after wiping, reads the whole volume the target was on (or the wiped
+    /// device) looking for sectors that begin like ones sampled from the target
+    /// beforehand, and records how many were found in the report
+    ///
+    /// finds copies the overwrite couldn't reach, e.g. in the journal, in
+    /// snapshots or left behind by earlier saves of the file. reading a volume
+    /// takes as long as a pass over it and usually needs root
+    ///
+    /// # Arguments
+    /// * `enabled` - True to scan for remnants after wiping
+    ///
+    /// # Returns
+    /// the shredder instance for method chaining
+    pub fn with_remnant_scan(mut self, enabled: bool) -> Self {
+        self.scan_remnants = enabled;
+        self
+    }
+
+    /// on btrfs, moves the target's data into a copy with copy-on-write
+    /// disabled so that subsequent passes overwrite it in place
+    ///
+    /// the original extents are released rather than overwritten, so the
+    /// limitation is still recorded in the report
+    pub fn with_nocow_copy(mut self, enabled: bool) -> Self {
+        self.nocow_copy = enabled;
+        self
+    }
+
+    /// sets how snapshots that may reference the target are handled
+    pub fn with_snapshot_policy(mut self, policy: SnapshotPolicy) -> Self {
+        self.snapshot_policy = policy;
+        self
+    }
+
+    /// unmounts filesystems on a target device before wiping it
+    ///
+    /// by default mounted devices are refused
+    pub fn with_unmount(mut self, enabled: bool) -> Self {
+        self.unmount = enabled;
+        self
+    }
+
+    /// adds directories to the protected-path denylist
+    ///
+    /// the built-in entries (/, /etc, C:\Windows, the home directory, ...)
+    /// always stay in place
+    pub fn with_protected_paths<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        for path in paths {
+            self.protected_paths.add(path);
+        }
+        self
+    }
+
+    /// allows wiping a device that holds an operating system
+    ///
+    /// meant for wiping an installed system from a live or offline
+    /// environment; callers should make the operator confirm the device
+    /// (e.g. by its serial number from [`device::device_info`]) first
+    pub fn with_allow_system_disk(mut self, allowed: bool) -> Self {
+        self.allow_system_disk = allowed;
+        self
+    }
+
+    /// runs multi-pass legacy standards such as Gutmann on SSDs and flash
+    /// instead of refusing them
+    ///
+    /// wear leveling sends every pass to fresh blocks, so the extra passes
+    /// only wear the media; the report records that the wipe was forced
+    pub fn with_force_multipass(mut self, forced: bool) -> Self {
+        self.force_multipass = forced;
+        self
+    }
+
+    /// adds requirements to those of the system policy, which always apply;
+    /// the stricter of each is enforced
+    ///
+    /// # Arguments
+    /// * `policy` - e.g. one loaded with [`Policy::load`]
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = self.policy.merge(policy);
+        self
+    }
+
+    /// sets the table of drive models whose hardware erase or TRIM is skipped;
+    /// the built-in table if not set
+    ///
+    /// # Arguments
+    /// * `quirks` - e.g. `Quirks::builtin().with_entries(config.quirks)`
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// sets how other processes holding the target open are handled
+    pub fn with_open_file_policy(mut self, policy: OpenFilePolicy) -> Self {
+        self.open_file_policy = policy;
+        self
+    }
+
+    /// clears immutable and append-only flags (chattr +i/+a, chflags
+    /// uchg/schg/uappnd/sappnd) before wiping instead of refusing the file
+    ///
+    /// requires root (CAP_LINUX_IMMUTABLE on linux)
+    pub fn with_clear_protection_flags(mut self, enabled: bool) -> Self {
+        self.clear_protection_flags = enabled;
+        self
+    }
+
+    /// clears the read-only, hidden and system attributes of the target on
+    /// windows before wiping (enabled by default)
+    ///
+    /// when disabled, read-only files fail to open for writing
+    pub fn with_clear_attributes(mut self, enabled: bool) -> Self {
+        self.clear_attributes = enabled;
+        self
+    }
+
+    /// reports the progress of each overwrite pass to `callback`, e.g. to
+    /// draw a progress bar
+    ///
+    /// the callback runs on the wiping thread after every buffer written, so
+    /// it should return quickly
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// saves checkpoints of device wipes in `dir`, so one cut short by a power
+    /// loss or crash resumes near where it stopped when the same device is
+    /// wiped again with the same standard
+    ///
+    /// checkpointed passes are written front to back on one thread. files
+    /// are small enough to start over and aren't checkpointed
+    pub fn with_checkpoint_dir(mut self, dir: PathBuf) -> Self {
+        self.checkpoint_dir = Some(dir);
+        self
+    }
+
+    /// runs the next wipe as a job registered with [`jobs::register`], so its
+    /// id is known before the wipe starts
+    ///
+    /// only a job that is still queued is taken; later wipes by this shredder
+    /// get jobs of their own
+    pub fn with_job(mut self, job: JobId) -> Self {
+        self.job = Some(job);
+        self
+    }
+
+    /// gets the current buffer size
+    pub fn get_buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+}
+
+/// wraps an error in the pass, phase and offset it happened at, for `map_err`;
+/// errors that already carry them are left alone
+fn pass_failed<E: Into<WipeError>>(
+    pass: usize,
+    phase: WipePhase,
+    offset: u64,
+) -> impl FnOnce(E) -> WipeError {
+    move |e| match e.into() {
+        e @ WipeError::PassFailed { .. } => e,
+        e => WipeError::PassFailed {
+            pass,
+            phase,
+            offset,
+            source: Box::new(e),
+        },
+    }
+}
+
+/// reads a whole target in 1 MiB chunks and compares it with what `source`
+/// writes, front to back or back to front
+fn compare_full(
+    file: &mut File,
+    file_size: u64,
+    source: &PassSource,
+    pass: usize,
+    backwards: bool,
+) -> Result<()> {
+    const CHUNK: u64 = 1024 * 1024;
+
+    let mut verify_buf = Zeroizing::new(vec![0u8; CHUNK as usize]);
+    let mut expected = Zeroizing::new(vec![0u8; CHUNK as usize]);
+    let chunks = file_size.div_ceil(CHUNK);
+    for index in 0..chunks {
+        let chunk = if backwards { chunks - 1 - index } else { index };
+        let offset = chunk * CHUNK;
+        let len = (file_size - offset).min(CHUNK) as usize;
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_exact(&mut verify_buf[..len]))
+            .map_err(pass_failed(pass, WipePhase::Verify, offset))?;
+        source.fill(offset, &mut expected[..len]);
+
+        if verify_buf[..len] != expected[..len] {
+            return Err(pass_failed(pass, WipePhase::Verify, offset)(
+                WipeError::VerificationFailed(format!(
+                    "Pattern mismatch during full verification in the {} bytes",
+                    len
+                )),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// reads a whole target and compares the SHA-256 of what was read with the
+/// SHA-256 of what `source` writes; returns the digest as lowercase hex
+fn compare_hashes(
+    file: &mut File,
+    file_size: u64,
+    source: &PassSource,
+    pass: usize,
+) -> Result<String> {
+    const CHUNK: u64 = 1024 * 1024;
+
+    let mut read = hash::Sha256::new();
+    let mut written = hash::Sha256::new();
+    let mut buffer = Zeroizing::new(vec![0u8; CHUNK as usize]);
+    file.seek(SeekFrom::Start(0))
+        .map_err(pass_failed(pass, WipePhase::Verify, 0))?;
+    let mut offset = 0;
+    while offset < file_size {
+        let len = (file_size - offset).min(CHUNK) as usize;
+        file.read_exact(&mut buffer[..len]).map_err(pass_failed(
+            pass,
+            WipePhase::Verify,
+            offset,
+        ))?;
+        read.update(&buffer[..len]);
+        source.fill(offset, &mut buffer[..len]);
+        written.update(&buffer[..len]);
+        offset += len as u64;
+    }
+
+    let (read, written) = (read.finish_hex(), written.finish_hex());
+    if read != written {
+        return Err(pass_failed(pass, WipePhase::Verify, 0)(
+            WipeError::VerificationFailed(format!(
+                "SHA-256 of the target {} doesn't match the {} written",
+                read, written
+            )),
+        ));
+    }
+    Ok(written)
+}
+
+/// a chunk read straight back after writing didn't hold what was written
+fn readback_mismatch(pass: usize, offset: u64) -> WipeError {
+    pass_failed(pass, WipePhase::Verify, offset)(WipeError::VerificationFailed(
+        "Immediate verification failed".to_string(),
+    ))
+}
+
+/// raw volume a mounted filesystem lives on, if it can be read
+fn remnant_volume(mount: &filesystem::MountInfo) -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        // \\?\Volume{guid}\ opens the volume itself without the trailing separator
+        mount
+            .source
+            .starts_with(r"\\?\Volume")
+            .then(|| PathBuf::from(mount.source.trim_end_matches('\\')))
+    }
+    #[cfg(not(windows))]
+    {
+        let source = PathBuf::from(&mount.source);
+        device::is_block_device(&source).then_some(source)
+    }
+}
+
+/// size of the target in bytes
+///
+/// block devices report a length of 0 in their metadata, so their size is
+/// asked from the device itself
+fn target_size(file: &mut File) -> Result<u64> {
+    let metadata = file.metadata()?;
+    if metadata.is_file() {
+        Ok(metadata.len())
+    } else {
+        device::device_size(file)
+    }
+}
+
+/// opens a target for reading with its cached pages dropped, so reads come from
+/// the media; the data must have been synced
+///
+/// linux and freebsd evict the pages with posix_fadvise, macOS stops caching
+/// reads on the handle with F_NOCACHE, and on windows opening a non-buffered
+/// handle purges the file from the cache
+fn open_uncached(path: &Path) -> io::Result<File> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        drop(
+            std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(winapi::um::winbase::FILE_FLAG_NO_BUFFERING)
+                .open(path)?,
+        );
+    }
+
+    let file = File::open(path)?;
+    drop_cached_pages(&file);
+    Ok(file)
+}
+
+/// evicts the cached pages of an open target (linux, freebsd), or stops
+/// caching reads through the handle (macOS)
+fn drop_cached_pages(file: &File) {
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    unsafe {
+        use std::os::unix::io::AsRawFd;
+        libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED);
+    }
+    #[cfg(target_os = "macos")]
+    unsafe {
+        use std::os::unix::io::AsRawFd;
+        libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1);
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "macos"
+    )))]
+    let _ = file;
+}
+
+/// rounds a buffer size down to whole blocks
+fn whole_blocks(size: usize, block: usize) -> usize {
+    (size - size % block).max(block)
+}
+
+/// threads a shredder uses by default for the storage type
+fn default_threads(storage_type: &StorageType) -> usize {
+    match storage_type {
+        StorageType::Ssd(caps) if caps.supports_nvme_sanitize => {
+            std::thread::available_parallelism().map_or(1, |n| n.get().min(4))
+        }
+        _ => 1,
+    }
+}
+
+/// writes all of `buf` at `offset`; the file position is left alone on unix
+/// but moved past what was written on windows, so callers mixing this with
+/// sequential writes must seek first
+#[cfg(unix)]
+fn write_all_at(file: &File, buf: &[u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn write_all_at(file: &File, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_write(buf, offset)? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            n => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// fills `buf` from `offset`; like `write_all_at`, this moves the file
+/// position on windows but not on unix
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+        }
     }
+    Ok(())
 }
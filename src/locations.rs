@@ -0,0 +1,260 @@
+use crate::Result;
+use std::path::PathBuf;
+
+/// lists everything in the Recycle Bin of every volume
+///
+/// each deleted item is stored in `X:\$Recycle.Bin\<SID>\` as a `$R...` file
+/// or directory holding the data and a `$I...` file holding its original name,
+/// path and deletion time; both are returned, data first
+///
+/// # Returns
+/// * `Result<Vec<PathBuf>>` - Recycle Bin entries readable by the current user
+pub fn recycle_bin_entries() -> Result<Vec<PathBuf>> {
+    #[cfg(windows)]
+    {
+        use winapi::um::fileapi::GetLogicalDrives;
+
+        let drives = unsafe { GetLogicalDrives() };
+        let mut entries = Vec::new();
+
+        for index in 0..26u8 {
+            if drives & (1 << index) == 0 {
+                continue;
+            }
+            let bin = PathBuf::from(format!("{}:\\$Recycle.Bin", (b'A' + index) as char));
+            let Ok(users) = std::fs::read_dir(&bin) else {
+                continue;
+            };
+
+            // one folder per user SID; other users' folders need admin rights
+            for user in users.flatten() {
+                let items = match std::fs::read_dir(user.path()) {
+                    Ok(items) => items,
+                    Err(e) => {
+                        log::debug!("Skipping {}: {}", user.path().display(), e);
+                        continue;
+                    }
+                };
+                for item in items.flatten() {
+                    // desktop.ini only marks the folder as a Recycle Bin
+                    if !item.file_name().eq_ignore_ascii_case("desktop.ini") {
+                        entries.push(item.path());
+                    }
+                }
+            }
+        }
+
+        // data first: if a run is aborted, leftover data stays listed in Explorer via its $I file
+        entries.sort_by_key(|entry| {
+            entry
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("$I"))
+        });
+        Ok(entries)
+    }
+    #[cfg(not(windows))]
+    {
+        Err(crate::WipeError::UnsupportedOperation(
+            "The Recycle Bin only exists on Windows".into(),
+        ))
+    }
+}
+
+/// lists everything in the user's trash
+///
+/// on macOS that is `~/.Trash` plus the per-volume `/Volumes/*/.Trashes/<uid>`
+/// folders, including the .DS_Store files that record where each item was
+/// deleted from. on linux it is the XDG trash in `~/.local/share/Trash` and
+/// the `.Trash/<uid>` and `.Trash-<uid>` folders of other mounts, with the
+/// `info/*.trashinfo` records holding each item's original path and deletion time
+///
+/// # Returns
+/// * `Result<Vec<PathBuf>>` - Trash entries of the invoking user
+pub fn trash_entries() -> Result<Vec<PathBuf>> {
+    #[cfg(target_os = "macos")]
+    {
+        let home = user_home().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Could not determine home directory",
+            )
+        })?;
+
+        let mut entries = match list_dir(&home.join(".Trash")) {
+            Ok(entries) => entries,
+            // ~/.Trash is guarded by TCC, not by file permissions
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                return Err(crate::WipeError::UnsupportedOperation(
+                    "Access to ~/.Trash was denied; grant your terminal Full Disk Access in \
+                     System Settings > Privacy & Security"
+                        .into(),
+                ))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let uid = invoking_uid();
+        if let Ok(volumes) = std::fs::read_dir("/Volumes") {
+            for volume in volumes.flatten() {
+                // the boot volume shows up as a symlink to /
+                if volume.file_type().is_ok_and(|t| t.is_symlink()) {
+                    continue;
+                }
+                let trash = volume.path().join(".Trashes").join(uid.to_string());
+                entries.extend(list_dir(&trash).unwrap_or_default());
+            }
+        }
+
+        Ok(entries)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // under sudo the environment belongs to root, so only trust it otherwise
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .filter(|_| std::env::var_os("SUDO_UID").is_none())
+            .map(PathBuf::from)
+            .or_else(|| user_home().map(|home| home.join(".local/share")))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "Could not determine home directory",
+                )
+            })?;
+
+        let uid = invoking_uid();
+        let mut trash_dirs = vec![data_home.join("Trash")];
+        for mount_point in crate::filesystem::mount_points()? {
+            trash_dirs.push(mount_point.join(".Trash").join(uid.to_string()));
+            trash_dirs.push(mount_point.join(format!(".Trash-{}", uid)));
+        }
+        trash_dirs.dedup();
+
+        let mut entries = Vec::new();
+        for trash in trash_dirs {
+            entries.extend(xdg_trash_entries(&trash)?);
+        }
+        Ok(entries)
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        Err(crate::WipeError::UnsupportedOperation(
+            "Trash wiping is not supported on this platform".into(),
+        ))
+    }
+}
+
+/// locates the Windows hibernation file, hiberfil.sys, which holds a snapshot of
+/// memory including the contents of files that were open before hibernating
+///
+/// the running Windows keeps its own hiberfil.sys locked, so without `root`
+/// this fails with guidance unless hibernation is currently off. with `root`
+/// the file is looked up on that volume instead, e.g. an offline Windows
+/// installation mounted from another OS
+///
+/// # Arguments
+/// * `root` - Root of the Windows volume to look on, or None for the running system
+///
+/// # Returns
+/// * `Result<Option<PathBuf>>` - Path to hiberfil.sys, or None if there is none
+pub fn hibernation_file(root: Option<&std::path::Path>) -> Result<Option<PathBuf>> {
+    let path =
+        match root {
+            Some(root) => root.join("hiberfil.sys"),
+            #[cfg(windows)]
+            None => {
+                let system_drive = std::env::var_os("SystemDrive").unwrap_or_else(|| "C:".into());
+                let mut path = PathBuf::from(system_drive);
+                path.push("\\hiberfil.sys");
+                path
+            }
+            #[cfg(not(windows))]
+            None => return Err(crate::WipeError::UnsupportedOperation(
+                "hiberfil.sys only exists on Windows volumes; pass the root of one to look there"
+                    .into(),
+            )),
+        };
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    #[cfg(windows)]
+    if root.is_none() {
+        use winapi::shared::winerror::ERROR_SHARING_VIOLATION;
+
+        let in_use = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .is_err_and(|e| e.raw_os_error() == Some(ERROR_SHARING_VIOLATION as i32));
+        if in_use {
+            return Err(crate::WipeError::UnsupportedOperation(
+                "hiberfil.sys is locked while Windows is running. Wipe it from another OS by \
+                 passing the volume's mount point, or turn hibernation off with \
+                 `powercfg /hibernate off` (which deletes it without overwriting) and wipe free space"
+                    .into(),
+            ));
+        }
+    }
+
+    Ok(Some(path))
+}
+
+/// contents of one XDG trash directory
+///
+/// the trashed data in files/ comes first, then the info/ records and the
+/// directorysizes cache, which also names every trashed directory
+#[cfg(target_os = "linux")]
+fn xdg_trash_entries(trash: &std::path::Path) -> Result<Vec<PathBuf>> {
+    let mut entries = list_dir(&trash.join("files"))?;
+    entries.extend(list_dir(&trash.join("info"))?);
+    // left behind by some file managers when emptying fails halfway
+    entries.extend(list_dir(&trash.join("expunged"))?);
+
+    let sizes = trash.join("directorysizes");
+    if sizes.exists() {
+        entries.push(sizes);
+    }
+    Ok(entries)
+}
+
+/// lists the entries of a directory; a missing directory has none
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn list_dir(dir: &std::path::Path) -> std::io::Result<Vec<PathBuf>> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries.map(|entry| entry.map(|e| e.path())).collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// uid of the user who invoked the tool, looking through sudo
+#[cfg(unix)]
+pub(crate) fn invoking_uid() -> u32 {
+    std::env::var("SUDO_UID")
+        .ok()
+        .and_then(|uid| uid.parse().ok())
+        .unwrap_or_else(|| unsafe { libc::getuid() })
+}
+
+/// home directory of the user who invoked the tool
+///
+/// under sudo $HOME may point at root's home, so the password database is
+/// consulted for the invoking user instead
+#[cfg(unix)]
+pub(crate) fn user_home() -> Option<PathBuf> {
+    use std::ffi::CStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    if std::env::var_os("SUDO_UID").is_none() {
+        if let Some(home) = std::env::var_os("HOME") {
+            return Some(PathBuf::from(home));
+        }
+    }
+
+    let passwd = unsafe { libc::getpwuid(invoking_uid()) };
+    if passwd.is_null() {
+        return None;
+    }
+    let dir = unsafe { CStr::from_ptr((*passwd).pw_dir) };
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(dir.to_bytes())))
+}
@@ -0,0 +1,149 @@
+//! one wipe per target at a time, so two wipes can't interleave their passes
+//! and each verify what the other wrote
+//!
+//! within a process (the daemon, the API, `wipe_many` on several threads) a
+//! registry of the targets being wiped is checked; across processes an
+//! advisory lock is taken on the target itself: flock on unix, LockFileEx on
+//! windows. locks are only held against other shredders; other programs can
+//! still write to the target
+
+use crate::{Result, WipeError};
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// targets being wiped by this process
+static WIPING: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+/// held for the duration of a wipe
+#[derive(Debug)]
+pub(crate) struct TargetLock {
+    key: PathBuf,
+    /// the advisory lock lasts as long as this handle
+    _file: Option<File>,
+}
+
+impl TargetLock {
+    /// locks `path`, or fails with `WipeError::TargetBusy` if another wipe has it
+    pub(crate) fn acquire(path: &Path) -> Result<Self> {
+        // symlinks and other names of the same file lock the same target
+        let key = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        {
+            let mut wiping = WIPING.lock().unwrap_or_else(|e| e.into_inner());
+            if !wiping.get_or_insert_with(HashSet::new).insert(key.clone()) {
+                return Err(WipeError::TargetBusy(path.to_path_buf()));
+            }
+        }
+        // dropped, and so unregistered, if the advisory lock can't be had
+        let mut lock = Self { key, _file: None };
+        lock._file = lock_file(&lock.key)?;
+        Ok(lock)
+    }
+}
+
+impl Drop for TargetLock {
+    fn drop(&mut self) {
+        let mut wiping = WIPING.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(wiping) = wiping.as_mut() {
+            wiping.remove(&self.key);
+        }
+    }
+}
+
+/// takes the advisory lock; targets that can't be opened, e.g. ones that
+/// don't exist, are only registered, and the wipe reports why it can't open them
+#[cfg(unix)]
+fn lock_file(path: &Path) -> Result<Option<File>> {
+    use std::os::unix::io::AsRawFd;
+
+    let Ok(file) = File::open(path) else {
+        return Ok(None);
+    };
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let error = std::io::Error::last_os_error();
+        return match error.raw_os_error() {
+            Some(libc::EWOULDBLOCK) => Err(WipeError::TargetBusy(path.to_path_buf())),
+            _ => {
+                log::debug!("Unable to lock {}: {}", path.display(), error);
+                Ok(None)
+            }
+        };
+    }
+    Ok(Some(file))
+}
+
+/// windows byte-range locks are mandatory, so the byte locked is one far past
+/// the end that no write ever reaches
+#[cfg(windows)]
+fn lock_file(path: &Path) -> Result<Option<File>> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use winapi::shared::winerror::ERROR_LOCK_VIOLATION;
+    use winapi::um::fileapi::LockFileEx;
+    use winapi::um::minwinbase::{LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY, OVERLAPPED};
+    use winapi::um::winnt::{FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE};
+
+    let Ok(file) = std::fs::OpenOptions::new()
+        .read(true)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE)
+        .open(path)
+    else {
+        return Ok(None);
+    };
+    let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+    unsafe {
+        let offset = overlapped.u.s_mut();
+        offset.Offset = 0xffff_fffe;
+        offset.OffsetHigh = 0xffff_ffff;
+    }
+    let locked = unsafe {
+        LockFileEx(
+            file.as_raw_handle() as _,
+            LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY,
+            0,
+            1,
+            0,
+            &mut overlapped,
+        )
+    };
+    if locked == 0 {
+        let error = std::io::Error::last_os_error();
+        return match error.raw_os_error() {
+            Some(code) if code == ERROR_LOCK_VIOLATION as i32 => {
+                Err(WipeError::TargetBusy(path.to_path_buf()))
+            }
+            _ => {
+                log::debug!("Unable to lock {}: {}", path.display(), error);
+                Ok(None)
+            }
+        };
+    }
+    Ok(Some(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("target");
+        std::fs::write(&path, b"data").unwrap();
+
+        let lock = TargetLock::acquire(&path).unwrap();
+        assert!(matches!(
+            TargetLock::acquire(&dir.path().join(".").join("target")),
+            Err(WipeError::TargetBusy(_))
+        ));
+        // another open file description, as another process would have
+        assert!(matches!(lock_file(&path), Err(WipeError::TargetBusy(_))));
+        drop(lock);
+
+        let again = TargetLock::acquire(&path).unwrap();
+        drop(again);
+        // targets that don't exist are only registered
+        assert!(TargetLock::acquire(&dir.path().join("missing")).is_ok());
+    }
+}
@@ -1,15 +1,64 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use shredder::{
-    standards::{Nist80088Config, SanitizationMethod, VerificationLevel, WipeStandard},
+    audit::AuditLog,
+    bench,
+    config::{Config, JobConfig},
+    device, estimate, filesystem,
+    handles::OpenFilePolicy,
+    helper, integrate, locations,
+    notify::{self, Notification},
+    patterns::WipePattern,
+    policy::Policy,
+    polkit,
+    power::SleepInhibitor,
+    presets::Preset,
+    progress::Progress,
+    quirks::Quirks,
+    remote::{self, RemoteOptions, RemoteTarget},
+    report::{self, BatchSummary, MultiWipeReport, WipeReport},
+    schedule::{CalendarTime, Schedule},
+    snapshots::SnapshotPolicy,
+    standards::{NamedStandard, VerificationLevel, WipeStandard},
     storage::{StorageCapabilities, StorageInfo, StorageType},
-    Shredder,
+    swap::{self, SwapArea},
+    systemd, trash_put,
+    verify::{self, SamplePlacement},
+    Shredder, WipeError,
 };
 
+mod tui;
+mod wizard;
+
+/// process exit codes, so scripts can branch on the outcome (listed in the README)
+mod exit_code {
+    /// everything was wiped
+    pub const SUCCESS: i32 = 0;
+    /// any other error, e.g. a missing path or an I/O error during the wipe
+    pub const FAILURE: i32 = 1;
+    /// invalid command line (also used by clap)
+    pub const USAGE: i32 = 2;
+    /// not running as root/administrator, or the OS denied access
+    pub const PERMISSION_DENIED: i32 = 3;
+    /// the wipe finished but reading back the target did not match
+    pub const VERIFICATION_FAILED: i32 = 4;
+    /// the device, filesystem or platform doesn't support the operation
+    pub const UNSUPPORTED: i32 = 5;
+    /// some targets of a batch were wiped, others failed
+    pub const PARTIAL_FAILURE: i32 = 6;
+    /// a safety check refused the target before anything was modified
+    pub const REFUSED: i32 = 7;
+    /// the user declined the confirmation prompt
+    pub const CANCELLED: i32 = 8;
+}
+
 /// a secure file deletion tool that says Auf Wiedersen to your files
-#[derive(Parser)]
+#[derive(Parser, Clone)]
 #[command(name = "shred")]
 #[command(author = "alake <g4titan1@gmail.com>")]
 #[command(version)]
@@ -17,18 +66,37 @@ use shredder::{
 #[command(
     long_about = "Securely erases files and devices using various military-grade standards including NIST 800-88, DoD 5220.22-M, and more."
 )]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// path to file or device to securely erase
-    #[arg(help = "Path to file or device to securely erase")]
-    path: PathBuf,
+    #[arg(
+        required_unless_present = "preset",
+        conflicts_with = "preset",
+        help = "Path to file or device to securely erase"
+    )]
+    path: Option<PathBuf>,
+
+    /// application trace bundles to wipe instead of a path
+    #[arg(
+        long,
+        value_name = "NAME",
+        value_delimiter = ',',
+        help = "Wipe a bundle of application traces (browser-caches, shell-history, thumbnails, office-temp)",
+        long_help = "Wipe a bundle of application traces of the invoking user instead of a path. Can be repeated or comma-separated:\n  browser-caches - Disk caches of Firefox, Chrome, Chromium, Edge, Brave and Safari\n  shell-history - Shell and interpreter histories (bash, zsh, fish, PowerShell, python, psql, ...)\n  thumbnails - Thumbnail caches (~/.cache/thumbnails, QuickLook, Explorer thumbcache)\n  office-temp - Office and LibreOffice autorecovery files and recent-document lists"
+    )]
+    preset: Vec<String>,
 
     /// wiping standard to use
     #[arg(
         short,
         long,
+        global = true,
         default_value = "nist",
-        help = "Wiping standard to use (nist, dod, gutmann, vsitr)",
-        long_help = "Available standards:\n  nist - NIST 800-88 (default, recommended)\n  dod - DoD 5220.22-M (3 passes)\n  gutmann - Gutmann 35-pass method\n  vsitr - German VSITR 7-pass standard"
+        help = "Wiping standard to use (nist, nist-clear, auto, dod, gutmann, vsitr)",
+        long_help = "Available standards:\n  nist - NIST 800-88 Purge (default, recommended)\n  nist-clear - NIST 800-88 Clear (1 pass), for media that stays in the organization\n  auto - the NIST 800-88 method for the detected media (secure erase, TRIM + 1 pass, or Purge)\n  dod - DoD 5220.22-M (3 passes)\n  gutmann - Gutmann 35-pass method\n  vsitr - German VSITR 7-pass standard"
     )]
     standard: String,
 
@@ -36,16 +104,39 @@ struct Cli {
     #[arg(
         short,
         long,
+        global = true,
         default_value = "full",
-        help = "Verification level (none, basic, full, enhanced)",
-        long_help = "Verification levels:\n  none - No verification\n  basic - Sample verification\n  full - Complete verification (default)\n  enhanced - Multiple verification passes"
+        help = "Verification level (none, basic, full, enhanced, hash, sampled[:N%])",
+        long_help = "Verification levels:\n  none - No verification\n  basic - Sample verification\n  full - Complete verification (default)\n  enhanced - Two independent full reads through new handles with the cache dropped, the second back to front; with --separate-verifier a third in another process\n  hash - Read every pass back and compare the SHA-256 of what was read with the SHA-256 of what was written; the digests are logged and recorded in the report\n  sampled[:N%] - Read N% (default 1%) of the 4 KiB blocks, one from each stretch of the target, and report the share that could still be unwiped with 95% confidence; for large devices\nWith any level but none, every chunk is also read back right after it is written, which roughly doubles the I/O of a wipe; none only writes."
     )]
     verify: String,
 
+    /// share of blocks read by basic verification
+    #[arg(
+        long,
+        global = true,
+        value_name = "SHARE",
+        default_value = "1%",
+        help = "Share of blocks read by basic verification, e.g. 5%",
+        long_help = "Share of the 4 KiB blocks that --verify basic reads back after a wipe, as a percentage (5%) or a fraction (0.05). The first and the last block are always read. Defaults to 1%."
+    )]
+    sample_rate: String,
+
+    /// placement of the blocks read by basic verification
+    #[arg(
+        long,
+        global = true,
+        default_value = "random",
+        help = "Placement of sampled blocks (random, stratified)",
+        long_help = "Where the blocks read by --verify basic and `shred verify --sample` are taken from:\n  random - Anywhere in the target (default)\n  stratified - One from each of equal stretches of the target, so no region goes unchecked by chance"
+    )]
+    sample_placement: String,
+
     /// force operation without confirmation
     #[arg(
         short,
         long,
+        global = true,
         help = "Force operation without confirmation",
         long_help = "Skip the 'Auf Wiedersen' confirmation prompt. Use with caution!"
     )]
@@ -54,10 +145,624 @@ struct Cli {
     /// skip root/admin check
     #[arg(
         long,
+        global = true,
         help = "Skip root/admin check (use with caution)",
-        long_help = "Skip the root/administrator privilege check. Note: Operations may fail without proper privileges."
+        long_help = "Skip the root/administrator privilege check. Note: Operations may fail without proper privileges. Wiping files and devices doesn't need root: without it, secure erase and TRIM run in an elevated helper started through sudo; with this option they are tried in-process instead."
     )]
     no_root_check: bool,
+
+    /// disable copy-on-write before overwriting (btrfs)
+    #[arg(
+        long,
+        global = true,
+        help = "On btrfs, move data into a NOCOW copy before overwriting",
+        long_help = "On btrfs, copy the file into a new file with copy-on-write disabled (chattr +C) before overwriting, so the passes land on the same blocks. The original extents are only released, so a free-space wipe is still recommended."
+    )]
+    nocow: bool,
+
+    /// snapshot handling
+    #[arg(
+        long,
+        global = true,
+        default_value = "warn",
+        help = "Snapshot handling (ignore, warn, refuse, delete)",
+        long_help = "How to handle btrfs/ZFS snapshots, APFS local (Time Machine) snapshots and Windows Volume Shadow Copies that may still hold the file's data:\n  ignore - Don't look for snapshots\n  warn - Wipe and list the snapshots afterwards (default)\n  refuse - Don't wipe while such snapshots exist\n  delete - Delete the snapshots (btrfs subvolume delete, zfs destroy, tmutil deletelocalsnapshots, vssadmin delete shadows) before wiping"
+    )]
+    snapshots: String,
+
+    /// unmount the target device first
+    #[arg(
+        long,
+        global = true,
+        help = "Unmount filesystems on the target device before wiping",
+        long_help = "When the target is a block device, unmount any mounted filesystems on it or its partitions before wiping. Without this flag mounted devices are refused."
+    )]
+    unmount: bool,
+
+    /// clear immutable/append-only flags
+    #[arg(
+        long,
+        global = true,
+        help = "Clear immutable/append-only flags before wiping",
+        long_help = "Clear the immutable and append-only attributes (chattr +i/+a on Linux, chflags uchg/schg/uappnd/sappnd on macOS and BSD) before wiping. Without this flag such files are refused. Requires root."
+    )]
+    clear_flags: bool,
+
+    /// keep windows file attributes
+    #[arg(
+        long,
+        global = true,
+        help = "Don't clear read-only/hidden/system attributes (Windows)",
+        long_help = "On Windows the read-only, hidden and system attributes are cleared before overwriting, since read-only files can't be opened for writing. Pass this flag to leave them alone; read-only files will then fail to wipe."
+    )]
+    keep_attributes: bool,
+
+    /// open file handling
+    #[arg(
+        long,
+        global = true,
+        default_value = "warn",
+        help = "Handling of files open in other processes (ignore, warn, refuse)",
+        long_help = "How to handle a target that other processes have open (found through /proc on Linux, lsof on macOS and the Restart Manager on Windows). Data those processes write during or after the wipe ends up back on disk:\n  ignore - Don't check\n  warn - Wipe and list the processes afterwards (default)\n  refuse - Don't wipe while other processes have the target open"
+    )]
+    open_files: String,
+
+    /// allow wiping a disk that holds an operating system
+    #[arg(
+        long,
+        global = true,
+        help = "Allow wiping a disk that holds an operating system",
+        long_help = "Allow wiping the system disk or a disk with an installed operating system, e.g. from a live USB. The disk's details are shown and its serial number must be typed in to confirm; --force does not skip this."
+    )]
+    allow_system_disk: bool,
+
+    /// run multi-pass legacy standards on SSDs and flash
+    #[arg(
+        long,
+        global = true,
+        help = "Run multi-pass standards such as Gutmann on SSDs and flash",
+        long_help = "Run DoD, VSITR or Gutmann on SSDs and flash media instead of refusing. Wear leveling sends every pass to fresh blocks, so the extra passes wear the device without reaching the data it remapped; the report records that the wipe was forced. Prefer nist or auto on such media."
+    )]
+    force_multipass: bool,
+
+    /// skip chunks that already hold the pattern
+    #[arg(
+        long,
+        global = true,
+        help = "Don't rewrite chunks that already hold the pattern",
+        long_help = "Read each chunk before overwriting it and skip the write when it already holds the pass's pattern. On mostly blank targets, such as freshly zeroed thin-provisioned images, this saves most of the writes and keeps the image sparse; elsewhere it adds a read per chunk. Random passes never match. Skipped bytes are not counted as written in the report."
+    )]
+    skip_matching: bool,
+
+    /// size obfuscation
+    #[arg(
+        long,
+        global = true,
+        help = "Shrink wiped files to random sizes before removing them",
+        long_help = "After the last pass, truncate each wiped file to four random, ever smaller sizes, syncing after each, before it is released and removed. The filesystem journal and the file's last recorded size then show made-up sizes instead of the original one, like GNU shred's size obfuscation. Devices are not affected."
+    )]
+    obfuscate_size: bool,
+
+    /// synchronous writes
+    #[arg(
+        long,
+        global = true,
+        help = "Make every write reach the disk before the next (O_DSYNC, slow)",
+        long_help = "Open targets with O_DSYNC (FILE_FLAG_WRITE_THROUGH on Windows), so every write is on the media before it returns, using FUA writes where the drive supports them and a cache flush per write otherwise. Each pass is synced when it ends either way; this is for policies that require that nothing of a pass sits in a cache. Expect wipes to be several times slower, most of all on hard disks."
+    )]
+    sync_writes: bool,
+
+    /// sandbox the wiping thread
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "separate_verifier",
+        help = "Sandbox each wipe once its target is open (Linux, macOS)",
+        long_help = "Overwrite each target on a thread that, once the target is open, keeps only the capabilities the rest of the wipe uses, can't gain privileges through setuid programs and gets a seccomp filter allowing only the system calls a wipe makes: it can't start programs, open sockets, mount anything or use io_uring. Hardware erase goes through the elevated helper, started before. Reports, notifications and the audit log are written outside the sandbox. On macOS the whole process is sandboxed from the first wipe on, without networking or starting programs, so mail and desktop notifications are not sent. On other platforms wipes run without it."
+    )]
+    sandbox: bool,
+
+    /// erase signatures before wiping a device
+    #[arg(
+        long,
+        global = true,
+        help = "Erase filesystem, RAID and LVM signatures of a device before the passes",
+        long_help = "Before the first pass over a device, zero the magic bytes of every filesystem, RAID and LVM superblock on it and of their backups (see `shred signatures`). Takes seconds, and a wipe that is interrupted later still leaves nothing that mounts or assembles. Files are not affected."
+    )]
+    erase_signatures: bool,
+
+    /// quick device wipe
+    #[arg(
+        long,
+        global = true,
+        value_name = "MIB",
+        num_args = 0..=1,
+        default_missing_value = "16",
+        help = "Only overwrite the first and last MiB (default 16) of a device and its partitions",
+        long_help = "Instead of the standard's passes, erase the device's signatures and overwrite the first and last MIB MiB (16 with plain --quick) of the device and of each partition on it with zeros, read back unless --verify none. The partition table, boot code, superblocks and the metadata at the end of partitions are destroyed in seconds, but the data in between is NOT: use it to redeploy devices that held nothing sensitive. The report is marked quick and records no NIST 800-88 action; a policy that requires one refuses it. Devices only."
+    )]
+    quick: Option<u64>,
+
+    /// worker threads per target
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Overwrite large targets with N threads, each in its own region",
+        long_help = "Split targets of 32 MiB and more into regions of at least 16 MiB that N threads overwrite and verify side by side, each through its own file descriptor. Helps on NVMe drives, where a single thread can't keep up with the device; on hard disks it makes the heads seek between regions. Defaults to up to 4 threads on NVMe drives and 1 elsewhere."
+    )]
+    threads: Option<usize>,
+
+    /// seconds between progress lines when stderr isn't a terminal
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECONDS",
+        default_value_t = 60,
+        help = "Seconds between progress lines in non-interactive runs (0 turns them off)",
+        long_help = "When stderr isn't a terminal, e.g. under systemd, cron or the daemon, print a line every SECONDS instead of the progress bar: `progress path=\"/dev/sdb\" pass=2/3 percent=41.2 mb_per_s=153.2 elapsed=4h51m eta=6h58m`, so someone tailing the log can see a long wipe is still moving. 0 turns the lines off."
+    )]
+    progress_interval: u64,
+
+    /// hash targets before wiping
+    #[arg(
+        long,
+        global = true,
+        help = "Record the SHA-256 of each target before wiping it",
+        long_help = "Read each target once before the first pass and record the SHA-256 of its contents in the report (--report), the audit log, notifications and API certificates, for chain-of-custody records of exactly which artifact was destroyed. Adds a full read of the target to the wipe."
+    )]
+    hash: bool,
+
+    /// scan for remnants after wiping
+    #[arg(
+        long,
+        global = true,
+        help = "Scan the volume for leftover copies of the data after wiping",
+        long_help = "Before wiping, sample up to 16 distinctive sectors of each target (always the first, which holds the file's magic bytes); afterwards, read the whole volume the file was on, or the wiped device, and report the sectors that still begin like them. Finds copies the overwrite couldn't reach, e.g. in the filesystem journal, snapshots or earlier saves. Reading a volume takes as long as a pass over it and needs root. The number found is recorded in the report, and any remnants are listed as a limitation."
+    )]
+    scan_remnants: bool,
+
+    /// repeat enhanced verification in a separate process
+    #[arg(
+        long,
+        global = true,
+        help = "End enhanced verification with a read in a separate process",
+        long_help = "With --verify enhanced, finish with one more full read done by `shred verify` in a separate process, which shares no file handle, mapping or cached page with the wiping one. Repeated patterns are checked byte for byte there, random data for entropy."
+    )]
+    separate_verifier: bool,
+
+    /// audit records in the system log
+    #[arg(
+        long,
+        global = true,
+        help = "Record each wipe in syslog (Unix) or the Windows Event Log",
+        long_help = "Write a record to the system log when a wipe starts, for every target it wiped, and for every failure: syslog with the auth facility on Unix, the Application event log with source 'shredder' on Windows (event IDs 1 started, 2 finished, 3 failed). Records include the invoking user. Can also be turned on with `audit_log = true` in the configuration file."
+    )]
+    audit_log: bool,
+
+    /// configuration file
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Read settings from this configuration file",
+        long_help = "Read settings from this TOML configuration file instead of the default location ($XDG_CONFIG_HOME/shredder/config.toml or ~/.config/shredder/config.toml, %APPDATA%\\shredder\\config.toml on Windows). Use `protected_paths = [...]` to extend the built-in denylist of paths that are never wiped."
+    )]
+    config: Option<PathBuf>,
+
+    /// report file
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Write a report of the wipe to this file (TOML)",
+        long_help = "After a successful wipe, write what was wiped (path, filesystem, passes, bytes written, the identity of the device, the NIST 800-88 action achieved and limitations) to this file as TOML, one [[wipes]] table per target. With `remote`, the report of the remote machine is saved here; with `verify`, the verification report is written as a [verification] table."
+    )]
+    report: Option<PathBuf>,
+}
+
+/// places where deleted files linger
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// securely wipe the Recycle Bin of every volume (Windows)
+    #[command(
+        name = "recycle-bin",
+        about = "Securely wipe the Recycle Bin of every volume (Windows)",
+        long_about = "Securely wipe everything in the Recycle Bin of every volume, including the $I files that record each item's original name, path and deletion time. Items of other users are included when run as administrator."
+    )]
+    RecycleBin,
+
+    /// securely wipe the trash
+    #[command(
+        about = "Securely wipe the trash (macOS, Linux)",
+        long_about = "Securely wipe everything in the trash of the invoking user, including the metadata that records where each item came from:\n  macOS - ~/.Trash and the .Trashes folders of mounted volumes, with their .DS_Store files\n  Linux - ~/.local/share/Trash and the .Trash-<uid> folders of mounted filesystems, with their info/*.trashinfo records"
+    )]
+    Trash,
+
+    /// trash-put that shreds instead
+    #[command(
+        name = "trash-put",
+        about = "Move files to the trash and shred them after a grace period, like trash-put (Linux)",
+        long_about = "A drop-in for trash-put (and, with the right aliases, rm) that securely shreds what it is given. With a grace period (--grace, or trash_grace_secs in the configuration file) the files go to the trash first, where the file manager can restore them, and are shredded once it is over; without one they are shredded right away. Items whose grace period is over are shredded by a background process, and by every later run in case it was not running. The binary runs this command when it is invoked as trash-put, e.g. through a symlink, in which case -v means --verbose. --force ignores nonexistent files and never prompts; -d, -r and -R are accepted and ignored, as trash-put does. A grace period needs the freedesktop.org trash, so it is only supported on Linux."
+    )]
+    TrashPut {
+        /// files and directories to delete
+        #[arg(value_name = "FILE", required_unless_present = "purge")]
+        files: Vec<PathBuf>,
+
+        /// seconds to keep the files in the trash
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            help = "Keep the files in the trash for SECONDS before shredding them (0 shreds them right away) [default: trash_grace_secs of the configuration file, or 0]"
+        )]
+        grace: Option<u64>,
+
+        /// ask before each file
+        #[arg(short, long, help = "Ask before deleting each file")]
+        interactive: bool,
+
+        /// say what was done
+        #[arg(long, help = "Print a line for each file trashed or shredded")]
+        verbose: bool,
+
+        /// accepted for compatibility with trash-put and rm
+        #[arg(short = 'r', short_alias = 'R', long, hide = true)]
+        recursive: bool,
+
+        /// accepted for compatibility with trash-put and rm
+        #[arg(short, long, hide = true)]
+        directory: bool,
+
+        /// only shred what is due
+        #[arg(
+            long,
+            help = "Shred the trashed files whose grace period is over, and nothing else"
+        )]
+        purge: bool,
+
+        /// keep purging until nothing is pending, as the background process does
+        #[arg(long, hide = true, requires = "purge")]
+        wait: bool,
+    },
+
+    /// securely wipe several devices at once
+    #[command(
+        about = "Securely wipe several devices at once, each with its own progress and report",
+        long_about = "Wipe whole disks or partitions side by side, e.g. the drives of a decommissioned server, up to --parallel at a time. Each device gets the same checks as a single wipe (system disk, mounts, policy), its own storage detection, progress line and report; --report writes them all to one file. A device that fails doesn't stop the others, and a summary of every device follows. Asks once for confirmation of all of them unless --force is given."
+    )]
+    Device {
+        /// devices to wipe
+        #[arg(
+            value_name = "DEVICE",
+            required = true,
+            help = "Devices to wipe, e.g. /dev/sdb /dev/sdc"
+        )]
+        paths: Vec<PathBuf>,
+
+        /// devices wiped at the same time
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 4,
+            help = "Number of devices wiped at the same time"
+        )]
+        parallel: usize,
+    },
+
+    /// securely wipe swap partitions and swapfiles
+    #[command(
+        about = "Securely wipe swap partitions and swapfiles (Linux)",
+        long_about = "Securely wipe every active swap area, where pages of wiped files and other secrets often persist. Each area is disabled with swapoff, overwritten with the selected standard, re-created with mkswap keeping its UUID and label, and enabled again with its original priority. swapoff needs enough free memory to hold everything currently swapped out."
+    )]
+    Swap,
+
+    /// securely wipe the hibernation image
+    #[command(
+        about = "Securely wipe the hibernation image (Windows, Linux)",
+        long_about = "Securely wipe the memory snapshot written when the system hibernates, which can hold the contents of files that were open at the time:\n  Windows - hiberfil.sys, which is locked while Windows runs; wipe it from another OS with --root\n  Linux - the swap partition or swapfile named by the resume= kernel parameter, which is re-created afterwards"
+    )]
+    Hibernation {
+        /// root of an offline Windows volume holding hiberfil.sys
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Look for hiberfil.sys on this volume, e.g. an offline Windows installation"
+        )]
+        root: Option<PathBuf>,
+    },
+
+    /// securely wipe a file or device on another machine over ssh
+    #[command(
+        about = "Securely wipe a file or device on another machine over SSH",
+        long_about = "Run shred on the remote machine over ssh with the same options (standard, verification, ...), streaming its progress and prompts, and bring its wipe report back; save it with --report. shred must be installed on the remote machine; log in as root or pass --sudo. The remote shell must be POSIX (Linux, macOS, BSD)."
+    )]
+    Remote {
+        /// remote target
+        #[arg(
+            value_name = "USER@HOST:PATH",
+            help = "File or device on the remote machine, e.g. admin@db01:/dev/sdb"
+        )]
+        target: String,
+
+        /// run the remote shredder through sudo
+        #[arg(long, help = "Run shred on the remote machine through sudo")]
+        sudo: bool,
+
+        /// shredder binary on the remote machine
+        #[arg(
+            long,
+            value_name = "PATH",
+            default_value = "shred",
+            help = "Path of shred on the remote machine"
+        )]
+        remote_program: String,
+
+        /// extra ssh options
+        #[arg(
+            long = "ssh-option",
+            value_name = "OPTION",
+            allow_hyphen_values = true,
+            help = "Pass an option to ssh, e.g. --ssh-option=-p2222 (repeatable)"
+        )]
+        ssh_options: Vec<String>,
+    },
+
+    /// run the scheduled jobs from the configuration file
+    #[command(
+        about = "Run the wipe jobs scheduled in the configuration file",
+        long_about = "Stay in the foreground and run the [[jobs]] of the configuration file on their cron schedules, e.g. to wipe a spool directory every night. Each job can set its own standard, verification level and report file; failures are logged and the daemon keeps running. Meant to be started by systemd, launchd or a Windows service wrapper."
+    )]
+    Daemon {
+        /// address for the HTTP API
+        #[cfg(feature = "api")]
+        #[arg(
+            long,
+            value_name = "ADDR",
+            help = "Serve the HTTP API on this address, e.g. 127.0.0.1:7878",
+            long_help = "Serve the HTTP API on this address so fleet-management systems can submit wipes (POST /jobs), poll their status (GET /jobs/<id>) and fetch the wipe report (GET /jobs/<id>/certificate). Requests must send `Authorization: Bearer <api_token>` with the token from the configuration file."
+        )]
+        listen: Option<String>,
+    },
+
+    /// run one job from the configuration file now
+    #[command(
+        about = "Run one wipe job from the configuration file now",
+        long_about = "Run the [[jobs]] entry with the given name once, with its standard, verification level and report file, and exit with an error if any of it could not be wiped. Used by the timers from `shred systemd-units`, and handy for testing a job."
+    )]
+    Job {
+        /// name of the job
+        #[arg(
+            value_name = "NAME",
+            help = "Name of the job in the configuration file"
+        )]
+        name: String,
+    },
+
+    /// generate systemd units for the daemon and the scheduled jobs
+    #[command(
+        name = "systemd-units",
+        about = "Generate systemd units for the daemon and each job (Linux)",
+        long_about = "Generate shredder-daemon.service, which runs `shred daemon`, and a shredder-job-<name>.service and .timer for each [[jobs]] entry, whose OnCalendar= lines follow the job's cron schedule. Enable either the daemon or the timers, not both. The units refer to this binary and the configuration file by absolute path."
+    )]
+    SystemdUnits {
+        /// directory to write the units to
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Write the units to this directory, e.g. /etc/systemd/system, instead of printing them"
+        )]
+        output: Option<PathBuf>,
+    },
+
+    /// generate the polkit action for the elevated helper
+    #[command(
+        name = "polkit-action",
+        about = "Generate the polkit action that lets desktop sessions start the elevated helper (Linux)",
+        long_about = "Generate the polkit action io.github.g4titanx.shredder.helper for this binary. Once it is installed in /usr/share/polkit-1/actions, shred run as an ordinary user starts its elevated helper for secure erase and TRIM with pkexec, which shows the desktop's authentication dialog, instead of sudo. Administrators stay authorized for a few minutes."
+    )]
+    PolkitAction {
+        /// directory to write the action file to
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Write the action to this directory, e.g. /usr/share/polkit-1/actions, instead of printing it"
+        )]
+        output: Option<PathBuf>,
+    },
+
+    /// add or remove the file manager entries
+    #[command(
+        about = "Add \"Shred with shredder\" to the file manager's context menu",
+        long_about = "Add a \"Shred with shredder\" entry for the current user: to Explorer's context menu and the Send To menu on Windows, to Nautilus (under Scripts) and Dolphin on Linux, and as a Finder quick action on macOS. The entry asks for confirmation in a dialog, then wipes the selected files and directories with the standard and options of the configuration file, and shows a desktop notification when it is done."
+    )]
+    Integrate {
+        /// remove the entries instead
+        #[arg(long, help = "Remove the entries added before")]
+        remove: bool,
+    },
+
+    /// serve the D-Bus interface
+    #[cfg(all(unix, feature = "dbus"))]
+    #[command(
+        about = "Serve the org.shredder.Manager D-Bus interface on the session bus (Linux)",
+        long_about = "Own org.shredder.Manager on the session bus and wipe what file managers and desktop applets ask for through its WipeFile and WipeDirectory methods, one at a time, with the options of the command line and configuration file. Progress and Finished signals report each job by the id the method returned. `shred integrate` installs an activation file, so the bus starts the service when it is first called."
+    )]
+    Dbus,
+
+    /// what the file manager entries run
+    #[command(name = "shell-action", hide = true)]
+    ShellAction {
+        /// files and directories selected in the file manager
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// browse, select and wipe targets interactively
+    #[command(
+        about = "Browse, select and wipe targets in a terminal UI",
+        long_about = "Open a full-screen file browser: mark files and directories with space, pick a standard (each pass is listed with its pattern) and start the wipes, which run side by side with live progress for each. The other command line options, such as --verify, apply to the wipes started from it."
+    )]
+    Tui {
+        /// directory to start browsing in
+        #[arg(
+            value_name = "DIR",
+            help = "Directory to start in [default: the current directory]"
+        )]
+        dir: Option<PathBuf>,
+    },
+
+    /// answer a few questions and get a NIST method proposed
+    #[command(
+        about = "Choose a NIST 800-88 method by answering a few questions, then wipe",
+        long_about = "Ask what is being wiped, what kind of storage it is on (detected where possible) and whether the media is leaving the organization, then propose NIST 800-88 Clear or Purge with the reasons and passes behind it, and run it after the usual confirmation. Prints the equivalent command line for next time."
+    )]
+    Wizard {
+        /// file, directory or device to wipe
+        #[arg(
+            value_name = "PATH",
+            help = "File, directory or device to wipe [default: asked for]"
+        )]
+        path: Option<PathBuf>,
+    },
+
+    /// explain the recommended method for a target without wiping it
+    #[command(
+        about = "Explain which standard and mechanism fit a target, without wiping it",
+        long_about = "Detect the storage a file or device is on and explain which NIST 800-88 method (Clear or Purge) fits it, why, and how shredder would carry it out: the drive's sanitize or secure erase, TRIM and a single pass, or overwrite passes, and when only destroying the media is enough. Media is assumed to stay in the organization unless --leaving is given. Nothing is written."
+    )]
+    Advise {
+        /// file or device to advise on
+        #[arg(value_name = "PATH", help = "File or device to advise on")]
+        path: PathBuf,
+
+        /// the media leaves the organization's control
+        #[arg(
+            long,
+            help = "The media leaves your organization (sold, donated, returned, recycled)"
+        )]
+        leaving: bool,
+    },
+
+    /// list the supported standards
+    #[command(
+        about = "List the supported standards with their passes, media and references",
+        long_about = "List every standard accepted by --standard: its passes and their patterns, whether a hardware erase is tried first, the media it is meant for and the documents that define it."
+    )]
+    Standards,
+
+    /// estimate how long a wipe would take
+    #[command(
+        about = "Estimate the data written and the time a wipe would take",
+        long_about = "Work out how many bytes a wipe of the path would write (size × passes) and read back for verification, whether the device would erase itself instead, and how long it takes at a typical throughput for the detected storage, or at a measured one given with --throughput. Nothing is written."
+    )]
+    Estimate {
+        /// file, directory or device to estimate
+        #[arg(value_name = "PATH", help = "File, directory or device to estimate")]
+        path: PathBuf,
+
+        /// sustained throughput to assume
+        #[arg(
+            long,
+            value_name = "MB/S",
+            help = "Assume this sustained throughput in MB/s instead of a typical one for the storage type"
+        )]
+        throughput: Option<f64>,
+    },
+
+    /// check a wiped device or file against a pattern
+    #[command(
+        about = "Check that a wiped device or file holds the expected pattern",
+        long_about = "Read a device or file, all of it or a random sample of its 4 KiB blocks (always including the first and last), and check that it holds the expected pattern, e.g. after a wipe by another tool or an earlier run. Random data is checked for high entropy rather than byte for byte. Nothing is written; save the result with --report. Exits with code 4 when a block doesn't match."
+    )]
+    Verify {
+        /// device or file to check
+        #[arg(value_name = "PATH", help = "Device or file to check")]
+        path: PathBuf,
+
+        /// expected pattern
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            default_value = "zeros",
+            help = "Expected pattern: zeros, ones, random or hex bytes such as 0x92,0x49,0x24"
+        )]
+        pattern: String,
+
+        /// share of the blocks to read
+        #[arg(
+            long,
+            value_name = "PERCENT",
+            default_value = "100%",
+            help = "Share of the blocks to read, e.g. 5%"
+        )]
+        sample: String,
+    },
+
+    /// list or erase filesystem, RAID and LVM signatures
+    #[command(
+        about = "List or erase filesystem, RAID and LVM signatures on a device (wipefs-style)",
+        long_about = "Look for the superblocks of filesystems (ext2/3/4, XFS, btrfs, NTFS, FAT, exFAT, APFS, HFS+, F2FS, ISO 9660), swap, LUKS, BitLocker, LVM physical volumes, md RAID members and ZFS pool members on a device or disk image, including their backup copies, and list them. With --erase their magic bytes are zeroed, so nothing on the device is recognized or mounted any more, in seconds. The data itself is NOT wiped: use it before a wipe, or to redeploy a disk that held nothing sensitive. Partition tables are left alone. Asks for confirmation unless --force is given."
+    )]
+    Signatures {
+        /// device or disk image
+        #[arg(value_name = "PATH", help = "Device or disk image")]
+        path: PathBuf,
+
+        /// erase the signatures found
+        #[arg(long, help = "Zero the magic bytes of the signatures found")]
+        erase: bool,
+    },
+
+    /// show or destroy a disk's partition table
+    #[command(
+        about = "Show or destroy the partition table of a disk (MBR and both GPT copies)",
+        long_about = "Read the MBR or GPT partition table of a disk or disk image and list its partitions. With --destroy the MBR, every extended boot record, the primary GPT and the backup GPT in the last sectors of the disk are zeroed, which zeroing the first megabyte misses, and the disk is read back to make sure no GPT header remains. The partitions' data is NOT wiped. Asks for confirmation unless --force is given."
+    )]
+    Partitions {
+        /// disk or disk image
+        #[arg(value_name = "PATH", help = "Disk or disk image")]
+        path: PathBuf,
+
+        /// destroy the table
+        #[arg(long, help = "Zero the MBR and both GPT copies")]
+        destroy: bool,
+    },
+
+    /// find the fastest buffer size for a disk
+    #[command(
+        about = "Measure the fastest write buffer size for a disk and save it to the configuration",
+        long_about = "Write and read back test data with buffer sizes from 64 KiB to 16 MiB, synced to the disk, and save the size with the best write throughput as buffer_size in the configuration file (--config, or the default one), where later wipes pick it up. Given a directory, a temporary file is used and removed. Given a device, its first --size MiB are OVERWRITTEN, so it asks for confirmation unless --force is given."
+    )]
+    Bench {
+        /// directory on the disk to measure, or a device
+        #[arg(
+            value_name = "TARGET",
+            help = "Directory on the disk to measure, or a device (overwritten)"
+        )]
+        target: PathBuf,
+
+        /// data written per buffer size
+        #[arg(
+            long,
+            value_name = "MIB",
+            default_value_t = 64,
+            help = "MiB written with each buffer size; larger gives steadier numbers"
+        )]
+        size: u64,
+
+        /// only print the results
+        #[arg(long, help = "Print the results without saving the best size")]
+        no_save: bool,
+    },
+
+    /// elevated helper for secure erase and TRIM, started by shred itself
+    #[command(
+        hide = true,
+        about = "Run secure erase and TRIM for an unprivileged shred (started by shred)",
+        long_about = "Answer secure erase and TRIM requests read from stdin, one per line, until stdin is closed. Started through sudo by a shred running as an ordinary user; every target is opened as that user before it is touched."
+    )]
+    Helper,
 }
 
 fn check_privileges() -> bool {
@@ -67,17 +772,16 @@ fn check_privileges() -> bool {
     }
     #[cfg(windows)]
     {
-        use winapi::um::securitybaseapi::IsUserAnAdmin;
-        unsafe { IsUserAnAdmin() != 0 }
+        shredder::elevate::is_elevated()
     }
 }
 
-fn confirm_operation(path: &PathBuf, force: bool) -> bool {
+fn confirm_operation(what: &str, force: bool) -> bool {
     if force {
         return true;
     }
 
-    println!("🔥 Preparing to securely erase: {}", path.display());
+    println!("🔥 Preparing to securely erase: {}", what);
     println!("⚠️  WARNING: This operation is irreversible!");
     println!("Type 'Auf Wiedersen' to confirm:");
 
@@ -89,46 +793,161 @@ fn confirm_operation(path: &PathBuf, force: bool) -> bool {
     input.trim() == "Auf Wiedersen"
 }
 
-fn parse_standard(standard: &str) -> WipeStandard {
-    use shredder::standards::{LegacyConfig, LegacyStandard};
+/// shows what is on a system disk and makes the operator re-type its serial number
+fn confirm_system_disk(path: &Path) -> bool {
+    let info = device::device_info(path).unwrap_or_else(|e| {
+        eprintln!("Warning: Unable to read device details: {}", e);
+        device::DeviceInfo::default()
+    });
+    let unknown = || "unknown".to_string();
 
-    match standard.to_lowercase().as_str() {
-        "nist" => WipeStandard::Modern(Nist80088Config {
-            method: SanitizationMethod::Purge,
-            verify_level: VerificationLevel::Full,
-        }),
-        "dod" => WipeStandard::Legacy(LegacyConfig {
-            standard: LegacyStandard::Dod522022M,
-            extra_verification: true,
-        }),
-        "gutmann" => WipeStandard::Legacy(LegacyConfig {
-            standard: LegacyStandard::Gutmann,
-            extra_verification: true,
-        }),
-        "vsitr" => WipeStandard::Legacy(LegacyConfig {
-            standard: LegacyStandard::VsitrStandard,
-            extra_verification: true,
-        }),
+    println!("🛑 {} holds an operating system!", path.display());
+    println!(
+        "   Model:      {}",
+        info.model.clone().unwrap_or_else(unknown)
+    );
+    println!(
+        "   Serial:     {}",
+        info.serial.clone().unwrap_or_else(unknown)
+    );
+    println!(
+        "   Size:       {}",
+        info.size
+            .map(|bytes| format!("{:.1} GB ({} bytes)", bytes as f64 / 1e9, bytes))
+            .unwrap_or_else(unknown)
+    );
+    if info.partitions.is_empty() {
+        println!("   Partitions: none found");
+    } else {
+        println!("   Partitions: {}", info.partitions.join(", "));
+    }
+    println!("⚠️  EVERYTHING on this disk, including all partitions above, will be destroyed!");
+
+    // without a serial number, fall back to the full device path
+    let expected = match &info.serial {
+        Some(serial) => {
+            println!("Type the disk's serial number to confirm:");
+            serial.clone()
+        }
+        None => {
+            println!("Type the full device path to confirm:");
+            path.display().to_string()
+        }
+    };
+
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input).is_err() {
+        return false;
+    }
+
+    input.trim() == expected
+}
+
+fn parse_open_file_policy(policy: &str) -> OpenFilePolicy {
+    match policy.to_lowercase().as_str() {
+        "ignore" => OpenFilePolicy::Ignore,
+        "warn" => OpenFilePolicy::Warn,
+        "refuse" => OpenFilePolicy::Refuse,
         _ => {
+            eprintln!(
+                "Warning: Unknown open file policy '{}', defaulting to warn",
+                policy
+            );
+            OpenFilePolicy::Warn
+        }
+    }
+}
+
+fn parse_preset(preset: &str) -> Preset {
+    let preset = preset.to_lowercase();
+    match Preset::ALL.into_iter().find(|p| p.name() == preset) {
+        Some(preset) => preset,
+        None => {
+            let names: Vec<&str> = Preset::ALL.iter().map(|p| p.name()).collect();
+            eprintln!(
+                "Error: Unknown preset '{}'. Available presets: {}",
+                preset,
+                names.join(", ")
+            );
+            process::exit(exit_code::USAGE);
+        }
+    }
+}
+
+fn parse_standard(standard: &str) -> WipeStandard {
+    match standard.parse() {
+        Ok(standard) => standard,
+        Err(_) => {
             eprintln!(
                 "Warning: Unknown standard '{}', defaulting to NIST",
                 standard
             );
-            WipeStandard::Modern(Nist80088Config {
-                method: SanitizationMethod::Purge,
-                verify_level: VerificationLevel::Full,
-            })
+            NamedStandard::ALL[0].standard()
         }
     }
 }
 
-fn parse_verification_level(level: &str) -> VerificationLevel {
-    match level.to_lowercase().as_str() {
-        "none" => VerificationLevel::None,
-        "basic" => VerificationLevel::Basic,
-        "full" => VerificationLevel::Full,
-        "enhanced" => VerificationLevel::Enhanced,
+fn parse_pattern(pattern: &str) -> WipePattern {
+    match pattern.to_lowercase().as_str() {
+        "zeros" => WipePattern::Zeros,
+        "ones" => WipePattern::Ones,
+        "random" => WipePattern::Random,
+        bytes => {
+            let parsed: Result<Vec<u8>, _> = bytes
+                .split([',', ' '])
+                .filter(|byte| !byte.is_empty())
+                .map(|byte| u8::from_str_radix(byte.trim_start_matches("0x"), 16))
+                .collect();
+            match parsed {
+                Ok(bytes) if !bytes.is_empty() => WipePattern::Custom(bytes),
+                _ => {
+                    eprintln!(
+                        "Error: Unknown pattern '{}'. Use zeros, ones, random or hex bytes such as 0x92,0x49,0x24",
+                        pattern
+                    );
+                    process::exit(exit_code::USAGE);
+                }
+            }
+        }
+    }
+}
+
+/// parses a share such as "5%" or "0.05" into a fraction
+fn parse_sample(sample: &str) -> f64 {
+    let fraction = match sample.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => sample.trim().parse::<f64>(),
+    };
+    match fraction {
+        Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => fraction,
+        _ => {
+            eprintln!(
+                "Error: Invalid sample '{}'. Use a percentage above 0% and up to 100%, e.g. 5%",
+                sample
+            );
+            process::exit(exit_code::USAGE);
+        }
+    }
+}
+
+fn parse_sample_placement(placement: &str) -> SamplePlacement {
+    match placement.to_lowercase().as_str() {
+        "random" => SamplePlacement::Random,
+        "stratified" => SamplePlacement::Stratified,
         _ => {
+            eprintln!(
+                "Warning: Unknown sample placement '{}', defaulting to random",
+                placement
+            );
+            SamplePlacement::Random
+        }
+    }
+}
+
+fn parse_verification_level(level: &str) -> VerificationLevel {
+    match level.parse() {
+        Ok(level) => level,
+        Err(_) => {
             eprintln!(
                 "Warning: Unknown verification level '{}', defaulting to Full",
                 level
@@ -138,54 +957,183 @@ fn parse_verification_level(level: &str) -> VerificationLevel {
     }
 }
 
+fn parse_snapshot_policy(policy: &str) -> SnapshotPolicy {
+    match policy.to_lowercase().as_str() {
+        "ignore" => SnapshotPolicy::Ignore,
+        "warn" => SnapshotPolicy::Warn,
+        "refuse" => SnapshotPolicy::Refuse,
+        "delete" => SnapshotPolicy::Delete,
+        _ => {
+            eprintln!(
+                "Warning: Unknown snapshot policy '{}', defaulting to warn",
+                policy
+            );
+            SnapshotPolicy::Warn
+        }
+    }
+}
+
 fn main() {
     // initialize logger
     env_logger::init();
 
     // parse command line arguments
-    let cli = Cli::parse();
+    let cli = match trash_put_args() {
+        Some(args) => Cli::parse_from(args),
+        None => Cli::parse(),
+    };
 
-    // validate path and check if it's a file
-    if !cli.path.exists() {
-        eprintln!("Error: Path not found: {}", cli.path.display());
-        process::exit(1);
-    }
+    let config = match &cli.config {
+        Some(path) => Config::load(path),
+        None => Config::load_default(),
+    };
+    let mut config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: Unable to load configuration: {}", e);
+            process::exit(exit_code::FAILURE);
+        }
+    };
+    enforce_policy(&cli, &mut config, &Policy::system());
 
-    if cli.path.is_dir() {
-        eprintln!(
-            "Error: {} is a directory. This tool only works with files.",
-            cli.path.display()
-        );
-        process::exit(1);
+    match &cli.command {
+        Some(Command::Helper) => {
+            if let Err(e) = helper::serve(io::stdin().lock(), io::stdout().lock()) {
+                eprintln!("Error: {}", e);
+                process::exit(exit_code::FAILURE);
+            }
+        }
+        Some(Command::RecycleBin) => match locations::recycle_bin_entries() {
+            Ok(entries) => shred_many(&cli, &config, "the Recycle Bin", entries),
+            Err(e) => {
+                eprintln!("Error: Unable to list the Recycle Bin: {}", e);
+                process::exit(exit_code::FAILURE);
+            }
+        },
+        Some(Command::Trash) => match locations::trash_entries() {
+            Ok(entries) => shred_many(&cli, &config, "the trash", entries),
+            Err(e) => {
+                eprintln!("Error: Unable to list the trash: {}", e);
+                process::exit(exit_code::FAILURE);
+            }
+        },
+        Some(Command::TrashPut {
+            purge: true, wait, ..
+        }) => purge_trash(&cli, &config, *wait),
+        Some(Command::TrashPut {
+            files,
+            grace,
+            interactive,
+            verbose,
+            ..
+        }) => trash_put(
+            &cli,
+            &config,
+            files,
+            grace.unwrap_or(config.trash_grace_secs),
+            *interactive && !cli.force,
+            *verbose,
+        ),
+        Some(Command::Swap) => match swap::active_swap_areas() {
+            Ok(areas) if areas.is_empty() => println!("✓ No swap in use"),
+            Ok(areas) => shred_swap_areas(&cli, &config, "all swap areas", areas),
+            Err(e) => {
+                eprintln!("Error: Unable to list swap areas: {}", e);
+                process::exit(exit_code::FAILURE);
+            }
+        },
+        Some(Command::Remote {
+            target,
+            sudo,
+            remote_program,
+            ssh_options,
+        }) => shred_remote(&cli, target, *sudo, remote_program, ssh_options),
+        Some(Command::Daemon { .. }) => run_daemon(&cli, &config),
+        Some(Command::Job { name }) => run_named_job(&cli, &config, name),
+        Some(Command::SystemdUnits { output }) => {
+            write_systemd_units(&cli, &config, output.as_deref())
+        }
+        Some(Command::PolkitAction { output }) => write_polkit_action(output.as_deref()),
+        Some(Command::Integrate { remove }) => integrate(*remove),
+        #[cfg(all(unix, feature = "dbus"))]
+        Some(Command::Dbus) => serve_dbus(&cli, &config),
+        Some(Command::ShellAction { paths }) => shell_action(&cli, &config, paths),
+        Some(Command::Hibernation { root }) => shred_hibernation(&cli, &config, root.as_deref()),
+        Some(Command::Tui { dir }) => run_tui(&cli, &config, dir.as_deref()),
+        Some(Command::Wizard { path }) => wizard::run(&cli, &config, path.as_deref()),
+        Some(Command::Advise { path, leaving }) => wizard::advise(path, *leaving),
+        Some(Command::Standards) => print_standards(),
+        Some(Command::Estimate { path, throughput }) => print_estimate(&cli, path, *throughput),
+        Some(Command::Bench {
+            target,
+            size,
+            no_save,
+        }) => run_bench(&cli, target, *size, *no_save),
+        Some(Command::Verify {
+            path,
+            pattern,
+            sample,
+        }) => verify_target(&cli, path, pattern, sample),
+        Some(Command::Signatures { path, erase }) => handle_signatures(&cli, &config, path, *erase),
+        Some(Command::Partitions { path, destroy }) => {
+            handle_partitions(&cli, &config, path, *destroy)
+        }
+        Some(Command::Device { paths, parallel }) => shred_devices(&cli, &config, paths, *parallel),
+        None => match &cli.path {
+            Some(path) => shred_path(&cli, &config, path),
+            None => {
+                let presets: Vec<Preset> = cli.preset.iter().map(|p| parse_preset(p)).collect();
+                let entries = presets.iter().flat_map(|preset| preset.entries()).collect();
+                let names: Vec<&str> = presets.iter().map(|preset| preset.name()).collect();
+                shred_many(&cli, &config, &names.join(", "), entries);
+            }
+        },
     }
+}
 
-    // check for root/admin privileges if not explicitly skipped
+/// check for root/admin privileges if not explicitly skipped; on windows the
+/// command is run again as administrator after a UAC prompt instead
+fn require_privileges(cli: &Cli) {
     if !cli.no_root_check && !check_privileges() {
+        #[cfg(windows)]
+        relaunch_elevated();
         eprintln!("Error: This program needs root/administrator privileges.");
         #[cfg(unix)]
         eprintln!("Please run with sudo: sudo shred <path>");
         #[cfg(windows)]
         eprintln!("Please run as administrator");
-        process::exit(1);
-    }
-
-    // validate path
-    if !cli.path.exists() {
-        eprintln!("Error: File not found: {}", cli.path.display());
-        process::exit(1);
+        process::exit(exit_code::PERMISSION_DENIED);
     }
+}
 
-    // get confirmation unless --force is used
-    if !confirm_operation(&cli.path, cli.force) {
-        println!("Operation cancelled. Your file lives another day.");
-        process::exit(0);
+/// runs this command again, with the same arguments, as administrator and
+/// exits with its exit code; returns if it couldn't be started
+#[cfg(windows)]
+fn relaunch_elevated() {
+    let program = match std::env::current_exe() {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("Error: Unable to find the shred binary: {}", e);
+            return;
+        }
+    };
+    let args: Vec<std::ffi::OsString> = std::env::args_os().skip(1).collect();
+    println!("Administrator rights are needed; continuing in an elevated window...");
+    match shredder::elevate::run_elevated(&program, &args) {
+        Ok(code) => {
+            match code {
+                exit_code::SUCCESS => println!("✓ The elevated run finished"),
+                code => eprintln!("Error: The elevated run failed (exit code {})", code),
+            }
+            process::exit(code);
+        }
+        Err(e) => eprintln!("Error: Unable to run as administrator: {}", e),
     }
+}
 
-    println!("☠️  Initiating secure deletion...");
-
-    // detect storage type with progress indication
-    println!("Detecting storage type...");
-    let storage_info = match StorageType::detect_from_path(&cli.path) {
+/// detects the storage type, falling back to HDD mode when detection fails
+fn detect_storage(path: &Path) -> StorageInfo {
+    match StorageType::detect_from_path(path) {
         Ok(info) => {
             println!("✓ Detected storage type");
             info
@@ -193,20 +1141,28 @@ fn main() {
         Err(e) => {
             eprintln!("Warning: Error detecting storage type: {}", e);
             println!("Falling back to HDD mode for maximum compatibility");
-            StorageInfo {
-                device_type: StorageType::Hdd(StorageCapabilities {
-                    supports_trim: false,
-                    supports_secure_erase: false,
-                    supports_nvme_sanitize: false,
-                    has_wear_leveling: false,
-                }),
-                block_size: 4096,
-                total_size: 0,
-            }
+            fallback_storage()
         }
-    };
+    }
+}
 
-    // create shredder with selected standard and verification level
+/// HDD mode, used when the storage type can't be detected
+fn fallback_storage() -> StorageInfo {
+    StorageInfo {
+        device_type: StorageType::Hdd(StorageCapabilities {
+            supports_trim: false,
+            supports_secure_erase: false,
+            supports_nvme_sanitize: false,
+            has_wear_leveling: false,
+        }),
+        block_size: 4096,
+        physical_block_size: 4096,
+        total_size: 0,
+    }
+}
+
+/// the selected standard with the selected verification level
+fn selected_standard(cli: &Cli) -> WipeStandard {
     let mut standard = parse_standard(&cli.standard);
     // update verification level if specified
     match &mut standard {
@@ -219,21 +1175,2154 @@ fn main() {
         WipeStandard::Custom(config) => {
             config.verify_each_pass = cli.verify.to_lowercase() != "none";
         }
+        WipeStandard::Auto(config) => {
+            config.verify_level = parse_verification_level(&cli.verify);
+        }
+    }
+    standard
+}
+
+/// creates a shredder with the selected standard, verification level and options
+fn build_shredder(
+    cli: &Cli,
+    config: &Config,
+    storage: &StorageInfo,
+    allow_system_disk: bool,
+) -> Shredder {
+    let shredder = Shredder::new(selected_standard(cli), storage.device_type.clone())
+        .with_block_size(storage.physical_block_size)
+        .with_nocow_copy(cli.nocow)
+        .with_snapshot_policy(parse_snapshot_policy(&cli.snapshots))
+        .with_unmount(cli.unmount)
+        .with_protected_paths(config.protected_paths.clone())
+        .with_allow_system_disk(allow_system_disk)
+        .with_force_multipass(cli.force_multipass)
+        .with_quirks(Quirks::builtin().with_entries(config.quirks.clone()))
+        .with_open_file_policy(parse_open_file_policy(&cli.open_files))
+        .with_sampling(
+            parse_sample(&cli.sample_rate),
+            parse_sample_placement(&cli.sample_placement),
+        )
+        .with_clear_protection_flags(cli.clear_flags)
+        .with_clear_attributes(!cli.keep_attributes)
+        .with_sync_writes(cli.sync_writes)
+        .with_sandbox(cli.sandbox)
+        .with_signature_erase(cli.erase_signatures)
+        .with_size_obfuscation(cli.obfuscate_size)
+        .with_skip_matching(cli.skip_matching)
+        .with_content_hash(cli.hash)
+        .with_remnant_scan(cli.scan_remnants)
+        .with_checkpoint_dir(
+            config
+                .checkpoint_dir
+                .clone()
+                .unwrap_or_else(|| Config::state_dir().join("checkpoints")),
+        );
+    // an ordinary user overwrites their own files; only secure erase and TRIM
+    // need root, and go through the elevated helper. a sandboxed wipe can't
+    // start hdparm itself, so it uses the helper even as root
+    let use_helper = cfg!(unix) && !cli.no_root_check && (cli.sandbox || !check_privileges());
+    let shredder = match use_helper.then(std::env::current_exe) {
+        Some(Ok(program)) => shredder.with_helper(program),
+        Some(Err(e)) => {
+            eprintln!(
+                "Warning: Unable to find the shred executable for the elevated helper: {}",
+                e
+            );
+            shredder
+        }
+        None => shredder,
+    };
+    let shredder = match cli.separate_verifier.then(std::env::current_exe) {
+        Some(Ok(program)) => shredder.with_verifier_process(program),
+        Some(Err(e)) => {
+            eprintln!(
+                "Warning: Unable to find the shred executable for --separate-verifier: {}",
+                e
+            );
+            shredder
+        }
+        None => shredder,
+    };
+    // measured by `shred bench`
+    let shredder = match config.buffer_size {
+        Some(size) => shredder.with_buffer_size(size),
+        None => shredder,
+    };
+    let shredder = match cli.threads {
+        Some(threads) => shredder.with_threads(threads),
+        None => shredder,
+    };
+    let shredder = match cli.quick {
+        Some(mib) => shredder.with_quick_wipe(mib * 1024 * 1024),
+        None => shredder,
+    };
+
+    // the bar only makes sense to someone watching; logs get a line now and then
+    if io::stderr().is_terminal() {
+        shredder.with_progress(progress_bar())
+    } else if cli.progress_interval > 0 {
+        shredder.with_progress(progress_log(Duration::from_secs(cli.progress_interval)))
+    } else {
+        shredder
+    }
+}
+
+/// prints a line with the pass, percentage, throughput and ETA to stderr
+/// every `interval`, for runs nobody watches on a terminal
+fn progress_log(interval: Duration) -> impl Fn(&Progress) + Send + Sync {
+    struct State {
+        path: PathBuf,
+        started: Instant,
+        logged: Instant,
+    }
+    let state = std::sync::Mutex::new(State {
+        path: PathBuf::new(),
+        started: Instant::now(),
+        logged: Instant::now(),
+    });
+
+    move |progress: &Progress| {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.path != progress.path {
+            state.path = progress.path.to_path_buf();
+            state.started = Instant::now();
+            state.logged = Instant::now();
+        }
+        if state.logged.elapsed() < interval {
+            return;
+        }
+        state.logged = Instant::now();
+
+        let elapsed = state.started.elapsed();
+        let (rate, eta) = rate_and_eta(progress, elapsed);
+        eprintln!(
+            "progress path={:?} pass={}/{} percent={:.1} mb_per_s={:.1} elapsed={} eta={}",
+            progress.path.display().to_string(),
+            progress.pass,
+            progress.passes,
+            progress.fraction() * 100.0,
+            rate / 1_000_000.0,
+            format_duration(elapsed),
+            eta.map_or_else(|| "--".to_string(), format_duration)
+        );
+    }
+}
+
+/// draws a progress bar with the pass, percentage, throughput and ETA on
+/// stderr, a new one for each target
+fn progress_bar() -> impl Fn(&Progress) + Send + Sync {
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    let style = ProgressStyle::with_template(
+        "Pass {msg} [{bar:30}] {percent:>3}% {decimal_bytes_per_sec:>11} ETA {eta}",
+    )
+    .expect("progress bar template")
+    .progress_chars("## ");
+    struct State {
+        path: PathBuf,
+        pass: usize,
+        bar: Option<ProgressBar>,
+    }
+    let state = std::sync::Mutex::new(State {
+        path: PathBuf::new(),
+        pass: 0,
+        bar: None,
+    });
+
+    // indicatif limits redrawing itself, so every buffer can be reported
+    move |progress: &Progress| {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.path != progress.path {
+            state.path = progress.path.to_path_buf();
+            state.pass = 0;
+            state.bar = None;
+        }
+        let new_pass = state.pass != progress.pass;
+        state.pass = progress.pass;
+        let bar = state.bar.get_or_insert_with(|| {
+            ProgressBar::new(progress.total_bytes()).with_style(style.clone())
+        });
+        if new_pass {
+            bar.set_message(format!("{}/{}", progress.pass, progress.passes));
+        }
+        bar.set_position(progress.total_written());
+        if progress.total_written() == progress.total_bytes() {
+            bar.finish();
+            // indicatif leaves the cursor on the finished bar
+            eprintln!();
+        }
+    }
+}
+
+/// bytes per second written so far, and the time left at that rate if
+/// anything was written yet
+fn rate_and_eta(progress: &Progress, elapsed: Duration) -> (f64, Option<Duration>) {
+    let rate = progress.total_written() as f64 / elapsed.as_secs_f64().max(0.001);
+    let eta = (rate > 0.0).then(|| {
+        let remaining = (progress.total_bytes() - progress.total_written()) as f64 / rate;
+        Duration::from_secs_f64(remaining)
+    });
+    (rate, eta)
+}
+
+/// pass, bar, percentage, throughput and ETA of a wipe running for `elapsed`
+fn progress_text(progress: &Progress, elapsed: Duration) -> String {
+    let (rate, eta) = rate_and_eta(progress, elapsed);
+    let eta = eta.map_or_else(|| "--".to_string(), format_duration);
+
+    const WIDTH: usize = 30;
+    let filled = (progress.fraction() * WIDTH as f64) as usize;
+    format!(
+        "Pass {}/{} [{}{}] {:5.1}% {:7.1} MB/s ETA {}",
+        progress.pass,
+        progress.passes,
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        progress.fraction() * 100.0,
+        rate / 1_000_000.0,
+        eta
+    )
+}
+
+/// a progress line per device on stderr, redrawn together
+struct ProgressLines {
+    state: std::sync::Mutex<LinesState>,
+}
+
+struct LinesState {
+    names: Vec<String>,
+    lines: Vec<String>,
+    started: Vec<Option<Instant>>,
+    drawn: Option<Instant>,
+}
+
+impl ProgressLines {
+    fn new(names: &[String]) -> Self {
+        Self {
+            state: std::sync::Mutex::new(LinesState {
+                names: names.to_vec(),
+                lines: vec!["waiting".to_string(); names.len()],
+                started: vec![None; names.len()],
+                drawn: None,
+            }),
+        }
+    }
+
+    /// progress callback for the device at `index`
+    fn callback(lines: &std::sync::Arc<Self>, index: usize) -> impl Fn(&Progress) + Send + Sync {
+        let lines = std::sync::Arc::clone(lines);
+        move |progress: &Progress| {
+            let mut state = lines.state.lock().unwrap_or_else(|e| e.into_inner());
+            let started = *state.started[index].get_or_insert_with(Instant::now);
+            state.lines[index] = progress_text(progress, started.elapsed());
+            // redrawing on every buffer of every device would slow the wipes down
+            if state
+                .drawn
+                .is_none_or(|drawn| drawn.elapsed() >= Duration::from_millis(200))
+            {
+                state.draw();
+            }
+        }
+    }
+
+    /// replaces the device's progress with how it ended
+    fn finish(&self, index: usize, status: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.lines[index] = status.to_string();
+        state.draw();
+    }
+}
+
+impl LinesState {
+    fn draw(&mut self) {
+        let width = self.names.iter().map(String::len).max().unwrap_or(0);
+        let mut out = String::new();
+        if self.drawn.is_some() {
+            // back to the first line
+            out.push_str(&format!("\x1b[{}A", self.lines.len()));
+        }
+        for (name, line) in self.names.iter().zip(&self.lines) {
+            out.push_str(&format!("\r{:<width$}  {}\x1b[K\n", name, line));
+        }
+        eprint!("{}", out);
+        self.drawn = Some(Instant::now());
+    }
+}
+
+/// formats a duration as 1h02m, 3m05s or 12s
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, _) => format!("{}h{:02}m", h, m),
+    }
+}
+
+/// formats a byte count with binary units, e.g. "1.5 GiB"
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// prints how each target of a batch fared as a table, with the totals and
+/// the time the whole batch took underneath
+fn print_summary(summary: &BatchSummary, elapsed: Duration) {
+    let mut rows = vec![[
+        "STATUS".to_string(),
+        "TARGET".to_string(),
+        "METHOD".to_string(),
+        "WRITTEN".to_string(),
+        "DURATION".to_string(),
+    ]];
+    for row in &summary.rows {
+        let mark = if row.error.is_none() { "✓" } else { "✗" };
+        rows.push([
+            format!("{} {}", mark, row.status()),
+            row.path.display().to_string(),
+            row.method.clone().unwrap_or_else(|| "-".to_string()),
+            format_size(row.bytes_written),
+            row.duration
+                .map_or_else(|| "-".to_string(), format_duration),
+        ]);
+    }
+    let totals = [
+        "total".to_string(),
+        format!("{} of {} wiped", summary.wiped(), summary.rows.len()),
+        String::new(),
+        format_size(summary.bytes_written()),
+        format_duration(elapsed),
+    ];
+
+    let mut widths = [0; 5];
+    for row in rows.iter().chain([&totals]) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |row: &[String; 5]| {
+        format!(
+            "  {:<w0$}  {:<w1$}  {:<w2$}  {:>w3$}  {:>w4$}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            row[4],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+            w4 = widths[4],
+        )
+    };
+    println!("\nSummary:");
+    for row in &rows {
+        println!("{}", line(row));
     }
+    println!(
+        "  {}",
+        "-".repeat(widths.iter().sum::<usize>() + 2 * (widths.len() - 1))
+    );
+    println!("{}", line(&totals));
+}
+
+/// one line naming a device: node, model and whatever identifies the unit
+fn describe_identity(identity: &shredder::report::DeviceIdentity) -> String {
+    let mut details = Vec::new();
+    if let Some(serial) = &identity.serial {
+        details.push(format!("serial {}", serial));
+    }
+    if let Some(wwn) = &identity.wwn {
+        details.push(format!("WWN {}", wwn));
+    }
+    if let Some(firmware) = &identity.firmware {
+        details.push(format!("firmware {}", firmware));
+    }
+    if let Some(capacity) = identity.capacity {
+        details.push(format_size(capacity));
+    }
+    let device = identity
+        .device
+        .as_ref()
+        .map_or_else(|| "unknown".to_string(), |path| path.display().to_string());
+    let model = identity.model.as_deref().unwrap_or("unknown model");
+    if details.is_empty() {
+        format!("{} ({})", device, model)
+    } else {
+        format!("{} ({}, {})", device, model, details.join(", "))
+    }
+}
+
+/// securely wipes a single file or device
+fn shred_path(cli: &Cli, config: &Config, path: &Path) {
+    // \\?\ form on windows, so long paths and trailing dots/spaces can be opened
+    let target = filesystem::extended_length_path(path);
+
+    // validate path and check if it's a file
+    if !target.exists() {
+        eprintln!("Error: Path not found: {}", path.display());
+        process::exit(exit_code::FAILURE);
+    }
+
+    if target.is_dir() {
+        eprintln!(
+            "Error: {} is a directory. This tool only works with files.",
+            path.display()
+        );
+        process::exit(exit_code::UNSUPPORTED);
+    }
+
+    // the elevated helper needs a pipe, which UAC can't hand to an elevated
+    // process, so the whole wipe is run as administrator instead
+    #[cfg(windows)]
+    require_privileges(cli);
+
+    if cli.quick.is_some() && !device::is_block_device(&target) {
+        eprintln!(
+            "Error: --quick is for devices; {} is overwritten in full without it.",
+            path.display()
+        );
+        process::exit(exit_code::USAGE);
+    }
+
+    let is_system_disk = confirm_if_system_disk(cli, path, &target);
+
+    // get confirmation unless --force is used
+    if !confirm_operation(&path.display().to_string(), cli.force) {
+        println!("Operation cancelled. Your file lives another day.");
+        process::exit(exit_code::CANCELLED);
+    }
+
+    println!("☠️  Initiating secure deletion...");
+
+    // detect storage type with progress indication
+    println!("Detecting storage type...");
+    let storage_info = detect_storage(&target);
 
-    let shredder = Shredder::new(standard, storage_info.device_type);
+    let shredder = build_shredder(
+        cli,
+        config,
+        &storage_info,
+        is_system_disk && cli.allow_system_disk,
+    );
 
     // perform secure deletion
     println!("Starting secure deletion...");
-    match shredder.wipe(&cli.path) {
-        Ok(()) => {
+    let subject = path.display().to_string();
+    let started = CalendarTime::now();
+    let timer = Instant::now();
+    let _awake = SleepInhibitor::acquire(&format!("Wiping {}", subject));
+    let audit = audit_log(cli, config);
+    if let Some(audit) = &audit {
+        audit.started(&subject);
+    }
+    match shredder.wipe_with_report(&target) {
+        Ok(report) => {
             println!("✨ File has been securely shredded!");
-            process::exit(0);
+            print_report(cli, &report);
+            let reports = vec![report];
+            if let Some(audit) = &audit {
+                audit.finished(&subject, &reports);
+            }
+            save_reports(cli, &reports);
+            desktop_notification(cli, config, timer, &subject, None);
+            send_notification(config, &subject, started, reports, Vec::new(), None);
+            process::exit(exit_code::SUCCESS);
+        }
+        Err(e) => {
+            let error = e.to_string();
+            if let Some(audit) = &audit {
+                audit.failed(&subject, std::slice::from_ref(&error));
+            }
+            desktop_notification(cli, config, timer, &subject, Some(&error));
+            send_notification(config, &subject, started, Vec::new(), vec![error], None);
+            exit_with_error(e)
+        }
+    }
+}
+
+/// prints what a report says beyond the target having been wiped
+fn print_report(cli: &Cli, report: &WipeReport) {
+    if let Some(sha256) = &report.sha256 {
+        println!("SHA-256 before wiping: {}", sha256);
+    }
+    match report.remnants {
+        Some(0) => println!("✓ No remnants of the data found on the volume"),
+        None if cli.scan_remnants => {
+            println!("⚠️  The volume could not be scanned for remnants (RUST_LOG=warn shows why)")
+        }
+        _ => {}
+    }
+    for (pass, digest) in report.pass_sha256.iter().enumerate() {
+        println!("✓ Pass {} verified by SHA-256: {}", pass + 1, digest);
+    }
+    if let Some(sampled) = &report.sampled_verification {
+        println!(
+            "✓ Verified {} of {} blocks: at most {:.2}% of the target left unwiped (95% confidence)",
+            sampled.blocks_checked,
+            sampled.blocks,
+            sampled.max_unwiped * 100.0
+        );
+    }
+    if let Some(identity) = &report.device {
+        println!("✓ Device: {}", describe_identity(identity));
+    }
+    if let Some(partition) = &report.partition {
+        println!(
+            "✓ Partition {}: bytes {}..{} of the disk, the rest left intact",
+            partition.number,
+            partition.start,
+            partition.end()
+        );
+    }
+    if let Some(action) = report.nist_action {
+        println!("✓ NIST SP 800-88 {} achieved", action);
+    }
+    if report.destroy_required {
+        println!("❗ Purge was not achieved: only destroying the media meets it");
+    }
+    if report.quick {
+        println!("❗ Quick wipe: the data between the overwritten regions is still there");
+    }
+    for limitation in &report.limitations {
+        println!("⚠️  Limitation: {}", limitation);
+    }
+}
+
+/// securely wipes several devices side by side, up to `parallel` at a time,
+/// then prints each one's report and a summary of all of them
+fn shred_devices(cli: &Cli, config: &Config, paths: &[PathBuf], parallel: usize) {
+    #[cfg(windows)]
+    require_privileges(cli);
+
+    let mut targets = Vec::new();
+    for path in paths {
+        let target = filesystem::extended_length_path(path);
+        if !device::is_block_device(&target) {
+            eprintln!(
+                "Error: {} is not a device; files are wiped with `shred <path>`.",
+                path.display()
+            );
+            process::exit(exit_code::USAGE);
+        }
+        let is_system_disk = confirm_if_system_disk(cli, path, &target);
+        targets.push((path, target, is_system_disk && cli.allow_system_disk));
+    }
+
+    let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+    let what = format!("{} devices ({})", names.len(), names.join(", "));
+    if !confirm_operation(&what, cli.force) {
+        println!("Operation cancelled. The devices were not modified.");
+        process::exit(exit_code::CANCELLED);
+    }
+
+    println!("☠️  Initiating secure deletion...");
+    let storage: Vec<StorageInfo> = targets
+        .iter()
+        .map(|(_, target, _)| detect_storage(target))
+        .collect();
+    let started = CalendarTime::now();
+    let timer = Instant::now();
+    let _awake = SleepInhibitor::acquire(&format!("Wiping {}", what));
+    let audit = audit_log(cli, config);
+    if let Some(audit) = &audit {
+        audit.started(&what);
+    }
+
+    // each device on its own line when someone is watching, otherwise a
+    // line as each one finishes
+    let lines = io::stderr()
+        .is_terminal()
+        .then(|| std::sync::Arc::new(ProgressLines::new(&names)));
+    // each device's outcome, filled in by whichever worker wiped it
+    let results: Vec<std::sync::Mutex<Option<shredder::Result<WipeReport>>>> =
+        targets.iter().map(|_| Default::default()).collect();
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..parallel.clamp(1, targets.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some((path, target, allow_system_disk)) = targets.get(index) else {
+                    break;
+                };
+                let mut shredder = build_shredder(cli, config, &storage[index], *allow_system_disk);
+                if let Some(lines) = &lines {
+                    shredder = shredder.with_progress(ProgressLines::callback(lines, index));
+                }
+                let began = Instant::now();
+                let result = shredder.wipe_with_report(target);
+                let status = match &result {
+                    Ok(_) => format!("✓ wiped in {}", format_duration(began.elapsed())),
+                    Err(_) => format!("✗ failed after {}", format_duration(began.elapsed())),
+                };
+                match &lines {
+                    Some(lines) => lines.finish(index, &status),
+                    None => println!("{} {}", path.display(), status),
+                }
+                *results[index].lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+            });
+        }
+    });
+
+    let mut outcome = MultiWipeReport::default();
+    for ((path, _, _), result) in targets.iter().zip(results) {
+        let result = result
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .expect("every device is wiped");
+        if let Ok(report) = &result {
+            println!("\n✨ {} has been securely wiped", path.display());
+            print_report(cli, report);
+        }
+        outcome.record(path, result);
+    }
+    finish_batch(cli, config, &what, (started, timer), audit, outcome);
+}
+
+/// securely wipes a set of files and directories, e.g. the contents of a trash folder
+fn shred_many(cli: &Cli, config: &Config, what: &str, entries: Vec<PathBuf>) {
+    #[cfg(windows)]
+    require_privileges(cli);
+
+    if entries.is_empty() {
+        println!("✓ Nothing to wipe in {}", what);
+        process::exit(exit_code::SUCCESS);
+    }
+    println!("Found {} item(s) in {}", entries.len(), what);
+
+    if !confirm_operation(what, cli.force) {
+        println!("Operation cancelled. Your files live another day.");
+        process::exit(exit_code::CANCELLED);
+    }
+
+    println!("☠️  Initiating secure deletion...");
+
+    // storage type is detected once per filesystem
+    let mut storage_by_mount: HashMap<PathBuf, StorageInfo> = HashMap::new();
+    let mut outcome = MultiWipeReport::default();
+    let started = CalendarTime::now();
+    let timer = Instant::now();
+    let _awake = SleepInhibitor::acquire(&format!("Wiping {}", what));
+    let audit = audit_log(cli, config);
+    if let Some(audit) = &audit {
+        audit.started(what);
+    }
+
+    for entry in &entries {
+        let mount_point = filesystem::mount_info(entry)
+            .map(|mount| mount.mount_point)
+            .unwrap_or_default();
+        let storage = storage_by_mount
+            .entry(mount_point)
+            .or_insert_with(|| detect_storage(entry));
+
+        let shredder = build_shredder(cli, config, storage, false);
+        outcome.merge(shredder.wipe_many([entry]));
+    }
+
+    let reports = &outcome.wiped;
+    println!(
+        "✨ {} file(s) securely shredded from {}!",
+        reports.len(),
+        what
+    );
+    let mut limitations: Vec<&String> = Vec::new();
+    for limitation in reports.iter().flat_map(|r| &r.limitations) {
+        if !limitations.contains(&limitation) {
+            limitations.push(limitation);
+        }
+    }
+    for limitation in &limitations {
+        println!("⚠️  Limitation: {}", limitation);
+    }
+    finish_batch(cli, config, what, (started, timer), audit, outcome);
+}
+
+/// prints the summary table of a batch, saves the reports, writes the audit
+/// records and sends the notifications, then exits: with success if every target was wiped, otherwise with
+/// the failure's exit code
+fn finish_batch(
+    cli: &Cli,
+    config: &Config,
+    what: &str,
+    (started, timer): (CalendarTime, Instant),
+    audit: Option<AuditLog>,
+    outcome: MultiWipeReport,
+) -> ! {
+    let errors = outcome.error_messages();
+    for error in &errors {
+        eprintln!("Failed while wiping {}", error);
+    }
+    let summary = outcome.summary();
+    print_summary(&summary, timer.elapsed());
+    if let Some(audit) = &audit {
+        audit.finished(what, &outcome.wiped);
+        audit.failed(what, &errors);
+    }
+    save_reports(cli, &outcome.wiped);
+    desktop_notification(cli, config, timer, what, errors.first().map(String::as_str));
+
+    let partial = outcome.is_partial();
+    let MultiWipeReport { wiped, failed } = outcome;
+    send_notification(config, what, started, wiped, errors, Some(summary));
+    match failed.into_iter().next() {
+        Some((_, e)) => exit_with_error_after(e, partial),
+        None => process::exit(exit_code::SUCCESS),
+    }
+}
+
+/// connects to the system log if --audit-log or the configuration asks for it
+fn audit_log(cli: &Cli, config: &Config) -> Option<AuditLog> {
+    if !cli.audit_log && !config.audit_log {
+        return None;
+    }
+    match AuditLog::open() {
+        Ok(log) => Some(log),
+        Err(e) => {
+            eprintln!("Warning: Unable to open the audit log: {}", e);
+            None
+        }
+    }
+}
+
+/// writes the --report file, if one was requested
+fn save_reports(cli: &Cli, reports: &[WipeReport]) {
+    if let Some(path) = &cli.report {
+        if let Err(e) = report::write_reports(path, reports) {
+            eprintln!("Warning: Unable to write report {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// sends the outcome to the webhook and mail recipients in the configuration, if any
+fn send_notification(
+    config: &Config,
+    subject: &str,
+    started: CalendarTime,
+    reports: Vec<WipeReport>,
+    errors: Vec<String>,
+    summary: Option<BatchSummary>,
+) {
+    if config.notify.webhook.is_none() && config.notify.email.is_none() {
+        return;
+    }
+    let notification = Notification {
+        subject: subject.to_string(),
+        started,
+        finished: CalendarTime::now(),
+        reports,
+        errors,
+        summary,
+    };
+    if let Err(e) = notify::send(&config.notify, &notification) {
+        eprintln!("Warning: {}", e);
+    }
+}
+
+/// tells the user at the desktop that a long wipe is over, since they have
+/// usually switched to something else by then
+fn desktop_notification(
+    cli: &Cli,
+    config: &Config,
+    timer: Instant,
+    subject: &str,
+    error: Option<&str>,
+) {
+    // a wipe started from the file manager has no terminal to report back to
+    let from_file_manager = matches!(cli.command, Some(Command::ShellAction { .. }));
+    let long_enough = timer.elapsed() >= Duration::from_secs(config.notify.desktop_after_secs);
+    if !config.notify.desktop || !(from_file_manager || long_enough && io::stdout().is_terminal()) {
+        return;
+    }
+    let result = match error {
+        None => notify::desktop(
+            "Wipe finished",
+            &format!("{} was securely shredded", subject),
+        ),
+        Some(error) => notify::desktop("Wipe failed", error),
+    };
+    // a desktop without a notification service is not worth a warning
+    if let Err(e) = result {
+        log::debug!("{}", e);
+    }
+}
+
+/// wipes a target on another machine by running shred there over ssh
+fn shred_remote(cli: &Cli, target: &str, sudo: bool, program: &str, ssh_options: &[String]) {
+    let target = match RemoteTarget::parse(target) {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(exit_code::FAILURE);
         }
+    };
+
+    // everything that changes how the remote side wipes is passed on
+    let mut args = vec![
+        "--standard".to_string(),
+        cli.standard.clone(),
+        "--verify".to_string(),
+        cli.verify.clone(),
+        "--sample-rate".to_string(),
+        cli.sample_rate.clone(),
+        "--sample-placement".to_string(),
+        cli.sample_placement.clone(),
+        "--snapshots".to_string(),
+        cli.snapshots.clone(),
+        "--open-files".to_string(),
+        cli.open_files.clone(),
+    ];
+    for (enabled, flag) in [
+        (cli.force, "--force"),
+        (cli.no_root_check, "--no-root-check"),
+        (cli.nocow, "--nocow"),
+        (cli.unmount, "--unmount"),
+        (cli.clear_flags, "--clear-flags"),
+        (cli.keep_attributes, "--keep-attributes"),
+        (cli.allow_system_disk, "--allow-system-disk"),
+        (cli.force_multipass, "--force-multipass"),
+        (cli.separate_verifier, "--separate-verifier"),
+        (cli.sandbox, "--sandbox"),
+        (cli.erase_signatures, "--erase-signatures"),
+    ] {
+        if enabled {
+            args.push(flag.to_string());
+        }
+    }
+    if let Some(mib) = cli.quick {
+        args.push(format!("--quick={}", mib));
+    }
+    let options = RemoteOptions {
+        program: program.to_string(),
+        sudo,
+        args,
+        ssh_args: ssh_options.to_vec(),
+    };
+
+    println!("Connecting to {}...", target.host);
+    let outcome = match remote::wipe_remote(&target, &options) {
+        Ok(outcome) => outcome,
         Err(e) => {
+            eprintln!("Error: Remote wipe of {} failed: {}", target, e);
+            process::exit(exit_code::FAILURE);
+        }
+    };
+
+    for report in &outcome.reports {
+        println!(
+            "✓ Report from {}: {} wiped with {} pass(es), {} bytes written",
+            target.host,
+            report.path.display(),
+            report.passes,
+            report.bytes_written
+        );
+    }
+    if !outcome.reports.is_empty() {
+        save_reports(cli, &outcome.reports);
+    }
+
+    match outcome.exit_code {
+        Some(0) => process::exit(exit_code::SUCCESS),
+        Some(code) => {
+            eprintln!("Error: shred on {} exited with code {}", target.host, code);
+            process::exit(code);
+        }
+        None => {
+            eprintln!(
+                "Error: The ssh connection to {} was terminated",
+                target.host
+            );
+            process::exit(exit_code::FAILURE);
+        }
+    }
+}
+
+/// securely wipes swap areas and puts them back into service
+fn shred_swap_areas(cli: &Cli, config: &Config, what: &str, areas: Vec<SwapArea>) {
+    require_privileges(cli);
+
+    for area in &areas {
+        println!(
+            "Found swap {} ({} MiB)",
+            area.path.display(),
+            area.size >> 20
+        );
+    }
+
+    if !confirm_operation(what, cli.force) {
+        println!("Operation cancelled. Your swap lives another day.");
+        process::exit(exit_code::CANCELLED);
+    }
+
+    println!("☠️  Initiating secure deletion...");
+
+    let mut outcome = MultiWipeReport::default();
+    let started = CalendarTime::now();
+    let timer = Instant::now();
+    let _awake = SleepInhibitor::acquire(&format!("Wiping {}", what));
+    let audit = audit_log(cli, config);
+    if let Some(audit) = &audit {
+        audit.started(what);
+    }
+    for area in &areas {
+        println!("Wiping swap {}...", area.path.display());
+        let storage_info = detect_storage(&area.path);
+        let shredder = build_shredder(cli, config, &storage_info, false);
+        let result = swap::wipe_swap(&shredder, area);
+        if let Ok(report) = &result {
+            for limitation in &report.limitations {
+                println!("⚠️  Limitation: {}", limitation);
+            }
+        }
+        outcome.record(&area.path, result);
+    }
+
+    println!("✨ {} swap area(s) securely shredded!", outcome.wiped.len());
+    finish_batch(cli, config, what, (started, timer), audit, outcome);
+}
+
+/// securely wipes the hibernation image: hiberfil.sys on windows volumes,
+/// the resume swap area on linux
+fn shred_hibernation(cli: &Cli, config: &Config, root: Option<&Path>) {
+    // linux writes the image into swap
+    if cfg!(target_os = "linux") && root.is_none() {
+        match swap::resume_area() {
+            Ok(Some(area)) => shred_swap_areas(cli, config, "the hibernation image", vec![area]),
+            Ok(None) => println!("✓ No hibernation resume device configured"),
+            Err(e) => {
+                eprintln!("Error: Unable to find the hibernation resume device: {}", e);
+                process::exit(exit_code::FAILURE);
+            }
+        }
+        return;
+    }
+
+    match locations::hibernation_file(root) {
+        Ok(Some(path)) => shred_path(cli, config, &path),
+        Ok(None) => println!("✓ No hibernation image found"),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(exit_code::FAILURE);
+        }
+    }
+}
+
+/// runs the configured jobs on their schedules until the process is stopped
+fn run_daemon(cli: &Cli, config: &Config) -> ! {
+    require_privileges(cli);
+
+    #[cfg(feature = "api")]
+    let listening = match &cli.command {
+        Some(Command::Daemon { listen: Some(addr) }) => {
+            start_api(cli, config, addr);
+            true
+        }
+        _ => false,
+    };
+    #[cfg(not(feature = "api"))]
+    let listening = false;
+
+    if config.jobs.is_empty() && !listening {
+        eprintln!("Error: No [[jobs]] defined in the configuration file");
+        process::exit(exit_code::FAILURE);
+    }
+    let mut jobs = Vec::new();
+    for job in &config.jobs {
+        match Schedule::parse(&job.schedule) {
+            Ok(schedule) => jobs.push((job, schedule)),
+            Err(e) => {
+                eprintln!("Error: Job '{}': {}", job.name, e);
+                process::exit(exit_code::FAILURE);
+            }
+        }
+    }
+    println!("Running {} scheduled job(s)...", jobs.len());
+
+    loop {
+        // wake up at the start of every minute
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        std::thread::sleep(Duration::from_secs(60 - seconds % 60));
+
+        let now = CalendarTime::now();
+        for (job, schedule) in &jobs {
+            if schedule.matches(&now) {
+                run_job(cli, config, job, &now);
+            }
+        }
+    }
+}
+
+/// starts the HTTP API in the background; wipes submitted through it use the
+/// daemon's options unless the request names a standard or verification level
+#[cfg(feature = "api")]
+fn start_api(cli: &Cli, config: &Config, addr: &str) {
+    use shredder::api::{ApiServer, JobRequest};
+    use shredder::jobs::JobId;
+
+    let Some(token) = config.api_token.clone() else {
+        eprintln!("Error: Set api_token in the configuration file to use --listen");
+        process::exit(exit_code::FAILURE);
+    };
+    let queue_file = config
+        .job_queue
+        .clone()
+        .unwrap_or_else(shredder::api::default_queue_path);
+    let server =
+        match ApiServer::bind(addr, token).and_then(|server| server.with_queue_file(queue_file)) {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("Error: Unable to serve the API on {}: {}", addr, e);
+                process::exit(exit_code::FAILURE);
+            }
+        };
+    println!("API listening on {}", addr);
+
+    let (cli, config) = (cli.clone(), config.clone());
+    std::thread::spawn(move || {
+        let served = server.run(move |request: &JobRequest, job: JobId| {
+            let job_cli =
+                with_overrides(&cli, request.standard.as_deref(), request.verify.as_deref());
+            let storage_info = detect_storage(&request.path);
+            let started = CalendarTime::now();
+            let subject = request.path.display().to_string();
+            let _awake = SleepInhibitor::acquire(&format!("Wiping {}", subject));
+            let audit = audit_log(&job_cli, &config);
+            if let Some(audit) = &audit {
+                audit.started(&subject);
+            }
+            let result = build_shredder(&job_cli, &config, &storage_info, false)
+                .with_job(job)
+                .wipe_with_report(&request.path);
+
+            let (reports, errors) = match &result {
+                Ok(report) => (vec![report.clone()], Vec::new()),
+                Err(e) => (Vec::new(), vec![e.to_string()]),
+            };
+            if let Some(audit) = &audit {
+                audit.finished(&subject, &reports);
+                audit.failed(&subject, &errors);
+            }
+            send_notification(&config, &subject, started, reports, errors, None);
+            result
+        });
+        if let Err(e) = served {
+            eprintln!("Error: API stopped: {}", e);
+            process::exit(exit_code::FAILURE);
+        }
+    });
+}
+
+/// owns org.shredder.Manager on the session bus and runs the wipes asked for
+/// through it until the bus goes away
+#[cfg(all(unix, feature = "dbus"))]
+fn serve_dbus(cli: &Cli, config: &Config) -> ! {
+    use shredder::dbus::{DbusService, BUS_NAME};
+    use shredder::progress::ProgressCallback;
+
+    let service = match DbusService::connect_session() {
+        Ok(service) => service,
+        Err(e) => {
+            eprintln!("Error: Unable to serve {}: {}", BUS_NAME, e);
+            process::exit(exit_code::FAILURE);
+        }
+    };
+    println!("Serving {} on the session bus", BUS_NAME);
+
+    let (cli, config) = (cli.clone(), config.clone());
+    let served = service.run(move |path: &Path, progress: ProgressCallback| {
+        let subject = path.display().to_string();
+        let started = CalendarTime::now();
+        let _awake = SleepInhibitor::acquire(&format!("Wiping {}", subject));
+        let audit = audit_log(&cli, &config);
+        if let Some(audit) = &audit {
+            audit.started(&subject);
+        }
+        let storage_info = detect_storage(path);
+        let outcome = build_shredder(&cli, &config, &storage_info, false)
+            .with_progress(progress)
+            .wipe_many([path]);
+
+        let errors = outcome.error_messages();
+        if let Some(audit) = &audit {
+            audit.finished(&subject, &outcome.wiped);
+            audit.failed(&subject, &errors);
+        }
+        let summary = outcome.summary();
+        send_notification(
+            &config,
+            &subject,
+            started,
+            outcome.wiped.clone(),
+            errors,
+            Some(summary),
+        );
+        outcome
+    });
+    if let Err(e) = served {
+        eprintln!("Error: Lost the session bus: {}", e);
+    }
+    process::exit(exit_code::FAILURE);
+}
+
+/// prints every named standard for `shred standards`
+fn print_standards() {
+    for (i, named) in NamedStandard::ALL.iter().enumerate() {
+        let standard = named.standard();
+        let passes = standard.passes();
+        let default = if i == 0 { " (default)" } else { "" };
+
+        if i > 0 {
+            println!();
+        }
+        println!("{}{} - {}", named.name, default, named.title);
+        println!("  {}", named.summary);
+        if standard.tries_hardware_erase() {
+            println!(
+                "  Passes:     hardware secure erase or sanitize where supported, otherwise {}",
+                passes.len()
+            );
+        } else {
+            println!("  Passes:     {}", passes.len());
+        }
+        for (pass, pattern) in passes.iter().enumerate() {
+            println!("    {:>2}. {}", pass + 1, pattern);
+        }
+        println!("  Media:      {}", named.media);
+        for (j, reference) in named.references.iter().enumerate() {
+            let label = if j == 0 { "References:" } else { "" };
+            println!("  {:<11} {}", label, reference);
+        }
+    }
+}
+
+/// prints what a wipe of the path would take, for `shred estimate`
+fn print_estimate(cli: &Cli, path: &Path, throughput: Option<f64>) {
+    let standard = selected_standard(cli);
+    let (storage, detected) = match StorageType::detect_from_path(path) {
+        Ok(info) => (info.device_type, true),
+        Err(_) => (fallback_storage().device_type, false),
+    };
+    let throughput = throughput.map(|mb| (mb * 1e6) as u64);
+
+    let estimate = match estimate::estimate(path, &standard, &storage, throughput) {
+        Ok(estimate) => estimate,
+        Err(e) => {
+            eprintln!("Error: Unable to read {}: {}", path.display(), e);
+            process::exit(exit_code::FAILURE);
+        }
+    };
+
+    let title = NamedStandard::find(&cli.standard).map_or(cli.standard.as_str(), |n| n.title);
+    let storage_name = match &storage {
+        StorageType::Hdd(_) => "hard disk",
+        StorageType::Ssd(caps) if caps.supports_nvme_sanitize => "NVMe SSD",
+        StorageType::Ssd(_) => "SSD",
+        StorageType::Flash(_) => "flash media",
+    };
+
+    println!("Estimate for {} ({})", path.display(), title);
+    println!(
+        "  Data:          {} in {} file(s)",
+        format_size(estimate.bytes),
+        estimate.files
+    );
+    if detected {
+        println!("  Storage:       {}", storage_name);
+    } else {
+        println!("  Storage:       unknown, assuming a hard disk");
+    }
+    if estimate.hardware_erase {
+        println!(
+            "  Mechanism:     hardware secure erase, usually minutes; {} overwrite pass(es) if it fails",
+            estimate.passes
+        );
+    } else {
+        println!("  Mechanism:     {} overwrite pass(es)", estimate.passes);
+    }
+    println!(
+        "  Writes:        {} ({} × {} passes)",
+        format_size(estimate.bytes_written),
+        format_size(estimate.bytes),
+        estimate.passes
+    );
+    println!(
+        "  Verification:  {} read back",
+        format_size(estimate.bytes_verified)
+    );
+    let source = if throughput.is_some() {
+        "as given"
+    } else {
+        "typical; pass --throughput with a measured figure"
+    };
+    println!(
+        "  Throughput:    {}/s ({})",
+        format_size(estimate.throughput),
+        source
+    );
+    println!(
+        "  Time:          about {}",
+        format_duration(estimate.duration())
+    );
+}
+
+/// checks a target against a pattern for `shred verify`
+/// disks holding an OS need an explicit override, even with --force; exits
+/// unless it was given and the disk's serial number re-typed
+///
+/// # Returns
+/// * `bool` - Whether the target is a system disk the operator confirmed
+fn confirm_if_system_disk(cli: &Cli, path: &Path, target: &Path) -> bool {
+    let is_system_disk =
+        device::is_block_device(target) && device::is_system_disk(target).unwrap_or(false);
+    if is_system_disk {
+        if !cli.allow_system_disk {
+            eprintln!(
+                "Error: {} holds an operating system and will not be wiped.",
+                path.display()
+            );
+            eprintln!("To wipe it from a live or offline environment, pass --allow-system-disk.");
+            process::exit(exit_code::REFUSED);
+        }
+        if !confirm_system_disk(path) {
+            println!("Confirmation did not match. The disk was not modified.");
+            process::exit(exit_code::CANCELLED);
+        }
+    }
+    is_system_disk
+}
+
+/// lists the signatures on a device, or erases them after confirmation
+fn handle_signatures(cli: &Cli, config: &Config, path: &Path, erase: bool) {
+    let target = filesystem::extended_length_path(path);
+    if !erase {
+        match shredder::signatures::find(&target) {
+            Ok(found) if found.is_empty() => {
+                println!("✓ No signatures found on {}", path.display())
+            }
+            Ok(found) => {
+                for signature in &found {
+                    println!("{}", signature);
+                }
+                println!("Pass --erase to erase them.");
+            }
+            Err(e) => exit_with_error(e),
+        }
+        return;
+    }
+
+    #[cfg(windows)]
+    require_privileges(cli);
+    let is_system_disk = confirm_if_system_disk(cli, path, &target);
+    if !confirm_operation(&format!("the signatures on {}", path.display()), cli.force) {
+        println!("Operation cancelled. The device was not modified.");
+        process::exit(exit_code::CANCELLED);
+    }
+
+    // nothing is overwritten beyond the magic bytes, so the storage type doesn't matter
+    let shredder = build_shredder(
+        cli,
+        config,
+        &fallback_storage(),
+        is_system_disk && cli.allow_system_disk,
+    );
+    match shredder.erase_signatures(&target) {
+        Ok(erased) if erased.is_empty() => println!("✓ No signatures found on {}", path.display()),
+        Ok(erased) => {
+            for signature in &erased {
+                println!("✓ Erased {}", signature);
+            }
+            println!("⚠️  Only the signatures were erased; the data is still on the device.");
+        }
+        Err(e) => exit_with_error(e),
+    }
+}
+
+/// lists the partitions of a disk, or destroys its table after confirmation
+fn handle_partitions(cli: &Cli, config: &Config, path: &Path, destroy: bool) {
+    let target = filesystem::extended_length_path(path);
+    if !destroy {
+        match shredder::partitions::read_table(&target) {
+            Ok(None) => println!("✓ No partition table found on {}", path.display()),
+            Ok(Some(table)) => {
+                println!(
+                    "{:?} partition table, {}-byte sectors",
+                    table.scheme, table.sector_size
+                );
+                for partition in &table.partitions {
+                    println!(
+                        "  {:>3}  start {:>14}  length {:>14}",
+                        partition.number, partition.start, partition.length
+                    );
+                }
+                println!("Pass --destroy to destroy it.");
+            }
+            Err(e) => exit_with_error(e),
+        }
+        return;
+    }
+
+    #[cfg(windows)]
+    require_privileges(cli);
+    let is_system_disk = confirm_if_system_disk(cli, path, &target);
+    if !confirm_operation(
+        &format!("the partition table of {}", path.display()),
+        cli.force,
+    ) {
+        println!("Operation cancelled. The disk was not modified.");
+        process::exit(exit_code::CANCELLED);
+    }
+
+    let shredder = build_shredder(
+        cli,
+        config,
+        &fallback_storage(),
+        is_system_disk && cli.allow_system_disk,
+    );
+    match shredder.destroy_partition_table(&target) {
+        Ok(zeroed) => {
+            for (start, end) in &zeroed {
+                println!("✓ Zeroed bytes {}..{}", start, end);
+            }
+            println!("✓ No GPT header or MBR remains on {}", path.display());
+            println!("⚠️  Only the partition table was destroyed; the data is still on the disk.");
+        }
+        Err(e) => exit_with_error(e),
+    }
+}
+
+fn verify_target(cli: &Cli, path: &Path, pattern: &str, sample: &str) {
+    let pattern = parse_pattern(pattern);
+    let sample = parse_sample(sample);
+
+    println!(
+        "🔍 Verifying {} against {} ({:.1}% of blocks)...",
+        path.display(),
+        pattern,
+        sample * 100.0
+    );
+    let target = filesystem::extended_length_path(path);
+    let placement = parse_sample_placement(&cli.sample_placement);
+    let mut report = match verify::verify_pattern(&target, &pattern, sample, placement) {
+        Ok(report) => report,
+        Err(e) => exit_with_error(e),
+    };
+    report.path = path.to_path_buf();
+
+    if let Some(file) = &cli.report {
+        let written = report
+            .to_toml()
+            .and_then(|toml| std::fs::write(file, toml).map_err(WipeError::from));
+        if let Err(e) = written {
+            eprintln!("Warning: Unable to write report {}: {}", file.display(), e);
+        }
+    }
+
+    let checked = report.blocks_checked * verify::BLOCK_SIZE as u64;
+    if report.passed() {
+        println!(
+            "✓ All {} blocks read ({} of {}) hold {}",
+            report.blocks_checked,
+            format_size(checked.min(report.size)),
+            format_size(report.size),
+            pattern
+        );
+        process::exit(exit_code::SUCCESS);
+    }
+
+    let offsets: Vec<String> = report
+        .mismatches
+        .iter()
+        .map(|offset| format!("{:#x}", offset))
+        .collect();
+    eprintln!(
+        "✗ {} of {} blocks read don't hold {}, starting at offsets {}",
+        report.mismatched_blocks,
+        report.blocks_checked,
+        pattern,
+        offsets.join(", ")
+    );
+    process::exit(exit_code::VERIFICATION_FAILED);
+}
+
+/// measures buffer sizes and saves the fastest, for `shred bench`
+fn run_bench(cli: &Cli, target: &Path, size: u64, no_save: bool) {
+    if device::is_block_device(target) {
+        require_privileges(cli);
+        if device::is_system_disk(target).unwrap_or(false) {
+            eprintln!(
+                "Error: {} holds an operating system and will not be benchmarked.",
+                target.display()
+            );
+            process::exit(exit_code::REFUSED);
+        }
+        let what = format!("the first {} MiB of {}", size, target.display());
+        if !confirm_operation(&what, cli.force) {
+            println!("Benchmark cancelled. The device was not modified.");
+            process::exit(exit_code::CANCELLED);
+        }
+    }
+
+    println!(
+        "⏱  Measuring {} buffer sizes with {} MiB each...",
+        bench::CANDIDATES.len(),
+        size
+    );
+    let results = match bench::benchmark(target, size << 20) {
+        Ok(results) => results,
+        Err(e) => exit_with_error(e),
+    };
+    let best = bench::best_buffer_size(&results);
+
+    println!("{:>10}  {:>12}  {:>12}", "Buffer", "Write", "Read");
+    for result in &results {
+        let mark = if Some(result.buffer_size) == best {
+            "  ← best"
+        } else {
+            ""
+        };
+        println!(
+            "{:>10}  {:>10}/s  {:>10}/s{}",
+            format_size(result.buffer_size as u64),
+            format_size(result.write_speed as u64),
+            format_size(result.read_speed as u64),
+            mark
+        );
+    }
+
+    let Some(best) = best else {
+        return;
+    };
+    if no_save {
+        return;
+    }
+    let Some(path) = cli.config.clone().or_else(Config::default_path) else {
+        eprintln!("Error: No configuration file location; pass --config");
+        process::exit(exit_code::FAILURE);
+    };
+    match Config::save_buffer_size(&path, best) {
+        Ok(()) => println!(
+            "✓ Saved buffer_size = {} ({}) to {}",
+            best,
+            format_size(best as u64),
+            path.display()
+        ),
+        Err(e) => {
+            eprintln!("Error: Unable to update {}: {}", path.display(), e);
+            process::exit(exit_code::FAILURE);
+        }
+    }
+}
+
+/// runs `shred tui`
+fn run_tui(cli: &Cli, config: &Config, dir: Option<&Path>) {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        eprintln!("Error: The terminal UI needs an interactive terminal");
+        process::exit(exit_code::USAGE);
+    }
+    require_privileges(cli);
+    if let Err(e) = tui::run(cli, config, dir) {
+        eprintln!("Error: {}", e);
+        process::exit(exit_code::FAILURE);
+    }
+}
+
+/// refuses command lines weaker than the system policy before anything is
+/// asked or wiped; the library checks every wipe against it again
+fn enforce_policy(cli: &Cli, config: &mut Config, policy: &Policy) {
+    config.audit_log |= policy.audit_log;
+
+    let wipes = match &cli.command {
+        None => cli.path.is_some() || !cli.preset.is_empty(),
+        Some(command) => matches!(
+            command,
+            Command::RecycleBin
+                | Command::Trash
+                | Command::ShellAction { .. }
+                | Command::TrashPut { .. }
+                | Command::Device { .. }
+                | Command::Swap
+                | Command::Hibernation { .. }
+                | Command::Remote { .. }
+                | Command::Tui { .. }
+                | Command::Wizard { .. }
+        ),
+    };
+    #[cfg(all(unix, feature = "dbus"))]
+    let wipes = wipes || matches!(cli.command, Some(Command::Dbus));
+    let runs_jobs = matches!(
+        cli.command,
+        Some(Command::Daemon { .. } | Command::Job { .. })
+    );
+    let refuse = |reason: String| -> ! {
+        eprintln!(
+            "Error: Refused by policy {}: {}",
+            Policy::system_path().display(),
+            reason
+        );
+        process::exit(exit_code::REFUSED);
+    };
+
+    if wipes {
+        // the automatic standard is checked once the storage is known
+        let standard = selected_standard(cli);
+        if !matches!(standard, WipeStandard::Auto(_)) {
+            if let Err(e) = policy.check_standard(&standard) {
+                refuse(e.to_string());
+            }
+        }
+        if cli.quick.is_some() {
+            if let Err(e) = policy.check_quick() {
+                refuse(e.to_string());
+            }
+        }
+        if policy.require_report && cli.report.is_none() {
+            refuse("every wipe must write a report; pass --report <FILE>".to_string());
+        }
+    }
+    if runs_jobs && policy.require_report {
+        if let Some(job) = config.jobs.iter().find(|job| job.report.is_none()) {
+            refuse(format!(
+                "every job must write a report; job '{}' has no report file",
+                job.name
+            ));
+        }
+    }
+}
+
+/// the command line options with a job's standard and verification level applied
+fn with_overrides(cli: &Cli, standard: Option<&str>, verify: Option<&str>) -> Cli {
+    Cli {
+        standard: standard.map_or_else(|| cli.standard.clone(), str::to_string),
+        verify: verify.map_or_else(|| cli.verify.clone(), str::to_string),
+        ..cli.clone()
+    }
+}
+
+/// adds the file manager entries for the current user, or removes them
+fn integrate(remove: bool) {
+    let result = if remove {
+        integrate::uninstall()
+    } else {
+        match std::env::current_exe() {
+            // the entries run this binary by its absolute path
+            Ok(program) => integrate::install(&program),
+            Err(e) => {
+                eprintln!("Error: Unable to find the shred binary: {}", e);
+                process::exit(exit_code::FAILURE);
+            }
+        }
+    };
+    match result {
+        Ok(changed) if changed.is_empty() => println!("✓ No entries to remove"),
+        Ok(changed) => {
+            for entry in &changed {
+                println!("✓ {} {}", if remove { "Removed" } else { "Added" }, entry);
+            }
+            if !remove {
+                println!(
+                    "\"{}\" is in the context menu; restart the file manager if it isn't showing yet",
+                    integrate::LABEL
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(exit_code::FAILURE);
+        }
+    }
+}
+
+/// wipes what was selected in a file manager, once the user agrees in a dialog
+fn shell_action(cli: &Cli, config: &Config, paths: &[PathBuf]) {
+    let confirmed = match integrate::confirm(paths) {
+        Ok(confirmed) => confirmed,
+        // e.g. the Send To console, which can take the typed confirmation
+        Err(e) if io::stdin().is_terminal() => {
+            log::debug!("{}", e);
+            confirm_operation("the selection", false)
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(exit_code::FAILURE);
+        }
+    };
+    if !confirmed {
+        println!("Operation cancelled. Your files live another day.");
+        process::exit(exit_code::CANCELLED);
+    }
+
+    let cli = Cli {
+        force: true,
+        ..cli.clone()
+    };
+    shred_many(&cli, config, "the selection", paths.to_vec());
+}
+
+/// the arguments as `shred trash-put` takes them, when the binary was invoked
+/// as trash-put; trash-put's -v becomes --verbose, since -v is --verify here
+fn trash_put_args() -> Option<Vec<std::ffi::OsString>> {
+    let mut args = std::env::args_os();
+    let program = args.next()?;
+    if Path::new(&program).file_stem()? != "trash-put" {
+        return None;
+    }
+
+    let mut rewritten = vec![program, "trash-put".into()];
+    let mut options = true;
+    for arg in args {
+        if arg == "--" {
+            options = false;
+        }
+        if options && arg == "-v" {
+            rewritten.push("--verbose".into());
+        } else {
+            rewritten.push(arg);
+        }
+    }
+    Some(rewritten)
+}
+
+/// deletes files the way trash-put does, except that they are shredded:
+/// right away, or after `grace` seconds in the trash
+fn trash_put(
+    cli: &Cli,
+    config: &Config,
+    files: &[PathBuf],
+    grace: u64,
+    interactive: bool,
+    verbose: bool,
+) -> ! {
+    let mut failed = false;
+    let mut now = Vec::new();
+    let mut deferred = false;
+    for file in files {
+        let Ok(metadata) = std::fs::symlink_metadata(file) else {
+            // like rm -f, --force is quiet about what isn't there
+            if !cli.force {
+                eprintln!("trash-put: cannot trash non existent '{}'", file.display());
+                failed = true;
+            }
+            continue;
+        };
+        let kind = if metadata.is_dir() {
+            "directory"
+        } else {
+            "regular file"
+        };
+        if interactive && !ask(&format!("trash-put: shred {} '{}'? ", kind, file.display())) {
+            continue;
+        }
+        if grace == 0 {
+            now.push(file.clone());
+            continue;
+        }
+
+        match trash_put::defer(file, grace) {
+            Ok(pending) => {
+                deferred = true;
+                if verbose {
+                    println!(
+                        "trash-put: '{}' trashed as '{}', to be shredded in {}",
+                        file.display(),
+                        pending.trashed.display(),
+                        format_duration(Duration::from_secs(grace))
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!("trash-put: cannot trash '{}': {}", file.display(), e);
+                failed = true;
+            }
+        }
+    }
+
+    let outcome = shred_quietly(cli, config, "files deleted with trash-put", &now);
+    for file in &now {
+        if verbose
+            && !outcome
+                .failed
+                .iter()
+                .any(|(path, _)| path.starts_with(file))
+        {
+            println!("trash-put: '{}' shredded", file.display());
+        }
+    }
+    failed |= !outcome.failed.is_empty();
+
+    // whatever came due while no purger was running, e.g. across a reboot
+    failed |= !shred_due(cli, config);
+    if deferred {
+        spawn_purger(cli);
+    }
+    process::exit(if failed {
+        exit_code::FAILURE
+    } else {
+        exit_code::SUCCESS
+    });
+}
+
+/// shreds the trashed files whose grace period is over; with `wait`, keeps
+/// at it until nothing is pending, unless another process already does
+fn purge_trash(cli: &Cli, config: &Config, wait: bool) -> ! {
+    if !wait {
+        let wiped = shred_due(cli, config);
+        process::exit(if wiped {
+            exit_code::SUCCESS
+        } else {
+            exit_code::FAILURE
+        });
+    }
+
+    let _lock = match trash_put::purger_lock() {
+        Ok(Some(lock)) => lock,
+        Ok(None) => process::exit(exit_code::SUCCESS),
+        Err(e) => {
+            eprintln!("Error: Unable to lock the pending list: {}", e);
+            process::exit(exit_code::FAILURE);
+        }
+    };
+    loop {
+        shred_due(cli, config);
+        let due = match trash_put::next_due() {
+            Ok(Some(due)) => due,
+            Ok(None) => process::exit(exit_code::SUCCESS),
+            Err(e) => {
+                eprintln!("Error: Unable to read the pending list: {}", e);
+                process::exit(exit_code::FAILURE);
+            }
+        };
+        // woken up now and then, since other runs add items that are due sooner
+        let wait = due.saturating_sub(trash_put::now_secs()).clamp(1, 60);
+        std::thread::sleep(Duration::from_secs(wait));
+    }
+}
+
+/// shreds the trashed files that are due and drops their trash records;
+/// a file that can't be shredded stays in the trash and on the pending list,
+/// to be tried again later. false if any failed
+fn shred_due(cli: &Cli, config: &Config) -> bool {
+    let due = match trash_put::take_due() {
+        Ok(due) => due,
+        Err(e) => {
+            eprintln!("trash-put: cannot read the pending list: {}", e);
+            return false;
+        }
+    };
+    let targets: Vec<PathBuf> = due.iter().map(|pending| pending.trashed.clone()).collect();
+    let outcome = shred_quietly(cli, config, "files trashed with trash-put", &targets);
+    let mut retry = Vec::new();
+    for pending in due {
+        let failed = outcome
+            .failed
+            .iter()
+            .any(|(path, _)| path.starts_with(&pending.trashed));
+        if failed {
+            retry.push(pending);
+        } else if let Err(e) = pending.forget() {
+            log::warn!("Unable to remove {}: {}", pending.info.display(), e);
+        }
+    }
+    if let Err(e) = trash_put::retry_later(retry) {
+        eprintln!(
+            "trash-put: cannot put failed files back on the pending list: {}",
+            e
+        );
+        return false;
+    }
+    outcome.failed.is_empty()
+}
+
+/// wipes `targets` without the chatter of the other commands, printing only
+/// what failed, and records the wipes like the others: in the audit log, the
+/// reports and the notifications
+fn shred_quietly(cli: &Cli, config: &Config, what: &str, targets: &[PathBuf]) -> MultiWipeReport {
+    let mut outcome = MultiWipeReport::default();
+    if targets.is_empty() {
+        return outcome;
+    }
+
+    let started = CalendarTime::now();
+    let timer = Instant::now();
+    let _awake = SleepInhibitor::acquire(&format!("Wiping {}", what));
+    let audit = audit_log(cli, config);
+    if let Some(audit) = &audit {
+        audit.started(what);
+    }
+
+    let mut storage_by_mount: HashMap<PathBuf, StorageInfo> = HashMap::new();
+    for target in targets {
+        let mount_point = filesystem::mount_info(target)
+            .map(|mount| mount.mount_point)
+            .unwrap_or_default();
+        let storage = storage_by_mount.entry(mount_point).or_insert_with(|| {
+            StorageType::detect_from_path(target).unwrap_or_else(|e| {
+                log::debug!("Error detecting storage type: {}", e);
+                fallback_storage()
+            })
+        });
+        let shredder = build_shredder(cli, config, storage, false);
+        outcome.merge(shredder.wipe_many([target]));
+    }
+
+    let errors = outcome.error_messages();
+    for (path, e) in &outcome.failed {
+        eprintln!("trash-put: cannot shred '{}': {}", path.display(), e);
+    }
+    if let Some(audit) = &audit {
+        audit.finished(what, &outcome.wiped);
+        audit.failed(what, &errors);
+    }
+    save_reports(cli, &outcome.wiped);
+    desktop_notification(cli, config, timer, what, errors.first().map(String::as_str));
+    send_notification(
+        config,
+        what,
+        started,
+        outcome.wiped.clone(),
+        errors,
+        Some(outcome.summary()),
+    );
+    outcome
+}
+
+/// starts `shred trash-put --purge --wait` in the background, detached from
+/// the terminal, to shred the deferred files when they come due
+fn spawn_purger(cli: &Cli) {
+    let program = match std::env::current_exe() {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("trash-put: cannot start the background purge: {}", e);
+            return;
+        }
+    };
+    let mut command = process::Command::new(program);
+    command
+        .args(["trash-put", "--purge", "--wait"])
+        .args(["--standard", &cli.standard, "--verify", &cli.verify])
+        .current_dir("/")
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null());
+    if let Some(config) = &cli.config {
+        command
+            .arg("--config")
+            .arg(std::path::absolute(config).unwrap_or_else(|_| config.clone()));
+    }
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    if let Err(e) = command.spawn() {
+        eprintln!("trash-put: cannot start the background purge: {}", e);
+    }
+}
+
+/// asks a yes/no question on stderr, as rm -i does; anything but y is no
+fn ask(question: &str) -> bool {
+    eprint!("{}", question);
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).is_ok()
+        && answer.trim_start().to_lowercase().starts_with('y')
+}
+
+/// prints or writes the polkit action for the elevated helper
+fn write_polkit_action(output: Option<&Path>) {
+    // pkexec matches the action by the binary's absolute path
+    let program = match std::env::current_exe() {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("Error: Unable to find the shred binary: {}", e);
+            process::exit(exit_code::FAILURE);
+        }
+    };
+    let action = polkit::action(&program);
+
+    let Some(dir) = output else {
+        print!("{}", action);
+        return;
+    };
+    let path = dir.join(format!("{}.policy", polkit::ACTION_ID));
+    if let Err(e) = std::fs::write(&path, action) {
+        eprintln!("Error: Unable to write {}: {}", path.display(), e);
+        process::exit(exit_code::FAILURE);
+    }
+    println!("✓ Wrote {}", path.display());
+    if dir != Path::new(polkit::ACTIONS_DIR) {
+        println!("pkexec is used once it is in {}", polkit::ACTIONS_DIR);
+    }
+}
+
+/// runs the job with the given name once, for `shred job` and systemd timers
+fn run_named_job(cli: &Cli, config: &Config, name: &str) -> ! {
+    require_privileges(cli);
+
+    let Some(job) = config.jobs.iter().find(|job| job.name == name) else {
+        eprintln!("Error: No job named '{}' in the configuration file", name);
+        process::exit(exit_code::FAILURE);
+    };
+    process::exit(run_job(cli, config, job, &CalendarTime::now()));
+}
+
+/// prints or writes systemd units for the daemon and every configured job
+fn write_systemd_units(cli: &Cli, config: &Config, output: Option<&Path>) {
+    // the units must work regardless of the directory systemd starts them in
+    let config_path = match cli.config.clone().or_else(Config::default_path) {
+        Some(path) => std::fs::canonicalize(&path).unwrap_or(path),
+        None => {
+            eprintln!("Error: Pass the configuration file with --config");
+            process::exit(exit_code::FAILURE);
+        }
+    };
+    let program = match std::env::current_exe() {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("Error: Unable to find the shred binary: {}", e);
+            process::exit(exit_code::FAILURE);
+        }
+    };
+
+    let daemon = systemd::daemon_service(&program, &config_path);
+    let mut enable = vec![daemon.name.clone()];
+    let mut units = vec![daemon];
+    for job in &config.jobs {
+        match systemd::job_units(&program, &config_path, job) {
+            Ok((service, timer)) => {
+                enable.push(timer.name.clone());
+                units.extend([service, timer]);
+            }
+            Err(e) => {
+                eprintln!("Error: Job '{}': {}", job.name, e);
+                process::exit(exit_code::FAILURE);
+            }
+        }
+    }
+
+    let Some(dir) = output else {
+        for unit in &units {
+            println!("# {}\n{}", unit.name, unit.contents);
+        }
+        return;
+    };
+    for unit in &units {
+        let path = dir.join(&unit.name);
+        if let Err(e) = std::fs::write(&path, &unit.contents) {
+            eprintln!("Error: Unable to write {}: {}", path.display(), e);
+            process::exit(exit_code::FAILURE);
+        }
+        println!("✓ Wrote {}", path.display());
+    }
+    println!("Run `systemctl daemon-reload`, then enable either the daemon or the job timers:");
+    for name in &enable {
+        println!("  systemctl enable --now {}", name);
+    }
+}
+
+/// runs one scheduled job; failures are reported instead of ending the daemon
+///
+/// returns the exit code for `shred job`: success, partial failure or failure
+fn run_job(cli: &Cli, config: &Config, job: &JobConfig, started: &CalendarTime) -> i32 {
+    println!(
+        "[{}] Starting job '{}' on {}",
+        started,
+        job.name,
+        job.path.display()
+    );
+
+    let job_cli = with_overrides(cli, job.standard.as_deref(), job.verify.as_deref());
+    let mut errors: Vec<String> = Vec::new();
+    let _awake = SleepInhibitor::acquire(&format!("Running job '{}'", job.name));
+
+    // a directory's contents are wiped, the directory itself stays
+    let entries = if job.path.is_dir() {
+        match std::fs::read_dir(&job.path) {
+            Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+            Err(e) => {
+                errors.push(format!("{}: {}", job.path.display(), e));
+                Vec::new()
+            }
+        }
+    } else if job.path.exists() {
+        vec![job.path.clone()]
+    } else {
+        Vec::new()
+    };
+
+    let subject = format!("job '{}'", job.name);
+    let audit = audit_log(cli, config);
+    if let Some(audit) = &audit {
+        audit.started(&subject);
+    }
+    let mut reports: Vec<WipeReport> = Vec::new();
+    let mut summary = None;
+    if !entries.is_empty() {
+        let storage_info = detect_storage(&job.path);
+        let shredder = build_shredder(&job_cli, config, &storage_info, false);
+        let timer = Instant::now();
+        let outcome = shredder.wipe_many(&entries);
+        errors.extend(outcome.error_messages());
+        let batch = outcome.summary();
+        print_summary(&batch, timer.elapsed());
+        summary = Some(batch);
+        reports = outcome.wiped;
+    }
+    let files = reports.len();
+
+    for error in &errors {
+        eprintln!("Job '{}' failed to wipe {}", job.name, error);
+    }
+    if let Some(audit) = &audit {
+        audit.finished(&subject, &reports);
+        audit.failed(&subject, &errors);
+    }
+    let code = match (errors.is_empty(), files) {
+        (true, _) => exit_code::SUCCESS,
+        (false, 0) => exit_code::FAILURE,
+        (false, _) => exit_code::PARTIAL_FAILURE,
+    };
+    let status = if errors.is_empty() { "ok" } else { "failed" };
+    println!(
+        "[{}] Job '{}' finished: {} file(s) shredded, {} error(s)",
+        CalendarTime::now(),
+        job.name,
+        files,
+        errors.len()
+    );
+
+    if let Some(report) = &job.report {
+        let mut line = format!(
+            "{} job={} path={} standard={} files={} errors={} status={}\n",
+            started,
+            job.name,
+            job.path.display(),
+            job_cli.standard,
+            files,
+            errors.len(),
+            status
+        );
+        for error in &errors {
+            line.push_str(&format!("  error: {}\n", error));
+        }
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(report)
+            .and_then(|mut file| file.write_all(line.as_bytes()));
+        if let Err(e) = written {
+            eprintln!(
+                "Job '{}': Unable to write report {}: {}",
+                job.name,
+                report.display(),
+                e
+            );
+        }
+    }
+    send_notification(config, &subject, *started, reports, errors, summary);
+    code
+}
+
+/// explains a failed wipe and exits with the matching exit code
+fn exit_with_error(error: WipeError) -> ! {
+    exit_with_error_after(error, false)
+}
+
+/// like `exit_with_error`, for a batch in which `partial` says whether some
+/// targets were already wiped before the error
+fn exit_with_error_after(error: WipeError, partial: bool) -> ! {
+    if partial {
+        eprintln!("Error during secure deletion: {}", error);
+        eprintln!("⚠️  Some items were wiped before the error; the rest were not.");
+        process::exit(exit_code::PARTIAL_FAILURE);
+    }
+
+    match error {
+        e @ WipeError::ProtectedPath { .. } => {
+            eprintln!("Error: {}", e);
+            eprintln!("The file was not modified.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::SystemDisk(_) => {
+            eprintln!("Error: {}", e);
+            eprintln!("The device was not modified.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::PartitionBounds { .. } => {
+            eprintln!("Error: {}", e);
+            eprintln!("The device was not modified. Have the OS re-read the partition table (partprobe) and check it.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::PolicyViolation(_) => {
+            eprintln!("Error: {}", e);
+            eprintln!("The target was not modified. Ask your administrator about the policy.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::MultipassOnWearLeveledStorage { .. } => {
+            eprintln!("Error: {}", e);
+            eprintln!(
+                "The target was not modified. Use --standard auto, or pass --force-multipass."
+            );
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::ProtectedFlags { .. } => {
+            eprintln!("Error: {}", e);
+            eprintln!("The file was not modified. Pass --clear-flags to remove the attribute.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::ReadOnlyFilesystem(_) => {
+            eprintln!("Error: {}", e);
+            eprintln!("The file was not modified. Remount the filesystem read-write and retry.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::DeviceMounted { .. } => {
+            eprintln!("Error: {}", e);
+            eprintln!("The device was not modified. Unmount it or pass --unmount.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::VolumeLock { .. } => {
+            eprintln!("Error: {}", e);
+            eprintln!("The device was not modified. Close programs using its volumes and retry.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::FileInUse(_) => {
+            eprintln!("Error: {}", e);
+            eprintln!("The file was not modified. Close those programs or use --open-files warn.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::TargetBusy(_) => {
+            eprintln!("Error: {}", e);
+            eprintln!("The target was not modified. Wait for the other wipe to finish.");
+            process::exit(exit_code::REFUSED);
+        }
+        e @ WipeError::SnapshotsPresent(_) => {
+            eprintln!("Error: {}", e);
+            eprintln!("The file was not modified. Delete the snapshots or use --snapshots warn.");
+            process::exit(exit_code::REFUSED);
+        }
+        e => {
+            let code = match e.root_cause() {
+                WipeError::Io(io) if io.kind() == io::ErrorKind::PermissionDenied => {
+                    exit_code::PERMISSION_DENIED
+                }
+                WipeError::VerificationFailed(_) => exit_code::VERIFICATION_FAILED,
+                WipeError::UnsupportedOperation(_) => exit_code::UNSUPPORTED,
+                _ => exit_code::FAILURE,
+            };
             eprintln!("Error during secure deletion: {}", e);
             eprintln!("⚠️  WARNING: The file may not have been completely shredded!");
-            process::exit(1);
+            process::exit(code);
         }
     }
 }
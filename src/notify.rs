@@ -0,0 +1,438 @@
+use crate::config::{EmailConfig, NotifyConfig, StartTls};
+use crate::report::{
+    json_string, report_json, reports_to_toml, summary_json, BatchSummary, WipeReport,
+};
+use crate::schedule::CalendarTime;
+use crate::{Result, WipeError};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// how long to wait for a webhook or mail server before giving up
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// outcome of a wipe or job, sent to the configured webhook and mail recipients
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// what was wiped, e.g. "job 'print-spool'" or "/dev/sdb"
+    pub subject: String,
+
+    /// when the wipe started
+    pub started: CalendarTime,
+
+    /// when the wipe finished or failed
+    pub finished: CalendarTime,
+
+    /// reports of the targets that were wiped
+    pub reports: Vec<WipeReport>,
+
+    /// errors that occurred, empty if the wipe succeeded
+    pub errors: Vec<String>,
+
+    /// how each target fared, when several were wiped as one batch
+    pub summary: Option<BatchSummary>,
+}
+
+impl Notification {
+    /// whether everything was wiped
+    pub fn succeeded(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// JSON document posted to the webhook
+    fn to_json(&self) -> String {
+        let errors: Vec<String> = self.errors.iter().map(|e| json_string(e)).collect();
+        let wipes: Vec<String> = self.reports.iter().map(report_json).collect();
+        format!(
+            "{{\"host\":{},\"subject\":{},\"status\":\"{}\",\"started\":{},\"finished\":{},\"errors\":[{}],\"wipes\":[{}],\"summary\":{}}}",
+            json_string(&hostname()),
+            json_string(&self.subject),
+            if self.succeeded() { "succeeded" } else { "failed" },
+            json_string(&self.started.to_string()),
+            json_string(&self.finished.to_string()),
+            errors.join(","),
+            wipes.join(","),
+            self.summary
+                .as_ref()
+                .map_or_else(|| "null".to_string(), summary_json),
+        )
+    }
+
+    /// subject line and plain text body of the mail, with the reports attached as TOML
+    fn to_mail(&self) -> (String, String) {
+        let status = if self.succeeded() {
+            "succeeded"
+        } else {
+            "failed"
+        };
+        let host = hostname();
+        let mut body = format!(
+            "Wipe of {} on {} {}.\n\nStarted:  {}\nFinished: {}\nFiles:    {}\n",
+            self.subject,
+            host,
+            status,
+            self.started,
+            self.finished,
+            self.reports.len()
+        );
+        if !self.errors.is_empty() {
+            body.push_str("\nErrors:\n");
+            for error in &self.errors {
+                body.push_str(&format!("  {}\n", error));
+            }
+        }
+        if !self.reports.is_empty() {
+            body.push_str("\nReport:\n\n");
+            body.push_str(&reports_to_toml(&self.reports).unwrap_or_default());
+        }
+        (
+            format!("shredder: {} {} on {}", self.subject, status, host),
+            body,
+        )
+    }
+}
+
+/// sends a notification to every configured target
+///
+/// all targets are tried even if one fails
+///
+/// # Arguments
+/// * `config` - Webhook and mail settings
+/// * `notification` - Outcome to send
+///
+/// # Returns
+/// * `Result<()>` - Ok if every target accepted it, otherwise the failures
+pub fn send(config: &NotifyConfig, notification: &Notification) -> Result<()> {
+    let mut failures = Vec::new();
+    if let Some(url) = &config.webhook {
+        if let Err(e) = post_webhook(url, &notification.to_json()) {
+            failures.push(format!("webhook {}: {}", url, e));
+        }
+    }
+    if let Some(email) = &config.email {
+        let (subject, body) = notification.to_mail();
+        if let Err(e) = send_mail(email, &subject, &body) {
+            failures.push(format!("mail via {}: {}", email.smtp, e));
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(WipeError::Notification(failures.join("; ")))
+    }
+}
+
+/// shows a desktop notification to the user who started the wipe
+///
+/// uses notify-send on linux and the BSDs, osascript on macOS and a toast on
+/// windows. under sudo the notification is shown in the invoking user's session
+///
+/// # Arguments
+/// * `title` - First line, e.g. "Wipe finished"
+/// * `message` - Details, e.g. the wiped path
+///
+/// # Returns
+/// * `Result<()>` - Ok if the notification was handed to the desktop
+pub fn desktop(title: &str, message: &str) -> Result<()> {
+    let status = desktop_command(title, message)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| WipeError::Notification(format!("desktop notification: {}", e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(WipeError::Notification(format!(
+            "desktop notification: {}",
+            status
+        )))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_command(title: &str, message: &str) -> Command {
+    let script = format!(
+        "display notification {} with title {}",
+        applescript_string(message),
+        applescript_string(title)
+    );
+    // as root, post into the invoking user's login session
+    let uid = crate::locations::invoking_uid();
+    let mut command = if unsafe { libc::geteuid() } == 0 && uid != 0 {
+        let mut command = Command::new("launchctl");
+        command.args(["asuser", &uid.to_string(), "osascript"]);
+        command
+    } else {
+        Command::new("osascript")
+    };
+    command.args(["-e", &script]);
+    command
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn desktop_command(title: &str, message: &str) -> Command {
+    // as root, notify-send has to run as the invoking user to reach their session bus
+    let uid = crate::locations::invoking_uid();
+    let mut command = if unsafe { libc::geteuid() } == 0 && uid != 0 {
+        let mut command = Command::new("sudo");
+        command.args(["-u", &format!("#{}", uid), "env"]);
+        command.arg(format!(
+            "DBUS_SESSION_BUS_ADDRESS=unix:path=/run/user/{}/bus",
+            uid
+        ));
+        command.arg("notify-send");
+        command
+    } else {
+        Command::new("notify-send")
+    };
+    command.args(["--app-name=shredder", title, message]);
+    command
+}
+
+#[cfg(windows)]
+fn desktop_command(title: &str, message: &str) -> Command {
+    // the texts travel in environment variables so they need no quoting
+    const SCRIPT: &str = "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+        $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+        $text = $xml.GetElementsByTagName('text'); \
+        $text[0].AppendChild($xml.CreateTextNode($env:SHREDDER_TITLE)) > $null; \
+        $text[1].AppendChild($xml.CreateTextNode($env:SHREDDER_MESSAGE)) > $null; \
+        $app = '{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe'; \
+        [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($app).Show([Windows.UI.Notifications.ToastNotification]::new($xml))";
+
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("SHREDDER_TITLE", title)
+        .env("SHREDDER_MESSAGE", message);
+    command
+}
+
+/// posts JSON to a URL with curl, which handles https, proxies and redirects
+fn post_webhook(url: &str, json: &str) -> std::io::Result<()> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--max-time", &TIMEOUT.as_secs().to_string()])
+        .args(["--header", "Content-Type: application/json"])
+        .args(["--data-binary", "@-", "--output"])
+        .arg(if cfg!(windows) { "NUL" } else { "/dev/null" })
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(json.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// delivers a mail through an SMTP relay, upgrading the connection with
+/// STARTTLS and logging in as configured
+fn send_mail(config: &EmailConfig, subject: &str, body: &str) -> std::io::Result<()> {
+    use lettre::message::{header::ContentType, Mailbox};
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::transport::smtp::client::{Tls, TlsParameters};
+    use lettre::transport::smtp::extension::ClientId;
+    use lettre::{Message, SmtpTransport, Transport};
+
+    let invalid = |e: &dyn std::fmt::Display| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string())
+    };
+    let mailbox = |address: &str| address.parse::<Mailbox>().map_err(|e| invalid(&e));
+
+    // a line break would end the header and start another one; lettre encodes
+    // the rest (RFC 2047) and dot-stuffs the body
+    let subject = subject.replace(['\r', '\n'], " ");
+    let mut message = Message::builder()
+        .from(mailbox(&config.from)?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for recipient in &config.to {
+        message = message.to(mailbox(recipient)?);
+    }
+    let message = message.body(body.to_string()).map_err(|e| invalid(&e))?;
+
+    let (host, port) = match config.smtp.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|e| invalid(&e))?),
+        None => (config.smtp.as_str(), 25),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let parameters = TlsParameters::new(host.to_string()).map_err(std::io::Error::other)?;
+    let mut transport = SmtpTransport::builder_dangerous(host)
+        .port(port)
+        .hello_name(ClientId::Domain(hostname()))
+        .timeout(Some(TIMEOUT));
+    transport = match (&config.username, config.starttls) {
+        // the password never goes out in plain text
+        (Some(_), StartTls::Never) => {
+            return Err(invalid(&"a username needs starttls"));
+        }
+        (Some(username), _) => {
+            transport
+                .tls(Tls::Required(parameters))
+                .credentials(Credentials::new(
+                    username.clone(),
+                    config.password.clone().unwrap_or_default(),
+                ))
+        }
+        (None, StartTls::Required) => transport.tls(Tls::Required(parameters)),
+        (None, StartTls::Opportunistic) => transport.tls(Tls::Opportunistic(parameters)),
+        (None, StartTls::Never) => transport.tls(Tls::None),
+    };
+
+    transport
+        .build()
+        .send(&message)
+        .map(|_| ())
+        .map_err(std::io::Error::other)
+}
+
+/// name of this machine, so recipients know where the wipe ran
+fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut buffer = [0u8; 256];
+        let result =
+            unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+        if result == 0 {
+            let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            return String::from_utf8_lossy(&buffer[..end]).into_owned();
+        }
+    }
+    #[cfg(windows)]
+    if let Ok(name) = std::env::var("COMPUTERNAME") {
+        return name;
+    }
+    "unknown host".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+
+    fn notification(errors: Vec<String>) -> Notification {
+        let time = CalendarTime {
+            year: 2025,
+            month: 5,
+            day: 14,
+            hour: 3,
+            minute: 0,
+            weekday: 3,
+        };
+        let mut report = WipeReport::new(PathBuf::from("/srv/old.db"));
+        report.record_pass(4096);
+        Notification {
+            subject: "job 'nightly'".into(),
+            started: time,
+            finished: time,
+            reports: vec![report],
+            errors,
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let json = notification(vec!["/srv/x: denied".into()]).to_json();
+        assert!(json.contains("\"subject\":\"job 'nightly'\""), "{json}");
+        assert!(json.contains("\"status\":\"failed\""), "{json}");
+        assert!(json.contains("\"errors\":[\"/srv/x: denied\"]"), "{json}");
+        assert!(json.contains(
+            "\"path\":\"/srv/old.db\",\"filesystem\":null,\"passes\":1,\"bytes_written\":4096"
+        ));
+        assert!(json.ends_with(",\"summary\":null}"), "{json}");
+    }
+
+    /// a minimal relay that accepts one message and hands back the transcript
+    #[test]
+    fn test_send_mail() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let replies = [
+                "220 relay\r\n",
+                "250-relay\r\n250 OK\r\n",
+                "250 OK\r\n",
+                "251 User not local; will forward\r\n",
+                "354 Go ahead\r\n",
+                "250 Queued\r\n",
+                "221 Bye\r\n",
+            ];
+            let mut transcript = String::new();
+            let mut buffer = [0u8; 4096];
+            stream.write_all(replies[0].as_bytes()).unwrap();
+            for reply in &replies[1..] {
+                // each command (or the whole message) is answered once it is complete
+                loop {
+                    let read = stream.read(&mut buffer).unwrap();
+                    transcript.push_str(&String::from_utf8_lossy(&buffer[..read]));
+                    let done = if reply.starts_with("250 Queued") {
+                        transcript.ends_with("\r\n.\r\n")
+                    } else {
+                        transcript.ends_with("\r\n")
+                    };
+                    if done {
+                        break;
+                    }
+                }
+                stream.write_all(reply.as_bytes()).unwrap();
+            }
+            transcript
+        });
+
+        let config = EmailConfig {
+            smtp: addr.to_string(),
+            from: "shredder@example.com".into(),
+            to: vec!["ops@example.com".into()],
+            starttls: StartTls::Opportunistic,
+            username: None,
+            password: None,
+        };
+        let (subject, body) = notification(Vec::new()).to_mail();
+        // a line break in the subject can't add headers
+        let subject = format!("{}\r\nBcc: else@example.com", subject);
+        send_mail(&config, &subject, &format!("{}.hidden\n", body)).unwrap();
+
+        let transcript = server.join().unwrap();
+        assert!(transcript.contains("RCPT TO:<ops@example.com>\r\n"));
+        assert!(transcript.contains("Subject: shredder: job 'nightly' succeeded on "));
+        assert!(!transcript.contains("\r\nBcc:"), "{transcript}");
+        assert!(transcript.contains("path = \"/srv/old.db\""));
+        assert!(transcript.contains("\r\n..hidden\r\n"), "{transcript}");
+        assert!(transcript.ends_with("QUIT\r\n"));
+    }
+
+    #[test]
+    fn test_send_mail_credentials_need_starttls() {
+        let config = EmailConfig {
+            smtp: "127.0.0.1:9".into(),
+            from: "shredder@example.com".into(),
+            to: vec!["ops@example.com".into()],
+            starttls: StartTls::Never,
+            username: Some("shredder".into()),
+            password: Some("secret".into()),
+        };
+        let error = send_mail(&config, "subject", "body").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::filesystem::MountInfo;
+use crate::report::WipeReport;
+use crate::{Result, WipeError};
+use log::{debug, warn};
+use std::fs::OpenOptions;
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::fs::MetadataExt;
+use std::os::windows::io::AsRawHandle;
+use std::path::Path;
+use std::ptr;
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::winerror::{ERROR_HANDLE_EOF, ERROR_MORE_DATA};
+use winapi::um::fileapi::{FindClose, FindFirstFileW};
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::ioapiset::DeviceIoControl;
+use winapi::um::minwinbase::WIN32_FIND_DATAW;
+use winapi::um::winioctl::{
+    FSCTL_GET_RETRIEVAL_POINTERS, FSCTL_SET_COMPRESSION, RETRIEVAL_POINTERS_BUFFER,
+    STARTING_VCN_INPUT_BUFFER,
+};
+use winapi::um::winnt::{
+    COMPRESSION_FORMAT_NONE, FILE_ATTRIBUTE_COMPRESSED, FILE_ATTRIBUTE_ENCRYPTED,
+    FILE_ATTRIBUTE_REPARSE_POINT, IO_REPARSE_TAG_DEDUP,
+};
+
+/// handles EFS-encrypted targets
+///
+/// writes to an EFS file go through the encryption layer, so the passes land
+/// on disk as ciphertext and only a user holding the file's key can open it.
+/// the $EFS metadata (wrapped file keys) and any plaintext left behind when
+/// the file was converted to encrypted form are outside the file's clusters
+pub(crate) fn check_encryption(
+    path: &Path,
+    mount: &MountInfo,
+    report: &mut WipeReport,
+) -> Result<()> {
+    let attributes = std::fs::metadata(path)?.file_attributes();
+    if attributes & FILE_ATTRIBUTE_ENCRYPTED == 0 {
+        return Ok(());
+    }
+
+    debug!("Target is EFS-encrypted");
+    if let Err(e) = OpenOptions::new().read(true).write(true).open(path) {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            return Err(WipeError::UnsupportedOperation(format!(
+                "{} is EFS-encrypted and can only be wiped by a user holding its key",
+                path.display()
+            )));
+        }
+        return Err(e.into());
+    }
+
+    report.add_limitation(format!(
+        "File was EFS-encrypted: its wrapped keys in the $EFS attribute are freed with the MFT \
+         record, and a plaintext copy may remain in free space if the file was encrypted after \
+         it was written. Run `cipher /w:{}` to wipe the volume's free space.",
+        mount.mount_point.display()
+    ));
+    Ok(())
+}
+
+/// rewrites the target so that pattern writes map 1:1 onto its clusters
+///
+/// compressed files are decompressed and deduplicated files are rehydrated;
+/// the clusters that held the old representation are released rather than
+/// overwritten, which is recorded in the report
+pub(crate) fn prepare_target(path: &Path, report: &mut WipeReport) -> Result<()> {
+    let attributes = std::fs::metadata(path)?.file_attributes();
+
+    if attributes & FILE_ATTRIBUTE_REPARSE_POINT != 0 && reparse_tag(path)? == IO_REPARSE_TAG_DEDUP
+    {
+        debug!("Target is deduplicated, rehydrating before overwrite");
+        rehydrate(path)?;
+        report.add_limitation(
+            "File was deduplicated: its original chunks live in the volume's shared chunk store \
+             and may be referenced by other files. Run a dedup garbage collection job and wipe \
+             the volume's free space to remove them.",
+        );
+    }
+
+    // re-read: rehydration produces an ordinary file that may still be compressed
+    let attributes = std::fs::metadata(path)?.file_attributes();
+    if attributes & FILE_ATTRIBUTE_COMPRESSED != 0 {
+        debug!("Target is NTFS-compressed, decompressing before overwrite");
+        decompress(path)?;
+        report.add_limitation(
+            "File was NTFS-compressed: it was decompressed onto new clusters before wiping, \
+             so the clusters holding the compressed data were released, not overwritten. \
+             Wipe the volume's free space to remove them.",
+        );
+    }
+
+    if is_mft_resident(path)? {
+        debug!("Target data is resident in its MFT record, growing it non-resident");
+        grow_non_resident(path)?;
+        report.add_limitation(
+            "File was small enough to be stored inside its MFT record. It was moved out to \
+             regular clusters before wiping, but the MFT record slack may still hold the \
+             original bytes until the record is reused.",
+        );
+    }
+
+    Ok(())
+}
+
+/// size a file is grown to so NTFS must move its data out of the MFT record
+/// (MFT records are 1KB or 4KB, so a full 4KB cluster never fits)
+const NON_RESIDENT_SIZE: u64 = 4096;
+
+/// checks whether the file's data lives inside its MFT record
+///
+/// resident files have no cluster allocation, so asking for their
+/// retrieval pointers fails with ERROR_HANDLE_EOF
+fn is_mft_resident(path: &Path) -> Result<bool> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        // nothing to leak
+        return Ok(false);
+    }
+
+    let mut input: STARTING_VCN_INPUT_BUFFER = unsafe { std::mem::zeroed() };
+    let mut output: RETRIEVAL_POINTERS_BUFFER = unsafe { std::mem::zeroed() };
+    let mut bytes_returned: DWORD = 0;
+
+    let success = unsafe {
+        DeviceIoControl(
+            file.as_raw_handle() as *mut _,
+            FSCTL_GET_RETRIEVAL_POINTERS,
+            &mut input as *mut _ as *mut _,
+            std::mem::size_of::<STARTING_VCN_INPUT_BUFFER>() as DWORD,
+            &mut output as *mut _ as *mut _,
+            std::mem::size_of::<RETRIEVAL_POINTERS_BUFFER>() as DWORD,
+            &mut bytes_returned,
+            ptr::null_mut(),
+        )
+    };
+
+    if success != 0 {
+        return Ok(false);
+    }
+
+    let err = std::io::Error::last_os_error();
+    if err.raw_os_error() == Some(ERROR_HANDLE_EOF as i32) {
+        Ok(true)
+    } else if err.raw_os_error() == Some(ERROR_MORE_DATA as i32) {
+        // more extents than fit in the buffer, so certainly non-resident
+        Ok(false)
+    } else {
+        Err(err.into())
+    }
+}
+
+/// grows a resident file beyond the MFT record size, forcing NTFS to
+/// allocate clusters; the pattern passes then cover the whole new length
+fn grow_non_resident(path: &Path) -> Result<()> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let size = file.metadata()?.len();
+    if size >= NON_RESIDENT_SIZE {
+        return Ok(());
+    }
+
+    // write real bytes: a bare set_len() may leave the data resident
+    file.seek(SeekFrom::Start(size))?;
+    file.write_all(&vec![0u8; (NON_RESIDENT_SIZE - size) as usize])?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// reads the reparse tag of a file through its directory entry
+fn reparse_tag(path: &Path) -> Result<DWORD> {
+    let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut find_data: WIN32_FIND_DATAW = unsafe { std::mem::zeroed() };
+
+    let handle = unsafe { FindFirstFileW(wide_path.as_ptr(), &mut find_data) };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    unsafe { FindClose(handle) };
+
+    // dwReserved0 holds the tag when FILE_ATTRIBUTE_REPARSE_POINT is set
+    Ok(find_data.dwReserved0)
+}
+
+/// turns off NTFS compression, which rewrites the data uncompressed
+fn decompress(path: &Path) -> Result<()> {
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut format = COMPRESSION_FORMAT_NONE;
+    let mut bytes_returned: DWORD = 0;
+
+    let success = unsafe {
+        DeviceIoControl(
+            file.as_raw_handle() as *mut _,
+            FSCTL_SET_COMPRESSION,
+            &mut format as *mut _ as *mut _,
+            std::mem::size_of_val(&format) as DWORD,
+            ptr::null_mut(),
+            0,
+            &mut bytes_returned,
+            ptr::null_mut(),
+        )
+    };
+
+    if success == 0 {
+        Err(std::io::Error::last_os_error().into())
+    } else {
+        Ok(())
+    }
+}
+
+/// replaces a deduplicated file with a private, fully materialized copy
+fn rehydrate(path: &Path) -> Result<()> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid path"))?;
+    let temp_path = parent.join(format!("{}.rehydrate", file_name.to_string_lossy()));
+
+    let copied = std::fs::copy(path, &temp_path)
+        .and_then(|_| OpenOptions::new().write(true).open(&temp_path)?.sync_all());
+    if let Err(e) = copied {
+        warn!("Unable to rehydrate deduplicated file: {}", e);
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
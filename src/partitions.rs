@@ -0,0 +1,586 @@
+//! reading MBR and GPT partition tables straight from a device, to find where
+//! partitions begin and end without asking the OS, which may not have the disk
+//! attached to any driver that knows its partitions, and destroying them
+//!
+//! destroying a table means the MBR, every extended boot record, the primary
+//! GPT and the backup GPT in the last sectors of the disk, which zeroing the
+//! first megabyte misses: partitioning tools and firmware restore the primary
+//! from it
+
+use crate::signatures::read;
+use crate::{write_all_at, Result, WipeError};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// kind of partition table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    /// a master boot record with up to four primary partitions, and logical
+    /// partitions chained from an extended one
+    Mbr,
+    /// a GUID partition table, behind a protective MBR, with a backup at the end
+    Gpt,
+}
+
+/// a partition, in bytes from the start of the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Partition {
+    /// number as the OS names it, e.g. 3 for /dev/sda3; logical MBR partitions start at 5
+    pub number: u32,
+    /// first byte
+    pub start: u64,
+    /// size in bytes
+    pub length: u64,
+}
+
+impl Partition {
+    /// byte after the last one
+    pub fn end(&self) -> u64 {
+        self.start + self.length
+    }
+}
+
+/// a disk's partition table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionTable {
+    pub scheme: Scheme,
+    /// logical sector size the table counts in
+    pub sector_size: u64,
+    pub partitions: Vec<Partition>,
+}
+
+/// GPT header signature
+pub(crate) const GPT_SIGNATURE: &[u8] = b"EFI PART";
+
+/// reads the partition table of a disk
+///
+/// # Arguments
+/// * `path` - Whole disk or disk image
+///
+/// # Returns
+/// * `Result<Option<PartitionTable>>` - The table, or `None` if the disk has none
+pub fn read_table<P: AsRef<Path>>(path: P) -> Result<Option<PartitionTable>> {
+    let mut file = File::open(path.as_ref())?;
+    let size = crate::target_size(&mut file)?;
+    Ok(parse(&file, size)?)
+}
+
+pub(crate) fn parse(file: &File, size: u64) -> io::Result<Option<PartitionTable>> {
+    let Some(mbr) = read(file, size, 0, 512)? else {
+        return Ok(None);
+    };
+    if mbr[510..512] != [0x55, 0xaa] {
+        return Ok(None);
+    }
+    let entries: Vec<&[u8]> = mbr[446..510].chunks_exact(16).collect();
+    if entries.iter().any(|entry| entry[4] == 0xee) {
+        // the header is in LBA 1, whose size the protective MBR doesn't say
+        for sector_size in [512, 4096] {
+            if let Some(table) = parse_gpt(file, size, sector_size)? {
+                return Ok(Some(table));
+            }
+        }
+        return Ok(None);
+    }
+    parse_mbr(file, size, &entries).map(Some)
+}
+
+fn le32(bytes: &[u8], at: usize) -> u64 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as u64
+}
+
+fn le64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// MBRs count in 512-byte sectors, even on 4Kn disks that have one
+fn parse_mbr(file: &File, size: u64, entries: &[&[u8]]) -> io::Result<PartitionTable> {
+    const SECTOR: u64 = 512;
+    let mut partitions = Vec::new();
+    let mut extended = None;
+    for (i, entry) in entries.iter().enumerate() {
+        let (kind, start, count) = (entry[4], le32(entry, 8), le32(entry, 12));
+        if kind == 0 || count == 0 {
+            continue;
+        }
+        if matches!(kind, 0x05 | 0x0f | 0x85) {
+            extended = Some(start);
+        }
+        partitions.push(Partition {
+            number: i as u32 + 1,
+            start: start * SECTOR,
+            length: count * SECTOR,
+        });
+    }
+
+    if let Some(base) = extended {
+        for (number, (at, ebr)) in (5..).zip(extended_boot_records(file, size, base)?) {
+            let logical = &ebr[446..462];
+            if logical[4] != 0 && le32(logical, 12) != 0 {
+                partitions.push(Partition {
+                    number,
+                    start: at + le32(logical, 8) * SECTOR,
+                    length: le32(logical, 12) * SECTOR,
+                });
+            }
+        }
+    }
+
+    Ok(PartitionTable {
+        scheme: Scheme::Mbr,
+        sector_size: SECTOR,
+        partitions,
+    })
+}
+
+/// the chain of extended boot records of an extended partition starting at
+/// sector `base`, with their offsets: each describes one logical partition
+/// and points to the next, relative to `base`
+fn extended_boot_records(file: &File, size: u64, base: u64) -> io::Result<Vec<(u64, Vec<u8>)>> {
+    const SECTOR: u64 = 512;
+    let mut records = Vec::new();
+    let mut next = 0;
+    // a loop in the chain must not hang us
+    while records.len() < 128 {
+        let at = (base + next) * SECTOR;
+        let Some(ebr) = read(file, size, at, 512)? else {
+            break;
+        };
+        if ebr[510..512] != [0x55, 0xaa] {
+            break;
+        }
+        let link = &ebr[462..478];
+        let done = link[4] == 0 || le32(link, 8) == 0;
+        next = le32(link, 8);
+        records.push((at, ebr));
+        if done {
+            break;
+        }
+    }
+    Ok(records)
+}
+
+fn parse_gpt(file: &File, size: u64, sector_size: u64) -> io::Result<Option<PartitionTable>> {
+    let Some(header) = read(file, size, sector_size, 92)? else {
+        return Ok(None);
+    };
+    if &header[..8] != GPT_SIGNATURE {
+        return Ok(None);
+    }
+    let entries_lba = le64(&header, 72);
+    let count = le32(&header, 80).min(1024);
+    let entry_size = le32(&header, 84);
+    if entry_size < 128 {
+        return Ok(None);
+    }
+    let Some(entries) = read(
+        file,
+        size,
+        entries_lba * sector_size,
+        (count * entry_size) as usize,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let partitions = entries
+        .chunks_exact(entry_size as usize)
+        .enumerate()
+        // unused entries have a zero type GUID
+        .filter(|(_, entry)| entry[..16].iter().any(|&b| b != 0))
+        .map(|(i, entry)| {
+            let (first, last) = (le64(entry, 32), le64(entry, 40));
+            Partition {
+                number: i as u32 + 1,
+                start: first * sector_size,
+                length: (last + 1).saturating_sub(first) * sector_size,
+            }
+        })
+        .collect();
+    Ok(Some(PartitionTable {
+        scheme: Scheme::Gpt,
+        sector_size,
+        partitions,
+    }))
+}
+
+/// partition `number` of `table`, if a partition device starting at byte
+/// `start` (where the OS says) and `length` bytes long lies within it
+///
+/// # Returns
+/// * `Result<Partition, String>` - The partition, or why the device doesn't fit it
+pub(crate) fn check_bounds(
+    table: &PartitionTable,
+    number: u32,
+    start: Option<u64>,
+    length: u64,
+) -> std::result::Result<Partition, String> {
+    let partition = table
+        .partitions
+        .iter()
+        .find(|partition| partition.number == number)
+        .copied()
+        .ok_or_else(|| format!("the partition table has no partition {}", number))?;
+    if let Some(start) = start.filter(|&start| start != partition.start) {
+        return Err(format!(
+            "it starts at byte {}, partition {} of the table at byte {}",
+            start, number, partition.start
+        ));
+    }
+    if length > partition.length {
+        return Err(format!(
+            "it is {} bytes long, partition {} of the table only {}",
+            length, number, partition.length
+        ));
+    }
+    Ok(partition)
+}
+
+/// zeroes the MBR, the extended boot records, and the primary and backup
+/// GPT headers with their partition entries, then reads the disk again to
+/// make sure no GPT header or MBR boot signature is left
+///
+/// # Arguments
+/// * `path` - Whole disk or disk image, not in use
+///
+/// # Returns
+/// * `Result<Vec<(u64, u64)>>` - Byte ranges zeroed, as (start, end) pairs
+pub fn destroy<P: AsRef<Path>>(path: P) -> Result<Vec<(u64, u64)>> {
+    let path = path.as_ref();
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)?;
+    let size = crate::target_size(&mut file)?;
+    let ranges = table_ranges(&file, size)?;
+    for &(start, end) in &ranges {
+        write_all_at(&file, &vec![0u8; (end - start) as usize], start)?;
+    }
+    file.sync_all()?;
+
+    let left = leftovers(&file, size)?;
+    if !left.is_empty() {
+        return Err(WipeError::VerificationFailed(format!(
+            "{} still holds {} after destroying its partition table",
+            path.display(),
+            left.join(", ")
+        )));
+    }
+    reread(&file);
+    Ok(ranges)
+}
+
+/// where the tables are; the first sectors always, the end of the disk only
+/// where a backup GPT header was found
+fn table_ranges(file: &File, size: u64) -> io::Result<Vec<(u64, u64)>> {
+    // the MBR, the primary GPT header and the 128 entries that follow it:
+    // 6 sectors of 4096 bytes, which covers the 34 of 512
+    let mut ranges = vec![(0, (6 * 4096).min(size))];
+
+    if let Some(mbr) = read(file, size, 0, 512)? {
+        let extended = mbr[446..510]
+            .chunks_exact(16)
+            .find(|entry| matches!(entry[4], 0x05 | 0x0f | 0x85))
+            .map(|entry| le32(entry, 8));
+        if let Some(base) = extended.filter(|_| mbr[510..512] == [0x55, 0xaa]) {
+            for (at, _) in extended_boot_records(file, size, base)? {
+                ranges.push((at, at + 512));
+            }
+        }
+    }
+
+    for sector_size in [512u64, 4096] {
+        if let Some(header) = read(file, size, sector_size, 92)? {
+            if &header[..8] == GPT_SIGNATURE {
+                // entry arrays bigger than the usual 128 entries
+                let entries = le64(&header, 72) * sector_size;
+                let length = le32(&header, 80).min(1024) * le32(&header, 84);
+                ranges.push((entries, (entries + length).min(size)));
+                // the backup header, wherever the primary says it is
+                let backup = le64(&header, 32) * sector_size;
+                if backup + sector_size <= size {
+                    ranges.extend(backup_range(file, size, backup, sector_size)?);
+                }
+            }
+        }
+        // the backup header normally sits in the last sector
+        if let Some(last) = size.checked_sub(sector_size) {
+            ranges.extend(backup_range(file, size, last, sector_size)?);
+        }
+    }
+
+    Ok(merge(ranges))
+}
+
+/// the backup GPT header at `at` and the entries before it, if there is one
+fn backup_range(
+    file: &File,
+    size: u64,
+    at: u64,
+    sector_size: u64,
+) -> io::Result<Option<(u64, u64)>> {
+    let Some(header) = read(file, size, at, 92)? else {
+        return Ok(None);
+    };
+    if &header[..8] != GPT_SIGNATURE {
+        return Ok(None);
+    }
+    let entries = (le64(&header, 72) * sector_size).min(at);
+    // 32 sectors of entries when the header's own pointer makes no sense
+    let start = if at - entries <= 1024 * 1024 {
+        entries
+    } else {
+        at.saturating_sub(32 * sector_size)
+    };
+    Ok(Some((start, at + sector_size)))
+}
+
+/// GPT headers and MBR boot signatures still on the disk
+fn leftovers(file: &File, size: u64) -> io::Result<Vec<String>> {
+    let mut left = Vec::new();
+    if read(file, size, 510, 2)?.is_some_and(|signature| signature == [0x55, 0xaa]) {
+        left.push("an MBR boot signature".to_string());
+    }
+    for sector_size in [512u64, 4096] {
+        let mut places = vec![sector_size];
+        places.extend(size.checked_sub(sector_size));
+        for at in places {
+            if read(file, size, at, 8)?.is_some_and(|magic| magic == GPT_SIGNATURE) {
+                left.push(format!("a GPT header at offset {}", at));
+            }
+        }
+    }
+    Ok(left)
+}
+
+/// has the kernel drop the partitions it knows of; fails harmlessly for
+/// images, and while a partition is in use
+#[cfg(target_os = "linux")]
+fn reread(file: &File) {
+    use std::os::unix::io::AsRawFd;
+
+    const BLKRRPART: libc::c_ulong = 0x125f;
+    if file.metadata().is_ok_and(|m| !m.is_file())
+        && unsafe { libc::ioctl(file.as_raw_fd(), BLKRRPART as _) } != 0
+    {
+        log::debug!(
+            "Unable to have the kernel re-read the partition table: {}",
+            io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reread(_file: &File) {}
+
+/// the first and last `region` bytes of a disk and of each of its partitions,
+/// as sorted, disjoint (start, end) pairs; what a quick wipe overwrites
+pub(crate) fn edges(size: u64, region: u64, table: Option<&PartitionTable>) -> Vec<(u64, u64)> {
+    let mut spans = vec![(0, size)];
+    if let Some(table) = table {
+        spans.extend(
+            table
+                .partitions
+                .iter()
+                .map(|partition| (partition.start, partition.end().min(size))),
+        );
+    }
+    let regions: Vec<(u64, u64)> = spans
+        .into_iter()
+        .filter(|(start, end)| start < end)
+        .flat_map(|(start, end)| {
+            let head = (start, (start + region).min(end));
+            let tail = (end.saturating_sub(region).max(start), end);
+            [head, tail]
+        })
+        .collect();
+    merge(regions)
+}
+
+/// sorts byte ranges and joins the ones that overlap or touch
+fn merge(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// a disk image with a protective MBR and a GPT of 128 entries holding
+    /// `partitions` as (first, last) LBAs of 512 bytes
+    pub(crate) fn gpt_image(sectors: u64, partitions: &[(u64, u64)]) -> Vec<u8> {
+        let mut image = vec![0u8; (sectors * 512) as usize];
+        image[446 + 4] = 0xee;
+        image[446 + 8..446 + 12].copy_from_slice(&1u32.to_le_bytes());
+        image[446 + 12..446 + 16].copy_from_slice(&((sectors - 1) as u32).to_le_bytes());
+        image[510..512].copy_from_slice(&[0x55, 0xaa]);
+
+        let mut write_header = |lba: u64, entries: u64| {
+            let at = (lba * 512) as usize;
+            image[at..at + 8].copy_from_slice(GPT_SIGNATURE);
+            image[at + 72..at + 80].copy_from_slice(&entries.to_le_bytes());
+            image[at + 80..at + 84].copy_from_slice(&128u32.to_le_bytes());
+            image[at + 84..at + 88].copy_from_slice(&128u32.to_le_bytes());
+            for (i, (first, last)) in partitions.iter().enumerate() {
+                let entry = (entries * 512) as usize + i * 128;
+                image[entry] = 0xaf;
+                image[entry + 32..entry + 40].copy_from_slice(&first.to_le_bytes());
+                image[entry + 40..entry + 48].copy_from_slice(&last.to_le_bytes());
+            }
+        };
+        write_header(1, 2);
+        write_header(sectors - 1, sectors - 33);
+        image
+    }
+
+    fn table(image: &[u8]) -> Option<PartitionTable> {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(image).unwrap();
+        read_table(file.path()).unwrap()
+    }
+
+    #[test]
+    fn test_gpt() {
+        let table = table(&gpt_image(4096, &[(2048, 3071), (3072, 4062)])).unwrap();
+        assert_eq!(table.scheme, Scheme::Gpt);
+        assert_eq!(
+            table.partitions,
+            [
+                Partition {
+                    number: 1,
+                    start: 2048 * 512,
+                    length: 1024 * 512
+                },
+                Partition {
+                    number: 2,
+                    start: 3072 * 512,
+                    length: 991 * 512
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mbr_with_logical_partitions() {
+        let mut image = vec![0u8; 8192 * 512];
+        let mut entry = |at: usize, kind: u8, start: u32, count: u32| {
+            image[at + 4] = kind;
+            image[at + 8..at + 12].copy_from_slice(&start.to_le_bytes());
+            image[at + 12..at + 16].copy_from_slice(&count.to_le_bytes());
+        };
+        entry(446, 0x83, 2048, 2048);
+        entry(462, 0x05, 4096, 4096);
+        // first EBR at 4096: a logical partition and a link to the next EBR at 4096 + 2048
+        entry(4096 * 512 + 446, 0x83, 63, 1000);
+        entry(4096 * 512 + 462, 0x05, 2048, 2048);
+        entry(6144 * 512 + 446, 0x07, 63, 2000);
+        for sector in [0, 4096, 6144] {
+            image[sector * 512 + 510..sector * 512 + 512].copy_from_slice(&[0x55, 0xaa]);
+        }
+
+        let table = table(&image).unwrap();
+        assert_eq!(table.scheme, Scheme::Mbr);
+        let found: Vec<(u32, u64, u64)> = table
+            .partitions
+            .iter()
+            .map(|p| (p.number, p.start / 512, p.length / 512))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, 2048, 2048),
+                (2, 4096, 4096),
+                (5, 4096 + 63, 1000),
+                (6, 6144 + 63, 2000)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edges() {
+        const MIB: u64 = 1024 * 1024;
+        let table = PartitionTable {
+            scheme: Scheme::Gpt,
+            sector_size: 512,
+            partitions: vec![
+                Partition {
+                    number: 1,
+                    start: MIB,
+                    length: 100 * MIB,
+                },
+                // smaller than both ends together
+                Partition {
+                    number: 2,
+                    start: 200 * MIB,
+                    length: 3 * MIB,
+                },
+            ],
+        };
+        assert_eq!(
+            edges(1024 * MIB, 2 * MIB, Some(&table)),
+            [
+                (0, 3 * MIB),
+                (99 * MIB, 101 * MIB),
+                (200 * MIB, 203 * MIB),
+                (1022 * MIB, 1024 * MIB),
+            ]
+        );
+        assert_eq!(edges(MIB, 2 * MIB, None), [(0, MIB)]);
+    }
+
+    #[test]
+    fn test_check_bounds() {
+        let table = PartitionTable {
+            scheme: Scheme::Gpt,
+            sector_size: 512,
+            partitions: vec![Partition {
+                number: 3,
+                start: 1 << 20,
+                length: 1 << 30,
+            }],
+        };
+        assert_eq!(
+            check_bounds(&table, 3, Some(1 << 20), 1 << 30),
+            Ok(table.partitions[0])
+        );
+        assert!(check_bounds(&table, 3, None, 1 << 30).is_ok());
+        // stale kernel view of a table that has changed since
+        assert!(check_bounds(&table, 3, Some(2 << 20), 1 << 30).is_err());
+        assert!(check_bounds(&table, 3, Some(1 << 20), (1 << 30) + 512).is_err());
+        assert!(check_bounds(&table, 4, None, 512).is_err());
+    }
+
+    #[test]
+    fn test_destroy() {
+        // the backup GPT sits where zeroing the first megabytes doesn't reach
+        let mut image = gpt_image(16384, &[(2048, 16350)]);
+        image[2048 * 512..2049 * 512].fill(0x5a);
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&image).unwrap();
+
+        let ranges = destroy(file.path()).unwrap();
+        assert_eq!(ranges, [(0, 24576), ((16384 - 33) * 512, 16384 * 512)]);
+        assert_eq!(read_table(file.path()).unwrap(), None);
+        let destroyed = std::fs::read(file.path()).unwrap();
+        assert!(destroyed[..24576].iter().all(|&b| b == 0));
+        assert!(destroyed[16351 * 512..].iter().all(|&b| b == 0));
+        // the partition's data is untouched
+        assert!(destroyed[2048 * 512..2049 * 512].iter().all(|&b| b == 0x5a));
+    }
+
+    #[test]
+    fn test_no_table() {
+        assert_eq!(table(&vec![0u8; 4096]), None);
+    }
+}
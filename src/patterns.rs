@@ -1,7 +1,13 @@
-use rand::RngCore;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
 
 /// represents different patterns used for secure data wiping
-#[derive(Debug, Clone)]
+///
+/// in configuration files: "zeros", "ones", "random" or `{ custom = [0x92, 0x49, 0x24] }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum WipePattern {
     /// fill with zeros (0x00)
     /// used in various standards as part of multi-pass overwriting
@@ -93,6 +99,100 @@ impl WipePattern {
     }
 }
 
+/// random data that can be regenerated for any offset, written by random passes
+///
+/// every offset of the target gets different bytes, so storage that compresses
+/// or deduplicates can't store the pass in less space than the target; a
+/// repeated random buffer would let it. verification regenerates the bytes from
+/// the seed. the generator is ChaCha12, the one behind rand's StdRng
+pub(crate) struct Keystream {
+    seed: [u8; 32],
+}
+
+impl Keystream {
+    /// a keystream with a fresh random seed
+    pub(crate) fn new() -> Self {
+        let mut seed = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut seed);
+        Self { seed }
+    }
+
+    /// the keystream of an earlier pass, to continue it where it stopped
+    pub(crate) fn from_seed(seed: [u8; 32]) -> Self {
+        Self { seed }
+    }
+
+    /// the seed the stream is generated from
+    pub(crate) fn seed(&self) -> [u8; 32] {
+        self.seed
+    }
+
+    /// fills `buffer` with the bytes at `offset` of the stream
+    pub(crate) fn fill(&self, offset: u64, buffer: &mut [u8]) {
+        let mut rng = ChaCha12Rng::from_seed(self.seed);
+        // the generator seeks in 4-byte words
+        rng.set_word_pos((offset / 4) as u128);
+        let skip = (offset % 4) as usize;
+        let mut rest = buffer;
+        if skip > 0 {
+            let word = rng.next_u32().to_le_bytes();
+            let n = (4 - skip).min(rest.len());
+            rest[..n].copy_from_slice(&word[skip..skip + n]);
+            rest = &mut rest[n..];
+        }
+        rng.fill_bytes(rest);
+    }
+}
+
+/// where random passes get the seeds of their keystreams
+///
+/// every seed comes from the operating system's generator unless the shredder
+/// was given a seed; then they are drawn from a ChaCha12 generator seeded with
+/// it, and random passes write the same bytes on every run. that is for tests
+/// and simulations, never for wiping data someone could reproduce
+pub(crate) enum SeedSource {
+    System,
+    Seeded(Box<Mutex<ChaCha12Rng>>),
+}
+
+impl SeedSource {
+    pub(crate) fn seeded(seed: u64) -> Self {
+        SeedSource::Seeded(Box::new(Mutex::new(ChaCha12Rng::seed_from_u64(seed))))
+    }
+
+    /// the keystream for the next random pass
+    pub(crate) fn keystream(&self) -> Keystream {
+        match self {
+            SeedSource::System => Keystream::new(),
+            SeedSource::Seeded(rng) => {
+                let mut seed = [0u8; 32];
+                rng.lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .fill_bytes(&mut seed);
+                Keystream { seed }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for WipePattern {
+    /// describes the pass, e.g. "zeros (0x00)" or "pattern 0x92 0x49 0x24"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WipePattern::Zeros => write!(f, "zeros (0x00)"),
+            WipePattern::Ones => write!(f, "ones (0xFF)"),
+            WipePattern::Random => write!(f, "random data"),
+            WipePattern::Custom(pattern) => {
+                write!(f, "pattern")?;
+                for byte in pattern {
+                    write!(f, " 0x{:02X}", byte)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,4 +213,43 @@ mod tests {
         WipePattern::Custom(pattern).fill_buffer(&mut buffer);
         assert_eq!(buffer, vec![0x55, 0xAA, 0x55, 0xAA]); // verify pattern repeats
     }
+
+    /// test that keystream data can be regenerated anywhere and doesn't repeat
+    #[test]
+    fn test_keystream_is_seekable_and_unique() {
+        let keystream = Keystream::new();
+        let mut whole = vec![0u8; 8192];
+        keystream.fill(0, &mut whole);
+
+        let mut part = vec![0u8; 4096];
+        keystream.fill(4096, &mut part);
+        assert_eq!(part, whole[4096..]);
+        let mut unaligned = vec![0u8; 10];
+        keystream.fill(3, &mut unaligned);
+        assert_eq!(unaligned, whole[3..13]);
+
+        // no block repeats another
+        assert_ne!(whole[..4096], whole[4096..]);
+        assert_ne!(whole, vec![0u8; 8192]);
+    }
+
+    /// test that seeded sources repeat their keystreams and the system one doesn't
+    #[test]
+    fn test_seeded_keystreams() {
+        let fill = |keystream: Keystream| {
+            let mut buffer = vec![0u8; 64];
+            keystream.fill(0, &mut buffer);
+            buffer
+        };
+        let (a, b) = (SeedSource::seeded(7), SeedSource::seeded(7));
+        let first = fill(a.keystream());
+        assert_eq!(first, fill(b.keystream()));
+        // each pass gets its own stream
+        assert_ne!(fill(a.keystream()), first);
+        assert_ne!(fill(SeedSource::seeded(8).keystream()), first);
+        assert_ne!(
+            fill(SeedSource::System.keystream()),
+            fill(SeedSource::System.keystream())
+        );
+    }
 }
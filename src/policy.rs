@@ -0,0 +1,258 @@
+//! system-wide policy set by an administrator: the weakest standard and
+//! verification wipes may use, paths that are never wiped and whether reports
+//! are required
+//!
+//! the policy lives outside the user's configuration, in
+//! /etc/shredder/policy.toml (%ProgramData%\shredder\policy.toml on windows),
+//! and every shredder checks it before wiping; a policy passed by a program
+//! can only make it stricter
+
+use crate::protection::ProtectedPaths;
+use crate::report::NistAction;
+use crate::standards::{SanitizationMethod, VerificationLevel, WipeStandard};
+use crate::{Result, WipeError};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// what an administrator requires of every wipe
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// weakest NIST SP 800-88 action a standard may achieve: with "purge",
+    /// NIST Clear and the legacy and custom standards are refused
+    pub minimum_action: Option<NistAction>,
+
+    /// weakest verification allowed; "none" < "sampled" and "basic" < "full"
+    /// < "enhanced" and "hash"
+    pub minimum_verification: Option<VerificationLevel>,
+
+    /// paths that must never be wiped, anything inside them included
+    pub banned_paths: Vec<PathBuf>,
+
+    /// wipes started from the command line must write a report (--report),
+    /// and scheduled jobs must have a report file
+    pub require_report: bool,
+
+    /// record every wipe in the audit log, whatever the user's settings
+    pub audit_log: bool,
+
+    /// why the policy file couldn't be read; a policy that can't be read
+    /// refuses every wipe rather than allowing all of them
+    #[serde(skip)]
+    unreadable: Option<String>,
+}
+
+impl Policy {
+    /// reads and parses a policy file
+    ///
+    /// # Arguments
+    /// * `path` - Path to the policy file
+    ///
+    /// # Returns
+    /// * `Result<Policy>` - Parsed policy
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents)
+            .map_err(|e| WipeError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// the system policy: no requirements if the file doesn't exist, and a
+    /// policy refusing every wipe if it exists but can't be read
+    ///
+    /// the file is read once per process, so every shredder a program creates
+    /// doesn't go back to the disk for it
+    pub fn system() -> Self {
+        static SYSTEM: OnceLock<Policy> = OnceLock::new();
+        SYSTEM.get_or_init(Self::read_system).clone()
+    }
+
+    fn read_system() -> Self {
+        let path = Self::system_path();
+        if !path.exists() {
+            return Self::default();
+        }
+        Self::load(&path).unwrap_or_else(|e| {
+            log::error!("Unable to read the policy {}: {}", path.display(), e);
+            Self {
+                unreadable: Some(e.to_string()),
+                ..Self::default()
+            }
+        })
+    }
+
+    /// location of the system-wide policy file
+    pub fn system_path() -> PathBuf {
+        #[cfg(windows)]
+        let base = std::env::var_os("ProgramData")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"));
+        #[cfg(not(windows))]
+        let base = PathBuf::from("/etc");
+
+        base.join("shredder").join("policy.toml")
+    }
+
+    /// the stricter of two policies, requirement by requirement
+    pub fn merge(self, other: Policy) -> Self {
+        let minimum_verification = match (self.minimum_verification, other.minimum_verification) {
+            (Some(a), Some(b)) if rank(b) > rank(a) => Some(b),
+            (a, b) => a.or(b),
+        };
+        let mut banned_paths = self.banned_paths;
+        banned_paths.extend(other.banned_paths);
+        Self {
+            minimum_action: self.minimum_action.max(other.minimum_action),
+            minimum_verification,
+            banned_paths,
+            require_report: self.require_report || other.require_report,
+            audit_log: self.audit_log || other.audit_log,
+            unreadable: self.unreadable.or(other.unreadable),
+        }
+    }
+
+    /// checks a standard, resolved for the storage, against the policy
+    ///
+    /// # Arguments
+    /// * `standard` - Standard the wipe would use
+    ///
+    /// # Returns
+    /// * `Result<()>` - `WipeError::PolicyViolation` if it is weaker than required
+    pub fn check_standard(&self, standard: &WipeStandard) -> Result<()> {
+        if let Some(reason) = &self.unreadable {
+            return Err(violation(format!(
+                "the policy file can't be read ({})",
+                reason
+            )));
+        }
+        let (action, verification) = match standard {
+            WipeStandard::Modern(config) => (
+                match config.method {
+                    SanitizationMethod::Clear => NistAction::Clear,
+                    SanitizationMethod::Purge => NistAction::Purge,
+                },
+                config.verify_level,
+            ),
+            // the method depends on the storage; checked again once resolved
+            WipeStandard::Auto(config) => (NistAction::Purge, config.verify_level),
+            // legacy and custom standards verify each pass in full, or not at all
+            _ if standard.verifies() => (NistAction::Clear, VerificationLevel::Full),
+            _ => (NistAction::Clear, VerificationLevel::None),
+        };
+        if let Some(minimum) = self.minimum_action {
+            if action < minimum {
+                return Err(violation(format!(
+                    "{} achieves at most NIST {}, the policy requires {}",
+                    standard, action, minimum
+                )));
+            }
+        }
+        if let Some(minimum) = self.minimum_verification {
+            if rank(verification) < rank(minimum) {
+                return Err(violation(format!(
+                    "verification {} is weaker than the policy's {}",
+                    verification, minimum
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// checks a quick wipe against the policy, which it fails whenever the
+    /// policy asks for a NIST action, since a quick wipe achieves none
+    ///
+    /// # Returns
+    /// * `Result<()>` - `WipeError::PolicyViolation` if the policy sets a minimum action
+    pub fn check_quick(&self) -> Result<()> {
+        if let Some(reason) = &self.unreadable {
+            return Err(violation(format!(
+                "the policy file can't be read ({})",
+                reason
+            )));
+        }
+        match self.minimum_action {
+            Some(minimum) => Err(violation(format!(
+                "a quick wipe achieves no NIST action, the policy requires {}",
+                minimum
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// checks a target against the banned paths
+    ///
+    /// # Arguments
+    /// * `path` - Path that is about to be wiped
+    pub fn check_path(&self, path: &Path) -> Result<()> {
+        let mut banned = ProtectedPaths::empty();
+        for entry in &self.banned_paths {
+            banned.add(entry);
+        }
+        banned.check(path).map_err(|e| match e {
+            WipeError::ProtectedPath { path, entry } => violation(format!(
+                "{} is banned by the policy entry {}",
+                path.display(),
+                entry.display()
+            )),
+            other => other,
+        })
+    }
+}
+
+fn violation(reason: String) -> WipeError {
+    WipeError::PolicyViolation(reason)
+}
+
+/// how thoroughly a verification level reads the target back
+fn rank(level: VerificationLevel) -> u8 {
+    match level {
+        VerificationLevel::None => 0,
+        VerificationLevel::Basic | VerificationLevel::Sampled(_) => 1,
+        VerificationLevel::Full => 2,
+        VerificationLevel::Enhanced | VerificationLevel::Hash => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy() {
+        let policy: Policy = toml::from_str(
+            "minimum_action = \"purge\"\nminimum_verification = \"full\"\nrequire_report = true\n",
+        )
+        .unwrap();
+        let standard = |name: &str| name.parse::<WipeStandard>().unwrap();
+        assert!(policy.check_standard(&standard("nist")).is_ok());
+        assert!(policy.check_standard(&standard("auto")).is_ok());
+        assert!(policy.check_standard(&standard("nist-clear")).is_err());
+        assert!(policy.check_standard(&standard("gutmann")).is_err());
+
+        let mut unverified = standard("nist");
+        if let WipeStandard::Modern(config) = &mut unverified {
+            config.verify_level = VerificationLevel::Sampled(0.5);
+        }
+        assert!(matches!(
+            policy.check_standard(&unverified),
+            Err(WipeError::PolicyViolation(_))
+        ));
+
+        // merging never loosens either side
+        let merged = Policy {
+            minimum_verification: Some(VerificationLevel::Basic),
+            audit_log: true,
+            ..Policy::default()
+        }
+        .merge(policy);
+        assert_eq!(merged.minimum_action, Some(NistAction::Purge));
+        assert_eq!(merged.minimum_verification, Some(VerificationLevel::Full));
+        assert!(merged.require_report && merged.audit_log);
+
+        let unreadable = Policy {
+            unreadable: Some("bad toml".into()),
+            ..Policy::default()
+        };
+        assert!(unreadable.check_standard(&standard("nist")).is_err());
+    }
+}
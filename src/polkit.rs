@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+/// the polkit action the elevated helper is started under
+pub const ACTION_ID: &str = "io.github.g4titanx.shredder.helper";
+
+/// where polkit looks for action files
+pub const ACTIONS_DIR: &str = "/usr/share/polkit-1/actions";
+
+/// installed location of the action file
+pub fn action_path() -> PathBuf {
+    Path::new(ACTIONS_DIR).join(format!("{}.policy", ACTION_ID))
+}
+
+/// whether the action is installed, in which case the helper is started with
+/// pkexec, and a desktop session shows its authentication dialog, instead of
+/// sudo asking on the terminal
+pub fn installed() -> bool {
+    action_path().exists()
+}
+
+/// action file letting pkexec start `<program> helper` after an administrator
+/// authenticates; the authorization is kept for a few minutes, so the helper
+/// of the next wipe doesn't ask again
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+///
+/// # Returns
+/// * `String` - Contents of the .policy file, for `action_path()`
+pub fn action(program: &Path) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE policyconfig PUBLIC \"-//freedesktop//DTD PolicyKit Policy Configuration 1.0//EN\"\n \
+         \"http://www.freedesktop.org/standards/PolicyKit/1/policyconfig.dtd\">\n\
+         <policyconfig>\n  \
+         <vendor>shredder</vendor>\n  \
+         <vendor_url>https://github.com/g4titanx/shredder</vendor_url>\n  \
+         <action id=\"{}\">\n    \
+         <description>Securely erase storage devices</description>\n    \
+         <message>Authentication is required to run a secure erase or TRIM on a storage device</message>\n    \
+         <defaults>\n      \
+         <allow_any>auth_admin</allow_any>\n      \
+         <allow_inactive>auth_admin</allow_inactive>\n      \
+         <allow_active>auth_admin_keep</allow_active>\n    \
+         </defaults>\n    \
+         <annotate key=\"org.freedesktop.policykit.exec.path\">{}</annotate>\n    \
+         <annotate key=\"org.freedesktop.policykit.exec.argv1\">helper</annotate>\n  \
+         </action>\n\
+         </policyconfig>\n",
+        ACTION_ID,
+        xml_escape(&program.to_string_lossy())
+    )
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action() {
+        let action = action(Path::new("/opt/a&b/shred"));
+        assert!(action.contains("<action id=\"io.github.g4titanx.shredder.helper\">"));
+        assert!(action.contains(
+            "<annotate key=\"org.freedesktop.policykit.exec.path\">/opt/a&amp;b/shred</annotate>"
+        ));
+        assert_eq!(
+            action_path(),
+            Path::new("/usr/share/polkit-1/actions/io.github.g4titanx.shredder.helper.policy")
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use log::debug;
+
+/// keeps the machine from suspending while it is alive
+///
+/// - linux: a systemd-inhibit lock against sleep, shutdown and idle suspend.
+///   systemd-inhibit exits once its stdin closes, so the lock is also released
+///   if the process dies
+/// - macOS: IOKit power assertions against idle and system sleep
+/// - windows: SetThreadExecutionState on the calling thread, so the guard has to
+///   be dropped on the thread that acquired it
+///
+/// everything is released when the guard is dropped
+pub struct SleepInhibitor {
+    #[cfg(target_os = "linux")]
+    child: std::process::Child,
+
+    #[cfg(target_os = "macos")]
+    assertions: Vec<macos::IOPMAssertionID>,
+
+    // SetThreadExecutionState is per thread, so the guard must not move
+    #[cfg(windows)]
+    _thread_bound: std::marker::PhantomData<*const ()>,
+}
+
+impl SleepInhibitor {
+    /// takes a sleep inhibitor lock or power assertion
+    ///
+    /// # Arguments
+    /// * `reason` - Shown by `systemd-inhibit --list` or `pmset -g assertions`
+    ///
+    /// # Returns
+    /// * `Option<SleepInhibitor>` - The guard, or None when sleep can't be prevented
+    ///   (no systemd, or an unsupported platform)
+    pub fn acquire(reason: &str) -> Option<Self> {
+        let inhibitor = acquire_platform(reason);
+        if inhibitor.is_none() {
+            debug!("Unable to keep the system awake during the wipe");
+        }
+        inhibitor
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn acquire_platform(reason: &str) -> Option<SleepInhibitor> {
+    use std::process::{Command, Stdio};
+
+    // cat holds the lock until its stdin, our end of the pipe, closes
+    let child = Command::new("systemd-inhibit")
+        .args([
+            "--what=sleep:shutdown:idle",
+            "--who=shredder",
+            &format!("--why={}", reason),
+            "--mode=block",
+            "cat",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    Some(SleepInhibitor { child })
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        drop(self.child.stdin.take());
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::ffi::{c_char, c_void, CString};
+
+    pub type IOPMAssertionID = u32;
+    type CFStringRef = *const c_void;
+
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const K_IOPM_ASSERTION_LEVEL_ON: u32 = 255;
+    const K_IO_RETURN_SUCCESS: i32 = 0;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            alloc: *const c_void,
+            string: *const c_char,
+            encoding: u32,
+        ) -> CFStringRef;
+        fn CFRelease(object: *const c_void);
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPMAssertionCreateWithName(
+            assertion_type: CFStringRef,
+            level: u32,
+            name: CFStringRef,
+            id: *mut IOPMAssertionID,
+        ) -> i32;
+        fn IOPMAssertionRelease(id: IOPMAssertionID) -> i32;
+    }
+
+    /// a CFString that is released when dropped
+    struct CfString(CFStringRef);
+
+    impl CfString {
+        fn new(text: &str) -> Option<Self> {
+            let text = CString::new(text).ok()?;
+            let string = unsafe {
+                CFStringCreateWithCString(
+                    std::ptr::null(),
+                    text.as_ptr(),
+                    K_CF_STRING_ENCODING_UTF8,
+                )
+            };
+            (!string.is_null()).then_some(Self(string))
+        }
+    }
+
+    impl Drop for CfString {
+        fn drop(&mut self) {
+            unsafe { CFRelease(self.0) };
+        }
+    }
+
+    /// creates a power assertion of the given type, e.g. "PreventUserIdleSystemSleep"
+    pub fn create_assertion(assertion_type: &str, reason: &str) -> Option<IOPMAssertionID> {
+        let assertion_type = CfString::new(assertion_type)?;
+        let name = CfString::new(reason)?;
+        let mut id: IOPMAssertionID = 0;
+        let result = unsafe {
+            IOPMAssertionCreateWithName(
+                assertion_type.0,
+                K_IOPM_ASSERTION_LEVEL_ON,
+                name.0,
+                &mut id,
+            )
+        };
+        (result == K_IO_RETURN_SUCCESS).then_some(id)
+    }
+
+    pub fn release_assertion(id: IOPMAssertionID) {
+        unsafe { IOPMAssertionRelease(id) };
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn acquire_platform(reason: &str) -> Option<SleepInhibitor> {
+    // idle sleep, and system sleep as far as macOS allows it (on AC power)
+    let assertions: Vec<_> = ["PreventUserIdleSystemSleep", "PreventSystemSleep"]
+        .iter()
+        .filter_map(|assertion_type| macos::create_assertion(assertion_type, reason))
+        .collect();
+    (!assertions.is_empty()).then_some(SleepInhibitor { assertions })
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        for id in self.assertions.drain(..) {
+            macos::release_assertion(id);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn acquire_platform(_reason: &str) -> Option<SleepInhibitor> {
+    use winapi::um::winbase::SetThreadExecutionState;
+    use winapi::um::winnt::{ES_CONTINUOUS, ES_SYSTEM_REQUIRED};
+
+    // the display may still turn off, only system sleep is held off
+    let previous = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+    (previous != 0).then_some(SleepInhibitor {
+        _thread_bound: std::marker::PhantomData,
+    })
+}
+
+#[cfg(windows)]
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        use winapi::um::winbase::SetThreadExecutionState;
+        use winapi::um::winnt::ES_CONTINUOUS;
+
+        unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn acquire_platform(_reason: &str) -> Option<SleepInhibitor> {
+    None
+}
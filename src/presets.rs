@@ -0,0 +1,327 @@
+use std::path::{Path, PathBuf};
+
+/// a bundle of application traces that can be wiped in one go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// disk caches of Firefox, Chrome, Chromium, Edge, Brave and Safari
+    BrowserCaches,
+    /// command histories of shells and interactive interpreters
+    ShellHistory,
+    /// thumbnail caches that keep previews of images and documents
+    Thumbnails,
+    /// autosave, autorecovery and recent-document files of office suites
+    OfficeTemp,
+}
+
+/// directory a preset pattern is relative to
+#[derive(Debug, Clone, Copy)]
+enum Base {
+    /// home directory of the invoking user
+    #[cfg(unix)]
+    Home,
+    /// %APPDATA% (roaming)
+    #[cfg(windows)]
+    AppData,
+    /// %LOCALAPPDATA%
+    #[cfg(windows)]
+    LocalAppData,
+    /// the per-user cache folder under /private/var/folders
+    #[cfg(target_os = "macos")]
+    UserCache,
+}
+
+// patterns are relative to their base, use / as separator and may contain *
+// in any component; a trailing /* selects a directory's contents so the
+// directory itself is left in place for the application
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod paths {
+    use super::Base::{self, Home};
+
+    pub(super) const BROWSER_CACHES: &[(Base, &str)] = &[
+        (Home, ".cache/mozilla/firefox/*/cache2/*"),
+        (Home, ".cache/google-chrome/*/Cache/*"),
+        (Home, ".cache/google-chrome/*/Code Cache/*"),
+        (Home, ".cache/chromium/*/Cache/*"),
+        (Home, ".cache/chromium/*/Code Cache/*"),
+        (Home, ".cache/microsoft-edge/*/Cache/*"),
+        (Home, ".cache/BraveSoftware/Brave-Browser/*/Cache/*"),
+    ];
+    pub(super) const SHELL_HISTORY: &[(Base, &str)] = &[
+        (Home, ".bash_history"),
+        (Home, ".zsh_history"),
+        (Home, ".zhistory"),
+        (Home, ".histfile"),
+        (Home, ".local/share/fish/fish_history"),
+        (Home, ".python_history"),
+        (Home, ".node_repl_history"),
+        (Home, ".psql_history"),
+        (Home, ".mysql_history"),
+        (Home, ".sqlite_history"),
+        (Home, ".lesshst"),
+        (Home, ".viminfo"),
+    ];
+    pub(super) const THUMBNAILS: &[(Base, &str)] =
+        &[(Home, ".cache/thumbnails/*"), (Home, ".thumbnails/*")];
+    pub(super) const OFFICE_TEMP: &[(Base, &str)] =
+        &[(Home, ".config/libreoffice/4/user/backup/*")];
+}
+
+#[cfg(target_os = "macos")]
+mod paths {
+    use super::Base::{self, Home, UserCache};
+
+    pub(super) const BROWSER_CACHES: &[(Base, &str)] = &[
+        (Home, "Library/Caches/Firefox/Profiles/*/cache2/*"),
+        (Home, "Library/Caches/Google/Chrome/*/Cache/*"),
+        (Home, "Library/Caches/Google/Chrome/*/Code Cache/*"),
+        (Home, "Library/Caches/Chromium/*/Cache/*"),
+        (Home, "Library/Caches/Chromium/*/Code Cache/*"),
+        (Home, "Library/Caches/Microsoft Edge/*/Cache/*"),
+        (Home, "Library/Caches/BraveSoftware/Brave-Browser/*/Cache/*"),
+        (Home, "Library/Caches/com.apple.Safari/*"),
+        (
+            Home,
+            "Library/Containers/com.apple.Safari/Data/Library/Caches/com.apple.Safari/*",
+        ),
+    ];
+    pub(super) const SHELL_HISTORY: &[(Base, &str)] = &[
+        (Home, ".zsh_history"),
+        (Home, ".zsh_sessions/*"),
+        (Home, ".bash_history"),
+        (Home, ".bash_sessions/*"),
+        (Home, ".local/share/fish/fish_history"),
+        (Home, ".python_history"),
+        (Home, ".node_repl_history"),
+        (Home, ".psql_history"),
+        (Home, ".mysql_history"),
+        (Home, ".sqlite_history"),
+        (Home, ".lesshst"),
+        (Home, ".viminfo"),
+    ];
+    pub(super) const THUMBNAILS: &[(Base, &str)] =
+        &[(UserCache, "com.apple.QuickLook.thumbnailcache/*")];
+    pub(super) const OFFICE_TEMP: &[(Base, &str)] = &[
+        (
+            Home,
+            "Library/Containers/com.microsoft.Word/Data/Library/Preferences/AutoRecovery/*",
+        ),
+        (
+            Home,
+            "Library/Containers/com.microsoft.Excel/Data/Library/Preferences/AutoRecovery/*",
+        ),
+        (
+            Home,
+            "Library/Containers/com.microsoft.Powerpoint/Data/Library/Preferences/AutoRecovery/*",
+        ),
+        (
+            Home,
+            "Library/Application Support/LibreOffice/4/user/backup/*",
+        ),
+    ];
+}
+
+#[cfg(windows)]
+mod paths {
+    use super::Base::{self, AppData, LocalAppData};
+
+    pub(super) const BROWSER_CACHES: &[(Base, &str)] = &[
+        (LocalAppData, "Mozilla/Firefox/Profiles/*/cache2/*"),
+        (LocalAppData, "Google/Chrome/User Data/*/Cache/*"),
+        (LocalAppData, "Google/Chrome/User Data/*/Code Cache/*"),
+        (LocalAppData, "Chromium/User Data/*/Cache/*"),
+        (LocalAppData, "Microsoft/Edge/User Data/*/Cache/*"),
+        (LocalAppData, "Microsoft/Edge/User Data/*/Code Cache/*"),
+        (
+            LocalAppData,
+            "BraveSoftware/Brave-Browser/User Data/*/Cache/*",
+        ),
+    ];
+    pub(super) const SHELL_HISTORY: &[(Base, &str)] = &[(
+        AppData,
+        "Microsoft/Windows/PowerShell/PSReadLine/*_history.txt",
+    )];
+    pub(super) const THUMBNAILS: &[(Base, &str)] = &[
+        (LocalAppData, "Microsoft/Windows/Explorer/thumbcache_*.db"),
+        (LocalAppData, "Microsoft/Windows/Explorer/iconcache_*.db"),
+    ];
+    pub(super) const OFFICE_TEMP: &[(Base, &str)] = &[
+        (AppData, "Microsoft/Office/Recent/*"),
+        (LocalAppData, "Microsoft/Office/UnsavedFiles/*"),
+        (AppData, "Microsoft/Word/*.asd"),
+        (AppData, "Microsoft/Word/*.wbk"),
+        (AppData, "Microsoft/Excel/*.xar"),
+        (AppData, "LibreOffice/4/user/backup/*"),
+    ];
+}
+
+impl Preset {
+    /// every preset, in the order they are listed in help text
+    pub const ALL: [Preset; 4] = [
+        Preset::BrowserCaches,
+        Preset::ShellHistory,
+        Preset::Thumbnails,
+        Preset::OfficeTemp,
+    ];
+
+    /// the name used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::BrowserCaches => "browser-caches",
+            Preset::ShellHistory => "shell-history",
+            Preset::Thumbnails => "thumbnails",
+            Preset::OfficeTemp => "office-temp",
+        }
+    }
+
+    /// expands the preset into the files and directories that exist for the
+    /// invoking user on this system
+    ///
+    /// # Returns
+    /// * `Vec<PathBuf>` - Paths to wipe; directories are meant for `Shredder::wipe_tree`
+    pub fn entries(&self) -> Vec<PathBuf> {
+        let patterns = match self {
+            Preset::BrowserCaches => paths::BROWSER_CACHES,
+            Preset::ShellHistory => paths::SHELL_HISTORY,
+            Preset::Thumbnails => paths::THUMBNAILS,
+            Preset::OfficeTemp => paths::OFFICE_TEMP,
+        };
+
+        let mut entries = Vec::new();
+        for &(base, pattern) in patterns {
+            match base_dir(base) {
+                Some(dir) => entries.extend(expand(&dir, pattern)),
+                None => log::debug!("Skipping {}: {:?} directory not found", pattern, base),
+            }
+        }
+        entries
+    }
+}
+
+fn base_dir(base: Base) -> Option<PathBuf> {
+    match base {
+        #[cfg(unix)]
+        Base::Home => crate::locations::user_home(),
+        #[cfg(windows)]
+        Base::AppData => std::env::var_os("APPDATA").map(PathBuf::from),
+        #[cfg(windows)]
+        Base::LocalAppData => std::env::var_os("LOCALAPPDATA").map(PathBuf::from),
+        #[cfg(target_os = "macos")]
+        Base::UserCache => darwin_user_cache_dir(),
+    }
+}
+
+/// finds the invoking user's cache folder, /private/var/folders/xx/<id>/C
+///
+/// `getconf DARWIN_USER_CACHE_DIR` would answer for root under sudo, so the
+/// folder is picked by its owner instead
+#[cfg(target_os = "macos")]
+fn darwin_user_cache_dir() -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let uid = crate::locations::invoking_uid();
+    std::fs::read_dir("/private/var/folders")
+        .ok()?
+        .flatten()
+        .flat_map(|bucket| std::fs::read_dir(bucket.path()).into_iter().flatten())
+        .flatten()
+        .find(|dir| dir.metadata().is_ok_and(|m| m.is_dir() && m.uid() == uid))
+        .map(|dir| dir.path().join("C"))
+}
+
+/// expands a pattern relative to `base` into the paths that exist
+fn expand(base: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut paths = vec![base.to_path_buf()];
+
+    for component in pattern.split('/') {
+        let mut next = Vec::new();
+        for path in &paths {
+            if component.contains('*') {
+                let Ok(entries) = std::fs::read_dir(path) else {
+                    continue;
+                };
+                for entry in entries.flatten() {
+                    if wildcard_match(component, &entry.file_name().to_string_lossy()) {
+                        next.push(entry.path());
+                    }
+                }
+            } else {
+                let candidate = path.join(component);
+                if candidate.symlink_metadata().is_ok() {
+                    next.push(candidate);
+                }
+            }
+        }
+        paths = next;
+    }
+
+    paths.sort();
+    paths
+}
+
+/// matches a file name against a pattern where * stands for any run of
+/// characters; case-insensitive on windows like the filesystem
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = if cfg!(windows) {
+        (pattern.to_lowercase(), name.to_lowercase())
+    } else {
+        (pattern.to_string(), name.to_string())
+    };
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if parts.len() == 1 {
+        return name == first;
+    }
+    if name.len() < first.len() + last.len() || !name.starts_with(first) || !name.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("thumbcache_*.db", "thumbcache_1024.db"));
+        assert!(wildcard_match("*_history.txt", "ConsoleHost_history.txt"));
+        assert!(wildcard_match("a*b*c", "a-b-c"));
+        assert!(!wildcard_match("a*b*c", "a-c-b"));
+        assert!(!wildcard_match("ab*ba", "aba"));
+        assert!(!wildcard_match(".zsh_history", ".zsh_history.bak"));
+    }
+
+    #[test]
+    fn test_expand() {
+        let base = tempfile::tempdir().unwrap();
+        for profile in ["abc.default", "xyz.work"] {
+            let cache = base.path().join("firefox").join(profile).join("cache2");
+            std::fs::create_dir_all(cache.join("entries")).unwrap();
+            std::fs::write(cache.join("index"), b"").unwrap();
+        }
+        std::fs::create_dir_all(base.path().join("firefox/no-cache")).unwrap();
+
+        let expanded = expand(base.path(), "firefox/*/cache2/*");
+        assert_eq!(
+            expanded,
+            vec![
+                base.path().join("firefox/abc.default/cache2/entries"),
+                base.path().join("firefox/abc.default/cache2/index"),
+                base.path().join("firefox/xyz.work/cache2/entries"),
+                base.path().join("firefox/xyz.work/cache2/index"),
+            ]
+        );
+        assert!(expand(base.path(), "missing/*").is_empty());
+    }
+}
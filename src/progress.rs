@@ -0,0 +1,63 @@
+use std::path::Path;
+
+/// progress of an overwrite pass, handed to the callback set with
+/// [`Shredder::with_progress`](crate::Shredder::with_progress)
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    /// target being overwritten
+    pub path: &'a Path,
+
+    /// current pass, starting at 1
+    pub pass: usize,
+
+    /// number of passes the standard performs
+    pub passes: usize,
+
+    /// bytes written so far in the current pass
+    pub bytes_written: u64,
+
+    /// size of the target, i.e. the bytes written by each pass
+    pub bytes_per_pass: u64,
+}
+
+impl Progress<'_> {
+    /// bytes written across all passes so far
+    pub fn total_written(&self) -> u64 {
+        (self.pass.saturating_sub(1) as u64) * self.bytes_per_pass + self.bytes_written
+    }
+
+    /// bytes all passes together write
+    pub fn total_bytes(&self) -> u64 {
+        self.passes as u64 * self.bytes_per_pass
+    }
+
+    /// fraction of the whole wipe that is done, from 0.0 to 1.0
+    pub fn fraction(&self) -> f64 {
+        match self.total_bytes() {
+            0 => 1.0,
+            total => self.total_written() as f64 / total as f64,
+        }
+    }
+}
+
+/// callback receiving progress updates; called after every buffer written
+pub type ProgressCallback = Box<dyn Fn(&Progress) + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_totals() {
+        let progress = Progress {
+            path: Path::new("/dev/sdb"),
+            pass: 3,
+            passes: 4,
+            bytes_written: 50,
+            bytes_per_pass: 100,
+        };
+        assert_eq!(progress.total_written(), 250);
+        assert_eq!(progress.total_bytes(), 400);
+        assert_eq!(progress.fraction(), 0.625);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{Result, WipeError};
+use std::path::{Path, PathBuf};
+
+/// paths the shredder refuses to touch
+///
+/// subtree entries protect a directory and everything below it (e.g. /etc),
+/// exact entries protect only the path itself (e.g. / or the home directory,
+/// whose contents are fair game)
+#[derive(Debug, Clone)]
+pub struct ProtectedPaths {
+    subtrees: Vec<PathBuf>,
+    exact: Vec<PathBuf>,
+}
+
+impl Default for ProtectedPaths {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ProtectedPaths {
+    /// an empty denylist that protects nothing
+    pub fn empty() -> Self {
+        Self {
+            subtrees: Vec::new(),
+            exact: Vec::new(),
+        }
+    }
+
+    /// the built-in denylist of operating system directories
+    pub fn builtin() -> Self {
+        let mut protected = Self::empty();
+
+        #[cfg(unix)]
+        {
+            for dir in [
+                "/bin", "/boot", "/etc", "/lib", "/lib64", "/proc", "/sbin", "/sys", "/usr",
+            ] {
+                protected.subtrees.push(PathBuf::from(dir));
+            }
+            for dir in ["/", "/home", "/root"] {
+                protected.exact.push(PathBuf::from(dir));
+            }
+        }
+        #[cfg(target_os = "macos")]
+        {
+            for dir in ["/System", "/Library", "/private/etc", "/private/var/db"] {
+                protected.subtrees.push(PathBuf::from(dir));
+            }
+            protected.exact.push(PathBuf::from("/Users"));
+        }
+        #[cfg(windows)]
+        {
+            for var in [
+                "SystemRoot",
+                "ProgramFiles",
+                "ProgramFiles(x86)",
+                "ProgramData",
+            ] {
+                if let Some(dir) = std::env::var_os(var) {
+                    protected.subtrees.push(PathBuf::from(dir));
+                }
+            }
+            let system_drive = std::env::var_os("SystemDrive").unwrap_or_else(|| "C:".into());
+            let mut root = PathBuf::from(system_drive);
+            root.push("\\");
+            protected.exact.push(root.join("Users"));
+            protected.exact.push(root);
+        }
+
+        // the user's home directory itself, but not the files inside it
+        #[cfg(unix)]
+        let home = std::env::var_os("HOME");
+        #[cfg(windows)]
+        let home = std::env::var_os("USERPROFILE");
+        if let Some(home) = home {
+            protected.exact.push(PathBuf::from(home));
+        }
+
+        protected
+    }
+
+    /// adds a directory that is protected along with everything inside it
+    pub fn add(&mut self, path: impl Into<PathBuf>) {
+        self.subtrees.push(path.into());
+    }
+
+    /// checks the target against the denylist
+    ///
+    /// both sides are canonicalized so symlinks and `..` can't be used to
+    /// slip past an entry
+    ///
+    /// # Arguments
+    /// * `path` - Path that is about to be wiped
+    ///
+    /// # Returns
+    /// * `Result<()>` - Error naming the matching entry if the path is protected
+    pub fn check(&self, path: &Path) -> Result<()> {
+        let target = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        let refuse = |entry: &Path| WipeError::ProtectedPath {
+            path: path.to_path_buf(),
+            entry: entry.to_path_buf(),
+        };
+
+        for entry in &self.subtrees {
+            // entries that don't exist on this system can't contain the target
+            if let Ok(canonical) = std::fs::canonicalize(entry) {
+                if target.starts_with(&canonical) {
+                    return Err(refuse(entry));
+                }
+            }
+        }
+        for entry in &self.exact {
+            if let Ok(canonical) = std::fs::canonicalize(entry) {
+                if target == canonical {
+                    return Err(refuse(entry));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
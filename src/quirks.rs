@@ -0,0 +1,162 @@
+//! drive models with firmware bugs that make a hardware erase or TRIM unsafe or
+//! useless, so those paths are skipped before they are tried
+//!
+//! the built-in table is kept short and only lists drives whose bugs are
+//! documented elsewhere, e.g. in the linux libata blacklist. more entries go in
+//! `[[quirks]]` tables of the configuration file
+
+use serde::Deserialize;
+
+/// what is known to be broken on one drive model
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Quirk {
+    /// model as the device reports it, with or without the vendor in front;
+    /// `*` stands for any run of characters and case is ignored
+    pub model: String,
+
+    /// the drive keeps its ATA security state frozen, so a secure erase can't
+    /// start
+    pub frozen_security: bool,
+
+    /// the drive's sanitize command reports success without erasing
+    /// everything, or fails in a way that leaves it unusable
+    pub broken_sanitize: bool,
+
+    /// the drive claims TRIM support but ignores it or discards the wrong
+    /// blocks
+    pub broken_trim: bool,
+
+    /// why the entry exists, shown in the log and the report
+    pub note: Option<String>,
+}
+
+impl Quirk {
+    fn new(model: &str, broken_trim: bool, note: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            broken_trim,
+            note: Some(note.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// whether the entry applies to a device reporting `model`
+    fn matches(&self, model: &str) -> bool {
+        let pattern = self.model.to_lowercase();
+        let model = model.trim().to_lowercase();
+        // linux puts the vendor ("ATA") in front of the model
+        let without_vendor = model.split_once(' ').map(|(_, rest)| rest);
+        crate::presets::wildcard_match(&pattern, &model)
+            || without_vendor.is_some_and(|rest| crate::presets::wildcard_match(&pattern, rest))
+    }
+
+    /// the note, or a generic reason
+    pub fn reason(&self) -> &str {
+        self.note.as_deref().unwrap_or("known firmware bug")
+    }
+}
+
+/// the quirks table consulted before hardware erase and TRIM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quirks {
+    entries: Vec<Quirk>,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Quirks {
+    /// the built-in table
+    pub fn builtin() -> Self {
+        Self {
+            entries: vec![
+                Quirk::new(
+                    "SuperSSpeed S238*",
+                    true,
+                    "TRIM discards the wrong blocks (blacklisted by linux libata)",
+                ),
+                Quirk::new(
+                    "M88V29*",
+                    true,
+                    "TRIM is not handled properly (blacklisted by linux libata)",
+                ),
+            ],
+        }
+    }
+
+    /// a table without any entries, so nothing is skipped
+    pub fn none() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// adds entries, e.g. the `[[quirks]]` tables of the configuration file
+    pub fn with_entries<I: IntoIterator<Item = Quirk>>(mut self, entries: I) -> Self {
+        self.entries.extend(entries);
+        self
+    }
+
+    /// looks up a model, combining every entry that matches it
+    ///
+    /// # Arguments
+    /// * `model` - Model as the device reports it
+    ///
+    /// # Returns
+    /// * `Option<Quirk>` - What is broken on it, or `None` if nothing is known
+    pub fn lookup(&self, model: &str) -> Option<Quirk> {
+        let mut matching = self.entries.iter().filter(|quirk| quirk.matches(model));
+        let mut combined = matching.next()?.clone();
+        for quirk in matching {
+            combined.frozen_security |= quirk.frozen_security;
+            combined.broken_sanitize |= quirk.broken_sanitize;
+            combined.broken_trim |= quirk.broken_trim;
+            combined.note = match (combined.note, &quirk.note) {
+                (Some(first), Some(other)) => Some(format!("{}; {}", first, other)),
+                (first, other) => first.or_else(|| other.clone()),
+            };
+        }
+        combined.model = model.to_string();
+        Some(combined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let quirks = Quirks::builtin();
+        assert!(
+            quirks
+                .lookup("ATA SuperSSpeed S238 128GB")
+                .unwrap()
+                .broken_trim
+        );
+        assert!(quirks.lookup("SuperSSpeed S100").is_none());
+        assert!(quirks.lookup("Samsung SSD 870 EVO 1TB").is_none());
+
+        let quirks = quirks.with_entries([
+            Quirk {
+                model: "acme nvme*".into(),
+                broken_sanitize: true,
+                note: Some("sanitize bricks it".into()),
+                ..Default::default()
+            },
+            Quirk {
+                model: "*1TB".into(),
+                frozen_security: true,
+                ..Default::default()
+            },
+        ]);
+        let quirk = quirks.lookup("ACME NVMe 1TB").unwrap();
+        assert!(quirk.broken_sanitize && quirk.frozen_security && !quirk.broken_trim);
+        assert_eq!(quirk.reason(), "sanitize bricks it");
+        assert!(Quirks::none().lookup("ATA SuperSSpeed S238").is_none());
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// filesystems store file data in whole sectors, so copies of a sector of the
+/// file start on a sector boundary of the volume
+const SECTOR: usize = 512;
+
+/// bytes compared at the start of each sampled sector
+const WINDOW: usize = 32;
+
+/// sectors sampled from a target, spread over its length
+const SAMPLES: usize = 16;
+
+/// windows with fewer distinct bytes (blank or filler sectors) are not
+/// sampled, since they'd match everywhere
+const MIN_DISTINCT: usize = 8;
+
+/// the starts of a few sectors of a target, read before it is wiped, to look
+/// for afterwards
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Signature {
+    windows: Vec<[u8; WINDOW]>,
+}
+
+impl Signature {
+    /// samples up to SAMPLES sectors, always including the first, which holds
+    /// the file's magic bytes
+    pub(crate) fn take(path: &Path) -> crate::Result<Self> {
+        let mut file = File::open(path)?;
+        let sectors = crate::target_size(&mut file)? / SECTOR as u64;
+        let mut windows = Vec::new();
+        if sectors == 0 {
+            return Ok(Self { windows });
+        }
+
+        let step = (sectors / SAMPLES as u64).max(1);
+        let mut window = [0u8; WINDOW];
+        for sector in (0..sectors).step_by(step as usize).take(SAMPLES) {
+            file.seek(SeekFrom::Start(sector * SECTOR as u64))?;
+            file.read_exact(&mut window)?;
+            if distinct_bytes(&window) >= MIN_DISTINCT && !windows.contains(&window) {
+                windows.push(window);
+            }
+        }
+        Ok(Self { windows })
+    }
+
+    /// whether nothing distinctive was found to look for
+    pub(crate) fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// reads a volume or device from start to end and returns the offsets of
+    /// the sectors that begin like a sampled sector
+    pub(crate) fn scan(&self, volume: &Path) -> io::Result<Vec<u64>> {
+        const CHUNK: usize = 1024 * 1024;
+
+        let mut file = crate::open_uncached(volume)?;
+        let mut buffer = zeroize::Zeroizing::new(vec![0u8; CHUNK]);
+        let mut found = Vec::new();
+        let mut offset = 0u64;
+        loop {
+            let n = read_full(&mut file, &mut buffer)?;
+            for (index, sector) in buffer[..n].chunks(SECTOR).enumerate() {
+                if sector.len() >= WINDOW && self.windows.iter().any(|w| sector[..WINDOW] == *w) {
+                    found.push(offset + (index * SECTOR) as u64);
+                }
+            }
+            if n < CHUNK {
+                break;
+            }
+            offset += n as u64;
+        }
+        Ok(found)
+    }
+}
+
+impl Drop for Signature {
+    fn drop(&mut self) {
+        // the windows are the target's contents
+        zeroize::Zeroize::zeroize(&mut self.windows);
+    }
+}
+
+/// fills as much of `buf` as the file has left; less only at the end
+fn read_full(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn distinct_bytes(window: &[u8]) -> usize {
+    let mut seen = [false; 256];
+    for &byte in window {
+        seen[byte as usize] = true;
+    }
+    seen.iter().filter(|&&seen| seen).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_signature_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("secret.pdf");
+        let mut data = vec![0u8; 64 * SECTOR];
+        data[..8].copy_from_slice(b"%PDF-1.7");
+        for (i, byte) in data[8..].iter_mut().enumerate() {
+            *byte = (i * 7 % 251) as u8;
+        }
+        std::fs::write(&target, &data).unwrap();
+        let signature = Signature::take(&target).unwrap();
+        assert!(!signature.is_empty());
+
+        // a "volume" holding a stale copy of the file's first sector at sector 3
+        let volume = dir.path().join("volume.img");
+        let mut image = File::create(&volume).unwrap();
+        image.write_all(&vec![0u8; 3 * SECTOR]).unwrap();
+        image.write_all(&data[..SECTOR]).unwrap();
+        image.write_all(&vec![0u8; 4 * SECTOR]).unwrap();
+        drop(image);
+        assert_eq!(signature.scan(&volume).unwrap(), [3 * SECTOR as u64]);
+
+        // blank files give nothing to look for
+        std::fs::write(&target, vec![0u8; 8 * SECTOR]).unwrap();
+        assert!(Signature::take(&target).unwrap().is_empty());
+    }
+}
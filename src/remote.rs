@@ -0,0 +1,256 @@
+use crate::report::{reports_from_toml, WipeReport};
+use crate::{Result, WipeError};
+use std::io::{IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+/// separates the remote shredder's own output from the report sent after it
+const REPORT_MARKER: &str = "@@SHREDDER-REPORT@@";
+
+/// a `user@host:/path` target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteTarget {
+    /// ssh destination, e.g. admin@db01 or an alias from ~/.ssh/config
+    pub host: String,
+
+    /// file or device on the remote machine
+    pub path: String,
+}
+
+impl RemoteTarget {
+    /// parses `[user@]host:/path`, with IPv6 addresses in brackets
+    ///
+    /// # Arguments
+    /// * `target` - Target as given on the command line
+    ///
+    /// # Returns
+    /// * `Result<RemoteTarget>` - Host and path, or an error if either is missing
+    pub fn parse(target: &str) -> Result<Self> {
+        let invalid = || {
+            WipeError::UnsupportedOperation(format!(
+                "Remote target '{}' must look like user@host:/path",
+                target
+            ))
+        };
+
+        // the colon of a bracketed IPv6 address doesn't separate the path
+        let search_from = target.find(']').unwrap_or(0);
+        let split = target[search_from..]
+            .find(':')
+            .map(|index| index + search_from)
+            .ok_or_else(invalid)?;
+        let (host, path) = (&target[..split], &target[split + 1..]);
+        if host.is_empty() || path.is_empty() {
+            return Err(invalid());
+        }
+        // ssh would take it for an option, e.g. -oProxyCommand=...
+        if host.starts_with('-') {
+            return Err(WipeError::UnsupportedOperation(format!(
+                "Remote host '{}' must not start with '-'",
+                host
+            )));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for RemoteTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.host, self.path)
+    }
+}
+
+/// how to start the shredder on the remote machine
+#[derive(Debug, Clone)]
+pub struct RemoteOptions {
+    /// shredder binary on the remote machine
+    pub program: String,
+
+    /// run it through sudo
+    pub sudo: bool,
+
+    /// options passed on to the remote shredder (standard, verification, ...)
+    pub args: Vec<String>,
+
+    /// extra arguments for ssh itself, e.g. ["-p", "2222"]
+    pub ssh_args: Vec<String>,
+}
+
+/// result of a remote wipe
+#[derive(Debug)]
+pub struct RemoteOutcome {
+    /// exit code of the remote shredder, None if ssh was killed by a signal
+    pub exit_code: Option<i32>,
+
+    /// reports of what the remote shredder wiped
+    pub reports: Vec<WipeReport>,
+}
+
+/// wipes a file or device on another machine by running the shredder there over ssh
+///
+/// the remote output (progress, confirmation prompts) is streamed to stdout as
+/// it arrives. a terminal is requested when stdin is one, so prompts and sudo
+/// passwords can be answered. afterwards the report the remote shredder wrote
+/// to a temporary file is sent back over the same connection and parsed
+///
+/// # Arguments
+/// * `target` - Remote host and path
+/// * `options` - Remote binary, sudo and the options to forward
+///
+/// # Returns
+/// * `Result<RemoteOutcome>` - Remote exit code and the reports it sent back
+pub fn wipe_remote(target: &RemoteTarget, options: &RemoteOptions) -> Result<RemoteOutcome> {
+    let mut ssh = Command::new("ssh");
+    if std::io::stdin().is_terminal() {
+        ssh.arg("-t");
+    }
+    let mut child = ssh
+        .args(&options.ssh_args)
+        // ssh reads options again after the host, hence both separators
+        .arg("--")
+        .arg(&target.host)
+        .arg("--")
+        .arg(remote_command(&target.path, options))
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| WipeError::UnsupportedOperation(format!("Unable to run ssh: {}", e)))?;
+
+    let mut remote_output = child.stdout.take().expect("stdout is piped");
+    let mut stdout = std::io::stdout();
+    let mut pending: Vec<u8> = Vec::new();
+    let mut report: Option<Vec<u8>> = None;
+    let mut buffer = [0u8; 4096];
+
+    loop {
+        let read = remote_output.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        if let Some(report) = &mut report {
+            report.extend_from_slice(&buffer[..read]);
+            continue;
+        }
+
+        pending.extend_from_slice(&buffer[..read]);
+        match find(&pending, REPORT_MARKER.as_bytes()) {
+            Some(index) => {
+                stdout.write_all(&pending[..index])?;
+                report = Some(pending[index + REPORT_MARKER.len()..].to_vec());
+                pending.clear();
+            }
+            None => {
+                // hold back what could be the start of the marker
+                let flush = pending.len().saturating_sub(REPORT_MARKER.len() - 1);
+                stdout.write_all(&pending[..flush])?;
+                pending.drain(..flush);
+            }
+        }
+        stdout.flush()?;
+    }
+    stdout.write_all(&pending)?;
+    stdout.flush()?;
+
+    let status = child.wait()?;
+    let reports = match report {
+        Some(report) => reports_from_toml(&String::from_utf8_lossy(&report))?,
+        None => Vec::new(),
+    };
+
+    Ok(RemoteOutcome {
+        exit_code: status.code(),
+        reports,
+    })
+}
+
+/// builds the POSIX shell snippet run on the remote machine
+fn remote_command(path: &str, options: &RemoteOptions) -> String {
+    let mut shred = vec![shell_quote(&options.program)];
+    shred.extend(options.args.iter().map(|arg| shell_quote(arg)));
+    shred.push("--report \"$F\" --".to_string());
+    shred.push(shell_quote(path));
+
+    let script = format!(
+        "F=$(mktemp) || exit 1; {}; s=$?; printf '\\n%s\\n' '{}'; cat \"$F\"; rm -f \"$F\"; exit $s",
+        shred.join(" "),
+        REPORT_MARKER
+    );
+    if options.sudo {
+        format!("sudo sh -c {}", shell_quote(&script))
+    } else {
+        script
+    }
+}
+
+/// quotes an argument for a POSIX shell
+pub(crate) fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_target() {
+        assert_eq!(
+            RemoteTarget::parse("admin@db01:/dev/sdb").unwrap(),
+            RemoteTarget {
+                host: "admin@db01".into(),
+                path: "/dev/sdb".into()
+            }
+        );
+        assert_eq!(
+            RemoteTarget::parse("root@[fe80::1]:/srv/data.img").unwrap(),
+            RemoteTarget {
+                host: "root@[fe80::1]".into(),
+                path: "/srv/data.img".into()
+            }
+        );
+        assert!(RemoteTarget::parse("db01").is_err());
+        assert!(RemoteTarget::parse("db01:").is_err());
+        assert!(RemoteTarget::parse(":/etc/passwd").is_err());
+        assert!(RemoteTarget::parse("-oProxyCommand=sh:/tmp").is_err());
+    }
+
+    #[test]
+    fn test_remote_command_quoting() {
+        let options = RemoteOptions {
+            program: "shred".into(),
+            sudo: true,
+            args: vec!["--standard".into(), "dod".into()],
+            ssh_args: Vec::new(),
+        };
+        assert!(remote_command("/srv/data", &options).starts_with("sudo sh -c '"));
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    /// the quoted path reaches the remote program unchanged
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_command_runs_in_sh() {
+        let options = RemoteOptions {
+            program: "echo".into(),
+            sudo: false,
+            args: vec!["-n".into()],
+            ssh_args: Vec::new(),
+        };
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(remote_command("/srv/it's $HOME", &options))
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        assert!(stdout.contains("-- /srv/it's $HOME"), "{stdout}");
+        assert!(stdout.contains(REPORT_MARKER));
+    }
+}
@@ -0,0 +1,542 @@
+use crate::{Result, WipeError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// summary of a completed wipe operation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WipeReport {
+    /// path of the wiped target
+    pub path: PathBuf,
+
+    /// filesystem type the target lived on, if it could be detected
+    pub filesystem: Option<String>,
+
+    /// number of overwrite passes performed
+    pub passes: usize,
+
+    /// total bytes written across all passes
+    pub bytes_written: u64,
+
+    /// SHA-256 of the contents before the first pass, as lowercase hex, if the
+    /// target was hashed
+    pub sha256: Option<String>,
+
+    /// sectors found on the volume afterwards that still begin like the wiped
+    /// data, if it was scanned for remnants
+    pub remnants: Option<u64>,
+
+    /// SHA-256 of the data of each pass verified by hash, as lowercase hex, in
+    /// the order of the passes
+    pub pass_sha256: Vec<String>,
+
+    /// what a sampled verification read, if the wipe was verified that way
+    pub sampled_verification: Option<SampledVerification>,
+
+    /// caveats that limit the assurance this wipe provides
+    /// an empty list means no known limitation applies
+    pub limitations: Vec<String>,
+
+    /// NIST SP 800-88 action achieved by the methods that succeeded, which
+    /// can fall short of the one the standard asked for
+    pub nist_action: Option<NistAction>,
+
+    /// the standard asked for Purge but nothing that succeeded purges this
+    /// media, so only destroying it meets the requirement
+    pub destroy_required: bool,
+
+    /// the physical device the target was stored on, if it could be found
+    pub device: Option<DeviceIdentity>,
+
+    /// only the start and end of the device and of its partitions were
+    /// overwritten; such a wipe is neither NIST Clear nor Purge
+    pub quick: bool,
+
+    /// the partition that was wiped, when the target was a single partition
+    /// of a disk, in bytes from the start of the disk
+    pub partition: Option<crate::partitions::Partition>,
+
+    /// job the wipe ran as, to query it with the functions of the jobs module
+    pub job: Option<crate::jobs::JobId>,
+
+    /// standard the target was wiped with, e.g. "nist" or "dod"
+    pub standard: Option<String>,
+
+    /// seconds the wipe took, from the checks before the first pass to the
+    /// removal of the file
+    pub duration_secs: Option<f64>,
+}
+
+/// identity of the physical device a wipe was done on, to tie the report to
+/// one unit in asset-disposal records
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceIdentity {
+    /// device node, e.g. /dev/sdb, or volume on windows
+    pub device: Option<PathBuf>,
+
+    /// vendor and model
+    pub model: Option<String>,
+
+    /// serial number
+    pub serial: Option<String>,
+
+    /// world wide name or other unique identifier
+    pub wwn: Option<String>,
+
+    /// firmware revision
+    pub firmware: Option<String>,
+
+    /// capacity in bytes
+    pub capacity: Option<u64>,
+}
+
+/// sanitization action of NIST SP 800-88 Rev. 1, ordered from weakest to
+/// strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NistAction {
+    /// protects against recovery with ordinary software tools
+    Clear,
+    /// makes recovery infeasible even with laboratory techniques
+    Purge,
+}
+
+impl std::fmt::Display for NistAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NistAction::Clear => write!(f, "Clear"),
+            NistAction::Purge => write!(f, "Purge"),
+        }
+    }
+}
+
+/// outcome of verifying a wipe by reading a sample of the target's blocks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SampledVerification {
+    /// 4 KiB blocks in the target
+    pub blocks: u64,
+
+    /// blocks read back, all of which held the pattern
+    pub blocks_checked: u64,
+
+    /// largest share of the blocks that could still be unwiped, with 95%
+    /// confidence, from 0.0 to 1.0
+    pub max_unwiped: f64,
+}
+
+impl WipeReport {
+    /// creates an empty report for the given target
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+
+    /// records a completed overwrite pass of `bytes` bytes
+    pub fn record_pass(&mut self, bytes: u64) {
+        self.passes += 1;
+        self.bytes_written += bytes;
+    }
+
+    /// records a caveat about the wipe's effectiveness
+    pub fn add_limitation<S: Into<String>>(&mut self, limitation: S) {
+        self.limitations.push(limitation.into());
+    }
+
+    /// records that Purge was required but not achieved, and why
+    pub fn require_destroy<S: Into<String>>(&mut self, reason: S) {
+        self.destroy_required = true;
+        self.add_limitation(format!(
+            "NIST Purge was not achieved: {}. Only destroying the media meets a Purge requirement.",
+            reason.into()
+        ));
+    }
+}
+
+/// outcome of a wipe over several targets, which carries on past the ones
+/// that fail
+#[derive(Debug, Default)]
+pub struct MultiWipeReport {
+    /// reports of the wiped files, in the order they were wiped
+    pub wiped: Vec<WipeReport>,
+
+    /// targets that could not be wiped, with the reason; they are left in place
+    pub failed: Vec<(PathBuf, WipeError)>,
+}
+
+impl MultiWipeReport {
+    /// records the outcome of wiping one target
+    pub fn record<P: Into<PathBuf>>(&mut self, path: P, result: Result<WipeReport>) {
+        match result {
+            Ok(report) => self.wiped.push(report),
+            Err(e) => self.failed.push((path.into(), e)),
+        }
+    }
+
+    /// adds the outcome of another batch, e.g. one wiped with different settings
+    pub fn merge(&mut self, other: MultiWipeReport) {
+        self.wiped.extend(other.wiped);
+        self.failed.extend(other.failed);
+    }
+
+    /// true if no target failed
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// true if some targets were wiped and others failed
+    pub fn is_partial(&self) -> bool {
+        !self.wiped.is_empty() && !self.failed.is_empty()
+    }
+
+    /// one "path: error" line per failed target
+    pub fn error_messages(&self) -> Vec<String> {
+        self.failed
+            .iter()
+            .map(|(path, e)| format!("{}: {}", path.display(), e))
+            .collect()
+    }
+
+    /// one row per target, the wiped ones first, to print or send at the end of the batch
+    pub fn summary(&self) -> BatchSummary {
+        let wiped = self.wiped.iter().map(|report| SummaryRow {
+            path: report.path.clone(),
+            error: None,
+            bytes_written: report.bytes_written,
+            method: report.standard.clone(),
+            duration: report.duration_secs.map(Duration::from_secs_f64),
+        });
+        let failed = self.failed.iter().map(|(path, e)| SummaryRow {
+            path: path.clone(),
+            error: Some(e.to_string()),
+            bytes_written: 0,
+            method: None,
+            duration: None,
+        });
+        BatchSummary {
+            rows: wiped.chain(failed).collect(),
+        }
+    }
+}
+
+/// how each target of a batch fared, with totals
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchSummary {
+    /// one row per target
+    pub rows: Vec<SummaryRow>,
+}
+
+/// how one target of a batch fared
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryRow {
+    /// the target
+    pub path: PathBuf,
+
+    /// why the target couldn't be wiped, None if it was
+    pub error: Option<String>,
+
+    /// bytes written across all passes
+    pub bytes_written: u64,
+
+    /// standard the target was wiped with, if it was
+    pub method: Option<String>,
+
+    /// how long the wipe took, if known
+    pub duration: Option<Duration>,
+}
+
+impl SummaryRow {
+    /// "wiped" or "failed"
+    pub fn status(&self) -> &'static str {
+        if self.error.is_none() {
+            "wiped"
+        } else {
+            "failed"
+        }
+    }
+}
+
+impl BatchSummary {
+    /// number of targets wiped
+    pub fn wiped(&self) -> usize {
+        self.rows.iter().filter(|row| row.error.is_none()).count()
+    }
+
+    /// number of targets that failed
+    pub fn failed(&self) -> usize {
+        self.rows.len() - self.wiped()
+    }
+
+    /// bytes written to all targets together
+    pub fn bytes_written(&self) -> u64 {
+        self.rows.iter().map(|row| row.bytes_written).sum()
+    }
+
+    /// time spent wiping, added up over the targets; more than the batch
+    /// took when they were wiped side by side
+    pub fn duration(&self) -> Duration {
+        self.rows.iter().filter_map(|row| row.duration).sum()
+    }
+}
+
+/// layout of a saved report file: one [[wipes]] table per target
+#[derive(Serialize, Deserialize)]
+struct ReportFile {
+    #[serde(default)]
+    wipes: Vec<WipeReport>,
+}
+
+/// renders reports as TOML, the format `--report` writes
+///
+/// # Arguments
+/// * `reports` - Reports of the wiped targets
+///
+/// # Returns
+/// * `Result<String>` - TOML document with one [[wipes]] table per report
+pub fn reports_to_toml(reports: &[WipeReport]) -> Result<String> {
+    let file = ReportFile {
+        wipes: reports.to_vec(),
+    };
+    toml::to_string(&file).map_err(|e| WipeError::Report(e.to_string()))
+}
+
+/// parses reports written by `reports_to_toml`
+///
+/// # Arguments
+/// * `text` - TOML document
+///
+/// # Returns
+/// * `Result<Vec<WipeReport>>` - Reports in the order they were written
+pub fn reports_from_toml(text: &str) -> Result<Vec<WipeReport>> {
+    toml::from_str::<ReportFile>(text)
+        .map(|file| file.wipes)
+        .map_err(|e| WipeError::Report(e.to_string()))
+}
+
+/// writes reports to a file, replacing it
+///
+/// # Arguments
+/// * `path` - Destination file
+/// * `reports` - Reports of the wiped targets
+pub fn write_reports(path: &Path, reports: &[WipeReport]) -> Result<()> {
+    std::fs::write(path, reports_to_toml(reports)?)?;
+    Ok(())
+}
+
+/// renders a report as a JSON object, as sent to webhooks
+pub(crate) fn report_json(report: &WipeReport) -> String {
+    let limitations: Vec<String> = report
+        .limitations
+        .iter()
+        .map(|limitation| json_string(limitation))
+        .collect();
+    let pass_sha256: Vec<String> = report
+        .pass_sha256
+        .iter()
+        .map(|digest| json_string(digest))
+        .collect();
+    format!(
+        "{{\"path\":{},\"filesystem\":{},\"passes\":{},\"bytes_written\":{},\"sha256\":{},\"pass_sha256\":[{}],\"remnants\":{},\"limitations\":[{}],\"nist_action\":{},\"destroy_required\":{},\"quick\":{},\"standard\":{},\"duration_secs\":{},\"partition\":{},\"device\":{}}}",
+        json_string(&report.path.to_string_lossy()),
+        json_option(report.filesystem.as_deref()),
+        report.passes,
+        report.bytes_written,
+        json_option(report.sha256.as_deref()),
+        pass_sha256.join(","),
+        report
+            .remnants
+            .map_or_else(|| "null".to_string(), |n| n.to_string()),
+        limitations.join(","),
+        json_option(report.nist_action.map(|a| a.to_string()).as_deref()),
+        report.destroy_required,
+        report.quick,
+        json_option(report.standard.as_deref()),
+        json_seconds(report.duration_secs),
+        partition_json(report.partition.as_ref()),
+        device_json(report.device.as_ref()),
+    )
+}
+
+/// renders a batch summary as a JSON object, as sent to webhooks
+pub(crate) fn summary_json(summary: &BatchSummary) -> String {
+    let rows: Vec<String> = summary
+        .rows
+        .iter()
+        .map(|row| {
+            format!(
+                "{{\"path\":{},\"status\":\"{}\",\"bytes_written\":{},\"method\":{},\"duration_secs\":{},\"error\":{}}}",
+                json_string(&row.path.to_string_lossy()),
+                row.status(),
+                row.bytes_written,
+                json_option(row.method.as_deref()),
+                json_seconds(row.duration.map(|d| d.as_secs_f64())),
+                json_option(row.error.as_deref()),
+            )
+        })
+        .collect();
+    format!(
+        "{{\"targets\":[{}],\"wiped\":{},\"failed\":{},\"bytes_written\":{},\"duration_secs\":{:.3}}}",
+        rows.join(","),
+        summary.wiped(),
+        summary.failed(),
+        summary.bytes_written(),
+        summary.duration().as_secs_f64(),
+    )
+}
+
+/// seconds with millisecond precision, or null
+fn json_seconds(seconds: Option<f64>) -> String {
+    seconds.map_or_else(|| "null".to_string(), |s| format!("{:.3}", s))
+}
+
+/// renders a partition as a JSON object, or null
+pub(crate) fn partition_json(partition: Option<&crate::partitions::Partition>) -> String {
+    partition.map_or_else(
+        || "null".to_string(),
+        |partition| {
+            format!(
+                "{{\"number\":{},\"start\":{},\"length\":{}}}",
+                partition.number, partition.start, partition.length
+            )
+        },
+    )
+}
+
+/// renders a device identity as a JSON object, or null
+pub(crate) fn device_json(device: Option<&DeviceIdentity>) -> String {
+    let Some(device) = device else {
+        return "null".to_string();
+    };
+    format!(
+        "{{\"device\":{},\"model\":{},\"serial\":{},\"wwn\":{},\"firmware\":{},\"capacity\":{}}}",
+        json_option(
+            device
+                .device
+                .as_ref()
+                .map(|path| path.to_string_lossy())
+                .as_deref()
+        ),
+        json_option(device.model.as_deref()),
+        json_option(device.serial.as_deref()),
+        json_option(device.wwn.as_deref()),
+        json_option(device.firmware.as_deref()),
+        device
+            .capacity
+            .map_or_else(|| "null".to_string(), |n| n.to_string()),
+    )
+}
+
+/// quotes and escapes a string for JSON output
+pub(crate) fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// a JSON string, or null
+pub(crate) fn json_option(value: Option<&str>) -> String {
+    value.map_or_else(|| "null".to_string(), json_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_round_trip() {
+        let mut report = WipeReport::new(PathBuf::from("/srv/old.db"));
+        report.filesystem = Some("ext4".into());
+        report.record_pass(4096);
+        report.record_pass(4096);
+        report.sha256 =
+            Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".into());
+        report.add_limitation("Data may survive in snapshots");
+        report.nist_action = Some(NistAction::Clear);
+        report.require_destroy("the sanitize command failed");
+        report.device = Some(DeviceIdentity {
+            device: Some(PathBuf::from("/dev/sdb")),
+            serial: Some("S3Z1NB0K123456A".into()),
+            wwn: Some("naa.5002538e40a1b2c3".into()),
+            capacity: Some(500107862016),
+            ..Default::default()
+        });
+        let reports = vec![report, WipeReport::new(PathBuf::from("/srv/empty"))];
+
+        let text = reports_to_toml(&reports).unwrap();
+        assert_eq!(reports_from_toml(&text).unwrap(), reports);
+
+        let json = report_json(&reports[0]);
+        assert!(json.contains("\"nist_action\":\"Clear\",\"destroy_required\":true"));
+        assert!(json.contains("\"serial\":\"S3Z1NB0K123456A\",\"wwn\":\"naa.5002538e40a1b2c3\",\"firmware\":null,\"capacity\":500107862016}"));
+        assert!(report_json(&reports[1]).ends_with("\"device\":null}"));
+    }
+
+    #[test]
+    fn test_multi_wipe_report() {
+        let mut outcome = MultiWipeReport::default();
+        outcome.record("/srv/a", Ok(WipeReport::new(PathBuf::from("/srv/a"))));
+        assert!(outcome.is_success());
+
+        let mut other = MultiWipeReport::default();
+        other.record(
+            "/srv/b",
+            Err(WipeError::FileInUse(vec!["postgres (1234)".into()])),
+        );
+        outcome.merge(other);
+        assert!(!outcome.is_success());
+        assert!(outcome.is_partial());
+        assert_eq!(
+            outcome.error_messages(),
+            ["/srv/b: Target is open in other processes: postgres (1234)"]
+        );
+    }
+
+    #[test]
+    fn test_batch_summary() {
+        let mut outcome = MultiWipeReport::default();
+        outcome.record(
+            "/srv/b",
+            Err(WipeError::FileInUse(vec!["postgres (1234)".into()])),
+        );
+        for (path, seconds) in [("/srv/a", 1.5), ("/srv/c", 2.0)] {
+            let mut report = WipeReport::new(PathBuf::from(path));
+            report.record_pass(4096);
+            report.standard = Some("nist".into());
+            report.duration_secs = Some(seconds);
+            outcome.record(path, Ok(report));
+        }
+
+        let summary = outcome.summary();
+        let statuses: Vec<_> = summary.rows.iter().map(|row| row.status()).collect();
+        assert_eq!(statuses, ["wiped", "wiped", "failed"]);
+        assert_eq!((summary.wiped(), summary.failed()), (2, 1));
+        assert_eq!(summary.bytes_written(), 8192);
+        assert_eq!(summary.duration(), Duration::from_millis(3500));
+
+        let json = summary_json(&summary);
+        assert!(json.contains("{\"path\":\"/srv/a\",\"status\":\"wiped\",\"bytes_written\":4096,\"method\":\"nist\",\"duration_secs\":1.500,\"error\":null}"), "{json}");
+        assert!(json.contains("\"status\":\"failed\",\"bytes_written\":0,\"method\":null,\"duration_secs\":null,\"error\":\"Target is open"), "{json}");
+        assert!(
+            json.ends_with(
+                "\"wiped\":2,\"failed\":1,\"bytes_written\":8192,\"duration_secs\":3.500}"
+            ),
+            "{json}"
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! confining the thread that overwrites a target once the target is open, so
+//! a wipe driven by untrusted input can do little besides overwriting it
+//!
+//! on linux the thread drops every capability but those the rest of the wipe
+//! uses, can no longer gain privileges through setuid programs, and gets a
+//! seccomp filter that only allows the system calls a wipe makes: reading,
+//! writing and syncing files, the few ioctls it issues, memory, threads and
+//! time. anything else fails with EPERM, programs can't be started, sockets
+//! can't be opened and io_uring, which would bypass the filter, can't be set
+//! up. both are per thread, which is why a sandboxed wipe runs on a thread of
+//! its own and the caller keeps its rights afterwards; the other threads still
+//! share its memory, so this contains what the wipe itself can be made to do
+//! rather than code that takes over the whole process
+//!
+//! on macOS sandbox_init only confines a whole process, for good: the first
+//! sandboxed wipe takes away networking and starting programs from the
+//! process. other platforms are not sandboxed
+
+use crate::Result;
+
+/// what the wipe still does once the thread is confined, beyond writing and
+/// reading back the open target
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) struct Needs {
+    /// the target is a file that is unlinked once it is overwritten
+    pub remove: bool,
+
+    /// TRIM is issued from the wiping thread rather than through the helper
+    pub trim: bool,
+}
+
+/// capabilities every confined wipe keeps: reading the target again for
+/// verification
+#[cfg(target_os = "linux")]
+const KEPT_CAPABILITIES: &[u32] = &[
+    2, // CAP_DAC_READ_SEARCH
+];
+
+/// kept to unlink a wiped file from a directory of another user
+#[cfg(target_os = "linux")]
+const REMOVE_CAPABILITIES: &[u32] = &[
+    1, // CAP_DAC_OVERRIDE
+    3, // CAP_FOWNER
+];
+
+/// kept for FITRIM
+#[cfg(target_os = "linux")]
+const TRIM_CAPABILITIES: &[u32] = &[
+    21, // CAP_SYS_ADMIN
+];
+
+/// system calls a wipe makes once the target is open; ioctl is allowed for
+/// `ALLOWED_IOCTLS` only
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+const ALLOWED_SYSCALLS: &[libc::c_long] = &[
+    // the target, verification handles, checkpoints, logging and progress
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_readv,
+    libc::SYS_writev,
+    libc::SYS_pread64,
+    libc::SYS_pwrite64,
+    libc::SYS_preadv,
+    libc::SYS_pwritev,
+    libc::SYS_lseek,
+    libc::SYS_close,
+    libc::SYS_fsync,
+    libc::SYS_fdatasync,
+    libc::SYS_sync_file_range,
+    libc::SYS_fallocate,
+    libc::SYS_ftruncate,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_fadvise64,
+    #[cfg(target_arch = "aarch64")]
+    223, // fadvise64_64, which libc doesn't name there
+    libc::SYS_fcntl,
+    libc::SYS_openat,
+    libc::SYS_fstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_fstatfs,
+    libc::SYS_statfs,
+    libc::SYS_getdents64,
+    libc::SYS_readlinkat,
+    libc::SYS_faccessat,
+    libc::SYS_faccessat2,
+    libc::SYS_getcwd,
+    libc::SYS_renameat,
+    libc::SYS_renameat2,
+    libc::SYS_unlinkat,
+    // memory
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_mprotect,
+    libc::SYS_madvise,
+    libc::SYS_msync,
+    libc::SYS_brk,
+    // threads of parallel writes, and the thread's own end
+    libc::SYS_clone,
+    libc::SYS_clone3,
+    libc::SYS_futex,
+    libc::SYS_set_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_sched_yield,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_sigaltstack,
+    libc::SYS_restart_syscall,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    // time, randomness and who we are
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_getres,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    libc::SYS_getrandom,
+    libc::SYS_sysinfo,
+    libc::SYS_getpid,
+    libc::SYS_gettid,
+    libc::SYS_getuid,
+    libc::SYS_geteuid,
+    libc::SYS_getgid,
+    libc::SYS_getegid,
+    // older C libraries still use the original calls
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_stat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_lstat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_readlink,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_access,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_rename,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_unlink,
+];
+
+/// ioctls a wipe issues: sector sizes of devices, inode flags,
+/// FITRIM, and the terminal queries of logging and progress
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+const ALLOWED_IOCTLS: &[u32] = &[
+    libc::BLKSSZGET as u32,
+    libc::BLKPBSZGET as u32,
+    libc::FS_IOC_GETFLAGS as u32,
+    libc::FS_IOC_SETFLAGS as u32,
+    crate::trim::FITRIM as u32,
+    libc::TCGETS as u32,
+    libc::TIOCGWINSZ as u32,
+];
+
+/// AUDIT_ARCH_* of the system calls the filter is written for
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// confines the calling thread and the threads it starts
+///
+/// # Arguments
+/// * `needs` - What the wipe does after this, deciding the capabilities kept
+///
+/// # Returns
+/// * `Result<()>` - An error if the thread could not be confined, in which
+///   case nothing should be written
+#[cfg(target_os = "linux")]
+pub(crate) fn confine_thread(needs: Needs) -> Result<()> {
+    let mut kept = KEPT_CAPABILITIES.to_vec();
+    if needs.remove {
+        kept.extend(REMOVE_CAPABILITIES);
+    }
+    if needs.trim {
+        kept.extend(TRIM_CAPABILITIES);
+    }
+    drop_capabilities(&kept)?;
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    install_filter()?;
+    log::debug!("Wiping thread sandboxed");
+    Ok(())
+}
+
+/// confines the whole process with sandbox_init, once: no networking and no
+/// new programs; files can still be written, for the reports and audit log
+#[cfg(target_os = "macos")]
+pub(crate) fn confine_thread(_needs: Needs) -> Result<()> {
+    use std::ffi::{c_char, CStr};
+    use std::sync::OnceLock;
+
+    extern "C" {
+        fn sandbox_init(profile: *const c_char, flags: u64, errorbuf: *mut *mut c_char) -> i32;
+        fn sandbox_free_error(errorbuf: *mut c_char);
+    }
+
+    const PROFILE: &CStr = c"(version 1)
+        (allow default)
+        (deny network-outbound (remote ip))
+        (deny network-inbound)
+        (deny network-bind)
+        (deny process-fork)
+        (deny process-exec*)";
+
+    static CONFINED: OnceLock<std::result::Result<(), String>> = OnceLock::new();
+    let confined = CONFINED.get_or_init(|| {
+        let mut error: *mut c_char = std::ptr::null_mut();
+        if unsafe { sandbox_init(PROFILE.as_ptr(), 0, &mut error) } == 0 {
+            log::debug!("Process sandboxed");
+            return Ok(());
+        }
+        let message = if error.is_null() {
+            "unknown error".to_string()
+        } else {
+            let message = unsafe { CStr::from_ptr(error) }
+                .to_string_lossy()
+                .into_owned();
+            unsafe { sandbox_free_error(error) };
+            message
+        };
+        Err(message)
+    });
+    confined.clone().map_err(|message| {
+        crate::WipeError::UnsupportedOperation(format!("sandbox_init: {}", message))
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn confine_thread(_needs: Needs) -> Result<()> {
+    log::warn!("Sandboxing is only available on linux and macOS; wiping without it");
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn drop_capabilities(kept: &[u32]) -> Result<()> {
+    #[repr(C)]
+    struct Header {
+        version: u32,
+        pid: libc::c_int,
+    }
+    #[repr(C)]
+    #[derive(Default, Clone, Copy)]
+    struct Data {
+        effective: u32,
+        permitted: u32,
+        inheritable: u32,
+    }
+    const VERSION_3: u32 = 0x2008_0522;
+
+    // out of the bounding set first, so programs started as root don't get
+    // them back. that needs CAP_SETPCAP, which unprivileged threads lack and
+    // don't need, since they can't gain capabilities anyway
+    for cap in (0..64u32).filter(|cap| !kept.contains(cap)) {
+        if unsafe { libc::prctl(libc::PR_CAPBSET_DROP, cap as libc::c_ulong, 0, 0, 0) } != 0 {
+            let error = std::io::Error::last_os_error();
+            match error.raw_os_error() {
+                // past the last capability the kernel knows
+                Some(libc::EINVAL) => break,
+                Some(libc::EPERM) => continue,
+                _ => return Err(error.into()),
+            }
+        }
+    }
+
+    let mut header = Header {
+        version: VERSION_3,
+        pid: 0,
+    };
+    let mut data = [Data::default(); 2];
+    if unsafe { libc::syscall(libc::SYS_capget, &mut header, data.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let mask = kept.iter().fold([0u32; 2], |mut mask, &cap| {
+        mask[(cap / 32) as usize] |= 1 << (cap % 32);
+        mask
+    });
+    for (data, mask) in data.iter_mut().zip(mask) {
+        data.effective &= mask;
+        data.permitted &= mask;
+        data.inheritable = 0;
+    }
+    if unsafe { libc::syscall(libc::SYS_capset, &mut header, data.as_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn install_filter() -> Result<()> {
+    let program = filter();
+    let program = libc::sock_fprog {
+        len: program.len() as libc::c_ushort,
+        filter: program.as_ptr() as *mut libc::sock_filter,
+    };
+    // without SECCOMP_FILTER_FLAG_TSYNC, only this thread and those it starts
+    let result = unsafe {
+        libc::prctl(
+            libc::PR_SET_SECCOMP,
+            libc::SECCOMP_MODE_FILTER as libc::c_ulong,
+            &program as *const libc::sock_fprog,
+        )
+    };
+    if result != 0 {
+        return Err(crate::WipeError::UnsupportedOperation(format!(
+            "seccomp filter: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+/// without a list of system calls for the architecture, a wipe is refused
+/// rather than run with only its capabilities dropped
+#[cfg(all(
+    target_os = "linux",
+    not(any(target_arch = "x86_64", target_arch = "aarch64"))
+))]
+fn install_filter() -> Result<()> {
+    Err(crate::WipeError::UnsupportedOperation(
+        "No seccomp filter for this architecture".into(),
+    ))
+}
+
+/// the BPF program: the allowed system calls, and ioctl with an allowed
+/// request, are let through; everything else, and every system call of
+/// another architecture, fails with EPERM
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn filter() -> Vec<libc::sock_filter> {
+    const LOAD: u16 = 0x20; // BPF_LD | BPF_W | BPF_ABS
+    const JEQ: u16 = 0x15; // BPF_JMP | BPF_JEQ | BPF_K
+    const RET: u16 = 0x06; // BPF_RET | BPF_K
+    const NR: u32 = 0; // offsetof(struct seccomp_data, nr)
+    const ARCH: u32 = 4; // offsetof(struct seccomp_data, arch)
+    const ARG1: u32 = 24; // low half of args[1], little-endian; ioctl's cmd is 32 bits
+    let op = |code, jt, jf, k| libc::sock_filter { code, jt, jf, k };
+    let deny = op(RET, 0, 0, libc::SECCOMP_RET_ERRNO | libc::EPERM as u32);
+    let allow = op(RET, 0, 0, libc::SECCOMP_RET_ALLOW);
+
+    let mut program = vec![
+        op(LOAD, 0, 0, ARCH),
+        op(JEQ, 1, 0, AUDIT_ARCH),
+        deny,
+        op(LOAD, 0, 0, NR),
+    ];
+    // x32 system calls are x86_64 ones with bit 30 set
+    #[cfg(target_arch = "x86_64")]
+    {
+        const JGE: u16 = 0x35; // BPF_JMP | BPF_JGE | BPF_K
+        program.extend([op(JGE, 0, 1, 0x4000_0000), deny]);
+    }
+
+    // ioctl: past its requests, the deny and the allow when it isn't one
+    let requests = ALLOWED_IOCTLS.len();
+    program.push(op(JEQ, 0, (requests + 3) as u8, libc::SYS_ioctl as u32));
+    program.push(op(LOAD, 0, 0, ARG1));
+    for (i, &request) in ALLOWED_IOCTLS.iter().enumerate() {
+        program.push(op(JEQ, (requests - i) as u8, 0, request));
+    }
+    program.extend([deny, allow]);
+
+    let count = ALLOWED_SYSCALLS.len();
+    for (i, &nr) in ALLOWED_SYSCALLS.iter().enumerate() {
+        // past the remaining checks and the deny, to the allow at the end
+        program.push(op(JEQ, (count - i) as u8, 0, nr as u32));
+    }
+    program.extend([deny, allow]);
+    program
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confine_thread() {
+        let outside = std::thread::spawn(|| {
+            confine_thread(Needs::default()).unwrap();
+            // files can still be written and read back, and threads started
+            let mut file = tempfile::tempfile().unwrap();
+            std::io::Write::write_all(&mut file, b"data").unwrap();
+            file.sync_all().unwrap();
+            let mut read = [0u8; 4];
+            std::os::unix::fs::FileExt::read_exact_at(&file, &mut read, 0).unwrap();
+            assert_eq!(&read, b"data");
+            std::thread::spawn(|| ()).join().unwrap();
+
+            // everything else is refused
+            let errno = || std::io::Error::last_os_error().raw_os_error();
+            let socket = std::net::UdpSocket::bind("127.0.0.1:0");
+            let program = std::process::Command::new("true").status();
+            let mount = unsafe {
+                libc::mount(
+                    c"none".as_ptr(),
+                    c"/mnt".as_ptr(),
+                    c"tmpfs".as_ptr(),
+                    0,
+                    std::ptr::null(),
+                )
+            };
+            let mount = (mount, errno());
+            let mut params = [0u8; 120];
+            let uring = unsafe { libc::syscall(libc::SYS_io_uring_setup, 1, params.as_mut_ptr()) };
+            let uring = (uring, errno());
+            // TIOCSTI would push input into the terminal
+            let byte = 0u8;
+            let inject = unsafe { libc::ioctl(0, libc::TIOCSTI, &byte) };
+            let inject = (inject, errno());
+            (
+                socket.map_err(|e| e.raw_os_error()).err(),
+                program.is_err(),
+                mount,
+                uring,
+                inject,
+            )
+        })
+        .join()
+        .unwrap();
+        assert_eq!(
+            outside,
+            (
+                Some(Some(libc::EPERM)),
+                true,
+                (-1, Some(libc::EPERM)),
+                (-1, Some(libc::EPERM)),
+                (-1, Some(libc::EPERM)),
+            )
+        );
+
+        // other threads are untouched
+        assert!(std::net::UdpSocket::bind("127.0.0.1:0").is_ok());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{Result, WipeError};
+use serde::{Deserialize, Serialize};
+
+/// a local date and time broken down the way cron looks at it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalendarTime {
+    /// full year, e.g. 2025
+    pub year: i32,
+    /// 1-12
+    pub month: u32,
+    /// day of the month, 1-31
+    pub day: u32,
+    /// 0-23
+    pub hour: u32,
+    /// 0-59
+    pub minute: u32,
+    /// 0 (Sunday) to 6 (Saturday)
+    pub weekday: u32,
+}
+
+impl CalendarTime {
+    /// the current local time
+    pub fn now() -> Self {
+        #[cfg(unix)]
+        {
+            let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+            unsafe {
+                let now = libc::time(std::ptr::null_mut());
+                libc::localtime_r(&now, &mut tm);
+            }
+            Self {
+                year: tm.tm_year + 1900,
+                month: tm.tm_mon as u32 + 1,
+                day: tm.tm_mday as u32,
+                hour: tm.tm_hour as u32,
+                minute: tm.tm_min as u32,
+                weekday: tm.tm_wday as u32,
+            }
+        }
+        #[cfg(windows)]
+        {
+            use winapi::um::minwinbase::SYSTEMTIME;
+            use winapi::um::sysinfoapi::GetLocalTime;
+
+            let mut time: SYSTEMTIME = unsafe { std::mem::zeroed() };
+            unsafe { GetLocalTime(&mut time) };
+            Self {
+                year: time.wYear as i32,
+                month: time.wMonth as u32,
+                day: time.wDay as u32,
+                hour: time.wHour as u32,
+                minute: time.wMinute as u32,
+                weekday: time.wDayOfWeek as u32,
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for CalendarTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute
+        )
+    }
+}
+
+/// a cron-style schedule: minute, hour, day of month, month and day of week
+///
+/// each field takes `*`, a value, a range `a-b`, a step `*/n` or `a-b/n`, or a
+/// comma-separated list of those. months and weekdays also accept three-letter
+/// names (jan, mon) and weekday 7 is Sunday like 0. the shortcuts @hourly,
+/// @daily, @weekly, @monthly and @yearly are understood as well
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // like cron, a restricted day of month and day of week match if either does
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl Schedule {
+    /// parses a cron expression
+    ///
+    /// # Arguments
+    /// * `expression` - Five fields (`0 3 * * *`) or a shortcut (`@daily`)
+    ///
+    /// # Returns
+    /// * `Result<Schedule>` - Parsed schedule, or a config error naming the bad field
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(WipeError::Config(format!(
+                "Schedule '{}' needs 5 fields (minute hour day month weekday)",
+                expression
+            )));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7, &WEEKDAY_NAMES, 0)?;
+        // 7 is another name for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, &[], 0)?,
+            hours: parse_field(fields[1], 0, 23, &[], 0)?,
+            days: parse_field(fields[2], 1, 31, &[], 0)?,
+            months: parse_field(fields[3], 1, 12, &MONTH_NAMES, 1)?,
+            weekdays,
+            days_restricted: !fields[2].starts_with('*'),
+            weekdays_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    /// the schedule as systemd OnCalendar= expressions
+    ///
+    /// systemd requires the day of month and the day of week to both match, so a
+    /// schedule restricting both (which cron fires on either) becomes two expressions
+    ///
+    /// # Returns
+    /// * `Vec<String>` - Expressions for a timer unit, e.g. ["Mon..Fri *-*-* 09:00:00"]
+    pub fn to_on_calendar(&self) -> Vec<String> {
+        let time = format!(
+            "{}:{}:00",
+            calendar_values(self.hours, 0, 23, None),
+            calendar_values(self.minutes, 0, 59, None)
+        );
+        let months = calendar_values(self.months, 1, 12, None);
+        let days = calendar_values(self.days, 1, 31, None);
+        let weekdays = calendar_values(self.weekdays, 0, 6, Some(&CALENDAR_WEEKDAYS));
+
+        let expression = |weekdays: &str, days: &str| match weekdays {
+            "*" => format!("*-{}-{} {}", months, days, time),
+            _ => format!("{} *-{}-{} {}", weekdays, months, days, time),
+        };
+        if self.days_restricted && self.weekdays_restricted {
+            vec![expression("*", &days), expression(&weekdays, "*")]
+        } else {
+            vec![expression(&weekdays, &days)]
+        }
+    }
+
+    /// whether the schedule fires in the given minute
+    pub fn matches(&self, time: &CalendarTime) -> bool {
+        let bit = |set: u64, value: u32| set & (1 << value) != 0;
+
+        let day_matches = match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => bit(self.days, time.day) || bit(self.weekdays, time.weekday),
+            _ => bit(self.days, time.day) && bit(self.weekdays, time.weekday),
+        };
+        bit(self.minutes, time.minute)
+            && bit(self.hours, time.hour)
+            && bit(self.months, time.month)
+            && day_matches
+    }
+}
+
+const CALENDAR_WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// renders a bitset as a systemd calendar component: `*`, or values and `a..b` ranges
+fn calendar_values(set: u64, min: u32, max: u32, names: Option<&[&str; 7]>) -> String {
+    let all = (min..=max).all(|v| set & (1 << v) != 0);
+    if all {
+        return "*".to_string();
+    }
+    let name = |v: u32| match names {
+        Some(names) => names[v as usize].to_string(),
+        None => format!("{:02}", v),
+    };
+
+    let mut parts = Vec::new();
+    let mut v = min;
+    while v <= max {
+        if set & (1 << v) == 0 {
+            v += 1;
+            continue;
+        }
+        let start = v;
+        while v < max && set & (1 << (v + 1)) != 0 {
+            v += 1;
+        }
+        parts.push(match v - start {
+            0 => name(start),
+            1 => format!("{},{}", name(start), name(v)),
+            _ => format!("{}..{}", name(start), name(v)),
+        });
+        v += 1;
+    }
+    parts.join(",")
+}
+
+/// parses one field into a bitset of the values it selects
+///
+/// `names` are accepted in place of numbers, the first one standing for `first_name`
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], first_name: u32) -> Result<u64> {
+    let invalid = || WipeError::Config(format!("Invalid schedule field '{}'", field));
+    let value = |text: &str| -> Result<u32> {
+        let lower = text.to_lowercase();
+        if let Some(index) = names.iter().position(|name| *name == lower) {
+            return Ok(index as u32 + first_name);
+        }
+        let value: u32 = text.parse().map_err(|_| invalid())?;
+        if value < min || value > max {
+            return Err(invalid());
+        }
+        Ok(value)
+    };
+
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // a/n runs from a to the end of the range, like cron
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let single = value(range)?;
+                    (single, single)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for v in (start..=end).step_by(step as usize) {
+            set |= 1 << v;
+        }
+    }
+
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(month: u32, day: u32, weekday: u32, hour: u32, minute: u32) -> CalendarTime {
+        CalendarTime {
+            year: 2025,
+            month,
+            day,
+            hour,
+            minute,
+            weekday,
+        }
+    }
+
+    #[test]
+    fn test_nightly_schedule() {
+        let schedule = Schedule::parse("0 3 * * *").unwrap();
+        assert!(schedule.matches(&at(5, 14, 3, 3, 0)));
+        assert!(!schedule.matches(&at(5, 14, 3, 3, 1)));
+        assert!(!schedule.matches(&at(5, 14, 3, 4, 0)));
+    }
+
+    #[test]
+    fn test_steps_ranges_and_names() {
+        let schedule = Schedule::parse("*/15 9-17 * * mon-fri").unwrap();
+        assert!(schedule.matches(&at(1, 6, 1, 9, 45)));
+        assert!(!schedule.matches(&at(1, 6, 1, 9, 50)));
+        assert!(!schedule.matches(&at(1, 5, 0, 10, 0)));
+
+        let weekly = Schedule::parse("@weekly").unwrap();
+        assert_eq!(weekly, Schedule::parse("0 0 * * 7").unwrap());
+        assert!(weekly.matches(&at(1, 5, 0, 0, 0)));
+    }
+
+    #[test]
+    fn test_day_of_month_or_weekday() {
+        // the 1st of the month or any Sunday
+        let schedule = Schedule::parse("30 2 1 * sun").unwrap();
+        assert!(schedule.matches(&at(3, 1, 6, 2, 30)));
+        assert!(schedule.matches(&at(3, 9, 0, 2, 30)));
+        assert!(!schedule.matches(&at(3, 10, 1, 2, 30)));
+    }
+
+    #[test]
+    fn test_on_calendar() {
+        let calendar = |expression: &str| Schedule::parse(expression).unwrap().to_on_calendar();
+        assert_eq!(calendar("0 3 * * *"), ["*-*-* 03:00:00"]);
+        assert_eq!(
+            calendar("*/15 9-17 * * mon-fri"),
+            ["Mon..Fri *-*-* 09..17:00,15,30,45:00"]
+        );
+        assert_eq!(calendar("30 2 1 jan,jul *"), ["*-01,07-01 02:30:00"]);
+        assert_eq!(
+            calendar("30 2 1 * sun"),
+            ["*-*-01 02:30:00", "Sun *-*-* 02:30:00"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        for expression in [
+            "",
+            "0 3 * *",
+            "60 * * * *",
+            "0 0 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(
+                matches!(Schedule::parse(expression), Err(WipeError::Config(_))),
+                "{expression}"
+            );
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::Result;
 use std::path::Path;
 
 #[cfg(target_os = "linux")]
-pub fn perform_secure_erase(path: &Path) -> Result<()> {
+pub fn perform_secure_erase(path: &Path, allow_system_disk: bool) -> Result<()> {
     use std::process::Command;
 
     // Check for root privileges
@@ -14,7 +14,7 @@ pub fn perform_secure_erase(path: &Path) -> Result<()> {
 
     // Check if it's a system disk
     let is_system = is_linux_system_disk(path)?;
-    if is_system {
+    if is_system && !allow_system_disk {
         return Err(crate::WipeError::UnsupportedOperation(
             "Cannot securely erase the system disk while system is running".into(),
         ));
@@ -110,7 +110,7 @@ fn get_linux_device_info(path: &Path) -> Result<String> {
 }
 
 #[cfg(target_os = "macos")]
-pub fn perform_secure_erase(path: &Path) -> Result<()> {
+pub fn perform_secure_erase(path: &Path, allow_system_disk: bool) -> Result<()> {
     use std::process::Command;
 
     // Check for root privileges
@@ -125,7 +125,7 @@ pub fn perform_secure_erase(path: &Path) -> Result<()> {
     log::info!("Detected device: {}", device_info);
 
     // Check if it's a system disk
-    if is_macos_system_disk(path)? {
+    if !allow_system_disk && is_macos_system_disk(path)? {
         return Err(crate::WipeError::UnsupportedOperation(
             "Cannot securely erase the system disk while system is running".into(),
         ));
@@ -210,19 +210,41 @@ fn get_macos_device_info(path: &Path) -> Result<String> {
         .to_string())
 }
 
+/// IOCTL_STORAGE_PROTOCOL_COMMAND, which winapi leaves out
 #[cfg(target_os = "windows")]
-pub fn perform_secure_erase(path: &Path) -> Result<()> {
+const IOCTL_STORAGE_PROTOCOL_COMMAND: u32 = 0x002d_d3c0;
+
+/// STORAGE_DEVICE_DESCRIPTOR, which winapi leaves out
+#[cfg(target_os = "windows")]
+#[repr(C)]
+#[allow(non_snake_case)]
+struct STORAGE_DEVICE_DESCRIPTOR {
+    Version: u32,
+    Size: u32,
+    DeviceType: u8,
+    DeviceTypeModifier: u8,
+    RemovableMedia: u8,
+    CommandQueueing: u8,
+    VendorIdOffset: u32,
+    ProductIdOffset: u32,
+    ProductRevisionOffset: u32,
+    SerialNumberOffset: u32,
+    BusType: u32,
+    RawPropertiesLength: u32,
+    RawDeviceProperties: [u8; 1],
+}
+
+#[cfg(target_os = "windows")]
+pub fn perform_secure_erase(path: &Path, allow_system_disk: bool) -> Result<()> {
     use std::os::windows::prelude::*;
     use std::ptr;
-    use winapi::shared::minwindef::DWORD;
-    use winapi::um::fileapi::{CreateFileW, GetVolumeInformationW, OPEN_EXISTING};
+    use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
     use winapi::um::handleapi::INVALID_HANDLE_VALUE;
-    use winapi::um::winioctl::*;
     use winapi::um::winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE};
 
     // Safety check: Prevent erasing system drive
     let root_path = get_volume_root(path)?;
-    if is_system_drive(&root_path) {
+    if !allow_system_disk && is_system_drive(&root_path) {
         return Err(crate::WipeError::UnsupportedOperation(
             "Cannot securely erase the system drive while Windows is running".into(),
         ));
@@ -284,8 +306,10 @@ pub fn perform_secure_erase(path: &Path) -> Result<()> {
 /// Attempts ATA secure erase command - most effective for traditional HDDs
 #[cfg(target_os = "windows")]
 fn try_ata_secure_erase(handle: winapi::um::winnt::HANDLE) -> Result<()> {
+    use std::ptr;
     use winapi::shared::minwindef::DWORD;
-    use winapi::um::winioctl::*;
+    use winapi::shared::ntddscsi::IOCTL_ATA_PASS_THROUGH;
+    use winapi::um::ioapiset::DeviceIoControl;
 
     // ATA Secure Erase command structure
     #[repr(C, packed)]
@@ -346,8 +370,9 @@ fn try_ata_secure_erase(handle: winapi::um::winnt::HANDLE) -> Result<()> {
 /// Attempts NVMe sanitize command - most effective for NVMe SSDs
 #[cfg(target_os = "windows")]
 fn try_nvme_sanitize(handle: winapi::um::winnt::HANDLE) -> Result<()> {
+    use std::ptr;
     use winapi::shared::minwindef::DWORD;
-    use winapi::um::winioctl::*;
+    use winapi::um::ioapiset::DeviceIoControl;
 
     // NVMe Sanitize command structure
     #[repr(C, packed)]
@@ -414,8 +439,9 @@ fn try_nvme_sanitize(handle: winapi::um::winnt::HANDLE) -> Result<()> {
 /// Fallback method: Block-by-block overwrite
 #[cfg(target_os = "windows")]
 fn perform_block_erase(handle: winapi::um::winnt::HANDLE) -> Result<()> {
+    use std::ptr;
     use winapi::shared::minwindef::DWORD;
-    use winapi::um::fileapi::DeviceIoControl;
+    use winapi::um::ioapiset::DeviceIoControl;
     use winapi::um::winioctl::*;
 
     log::warn!("Using fallback block erase method - this is slower and may not be as secure as hardware-based methods");
@@ -450,7 +476,7 @@ fn perform_block_erase(handle: winapi::um::winnt::HANDLE) -> Result<()> {
     }
 
     // Calculate total disk size
-    let disk_size = disk_geometry.Cylinders.QuadPart
+    let disk_size = unsafe { *disk_geometry.Cylinders.QuadPart() }
         * (disk_geometry.TracksPerCylinder
             * disk_geometry.SectorsPerTrack
             * disk_geometry.BytesPerSector) as i64;
@@ -510,26 +536,11 @@ fn is_system_drive(path: &Path) -> bool {
 
 #[cfg(target_os = "windows")]
 fn has_admin_privileges() -> bool {
-    use std::mem;
-    use winapi::um::securitybaseapi::*;
-    use winapi::um::winnt::TOKEN_ELEVATION;
-
-    unsafe {
-        let mut token_elevation: TOKEN_ELEVATION = mem::zeroed();
-        let mut size = mem::size_of::<TOKEN_ELEVATION>() as u32;
-        let mut elevated = false;
-
-        let success = IsUserAnAdmin();
-        if success != 0 {
-            elevated = true;
-        }
-
-        elevated
-    }
+    crate::elevate::is_elevated()
 }
 
 #[cfg(target_os = "windows")]
-fn get_volume_root(path: &Path) -> Result<PathBuf> {
+fn get_volume_root(path: &Path) -> Result<std::path::PathBuf> {
     let path_str = path
         .to_str()
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid path"))?;
@@ -548,7 +559,7 @@ fn get_volume_root(path: &Path) -> Result<PathBuf> {
             .to_path_buf()
     } else {
         // Handle regular paths
-        PathBuf::from(&path_str[..3]) // Drive letter + ":\"
+        std::path::PathBuf::from(&path_str[..3]) // Drive letter + ":\"
     };
 
     Ok(root)
@@ -558,7 +569,8 @@ fn get_volume_root(path: &Path) -> Result<PathBuf> {
 fn check_ata_security_support(handle: winapi::um::winnt::HANDLE) -> Result<bool> {
     use std::ptr;
     use winapi::shared::minwindef::DWORD;
-    use winapi::um::winioctl::*;
+    use winapi::shared::ntddscsi::IOCTL_ATA_PASS_THROUGH;
+    use winapi::um::ioapiset::DeviceIoControl;
 
     #[repr(C, packed)]
     struct ATAIdentifyDevice {
@@ -595,7 +607,7 @@ fn check_ata_security_support(handle: winapi::um::winnt::HANDLE) -> Result<bool>
 fn check_nvme_sanitize_support(handle: winapi::um::winnt::HANDLE) -> Result<bool> {
     use std::ptr;
     use winapi::shared::minwindef::DWORD;
-    use winapi::um::winioctl::*;
+    use winapi::um::ioapiset::DeviceIoControl;
 
     #[repr(C, packed)]
     struct NVMeIdentifyController {
@@ -632,7 +644,7 @@ fn check_nvme_sanitize_support(handle: winapi::um::winnt::HANDLE) -> Result<bool
 fn monitor_nvme_sanitize_progress(handle: winapi::um::winnt::HANDLE) -> Result<()> {
     use std::{ptr, thread, time};
     use winapi::shared::minwindef::DWORD;
-    use winapi::um::winioctl::*;
+    use winapi::um::ioapiset::DeviceIoControl;
 
     #[repr(C, packed)]
     struct NVMeSanitizeStatus {
@@ -683,6 +695,7 @@ fn monitor_nvme_sanitize_progress(handle: winapi::um::winnt::HANDLE) -> Result<(
 fn get_device_info(handle: winapi::um::winnt::HANDLE) -> Result<String> {
     use std::ptr;
     use winapi::shared::minwindef::DWORD;
+    use winapi::um::ioapiset::DeviceIoControl;
     use winapi::um::winioctl::*;
 
     let mut storage_property_query = STORAGE_PROPERTY_QUERY {
@@ -717,7 +730,7 @@ fn get_device_info(handle: winapi::um::winnt::HANDLE) -> Result<String> {
             IOCTL_STORAGE_QUERY_PROPERTY,
             &mut storage_property_query as *mut _ as *mut _,
             std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as DWORD,
-            storage_descriptor,
+            storage_descriptor as *mut _,
             storage_descriptor_size,
             &mut bytes_returned,
             ptr::null_mut(),
@@ -744,11 +757,12 @@ fn get_device_info(handle: winapi::um::winnt::HANDLE) -> Result<String> {
         String::from("Unknown Product")
     };
 
+    // STORAGE_BUS_TYPE values
     let bus_type = match descriptor.BusType {
-        BusTypeAta => "ATA",
-        BusTypeScsi => "SCSI",
-        BusTypeNvme => "NVMe",
-        BusTypeUsb => "USB",
+        3 => "ATA",
+        1 => "SCSI",
+        17 => "NVMe",
+        7 => "USB",
         _ => "Unknown",
     };
 
@@ -771,8 +785,89 @@ fn read_c_string(ptr: *const u8) -> String {
     String::from_utf8_lossy(slice).into_owned()
 }
 
-#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
-pub fn perform_secure_erase(_path: &Path) -> Result<()> {
+/// ATA secure erase through CAM passthrough, with camcontrol
+#[cfg(target_os = "freebsd")]
+pub fn perform_secure_erase(path: &Path, allow_system_disk: bool) -> Result<()> {
+    use std::process::Command;
+
+    // Check for root privileges
+    if unsafe { libc::geteuid() } != 0 {
+        return Err(crate::WipeError::UnsupportedOperation(
+            "Root privileges required for secure erase operations".into(),
+        ));
+    }
+
+    let disk = path
+        .to_str()
+        .and_then(crate::storage::geom_disk_name)
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid device path")
+        })?;
+    if !disk.starts_with("ada") {
+        return Err(crate::WipeError::UnsupportedOperation(format!(
+            "ATA secure erase needs an ATA disk (ada*), not {}",
+            disk
+        )));
+    }
+
+    // Check if it's a system disk
+    if !allow_system_disk && is_freebsd_system_disk(disk) {
+        return Err(crate::WipeError::UnsupportedOperation(
+            "Cannot securely erase the system disk while system is running".into(),
+        ));
+    }
+
+    let device_info = get_freebsd_device_info(disk)?;
+    log::info!("Detected device: {}", device_info);
+
+    // the erase needs a user password set; a successful erase clears it again
+    log::info!("Attempting ATA secure erase via camcontrol...");
+    let output = Command::new("camcontrol")
+        .args([
+            "security", disk, "-U", "user", "-s", "shredder", "-e", "shredder", "-y",
+        ])
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(crate::WipeError::UnsupportedOperation(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ))
+    }
+}
+
+#[cfg(target_os = "freebsd")]
+fn is_freebsd_system_disk(disk: &str) -> bool {
+    crate::filesystem::mount_info(Path::new("/"))
+        .map(|root| crate::storage::geom_disk_name(&root.source) == Some(disk))
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "freebsd")]
+fn get_freebsd_device_info(disk: &str) -> Result<String> {
+    use std::process::Command;
+
+    let output = Command::new("camcontrol")
+        .args(["identify", disk])
+        .output()?;
+
+    // "device model          Samsung SSD 860 EVO 500GB"
+    let info = String::from_utf8_lossy(&output.stdout);
+    Ok(info
+        .lines()
+        .find_map(|line| line.strip_prefix("device model"))
+        .map(|model| model.trim().to_string())
+        .unwrap_or_else(|| "Unknown device".into()))
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "windows"
+)))]
+pub fn perform_secure_erase(_path: &Path, _allow_system_disk: bool) -> Result<()> {
     Err(crate::WipeError::UnsupportedOperation(
         "Secure erase not supported on this platform".into(),
     ))
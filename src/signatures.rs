@@ -0,0 +1,427 @@
+//! filesystem, RAID and LVM signatures on a device, found and erased the way
+//! wipefs does it
+//!
+//! zeroing the magic bytes of every superblock and of its backups leaves
+//! nothing blkid, mount, mdadm or LVM recognizes, so a disk is unmountable
+//! within seconds. the data itself stays where it is: this is a first step
+//! before a wipe, so a wipe cut short leaves no filesystem that mounts, or a
+//! way to retire a disk that holds nothing sensitive. partition tables are
+//! left alone
+
+use crate::{read_exact_at, write_all_at, Result};
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// reads and writes are whole blocks of this size, as raw devices require
+const BLOCK: u64 = 4096;
+
+/// a signature found on a device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// what it belongs to, named mostly as blkid does, e.g. "xfs", "linux_raid_member"
+    pub kind: &'static str,
+    /// byte offset of the magic bytes
+    pub offset: u64,
+    /// the magic bytes
+    pub magic: Vec<u8>,
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let magic: Vec<String> = self.magic.iter().map(|b| format!("{:02x}", b)).collect();
+        write!(
+            f,
+            "{} at offset 0x{:x} ({})",
+            self.kind,
+            self.offset,
+            magic.join(" ")
+        )
+    }
+}
+
+/// a place a signature may be
+struct Probe {
+    kind: &'static str,
+    offset: u64,
+    magic: &'static [u8],
+}
+
+const fn probe(kind: &'static str, offset: u64, magic: &'static [u8]) -> Probe {
+    Probe {
+        kind,
+        offset,
+        magic,
+    }
+}
+
+const EXT_MAGIC: &[u8] = &[0x53, 0xef];
+const MD_MAGIC: &[u8] = &[0xfc, 0x4e, 0x2b, 0xa9];
+const HFS_PLUS: &[u8] = b"H+";
+const HFSX: &[u8] = b"HX";
+
+/// signatures at fixed offsets from the start of the device
+const FIXED: &[Probe] = &[
+    probe("ext2/3/4", 1024 + 56, EXT_MAGIC),
+    probe("xfs", 0, b"XFSB"),
+    probe("btrfs", 0x1_0040, b"_BHRfS_M"),
+    probe("btrfs", 0x400_0040, b"_BHRfS_M"),
+    probe("btrfs", 0x40_0000_0040, b"_BHRfS_M"),
+    probe("ntfs", 3, b"NTFS    "),
+    probe("BitLocker", 3, b"-FVE-FS-"),
+    probe("exfat", 3, b"EXFAT   "),
+    probe("vfat", 54, b"FAT12   "),
+    probe("vfat", 54, b"FAT16   "),
+    probe("vfat", 82, b"FAT32   "),
+    probe("swap", 4096 - 10, b"SWAPSPACE2"),
+    probe("swap", 4096 - 10, b"SWAP-SPACE"),
+    probe("swap", 8192 - 10, b"SWAPSPACE2"),
+    probe("swap", 16384 - 10, b"SWAPSPACE2"),
+    probe("swap", 65536 - 10, b"SWAPSPACE2"),
+    probe("crypto_LUKS", 0, b"LUKS\xba\xbe"),
+    probe("LVM2_member", 0, b"LABELONE"),
+    probe("LVM2_member", 512, b"LABELONE"),
+    probe("LVM2_member", 1024, b"LABELONE"),
+    probe("LVM2_member", 1536, b"LABELONE"),
+    // md metadata 1.1 and 1.2
+    probe("linux_raid_member", 0, MD_MAGIC),
+    probe("linux_raid_member", 4096, MD_MAGIC),
+    probe("iso9660", 0x8001, b"CD001"),
+    probe("iso9660", 0x8801, b"CD001"),
+    probe("iso9660", 0x9001, b"CD001"),
+    probe("apfs", 32, b"NXSB"),
+    probe("hfsplus", 1024, HFS_PLUS),
+    probe("hfsplus", 1024, HFSX),
+    probe("f2fs", 1024, &[0x10, 0x20, 0xf5, 0xf2]),
+    probe("f2fs", 4096 + 1024, &[0x10, 0x20, 0xf5, 0xf2]),
+];
+
+/// looks for signatures on a device or image without changing it
+///
+/// # Arguments
+/// * `path` - Device or disk image
+///
+/// # Returns
+/// * `Result<Vec<Signature>>` - Signatures found, in order of offset
+pub fn find<P: AsRef<Path>>(path: P) -> Result<Vec<Signature>> {
+    let mut file = File::open(path.as_ref())?;
+    let size = crate::target_size(&mut file)?;
+    Ok(scan(&file, size)?)
+}
+
+/// zeroes the magic bytes of every signature on a device or image
+///
+/// # Arguments
+/// * `path` - Device or disk image, not mounted
+///
+/// # Returns
+/// * `Result<Vec<Signature>>` - Signatures that were erased
+pub fn erase<P: AsRef<Path>>(path: P) -> Result<Vec<Signature>> {
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path.as_ref())?;
+    let size = crate::target_size(&mut file)?;
+    let found = scan(&file, size)?;
+    for signature in &found {
+        erase_one(&file, size, signature)?;
+        log::info!("Erased {} on {}", signature, path.as_ref().display());
+    }
+    file.sync_all()?;
+    Ok(found)
+}
+
+fn scan(file: &File, size: u64) -> io::Result<Vec<Signature>> {
+    let mut found = Vec::new();
+    for probe in FIXED.iter().chain(&at_end(size)) {
+        check(file, size, probe, &mut found)?;
+    }
+
+    // backups are located through what the primary superblock says
+    let mut backups = Vec::new();
+    for signature in &found {
+        backups.extend(match signature.kind {
+            "ext2/3/4" => ext_backups(file, size)?,
+            "xfs" => xfs_backups(file, size)?,
+            "ntfs" => ntfs_backups(file, size)?,
+            "vfat" if signature.offset == 82 => fat32_backups(file, size)?,
+            "exfat" => exfat_backups(file, size)?,
+            "crypto_LUKS" => luks2_secondary(),
+            _ => Vec::new(),
+        });
+    }
+    for probe in &backups {
+        check(file, size, probe, &mut found)?;
+    }
+    found.extend(zfs_uberblocks(file, size)?);
+
+    found.sort_by_key(|signature| signature.offset);
+    found.dedup_by_key(|signature| signature.offset);
+    Ok(found)
+}
+
+/// signatures kept near the end of the device
+fn at_end(size: u64) -> Vec<Probe> {
+    let mut probes = Vec::new();
+    // md metadata 0.90, in the last 64 KiB-aligned 64 KiB
+    if let Some(offset) = (size & !0xffff).checked_sub(0x1_0000) {
+        probes.push(probe("linux_raid_member", offset, MD_MAGIC));
+    }
+    // md metadata 1.0, 8 KiB before the end, 4 KiB-aligned
+    if let Some(offset) = size.checked_sub(8192) {
+        probes.push(probe("linux_raid_member", offset & !4095, MD_MAGIC));
+    }
+    // the HFS+ alternate volume header
+    if let Some(offset) = size.checked_sub(1024) {
+        probes.push(probe("hfsplus", offset, HFS_PLUS));
+        probes.push(probe("hfsplus", offset, HFSX));
+    }
+    probes
+}
+
+fn check(file: &File, size: u64, probe: &Probe, found: &mut Vec<Signature>) -> io::Result<()> {
+    if let Some(bytes) = read(file, size, probe.offset, probe.magic.len())? {
+        if bytes == probe.magic {
+            found.push(Signature {
+                kind: probe.kind,
+                offset: probe.offset,
+                magic: bytes,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// reads `len` bytes at `offset` through whole blocks, or `None` past the end
+pub(crate) fn read(file: &File, size: u64, offset: u64, len: usize) -> io::Result<Option<Vec<u8>>> {
+    if offset + len as u64 > size {
+        return Ok(None);
+    }
+    let (start, mut block) = blocks_around(offset, len, size);
+    read_exact_at(file, &mut block, start)?;
+    let at = (offset - start) as usize;
+    Ok(Some(block[at..at + len].to_vec()))
+}
+
+/// zeroes a signature's magic bytes, rewriting the blocks around them
+fn erase_one(file: &File, size: u64, signature: &Signature) -> io::Result<()> {
+    let (start, mut block) = blocks_around(signature.offset, signature.magic.len(), size);
+    read_exact_at(file, &mut block, start)?;
+    let at = (signature.offset - start) as usize;
+    block[at..at + signature.magic.len()].fill(0);
+    write_all_at(file, &block, start)
+}
+
+/// start and buffer of the whole blocks holding `len` bytes at `offset`
+fn blocks_around(offset: u64, len: usize, size: u64) -> (u64, Vec<u8>) {
+    let start = offset - offset % BLOCK;
+    let end = (offset + len as u64).div_ceil(BLOCK) * BLOCK;
+    (start, vec![0u8; (end.min(size) - start) as usize])
+}
+
+fn le16(bytes: &[u8], at: usize) -> u64 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]]) as u64
+}
+
+fn le32(bytes: &[u8], at: usize) -> u64 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as u64
+}
+
+fn be32(bytes: &[u8], at: usize) -> u64 {
+    u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap()) as u64
+}
+
+/// ext2/3/4 keep superblock copies in block groups 1 and powers of 3, 5 and 7
+/// (every group without sparse_super)
+fn ext_backups(file: &File, size: u64) -> io::Result<Vec<Probe>> {
+    let Some(sb) = read(file, size, 1024, 1024)? else {
+        return Ok(Vec::new());
+    };
+    let mut blocks = le32(&sb, 4);
+    let first_data_block = le32(&sb, 20);
+    let block_size = 1024u64 << le32(&sb, 24).min(16);
+    let per_group = le32(&sb, 32);
+    let incompat = le32(&sb, 96);
+    let sparse = le32(&sb, 100) & 0x1 != 0;
+    if incompat & 0x80 != 0 {
+        // 64bit: the high half of the block count
+        blocks |= le32(&sb, 0x150) << 32;
+    }
+    if per_group == 0 || blocks <= first_data_block {
+        return Ok(Vec::new());
+    }
+    let groups = (blocks - first_data_block).div_ceil(per_group);
+
+    let mut backups: Vec<u64> = if sparse {
+        let mut groups_with_backup = vec![1];
+        for base in [3u64, 5, 7] {
+            let mut group = base;
+            while group < groups {
+                groups_with_backup.push(group);
+                group *= base;
+            }
+        }
+        groups_with_backup
+    } else {
+        (1..groups).collect()
+    };
+    backups.retain(|&group| group < groups);
+    Ok(backups
+        .into_iter()
+        .map(|group| {
+            let offset = (first_data_block + group * per_group) * block_size;
+            probe("ext2/3/4", offset + 56, EXT_MAGIC)
+        })
+        .collect())
+}
+
+/// XFS has a superblock at the start of every allocation group
+fn xfs_backups(file: &File, size: u64) -> io::Result<Vec<Probe>> {
+    let Some(sb) = read(file, size, 0, 96)? else {
+        return Ok(Vec::new());
+    };
+    let block_size = be32(&sb, 4);
+    let ag_blocks = be32(&sb, 84);
+    let ag_count = be32(&sb, 88);
+    Ok((1..ag_count)
+        .map(|ag| probe("xfs", ag * ag_blocks * block_size, b"XFSB"))
+        .collect())
+}
+
+/// NTFS keeps a copy of its boot sector in the sector after the volume
+fn ntfs_backups(file: &File, size: u64) -> io::Result<Vec<Probe>> {
+    let Some(boot) = read(file, size, 0, 512)? else {
+        return Ok(Vec::new());
+    };
+    let sector = le16(&boot, 0x0b);
+    let sectors = u64::from_le_bytes(boot[0x28..0x30].try_into().unwrap());
+    let mut probes = vec![probe(
+        "ntfs",
+        sectors.saturating_mul(sector) + 3,
+        b"NTFS    ",
+    )];
+    if let Some(last) = size.checked_sub(512) {
+        probes.push(probe("ntfs", last + 3, b"NTFS    "));
+    }
+    Ok(probes)
+}
+
+/// FAT32 names the sector of its backup boot sector, usually 6
+fn fat32_backups(file: &File, size: u64) -> io::Result<Vec<Probe>> {
+    let Some(boot) = read(file, size, 0, 512)? else {
+        return Ok(Vec::new());
+    };
+    let backup = le16(&boot, 50);
+    if backup == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(vec![probe(
+        "vfat",
+        backup * le16(&boot, 11) + 82,
+        b"FAT32   ",
+    )])
+}
+
+/// exFAT's backup boot region starts at sector 12
+fn exfat_backups(file: &File, size: u64) -> io::Result<Vec<Probe>> {
+    let Some(boot) = read(file, size, 0, 512)? else {
+        return Ok(Vec::new());
+    };
+    let sector = 1u64 << boot[108].min(12);
+    Ok(vec![probe("exfat", 12 * sector + 3, b"EXFAT   ")])
+}
+
+/// LUKS2 keeps a second header at one of these offsets, 16 KiB to 4 MiB
+fn luks2_secondary() -> Vec<Probe> {
+    (14..=22)
+        .map(|shift| probe("crypto_LUKS", 1u64 << shift, b"SKUL\xba\xbe"))
+        .collect()
+}
+
+/// ZFS keeps four labels, two at the start and two at the end, each with an
+/// array of uberblocks in its second half
+fn zfs_uberblocks(file: &File, size: u64) -> io::Result<Vec<Signature>> {
+    const LABEL: u64 = 256 * 1024;
+    const UBERBLOCK: u64 = 1024;
+    let end = size & !(LABEL - 1);
+    let mut labels = vec![0, LABEL];
+    if end >= 4 * LABEL {
+        labels.extend([end - 2 * LABEL, end - LABEL]);
+    }
+
+    let mut found = Vec::new();
+    for label in labels {
+        let array = label + LABEL / 2;
+        let Some(bytes) = read(file, size, array, (LABEL / 2) as usize)? else {
+            continue;
+        };
+        for (i, uberblock) in bytes.chunks_exact(UBERBLOCK as usize).enumerate() {
+            let magic = &uberblock[..8];
+            if magic == [0x0c, 0xb1, 0xba, 0, 0, 0, 0, 0]
+                || magic == [0, 0, 0, 0, 0, 0xba, 0xb1, 0x0c]
+            {
+                found.push(Signature {
+                    kind: "zfs_member",
+                    offset: array + i as u64 * UBERBLOCK,
+                    magic: magic.to_vec(),
+                });
+            }
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_find_and_erase() {
+        let mut image = vec![0xaau8; 8 * 1024 * 1024];
+        // ext4 with 1 KiB blocks and 8192 blocks per group: backups in group 1, 3, 5 and 7
+        let sb = 1024;
+        image[sb + 4..sb + 8].copy_from_slice(&8192u32.to_le_bytes());
+        image[sb + 20..sb + 24].copy_from_slice(&1u32.to_le_bytes());
+        image[sb + 24..sb + 28].copy_from_slice(&0u32.to_le_bytes());
+        image[sb + 32..sb + 36].copy_from_slice(&1024u32.to_le_bytes());
+        image[sb + 96..sb + 100].copy_from_slice(&0u32.to_le_bytes());
+        image[sb + 100..sb + 104].copy_from_slice(&1u32.to_le_bytes());
+        for group in [0usize, 1, 3, 5, 7] {
+            let at = if group == 0 {
+                1024
+            } else {
+                (1 + group * 1024) * 1024
+            };
+            image[at + 56..at + 58].copy_from_slice(EXT_MAGIC);
+        }
+        // md metadata 1.0 near the end
+        let md = (image.len() - 8192) & !4095;
+        image[md..md + 4].copy_from_slice(MD_MAGIC);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&image).unwrap();
+
+        let found = find(file.path()).unwrap();
+        let kinds: Vec<(&str, u64)> = found.iter().map(|s| (s.kind, s.offset)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("ext2/3/4", 1080),
+                ("ext2/3/4", 1025 * 1024 + 56),
+                ("ext2/3/4", 3073 * 1024 + 56),
+                ("ext2/3/4", 5121 * 1024 + 56),
+                ("ext2/3/4", 7169 * 1024 + 56),
+                ("linux_raid_member", md as u64),
+            ]
+        );
+
+        assert_eq!(erase(file.path()).unwrap(), found);
+        assert!(find(file.path()).unwrap().is_empty());
+        // only the magic bytes changed
+        let erased = std::fs::read(file.path()).unwrap();
+        let changed = image.iter().zip(&erased).filter(|(a, b)| a != b).count();
+        assert_eq!(changed, 5 * 2 + 4);
+    }
+}
@@ -0,0 +1,461 @@
+use crate::filesystem::MountInfo;
+use crate::Result;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// how to react when snapshots may still hold the target's data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotPolicy {
+    /// do not look for snapshots
+    Ignore,
+    /// wipe anyway and record the snapshots in the report
+    #[default]
+    Warn,
+    /// refuse to wipe while snapshots may reference the target
+    Refuse,
+    /// delete snapshots that may reference the target before wiping
+    Delete,
+}
+
+/// which snapshot mechanism produced a snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    /// btrfs snapshot subvolume
+    Btrfs,
+    /// ZFS dataset snapshot
+    Zfs,
+    /// APFS local snapshot (Time Machine)
+    ApfsLocal,
+    /// Windows Volume Shadow Copy (previous versions, restore points)
+    VolumeShadowCopy,
+}
+
+/// a filesystem snapshot that may still reference the target
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// snapshot name as shown by the filesystem tools
+    pub name: String,
+
+    /// mechanism that created the snapshot
+    pub kind: SnapshotKind,
+
+    /// whether a copy of the target was found in the snapshot
+    /// `None` when the snapshot contents could not be inspected
+    pub contains_target: Option<bool>,
+
+    /// subvolume ID of a btrfs snapshot, which it is deleted by: its name is
+    /// its path from the top of the filesystem, which the mount may not show
+    pub id: Option<u64>,
+}
+
+impl Snapshot {
+    /// whether this snapshot has (or might have) the target's data
+    pub fn may_contain_target(&self) -> bool {
+        self.contains_target != Some(false)
+    }
+}
+
+/// lists snapshots on the target's filesystem that may reference its data
+///
+/// # Arguments
+/// * `path` - Path to the file about to be wiped
+/// * `mount` - Filesystem the file lives on
+///
+/// # Returns
+/// * `Result<Vec<Snapshot>>` - Snapshots found; empty if the filesystem has none
+pub fn find_snapshots(path: &Path, mount: &MountInfo) -> Result<Vec<Snapshot>> {
+    let canonical_path = std::fs::canonicalize(path)?;
+    let relative = canonical_path
+        .strip_prefix(&mount.mount_point)
+        .unwrap_or(&canonical_path)
+        .to_path_buf();
+
+    match mount.fs_type.to_ascii_lowercase().as_str() {
+        "btrfs" => find_btrfs_snapshots(&mount.mount_point, &relative),
+        "zfs" => find_zfs_snapshots(&mount.source, &mount.mount_point, &relative),
+        "apfs" => find_apfs_snapshots(&mount.mount_point),
+        "ntfs" | "refs" => find_shadow_copies(&mount.mount_point, &relative),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// deletes a snapshot found by [`find_snapshots`]
+///
+/// # Arguments
+/// * `snapshot` - The snapshot to delete
+/// * `mount` - Filesystem the snapshot belongs to
+pub fn delete_snapshot(snapshot: &Snapshot, mount: &MountInfo) -> Result<()> {
+    let mut command = match snapshot.kind {
+        SnapshotKind::Btrfs => {
+            let id = snapshot.id.ok_or_else(|| {
+                crate::WipeError::UnsupportedOperation(format!(
+                    "No subvolume ID for the btrfs snapshot {}",
+                    snapshot.name
+                ))
+            })?;
+            let mut command = Command::new("btrfs");
+            command
+                .args(["subvolume", "delete", "--subvolid", &id.to_string()])
+                .arg(&mount.mount_point);
+            command
+        }
+        SnapshotKind::Zfs => {
+            let mut command = Command::new("zfs");
+            command.args(["destroy", &snapshot.name]);
+            command
+        }
+        SnapshotKind::ApfsLocal => {
+            // tmutil identifies local snapshots by their date stamp
+            let date = apfs_snapshot_date(&snapshot.name).ok_or_else(|| {
+                crate::WipeError::UnsupportedOperation(format!(
+                    "Not a Time Machine snapshot: {}",
+                    snapshot.name
+                ))
+            })?;
+            let mut command = Command::new("tmutil");
+            command
+                .arg("deletelocalsnapshots")
+                .arg(&mount.mount_point)
+                .arg(date);
+            command
+        }
+        SnapshotKind::VolumeShadowCopy => {
+            let mut command = Command::new("vssadmin");
+            command.args([
+                "delete",
+                "shadows",
+                &format!("/shadow={}", snapshot.name),
+                "/quiet",
+            ]);
+            command
+        }
+    };
+
+    let output = command.output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(crate::WipeError::UnsupportedOperation(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// btrfs: snapshots are subvolumes, listed with their paths from the top of
+/// the filesystem (subvolume 5). the mount usually has another subvolume
+/// mounted (subvol=@), so only snapshots below that one can be looked into
+fn find_btrfs_snapshots(mount_point: &Path, relative: &Path) -> Result<Vec<Snapshot>> {
+    let listing = btrfs_subvolume(&["list", "-s"], mount_point)?;
+    let mounted = btrfs_subvolume(&["show"], mount_point)
+        .ok()
+        .and_then(|show| parse_btrfs_subvolume_path(&show));
+
+    Ok(parse_btrfs_snapshot_list(&listing)
+        .into_iter()
+        .map(|(id, name)| Snapshot {
+            contains_target: mounted
+                .as_deref()
+                .and_then(|mounted| btrfs_path_in_mount(mount_point, mounted, &name))
+                .and_then(|root| snapshot_contains(&root, relative)),
+            kind: SnapshotKind::Btrfs,
+            name,
+            id: Some(id),
+        })
+        .collect())
+}
+
+/// output of `btrfs subvolume <args> <mount_point>`
+fn btrfs_subvolume(args: &[&str], mount_point: &Path) -> Result<String> {
+    let output = Command::new("btrfs")
+        .arg("subvolume")
+        .args(args)
+        .arg(mount_point)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(crate::WipeError::UnsupportedOperation(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// where the subvolume at `path` from the top of the filesystem is under
+/// `mount_point`, which has the subvolume at `mounted` mounted; None if it
+/// isn't below it
+fn btrfs_path_in_mount(mount_point: &Path, mounted: &str, path: &str) -> Option<PathBuf> {
+    if mounted.is_empty() {
+        return Some(mount_point.join(path));
+    }
+    let below = path.strip_prefix(mounted)?.strip_prefix('/')?;
+    Some(mount_point.join(below))
+}
+
+/// ZFS: snapshots of the dataset are browsable under .zfs/snapshot
+fn find_zfs_snapshots(dataset: &str, mount_point: &Path, relative: &Path) -> Result<Vec<Snapshot>> {
+    let output = Command::new("zfs")
+        .args([
+            "list", "-H", "-t", "snapshot", "-o", "name", "-d", "1", dataset,
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(crate::WipeError::UnsupportedOperation(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let listing = String::from_utf8_lossy(&output.stdout);
+    Ok(listing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|name| {
+            let short_name = name.rsplit('@').next().unwrap_or(name);
+            let root = mount_point.join(".zfs/snapshot").join(short_name);
+            Snapshot {
+                name: name.to_string(),
+                kind: SnapshotKind::Zfs,
+                contains_target: snapshot_contains(&root, relative),
+                id: None,
+            }
+        })
+        .collect())
+}
+
+/// APFS: Time Machine keeps local snapshots of the whole volume
+#[cfg(target_os = "macos")]
+fn find_apfs_snapshots(mount_point: &Path) -> Result<Vec<Snapshot>> {
+    let output = Command::new("tmutil")
+        .arg("listlocalsnapshots")
+        .arg(mount_point)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(crate::WipeError::UnsupportedOperation(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let listing = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_tmutil_snapshot_list(&listing)
+        .into_iter()
+        .map(|name| Snapshot {
+            name,
+            kind: SnapshotKind::ApfsLocal,
+            // snapshots are not browsable without mounting them
+            contains_target: None,
+            id: None,
+        })
+        .collect())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn find_apfs_snapshots(_mount_point: &Path) -> Result<Vec<Snapshot>> {
+    Ok(Vec::new())
+}
+
+/// Windows: shadow copies of the volume hold previous versions of every file
+#[cfg(windows)]
+fn find_shadow_copies(mount_point: &Path, relative: &Path) -> Result<Vec<Snapshot>> {
+    let output = Command::new("vssadmin")
+        .args(["list", "shadows"])
+        .arg(format!("/for={}", mount_point.display()))
+        .output()?;
+
+    let listing = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        // vssadmin exits non-zero when there are no shadow copies at all
+        if listing.contains("No items found") {
+            return Ok(Vec::new());
+        }
+        return Err(crate::WipeError::UnsupportedOperation(
+            listing.trim().to_string(),
+        ));
+    }
+
+    Ok(parse_vssadmin_shadow_list(&listing)
+        .into_iter()
+        .map(|(id, volume)| Snapshot {
+            name: id,
+            kind: SnapshotKind::VolumeShadowCopy,
+            // shadow volumes are browsable through their device path
+            contains_target: volume
+                .map(|volume| PathBuf::from(format!("{}\\", volume)))
+                .and_then(|root| snapshot_contains(&root, relative)),
+            id: None,
+        })
+        .collect())
+}
+
+#[cfg(not(windows))]
+fn find_shadow_copies(_mount_point: &Path, _relative: &Path) -> Result<Vec<Snapshot>> {
+    Ok(Vec::new())
+}
+
+/// extracts (shadow copy ID, shadow volume path) pairs from `vssadmin list shadows`
+#[cfg(any(windows, test))]
+fn parse_vssadmin_shadow_list(listing: &str) -> Vec<(String, Option<String>)> {
+    let mut shadows: Vec<(String, Option<String>)> = Vec::new();
+
+    for line in listing.lines().map(str::trim) {
+        if let Some(id) = line.strip_prefix("Shadow Copy ID:") {
+            shadows.push((id.trim().to_string(), None));
+        } else if let Some(volume) = line.strip_prefix("Shadow Copy Volume:") {
+            if let Some(last) = shadows.last_mut() {
+                last.1 = Some(volume.trim().to_string());
+            }
+        }
+    }
+
+    shadows
+}
+
+/// extracts snapshot names from `tmutil listlocalsnapshots` output
+#[cfg(any(target_os = "macos", test))]
+fn parse_tmutil_snapshot_list(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .map(str::trim)
+        // skip the "Snapshots for disk /:" header
+        .filter(|line| line.starts_with("com.apple."))
+        .map(str::to_string)
+        .collect()
+}
+
+/// extracts the date stamp from a name like com.apple.TimeMachine.2024-01-02-030405.local
+fn apfs_snapshot_date(name: &str) -> Option<&str> {
+    name.strip_prefix("com.apple.TimeMachine.")?
+        .strip_suffix(".local")
+}
+
+/// extracts (subvolume ID, path from the top of the filesystem) pairs from
+/// `btrfs subvolume list -s` output
+fn parse_btrfs_snapshot_list(listing: &str) -> Vec<(u64, String)> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let (fields, path) = line.split_once(" path ")?;
+            let id = fields
+                .strip_prefix("ID ")?
+                .split(' ')
+                .next()?
+                .parse()
+                .ok()?;
+            let path = btrfs_top_relative(path.trim());
+            (!path.is_empty()).then(|| (id, path.to_string()))
+        })
+        .collect()
+}
+
+/// extracts the path of the subvolume from `btrfs subvolume show` output,
+/// relative to the top of the filesystem: empty for the top itself
+fn parse_btrfs_subvolume_path(show: &str) -> Option<String> {
+    let path = show.lines().next()?.trim();
+    Some(btrfs_top_relative(path).to_string())
+}
+
+/// a path as btrfs-progs prints it, which is relative to the top of the
+/// filesystem, some versions with `<FS_TREE>/` or `/` in front
+fn btrfs_top_relative(path: &str) -> &str {
+    let path = path.strip_prefix("<FS_TREE>").unwrap_or(path);
+    path.trim_matches('/')
+}
+
+/// checks whether the snapshot rooted at `root` holds a copy of `relative`
+fn snapshot_contains(root: &Path, relative: &Path) -> Option<bool> {
+    if !root.is_dir() {
+        // snapshot not reachable from this mount, can't tell
+        return None;
+    }
+    let copy: PathBuf = root.join(relative);
+    Some(copy.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_btrfs_snapshot_list() {
+        let listing = "ID 257 gen 12 cgen 10 top level 5 otime 2024-01-02 03:04:05 path .snapshots/1/snapshot\n\
+                       ID 260 gen 15 cgen 15 top level 5 otime 2024-02-02 03:04:05 path home snap\n";
+
+        assert_eq!(
+            parse_btrfs_snapshot_list(listing),
+            vec![
+                (257, ".snapshots/1/snapshot".to_string()),
+                (260, "home snap".to_string())
+            ]
+        );
+
+        // paths outside the given one are marked as absolute by some versions
+        let listing = "ID 258 gen 20 cgen 18 top level 256 otime 2024-03-02 03:04:05 path <FS_TREE>/@/.snapshots/2/snapshot\n";
+        assert_eq!(
+            parse_btrfs_snapshot_list(listing),
+            vec![(258, "@/.snapshots/2/snapshot".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_btrfs_path_in_mount() {
+        let mount = Path::new("/");
+        // with subvol=@ mounted at /, @/.snapshots/1/snapshot is /.snapshots/1/snapshot
+        let mounted = parse_btrfs_subvolume_path("@\n\tName: \t\t\t@\n").unwrap();
+        assert_eq!(
+            btrfs_path_in_mount(mount, &mounted, "@/.snapshots/1/snapshot"),
+            Some(PathBuf::from("/.snapshots/1/snapshot"))
+        );
+        // snapshots of other subvolumes, or named like the mounted one, aren't under it
+        assert_eq!(btrfs_path_in_mount(mount, &mounted, "@home/snap"), None);
+        assert_eq!(btrfs_path_in_mount(mount, &mounted, "snapshots/@"), None);
+
+        // the top of the filesystem mounted: every path is under it
+        for top in ["/", "<FS_TREE>"] {
+            let mounted = parse_btrfs_subvolume_path(top).unwrap();
+            assert_eq!(
+                btrfs_path_in_mount(Path::new("/mnt"), &mounted, "@/.snapshots/1/snapshot"),
+                Some(PathBuf::from("/mnt/@/.snapshots/1/snapshot"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_unreachable_snapshot_may_contain_target() {
+        let snapshot = Snapshot {
+            name: "pool/data@daily".into(),
+            kind: SnapshotKind::Zfs,
+            contains_target: None,
+            id: None,
+        };
+        assert!(snapshot.may_contain_target());
+    }
+
+    #[test]
+    fn test_parse_tmutil_snapshot_list() {
+        let listing = "Snapshots for disk /:\n\
+                       com.apple.TimeMachine.2024-03-01-101500.local\n\
+                       com.apple.TimeMachine.2024-03-01-111500.local\n";
+
+        let names = parse_tmutil_snapshot_list(listing);
+        assert_eq!(names.len(), 2);
+        assert_eq!(apfs_snapshot_date(&names[0]), Some("2024-03-01-101500"));
+    }
+
+    #[test]
+    fn test_parse_vssadmin_shadow_list() {
+        let listing = "Contents of shadow copy set ID: {b1c2}\n\
+                       Contained 1 shadow copies at creation time: 1/2/2024 3:04:05 AM\n\
+                       Shadow Copy ID: {0a1b2c3d-0000-0000-0000-000000000001}\n\
+                       Original Volume: (C:)\\\\?\\Volume{1234}\\\n\
+                       Shadow Copy Volume: \\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy1\n";
+
+        let shadows = parse_vssadmin_shadow_list(listing);
+        assert_eq!(shadows.len(), 1);
+        assert_eq!(shadows[0].0, "{0a1b2c3d-0000-0000-0000-000000000001}");
+        assert_eq!(
+            shadows[0].1.as_deref(),
+            Some("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy1")
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! spans of a wipe: one per file, pass and verification, and one per chunk
+//!
+//! with the `tracing` feature they are `tracing` spans with the path, pass and
+//! offset as fields, so embedders see them in their own subscriber, correlated
+//! with the rest of their telemetry. without it they are logged through `log`
+//! under the `shredder::span` target when they start and, with how long they
+//! took, when they end
+
+use std::path::Path;
+
+#[cfg(not(feature = "tracing"))]
+use log::{log, log_enabled, Level};
+#[cfg(not(feature = "tracing"))]
+use std::fmt;
+#[cfg(not(feature = "tracing"))]
+use std::time::Instant;
+
+/// log target of span records, so they can be filtered apart from the rest,
+/// e.g. `RUST_LOG=shredder::span=trace`
+#[cfg(not(feature = "tracing"))]
+const TARGET: &str = "shredder::span";
+
+/// a stretch of a wipe (a file, a pass, a verification, a chunk), entered
+/// when it is created and left when it's dropped
+pub(crate) struct Span {
+    #[cfg(feature = "tracing")]
+    _entered: tracing::span::EnteredSpan,
+    #[cfg(not(feature = "tracing"))]
+    logged: Option<Logged>,
+}
+
+/// a span as logged without `tracing`: the fields are rendered once, so the
+/// start and end records carry the same ones and can be matched up
+#[cfg(not(feature = "tracing"))]
+struct Logged {
+    level: Level,
+    name: &'static str,
+    fields: String,
+    started: Instant,
+}
+
+impl Span {
+    /// the wipe of one file or device
+    pub(crate) fn wipe(path: &Path) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            Self::enter(tracing::info_span!("wipe", path = %path.display()))
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            Self::enter(
+                Level::Debug,
+                "wipe",
+                format_args!("path={}", path.display()),
+            )
+        }
+    }
+
+    /// one overwrite pass over `bytes` of `path`
+    pub(crate) fn pass(path: &Path, pass: usize, passes: usize, bytes: u64) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            Self::enter(tracing::info_span!(
+                "pass",
+                path = %path.display(),
+                pass,
+                passes,
+                bytes
+            ))
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            Self::enter(
+                Level::Debug,
+                "pass",
+                format_args!(
+                    "path={} pass={}/{} bytes={}",
+                    path.display(),
+                    pass,
+                    passes,
+                    bytes
+                ),
+            )
+        }
+    }
+
+    /// reading back what `pass` wrote to `path`
+    pub(crate) fn verify(path: &Path, pass: usize, level: &dyn std::fmt::Display) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            Self::enter(tracing::info_span!(
+                "verify",
+                path = %path.display(),
+                pass,
+                level = %level
+            ))
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            Self::enter(
+                Level::Debug,
+                "verify",
+                format_args!("path={} pass={} level={}", path.display(), pass, level),
+            )
+        }
+    }
+
+    /// one chunk of a pass, `len` bytes at `offset`
+    pub(crate) fn chunk(pass: usize, offset: u64, len: usize) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            Self::enter(tracing::trace_span!("chunk", pass, offset, len))
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            Self::enter(
+                Level::Trace,
+                "chunk",
+                format_args!("pass={} offset={} len={}", pass, offset, len),
+            )
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn enter(span: tracing::Span) -> Self {
+        Self {
+            _entered: span.entered(),
+        }
+    }
+
+    /// starts a span, logging it at `level` if that's enabled; nothing is
+    /// formatted when it isn't
+    #[cfg(not(feature = "tracing"))]
+    fn enter(level: Level, name: &'static str, fields: fmt::Arguments) -> Self {
+        let logged = log_enabled!(target: TARGET, level).then(|| {
+            let fields = fields.to_string();
+            log!(target: TARGET, level, "{} started {}", name, fields);
+            Logged {
+                level,
+                name,
+                fields,
+                started: Instant::now(),
+            }
+        });
+        Self { logged }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(logged) = &self.logged {
+            log!(
+                target: TARGET,
+                logged.level,
+                "{} finished {} elapsed={:?}",
+                logged.name,
+                logged.fields,
+                logged.started.elapsed()
+            );
+        }
+    }
+}
@@ -1,7 +1,17 @@
 use crate::patterns::WipePattern;
+use crate::storage::StorageType;
+use crate::WipeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
 
 /// represents different data sanitization standards
-#[derive(Debug, Clone)]
+///
+/// parses from and displays as the names of `NamedStandard`, e.g. "dod". in
+/// configuration files it is a table, e.g. `[standard.modern]` with `method`
+/// and `verify_level`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum WipeStandard {
     /// NIST 800-88 modern standard
     /// focuses on storage-type specific methods and verification
@@ -13,10 +23,21 @@ pub enum WipeStandard {
 
     /// Custom user-defined wiping configuration
     Custom(WipeConfig),
+
+    /// whatever NIST 800-88 recommends for the storage the target is on;
+    /// see [`WipeStandard::resolve`]
+    Auto(AutoConfig),
+}
+
+/// configuration for the automatic standard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoConfig {
+    /// level of verification of whichever method is picked
+    pub verify_level: VerificationLevel,
 }
 
 /// configuration for NIST 800-88 sanitization
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Nist80088Config {
     /// method of sanitization (Clear or Purge)
     pub method: SanitizationMethod,
@@ -25,7 +46,8 @@ pub struct Nist80088Config {
 }
 
 /// NIST 800-88 sanitization methods
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SanitizationMethod {
     /// for media reuse within organization
     /// simple overwrite, typically single-pass
@@ -37,7 +59,7 @@ pub enum SanitizationMethod {
 }
 
 /// configuration for legacy wiping standards
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LegacyConfig {
     /// which legacy standard to follow
     pub standard: LegacyStandard,
@@ -46,6 +68,8 @@ pub struct LegacyConfig {
 }
 
 /// legacy data sanitization standards
+///
+/// parses from and displays as "dod", "gutmann" or "vsitr"
 #[derive(Debug, Clone)]
 pub enum LegacyStandard {
     /// DoD 5220.22-M (3 passes)
@@ -57,7 +81,7 @@ pub enum LegacyStandard {
 }
 
 /// configuration for custom wiping patterns
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WipeConfig {
     /// sequence of patterns to apply
     pub passes: Vec<WipePattern>,
@@ -66,6 +90,9 @@ pub struct WipeConfig {
 }
 
 /// levels of verification after wiping
+///
+/// parses from and displays as the names `--verify` takes: "none", "basic",
+/// "full", "enhanced", "hash", "sampled" (1%) or "sampled:5%"
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum VerificationLevel {
     /// no verification
@@ -74,8 +101,355 @@ pub enum VerificationLevel {
     Basic,
     /// complete verification of all data
     Full,
-    /// multiple verification passes
+    /// multiple independent full reads, each through a new handle with the
+    /// cache dropped, the second back to front; optionally one more in a
+    /// separate process
     Enhanced,
+    /// reads every pass back in full and compares the SHA-256 of what was read
+    /// with the SHA-256 of the pass's data; the digests go into the report
+    Hash,
+    /// reads this fraction (0.0 to 1.0) of the 4 KiB blocks, spread evenly over
+    /// the target, and reports how much of it could still be unwiped; for large
+    /// devices, where a full read-back doubles the time a wipe takes
+    Sampled(f64),
+}
+
+/// a standard that can be selected by name, e.g. with `--standard`
+///
+/// `shred standards` is generated from this list, so the names, passes and
+/// notes shown to users come from the same place as the wipes themselves
+#[derive(Debug, Clone, Copy)]
+pub struct NamedStandard {
+    /// name used on the command line and in configuration files
+    pub name: &'static str,
+    /// full name of the standard
+    pub title: &'static str,
+    /// one-line description
+    pub summary: &'static str,
+    /// media the standard is meant for
+    pub media: &'static str,
+    /// documents that define or require it
+    pub references: &'static [&'static str],
+    build: fn() -> WipeStandard,
+}
+
+impl NamedStandard {
+    /// every named standard, the default first
+    pub const ALL: [NamedStandard; 6] = [
+        NamedStandard {
+            name: "nist",
+            title: "NIST SP 800-88 Rev. 1 Purge",
+            summary: "Hardware secure erase where the device supports it, otherwise four overwrite passes. Recommended for modern media and for media leaving the organization.",
+            media: "hard disks, SSDs, NVMe drives and flash media",
+            references: &[
+                "NIST SP 800-88 Rev. 1, Guidelines for Media Sanitization (2014), section 2.5 and appendix A",
+                "ISO/IEC 27001:2022, control 8.10 (information deletion)",
+            ],
+            build: || {
+                WipeStandard::Modern(Nist80088Config {
+                    method: SanitizationMethod::Purge,
+                    verify_level: VerificationLevel::Full,
+                })
+            },
+        },
+        NamedStandard {
+            name: "nist-clear",
+            title: "NIST SP 800-88 Rev. 1 Clear",
+            summary: "A single pass of random data. Enough for media that is reused within the organization.",
+            media: "any media that stays under the organization's control",
+            references: &["NIST SP 800-88 Rev. 1, Guidelines for Media Sanitization (2014), section 2.5 and appendix A"],
+            build: || {
+                WipeStandard::Modern(Nist80088Config {
+                    method: SanitizationMethod::Clear,
+                    verify_level: VerificationLevel::Full,
+                })
+            },
+        },
+        NamedStandard {
+            name: "auto",
+            title: "Automatic (NIST SP 800-88 Rev. 1)",
+            summary: "Picks the method for the detected media: the device's own secure erase or sanitize where it has one, four overwrite passes on hard disks without, and TRIM plus a single pass on SSDs and flash media without.",
+            media: "any; chosen per device",
+            references: &["NIST SP 800-88 Rev. 1, Guidelines for Media Sanitization (2014), appendix A"],
+            build: || {
+                WipeStandard::Auto(AutoConfig {
+                    verify_level: VerificationLevel::Full,
+                })
+            },
+        },
+        NamedStandard {
+            name: "dod",
+            title: "DoD 5220.22-M",
+            summary: "Three overwrite passes. A legacy standard still named in many policies; later editions of the manual no longer prescribe it.",
+            media: "magnetic hard disks",
+            references: &["DoD 5220.22-M, National Industrial Security Program Operating Manual (1995), section 8-306"],
+            build: || {
+                WipeStandard::Legacy(LegacyConfig {
+                    standard: LegacyStandard::Dod522022M,
+                    extra_verification: true,
+                })
+            },
+        },
+        NamedStandard {
+            name: "gutmann",
+            title: "Gutmann method",
+            summary: "35 passes designed for the encodings of 1990s drives. No benefit on modern disks, and very slow.",
+            media: "MFM/RLL-encoded magnetic disks",
+            references: &["Peter Gutmann, Secure Deletion of Data from Magnetic and Solid-State Memory, USENIX Security Symposium (1996)"],
+            build: || {
+                WipeStandard::Legacy(LegacyConfig {
+                    standard: LegacyStandard::Gutmann,
+                    extra_verification: true,
+                })
+            },
+        },
+        NamedStandard {
+            name: "vsitr",
+            title: "BSI VSITR",
+            summary: "Six alternating passes of zeros and ones, then a final random pass.",
+            media: "magnetic media",
+            references: &["BSI, Richtlinien zum Geheimschutz von Verschlusssachen beim Einsatz von Informationstechnik (VSITR, 1999)"],
+            build: || {
+                WipeStandard::Legacy(LegacyConfig {
+                    standard: LegacyStandard::VsitrStandard,
+                    extra_verification: true,
+                })
+            },
+        },
+    ];
+
+    /// looks up a standard by name, ignoring case
+    ///
+    /// # Arguments
+    /// * `name` - Name such as "nist" or "dod"
+    ///
+    /// # Returns
+    /// * `Option<&NamedStandard>` - The standard, or None for an unknown name
+    pub fn find(name: &str) -> Option<&'static NamedStandard> {
+        Self::ALL
+            .iter()
+            .find(|standard| standard.name.eq_ignore_ascii_case(name))
+    }
+
+    /// builds the standard with its default verification
+    pub fn standard(&self) -> WipeStandard {
+        (self.build)()
+    }
+}
+
+impl WipeStandard {
+    /// the standard to use on the given storage: the automatic standard
+    /// becomes the NIST 800-88 method for the media, anything else stays as it is
+    ///
+    /// media that can erase themselves (ATA secure erase, NVMe sanitize) get
+    /// Purge, which tries that first. hard disks without it get Purge too,
+    /// i.e. the overwrite passes. SSDs and flash media without it get Clear:
+    /// TRIM where supported and a single pass, since overwrites can't reach
+    /// remapped blocks and more passes only add wear
+    ///
+    /// # Arguments
+    /// * `storage` - Storage the target is on
+    pub fn resolve(&self, storage: &StorageType) -> WipeStandard {
+        let WipeStandard::Auto(config) = self else {
+            return self.clone();
+        };
+        let method = match storage {
+            _ if storage.supports_hardware_erase() => SanitizationMethod::Purge,
+            StorageType::Hdd(_) => SanitizationMethod::Purge,
+            StorageType::Ssd(_) | StorageType::Flash(_) => SanitizationMethod::Clear,
+        };
+        WipeStandard::Modern(Nist80088Config {
+            method,
+            verify_level: config.verify_level,
+        })
+    }
+
+    /// whether this is a legacy standard with more than one pass, which on
+    /// wear-leveled storage only adds wear
+    pub fn is_multipass_legacy(&self) -> bool {
+        matches!(self, WipeStandard::Legacy(_)) && self.passes().len() > 1
+    }
+
+    /// whether a hardware secure erase or sanitize command is tried before
+    /// the overwrite passes
+    pub fn tries_hardware_erase(&self) -> bool {
+        matches!(
+            self,
+            WipeStandard::Modern(Nist80088Config {
+                method: SanitizationMethod::Purge,
+                ..
+            }) | WipeStandard::Auto(_)
+        )
+    }
+
+    /// whether the wipe is read back at all; also decides whether each chunk is
+    /// read back right after it is written
+    pub fn verifies(&self) -> bool {
+        match self {
+            WipeStandard::Modern(config) => config.verify_level != VerificationLevel::None,
+            WipeStandard::Legacy(config) => config.extra_verification,
+            WipeStandard::Custom(config) => config.verify_each_pass,
+            WipeStandard::Auto(config) => config.verify_level != VerificationLevel::None,
+        }
+    }
+
+    /// the overwrite passes this standard performs in software, in order
+    ///
+    /// NIST Purge tries a hardware secure erase first where the device supports
+    /// it; these are the passes used otherwise. the automatic standard depends
+    /// on the storage, so before it is resolved these are the passes of Purge
+    pub fn passes(&self) -> Vec<WipePattern> {
+        match self {
+            WipeStandard::Modern(config) => config.method.patterns(),
+            WipeStandard::Legacy(config) => config.standard.get_patterns(),
+            WipeStandard::Custom(config) => config.passes.clone(),
+            WipeStandard::Auto(_) => SanitizationMethod::Purge.patterns(),
+        }
+    }
+}
+
+impl FromStr for WipeStandard {
+    type Err = WipeError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        NamedStandard::find(name)
+            .map(NamedStandard::standard)
+            .ok_or_else(|| WipeError::Config(format!("Unknown standard '{}'", name)))
+    }
+}
+
+impl fmt::Display for WipeStandard {
+    /// the name of the standard; custom configurations have none and show their passes
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WipeStandard::Modern(config) => match config.method {
+                SanitizationMethod::Purge => write!(f, "nist"),
+                SanitizationMethod::Clear => write!(f, "nist-clear"),
+            },
+            WipeStandard::Legacy(config) => write!(f, "{}", config.standard),
+            WipeStandard::Custom(config) => write!(f, "custom ({} passes)", config.passes.len()),
+            WipeStandard::Auto(_) => write!(f, "auto"),
+        }
+    }
+}
+
+impl FromStr for LegacyStandard {
+    type Err = WipeError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "dod" => Ok(LegacyStandard::Dod522022M),
+            "gutmann" => Ok(LegacyStandard::Gutmann),
+            "vsitr" => Ok(LegacyStandard::VsitrStandard),
+            _ => Err(WipeError::Config(format!(
+                "Unknown legacy standard '{}'",
+                name
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for LegacyStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LegacyStandard::Dod522022M => "dod",
+            LegacyStandard::Gutmann => "gutmann",
+            LegacyStandard::VsitrStandard => "vsitr",
+        })
+    }
+}
+
+impl FromStr for VerificationLevel {
+    type Err = WipeError;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        let lower = level.to_lowercase();
+        match lower.as_str() {
+            "none" => return Ok(VerificationLevel::None),
+            "basic" => return Ok(VerificationLevel::Basic),
+            "full" => return Ok(VerificationLevel::Full),
+            "enhanced" => return Ok(VerificationLevel::Enhanced),
+            "hash" => return Ok(VerificationLevel::Hash),
+            "sampled" => return Ok(VerificationLevel::Sampled(0.01)),
+            _ => {}
+        }
+
+        // sampled:5% or sampled:0.05
+        let share =
+            lower
+                .strip_prefix("sampled:")
+                .and_then(|share| match share.strip_suffix('%') {
+                    Some(percent) => percent.trim().parse::<f64>().ok().map(|p| p / 100.0),
+                    None => share.trim().parse::<f64>().ok(),
+                });
+        match share {
+            Some(share) if share > 0.0 && share <= 1.0 => Ok(VerificationLevel::Sampled(share)),
+            _ => Err(WipeError::Config(format!(
+                "Unknown verification level '{}'",
+                level
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for VerificationLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationLevel::None => write!(f, "none"),
+            VerificationLevel::Basic => write!(f, "basic"),
+            VerificationLevel::Full => write!(f, "full"),
+            VerificationLevel::Enhanced => write!(f, "enhanced"),
+            VerificationLevel::Hash => write!(f, "hash"),
+            // rounded so 0.05 doesn't show as 5.000000000000001%
+            VerificationLevel::Sampled(share) => {
+                write!(f, "sampled:{}%", (share * 1e6).round() / 1e4)
+            }
+        }
+    }
+}
+
+/// written as the name, so configuration files say `verify_level = "full"`
+impl Serialize for VerificationLevel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for VerificationLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let level = String::deserialize(deserializer)?;
+        level.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// written as the name, so configuration files say `standard = "gutmann"`
+impl Serialize for LegacyStandard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for LegacyStandard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let standard = String::deserialize(deserializer)?;
+        standard.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl SanitizationMethod {
+    /// overwrite passes for this method
+    pub fn patterns(&self) -> Vec<WipePattern> {
+        match self {
+            // single pass of random data
+            SanitizationMethod::Clear => vec![WipePattern::Random],
+            SanitizationMethod::Purge => vec![
+                WipePattern::Random, // random data pass
+                WipePattern::Zeros,  // zero pass
+                WipePattern::Ones,   // ones pass
+                WipePattern::Random, // final random pass
+            ],
+        }
+    }
 }
 
 impl LegacyStandard {
@@ -159,3 +533,143 @@ impl LegacyStandard {
         patterns
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_standards() {
+        let passes = |name| NamedStandard::find(name).unwrap().standard().passes().len();
+        assert_eq!(passes("nist"), 4);
+        assert_eq!(passes("NIST-Clear"), 1);
+        assert_eq!(passes("dod"), 3);
+        assert_eq!(passes("gutmann"), 35);
+        assert_eq!(passes("vsitr"), 7);
+        assert!(NamedStandard::find("unknown").is_none());
+        assert!(NamedStandard::ALL[0].standard().tries_hardware_erase());
+    }
+
+    #[test]
+    fn test_verifies() {
+        let unverified = WipeStandard::Custom(WipeConfig {
+            passes: vec![WipePattern::Zeros],
+            verify_each_pass: false,
+        });
+        assert!(!unverified.verifies());
+        let mut nist = NamedStandard::find("nist").unwrap().standard();
+        assert!(nist.verifies());
+        if let WipeStandard::Modern(config) = &mut nist {
+            config.verify_level = VerificationLevel::None;
+        }
+        assert!(!nist.verifies());
+    }
+
+    #[test]
+    fn test_auto_resolve() {
+        use crate::storage::StorageCapabilities;
+
+        let caps = |secure_erase, wear_leveling| StorageCapabilities {
+            supports_trim: wear_leveling,
+            supports_secure_erase: secure_erase,
+            supports_nvme_sanitize: false,
+            has_wear_leveling: wear_leveling,
+        };
+        let method = |storage: StorageType| match "auto"
+            .parse::<WipeStandard>()
+            .unwrap()
+            .resolve(&storage)
+        {
+            WipeStandard::Modern(config) => config.method,
+            other => panic!("expected a modern standard, got {:?}", other),
+        };
+        assert!(matches!(
+            method(StorageType::Hdd(caps(false, false))),
+            SanitizationMethod::Purge
+        ));
+        assert!(matches!(
+            method(StorageType::Ssd(caps(true, true))),
+            SanitizationMethod::Purge
+        ));
+        assert!(matches!(
+            method(StorageType::Ssd(caps(false, true))),
+            SanitizationMethod::Clear
+        ));
+        assert!(matches!(
+            method(StorageType::Flash(caps(false, true))),
+            SanitizationMethod::Clear
+        ));
+
+        let dod = NamedStandard::find("dod").unwrap().standard();
+        assert_eq!(
+            dod.resolve(&StorageType::Hdd(caps(false, false)))
+                .to_string(),
+            "dod"
+        );
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for name in ["nist", "nist-clear", "auto", "dod", "gutmann", "vsitr"] {
+            assert_eq!(name.parse::<WipeStandard>().unwrap().to_string(), name);
+        }
+        assert!("shred-it".parse::<WipeStandard>().is_err());
+        assert_eq!(
+            "Gutmann".parse::<LegacyStandard>().unwrap().to_string(),
+            "gutmann"
+        );
+
+        for level in ["none", "basic", "full", "enhanced", "hash", "sampled:5%"] {
+            assert_eq!(
+                level.parse::<VerificationLevel>().unwrap().to_string(),
+                level
+            );
+        }
+        assert_eq!(
+            "sampled".parse::<VerificationLevel>().unwrap(),
+            VerificationLevel::Sampled(0.01)
+        );
+        assert_eq!(
+            "sampled:0.25".parse::<VerificationLevel>().unwrap(),
+            VerificationLevel::Sampled(0.25)
+        );
+        assert!("sampled:150%".parse::<VerificationLevel>().is_err());
+        assert!("paranoid".parse::<VerificationLevel>().is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        #[derive(Serialize, Deserialize)]
+        struct File {
+            standard: WipeStandard,
+        }
+
+        let file: File = toml::from_str(
+            "[standard.modern]\nmethod = \"clear\"\nverify_level = \"sampled:2%\"\n",
+        )
+        .unwrap();
+        match &file.standard {
+            WipeStandard::Modern(config) => {
+                assert!(matches!(config.method, SanitizationMethod::Clear));
+                assert_eq!(config.verify_level, VerificationLevel::Sampled(0.02));
+            }
+            other => panic!("expected a modern standard, got {:?}", other),
+        }
+
+        let custom = File {
+            standard: WipeStandard::Custom(WipeConfig {
+                passes: vec![WipePattern::Random, WipePattern::Custom(vec![0x92, 0x49])],
+                verify_each_pass: true,
+            }),
+        };
+        let text = toml::to_string(&custom).unwrap();
+        let file: File = toml::from_str(&text).unwrap();
+        assert_eq!(file.standard.passes().len(), 2);
+        assert!(file.standard.verifies());
+
+        let legacy: File =
+            toml::from_str("[standard.legacy]\nstandard = \"dod\"\nextra_verification = false\n")
+                .unwrap();
+        assert_eq!(legacy.standard.to_string(), "dod");
+    }
+}
@@ -38,7 +38,7 @@ pub struct StorageCapabilities {
 }
 
 /// information about a storage device
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct StorageInfo {
     /// type of storage device and its capabilities
     pub device_type: StorageType,
@@ -47,11 +47,20 @@ pub struct StorageInfo {
     /// typically 512 or 4096 bytes
     pub block_size: usize,
 
+    /// size of the sectors the device writes internally, which writes are
+    /// aligned to; 4096 on 512e drives with a `block_size` of 512, the same
+    /// as `block_size` where the device doesn't say
+    pub physical_block_size: usize,
+
     /// total storage capacity in bytes
     pub total_size: u64,
 }
 
-impl StorageType {
+/// finds out what storage a path is on
+///
+/// detection goes through this trait so tests and embedders can put fake
+/// devices in front of code that would otherwise ask the operating system
+pub trait StorageProbe {
     /// detects storage type and capabilities from a file path
     ///
     /// # Arguments
@@ -59,7 +68,16 @@ impl StorageType {
     ///
     /// # Returns
     /// * `Result<StorageInfo>` - Information about the storage device
-    pub fn detect_from_path(path: &Path) -> Result<StorageInfo> {
+    fn probe(&self, path: &Path) -> Result<StorageInfo>;
+}
+
+/// asks the operating system: sysfs on linux, diskutil on macOS, GEOM on
+/// FreeBSD, disklabels on OpenBSD and NetBSD and the drive type on windows
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemProbe;
+
+impl StorageProbe for SystemProbe {
+    fn probe(&self, path: &Path) -> Result<StorageInfo> {
         // platform-specific implementations
         #[cfg(target_os = "linux")]
         {
@@ -73,14 +91,42 @@ impl StorageType {
         {
             Self::detect_storage_macos(path)
         }
-        #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+        #[cfg(target_os = "freebsd")]
+        {
+            Self::detect_storage_freebsd(path)
+        }
+        #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
         {
+            crate::bsd::detect_storage(path)
+        }
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "windows",
+            target_os = "macos",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        )))]
+        {
+            let _ = path;
             Err(crate::WipeError::UnsupportedOperation(
                 "Storage detection not supported on this platform".into(),
             ))
         }
     }
+}
+
+/// always reports the same device, whatever the path
+#[derive(Debug, Clone)]
+pub struct FixedProbe(pub StorageInfo);
+
+impl StorageProbe for FixedProbe {
+    fn probe(&self, _path: &Path) -> Result<StorageInfo> {
+        Ok(self.0.clone())
+    }
+}
 
+impl SystemProbe {
     /// linux-specific storage detection implementation
     #[cfg(target_os = "linux")]
     fn detect_storage_linux(path: &Path) -> Result<StorageInfo> {
@@ -94,16 +140,14 @@ impl StorageType {
         let device_name = canonical_path
             .file_name()
             .and_then(|name| name.to_str())
-            .and_then(|name| {
+            .map(|name| {
                 if name.starts_with("nvme") {
-                    Some(name.split('p').next().unwrap_or(name))
+                    name.split('p').next().unwrap_or(name)
                 } else {
-                    Some(name.trim_end_matches(char::is_numeric))
+                    name.trim_end_matches(char::is_numeric)
                 }
             })
-            .ok_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::Other, "Unable to determine device name")
-            })?;
+            .ok_or_else(|| std::io::Error::other("Unable to determine device name"))?;
 
         // Construct sysfs path
         let sysfs_path = PathBuf::from("/sys/block").join(device_name);
@@ -112,16 +156,17 @@ impl StorageType {
         let rotational_str = read_to_string(sysfs_path.join("queue/rotational"))?;
         let rotational = rotational_str.trim().parse::<u8>()?;
 
-        // Read device identifier
-        let device_id = read_to_string(sysfs_path.join("device/model"))
-            .unwrap_or_else(|_| String::from("Unknown"));
-
         // Determine if NVMe
         let is_nvme = device_name.starts_with("nvme");
 
-        // Read block size
+        // Read block size, and the physical one (BLKSSZGET and BLKPBSZGET)
         let block_size_str = read_to_string(sysfs_path.join("queue/logical_block_size"))?;
         let block_size = block_size_str.trim().parse::<usize>()?;
+        let physical_block_size = read_to_string(sysfs_path.join("queue/physical_block_size"))
+            .ok()
+            .and_then(|size| size.trim().parse::<usize>().ok())
+            .unwrap_or(block_size);
+        let sectors = crate::device::sector_sizes_from(block_size, physical_block_size);
 
         // Read device size in bytes
         let size_str = read_to_string(sysfs_path.join("size"))?;
@@ -153,7 +198,8 @@ impl StorageType {
 
         Ok(StorageInfo {
             device_type: storage_type,
-            block_size,
+            block_size: sectors.logical,
+            physical_block_size: sectors.physical,
             total_size,
         })
     }
@@ -170,14 +216,12 @@ impl StorageType {
             .components()
             .find(|c| {
                 if let std::path::Component::Normal(name) = c {
-                    name.to_str().map_or(false, |s| s.starts_with("/Volumes/"))
+                    name.to_str().is_some_and(|s| s.starts_with("/Volumes/"))
                 } else {
                     false
                 }
             })
-            .ok_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::Other, "Unable to determine volume")
-            })?;
+            .ok_or_else(|| std::io::Error::other("Unable to determine volume"))?;
 
         // run diskutil info command
         let output = Command::new("diskutil")
@@ -185,8 +229,7 @@ impl StorageType {
             .arg(volume_name)
             .output()?;
 
-        let info = str::from_utf8(&output.stdout)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        let info = str::from_utf8(&output.stdout).map_err(std::io::Error::other)?;
 
         // parse diskutil output
         let is_solid_state = info.contains("Solid State: Yes");
@@ -197,7 +240,7 @@ impl StorageType {
             .lines()
             .find(|line| line.contains("Device Block Size"))
             .and_then(|line| line.split(':').nth(1))
-            .and_then(|size| size.trim().split_whitespace().next())
+            .and_then(|size| size.split_whitespace().next())
             .and_then(|num| num.parse().ok())
             .unwrap_or(4096);
 
@@ -206,7 +249,7 @@ impl StorageType {
             .lines()
             .find(|line| line.contains("Total Size"))
             .and_then(|line| line.split(':').nth(1))
-            .and_then(|size| size.trim().split_whitespace().next())
+            .and_then(|size| size.split_whitespace().next())
             .and_then(|num| num.parse().ok())
             .unwrap_or(0);
 
@@ -234,40 +277,131 @@ impl StorageType {
             })
         };
 
+        // diskutil only shows the logical size; a device node can be asked
+        let physical_block_size = std::fs::File::open(path)
+            .ok()
+            .filter(|_| crate::device::is_block_device(path))
+            .and_then(|device| crate::device::sector_sizes(&device).ok())
+            .map_or(block_size, |sectors| sectors.physical.max(block_size));
+
         Ok(StorageInfo {
             device_type: storage_type,
             block_size,
+            physical_block_size,
             total_size,
         })
     }
 
+    /// FreeBSD storage detection, asking GEOM about the disk the path is on
+    #[cfg(target_os = "freebsd")]
+    fn detect_storage_freebsd(path: &Path) -> Result<StorageInfo> {
+        use std::process::Command;
+
+        // a device node is a provider itself; anything else is on the
+        // provider its filesystem was mounted from
+        let canonical_path = std::fs::canonicalize(path)?;
+        let provider = if canonical_path.starts_with("/dev") {
+            canonical_path.to_string_lossy().into_owned()
+        } else {
+            crate::filesystem::mount_info(&canonical_path)?.source
+        };
+        let disk = geom_disk_name(&provider).ok_or_else(|| {
+            std::io::Error::other(format!("{} is not on a disk GEOM knows", provider))
+        })?;
+
+        let output = Command::new("geom").args(["disk", "list", disk]).output()?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+            .into());
+        }
+        let geom = GeomDisk::parse(&String::from_utf8_lossy(&output.stdout));
+
+        // diskinfo tells whether the disk takes BIO_DELETE (TRIM/UNMAP)
+        let device = format!("/dev/{}", disk);
+        let supports_trim = Command::new("diskinfo")
+            .args(["-v", &device])
+            .output()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .any(|line| line.contains("# TRIM/UNMAP support") && line.contains("Yes"))
+            })
+            .unwrap_or(false);
+
+        // ATA security, the feature set behind secure erase, only on ada disks
+        let supports_secure_erase = disk.starts_with("ada")
+            && Command::new("camcontrol")
+                .args(["security", disk])
+                .output()
+                .map(|output| {
+                    String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+                        line.starts_with("Security supported") && line.trim_end().ends_with("yes")
+                    })
+                })
+                .unwrap_or(false);
+
+        let is_nvme = disk.starts_with("nvd") || disk.starts_with("nda");
+        let is_sd_card = disk.starts_with("mmcsd") || disk.starts_with("sdda");
+        let storage_type = if is_sd_card {
+            StorageType::Flash(StorageCapabilities {
+                supports_trim,
+                supports_secure_erase: false,
+                supports_nvme_sanitize: false,
+                has_wear_leveling: true,
+            })
+        } else if geom.rotation_rate == Some(0)
+            || (geom.rotation_rate.is_none() && (is_nvme || supports_trim))
+        {
+            // a rotation rate of 0 is how disks report being non-rotating
+            StorageType::Ssd(StorageCapabilities {
+                supports_trim,
+                supports_secure_erase,
+                supports_nvme_sanitize: is_nvme,
+                has_wear_leveling: true,
+            })
+        } else {
+            StorageType::Hdd(StorageCapabilities {
+                supports_trim: false,
+                supports_secure_erase,
+                supports_nvme_sanitize: false,
+                has_wear_leveling: false,
+            })
+        };
+
+        // GEOM reports the 4K physical sectors of 512e disks as the stripe size
+        let sectors = crate::device::sector_sizes_from(
+            geom.sector_size.unwrap_or(512),
+            geom.stripe_size.unwrap_or(0),
+        );
+        Ok(StorageInfo {
+            device_type: storage_type,
+            block_size: sectors.logical,
+            physical_block_size: sectors.physical,
+            total_size: geom.media_size.unwrap_or(0),
+        })
+    }
+
     /// windows-specific storage detection implementation
     #[cfg(target_os = "windows")]
     fn detect_storage_windows(path: &Path) -> Result<StorageInfo> {
         use std::ffi::OsStr;
         use std::os::windows::ffi::OsStrExt;
         use std::os::windows::fs::OpenOptionsExt;
-        use std::ptr;
-        use winapi::um::fileapi::{CreateFileW, GetDriveTypeW};
-        use winapi::um::handleapi::INVALID_HANDLE_VALUE;
-        use winapi::um::winioctl::{
-            PropertyStandardQuery, StorageDeviceProperty, STORAGE_DEVICE_DESCRIPTOR,
-            STORAGE_PROPERTY_QUERY, STORAGE_QUERY_TYPE,
-        };
+        use winapi::um::fileapi::GetDriveTypeW;
         use winapi::um::winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE};
 
         // get the root path (e.g., C:\ from C:\path\to\file)
         let root_path = path
             .ancestors()
             .find(|p| p.parent().is_none())
-            .ok_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::Other, "Unable to determine root path")
-            })?;
+            .ok_or_else(|| std::io::Error::other("Unable to determine root path"))?;
 
         // convert path to wide string for Windows API
-        let root_path_str = root_path.to_str().ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::Other, "Invalid path encoding")
-        })?;
+        let root_path_str = root_path
+            .to_str()
+            .ok_or_else(|| std::io::Error::other("Invalid path encoding"))?;
         let wide_path: Vec<u16> = OsStr::new(root_path_str)
             .encode_wide()
             .chain(Some(0))
@@ -276,34 +410,7 @@ impl StorageType {
         // get drive type
         let drive_type = unsafe { GetDriveTypeW(wide_path.as_ptr()) };
 
-        // open the volume
-        let handle = unsafe {
-            CreateFileW(
-                wide_path.as_ptr(),
-                0,
-                FILE_SHARE_READ | FILE_SHARE_WRITE,
-                ptr::null_mut(),
-                3, // OPEN_EXISTING
-                0,
-                ptr::null_mut(),
-            )
-        };
-
-        if handle == INVALID_HANDLE_VALUE {
-            return Err(std::io::Error::last_os_error().into());
-        }
-
-        // query storage device descriptor
-        let mut query = STORAGE_PROPERTY_QUERY {
-            PropertyId: StorageDeviceProperty,
-            QueryType: PropertyStandardQuery,
-            AdditionalParameters: [0u8; 1],
-        };
-
-        let mut descriptor: STORAGE_DEVICE_DESCRIPTOR = unsafe { std::mem::zeroed() };
-        let mut bytes_returned = 0u32;
-
-        // based on the drive type and device descriptor, determine storage type
+        // based on the drive type, determine storage type
         let storage_type = match drive_type {
             2 /* DRIVE_REMOVABLE */ => StorageType::Flash(StorageCapabilities {
                 supports_trim: false,
@@ -328,12 +435,42 @@ impl StorageType {
             }),
         };
 
+        // the storage access alignment of the device or the volume
+        let volume = if crate::device::is_block_device(path) {
+            path.to_path_buf()
+        } else {
+            let letter = root_path_str.chars().next().unwrap_or('C');
+            std::path::PathBuf::from(format!(r"\\.\{}:", letter))
+        };
+        let sectors = std::fs::OpenOptions::new()
+            .access_mode(0)
+            .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+            .open(&volume)
+            .ok()
+            .and_then(|device| crate::device::sector_sizes(&device).ok());
+
         Ok(StorageInfo {
             device_type: storage_type,
-            block_size: 4096, // default to 4K sectors for modern drives
-            total_size: 0,    // would need additional API calls to determine
+            // default to 4K sectors for modern drives
+            block_size: sectors.map_or(4096, |sectors| sectors.logical),
+            physical_block_size: sectors.map_or(4096, |sectors| sectors.physical),
+            total_size: 0, // would need additional API calls to determine
         })
     }
+}
+
+impl StorageType {
+    /// detects storage type and capabilities from a file path, asking the
+    /// operating system
+    ///
+    /// # Arguments
+    /// * `path` - Path to file or device to analyze
+    ///
+    /// # Returns
+    /// * `Result<StorageInfo>` - Information about the storage device
+    pub fn detect_from_path(path: &Path) -> Result<StorageInfo> {
+        SystemProbe.probe(path)
+    }
 
     /// checks if the device supports secure erase commands
     pub fn supports_secure_erase(&self) -> bool {
@@ -345,6 +482,15 @@ impl StorageType {
         }
     }
 
+    /// whether the device can erase itself, with ATA secure erase or NVMe
+    /// sanitize
+    pub fn supports_hardware_erase(&self) -> bool {
+        match self {
+            StorageType::Ssd(caps) => caps.supports_secure_erase || caps.supports_nvme_sanitize,
+            _ => self.supports_secure_erase(),
+        }
+    }
+
     /// checks if the device needs special handling for wear leveling
     pub fn requires_wear_leveling_handling(&self) -> bool {
         match self {
@@ -355,3 +501,110 @@ impl StorageType {
         }
     }
 }
+
+/// the disk a GEOM provider is on, e.g. "ada0" for /dev/ada0p2, da1s1a or
+/// nvd0p3.eli; None for providers that aren't named after a disk, like ZFS
+/// datasets and labels
+#[cfg(any(target_os = "freebsd", test))]
+pub(crate) fn geom_disk_name(provider: &str) -> Option<&str> {
+    let name = provider.strip_prefix("/dev/").unwrap_or(provider);
+    let letters = name.find(|c: char| !c.is_ascii_alphabetic())?;
+    let digits = name[letters..]
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(name.len() - letters);
+    (letters > 0 && digits > 0).then(|| &name[..letters + digits])
+}
+
+/// what `geom disk list <disk>` reports about a disk
+#[cfg(any(target_os = "freebsd", test))]
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct GeomDisk {
+    pub(crate) sector_size: Option<usize>,
+    /// the physical sector size on disks with larger physical than logical sectors
+    pub(crate) stripe_size: Option<usize>,
+    pub(crate) media_size: Option<u64>,
+    /// revolutions per minute; 0 for solid state, None when unknown
+    pub(crate) rotation_rate: Option<u32>,
+    /// vendor and model
+    pub(crate) descr: Option<String>,
+    /// serial number
+    pub(crate) ident: Option<String>,
+    /// logical unit id, the WWN of SCSI and SATA disks
+    pub(crate) lunid: Option<String>,
+}
+
+#[cfg(any(target_os = "freebsd", test))]
+impl GeomDisk {
+    pub(crate) fn parse(output: &str) -> Self {
+        let mut disk = GeomDisk::default();
+        for line in output.lines() {
+            let Some((key, text)) = line.trim().split_once(':') else {
+                continue;
+            };
+            // unknown strings are reported as "(null)"
+            let text = Some(text.trim().to_string()).filter(|t| !t.is_empty() && t != "(null)");
+            // "Mediasize: 500107862016 (466G)" carries the bytes first
+            let value = text
+                .as_deref()
+                .and_then(|t| t.split_whitespace().next())
+                .unwrap_or("");
+            match key {
+                "descr" => disk.descr = text,
+                "ident" => disk.ident = text,
+                "lunid" => disk.lunid = text,
+                "Sectorsize" => disk.sector_size = value.parse().ok(),
+                "Stripesize" => disk.stripe_size = value.parse().ok(),
+                "Mediasize" => disk.media_size = value.parse().ok(),
+                "rotationrate" => disk.rotation_rate = value.parse().ok(),
+                _ => {}
+            }
+        }
+        disk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geom_disks() {
+        assert_eq!(geom_disk_name("/dev/ada0p2"), Some("ada0"));
+        assert_eq!(geom_disk_name("da1s1a"), Some("da1"));
+        assert_eq!(geom_disk_name("/dev/nvd0p3.eli"), Some("nvd0"));
+        assert_eq!(geom_disk_name("/dev/mmcsd0"), Some("mmcsd0"));
+        assert_eq!(geom_disk_name("zroot/ROOT/default"), None);
+        assert_eq!(geom_disk_name("/dev/gpt/rootfs"), None);
+
+        let listing = "Geom name: ada0
+Providers:
+1. Name: ada0
+   Mediasize: 500107862016 (466G)
+   Sectorsize: 512
+   Stripesize: 4096
+   Mode: r2w2e4
+   descr: Samsung SSD 860 EVO 500GB
+   lunid: 5002538e40a1b2c3
+   ident: S3Z1NB0K123456A
+   rotationrate: 0
+   fwsectors: 63
+";
+        assert_eq!(
+            GeomDisk::parse(listing),
+            GeomDisk {
+                sector_size: Some(512),
+                stripe_size: Some(4096),
+                media_size: Some(500107862016),
+                rotation_rate: Some(0),
+                descr: Some("Samsung SSD 860 EVO 500GB".into()),
+                ident: Some("S3Z1NB0K123456A".into()),
+                lunid: Some("5002538e40a1b2c3".into()),
+            }
+        );
+        assert_eq!(GeomDisk::parse("   ident: (null)\n").ident, None);
+        assert_eq!(
+            GeomDisk::parse("   rotationrate: unknown\n").rotation_rate,
+            None
+        );
+    }
+}
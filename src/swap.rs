@@ -0,0 +1,412 @@
+use crate::{Result, Shredder, WipeError, WipeReport};
+use std::path::PathBuf;
+
+/// what backs a swap area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapKind {
+    /// a dedicated partition or block device
+    Partition,
+    /// a regular file on a mounted filesystem
+    File,
+}
+
+/// an active swap area as reported by the kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapArea {
+    /// device node or swapfile path
+    pub path: PathBuf,
+
+    /// partition or swapfile
+    pub kind: SwapKind,
+
+    /// usable size in bytes
+    pub size: u64,
+
+    /// swap priority; negative values were assigned by the kernel
+    pub priority: i32,
+
+    /// whether the kernel is currently swapping to it
+    pub active: bool,
+}
+
+/// lists the swap areas that are currently in use
+///
+/// # Returns
+/// * `Result<Vec<SwapArea>>` - Active swap partitions and swapfiles
+pub fn active_swap_areas() -> Result<Vec<SwapArea>> {
+    #[cfg(target_os = "linux")]
+    {
+        let swaps = std::fs::read_to_string("/proc/swaps")?;
+        Ok(parse_proc_swaps(&swaps))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(unsupported())
+    }
+}
+
+/// finds the swap area that hibernation (suspend to disk) writes its memory
+/// image to, as configured by the resume= kernel parameter
+///
+/// # Returns
+/// * `Result<Option<SwapArea>>` - Resume area, or None if hibernation has none configured
+pub fn resume_area() -> Result<Option<SwapArea>> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::resume_area()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(unsupported())
+    }
+}
+
+/// wipes a swap area and puts it back into service
+///
+/// an active area is disabled with swapoff (which pages its contents back
+/// into memory), overwritten with the shredder's standard, re-initialized
+/// with mkswap keeping its UUID and label so fstab entries still match, and
+/// enabled again with its original priority. swap is restored even when
+/// the wipe fails so the system isn't left without it.
+///
+/// # Arguments
+/// * `shredder` - Configured shredder used for the overwrite
+/// * `area` - Swap area from `active_swap_areas`
+///
+/// # Returns
+/// * `Result<WipeReport>` - Report of the overwrite
+pub fn wipe_swap(shredder: &Shredder, area: &SwapArea) -> Result<WipeReport> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::wipe_swap(shredder, area)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (shredder, area);
+        Err(unsupported())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> WipeError {
+    #[cfg(target_os = "macos")]
+    let message =
+        "macOS encrypts swap with a key that is discarded at shutdown, so there is nothing to wipe";
+    #[cfg(windows)]
+    let message = "pagefile.sys can't be wiped while Windows is running; set HKLM\\SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Memory Management\\ClearPageFileAtShutdown to 1 instead";
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let message = "Swap wiping is only supported on Linux";
+    WipeError::UnsupportedOperation(message.into())
+}
+
+/// parses /proc/swaps, whose sizes are in KiB and whose paths escape
+/// whitespace as octal (\040)
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_swaps(swaps: &str) -> Vec<SwapArea> {
+    swaps
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            let kind = match fields[1] {
+                "partition" => SwapKind::Partition,
+                "file" => SwapKind::File,
+                _ => return None,
+            };
+            Some(SwapArea {
+                path: PathBuf::from(crate::filesystem::unescape_mount_field(fields[0])),
+                kind,
+                size: fields[2].parse::<u64>().ok()? * 1024,
+                priority: fields[4].parse().ok()?,
+                active: true,
+            })
+        })
+        .collect()
+}
+
+/// identity stored in a swap header that mkswap should carry over
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SwapIdentity {
+    uuid: Option<String>,
+    label: Option<String>,
+}
+
+/// reads the UUID and label from the first page of a swap area
+///
+/// the header follows the 1024-byte boot block: version, last page and bad
+/// page count (4 bytes each), then a 16-byte UUID and a 16-byte label
+#[cfg(any(target_os = "linux", test))]
+fn parse_swap_header(page: &[u8]) -> SwapIdentity {
+    let mut identity = SwapIdentity::default();
+
+    if let Some(uuid) = page.get(1036..1052) {
+        if uuid.iter().any(|&b| b != 0) {
+            let hex: String = uuid.iter().map(|b| format!("{:02x}", b)).collect();
+            identity.uuid = Some(format!(
+                "{}-{}-{}-{}-{}",
+                &hex[0..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..32]
+            ));
+        }
+    }
+    if let Some(label) = page.get(1052..1068) {
+        let len = label.iter().position(|&b| b == 0).unwrap_or(label.len());
+        if len > 0 {
+            identity.label = Some(String::from_utf8_lossy(&label[..len]).into_owned());
+        }
+    }
+
+    identity
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::*;
+    use crate::filesystem;
+    use std::ffi::CString;
+    use std::io::Read;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+    use std::process::Command;
+
+    const SWAP_FLAG_PREFER: libc::c_int = 0x8000;
+    const SWAP_FLAG_PRIO_MASK: libc::c_int = 0x7fff;
+
+    pub(super) fn wipe_swap(shredder: &Shredder, area: &SwapArea) -> Result<WipeReport> {
+        let identity = read_identity(&area.path)?;
+        // /proc/swaps leaves out the header page, so take the real file size
+        let file_size = match area.kind {
+            SwapKind::File => std::fs::metadata(&area.path)?.len(),
+            SwapKind::Partition => 0,
+        };
+
+        if area.active {
+            log::info!("Disabling swap on {}", area.path.display());
+            swap_off(&area.path)?;
+        }
+
+        let wiped = shredder.wipe_with_report(&area.path);
+
+        // put the area back even if the wipe failed
+        let restored: Result<()> = (|| {
+            if area.kind == SwapKind::File {
+                recreate_swap_file(&area.path, file_size)?;
+            }
+            make_swap(&area.path, &identity)?;
+            if area.active {
+                swap_on(&area.path, area.priority)?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = &restored {
+            log::error!("Failed to re-enable swap on {}: {}", area.path.display(), e);
+        }
+
+        let report = wiped?;
+        restored?;
+        Ok(report)
+    }
+
+    pub(super) fn resume_area() -> Result<Option<SwapArea>> {
+        use std::os::unix::fs::MetadataExt;
+
+        let resume = match std::fs::read_to_string("/sys/power/resume") {
+            Ok(resume) => resume.trim().to_string(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let Some((major, minor)) = resume
+            .split_once(':')
+            .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+        else {
+            return Ok(None);
+        };
+        if (major, minor) == (0, 0) {
+            return Ok(None);
+        }
+        let device_number = libc::makedev(major, minor);
+        // a non-zero offset means the image goes into a swapfile on that device
+        let offset: u64 = std::fs::read_to_string("/sys/power/resume_offset")
+            .ok()
+            .and_then(|offset| offset.trim().parse().ok())
+            .unwrap_or(0);
+
+        let active = super::active_swap_areas()?;
+        if offset != 0 {
+            let swapfile = active.into_iter().find(|area| {
+                area.kind == SwapKind::File
+                    && std::fs::metadata(&area.path).is_ok_and(|m| m.dev() == device_number)
+            });
+            if swapfile.is_none() {
+                log::warn!("Resume swapfile on device {} is not active", resume);
+            }
+            return Ok(swapfile);
+        }
+
+        let device = std::fs::canonicalize(format!("/dev/block/{}", resume))?;
+        if let Some(area) = active.into_iter().find(|area| {
+            area.kind == SwapKind::Partition
+                && std::fs::canonicalize(&area.path).is_ok_and(|path| path == device)
+        }) {
+            return Ok(Some(area));
+        }
+
+        // a partition used only for hibernation isn't swapped to otherwise
+        let sectors: u64 = std::fs::read_to_string(format!("/sys/dev/block/{}/size", resume))
+            .ok()
+            .and_then(|size| size.trim().parse().ok())
+            .unwrap_or(0);
+        Ok(Some(SwapArea {
+            path: device,
+            kind: SwapKind::Partition,
+            size: sectors * 512,
+            priority: -1,
+            active: false,
+        }))
+    }
+
+    fn read_identity(path: &Path) -> Result<SwapIdentity> {
+        let mut page = vec![0u8; 4096];
+        let mut file = std::fs::File::open(path)?;
+        let read = file.read(&mut page)?;
+        page.truncate(read);
+        Ok(parse_swap_header(&page))
+    }
+
+    fn c_path(path: &Path) -> Result<CString> {
+        CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+            WipeError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Path contains a NUL byte",
+            ))
+        })
+    }
+
+    fn swap_off(path: &Path) -> Result<()> {
+        let c_path = c_path(path)?;
+        if unsafe { libc::swapoff(c_path.as_ptr()) } != 0 {
+            let err = std::io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ENOMEM) {
+                return Err(WipeError::UnsupportedOperation(format!(
+                    "Not enough free memory to page {} back in; close some applications and retry",
+                    path.display()
+                )));
+            }
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    fn swap_on(path: &Path, priority: i32) -> Result<()> {
+        let c_path = c_path(path)?;
+        let flags = if priority >= 0 {
+            SWAP_FLAG_PREFER | (priority & SWAP_FLAG_PRIO_MASK)
+        } else {
+            0
+        };
+        if unsafe { libc::swapon(c_path.as_ptr(), flags) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        log::info!("Re-enabled swap on {}", path.display());
+        Ok(())
+    }
+
+    /// recreates a swapfile that the wipe removed, fully allocated and
+    /// readable by root only
+    fn recreate_swap_file(path: &Path, size: u64) -> Result<()> {
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+
+        // btrfs only accepts swapfiles without copy-on-write
+        if filesystem::mount_info(path).is_ok_and(|info| info.is_copy_on_write()) {
+            filesystem::set_nocow(&file)?;
+        }
+
+        if unsafe { libc::fallocate(file.as_raw_fd(), 0, 0, size as libc::off_t) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        file.sync_all()?;
+        Ok(())
+    }
+
+    fn make_swap(path: &Path, identity: &SwapIdentity) -> Result<()> {
+        let mut command = Command::new("mkswap");
+        if let Some(uuid) = &identity.uuid {
+            command.args(["-U", uuid]);
+        }
+        if let Some(label) = &identity.label {
+            command.args(["-L", label]);
+        }
+        let output = command.arg(path).output()?;
+        if !output.status.success() {
+            return Err(WipeError::UnsupportedOperation(format!(
+                "mkswap failed on {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_swaps() {
+        let swaps = "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n\
+                     /dev/sda2                               partition\t8388604\t\t0\t\t-2\n\
+                     /var/swap\\040file                       file\t\t2097148\t\t1024\t\t10\n";
+        assert_eq!(
+            parse_proc_swaps(swaps),
+            vec![
+                SwapArea {
+                    path: PathBuf::from("/dev/sda2"),
+                    kind: SwapKind::Partition,
+                    size: 8388604 * 1024,
+                    priority: -2,
+                    active: true,
+                },
+                SwapArea {
+                    path: PathBuf::from("/var/swap file"),
+                    kind: SwapKind::File,
+                    size: 2097148 * 1024,
+                    priority: 10,
+                    active: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_swap_header() {
+        let mut page = vec![0u8; 4096];
+        page[1036..1052].copy_from_slice(&[
+            0x3f, 0x2a, 0x1c, 0x7e, 0x5b, 0x0d, 0x4e, 0x21, 0x9a, 0x61, 0x0c, 0x3d, 0x88, 0xe4,
+            0x12, 0x57,
+        ]);
+        page[1052..1056].copy_from_slice(b"swap");
+        assert_eq!(
+            parse_swap_header(&page),
+            SwapIdentity {
+                uuid: Some("3f2a1c7e-5b0d-4e21-9a61-0c3d88e41257".into()),
+                label: Some("swap".into()),
+            }
+        );
+        assert_eq!(parse_swap_header(&[0u8; 4096]), SwapIdentity::default());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::config::JobConfig;
+use crate::schedule::Schedule;
+use crate::Result;
+use std::path::Path;
+
+/// a generated unit file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    /// file name, e.g. "shredder-daemon.service"
+    pub name: String,
+
+    /// contents of the unit file
+    pub contents: String,
+}
+
+/// service that runs `shred daemon` with the given configuration file
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+/// * `config` - Absolute path of the configuration file
+///
+/// # Returns
+/// * `Unit` - shredder-daemon.service
+pub fn daemon_service(program: &Path, config: &Path) -> Unit {
+    Unit {
+        name: "shredder-daemon.service".to_string(),
+        contents: format!(
+            "[Unit]\n\
+             Description=shredder scheduled wipes\n\
+             After=local-fs.target network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             ExecStart={} daemon --config {}\n\
+             Restart=on-failure\n\
+             \n\
+             [Install]\n\
+             WantedBy=multi-user.target\n",
+            exec_quote(&program.to_string_lossy()),
+            exec_quote(&config.to_string_lossy())
+        ),
+    }
+}
+
+/// oneshot service and timer that run one job on its schedule, as an
+/// alternative to keeping `shred daemon` running
+///
+/// # Arguments
+/// * `program` - Absolute path of the shred binary
+/// * `config` - Absolute path of the configuration file
+/// * `job` - The job, whose cron schedule becomes OnCalendar= lines
+///
+/// # Returns
+/// * `Result<(Unit, Unit)>` - The .service and .timer, or a config error for a bad schedule
+pub fn job_units(program: &Path, config: &Path, job: &JobConfig) -> Result<(Unit, Unit)> {
+    let schedule = Schedule::parse(&job.schedule)?;
+    let base = format!("shredder-job-{}", unit_name(&job.name));
+
+    let service = Unit {
+        name: format!("{}.service", base),
+        contents: format!(
+            "[Unit]\n\
+             Description=shredder job '{}'\n\
+             After=local-fs.target\n\
+             \n\
+             [Service]\n\
+             Type=oneshot\n\
+             ExecStart={} job {} --config {}\n",
+            job.name,
+            exec_quote(&program.to_string_lossy()),
+            exec_quote(&job.name),
+            exec_quote(&config.to_string_lossy())
+        ),
+    };
+
+    let on_calendar: String = schedule
+        .to_on_calendar()
+        .iter()
+        .map(|expression| format!("OnCalendar={}\n", expression))
+        .collect();
+    let timer = Unit {
+        name: format!("{}.timer", base),
+        contents: format!(
+            "[Unit]\n\
+             Description=Run shredder job '{}' ({})\n\
+             \n\
+             [Timer]\n\
+             {}\
+             Persistent=true\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n",
+            job.name, job.schedule, on_calendar
+        ),
+    };
+
+    Ok((service, timer))
+}
+
+/// turns a job name into something usable in a unit file name
+fn unit_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// quotes an ExecStart= argument when it contains spaces, quotes or specifiers
+fn exec_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    if escaped != arg || arg.contains(char::is_whitespace) || arg.is_empty() {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_job_units() {
+        let job = JobConfig {
+            name: "print spool".into(),
+            schedule: "30 2 1 * sun".into(),
+            path: PathBuf::from("/var/spool/cups-pdf"),
+            standard: None,
+            verify: None,
+            report: None,
+        };
+        let (service, timer) = job_units(
+            Path::new("/usr/local/bin/shred"),
+            Path::new("/etc/shredder/config.toml"),
+            &job,
+        )
+        .unwrap();
+
+        assert_eq!(service.name, "shredder-job-print-spool.service");
+        assert!(service.contents.contains(
+            "ExecStart=/usr/local/bin/shred job \"print spool\" --config /etc/shredder/config.toml\n"
+        ));
+        assert_eq!(timer.name, "shredder-job-print-spool.timer");
+        assert!(timer
+            .contents
+            .contains("OnCalendar=*-*-01 02:30:00\nOnCalendar=Sun *-*-* 02:30:00\n"));
+    }
+
+    #[test]
+    fn test_exec_quote() {
+        assert_eq!(exec_quote("/usr/bin/shred"), "/usr/bin/shred");
+        assert_eq!(exec_quote("50% off"), "\"50%% off\"");
+        assert_eq!(exec_quote("$HOME"), "\"$$HOME\"");
+    }
+}
@@ -0,0 +1,253 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// something a wipe overwrites: the engine opens it, overwrites and verifies
+/// what it opened, and then lets the target decide what is left of it
+///
+/// implemented for regular files, block devices, the free space of a volume
+/// and handles opened elsewhere; new kinds only have to say how they are
+/// opened and removed. targets are shared with the thread a sandboxed wipe
+/// runs on, hence `Sync`
+pub trait WipeTarget: Sync {
+    /// path shown in reports, logs and progress
+    fn path(&self) -> &Path;
+
+    /// opens the target for overwriting
+    ///
+    /// # Arguments
+    /// * `sync_writes` - Open it so every write reaches the media before it
+    ///   returns, where the target allows
+    fn open(&self, sync_writes: bool) -> io::Result<File>;
+
+    /// opens the target again for reading back what was written, with its
+    /// cached pages dropped so reads come from the media
+    fn open_for_verify(&self) -> io::Result<File>;
+
+    /// what is done with the target once it has been overwritten and its
+    /// handle closed
+    fn remove(&self) -> io::Result<()>;
+}
+
+/// a regular file, unlinked after it is overwritten
+#[derive(Debug, Clone)]
+pub struct RegularFile {
+    path: PathBuf,
+}
+
+impl RegularFile {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl WipeTarget for RegularFile {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(&self, sync_writes: bool) -> io::Result<File> {
+        open_for_writing(&self.path, sync_writes)
+    }
+
+    fn open_for_verify(&self) -> io::Result<File> {
+        crate::open_uncached(&self.path)
+    }
+
+    fn remove(&self) -> io::Result<()> {
+        std::fs::remove_file(&self.path)
+    }
+}
+
+/// a whole disk or partition; its device node is left in place
+#[derive(Debug, Clone)]
+pub struct BlockDevice {
+    path: PathBuf,
+}
+
+impl BlockDevice {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl WipeTarget for BlockDevice {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(&self, sync_writes: bool) -> io::Result<File> {
+        open_for_writing(&self.path, sync_writes)
+    }
+
+    fn open_for_verify(&self) -> io::Result<File> {
+        crate::open_uncached(&self.path)
+    }
+
+    fn remove(&self) -> io::Result<()> {
+        log::debug!("Leaving device node {} in place", self.path.display());
+        Ok(())
+    }
+}
+
+/// the free space of the volume a directory is on, claimed by a file that is
+/// grown to fill it and removed once it is overwritten
+///
+/// overwrites what deleted files left behind. a little space is left free so
+/// the filesystem can still write its own metadata
+#[derive(Debug, Clone)]
+pub struct FreeSpace {
+    path: PathBuf,
+}
+
+impl FreeSpace {
+    /// space left free on the volume
+    const SLACK: u64 = 16 * 1024 * 1024;
+
+    /// # Arguments
+    /// * `dir` - Directory on the volume whose free space is overwritten
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            path: dir
+                .as_ref()
+                .join(format!(".shredder-free-space-{}", std::process::id())),
+        }
+    }
+}
+
+impl WipeTarget for FreeSpace {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(&self, sync_writes: bool) -> io::Result<File> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let size = free_bytes(dir)?.saturating_sub(Self::SLACK);
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&self.path)?;
+        let file = open_for_writing(&self.path, sync_writes)?;
+        if let Err(e) = file.set_len(size) {
+            drop(file);
+            let _ = std::fs::remove_file(&self.path);
+            return Err(e);
+        }
+        Ok(file)
+    }
+
+    fn open_for_verify(&self) -> io::Result<File> {
+        crate::open_uncached(&self.path)
+    }
+
+    fn remove(&self) -> io::Result<()> {
+        std::fs::remove_file(&self.path)
+    }
+}
+
+/// a file someone else opened, e.g. one that is already unlinked but still
+/// held open, or one passed in by a sandboxed caller. the handle is used as it
+/// is, so `sync_writes` doesn't apply, and it is left open for its owner
+#[derive(Debug)]
+pub struct OpenHandle {
+    file: File,
+    path: PathBuf,
+}
+
+impl OpenHandle {
+    /// # Arguments
+    /// * `file` - Handle opened for reading and writing
+    /// * `path` - Name to show for it in reports and logs
+    pub fn new<P: AsRef<Path>>(file: File, path: P) -> Self {
+        Self {
+            file,
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl WipeTarget for OpenHandle {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(&self, _sync_writes: bool) -> io::Result<File> {
+        self.file.try_clone()
+    }
+
+    fn open_for_verify(&self) -> io::Result<File> {
+        let file = self.file.try_clone()?;
+        crate::drop_cached_pages(&file);
+        Ok(file)
+    }
+
+    fn remove(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// opens a path for overwriting, with synchronous writes if requested
+fn open_for_writing(path: &Path, sync_writes: bool) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true);
+    if sync_writes {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.custom_flags(libc::O_DSYNC);
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            options.custom_flags(winapi::um::winbase::FILE_FLAG_WRITE_THROUGH);
+        }
+    }
+    options.open(path)
+}
+
+/// bytes an unprivileged user can still write to the volume `dir` is on
+#[cfg(unix)]
+fn free_bytes(dir: &Path) -> io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(dir.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn free_bytes(dir: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available: winapi::um::winnt::ULARGE_INTEGER = unsafe { std::mem::zeroed() };
+    let success = unsafe {
+        winapi::um::fileapi::GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if success == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { *available.QuadPart() })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn free_bytes(_dir: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Free space can't be measured on this platform",
+    ))
+}
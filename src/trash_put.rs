@@ -0,0 +1,506 @@
+//! `trash-put` with a grace period: items are moved into the freedesktop.org
+//! trash as usual, and shredded once the grace period is over unless they
+//! were restored or removed from the trash in the meantime
+//!
+//! an item goes into the trash directory on its own filesystem, the home trash
+//! ($XDG_DATA_HOME/Trash) or the `.Trash/<uid>` or `.Trash-<uid>` directory at
+//! the top of its mount, with an `info/*.trashinfo` record, so file managers
+//! list it and can put it back. what is due to be shredded is kept in a pending
+//! list in the user's state directory, along with the device and inode of each
+//! trashed item, so an item that was restored and replaced by another of the
+//! same name is not shredded in its place. the list is shared by concurrent
+//! runs through an advisory lock next to it
+//!
+//! only the user running the tool is considered, not the invoking user under
+//! sudo: the items are moved, not wiped, so they belong in their own trash
+
+use crate::{Result, WipeError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// an item waiting in the trash to be shredded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pending {
+    /// where the item is now, in the `files` directory of a trash
+    pub trashed: PathBuf,
+
+    /// its `.trashinfo` record
+    pub info: PathBuf,
+
+    /// where it was before it was trashed
+    pub original: PathBuf,
+
+    /// when it is due to be shredded, in seconds since the unix epoch
+    pub due: u64,
+
+    /// device of the trashed item
+    pub device: u64,
+
+    /// inode of the trashed item, to tell it from a later one of the same name
+    pub inode: u64,
+
+    /// how many times shredding it has failed so far
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl Pending {
+    /// whether the item is still the one that was trashed
+    fn still_trashed(&self) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            std::fs::symlink_metadata(&self.trashed)
+                .is_ok_and(|metadata| metadata.dev() == self.device && metadata.ino() == self.inode)
+        }
+        #[cfg(not(unix))]
+        {
+            false
+        }
+    }
+
+    /// removes the `.trashinfo` record once the item is shredded, so the
+    /// trash doesn't list it any more
+    pub fn forget(&self) -> Result<()> {
+        match std::fs::remove_file(&self.info) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// how long an item that couldn't be shredded waits before it is tried again,
+/// doubled after each further failure up to `MAX_RETRY_SECS`
+const RETRY_SECS: u64 = 600;
+const MAX_RETRY_SECS: u64 = 24 * 3600;
+
+/// the pending list as saved
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PendingFile {
+    #[serde(default)]
+    pending: Vec<Pending>,
+}
+
+/// moves `path` into the trash of its filesystem and adds it to the pending
+/// list, to be shredded `grace_secs` from now
+///
+/// # Arguments
+/// * `path` - File, directory or symlink to trash
+/// * `grace_secs` - Seconds the item stays in the trash before it is due
+///
+/// # Returns
+/// * `Result<Pending>` - The trashed item and when it is due
+#[cfg(target_os = "linux")]
+pub fn defer(path: &Path, grace_secs: u64) -> Result<Pending> {
+    use std::os::unix::fs::MetadataExt;
+
+    let original = std::path::absolute(path)?;
+    // the item itself is moved, so a trailing symlink is not followed
+    let metadata = std::fs::symlink_metadata(&original)?;
+    let trash = trash_dir(&original, metadata.dev())?;
+    let files = trash.join("files");
+    let info_dir = trash.join("info");
+    for dir in [&files, &info_dir] {
+        create_private_dir(dir)?;
+    }
+
+    // the spec records paths on other mounts relative to the top of the mount
+    let recorded = match trash.parent() {
+        Some(top) if !trash.starts_with(data_home()?) => original
+            .strip_prefix(top)
+            .unwrap_or(&original)
+            .to_path_buf(),
+        _ => original.clone(),
+    };
+    let record = trash_info(&recorded, &deletion_date());
+
+    let name = original
+        .file_name()
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Cannot trash {}", original.display()),
+            )
+        })?
+        .to_os_string();
+    let (trashed, info) = reserve(&files, &info_dir, &name, &record)?;
+    if let Err(e) = std::fs::rename(&original, &trashed) {
+        let _ = std::fs::remove_file(&info);
+        return Err(e.into());
+    }
+
+    let metadata = std::fs::symlink_metadata(&trashed)?;
+    let pending = Pending {
+        trashed,
+        info,
+        original,
+        due: now_secs() + grace_secs,
+        device: metadata.dev(),
+        inode: metadata.ino(),
+        attempts: 0,
+    };
+    update(|list| list.push(pending.clone()))?;
+    Ok(pending)
+}
+
+/// moving items to the trash needs the freedesktop.org trash layout
+#[cfg(not(target_os = "linux"))]
+pub fn defer(_path: &Path, _grace_secs: u64) -> Result<Pending> {
+    Err(WipeError::UnsupportedOperation(
+        "A grace period before shredding is only supported on Linux".into(),
+    ))
+}
+
+/// takes the items whose grace period is over off the pending list
+///
+/// items that were restored or removed from the trash since are dropped
+/// without being returned
+///
+/// # Returns
+/// * `Result<Vec<Pending>>` - Trashed items to shred now
+pub fn take_due() -> Result<Vec<Pending>> {
+    let now = now_secs();
+    let mut due = Vec::new();
+    update(|list| {
+        let (ready, waiting): (Vec<Pending>, Vec<Pending>) =
+            list.drain(..).partition(|pending| pending.due <= now);
+        *list = waiting;
+        due = ready;
+    })?;
+    due.retain(Pending::still_trashed);
+    Ok(due)
+}
+
+/// puts items that couldn't be shredded back on the pending list, due again
+/// after a wait that grows with every failure
+///
+/// # Arguments
+/// * `failed` - Items taken with `take_due` whose shredding failed
+pub fn retry_later(failed: Vec<Pending>) -> Result<()> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+    let now = now_secs();
+    update(|list| {
+        list.extend(failed.into_iter().map(|mut pending| {
+            let wait = (RETRY_SECS << pending.attempts.min(8)).min(MAX_RETRY_SECS);
+            pending.attempts += 1;
+            pending.due = now + wait;
+            pending
+        }))
+    })
+}
+
+/// when the next item on the pending list is due
+///
+/// # Returns
+/// * `Result<Option<u64>>` - Seconds since the unix epoch, or None if nothing is pending
+pub fn next_due() -> Result<Option<u64>> {
+    let mut next = None;
+    update(|list| {
+        list.retain(Pending::still_trashed);
+        next = list.iter().map(|pending| pending.due).min();
+    })?;
+    Ok(next)
+}
+
+/// takes the lock that keeps to one the processes waiting to shred pending
+/// items; held until the returned file is dropped
+///
+/// # Returns
+/// * `Result<Option<std::fs::File>>` - The held lock, or None if another process holds it
+pub fn purger_lock() -> Result<Option<std::fs::File>> {
+    let file = open_lock(&pending_path().with_extension("purger.lock"))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let error = std::io::Error::last_os_error();
+            return match error.kind() {
+                std::io::ErrorKind::WouldBlock => Ok(None),
+                _ => Err(error.into()),
+            };
+        }
+    }
+    Ok(Some(file))
+}
+
+/// current time in seconds since the unix epoch
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// the pending list: trash-pending.toml in $XDG_STATE_HOME/shredder, falling
+/// back to ~/.local/state, or in %LOCALAPPDATA%\shredder on windows
+pub fn pending_path() -> PathBuf {
+    #[cfg(windows)]
+    let base = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")));
+
+    base.unwrap_or_else(std::env::temp_dir)
+        .join("shredder")
+        .join("trash-pending.toml")
+}
+
+/// changes the pending list under its lock and saves it
+fn update(change: impl FnOnce(&mut Vec<Pending>)) -> Result<()> {
+    let path = pending_path();
+    let lock = open_lock(&path.with_extension("lock"))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+        if unsafe { libc::flock(lock.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+
+    let mut file: PendingFile = match std::fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text)
+            .map_err(|e| WipeError::Config(format!("{}: {}", path.display(), e)))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => PendingFile::default(),
+        Err(e) => return Err(e.into()),
+    };
+    change(&mut file.pending);
+
+    let text = toml::to_string(&file).map_err(|e| WipeError::Report(e.to_string()))?;
+    let temp_path = path.with_extension("toml.tmp");
+    std::fs::write(&temp_path, text)?;
+    std::fs::rename(&temp_path, &path)?;
+    drop(lock);
+    Ok(())
+}
+
+fn open_lock(path: &Path) -> Result<std::fs::File> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?)
+}
+
+/// $XDG_DATA_HOME, falling back to ~/.local/share
+#[cfg(target_os = "linux")]
+fn data_home() -> Result<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Could not determine home directory",
+            )
+            .into()
+        })
+}
+
+/// the trash directory on the filesystem `device`, where `path` is
+///
+/// the home trash if it is on that filesystem, otherwise `.Trash/<uid>` at the
+/// top of the mount if the administrator set up a sticky, non-symlink `.Trash`
+/// there, otherwise `.Trash-<uid>`
+#[cfg(target_os = "linux")]
+fn trash_dir(path: &Path, device: u64) -> Result<PathBuf> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let home_trash = data_home()?.join("Trash");
+    create_private_dir(&home_trash)?;
+    if std::fs::metadata(&home_trash)?.dev() == device {
+        return Ok(home_trash);
+    }
+
+    let top = crate::filesystem::mount_info(path)?.mount_point;
+    let uid = unsafe { libc::getuid() };
+    let shared = top.join(".Trash");
+    let usable = std::fs::symlink_metadata(&shared).is_ok_and(|metadata| {
+        metadata.is_dir() && metadata.permissions().mode() & libc::S_ISVTX != 0
+    });
+    if usable {
+        let trash = shared.join(uid.to_string());
+        if create_private_dir(&trash).is_ok() {
+            return Ok(trash);
+        }
+    }
+    let trash = top.join(format!(".Trash-{}", uid));
+    create_private_dir(&trash)?;
+    Ok(trash)
+}
+
+/// creates a directory only its owner can enter, along with missing parents
+#[cfg(target_os = "linux")]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+}
+
+/// claims a name for the item in the trash by creating its `.trashinfo`
+/// exclusively, adding .2, .3, ... to the name while it is taken
+///
+/// # Returns
+/// * `Result<(PathBuf, PathBuf)>` - Where the item goes and its record
+#[cfg(target_os = "linux")]
+fn reserve(
+    files: &Path,
+    info_dir: &Path,
+    name: &std::ffi::OsStr,
+    record: &str,
+) -> Result<(PathBuf, PathBuf)> {
+    use std::io::Write;
+
+    for n in 1.. {
+        let mut candidate = name.to_os_string();
+        if n > 1 {
+            candidate.push(format!(".{}", n));
+        }
+        let mut info_name = candidate.clone();
+        info_name.push(".trashinfo");
+        let info = info_dir.join(info_name);
+        let trashed = files.join(&candidate);
+        if std::fs::symlink_metadata(&trashed).is_ok() {
+            continue;
+        }
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info)
+        {
+            Ok(mut file) => {
+                file.write_all(record.as_bytes())?;
+                return Ok((trashed, info));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    unreachable!()
+}
+
+/// the contents of a `.trashinfo` record
+#[cfg(target_os = "linux")]
+fn trash_info(path: &Path, deletion_date: &str) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(path),
+        deletion_date
+    )
+}
+
+/// percent-encodes a path the way the trash spec asks, keeping the slashes
+#[cfg(target_os = "linux")]
+fn percent_encode(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// local time as YYYY-MM-DDThh:mm:ss
+#[cfg(target_os = "linux")]
+fn deletion_date() -> String {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        libc::localtime_r(&now, &mut tm);
+    }
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_info() {
+        let record = trash_info(
+            Path::new("/home/user/Monthly report (final).odt"),
+            "2026-10-15T09:30:00",
+        );
+        assert_eq!(
+            record,
+            "[Trash Info]\nPath=/home/user/Monthly%20report%20%28final%29.odt\n\
+             DeletionDate=2026-10-15T09:30:00\n"
+        );
+        assert_eq!(percent_encode(Path::new("a%b/ü")), "a%25b/%C3%BC");
+    }
+
+    #[test]
+    fn test_defer_and_take_due() {
+        let dir = tempfile::tempdir().unwrap();
+        // the pending list and home trash go where the environment points
+        std::env::set_var("XDG_STATE_HOME", dir.path().join("state"));
+        std::env::set_var("XDG_DATA_HOME", dir.path().join("data"));
+
+        let now = dir.path().join("now.txt");
+        let later = dir.path().join("later.txt");
+        let restored = dir.path().join("restored.txt");
+        for path in [&now, &later, &restored] {
+            std::fs::write(path, b"secret").unwrap();
+        }
+
+        let deferred = defer(&now, 0).unwrap();
+        assert!(!now.exists());
+        assert!(deferred.trashed.exists());
+        let info = std::fs::read_to_string(&deferred.info).unwrap();
+        assert!(info.contains(&format!("Path={}", percent_encode(&now))));
+        defer(&later, 3600).unwrap();
+        let put_back = defer(&restored, 0).unwrap();
+        std::fs::rename(&put_back.trashed, &restored).unwrap();
+
+        // the same name again gets a name of its own in the trash
+        std::fs::write(&now, b"another").unwrap();
+        let again = defer(&now, 3600).unwrap();
+        assert_ne!(again.trashed, deferred.trashed);
+
+        assert_eq!(take_due().unwrap(), vec![deferred.clone()]);
+        assert!(take_due().unwrap().is_empty());
+        assert!(next_due().unwrap().is_some_and(|due| due > now_secs()));
+
+        // one that couldn't be shredded goes back on the list, waiting longer
+        // after every failure
+        // when the retried item is due, which is then moved up to now
+        let retried_due = |attempts: u32| {
+            let mut due = 0;
+            update(|list| {
+                let pending = list.iter_mut().find(|p| p.trashed == deferred.trashed);
+                let pending = pending.unwrap();
+                assert_eq!(pending.attempts, attempts);
+                due = std::mem::take(&mut pending.due);
+            })
+            .unwrap();
+            due
+        };
+        retry_later(vec![deferred.clone()]).unwrap();
+        assert!(retried_due(1) >= now_secs() + RETRY_SECS - 1);
+        let failed = take_due().unwrap();
+        assert_eq!(failed.len(), 1);
+        retry_later(failed).unwrap();
+        assert!(retried_due(2) >= now_secs() + 2 * RETRY_SECS - 1);
+        assert!(deferred.trashed.exists());
+    }
+}
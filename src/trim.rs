@@ -1,14 +1,15 @@
 use crate::Result;
 use std::fs::File;
 
+/// FITRIM ioctl command, _IOWR('X', 121, struct fstrim_range)
+#[cfg(target_os = "linux")]
+pub(crate) const FITRIM: u64 = 0xc018_5879;
+
 #[cfg(target_os = "linux")]
 pub fn perform_trim(file: &mut File) -> Result<()> {
     use std::os::unix::io::AsRawFd;
 
     unsafe {
-        // FITRIM ioctl command
-        let FITRIM: u64 = 0x40086601;
-
         #[repr(C)]
         struct FtrimRange {
             start: u64,
@@ -48,75 +49,39 @@ pub fn perform_trim(file: &mut File) -> Result<()> {
     }
 }
 
+/// tells the filesystem with FSCTL_FILE_LEVEL_TRIM that the whole file is no
+/// longer in use, which it passes down to the device as TRIM
 #[cfg(target_os = "windows")]
 pub fn perform_trim(file: &mut File) -> Result<()> {
     use std::os::windows::io::AsRawHandle;
     use std::ptr;
     use winapi::shared::minwindef::DWORD;
-    use winapi::um::fileapi::DeviceIoControl;
-    use winapi::um::winioctl::*;
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winioctl::FSCTL_FILE_LEVEL_TRIM;
 
+    // FILE_LEVEL_TRIM with a single FILE_LEVEL_TRIM_RANGE, which winapi leaves out
     #[repr(C)]
-    struct DEVICE_MANAGE_DATA_SET_ATTRIBUTES {
-        size: DWORD,
-        action: DWORD,
-        flags: DWORD,
-        parameter_block_offset: DWORD,
-        parameter_block_length: DWORD,
-        data_set_ranges_offset: DWORD,
-        data_set_ranges_length: DWORD,
+    struct FileLevelTrim {
+        key: DWORD,
+        num_ranges: DWORD,
+        offset: u64,
+        length: u64,
     }
 
-    #[repr(C)]
-    struct DEVICE_DATA_SET_RANGE {
-        starting_offset: i64,
-        length_in_bytes: u64,
-    }
-
-    // get file size
-    let file_size = file.metadata()?.len();
-
-    // create the parameter block
-    let range = DEVICE_DATA_SET_RANGE {
-        starting_offset: 0,
-        length_in_bytes: file_size,
+    let mut trim = FileLevelTrim {
+        key: 0,
+        num_ranges: 1,
+        offset: 0,
+        length: file.metadata()?.len(),
     };
-
-    let attrs = DEVICE_MANAGE_DATA_SET_ATTRIBUTES {
-        size: std::mem::size_of::<DEVICE_MANAGE_DATA_SET_ATTRIBUTES>() as DWORD,
-        action: DEVICEDSMACTION_TRIM as DWORD,
-        flags: DEVICEDSMFLAGS_TRIM_SKIP_MAPPED_RANGES,
-        parameter_block_offset: 0,
-        parameter_block_length: 0,
-        data_set_ranges_offset: std::mem::size_of::<DEVICE_MANAGE_DATA_SET_ATTRIBUTES>() as DWORD,
-        data_set_ranges_length: std::mem::size_of::<DEVICE_DATA_SET_RANGE>() as DWORD,
-    };
-
-    // create buffer that contains both structures
-    let total_size = std::mem::size_of::<DEVICE_MANAGE_DATA_SET_ATTRIBUTES>()
-        + std::mem::size_of::<DEVICE_DATA_SET_RANGE>();
-    let mut buffer = vec![0u8; total_size];
-
-    // copy structures to buffer
-    unsafe {
-        let attrs_ptr = buffer.as_mut_ptr() as *mut DEVICE_MANAGE_DATA_SET_ATTRIBUTES;
-        ptr::write(attrs_ptr, attrs);
-
-        let range_ptr = buffer
-            .as_mut_ptr()
-            .add(std::mem::size_of::<DEVICE_MANAGE_DATA_SET_ATTRIBUTES>())
-            as *mut DEVICE_DATA_SET_RANGE;
-        ptr::write(range_ptr, range);
-    }
-
     let mut bytes_returned: DWORD = 0;
 
     let success = unsafe {
         DeviceIoControl(
             file.as_raw_handle() as *mut _,
             FSCTL_FILE_LEVEL_TRIM,
-            buffer.as_mut_ptr() as *mut _,
-            total_size as DWORD,
+            &mut trim as *mut _ as *mut _,
+            std::mem::size_of::<FileLevelTrim>() as DWORD,
             ptr::null_mut(),
             0,
             &mut bytes_returned,
@@ -131,7 +96,42 @@ pub fn perform_trim(file: &mut File) -> Result<()> {
     }
 }
 
-#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+/// discards the whole device with DIOCGDELETE, which GEOM passes down as
+/// TRIM or UNMAP
+#[cfg(target_os = "freebsd")]
+pub fn perform_trim(file: &mut File) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::io::AsRawFd;
+
+    // _IOR('d', 129, off_t) and _IOW('d', 136, off_t[2]) from <sys/disk.h>
+    const DIOCGMEDIASIZE: libc::c_ulong = 0x40086481;
+    const DIOCGDELETE: libc::c_ulong = 0x80106488;
+
+    // files are left to the filesystem, which trims the blocks it frees when
+    // mounted with TRIM (tunefs -t enable, zpool set autotrim=on)
+    if !file.metadata()?.file_type().is_char_device() {
+        log::debug!("Not a device; leaving TRIM to the filesystem");
+        return Ok(());
+    }
+
+    let mut size: libc::off_t = 0;
+    if unsafe { libc::ioctl(file.as_raw_fd(), DIOCGMEDIASIZE, &mut size) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let range: [libc::off_t; 2] = [0, size];
+    if unsafe { libc::ioctl(file.as_raw_fd(), DIOCGDELETE, &range) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().into())
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "windows"
+)))]
 pub fn perform_trim(_file: &mut File) -> Result<()> {
     Err(crate::WipeError::UnsupportedOperation(
         "TRIM not supported on this platform".into(),
@@ -0,0 +1,453 @@
+//! `shred tui`: browse for targets, pick a standard and watch wipes run
+//!
+//! drawn with ratatui on a crossterm terminal
+
+use super::{build_shredder, fallback_storage, format_size, with_overrides, Cli};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+use shredder::{
+    config::Config, progress::Progress, standards::NamedStandard, storage::StorageType,
+};
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// a key press
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Char(char),
+    Other,
+}
+
+/// what the keyboard currently controls
+enum Mode {
+    Browse,
+    ConfirmWipe,
+    ConfirmQuit,
+}
+
+/// state of one wipe started from the UI
+struct Job {
+    path: PathBuf,
+    standard: &'static str,
+    pass: usize,
+    passes: usize,
+    fraction: f64,
+    status: JobStatus,
+}
+
+enum JobStatus {
+    Running,
+    Done(usize),
+    Failed(String),
+}
+
+struct Entry {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+    size: u64,
+}
+
+struct App {
+    dir: PathBuf,
+    entries: Vec<Entry>,
+    cursor: usize,
+    list: ListState,
+    selected: BTreeSet<PathBuf>,
+    standard: usize,
+    mode: Mode,
+    message: Option<String>,
+    jobs: Arc<Mutex<Vec<Job>>>,
+    finished: usize,
+}
+
+/// runs the terminal UI until the user quits
+pub(crate) fn run(cli: &Cli, config: &Config, dir: Option<&Path>) -> io::Result<()> {
+    let start = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => std::env::current_dir()?,
+    };
+
+    let mut app = App {
+        dir: PathBuf::new(),
+        entries: Vec::new(),
+        cursor: 0,
+        list: ListState::default(),
+        selected: BTreeSet::new(),
+        standard: NamedStandard::ALL
+            .iter()
+            .position(|named| cli.standard.eq_ignore_ascii_case(named.name))
+            .unwrap_or(0),
+        mode: Mode::Browse,
+        message: None,
+        jobs: Arc::new(Mutex::new(Vec::new())),
+        finished: 0,
+    };
+    app.open(&start);
+
+    let mut terminal = Terminal::enter()?;
+    loop {
+        // wiped entries drop out of the listing as their jobs finish
+        let total = lock(&app.jobs).len();
+        let finished = total - app.running();
+        if finished != app.finished {
+            app.finished = finished;
+            app.reload();
+        }
+        terminal.0.draw(|frame| draw(frame, &mut app))?;
+        if !event::poll(Duration::from_millis(200))? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => parse_key(key),
+            _ => continue,
+        };
+        app.message = None;
+
+        match app.mode {
+            Mode::Browse => match key {
+                Key::Up => app.cursor = app.cursor.saturating_sub(1),
+                Key::Down if app.cursor + 1 < app.entries.len() => app.cursor += 1,
+                Key::Right | Key::Enter => {
+                    if let Some(entry) = app.entries.get(app.cursor) {
+                        if entry.is_dir {
+                            let path = entry.path.clone();
+                            app.open(&path);
+                        }
+                    }
+                }
+                Key::Left | Key::Backspace => {
+                    if let Some(parent) = app.dir.parent().map(Path::to_path_buf) {
+                        app.open(&parent);
+                    }
+                }
+                Key::Char(' ') => {
+                    if let Some(entry) = app.entries.get(app.cursor) {
+                        if !app.selected.remove(&entry.path) {
+                            app.selected.insert(entry.path.clone());
+                        }
+                        if app.cursor + 1 < app.entries.len() {
+                            app.cursor += 1;
+                        }
+                    }
+                }
+                Key::Char('s') => app.standard = (app.standard + 1) % NamedStandard::ALL.len(),
+                Key::Char('w') => {
+                    if app.selected.is_empty() {
+                        app.message = Some("Select targets with space first".into());
+                    } else {
+                        app.mode = Mode::ConfirmWipe;
+                    }
+                }
+                Key::Char('q') => {
+                    if app.running() == 0 {
+                        return Ok(());
+                    }
+                    app.mode = Mode::ConfirmQuit;
+                }
+                _ => {}
+            },
+            Mode::ConfirmWipe => {
+                if let Key::Char('y') = key {
+                    app.start_wipes(cli, config);
+                }
+                app.mode = Mode::Browse;
+            }
+            Mode::ConfirmQuit => {
+                if let Key::Char('y') = key {
+                    return Ok(());
+                }
+                app.mode = Mode::Browse;
+            }
+        }
+    }
+}
+
+impl App {
+    /// lists a directory, directories first
+    fn open(&mut self, dir: &Path) {
+        let mut entries: Vec<Entry> = match std::fs::read_dir(dir) {
+            Ok(read) => read
+                .flatten()
+                .map(|entry| {
+                    let metadata = std::fs::symlink_metadata(entry.path()).ok();
+                    Entry {
+                        name: entry.file_name().to_string_lossy().into_owned(),
+                        path: entry.path(),
+                        is_dir: metadata.as_ref().is_some_and(|m| m.is_dir()),
+                        size: metadata.map_or(0, |m| m.len()),
+                    }
+                })
+                .collect(),
+            Err(e) => {
+                self.message = Some(format!("{}: {}", dir.display(), e));
+                return;
+            }
+        };
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then(a.name.cmp(&b.name)));
+
+        self.dir = dir.to_path_buf();
+        self.entries = entries;
+        self.cursor = 0;
+        self.list = ListState::default();
+    }
+
+    /// lists the current directory again, keeping the cursor where it was
+    fn reload(&mut self) {
+        let (cursor, dir) = (self.cursor, self.dir.clone());
+        self.open(&dir);
+        self.cursor = cursor.min(self.entries.len().saturating_sub(1));
+    }
+
+    fn running(&self) -> usize {
+        lock(&self.jobs)
+            .iter()
+            .filter(|job| matches!(job.status, JobStatus::Running))
+            .count()
+    }
+
+    /// starts one wipe per selected target, each on its own thread
+    fn start_wipes(&mut self, cli: &Cli, config: &Config) {
+        let standard = NamedStandard::ALL[self.standard].name;
+        for path in std::mem::take(&mut self.selected) {
+            let index = {
+                let mut jobs = lock(&self.jobs);
+                jobs.push(Job {
+                    path: path.clone(),
+                    standard,
+                    pass: 0,
+                    passes: 0,
+                    fraction: 0.0,
+                    status: JobStatus::Running,
+                });
+                jobs.len() - 1
+            };
+
+            let job_cli = with_overrides(cli, Some(standard), None);
+            let config = config.clone();
+            let jobs = Arc::clone(&self.jobs);
+            std::thread::spawn(move || {
+                // detect_storage would print over the UI
+                let storage =
+                    StorageType::detect_from_path(&path).unwrap_or_else(|_| fallback_storage());
+                let progress_jobs = Arc::clone(&jobs);
+                // replaces the progress bar build_shredder sets up for terminals
+                let shredder = build_shredder(&job_cli, &config, &storage, false).with_progress(
+                    move |progress: &Progress| {
+                        let job = &mut lock(&progress_jobs)[index];
+                        job.pass = progress.pass;
+                        job.passes = progress.passes;
+                        job.fraction = progress.fraction();
+                    },
+                );
+                let status = match shredder.wipe_tree(&path) {
+                    Ok(reports) => JobStatus::Done(reports.len()),
+                    Err(e) => JobStatus::Failed(e.to_string()),
+                };
+                lock(&jobs)[index].status = status;
+            });
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// draws the whole screen
+fn draw(frame: &mut Frame, app: &mut App) {
+    let jobs = lock(&app.jobs);
+    let area = frame.area();
+    let jobs_height = (jobs.len() as u16 + 2).clamp(3, (area.height / 3).max(3));
+    let [header, body, jobs_area, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(jobs_height),
+        Constraint::Length(1),
+    ])
+    .areas(area);
+    let [list_area, details_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+
+    frame.render_widget(
+        Paragraph::new(format!(" shredder  {}", app.dir.display()))
+            .style(Style::new().add_modifier(Modifier::REVERSED)),
+        header,
+    );
+
+    let left = list_area.width as usize;
+    let items: Vec<ListItem> = app
+        .entries
+        .iter()
+        .map(|entry| {
+            let mark = if app.selected.contains(&entry.path) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let name = if entry.is_dir {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            let size = if entry.is_dir {
+                String::new()
+            } else {
+                format_size(entry.size)
+            };
+            let width = left.saturating_sub(size.len() + 5);
+            ListItem::new(format!("{} {:<width$} {}", mark, fit(&name, width), size))
+        })
+        .collect();
+    if items.is_empty() {
+        frame.render_widget(Paragraph::new("  (empty)"), list_area);
+    } else {
+        // the list scrolls itself so the cursor stays visible
+        app.list.select(Some(app.cursor));
+        frame.render_stateful_widget(
+            List::new(items).highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            list_area,
+            &mut app.list,
+        );
+    }
+
+    let named = &NamedStandard::ALL[app.standard];
+    let name = named.name;
+    let mut details = vec![
+        Line::from(format!("Standard: {}  (s to change)", name)),
+        Line::from(named.title),
+        Line::default(),
+        Line::from(named.summary),
+        Line::default(),
+    ];
+    for (i, pattern) in named.standard().passes().iter().enumerate() {
+        details.push(Line::from(format!("{:>3}. {}", i + 1, pattern)));
+    }
+    frame.render_widget(
+        Paragraph::new(details)
+            .wrap(Wrap { trim: false })
+            .block(Block::new().borders(Borders::LEFT)),
+        details_area,
+    );
+
+    let running = jobs
+        .iter()
+        .filter(|j| matches!(j.status, JobStatus::Running))
+        .count();
+    // newest first
+    let lines: Vec<Line> = jobs
+        .iter()
+        .rev()
+        .map(|job| {
+            let state = match &job.status {
+                JobStatus::Running if job.passes == 0 => "starting".to_string(),
+                JobStatus::Running => format!(
+                    "pass {}/{} {} {:5.1}%",
+                    job.pass,
+                    job.passes,
+                    bar(job.fraction, 20),
+                    job.fraction * 100.0
+                ),
+                JobStatus::Done(files) => format!("done, {} file(s) shredded", files),
+                JobStatus::Failed(error) => format!("failed: {}", error),
+            };
+            Line::from(format!(
+                " {} [{}] {}",
+                job.path.display(),
+                job.standard,
+                state
+            ))
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::new()
+                .borders(Borders::TOP)
+                .title(format!(" Wipes ({} running) ", running))
+                .title_style(Style::new().add_modifier(Modifier::BOLD)),
+        ),
+        jobs_area,
+    );
+
+    draw_footer(frame, app, name, footer);
+}
+
+fn draw_footer(frame: &mut Frame, app: &App, standard: &str, area: Rect) {
+    let warning = Style::new().fg(Color::Red).add_modifier(Modifier::BOLD);
+    let footer = match (&app.mode, &app.message) {
+        (Mode::ConfirmWipe, _) => Paragraph::new(format!(
+            " Wipe {} item(s) with {}? This cannot be undone. [y/N]",
+            app.selected.len(),
+            standard
+        ))
+        .style(warning),
+        (Mode::ConfirmQuit, _) => {
+            Paragraph::new(" Wipes are still running and will be interrupted. Quit? [y/N]")
+                .style(warning)
+        }
+        (_, Some(message)) => Paragraph::new(format!(" {}", message)),
+        _ => Paragraph::new(format!(
+            " ↑↓ move  → open  ← up  space select ({})  s standard  w wipe  q quit",
+            app.selected.len()
+        )),
+    };
+    frame.render_widget(footer, area);
+}
+
+/// truncates or pads text to exactly `width` columns
+fn fit(text: &str, width: usize) -> String {
+    let mut out: String = text.chars().take(width).collect();
+    let len = out.chars().count();
+    out.extend(std::iter::repeat_n(' ', width - len));
+    out
+}
+
+fn bar(fraction: f64, width: usize) -> String {
+    let filled = ((fraction * width as f64) as usize).min(width);
+    format!("[{}{}]", "#".repeat(filled), " ".repeat(width - filled))
+}
+
+fn parse_key(key: KeyEvent) -> Key {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => Key::Up,
+        KeyCode::Down | KeyCode::Char('j') => Key::Down,
+        KeyCode::Right => Key::Right,
+        KeyCode::Left => Key::Left,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Backspace => Key::Backspace,
+        // raw mode keeps ctrl-c from interrupting, so it quits like q
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Key::Char('q'),
+        KeyCode::Char(c) => Key::Char(c),
+        _ => Key::Other,
+    }
+}
+
+/// raw mode on the alternate screen; the previous state is restored on drop,
+/// and by ratatui's panic hook on a panic
+struct Terminal(DefaultTerminal);
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        ratatui::try_init().map(Self)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
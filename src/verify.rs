@@ -0,0 +1,366 @@
+use crate::patterns::WipePattern;
+use crate::{Result, WipeError};
+use rand::seq::index;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// unit of verification; each block either matches the pattern or doesn't
+pub const BLOCK_SIZE: usize = 4096;
+
+/// offsets of mismatching blocks kept in a report
+const MAX_MISMATCHES: usize = 16;
+
+/// where sampled blocks are taken from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SamplePlacement {
+    /// anywhere in the target; clusters and gaps happen by chance
+    #[default]
+    Random,
+    /// one from each of a number of equal stretches of the target, so no
+    /// stretch goes unchecked
+    Stratified,
+}
+
+/// outcome of checking a file or device against a pattern
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerificationReport {
+    /// path of the checked target
+    pub path: PathBuf,
+
+    /// pattern the target was expected to hold, e.g. "zeros (0x00)"
+    pub pattern: String,
+
+    /// size of the target in bytes
+    pub size: u64,
+
+    /// fraction of the blocks that was read, from 0.0 to 1.0
+    pub sample: f64,
+
+    /// number of blocks read
+    pub blocks_checked: u64,
+
+    /// number of blocks that didn't match
+    pub mismatched_blocks: u64,
+
+    /// offsets of the first mismatching blocks
+    pub mismatches: Vec<u64>,
+}
+
+impl VerificationReport {
+    /// whether every block read matched the pattern
+    pub fn passed(&self) -> bool {
+        self.mismatched_blocks == 0
+    }
+
+    /// renders the report as TOML, in a [verification] table
+    pub fn to_toml(&self) -> Result<String> {
+        #[derive(Serialize)]
+        struct File<'a> {
+            verification: &'a VerificationReport,
+        }
+        toml::to_string(&File { verification: self }).map_err(|e| WipeError::Report(e.to_string()))
+    }
+}
+
+/// checks that a file or device holds the given pattern, without writing to it
+///
+/// the target is read in 4 KiB blocks. with a sample below 1.0, that fraction of
+/// the blocks is read, placed as asked, always including the first and the last. random
+/// data can't be compared byte for byte, so blocks only have to look random
+/// (no repeated fill, high entropy)
+///
+/// cached pages of the target are dropped first, so the blocks come from the
+/// media rather than from memory
+///
+/// # Arguments
+/// * `path` - File or device to check
+/// * `pattern` - Pattern the target should hold
+/// * `sample` - Fraction of blocks to read, from 0.0 (nothing) to 1.0 (everything)
+/// * `placement` - Where the blocks of a sample are taken from
+///
+/// # Returns
+/// * `Result<VerificationReport>` - What was read and which blocks didn't match;
+///   a mismatch is not an error, IO failures are
+pub fn verify_pattern(
+    path: &Path,
+    pattern: &WipePattern,
+    sample: f64,
+    placement: SamplePlacement,
+) -> Result<VerificationReport> {
+    let mut file = crate::open_uncached(path)?;
+    let size = crate::target_size(&mut file)?;
+    let sample = sample.clamp(0.0, 1.0);
+    let mut report = VerificationReport {
+        path: path.to_path_buf(),
+        pattern: pattern.to_string(),
+        size,
+        sample,
+        ..Default::default()
+    };
+
+    let blocks = size.div_ceil(BLOCK_SIZE as u64);
+    let mut block = zeroize::Zeroizing::new(vec![0u8; BLOCK_SIZE]);
+    let check = |offset: u64, data: &[u8], report: &mut VerificationReport| {
+        report.blocks_checked += 1;
+        if !block_matches(pattern, data) {
+            report.mismatched_blocks += 1;
+            if report.mismatches.len() < MAX_MISMATCHES {
+                report.mismatches.push(offset);
+            }
+        }
+    };
+
+    if sample >= 1.0 {
+        // everything, in order
+        for number in 0..blocks {
+            let offset = number * BLOCK_SIZE as u64;
+            let len = block_len(size, offset);
+            file.read_exact(&mut block[..len])?;
+            check(offset, &block[..len], &mut report);
+        }
+    } else if blocks > 0 && sample > 0.0 {
+        let wanted = (blocks as f64 * sample).ceil() as u64;
+        for number in sample_blocks(blocks, wanted, placement) {
+            let offset = number * BLOCK_SIZE as u64;
+            let len = block_len(size, offset);
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut block[..len])?;
+            check(offset, &block[..len], &mut report);
+        }
+    }
+
+    Ok(report)
+}
+
+/// has another process read a target from start to end and check it against a
+/// pattern, by running `<program> verify <path> --pattern <pattern> --sample 100%`
+///
+/// the other process shares no handle, buffer or cached page with this one, so
+/// a read-back that only succeeds because of state in this process (a stale
+/// mapping, a cached page that escaped the drop) fails there
+///
+/// # Arguments
+/// * `program` - shred executable to run
+/// * `path` - File or device to check
+/// * `pattern` - Pattern as `--pattern` takes it, e.g. "random" or "0x00"
+///
+/// # Returns
+/// * `Result<()>` - Ok if the process found every block matching; a mismatch
+///   is a verification failure, a process that couldn't run an IO error
+pub(crate) fn verify_in_process(program: &Path, path: &Path, pattern: &str) -> Result<()> {
+    let output = std::process::Command::new(program)
+        .arg("verify")
+        .arg(path)
+        .args(["--pattern", pattern, "--sample", "100%", "--no-root-check"])
+        .output()?;
+    log::debug!(
+        "Verifier process: {}",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+    match output.status.code() {
+        Some(0) => Ok(()),
+        // exit_code::VERIFICATION_FAILED
+        Some(4) => Err(WipeError::VerificationFailed(
+            "Pattern mismatch in the separate verifier process".to_string(),
+        )),
+        _ => Err(WipeError::VerificationFailed(format!(
+            "Verifier process {} failed ({}): {}",
+            program.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+/// picks `wanted` of `blocks` block numbers, in order and placed as asked. the
+/// first and the last block are always in it
+pub(crate) fn sample_blocks(blocks: u64, wanted: u64, placement: SamplePlacement) -> Vec<u64> {
+    match placement {
+        SamplePlacement::Random => random_blocks(blocks, wanted),
+        SamplePlacement::Stratified => stratified_blocks(blocks, wanted),
+    }
+}
+
+/// picks `wanted` of `blocks` block numbers at random, in order. the first and
+/// the last block are always in it
+fn random_blocks(blocks: u64, wanted: u64) -> Vec<u64> {
+    if blocks == 0 {
+        return Vec::new();
+    }
+    let wanted = wanted.clamp(blocks.min(2), blocks);
+    let mut numbers = vec![0, blocks - 1];
+    if wanted > 2 {
+        // the rest at random from the blocks in between
+        let middle = (blocks - 2) as usize;
+        numbers.extend(
+            index::sample(&mut rand::thread_rng(), middle, (wanted - 2) as usize)
+                .into_iter()
+                .map(|i| i as u64 + 1),
+        );
+    }
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers
+}
+
+/// picks `wanted` of `blocks` block numbers, in order: the blocks are cut into
+/// `wanted` equal strata and one is picked at random from each, so the sample
+/// covers the whole target evenly. the first and the last block are always in it
+pub(crate) fn stratified_blocks(blocks: u64, wanted: u64) -> Vec<u64> {
+    let wanted = wanted.clamp(blocks.min(2), blocks);
+    let mut rng = rand::thread_rng();
+    let mut numbers: Vec<u64> = (0..wanted)
+        .map(|stratum| {
+            let start = stratum * blocks / wanted;
+            let end = (stratum + 1) * blocks / wanted;
+            rand::Rng::gen_range(&mut rng, start..end)
+        })
+        .collect();
+    if let Some(first) = numbers.first_mut() {
+        *first = 0;
+    }
+    if let Some(last) = numbers.last_mut() {
+        *last = blocks - 1;
+    }
+    numbers.dedup();
+    numbers
+}
+
+/// largest share of a target's blocks that could have been left unwiped, with
+/// 95% confidence, when `checked` blocks picked at random all matched
+///
+/// # Arguments
+/// * `checked` - Number of sampled blocks, all of which matched
+///
+/// # Returns
+/// * `f64` - Share from 0.0 to 1.0; 1.0 when nothing was checked
+pub fn max_unwiped_share(checked: u64) -> f64 {
+    if checked == 0 {
+        return 1.0;
+    }
+    1.0 - 0.05f64.powf(1.0 / checked as f64)
+}
+
+/// length of the block at `offset`; the last one may be short
+fn block_len(size: u64, offset: u64) -> usize {
+    (size - offset).min(BLOCK_SIZE as u64) as usize
+}
+
+/// whether a block holds the pattern
+fn block_matches(pattern: &WipePattern, block: &[u8]) -> bool {
+    match pattern {
+        WipePattern::Zeros | WipePattern::Ones => pattern.verify_buffer(block),
+        WipePattern::Random => looks_random(block),
+        // the pattern may not start at the block boundary
+        WipePattern::Custom(bytes) => (0..bytes.len()).any(|phase| {
+            block
+                .iter()
+                .enumerate()
+                .all(|(i, &b)| b == bytes[(i + phase) % bytes.len()])
+        }),
+    }
+}
+
+/// whether a block looks like random data: high byte entropy, which rules out
+/// fills, text and most file formats
+fn looks_random(block: &[u8]) -> bool {
+    // too short to tell
+    if block.len() < 256 {
+        return block.iter().any(|&b| b != block[0]);
+    }
+    let mut counts = [0u32; 256];
+    for &b in block {
+        counts[b as usize] += 1;
+    }
+    let len = block.len() as f64;
+    let entropy: f64 = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    // 4 KiB of random data comes out at about 7.95 bits per byte
+    entropy > 7.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+    use std::io::Write;
+
+    #[test]
+    fn test_verify_pattern() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&vec![0u8; BLOCK_SIZE * 10 + 100]).unwrap();
+
+        let report = verify_pattern(
+            file.path(),
+            &WipePattern::Zeros,
+            1.0,
+            SamplePlacement::Random,
+        )
+        .unwrap();
+        assert!(report.passed());
+        assert_eq!(report.blocks_checked, 11);
+
+        // a stray byte in the last, short block
+        file.write_all(&[1]).unwrap();
+        let report = verify_pattern(
+            file.path(),
+            &WipePattern::Zeros,
+            0.1,
+            SamplePlacement::Random,
+        )
+        .unwrap();
+        assert_eq!(report.blocks_checked, 2);
+        assert_eq!(report.mismatches, vec![BLOCK_SIZE as u64 * 10]);
+        assert!(!verify_pattern(
+            file.path(),
+            &WipePattern::Random,
+            1.0,
+            SamplePlacement::Random
+        )
+        .unwrap()
+        .passed());
+    }
+
+    #[test]
+    fn test_block_matches() {
+        let mut random = vec![0u8; BLOCK_SIZE];
+        rand::thread_rng().fill_bytes(&mut random);
+        assert!(block_matches(&WipePattern::Random, &random));
+        assert!(!block_matches(&WipePattern::Random, &[0x41; BLOCK_SIZE]));
+
+        let gutmann = WipePattern::Custom(vec![0x92, 0x49, 0x24]);
+        assert!(block_matches(&gutmann, &[0x49, 0x24, 0x92, 0x49]));
+        assert!(!block_matches(&gutmann, &[0x92, 0x92, 0x49]));
+    }
+
+    #[test]
+    fn test_stratified_blocks() {
+        let numbers = stratified_blocks(1000, 10);
+        assert_eq!(numbers.len(), 10);
+        assert_eq!((numbers[0], numbers[9]), (0, 999));
+        // one from each tenth
+        for (stratum, number) in numbers.iter().enumerate() {
+            assert_eq!(*number / 100, stratum as u64);
+        }
+        assert_eq!(stratified_blocks(3, 10), [0, 1, 2]);
+        assert_eq!(stratified_blocks(5, 0), [0, 4]);
+
+        let numbers = sample_blocks(1000, 10, SamplePlacement::Random);
+        assert_eq!(numbers.len(), 10);
+        assert_eq!((numbers[0], numbers[9]), (0, 999));
+        assert!(numbers.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(sample_blocks(0, 10, SamplePlacement::Random).is_empty());
+
+        assert_eq!(max_unwiped_share(0), 1.0);
+        // about 3 / n, the rule of three
+        assert!((max_unwiped_share(3000) - 0.001).abs() < 0.0001);
+    }
+}
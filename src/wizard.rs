@@ -0,0 +1,338 @@
+//! `shred wizard`: asks a few questions and proposes a NIST SP 800-88 method;
+//! `shred advise` explains the same proposal without asking or wiping
+
+use super::{exit_code, parse_standard, shred_many, shred_path, with_overrides, Cli};
+use shredder::{config::Config, device, storage::StorageType};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// kind of media, as far as sanitization is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Media {
+    Hdd,
+    Ssd,
+    Nvme,
+    Flash,
+}
+
+impl Media {
+    const ALL: [Media; 4] = [Media::Hdd, Media::Ssd, Media::Nvme, Media::Flash];
+
+    fn describe(self) -> &'static str {
+        match self {
+            Media::Hdd => "hard disk",
+            Media::Ssd => "SATA/SAS SSD",
+            Media::Nvme => "NVMe SSD",
+            Media::Flash => "USB stick, SD or memory card",
+        }
+    }
+
+    fn detect(path: &Path) -> Option<Self> {
+        Some(Self::of(
+            &StorageType::detect_from_path(path).ok()?.device_type,
+        ))
+    }
+
+    fn of(storage: &StorageType) -> Self {
+        match storage {
+            StorageType::Hdd(_) => Media::Hdd,
+            StorageType::Ssd(caps) if caps.supports_nvme_sanitize => Media::Nvme,
+            StorageType::Ssd(_) => Media::Ssd,
+            StorageType::Flash(_) => Media::Flash,
+        }
+    }
+}
+
+/// the proposed method and why
+struct Recommendation {
+    /// value for --standard
+    standard: &'static str,
+    /// NIST method name
+    method: &'static str,
+    /// explanations, most important first
+    reasons: Vec<&'static str>,
+}
+
+/// picks a NIST SP 800-88 method from the answers
+///
+/// media staying in the organization only needs Clear; media leaving it needs
+/// Purge, which on flash has to come from the device's own sanitize command
+fn recommend(media: Media, leaving: bool, whole_device: bool) -> Recommendation {
+    let mut reasons = Vec::new();
+    let (standard, method) = if leaving {
+        reasons.push(
+            "Media leaving your control needs Purge: recovery must be infeasible even with laboratory techniques.",
+        );
+        match media {
+            Media::Hdd => reasons.push(
+                "On hard disks NIST accepts overwriting for Purge. shredder uses the drive's ATA secure erase when available and otherwise overwrites with random data, zeros, ones and random data.",
+            ),
+            Media::Ssd | Media::Nvme => reasons.push(
+                "Overwriting can't reach the spare and remapped flash blocks of an SSD, so Purge relies on the drive's own sanitize, secure erase or cryptographic erase. shredder issues it where the drive supports it. If it can't, physically destroy the drive.",
+            ),
+            Media::Flash => reasons.push(
+                "USB sticks and memory cards have no sanitize command, and NIST recommends destroying them rather than relying on overwrites. shredder will overwrite it, but shred or disintegrate it afterwards if the data was sensitive.",
+            ),
+        }
+        ("nist", "Purge")
+    } else {
+        reasons.push(
+            "Media staying in your organization only needs Clear: one pass of random data defeats recovery with ordinary software tools.",
+        );
+        ("nist-clear", "Clear")
+    };
+
+    if !whole_device && media != Media::Hdd {
+        reasons.push(
+            "You are wiping individual files on flash storage. Old copies may survive in remapped blocks, journals and snapshots, so no method can guarantee they are gone. Wipe the whole device, or rely on full-disk encryption.",
+        );
+    }
+
+    Recommendation {
+        standard,
+        method,
+        reasons,
+    }
+}
+
+/// what shredder would actually do to carry out `method` on the storage, as far
+/// as its capabilities are known
+fn mechanism(method: &str, storage: Option<&StorageType>, whole_device: bool) -> String {
+    let caps = match storage {
+        Some(StorageType::Hdd(caps) | StorageType::Ssd(caps) | StorageType::Flash(caps)) => caps,
+        None => {
+            return "The storage couldn't be detected, so the mechanism depends on what the device supports.".to_string()
+        }
+    };
+    let wear_leveled = storage.is_some_and(StorageType::requires_wear_leveling_handling);
+    if method == "Clear" {
+        return if wear_leveled && caps.supports_trim {
+            "One pass of random data, after TRIM tells the drive the old blocks are unused."
+                .to_string()
+        } else {
+            "One pass of random data, read back to verify it.".to_string()
+        };
+    }
+    if !whole_device {
+        return "Overwrite passes on the file; a hardware erase covers whole devices only."
+            .to_string();
+    }
+    if caps.supports_nvme_sanitize {
+        "The drive's NVMe sanitize command, which also erases spare and remapped blocks."
+            .to_string()
+    } else if caps.supports_secure_erase {
+        "The drive's ATA secure erase, with overwrite passes if it fails.".to_string()
+    } else if wear_leveled {
+        "Overwrite passes only, since the drive reports no sanitize or secure erase. They can't reach remapped blocks: destroy the media if that isn't enough.".to_string()
+    } else {
+        "Overwrite passes with random data, zeros, ones and random data, read back to verify."
+            .to_string()
+    }
+}
+
+/// explains which method and mechanism fit a target, without wiping it
+pub(crate) fn advise(path: &Path, leaving: bool) {
+    if !path.exists() {
+        eprintln!("Error: Path not found: {}", path.display());
+        process::exit(exit_code::FAILURE);
+    }
+    let whole_device = device::is_block_device(path);
+    let storage = StorageType::detect_from_path(path)
+        .ok()
+        .map(|info| info.device_type);
+    // without a detected type, advise for the media that is hardest to purge
+    let media = storage.as_ref().map_or(Media::Ssd, Media::of);
+
+    println!("🧭 Advice for {}", path.display());
+    match &storage {
+        Some(_) => println!("   Storage: {} (detected)", media.describe()),
+        None => println!(
+            "   Storage: unknown, advising as for a {}",
+            media.describe()
+        ),
+    }
+    if leaving {
+        println!("   Destination: leaving your organization's control");
+    } else {
+        println!(
+            "   Destination: staying in your organization (pass --leaving if it is sold, donated, returned or recycled)"
+        );
+    }
+    println!();
+
+    let recommendation = recommend(media, leaving, whole_device);
+    println!(
+        "📋 Recommended: NIST SP 800-88 {} (--standard {})",
+        recommendation.method, recommendation.standard
+    );
+    for reason in &recommendation.reasons {
+        println!("   • {}", reason);
+    }
+    println!(
+        "   Mechanism: {}",
+        mechanism(recommendation.method, storage.as_ref(), whole_device)
+    );
+    println!();
+    println!("Nothing was wiped. To follow this advice:");
+    println!(
+        "   shred --standard {} {}",
+        recommendation.standard,
+        path.display()
+    );
+}
+
+/// reads one answer, exiting when stdin is closed
+fn ask(question: &str) -> String {
+    print!("{} ", question);
+    let _ = io::stdout().flush();
+
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => {
+            println!();
+            println!("Wizard cancelled. Nothing was wiped.");
+            process::exit(exit_code::CANCELLED);
+        }
+        Ok(_) => input.trim().to_string(),
+    }
+}
+
+fn ask_yes_no(question: &str) -> bool {
+    loop {
+        match ask(&format!("{} [y/n]", question)).to_lowercase().as_str() {
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => println!("Please answer y or n."),
+        }
+    }
+}
+
+/// runs the wizard, then the wipe it proposes
+pub(crate) fn run(cli: &Cli, config: &Config, path: Option<&Path>) {
+    println!("🧭 This wizard picks a NIST SP 800-88 sanitization method for you.");
+    println!();
+
+    // 1. the target
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => loop {
+            let answer = ask("What do you want to wipe? Enter a file, directory or device:");
+            let path = PathBuf::from(answer);
+            if path.exists() {
+                break path;
+            }
+            println!("{} does not exist.", path.display());
+        },
+    };
+    if !path.exists() {
+        eprintln!("Error: Path not found: {}", path.display());
+        process::exit(exit_code::FAILURE);
+    }
+    let whole_device = device::is_block_device(&path);
+
+    // 2. the media
+    let detected = Media::detect(&path);
+    println!();
+    println!("What kind of storage is it on?");
+    for (i, media) in Media::ALL.iter().enumerate() {
+        let mark = if Some(*media) == detected {
+            " (detected)"
+        } else {
+            ""
+        };
+        println!("  {}. {}{}", i + 1, media.describe(), mark);
+    }
+    let media = loop {
+        let prompt = if detected.is_some() {
+            "Choose 1-4, or press Enter to keep the detected type:"
+        } else {
+            "Choose 1-4:"
+        };
+        match (ask(prompt).as_str(), detected) {
+            ("", Some(media)) => break media,
+            (answer, _) => match answer.parse::<usize>() {
+                Ok(n @ 1..=4) => break Media::ALL[n - 1],
+                _ => println!("Please enter a number from 1 to 4."),
+            },
+        }
+    };
+
+    // 3. where it is going
+    println!();
+    let leaving = ask_yes_no(
+        "Will the media leave your organization's control (sold, donated, returned under warranty, recycled)?",
+    );
+
+    // the proposal
+    let recommendation = recommend(media, leaving, whole_device);
+    println!();
+    println!(
+        "📋 Recommended: NIST SP 800-88 {} (--standard {})",
+        recommendation.method, recommendation.standard
+    );
+    for reason in &recommendation.reasons {
+        println!("   • {}", reason);
+    }
+    println!("   Overwrite passes, if the drive can't erase itself:");
+    for (i, pattern) in parse_standard(recommendation.standard)
+        .passes()
+        .iter()
+        .enumerate()
+    {
+        println!("     {}. {}", i + 1, pattern);
+    }
+    println!(
+        "   Same as: shred --standard {} {}",
+        recommendation.standard,
+        path.display()
+    );
+    println!();
+
+    if !ask_yes_no("Wipe it now?") {
+        println!("Nothing was wiped.");
+        process::exit(exit_code::CANCELLED);
+    }
+
+    let cli = with_overrides(cli, Some(recommendation.standard), None);
+    if path.is_dir() {
+        shred_many(&cli, config, &path.display().to_string(), vec![path]);
+    } else {
+        shred_path(&cli, config, &path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommend() {
+        assert_eq!(recommend(Media::Hdd, false, true).standard, "nist-clear");
+        assert_eq!(recommend(Media::Nvme, true, true).method, "Purge");
+        assert_eq!(recommend(Media::Hdd, true, false).reasons.len(), 2);
+        // files on flash get a warning that they can't be purged reliably
+        assert_eq!(recommend(Media::Ssd, true, false).reasons.len(), 3);
+    }
+
+    #[test]
+    fn test_mechanism() {
+        use shredder::storage::StorageCapabilities;
+
+        let caps = |trim, secure_erase, sanitize, wear_leveling| StorageCapabilities {
+            supports_trim: trim,
+            supports_secure_erase: secure_erase,
+            supports_nvme_sanitize: sanitize,
+            has_wear_leveling: wear_leveling,
+        };
+        let nvme = StorageType::Ssd(caps(true, false, true, true));
+        assert!(mechanism("Purge", Some(&nvme), true).contains("NVMe sanitize"));
+        assert!(mechanism("Purge", Some(&nvme), false).contains("whole devices only"));
+        assert!(mechanism("Clear", Some(&nvme), true).contains("TRIM"));
+        let stick = StorageType::Flash(caps(false, false, false, true));
+        assert!(mechanism("Purge", Some(&stick), true).contains("destroy"));
+        let hdd = StorageType::Hdd(caps(false, true, false, false));
+        assert!(mechanism("Purge", Some(&hdd), true).contains("ATA secure erase"));
+        assert!(mechanism("Purge", None, true).contains("couldn't be detected"));
+    }
+}
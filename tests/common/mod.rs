@@ -63,7 +63,7 @@ pub fn verify_file_pattern(path: &Path, pattern: &[u8]) -> io::Result<bool> {
         if n == 0 {
             break;
         }
-        if &buffer[..n] != &pattern[..n] {
+        if buffer[..n] != pattern[..n] {
             return Ok(false);
         }
     }
@@ -78,9 +78,9 @@ pub fn create_test_directory() -> io::Result<(TempDir, Vec<PathBuf>)> {
     let mut files = Vec::new();
 
     // Create various test files
-    files.push(create_test_file(&dir.path(), 1024)?); // 1KB
-    files.push(create_test_file(&dir.path(), 1024 * 1024)?); // 1MB
-    files.push(create_pattern_file(&dir.path(), &[0xAA; 1024], 4096)?); // 4KB pattern
+    files.push(create_test_file(dir.path(), 1024)?); // 1KB
+    files.push(create_test_file(dir.path(), 1024 * 1024)?); // 1MB
+    files.push(create_pattern_file(dir.path(), &[0xAA; 1024], 4096)?); // 4KB pattern
 
     // Create a subdirectory with files
     let subdir = dir.path().join("subdir");
@@ -93,7 +93,36 @@ pub fn create_test_directory() -> io::Result<(TempDir, Vec<PathBuf>)> {
 /// simulates different storage types for testing
 #[cfg(test)]
 pub mod mock_storage {
-    use shredder::storage::{StorageCapabilities, StorageInfo, StorageType};
+    use shredder::storage::{StorageCapabilities, StorageInfo, StorageProbe, StorageType};
+    use shredder::WipeError;
+    use std::path::{Path, PathBuf};
+
+    /// a storage probe that knows a fixed set of paths and fails for the rest,
+    /// like detection does for paths on unrecognized devices
+    #[allow(dead_code)]
+    #[derive(Default)]
+    pub struct MockProbe {
+        devices: Vec<(PathBuf, StorageInfo)>,
+    }
+
+    #[allow(dead_code)]
+    impl MockProbe {
+        /// reports `info` for `path` and anything below it
+        pub fn with(mut self, path: &Path, info: StorageInfo) -> Self {
+            self.devices.push((path.to_path_buf(), info));
+            self
+        }
+    }
+
+    impl StorageProbe for MockProbe {
+        fn probe(&self, path: &Path) -> shredder::Result<StorageInfo> {
+            self.devices
+                .iter()
+                .find(|(prefix, _)| path.starts_with(prefix))
+                .map(|(_, info)| info.clone())
+                .ok_or_else(|| WipeError::UnsupportedOperation("Unknown device".into()))
+        }
+    }
 
     pub fn mock_hdd() -> StorageInfo {
         StorageInfo {
@@ -104,6 +133,7 @@ pub mod mock_storage {
                 has_wear_leveling: false,
             }),
             block_size: 512,
+            physical_block_size: 4096,
             total_size: 1024 * 1024 * 1024, // 1GB
         }
     }
@@ -117,6 +147,7 @@ pub mod mock_storage {
                 has_wear_leveling: true,
             }),
             block_size: 4096,
+            physical_block_size: 4096,
             total_size: 1024 * 1024 * 1024, // 1GB
         }
     }
@@ -130,6 +161,7 @@ pub mod mock_storage {
                 has_wear_leveling: true,
             }),
             block_size: 4096,
+            physical_block_size: 4096,
             total_size: 1024 * 1024 * 1024, // 1GB
         }
     }
@@ -1,10 +1,13 @@
 use shredder::{
+    events::WipeEvent,
+    jobs::{self, JobStatus},
     patterns::WipePattern,
     standards::{
         LegacyConfig, LegacyStandard, Nist80088Config, SanitizationMethod, VerificationLevel,
         WipeConfig, WipeStandard,
     },
-    Shredder,
+    verify::SamplePlacement,
+    Shredder, WipeError, WipePhase,
 };
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom, Write};
@@ -560,3 +563,947 @@ fn test_different_storage_types() {
 //         }
 //     }
 // }
+
+#[test]
+fn test_wipe_report_counts_passes() {
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 1024).unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Legacy(LegacyConfig {
+            standard: LegacyStandard::Dod522022M,
+            extra_verification: false,
+        }),
+        mock_storage::mock_hdd().device_type,
+    );
+
+    let report = shredder.wipe_with_report(&file_path).unwrap();
+    assert_eq!(report.path, file_path);
+    assert_eq!(report.passes, 3);
+    assert_eq!(report.bytes_written, 3 * 1024);
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_protected_path_is_refused() {
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 1024).unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Legacy(LegacyConfig {
+            standard: LegacyStandard::Dod522022M,
+            extra_verification: false,
+        }),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_protected_paths([dir.path()]);
+
+    match shredder.wipe(&file_path) {
+        Err(WipeError::ProtectedPath { entry, .. }) => assert_eq!(entry, dir.path()),
+        other => panic!("expected ProtectedPath error, got {:?}", other),
+    }
+    // nothing was touched
+    assert_eq!(std::fs::read(&file_path).unwrap().len(), 1024);
+}
+
+#[test]
+fn test_wipe_tree_removes_directory() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("tree");
+    std::fs::create_dir_all(root.join("nested")).unwrap();
+    create_test_file(&root, 1024).unwrap();
+    create_test_file(&root.join("nested"), 2048).unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Legacy(LegacyConfig {
+            standard: LegacyStandard::Dod522022M,
+            extra_verification: false,
+        }),
+        mock_storage::mock_hdd().device_type,
+    );
+
+    let reports = shredder.wipe_tree(&root).unwrap();
+    assert_eq!(reports.len(), 2);
+    assert!(!root.exists());
+}
+
+#[test]
+fn test_exit_codes() {
+    use std::process::{Command, Stdio};
+
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 1024).unwrap();
+    let config = dir.path().join("config.toml");
+    std::fs::write(
+        &config,
+        format!("protected_paths = [{:?}]\n", dir.path().join("keep")),
+    )
+    .unwrap();
+    let shred = |args: &[&std::ffi::OsStr], stdin: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_shred"))
+            .args(args)
+            .arg("--no-root-check")
+            .arg("--config")
+            .arg(&config)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        child.wait().unwrap().code()
+    };
+
+    // declining the prompt
+    assert_eq!(shred(&[file_path.as_os_str()], "n\n"), Some(8));
+    assert!(file_path.exists());
+
+    // refused by the denylist
+    std::fs::create_dir(dir.path().join("keep")).unwrap();
+    let protected = create_test_file(&dir.path().join("keep"), 1024).unwrap();
+    assert_eq!(
+        shred(&[protected.as_os_str(), "--force".as_ref()], ""),
+        Some(7)
+    );
+
+    // missing target
+    let missing = dir.path().join("missing");
+    assert_eq!(
+        shred(&[missing.as_os_str(), "--force".as_ref()], ""),
+        Some(1)
+    );
+}
+
+#[test]
+fn test_verify_without_wiping() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("zeroed.img");
+    std::fs::write(&path, vec![0u8; 64 * 1024]).unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Modern(Nist80088Config {
+            method: SanitizationMethod::Clear,
+            verify_level: VerificationLevel::Full,
+        }),
+        mock_storage::mock_hdd().device_type,
+    );
+
+    let report = shredder
+        .verify(&path, &WipePattern::Zeros, VerificationLevel::Full)
+        .unwrap();
+    assert!(report.passed());
+    assert_eq!(report.blocks_checked, 16);
+    assert!(path.exists());
+
+    let report = shredder
+        .verify(&path, &WipePattern::Ones, VerificationLevel::Basic)
+        .unwrap();
+    assert!(!report.passed());
+    assert_eq!(report.mismatches[0], 0);
+
+    let report = shredder
+        .verify(&path, &WipePattern::Ones, VerificationLevel::None)
+        .unwrap();
+    assert_eq!(report.blocks_checked, 0);
+}
+
+#[test]
+fn test_mmap_and_write_loop_wipe() {
+    let dir = tempdir().unwrap();
+    for mmap in [true, false] {
+        let file_path = create_test_file(dir.path(), 3 * 1024 * 1024 + 17).unwrap();
+        let shredder = Shredder::new(
+            WipeStandard::Legacy(LegacyConfig {
+                standard: LegacyStandard::Dod522022M,
+                extra_verification: false,
+            }),
+            mock_storage::mock_hdd().device_type,
+        )
+        .with_mmap(mmap);
+
+        let report = shredder.wipe_with_report(&file_path).unwrap();
+        assert_eq!(report.bytes_written, 3 * (3 * 1024 * 1024 + 17));
+        assert!(!file_path.exists());
+    }
+}
+
+#[test]
+fn test_multithreaded_wipe() {
+    let dir = tempdir().unwrap();
+    let size = 40 * 1024 * 1024 + 123;
+    let file_path = create_test_file(dir.path(), size).unwrap();
+    // every pass is read back in full, so the regions must line up
+    let shredder = Shredder::new(
+        WipeStandard::Custom(WipeConfig {
+            passes: vec![
+                WipePattern::Custom(vec![0x55, 0xAA, 0x92]),
+                WipePattern::Random,
+            ],
+            verify_each_pass: true,
+        }),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_threads(4);
+
+    let report = shredder.wipe_with_report(&file_path).unwrap();
+    assert_eq!(report.bytes_written, 2 * size as u64);
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_block_aligned_writes() {
+    // targets that don't end on a block boundary, through the write loop and
+    // through parallel regions, read back in full
+    let dir = tempdir().unwrap();
+    for (size, threads) in [(3 * 1024 * 1024 + 100, 1), (40 * 1024 * 1024 + 4095, 4)] {
+        let file_path = create_test_file(dir.path(), size).unwrap();
+        let shredder = Shredder::new(
+            WipeStandard::Custom(WipeConfig {
+                passes: vec![WipePattern::Random],
+                verify_each_pass: true,
+            }),
+            mock_storage::mock_hdd().device_type,
+        )
+        .with_block_size(4096)
+        .with_buffer_size(1024 * 1024 + 1000)
+        .with_mmap(false)
+        .with_threads(threads);
+
+        let report = shredder.wipe_with_report(&file_path).unwrap();
+        assert_eq!(report.bytes_written, size as u64, "size {}", size);
+        assert!(!file_path.exists());
+    }
+}
+
+#[test]
+fn test_random_passes_read_back_in_full() {
+    // pattern lengths (1% of the file) that don't divide the write sizes
+    let dir = tempdir().unwrap();
+    for (size, mmap) in [(10_000_000, true), (10_000_000, false), (30_000_001, false)] {
+        let file_path = create_test_file(dir.path(), size).unwrap();
+        let shredder = Shredder::new(
+            WipeStandard::Custom(WipeConfig {
+                passes: vec![WipePattern::Random, WipePattern::Random],
+                verify_each_pass: true,
+            }),
+            mock_storage::mock_hdd().device_type,
+        )
+        .with_mmap(mmap);
+
+        assert!(shredder.wipe(&file_path).is_ok(), "size {}", size);
+        assert!(!file_path.exists());
+    }
+}
+
+#[test]
+fn test_sync_writes() {
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 256 * 1024).unwrap();
+    let shredder = Shredder::new(
+        WipeStandard::Custom(WipeConfig {
+            passes: vec![WipePattern::Ones, WipePattern::Random],
+            verify_each_pass: true,
+        }),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_sync_writes(true);
+
+    let report = shredder.wipe_with_report(&file_path).unwrap();
+    assert_eq!(report.bytes_written, 2 * 256 * 1024);
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_skip_matching_chunks() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("blank.img");
+    // zeros except for one chunk in the middle
+    let mut data = vec![0u8; 8 * 1024 * 1024];
+    data[3 * 1024 * 1024..4 * 1024 * 1024].fill(0x41);
+
+    for mmap in [true, false] {
+        std::fs::write(&file_path, &data).unwrap();
+        let shredder = Shredder::new(
+            WipeStandard::Custom(WipeConfig {
+                passes: vec![WipePattern::Zeros],
+                verify_each_pass: true,
+            }),
+            mock_storage::mock_hdd().device_type,
+        )
+        .with_buffer_size(1024 * 1024)
+        .with_adaptive_buffer(false)
+        .with_mmap(mmap)
+        .with_skip_matching(true);
+
+        let report = shredder.wipe_with_report(&file_path).unwrap();
+        assert_eq!(report.bytes_written, 1024 * 1024, "mmap {}", mmap);
+        assert!(!file_path.exists());
+    }
+}
+
+#[test]
+fn test_pass_failure_context() {
+    let error = WipeError::PassFailed {
+        pass: 2,
+        phase: WipePhase::Write,
+        offset: 4096,
+        source: Box::new(WipeError::Io(std::io::Error::other("disk full"))),
+    };
+    assert_eq!(
+        error.to_string(),
+        "Pass 2 failed while writing at offset 4096: IO error: disk full"
+    );
+    assert!(matches!(error.root_cause(), WipeError::Io(_)));
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn test_wipe_many_continues_past_failures() {
+    let dir = tempdir().unwrap();
+    let nested = dir.path().join("nested");
+    std::fs::create_dir(&nested).unwrap();
+    let kept = nested.join("kept.db");
+    std::fs::write(&kept, b"keep me").unwrap();
+    std::fs::write(nested.join("old.log"), b"wipe me").unwrap();
+    let single = dir.path().join("single.txt");
+    std::fs::write(&single, b"wipe me too").unwrap();
+    let missing = dir.path().join("missing.txt");
+
+    let shredder = Shredder::new(
+        WipeStandard::Custom(WipeConfig {
+            passes: vec![WipePattern::Zeros],
+            verify_each_pass: false,
+        }),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_protected_paths([&kept]);
+
+    let outcome = shredder.wipe_many([&missing, &nested, &single]);
+    assert!(outcome.is_partial());
+    assert_eq!(outcome.wiped.len(), 2);
+    let failed: Vec<_> = outcome.failed.iter().map(|(path, _)| path).collect();
+    assert_eq!(failed, [&missing, &kept]);
+    assert!(matches!(
+        outcome.failed[1].1,
+        WipeError::ProtectedPath { .. }
+    ));
+
+    // the directory stays, holding only what could not be wiped
+    assert!(kept.exists());
+    assert!(!nested.join("old.log").exists());
+    assert!(!single.exists());
+}
+
+#[test]
+fn test_wipe_with_events() {
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 3 * 1024 * 1024).unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Custom(WipeConfig {
+            passes: vec![WipePattern::Zeros, WipePattern::Random],
+            verify_each_pass: true,
+        }),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_buffer_size(1024 * 1024)
+    .with_adaptive_buffer(false);
+
+    let events: Vec<WipeEvent> = shredder.wipe_with_events(&file_path).into_iter().collect();
+    let started: Vec<usize> = events
+        .iter()
+        .filter_map(|event| match event {
+            WipeEvent::PassStarted { pass, passes: 2 } => Some(*pass),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(started, [1, 2]);
+    let verified = events
+        .iter()
+        .filter(|event| matches!(event, WipeEvent::VerifyStarted { .. }))
+        .count();
+    assert_eq!(verified, 2);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        WipeEvent::Progress {
+            pass: 2,
+            bytes_written: 3145728,
+            ..
+        }
+    )));
+    match events.last() {
+        Some(WipeEvent::Completed(report)) => assert_eq!(report.passes, 2),
+        other => panic!("wipe didn't complete: {:?}", other),
+    }
+    assert!(!file_path.exists());
+
+    // a failed wipe ends the channel with its error
+    let shredder = Shredder::new(
+        WipeStandard::Custom(WipeConfig {
+            passes: vec![WipePattern::Zeros],
+            verify_each_pass: false,
+        }),
+        mock_storage::mock_hdd().device_type,
+    );
+    let events: Vec<WipeEvent> = shredder.wipe_with_events(&file_path).into_iter().collect();
+    assert!(matches!(events[..], [WipeEvent::Error(_)]));
+}
+
+#[test]
+fn test_job_status_and_result() {
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 2 * 1024 * 1024).unwrap();
+    let shredder = || {
+        Shredder::new(
+            WipeStandard::Custom(WipeConfig {
+                passes: vec![WipePattern::Zeros, WipePattern::Ones],
+                verify_each_pass: false,
+            }),
+            mock_storage::mock_hdd().device_type,
+        )
+    };
+
+    let job = shredder().spawn(&file_path);
+    let mut status = jobs::status(job);
+    for _ in 0..500 {
+        if matches!(status, Some(JobStatus::Succeeded | JobStatus::Failed)) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        status = jobs::status(job);
+    }
+    assert_eq!(status, Some(JobStatus::Succeeded));
+    let progress = jobs::progress(job).unwrap();
+    assert_eq!((progress.pass, progress.passes), (2, 2));
+    assert_eq!(progress.total_written(), 4 * 1024 * 1024);
+    let report = jobs::result(job).unwrap().unwrap();
+    assert_eq!(report.passes, 2);
+    assert_eq!(report.job, Some(job));
+
+    // synchronous wipes are jobs too, and failures keep their error
+    assert!(shredder().wipe_with_report(&file_path).is_err());
+    let queued = jobs::register(&file_path);
+    assert_eq!(jobs::status(queued), Some(JobStatus::Queued));
+    assert!(shredder().with_job(queued).wipe(&file_path).is_err());
+    assert_eq!(jobs::status(queued), Some(JobStatus::Failed));
+    assert!(matches!(jobs::result(queued), Some(Err(_))));
+}
+
+#[test]
+fn test_content_hash_in_report() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("evidence.txt");
+    std::fs::write(&file_path, b"abc").unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Custom(WipeConfig {
+            passes: vec![WipePattern::Zeros],
+            verify_each_pass: false,
+        }),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_content_hash(true);
+
+    let report = shredder.wipe_with_report(&file_path).unwrap();
+    assert_eq!(
+        report.sha256.as_deref(),
+        Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+    );
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_sampled_verification() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("large.bin");
+    std::fs::write(&file_path, vec![0xAB; 400 * 4096]).unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Modern(Nist80088Config {
+            method: SanitizationMethod::Clear,
+            verify_level: VerificationLevel::Sampled(0.05),
+        }),
+        mock_storage::mock_hdd().device_type,
+    );
+
+    let report = shredder.wipe_with_report(&file_path).unwrap();
+    let sampled = report.sampled_verification.unwrap();
+    assert_eq!(sampled.blocks, 400);
+    assert_eq!(sampled.blocks_checked, 20);
+    assert!(sampled.max_unwiped > 0.1 && sampled.max_unwiped < 0.2);
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_basic_verification_sampling() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("sampled.bin");
+    std::fs::write(&file_path, vec![0x5A; 64 * 4096 + 100]).unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Modern(Nist80088Config {
+            method: SanitizationMethod::Clear,
+            verify_level: VerificationLevel::Basic,
+        }),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_sampling(0.25, SamplePlacement::Stratified);
+    shredder.wipe(&file_path).unwrap();
+    assert!(!file_path.exists());
+
+    // the same settings apply when checking a target without wiping it
+    std::fs::write(&file_path, vec![0u8; 64 * 4096]).unwrap();
+    let report = shredder
+        .verify(&file_path, &WipePattern::Zeros, VerificationLevel::Basic)
+        .unwrap();
+    assert_eq!(report.blocks_checked, 16);
+    assert!(report.passed());
+}
+
+#[test]
+fn test_enhanced_verification_in_separate_process() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("enhanced.bin");
+    std::fs::write(&file_path, vec![0x33; 3 * 1024 * 1024 + 17]).unwrap();
+
+    let standard = WipeStandard::Modern(Nist80088Config {
+        method: SanitizationMethod::Clear,
+        verify_level: VerificationLevel::Enhanced,
+    });
+    let shredder = Shredder::new(standard.clone(), mock_storage::mock_hdd().device_type)
+        .with_verifier_process(env!("CARGO_BIN_EXE_shred"));
+    shredder.wipe(&file_path).unwrap();
+    assert!(!file_path.exists());
+
+    // a verifier that can't run fails the verification and keeps the file
+    std::fs::write(&file_path, vec![0x33; 4096]).unwrap();
+    let shredder = Shredder::new(standard, mock_storage::mock_hdd().device_type)
+        .with_verifier_process(dir.path().join("missing-shred"));
+    match shredder.wipe(&file_path) {
+        Err(
+            e @ WipeError::PassFailed {
+                phase: WipePhase::Verify,
+                ..
+            },
+        ) => assert!(matches!(e.root_cause(), WipeError::Io(_))),
+        other => panic!("expected a verification failure, got {:?}", other),
+    }
+    assert!(file_path.exists());
+}
+
+#[test]
+fn test_hash_verification_per_pass() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("hashed.bin");
+    std::fs::write(&file_path, vec![0x77; 2 * 1024 * 1024 + 5]).unwrap();
+
+    let shredder = Shredder::new(
+        WipeStandard::Modern(Nist80088Config {
+            method: SanitizationMethod::Purge,
+            verify_level: VerificationLevel::Hash,
+        }),
+        mock_storage::mock_hdd().device_type,
+    );
+
+    let report = shredder.wipe_with_report(&file_path).unwrap();
+    assert_eq!(report.passes, 4);
+    assert_eq!(report.pass_sha256.len(), 4);
+    assert!(report.pass_sha256.iter().all(|digest| digest.len() == 64));
+    // two passes of random data, one of zeros and one of ones
+    assert_ne!(report.pass_sha256[0], report.pass_sha256[3]);
+    assert_ne!(report.pass_sha256[1], report.pass_sha256[2]);
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_builder_validation() {
+    use shredder::builder::BuildError;
+
+    let gutmann = WipeStandard::Legacy(LegacyConfig {
+        standard: LegacyStandard::Gutmann,
+        extra_verification: false,
+    });
+    let purge: WipeStandard = "nist".parse().unwrap();
+
+    assert_eq!(
+        Shredder::builder().standard(purge.clone()).build().err(),
+        Some(BuildError::MissingStorage)
+    );
+    assert!(matches!(
+        Shredder::builder()
+            .standard(gutmann.clone())
+            .storage(mock_storage::mock_ssd().device_type)
+            .build(),
+        Err(BuildError::MultipassOnWearLeveledStorage { .. })
+    ));
+    assert!(matches!(
+        Shredder::builder()
+            .standard(purge.clone())
+            .storage(mock_storage::mock_flash().device_type)
+            .build(),
+        Err(BuildError::PurgeWithoutHardwareErase(_))
+    ));
+    assert!(matches!(
+        Shredder::builder()
+            .storage(mock_storage::mock_hdd().device_type)
+            .verify(VerificationLevel::Sampled(0.0))
+            .build(),
+        Err(BuildError::InvalidSample(_))
+    ));
+    assert!(matches!(
+        Shredder::builder()
+            .standard(WipeStandard::Custom(WipeConfig {
+                passes: Vec::new(),
+                verify_each_pass: false,
+            }))
+            .storage(mock_storage::mock_hdd().device_type)
+            .build(),
+        Err(BuildError::NoPasses)
+    ));
+
+    // the same standards are fine where they fit, and the shredder works
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("built.bin");
+    std::fs::write(&file_path, b"built").unwrap();
+    let shredder = Shredder::builder()
+        .standard(gutmann)
+        .storage(mock_storage::mock_hdd().device_type)
+        .verify(VerificationLevel::None)
+        .build()
+        .unwrap();
+    shredder.wipe(&file_path).unwrap();
+    assert!(!file_path.exists());
+    assert!(Shredder::builder()
+        .standard(purge)
+        .storage(mock_storage::mock_ssd().device_type)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn test_multipass_wear_guard() {
+    use shredder::builder::BuildError;
+
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 4096).unwrap();
+    let original = std::fs::read(&file_path).unwrap();
+    let dod: WipeStandard = "dod".parse().unwrap();
+
+    // frontends that skip the builder are refused before anything is written
+    let shredder = Shredder::new(dod.clone(), mock_storage::mock_flash().device_type);
+    assert!(matches!(
+        shredder.wipe(&file_path),
+        Err(WipeError::MultipassOnWearLeveledStorage { passes: 3, .. })
+    ));
+    assert_eq!(std::fs::read(&file_path).unwrap(), original);
+
+    // the builder refuses it up front, unless forced
+    assert!(matches!(
+        Shredder::builder()
+            .standard(dod.clone())
+            .storage(mock_storage::mock_ssd().device_type)
+            .build(),
+        Err(BuildError::MultipassOnWearLeveledStorage { passes: 3, .. })
+    ));
+    assert!(Shredder::builder()
+        .standard(dod.clone())
+        .storage(mock_storage::mock_ssd().device_type)
+        .force_multipass()
+        .build()
+        .is_ok());
+
+    // hard disks are unaffected
+    let shredder = Shredder::new(dod, mock_storage::mock_hdd().device_type);
+    shredder.wipe(&file_path).unwrap();
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_sandboxed_wipe() {
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 64 * 1024).unwrap();
+
+    let shredder = Shredder::new("dod".parse().unwrap(), mock_storage::mock_hdd().device_type)
+        .with_sandbox(true);
+    let report = shredder.wipe_with_report(&file_path).unwrap();
+    assert_eq!(report.passes, 3);
+    assert!(!file_path.exists());
+
+    // only the wiping thread was confined
+    assert!(std::net::UdpSocket::bind("127.0.0.1:0").is_ok());
+}
+
+#[test]
+fn test_policy_enforcement() {
+    use shredder::builder::BuildError;
+    use shredder::policy::Policy;
+
+    let dir = tempdir().unwrap();
+    let banned = dir.path().join("records");
+    std::fs::create_dir_all(&banned).unwrap();
+    let kept = create_test_file(&banned, 1024).unwrap();
+    let file_path = create_test_file(dir.path(), 1024).unwrap();
+    let policy: Policy = toml::from_str(&format!(
+        "minimum_action = \"purge\"\nbanned_paths = [{:?}]\n",
+        banned
+    ))
+    .unwrap();
+
+    // the builder refuses a standard weaker than the policy up front
+    assert!(matches!(
+        Shredder::builder()
+            .standard("nist-clear".parse().unwrap())
+            .storage(mock_storage::mock_hdd().device_type)
+            .policy(policy.clone())
+            .build(),
+        Err(BuildError::Policy(_))
+    ));
+
+    // and a shredder created directly refuses it before writing anything
+    let clear = Shredder::new(
+        "nist-clear".parse().unwrap(),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_policy(policy.clone());
+    assert!(matches!(
+        clear.wipe(&file_path),
+        Err(WipeError::PolicyViolation(_))
+    ));
+    assert!(file_path.exists());
+
+    // banned paths are refused even with a strong enough standard
+    let purge = Shredder::new(
+        "nist".parse().unwrap(),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_policy(policy);
+    assert!(matches!(
+        purge.wipe(&kept),
+        Err(WipeError::PolicyViolation(_))
+    ));
+    // including through a target that skips the other path checks
+    assert!(matches!(
+        purge.wipe_target(&shredder::target::RegularFile::new(&kept)),
+        Err(WipeError::PolicyViolation(_))
+    ));
+    assert!(kept.exists());
+    purge.wipe(&file_path).unwrap();
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_storage_probe_injection() {
+    use mock_storage::MockProbe;
+    use shredder::builder::BuildError;
+
+    let dir = tempdir().unwrap();
+    let ssd_dir = dir.path().join("ssd");
+    let hdd_dir = dir.path().join("hdd");
+    std::fs::create_dir_all(&ssd_dir).unwrap();
+    std::fs::create_dir_all(&hdd_dir).unwrap();
+    let probe = MockProbe::default()
+        .with(&ssd_dir, mock_storage::mock_ssd())
+        .with(&hdd_dir, mock_storage::mock_hdd());
+    let gutmann = WipeStandard::Legacy(LegacyConfig {
+        standard: LegacyStandard::Gutmann,
+        extra_verification: false,
+    });
+
+    // the storage comes from the probe, so the fake SSD is refused for Gutmann
+    let on_ssd = ssd_dir.join("file.bin");
+    assert!(matches!(
+        Shredder::builder()
+            .standard(gutmann.clone())
+            .detect_storage(&on_ssd, &probe)
+            .build(),
+        Err(BuildError::MultipassOnWearLeveledStorage { .. })
+    ));
+
+    // a path the probe doesn't know fails detection
+    assert!(matches!(
+        Shredder::builder()
+            .detect_storage(&dir.path().join("elsewhere"), &probe)
+            .build(),
+        Err(BuildError::Detection { .. })
+    ));
+
+    // and the fake HDD gets wiped as one
+    let file_path = create_test_file(&hdd_dir, 4096).unwrap();
+    let shredder = Shredder::builder()
+        .standard(WipeStandard::Custom(WipeConfig {
+            passes: vec![WipePattern::Zeros],
+            verify_each_pass: true,
+        }))
+        .detect_storage(&file_path, &probe)
+        .build()
+        .unwrap();
+    shredder.wipe(&file_path).unwrap();
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_wipe_target_kinds() {
+    use shredder::target::{OpenHandle, RegularFile};
+
+    let dir = tempdir().unwrap();
+    let shredder = Shredder::new(
+        WipeStandard::Custom(WipeConfig {
+            passes: vec![WipePattern::Ones, WipePattern::Zeros],
+            verify_each_pass: true,
+        }),
+        mock_storage::mock_hdd().device_type,
+    );
+
+    // a handle opened elsewhere is overwritten through that handle and left
+    // to its owner
+    let held = dir.path().join("held.bin");
+    std::fs::write(&held, vec![0x42; 10_000]).unwrap();
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&held)
+        .unwrap();
+    std::fs::remove_file(&held).unwrap();
+    let target = OpenHandle::new(file.try_clone().unwrap(), "held.bin (deleted)");
+    let report = shredder.wipe_target(&target).unwrap();
+    assert_eq!(report.passes, 2);
+    let mut contents = Vec::new();
+    let mut file = file;
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.read_to_end(&mut contents).unwrap();
+    // released once overwritten, like a wiped file
+    assert!(contents.iter().all(|&b| b == 0));
+
+    let plain = dir.path().join("plain.bin");
+    std::fs::write(&plain, vec![0x42; 10_000]).unwrap();
+    let report = shredder.wipe_target(&RegularFile::new(&plain)).unwrap();
+    assert_eq!(report.bytes_written, 20_000);
+    assert!(!plain.exists());
+}
+
+#[test]
+fn test_nist_outcome() {
+    use shredder::report::NistAction;
+
+    let dir = tempdir().unwrap();
+    let outcome = |standard: &str, storage: shredder::storage::StorageType| {
+        let file_path = create_test_file(dir.path(), 8192).unwrap();
+        let shredder = Shredder::new(standard.parse().unwrap(), storage);
+        let report = shredder.wipe_with_report(&file_path).unwrap();
+        (report.nist_action, report.destroy_required)
+    };
+
+    assert_eq!(
+        outcome("nist-clear", mock_storage::mock_hdd().device_type),
+        (Some(NistAction::Clear), false)
+    );
+    // overwriting a hard disk purges it, whether or not its secure erase worked
+    assert_eq!(
+        outcome("nist", mock_storage::mock_hdd().device_type),
+        (Some(NistAction::Purge), false)
+    );
+    // extra passes of a legacy standard add nothing NIST recognizes
+    assert_eq!(
+        outcome("dod", mock_storage::mock_hdd().device_type),
+        (Some(NistAction::Clear), false)
+    );
+    // flash without a hardware erase only gets Clear, and Purge needs destruction
+    assert_eq!(
+        outcome("nist", mock_storage::mock_flash().device_type),
+        (Some(NistAction::Clear), true)
+    );
+}
+
+#[test]
+fn test_seeded_random_passes() {
+    let dir = tempdir().unwrap();
+    // a Clear is one random pass; hash verification records its digest
+    let wipe = |seed: Option<u64>| {
+        let file_path = create_test_file(dir.path(), 64 * 1024).unwrap();
+        let mut builder = Shredder::builder()
+            .standard(WipeStandard::Modern(Nist80088Config {
+                method: SanitizationMethod::Clear,
+                verify_level: VerificationLevel::Hash,
+            }))
+            .storage(mock_storage::mock_hdd().device_type);
+        if let Some(seed) = seed {
+            builder = builder.rng_seed(seed);
+        }
+        let report = builder
+            .build()
+            .unwrap()
+            .wipe_with_report(&file_path)
+            .unwrap();
+        assert!(!file_path.exists());
+        report.pass_sha256
+    };
+
+    let first = wipe(Some(42));
+    assert_eq!(first.len(), 1);
+    assert_eq!(first, wipe(Some(42)));
+    assert_ne!(first, wipe(Some(43)));
+    // unseeded shredders never repeat a pass
+    assert_ne!(wipe(None), wipe(None));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_locked_target() {
+    use std::os::unix::io::AsRawFd;
+
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 4096).unwrap();
+    let original = std::fs::read(&file_path).unwrap();
+    let shredder = Shredder::new(
+        WipeStandard::Modern(Nist80088Config {
+            method: SanitizationMethod::Clear,
+            verify_level: VerificationLevel::Full,
+        }),
+        mock_storage::mock_hdd().device_type,
+    );
+
+    // as another shredder holding the target would
+    let other = File::open(&file_path).unwrap();
+    assert_eq!(
+        unsafe { libc::flock(other.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) },
+        0
+    );
+    assert!(matches!(
+        shredder.wipe(&file_path),
+        Err(WipeError::TargetBusy(_))
+    ));
+    assert_eq!(std::fs::read(&file_path).unwrap(), original);
+
+    drop(other);
+    assert!(shredder.wipe(&file_path).is_ok());
+    assert!(!file_path.exists());
+}
+
+#[test]
+fn test_quick_wipe_refuses_files() {
+    let dir = tempdir().unwrap();
+    let file_path = create_test_file(dir.path(), 4096).unwrap();
+    let original = std::fs::read(&file_path).unwrap();
+
+    // files are always overwritten in full, so a quick shredder leaves them alone
+    let shredder = Shredder::new(
+        "nist".parse().unwrap(),
+        mock_storage::mock_hdd().device_type,
+    )
+    .with_quick_wipe(16 * 1024 * 1024);
+    assert!(matches!(
+        shredder.wipe(&file_path),
+        Err(WipeError::UnsupportedOperation(_))
+    ));
+    assert_eq!(std::fs::read(&file_path).unwrap(), original);
+
+    // and a policy asking for a NIST action refuses quick wipes outright
+    let policy: shredder::policy::Policy = toml::from_str("minimum_action = \"clear\"\n").unwrap();
+    assert!(policy.check_quick().is_err());
+    assert!(shredder::policy::Policy::default().check_quick().is_ok());
+}